@@ -0,0 +1,89 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde::Deserialize;
+
+// What a command can fail with. The frontend gets {"kind": "rate_limited", "message": "..."}
+// rather than a bare string, so it can wait and retry after a 429 but report a 404 as missing
+#[derive(Debug, thiserror::Error)]
+pub enum RomBrowserError {
+    #[error("Network error: {0}")]
+    Network(String),
+    #[error("{0}")]
+    Parse(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Rate limited: {0}")]
+    RateLimited(String),
+    #[error("{context}: {source}")]
+    Io {
+        context: String,
+        #[source]
+        source: std::io::Error,
+    },
+    // Refused by the profile or read-only mode
+    #[error("{0}")]
+    Denied(String),
+    // Dropped by cancel_request before it finished; the id of the request
+    #[error("Request {0} was cancelled")]
+    Cancelled(String),
+    #[error("{0}")]
+    Other(String),
+}
+
+pub type CommandResult<T> = Result<T, RomBrowserError>;
+
+// Set for every script the GUI runs, so a failing one ends stderr with a ScriptError line
+// (script_errors.py) telling what kind of failure it was
+pub const ERRORS_ENV: &str = "ROM_BROWSER_ERRORS";
+
+// The last line of a failed script's stderr, e.g. {"kind": "rate_limited", "message": "..."}
+#[derive(Deserialize)]
+struct ScriptError {
+    kind: String,
+    message: String,
+}
+
+impl RomBrowserError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RomBrowserError::Network(_) => "network",
+            RomBrowserError::Parse(_) => "parse",
+            RomBrowserError::NotFound(_) => "not_found",
+            RomBrowserError::RateLimited(_) => "rate_limited",
+            RomBrowserError::Io { .. } => "io",
+            RomBrowserError::Denied(_) => "denied",
+            RomBrowserError::Cancelled(_) => "cancelled",
+            RomBrowserError::Other(_) => "other",
+        }
+    }
+
+    // A failed script, classified by the ScriptError line it ended stderr with. A script that
+    // printed none (a crash, or one that does not report its errors) is Other with all of stderr
+    pub fn from_script(stderr: &str) -> Self {
+        let last_line = stderr.lines().map(str::trim).rev().find(|line| !line.is_empty());
+        let Some(ScriptError { kind, message }) = last_line.and_then(|line| serde_json::from_str(line).ok()) else {
+            return RomBrowserError::Other(format!("Script error: {}", stderr.trim()));
+        };
+        match kind.as_str() {
+            "network" => RomBrowserError::Network(message),
+            "not_found" => RomBrowserError::NotFound(message),
+            "rate_limited" => RomBrowserError::RateLimited(message),
+            "denied" => RomBrowserError::Denied(message),
+            _ => RomBrowserError::Other(message),
+        }
+    }
+}
+
+// For map_err: an IO error with what was being done, e.g. io_error("Failed to save settings")
+pub fn io_error(context: impl Into<String>) -> impl FnOnce(std::io::Error) -> RomBrowserError {
+    let context = context.into();
+    move |source| RomBrowserError::Io { context, source }
+}
+
+impl Serialize for RomBrowserError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("RomBrowserError", 2)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.end()
+    }
+}
@@ -1,17 +1,34 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::process::Command;
-use std::path::Path;
-use tauri::State;
+use std::process::{Command, Stdio};
+use std::path::{Path, PathBuf};
+use tauri::{Manager, State};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Serialize, Deserialize)]
+mod error;
+mod names;
+mod palette;
+mod schemas;
+use error::{io_error, CommandResult, RomBrowserError, ERRORS_ENV};
+use names::{NameFilter, NameTags, SortKey};
+use palette::CoverPalette;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 struct GameInfo {
     name: String,
     platform: String,
     size: Option<String>,
+    // The size in bytes and the modified date (ISO 8601) the listing shows, for sorting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified: Option<String>,
     url: Option<String>,
     cover_art: Option<String>,
     rating: Option<f64>,
@@ -20,15 +37,146 @@ struct GameInfo {
     release_date: Option<String>,
     is_favorite: Option<bool>,
     is_downloaded: Option<bool>,
+    match_confidence: Option<f64>,
+    display_name: Option<String>,
+    // How well the game matched a search_in_platform query, from 0.0 to 1.0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    match_score: Option<f64>,
+    // Region, languages, revision, disc... parsed from the file name by names.py
+    #[serde(flatten)]
+    tags: NameTags,
+    // Set on the first file of a language variant group: every file of the group, itself included
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    variants: Vec<GameInfo>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl GameInfo {
+    // A file of a listing (rom_browser.py list, archive_org.py files, local_source.py files),
+    // before any metadata is known
+    fn from_listing_entry(entry: &serde_json::Value, platform: &str) -> Self {
+        GameInfo {
+            name: entry["name"].as_str().unwrap_or_default().to_string(),
+            platform: platform.to_string(),
+            size: entry["size_text"].as_str().map(String::from),
+            size_bytes: entry["size"].as_u64(),
+            modified: entry["modified"].as_str().map(String::from),
+            url: entry["url"].as_str().map(String::from),
+            cover_art: None,
+            rating: None,
+            summary: None,
+            genres: None,
+            release_date: None,
+            is_favorite: None,
+            is_downloaded: None,
+            match_confidence: None,
+            display_name: None,
+            match_score: None,
+            tags: NameTags::of_entry(entry),
+            variants: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 struct PlatformInfo {
     id: String,
     name: String,
     dataset: String,
 }
 
+// One page of a platform's games; page is the one returned, which may be the last one
+// rather than the one asked for
+#[derive(Debug, Serialize, JsonSchema)]
+struct GamePage {
+    games: Vec<GameInfo>,
+    page: usize,
+    page_size: usize,
+    total: usize,
+    total_pages: usize,
+}
+
+// Every region, revision and disc of one title, as browse_platform_grouped lists them
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+struct GameGroup {
+    title: String,
+    platform: String,
+    regions: Vec<String>,
+    spoken_languages: Vec<String>,
+    discs: Vec<u32>,
+    files: Vec<GameInfo>,
+}
+
+// Mirrors JobProgress in job_progress.py: how far a download, scan, scrape or sync has got
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct JobProgress {
+    job: String,
+    label: String,
+    done: f64,
+    total: Option<f64>,
+    unit: String,
+    rate: Option<f64>,
+    eta_seconds: Option<f64>,
+    elapsed_seconds: f64,
+    message: Option<String>,
+    finished: bool,
+    fraction: Option<f64>,
+}
+
+// A line a script writes to stderr when ROM_BROWSER_PROGRESS=json
+#[derive(Deserialize)]
+struct ProgressLine {
+    progress: JobProgress,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct MetadataCandidate {
+    source: String,
+    id: serde_json::Value,
+    name: String,
+    release_date: Option<String>,
+    cover_url: Option<String>,
+    likely_match: bool,
+}
+
+// What get_game_metadata returns for a game. A game without stored metadata has only the name,
+// description, rating, cover_art, platforms and genres
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct GameMetadata {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    display_name: Option<String>,
+    rating: Option<f64>,
+    description: String,
+    genres: NameList,
+    platforms: NameList,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    release_date: Option<String>,
+    cover_art: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    metacritic_score: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    match_confidence: Option<f64>,
+    // Field -> the provider it came from, or "manual" for an override
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    field_sources: HashMap<String, String>,
+    // The fields set by hand
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    overrides: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    palette: Option<CoverPalette>,
+    // The cover file on disk, when the cover is not a URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cover_path: Option<String>,
+}
+
+// Genres and platforms of game metadata: a JSON-encoded list for stored games, a plain list otherwise
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum NameList {
+    Encoded(String),
+    List(Vec<String>),
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct SettingsData {
     rom_directories: Vec<String>,
@@ -37,37 +185,623 @@ struct SettingsData {
     auto_scan: bool,
     scan_interval: u32,
     max_concurrent_downloads: u32,
+    #[serde(default)]
+    read_only: bool,
+    #[serde(default)]
+    proxy_url: String,
+    #[serde(default)]
+    cache_ttls: CacheTtls,
+    #[serde(default)]
+    job_limits: JobLimits,
+}
+
+// How long each cache stays fresh; 0 turns that cache off
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheTtls {
+    platform_list_hours: f64,
+    listing_hours: f64,
+    provider_response_hours: f64,
+}
+
+impl Default for CacheTtls {
+    fn default() -> Self {
+        CacheTtls {
+            platform_list_hours: 24.0,
+            listing_hours: 6.0,
+            provider_response_hours: 72.0,
+        }
+    }
+}
+
+// What hashing and scanning jobs may use, so they run alongside games (the jobs section of the
+// settings). Running jobs pick up changes within a few seconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JobLimits {
+    // Files worked on at once; 0 uses every core
+    cpu_threads: u32,
+    // Files read at once
+    io_concurrency: u32,
+    // Read speed across a job; 0 is unlimited
+    io_mb_per_second: f64,
+}
+
+impl Default for JobLimits {
+    fn default() -> Self {
+        JobLimits {
+            cpu_threads: 0,
+            io_concurrency: 2,
+            io_mb_per_second: 0.0,
+        }
+    }
+}
+
+impl JobLimits {
+    fn read(number: impl Fn(&str) -> Option<f64>) -> Self {
+        let defaults = JobLimits::default();
+        JobLimits {
+            cpu_threads: number("/jobs/cpu_threads").map_or(defaults.cpu_threads, |v| v.max(0.0) as u32),
+            io_concurrency: number("/jobs/io_concurrency").map_or(defaults.io_concurrency, |v| v.max(1.0) as u32),
+            io_mb_per_second: number("/jobs/io_mb_per_second").unwrap_or(defaults.io_mb_per_second),
+        }
+    }
+
+    fn save(&self) -> Result<(), RomBrowserError> {
+        update_rom_config(&[
+            ("jobs", "cpu_threads", serde_json::json!(self.cpu_threads)),
+            ("jobs", "io_concurrency", serde_json::json!(self.io_concurrency.max(1))),
+            ("jobs", "io_mb_per_second", serde_json::json!(self.io_mb_per_second.max(0.0))),
+        ])
+    }
+}
+
+// A value kept in AppState and when it was fetched
+struct Cached<T> {
+    value: T,
+    fetched: Instant,
+}
+
+impl<T: Clone> Cached<T> {
+    fn new(value: T) -> Self {
+        Cached { value, fetched: Instant::now() }
+    }
+
+    // The value while it is younger than hours; 0 hours turns the cache off
+    fn fresh(&self, hours: f64) -> Option<T> {
+        (self.fetched.elapsed() < Duration::from_secs_f64(hours.max(0.0) * 3600.0)).then(|| self.value.clone())
+    }
+}
+
+// The platform list and the platform listings already parsed, so browsing a platform again or
+// turning a page does not run the scripts again. They expire after cache.platform_list_hours and
+// cache.listing_hours like the scripts' own caches, and are dropped when the settings are saved
+// or the caches cleared. Commands only take a lock to look an entry up or store one, never while
+// a script runs, and lookups share it, so browsing, searching and metadata lookups run in parallel
+#[derive(Default)]
+struct AppState {
+    platforms: RwLock<Option<Cached<Vec<PlatformInfo>>>>,
+    listings: RwLock<HashMap<String, Cached<Vec<GameInfo>>>>,
+    // The owned_key of every file already downloaded, which listings are marked with
+    owned: RwLock<Option<Cached<HashSet<String>>>>,
+    // Browse and search requests the webview may still cancel, by the id it gave them
+    requests: Mutex<HashMap<String, tokio::sync::oneshot::Sender<()>>>,
+}
+
+impl AppState {
+    fn invalidate(&self) {
+        *self.platforms.write().unwrap_or_else(|e| e.into_inner()) = None;
+        self.listings.write().unwrap_or_else(|e| e.into_inner()).clear();
+        *self.owned.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CapabilityInfo {
+    profile: String,
+    capabilities: Vec<Capability>,
+}
+
+const REPO_ROOT: &str = "../..";
+// Game management scripts import their neighbours, so they run from their own directory
+const GAME_MANAGEMENT_DIR: &str = "../../scripts/game-management";
+const ROM_CONFIG_PATH: &str = "../../config/rom_browser.json";
+// The user's settings file; --config sets this so the Python scripts read the same file
+const CONFIG_ENV: &str = "ROM_BROWSER_CONFIG";
+const USER_CONFIG_NAME: &str = "rom-browser.toml";
+// Settings files are saved as <name>.tmp and renamed over <name>, keeping the previous
+// version as <name>.bak and a damaged one as <name>.corrupt, like atomic_files.py
+const TEMP_SUFFIX: &str = ".tmp";
+const BACKUP_SUFFIX: &str = ".bak";
+const CORRUPT_SUFFIX: &str = ".corrupt";
+const READ_ONLY_ENV: &str = "ROM_BROWSER_READ_ONLY";
+const PROFILE_ENV: &str = "ROM_BROWSER_PROFILE";
+const DEFAULT_PROFILE: &str = "admin";
+// Platform ids of Internet Archive items are the item identifier with this prefix
+const ARCHIVE_ORG_PREFIX: &str = "ia:";
+// Platform ids of local folders are the folder's source path with this prefix
+const LOCAL_PREFIX: &str = "local:";
+// Set for scripts whose progress is shown, so they report it as JSON lines on stderr
+const PROGRESS_ENV: &str = "ROM_BROWSER_PROGRESS";
+const JOB_PROGRESS_EVENT: &str = "job-progress";
+
+// Actions the webview may be allowed to invoke; browsing is always allowed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Capability {
+    Download,
+    EditMetadata,
+    EditSettings,
+    ScanLibrary,
+    DeleteGames,
+}
+
+impl Capability {
+    const ALL: [Capability; 5] = [
+        Capability::Download,
+        Capability::EditMetadata,
+        Capability::EditSettings,
+        Capability::ScanLibrary,
+        Capability::DeleteGames,
+    ];
+
+    fn describe(self) -> &'static str {
+        match self {
+            Capability::Download => "download games",
+            Capability::EditMetadata => "edit game metadata",
+            Capability::EditSettings => "change settings",
+            Capability::ScanLibrary => "scan or check the library",
+            Capability::DeleteGames => "delete games",
+        }
+    }
+}
+
+// Capabilities of each profile; an unknown profile gets none
+fn profile_capabilities(profile: &str) -> &'static [Capability] {
+    match profile {
+        "admin" => &Capability::ALL,
+        "read_only" => &[],
+        "kiosk" => &[Capability::Download],
+        _ => &[],
+    }
+}
+
+// The per-user config directory, as rom_config.py computes it
+fn user_config_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|base| base.join("rom-browser"))
+}
+
+// The per-user data directory for games.db, covers and downloads, as app_dirs.py computes it
+fn user_data_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+    base.map(|base| base.join("rom-browser"))
+}
+
+// --config / ROM_BROWSER_CONFIG, else rom-browser.toml in the user config directory
+fn user_config_file() -> Option<PathBuf> {
+    std::env::var_os(CONFIG_ENV)
+        .map(PathBuf::from)
+        .or_else(|| user_config_dir().map(|dir| dir.join(USER_CONFIG_NAME)))
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn parse_config_file(path: &Path) -> Result<serde_json::Value, RomBrowserError> {
+    let content = std::fs::read_to_string(path).map_err(io_error(format!("Failed to read {}", path.display())))?;
+    let invalid = |e: &dyn std::fmt::Display| RomBrowserError::Parse(format!("Invalid settings in {}: {}", path.display(), e));
+    if content.trim().is_empty() {
+        Ok(serde_json::json!({}))
+    } else if is_json(path) {
+        serde_json::from_str(&content).map_err(|e| invalid(&e))
+    } else {
+        toml::from_str(&content).map_err(|e| invalid(&e))
+    }
+}
+
+// Read a TOML or JSON settings file as JSON. A damaged file is replaced by its backup
+// and kept as <name>.corrupt
+fn read_config_file(path: &Path) -> Option<serde_json::Value> {
+    if !path.exists() {
+        return None;
+    }
+    let backup = sibling(path, BACKUP_SUFFIX);
+    // A save cut short by a crash leaves an empty file, which would read as no settings
+    let truncated = backup.exists() && path.metadata().is_ok_and(|metadata| metadata.len() == 0);
+    let error = match parse_config_file(path) {
+        Ok(config) if !truncated => return Some(config),
+        Ok(_) => "the file is empty".to_string(),
+        Err(e) => e.to_string(),
+    };
+
+    let config = parse_config_file(&backup).ok()?;
+    let restored = std::fs::copy(path, sibling(path, CORRUPT_SUFFIX))
+        .and_then(|_| std::fs::copy(&backup, sibling(path, TEMP_SUFFIX)))
+        .and_then(|_| std::fs::rename(sibling(path, TEMP_SUFFIX), path));
+    match restored {
+        Ok(()) => eprintln!("{} was damaged ({}); restored the previous version", path.display(), error),
+        Err(e) => eprintln!("Could not restore {} from its backup: {}", path.display(), e),
+    }
+    Some(config)
+}
+
+// Replace a settings file in one step, keeping the previous version as <name>.bak
+fn write_atomically(path: &Path, content: &str) -> std::io::Result<()> {
+    let temp = sibling(path, TEMP_SUFFIX);
+    let mut file = std::fs::File::create(&temp)?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+
+    if path.metadata().is_ok_and(|metadata| metadata.len() > 0) {
+        let kept = if parse_config_file(path).is_ok() { BACKUP_SUFFIX } else { CORRUPT_SUFFIX };
+        std::fs::copy(path, sibling(path, kept))?;
+    }
+    std::fs::rename(&temp, path)
+}
+
+fn merge_config(base: &mut serde_json::Value, overrides: serde_json::Value) {
+    match (base, overrides) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                merge_config(base.entry(key).or_insert(serde_json::Value::Null), value);
+            }
+        }
+        (base, value) => *base = value,
+    }
+}
+
+// Helper function to read the shared ROM browser config: the repository's
+// rom_browser.json with the user's rom-browser.toml on top
+fn read_rom_config() -> Option<serde_json::Value> {
+    let mut config = read_config_file(Path::new(ROM_CONFIG_PATH));
+    if let Some(user) = user_config_file().and_then(|path| read_config_file(&path)) {
+        merge_config(config.get_or_insert_with(|| serde_json::json!({})), user);
+    }
+    config
+}
+
+// A configured path, resolved against the repository root when relative
+fn config_path(pointer: &str) -> Option<PathBuf> {
+    let value = read_rom_config()
+        .and_then(|config| config.pointer(pointer).and_then(|v| v.as_str()).map(String::from))
+        .filter(|value| !value.is_empty())?;
+    let path = PathBuf::from(value);
+    Some(if path.is_absolute() { path } else { Path::new(REPO_ROOT).join(path) })
+}
+
+fn data_directory() -> PathBuf {
+    config_path("/paths/data_directory")
+        .or_else(user_data_dir)
+        .unwrap_or_else(|| PathBuf::from(REPO_ROOT))
+}
+
+// Unset paths default to a name in the data directory, like RomConfig.get_path
+fn games_db_path() -> PathBuf {
+    config_path("/paths/games_db").unwrap_or_else(|| data_directory().join("games.db"))
+}
+
+fn default_download_directory() -> String {
+    data_directory().join("downloads").display().to_string()
+}
+
+// Write settings to the user's file when there is one, else to rom_browser.json;
+// each update is (section, key, value) and a null value removes the key
+fn update_rom_config(updates: &[(&str, &str, serde_json::Value)]) -> Result<(), RomBrowserError> {
+    let path = user_config_file()
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| PathBuf::from(ROM_CONFIG_PATH));
+
+    let mut config = if path.exists() {
+        read_config_file(&path).ok_or_else(|| {
+            RomBrowserError::Parse(format!("Failed to read {}; it is damaged and has no backup", path.display()))
+        })?
+    } else {
+        serde_json::json!({})
+    };
+
+    for (section, key, value) in updates {
+        if !config[*section].is_object() {
+            config[*section] = serde_json::json!({});
+        }
+        let table = config[*section].as_object_mut().expect("section is an object");
+        if value.is_null() {
+            table.remove(*key);
+        } else {
+            table.insert(key.to_string(), value.clone());
+        }
+    }
+
+    let content = if is_json(&path) {
+        serde_json::to_string_pretty(&config).map(|content| content + "\n").map_err(|e| e.to_string())
+    } else {
+        toml::to_string_pretty(&config).map_err(|e| e.to_string())
+    }
+    .map_err(|e| RomBrowserError::Parse(format!("Failed to serialize settings: {}", e)))?;
+    write_atomically(&path, &content).map_err(io_error("Failed to save settings"))
+}
+
+// Read-only mode disables downloads, deletions and database writes
+fn is_read_only() -> bool {
+    if let Ok(value) = std::env::var(READ_ONLY_ENV) {
+        return matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes" | "on");
+    }
+
+    read_rom_config()
+        .and_then(|config| config.pointer("/safety/read_only").and_then(|v| v.as_bool()))
+        .unwrap_or(false)
+}
+
+// The profile comes from the environment (so a kiosk launcher can force it)
+// or from safety.profile in the shared config
+fn current_profile() -> String {
+    if let Ok(value) = std::env::var(PROFILE_ENV) {
+        return value.trim().to_lowercase();
+    }
+
+    read_rom_config()
+        .and_then(|config| config.pointer("/safety/profile").and_then(|v| v.as_str()).map(|v| v.trim().to_lowercase()))
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+fn require(capability: Capability) -> Result<(), RomBrowserError> {
+    let profile = current_profile();
+    if profile_capabilities(&profile).contains(&capability) {
+        Ok(())
+    } else {
+        Err(RomBrowserError::Denied(format!("The {} profile is not allowed to {}", profile, capability.describe())))
+    }
+}
+
+fn ensure_writable(action: &str) -> Result<(), RomBrowserError> {
+    if is_read_only() {
+        Err(RomBrowserError::Denied(format!("Read-only mode is enabled; refusing to {}", action)))
+    } else {
+        Ok(())
+    }
 }
 
-// Helper function to run Python scripts
-fn run_python_script(script_path: &str, args: &[&str]) -> Result<String, String> {
+// Python prelude for games.db queries: imports plus loaders for user overrides,
+// which are overlaid on the scraped metadata, and favorites. Genres and platforms come from
+// their own tables through game_attributes
+const GAMES_DB_PRELUDE_PY: &str = r#"
+import os
+import sqlite3
+import json
+import sys
+
+sys.path.insert(0, '../common')
+from game_attributes import get_attributes
+from text_compression import decompress_text
+
+def load_overrides(cursor):
+    cursor.execute("SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'overrides'")
+    if not cursor.fetchone():
+        return {}
+    cursor.execute('SELECT game_name, field, value FROM overrides')
+    overrides = {}
+    for game_name, field, value in cursor.fetchall():
+        overrides.setdefault(game_name, {})[field] = json.loads(value)
+    return overrides
+
+def load_favorites(cursor):
+    cursor.execute("SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'game_usage'")
+    if not cursor.fetchone():
+        return set()
+    cursor.execute('SELECT game_name FROM game_usage WHERE favorite')
+    return {row[0] for row in cursor.fetchall()}
+"#;
+
+// Helper function to run Python scripts; blocks until the script exits (see run_python_script)
+fn run_python_script_blocking(script_path: &str, args: &[&str]) -> Result<String, RomBrowserError> {
     let output = Command::new("python")
         .arg(script_path)
         .args(args)
+        .env(ERRORS_ENV, "json")
+        .output()
+        .map_err(io_error("Failed to execute Python script"))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(RomBrowserError::from_script(&String::from_utf8_lossy(&output.stderr)))
+    }
+}
+
+// run_python_script for commands that can be cancelled: the script is killed when the
+// returned future is dropped, e.g. by cancel_request
+async fn run_python_script_async(script_path: &str, args: &[&str]) -> Result<String, RomBrowserError> {
+    let output = tokio::process::Command::new("python")
+        .arg(script_path)
+        .args(args)
+        .env(ERRORS_ENV, "json")
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(io_error("Failed to execute Python script"))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(RomBrowserError::from_script(&String::from_utf8_lossy(&output.stderr)))
+    }
+}
+
+// Helper function to run a game management script from its own directory; blocks like
+// run_python_script_blocking
+fn run_game_management_script_blocking(script: &str, args: &[&str]) -> Result<String, RomBrowserError> {
+    let output = Command::new("python")
+        .arg(script)
+        .args(args)
+        .current_dir(GAME_MANAGEMENT_DIR)
+        .env(ERRORS_ENV, "json")
         .output()
-        .map_err(|e| format!("Failed to execute Python script: {}", e))?;
+        .map_err(io_error("Failed to execute Python script"))?;
 
     if output.status.success() {
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     } else {
-        Err(format!("Script error: {}", String::from_utf8_lossy(&output.stderr)))
+        Err(RomBrowserError::from_script(&String::from_utf8_lossy(&output.stderr)))
+    }
+}
+
+// Run a script that reports its progress (job_progress.py), passing each report on to the windows
+// as a job-progress event while it runs. dir is where it runs, as for run_game_management_script.
+// Blocks until it exits; commands use run_script_with_progress
+fn run_script_with_progress_blocking(app: &tauri::AppHandle, dir: &str, script: &str, args: &[&str]) -> Result<String, RomBrowserError> {
+    use std::io::{BufRead, BufReader, Read};
+
+    let mut child = Command::new("python")
+        .arg(script)
+        .args(args)
+        .current_dir(dir)
+        .env(PROGRESS_ENV, "json")
+        .env(ERRORS_ENV, "json")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(io_error("Failed to execute Python script"))?;
+
+    // Read stdout alongside, or a script with a lot to print would block on a full pipe
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let output = std::thread::spawn(move || {
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).map(|_| output)
+    });
+    let mut stderr = String::new();
+    for line in BufReader::new(child.stderr.take().expect("stderr is piped")).lines().map_while(Result::ok) {
+        match serde_json::from_str::<ProgressLine>(&line) {
+            Ok(line) => {
+                if let Err(e) = app.emit_all(JOB_PROGRESS_EVENT, line.progress) {
+                    eprintln!("Failed to report progress: {:?}", e);
+                }
+            }
+            Err(_) => {
+                stderr.push_str(&line);
+                stderr.push('\n');
+            }
+        }
+    }
+
+    let status = child.wait().map_err(io_error("Failed to execute Python script"))?;
+    let output = output
+        .join()
+        .map_err(|_| RomBrowserError::Other("Reading the script output failed".to_string()))?
+        .map_err(io_error("Failed to read the script output"))?;
+    if status.success() {
+        Ok(output)
+    } else {
+        Err(RomBrowserError::from_script(&stderr))
     }
 }
 
+// Run a script (or other blocking work) on the blocking thread pool, so a command waiting for
+// one does not hold up the async workers the other commands run on
+async fn run_blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, RomBrowserError> + Send + 'static,
+) -> Result<T, RomBrowserError> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| RomBrowserError::Other(format!("Background task failed: {}", e)))?
+}
+
+fn owned_args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+// The script runners for commands: each runs its blocking version with run_blocking
+async fn run_python_script(script_path: &str, args: &[&str]) -> Result<String, RomBrowserError> {
+    let (script_path, args) = (script_path.to_string(), owned_args(args));
+    run_blocking(move || {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run_python_script_blocking(&script_path, &args)
+    }).await
+}
+
+async fn run_game_management_script(script: &str, args: &[&str]) -> Result<String, RomBrowserError> {
+    let (script, args) = (script.to_string(), owned_args(args));
+    run_blocking(move || {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run_game_management_script_blocking(&script, &args)
+    }).await
+}
+
+async fn run_script_with_progress(app: &tauri::AppHandle, dir: &str, script: &str, args: &[&str]) -> Result<String, RomBrowserError> {
+    let (app, dir, script, args) = (app.clone(), dir.to_string(), script.to_string(), owned_args(args));
+    run_blocking(move || {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run_script_with_progress_blocking(&app, &dir, &script, &args)
+    }).await
+}
+
+// Run a request the webview gave an id, until it finishes or cancel_request(id) drops it. A new
+// request with the id of one still running replaces it, so the older one is cancelled
+async fn cancellable<T>(
+    state: &AppState,
+    request_id: Option<String>,
+    work: impl std::future::Future<Output = Result<T, RomBrowserError>>,
+) -> Result<T, RomBrowserError> {
+    let Some(request_id) = request_id else {
+        return work.await;
+    };
+    let (cancel, cancelled) = tokio::sync::oneshot::channel();
+    state.requests.lock().unwrap_or_else(|e| e.into_inner()).insert(request_id.clone(), cancel);
+
+    let result = tokio::select! {
+        result = work => result,
+        _ = cancelled => Err(RomBrowserError::Cancelled(request_id)),
+    };
+    // Drop the handles of finished requests, this one's included
+    state.requests.lock().unwrap_or_else(|e| e.into_inner()).retain(|_, cancel| !cancel.is_closed());
+    result
+}
+
 // Helper function to read JSON from Python script output
-fn parse_json_output<T: serde::de::DeserializeOwned>(output: &str) -> Result<T, String> {
+fn parse_json_output<T: serde::de::DeserializeOwned>(output: &str) -> Result<T, RomBrowserError> {
     serde_json::from_str(output)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))
+        .map_err(|e| RomBrowserError::Parse(format!("Failed to parse JSON: {}", e)))
 }
 
 #[tauri::command]
-async fn get_platforms() -> Result<Vec<PlatformInfo>, String> {
-    // Call the Python ROM browser script to get platforms
-    let script_path = "../../scripts/rom-sourcing/rom_browser.py";
-    
-    // For now, return the known platforms from the ROM browser
-    // In a full implementation, we'd parse the actual output
-    Ok(vec![
+async fn get_platforms(state: State<'_, AppState>, refresh: Option<bool>) -> CommandResult<Vec<PlatformInfo>> {
+    let hours = read_settings().cache_ttls.platform_list_hours;
+    if !refresh.unwrap_or(false) {
+        let cached = state.platforms.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(platforms) = cached.as_ref().and_then(|cached| cached.fresh(hours)) {
+            return Ok(platforms);
+        }
+    }
+
+    let platforms = run_blocking(|| Ok(fetch_platforms())).await?;
+    *state.platforms.write().unwrap_or_else(|e| e.into_inner()) = Some(Cached::new(platforms.clone()));
+    Ok(platforms)
+}
+
+fn fetch_platforms() -> Vec<PlatformInfo> {
+    // The archive platforms the ROM browser knows
+    let mut platforms = vec![
         PlatformInfo {
             id: "ps2".to_string(),
             name: "PlayStation 2".to_string(),
@@ -108,189 +842,995 @@ async fn get_platforms() -> Result<Vec<PlatformInfo>, String> {
             name: "Nintendo 64".to_string(),
             dataset: "no-intro".to_string(),
         },
-    ])
-}
-
-#[tauri::command]
-async fn browse_platform(platform_id: String) -> Result<Vec<GameInfo>, String> {
-    // This would call the Python ROM browser script with the platform ID
-    // For now, return mock data based on the platform
-    let games = match platform_id.as_str() {
-        "ps2" => vec![
-            GameInfo {
-                name: "Grand Theft Auto: San Andreas".to_string(),
-                platform: "PlayStation 2".to_string(),
-                size: Some("4.2 GB".to_string()),
-                url: Some("https://myrient.erista.me/files/Redump/Sony%20-%20PlayStation%202/Grand%20Theft%20Auto%20-%20San%20Andreas%20(USA).zip".to_string()),
-                cover_art: None,
-                rating: None,
-                summary: None,
-                genres: None,
-                release_date: None,
-                is_favorite: None,
-                is_downloaded: None,
-            },
-            GameInfo {
-                name: "Metal Gear Solid 3: Snake Eater".to_string(),
-                platform: "PlayStation 2".to_string(),
-                size: Some("3.8 GB".to_string()),
-                url: Some("https://myrient.erista.me/files/Redump/Sony%20-%20PlayStation%202/Metal%20Gear%20Solid%203%20-%20Snake%20Eater%20(USA).zip".to_string()),
-                cover_art: None,
-                rating: None,
-                summary: None,
-                genres: None,
-                release_date: None,
-                is_favorite: None,
-                is_downloaded: None,
-            },
-        ],
-        "xbox" => vec![
-            GameInfo {
-                name: "Halo: Combat Evolved".to_string(),
-                platform: "Xbox".to_string(),
-                size: Some("1.8 GB".to_string()),
-                url: Some("https://myrient.erista.me/files/Redump/Microsoft%20-%20Xbox/Halo%20-%20Combat%20Evolved%20(USA).zip".to_string()),
-                cover_art: None,
-                rating: None,
-                summary: None,
-                genres: None,
-                release_date: None,
-                is_favorite: None,
-                is_downloaded: None,
-            },
-        ],
-        _ => vec![],
-    };
-    
-    Ok(games)
+        // TOSEC platforms are "Manufacturer - System" across its nested folders
+        PlatformInfo {
+            id: "amiga".to_string(),
+            name: "Commodore - Amiga".to_string(),
+            dataset: "tosec".to_string(),
+        },
+        PlatformInfo {
+            id: "atari-st".to_string(),
+            name: "Atari - ST".to_string(),
+            dataset: "tosec".to_string(),
+        },
+        PlatformInfo {
+            id: "zx-spectrum".to_string(),
+            name: "Sinclair - ZX Spectrum".to_string(),
+            dataset: "tosec".to_string(),
+        },
+    ];
+
+    // Internet Archive items from the config are listed as platforms of their own,
+    // and a failure to list them must not hide the archive platforms
+    let items: Vec<serde_json::Value> = run_python_script_blocking("../../scripts/rom-sourcing/archive_org.py", &["items", "--json"])
+        .and_then(|output| parse_json_output(&output))
+        .unwrap_or_default();
+    for item in items {
+        let identifier = item["identifier"].as_str().unwrap_or_default();
+        platforms.push(PlatformInfo {
+            id: format!("{}{}", ARCHIVE_ORG_PREFIX, identifier),
+            name: item["name"].as_str().unwrap_or(identifier).to_string(),
+            dataset: "internet-archive".to_string(),
+        });
+    }
+
+    // Local folders (e.g. a NAS share) likewise
+    let roots: Vec<serde_json::Value> = run_python_script_blocking("../../scripts/rom-sourcing/local_source.py", &["roots", "--json"])
+        .and_then(|output| parse_json_output(&output))
+        .unwrap_or_default();
+    for root in roots {
+        let name = root["name"].as_str().unwrap_or_default();
+        platforms.push(PlatformInfo {
+            id: format!("{}{}/", LOCAL_PREFIX, name),
+            name: name.to_string(),
+            dataset: "local".to_string(),
+        });
+    }
+
+    platforms
 }
 
-#[tauri::command]
-async fn download_game(game_name: String, url: String) -> Result<String, String> {
-    // Call the Python ROM downloader script
-    let script_path = "../../scripts/rom-sourcing/rom_downloader.py";
-    
-    // For now, simulate the download
-    Ok(format!("Download started for: {}", game_name))
+// Lists the files of an Internet Archive item as games
+async fn browse_archive_org_item(identifier: &str) -> Result<Vec<GameInfo>, RomBrowserError> {
+    let output = run_python_script_async("../../scripts/rom-sourcing/archive_org.py", &["files", identifier, "--json"]).await?;
+    let files: Vec<serde_json::Value> = parse_json_output(&output)?;
+
+    Ok(files
+        .iter()
+        .map(|file| GameInfo::from_listing_entry(file, file["item_title"].as_str().unwrap_or(identifier)))
+        .collect())
 }
 
-#[tauri::command]
-async fn get_game_metadata(game_name: String) -> Result<serde_json::Value, String> {
-    // Query the games database for metadata
-    let db_path = "../../scripts/game-management/games.db";
-    
-    if !Path::new(db_path).exists() {
-        return Ok(serde_json::json!({
-            "name": game_name,
-            "description": "No metadata available",
-            "rating": null,
-            "cover_art": null,
-            "platforms": [],
-            "genres": []
-        }));
+// Lists every file below a local folder as games; they are on disk already
+async fn browse_local_directory(path: &str) -> Result<Vec<GameInfo>, RomBrowserError> {
+    let output =
+        run_python_script_async("../../scripts/rom-sourcing/local_source.py", &["files", path, "--recursive", "--json"]).await?;
+    let files: Vec<serde_json::Value> = parse_json_output(&output)?;
+
+    Ok(files
+        .iter()
+        .map(|file| GameInfo {
+            is_downloaded: Some(true),
+            ..GameInfo::from_listing_entry(file, file["platform"].as_str().unwrap_or(path))
+        })
+        .collect())
+}
+
+// Archive folder and display name of each platform get_platforms offers. TOSEC platforms span
+// nested folders, so they are browsed from the folder that holds their games
+fn archive_platform_path(platform_id: &str) -> Option<(&'static str, &'static str)> {
+    match platform_id {
+        "ps2" => Some(("Redump/Sony - PlayStation 2", "PlayStation 2")),
+        "xbox" => Some(("Redump/Microsoft - Xbox", "Xbox")),
+        "gamecube" => Some(("Redump/Nintendo - GameCube - NKit RVZ [zstd-19-128k]", "GameCube")),
+        "ps3" => Some(("Redump/Sony - PlayStation 3", "PlayStation 3")),
+        "wii" => Some(("Redump/Nintendo - Wii - NKit RVZ [zstd-19-128k]", "Nintendo Wii")),
+        "nes" => Some(("No-Intro/Nintendo - Nintendo Entertainment System (Headered)", "Nintendo Entertainment System")),
+        "snes" => Some(("No-Intro/Nintendo - Super Nintendo Entertainment System", "Super Nintendo Entertainment System")),
+        "n64" => Some(("No-Intro/Nintendo - Nintendo 64 (BigEndian)", "Nintendo 64")),
+        "amiga" => Some(("TOSEC/Commodore/Amiga/Games/[ADF]", "Commodore - Amiga")),
+        "atari-st" => Some(("TOSEC/Atari/ST/Games/[ST]", "Atari - ST")),
+        "zx-spectrum" => Some(("TOSEC/Sinclair/ZX Spectrum/Games/[TZX]", "Sinclair - ZX Spectrum")),
+        _ => None,
     }
-    
-    // Use Python to query the database
-    let python_code = format!(
-        r#"
-import sqlite3
-import json
-import sys
+}
 
-try:
-    conn = sqlite3.connect('{}')
-    cursor = conn.cursor()
-    
-    cursor.execute('''
-        SELECT name, rating, summary, genres, platforms, release_date, cover_url, metacritic_score
-        FROM games 
-        WHERE name LIKE ? OR name LIKE ?
-    ''', (f'%{{}}%', f'{{}}%'))
-    
-    row = cursor.fetchone()
-    conn.close()
-    
-    if row:
-        result = {{
-            'name': row[0],
-            'rating': row[1],
-            'description': row[2] or 'No description available',
-            'genres': row[3] or '',
-            'platforms': row[4] or '',
-            'release_date': row[5] or '',
-            'cover_art': row[6] or '',
-            'metacritic_score': row[7]
-        }}
-    else:
-        result = {{
-            'name': '{}',
-            'description': 'No metadata found',
-            'rating': null,
-            'cover_art': null,
-            'platforms': [],
-            'genres': []
-        }}
+// The games of a platform, from AppState while the listing is fresh
+async fn platform_listing(state: &AppState, platform_id: &str, refresh: bool) -> Result<Vec<GameInfo>, RomBrowserError> {
+    let hours = read_settings().cache_ttls.listing_hours;
+    let cached = if refresh {
+        None
+    } else {
+        let listings = state.listings.read().unwrap_or_else(|e| e.into_inner());
+        listings.get(platform_id).and_then(|cached| cached.fresh(hours))
+    };
+
+    let games = match cached {
+        Some(games) => games,
+        None => {
+            let games = fetch_platform_listing(platform_id).await?;
+            state.listings.write().unwrap_or_else(|e| e.into_inner())
+                .insert(platform_id.to_string(), Cached::new(games.clone()));
+            games
+        }
+    };
+    // Downloads change more often than listings, so ownership is marked on every browse
+    Ok(mark_owned(games, &owned_files(state).await))
+}
+
+// How long the list of owned files is reused before library_manager.py lists them again
+const OWNED_FILES_HOURS: f64 = 0.5 / 60.0;
+
+// Mirrors owned_key in library_db.py: the file name without extensions, in lower case, so a
+// download converted to another format still matches its listing entry
+fn owned_key(name: &str) -> String {
+    let mut stem = name;
+    while let Some((rest, extension)) = stem.rsplit_once('.') {
+        if !(1..=4).contains(&extension.len()) || !extension.chars().all(|c| c.is_ascii_alphanumeric()) {
+            break;
+        }
+        stem = rest;
+    }
+    stem.to_lowercase()
+}
+
+// The files the library or the downloads folder has. A failure only costs the marks, not the listing
+async fn owned_files(state: &AppState) -> HashSet<String> {
+    let cached = state.owned.read().unwrap_or_else(|e| e.into_inner()).as_ref().and_then(|cached| cached.fresh(OWNED_FILES_HOURS));
+    if let Some(owned) = cached {
+        return owned;
+    }
+    let output = run_python_script_async("../../scripts/rom-sourcing/library_manager.py", &["owned", "--json"]).await;
+    match output.and_then(|output| parse_json_output::<HashSet<String>>(&output)) {
+        Ok(owned) => {
+            *state.owned.write().unwrap_or_else(|e| e.into_inner()) = Some(Cached::new(owned.clone()));
+            owned
+        }
+        Err(e) => {
+            eprintln!("Failed to list the owned files: {}", e);
+            HashSet::new()
+        }
+    }
+}
+
+// Sets is_downloaded on the games of a remote listing; local folders are on disk already
+fn mark_owned(mut games: Vec<GameInfo>, owned: &HashSet<String>) -> Vec<GameInfo> {
+    for game in &mut games {
+        if game.is_downloaded != Some(true) {
+            game.is_downloaded = Some(owned.contains(&owned_key(&game.name)));
+        }
+    }
+    games
+}
+
+// The games of a listing whose tags pass the filter, or all of them without one
+fn filter_games(games: Vec<GameInfo>, filter: Option<NameFilter>) -> Vec<GameInfo> {
+    match filter {
+        Some(filter) => games.into_iter().filter(|game| filter.matches(&game.tags)).collect(),
+        None => games,
+    }
+}
+
+// Mirrors group_language_variants in names.py: files that differ only by their language tags
+// become one game, the first of them, holding all of them in variants and speaking all
+// their languages
+fn group_language_variants(games: Vec<GameInfo>) -> Vec<GameInfo> {
+    let mut groups: HashMap<names::VariantKey, usize> = HashMap::new();
+    let mut grouped: Vec<Vec<GameInfo>> = Vec::new();
+    for game in games {
+        match groups.entry(game.tags.language_variant_key(&game.name)) {
+            std::collections::hash_map::Entry::Occupied(group) => grouped[*group.get()].push(game),
+            std::collections::hash_map::Entry::Vacant(group) => {
+                group.insert(grouped.len());
+                grouped.push(vec![game]);
+            }
+        }
+    }
+
+    grouped
+        .into_iter()
+        .flat_map(|members| {
+            if members.len() == 1 {
+                return members;
+            }
+            let mut group = members[0].clone();
+            group.tags.spoken_languages.clear();
+            for language in members.iter().flat_map(|member| &member.tags.spoken_languages) {
+                if !group.tags.spoken_languages.contains(language) {
+                    group.tags.spoken_languages.push(language.clone());
+                }
+            }
+            group.variants = members;
+            vec![group]
+        })
+        .collect()
+}
+
+// Mirrors group_by_title in names.py: one group per title, in listing order
+fn group_by_title(games: Vec<GameInfo>) -> Vec<GameGroup> {
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut groups: Vec<GameGroup> = Vec::new();
+    for game in games {
+        let title = game.tags.title.clone().unwrap_or_else(|| game.name.clone());
+        let position = *positions.entry(title.to_lowercase()).or_insert_with(|| {
+            groups.push(GameGroup {
+                title: title.clone(),
+                platform: game.platform.clone(),
+                regions: Vec::new(),
+                spoken_languages: Vec::new(),
+                discs: Vec::new(),
+                files: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        let group = &mut groups[position];
+        for region in &game.tags.regions {
+            if !group.regions.contains(region) {
+                group.regions.push(region.clone());
+            }
+        }
+        for language in &game.tags.spoken_languages {
+            if !group.spoken_languages.contains(language) {
+                group.spoken_languages.push(language.clone());
+            }
+        }
+        if let Some(disc) = game.tags.disc.filter(|disc| !group.discs.contains(disc)) {
+            group.discs.push(disc);
+        }
+        group.files.push(game);
+    }
+    for group in &mut groups {
+        group.discs.sort_unstable();
+    }
+    groups
+}
+
+// Mirrors sort_entries in names.py. Games without the value sorted on go last either way,
+// and games that tie keep their order
+fn sort_games(games: Vec<GameInfo>, key: SortKey, reverse: bool) -> Vec<GameInfo> {
+    let region_priority: Vec<String> = read_rom_config()
+        .and_then(|config| config.pointer("/matching/region_priority").cloned())
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default();
+    let (mut known, unknown): (Vec<GameInfo>, Vec<GameInfo>) = games.into_iter().partition(|game| match key {
+        SortKey::Size => game.size_bytes.is_some(),
+        SortKey::Modified => game.modified.is_some(),
+        _ => true,
+    });
+
+    let compare = |a: &GameInfo, b: &GameInfo| match key {
+        SortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+        SortKey::Size => b.size_bytes.cmp(&a.size_bytes),
+        SortKey::Modified => b.modified.cmp(&a.modified),
+        SortKey::Region => names::region_rank(&a.tags.regions, &region_priority)
+            .cmp(&names::region_rank(&b.tags.regions, &region_priority)),
+        SortKey::Relevance => b.match_score.unwrap_or(0.0).total_cmp(&a.match_score.unwrap_or(0.0)),
+    };
+    if reverse {
+        known.sort_by(|a, b| compare(b, a));
+    } else {
+        known.sort_by(compare);
+    }
+    known.extend(unknown);
+    known
+}
+
+// How the browse commands present a platform's listing
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ListingView {
+    filter: Option<NameFilter>,
+    group_languages: bool,
+    // None keeps the listing's own order
+    sort: Option<SortKey>,
+    reverse: bool,
+    // Leaves out the games already downloaded
+    hide_owned: bool,
+}
+
+impl ListingView {
+    fn apply(self, games: Vec<GameInfo>) -> Vec<GameInfo> {
+        let mut games = filter_games(games, self.filter);
+        if self.hide_owned {
+            games.retain(|game| game.is_downloaded != Some(true));
+        }
+        if let Some(key) = self.sort {
+            games = sort_games(games, key, self.reverse);
+        }
+        if self.group_languages { group_language_variants(games) } else { games }
+    }
+}
+
+#[tauri::command]
+async fn browse_platform(
+    state: State<'_, AppState>,
+    platform_id: String,
+    refresh: Option<bool>,
+    view: Option<ListingView>,
+    request_id: Option<String>,
+) -> CommandResult<Vec<GameInfo>> {
+    let games = cancellable(&state, request_id, platform_listing(&state, &platform_id, refresh.unwrap_or(false))).await?;
+    Ok(view.unwrap_or_default().apply(games))
+}
+
+// One page of a platform's games, counting from 0. A page past the end gives the last page
+#[tauri::command]
+async fn browse_platform_paginated(
+    state: State<'_, AppState>,
+    platform_id: String,
+    page: usize,
+    page_size: usize,
+    refresh: Option<bool>,
+    view: Option<ListingView>,
+    request_id: Option<String>,
+) -> CommandResult<GamePage> {
+    let listing = platform_listing(&state, &platform_id, refresh.unwrap_or(false));
+    let games = view.unwrap_or_default().apply(cancellable(&state, request_id, listing).await?);
+    let page_size = page_size.max(1);
+    let total = games.len();
+    let total_pages = total.div_ceil(page_size).max(1);
+    let page = page.min(total_pages - 1);
+    let start = (page * page_size).min(total);
+    let end = (start + page_size).min(total);
+
+    Ok(GamePage {
+        games: games[start..end].to_vec(),
+        page,
+        page_size,
+        total,
+        total_pages,
+    })
+}
+
+// The games of a platform whose names match the query, allowing for typos, best match first.
+// Searches the cached listing; the view filters and groups the matches like a listing
+#[tauri::command]
+async fn search_in_platform(
+    state: State<'_, AppState>,
+    platform_id: String,
+    query: String,
+    view: Option<ListingView>,
+    request_id: Option<String>,
+) -> CommandResult<Vec<GameInfo>> {
+    let games = cancellable(&state, request_id, platform_listing(&state, &platform_id, false)).await?;
+    let matches: Vec<GameInfo> = games
+        .into_iter()
+        .filter_map(|game| {
+            let score = names::fuzzy_score(&query, &game.name);
+            (score > 0.0).then_some(GameInfo { match_score: Some(score), ..game })
+        })
+        .collect();
+    let mut view = view.unwrap_or_default();
+    view.sort.get_or_insert(SortKey::Relevance);
+    Ok(view.apply(matches))
+}
+
+// A platform's games grouped by title: each row holds every region, revision and disc of a game.
+// Only the view's filter applies; the files of a group are not grouped by language again
+#[tauri::command]
+async fn browse_platform_grouped(
+    state: State<'_, AppState>,
+    platform_id: String,
+    refresh: Option<bool>,
+    view: Option<ListingView>,
+    request_id: Option<String>,
+) -> CommandResult<Vec<GameGroup>> {
+    let games = cancellable(&state, request_id, platform_listing(&state, &platform_id, refresh.unwrap_or(false))).await?;
+    Ok(group_by_title(filter_games(games, view.unwrap_or_default().filter)))
+}
+
+async fn fetch_platform_listing(platform_id: &str) -> Result<Vec<GameInfo>, RomBrowserError> {
+    if let Some(identifier) = platform_id.strip_prefix(ARCHIVE_ORG_PREFIX) {
+        return browse_archive_org_item(identifier).await;
+    }
+    if let Some(path) = platform_id.strip_prefix(LOCAL_PREFIX) {
+        return browse_local_directory(path).await;
+    }
+
+    let (path, name) = archive_platform_path(platform_id)
+        .ok_or_else(|| RomBrowserError::NotFound(format!("Unknown platform: {}", platform_id)))?;
+    let output = run_python_script_async("../../scripts/rom-sourcing/rom_browser.py", &["list", path, "--json"]).await?;
+    let entries: Vec<serde_json::Value> = parse_json_output(&output)?;
+
+    Ok(entries
+        .iter()
+        .filter(|entry| !entry["is_dir"].as_bool().unwrap_or(false))
+        .map(|entry| GameInfo::from_listing_entry(entry, name))
+        .collect())
+}
+
+// Lists the files in a ZIP archive on the server without downloading it, reading only its central
+// directory with range requests: {url, size, comment, requests, members}. Each member has its name,
+// size, compressed_size, crc32, method and modified date, and the DAT title its CRC32 matches, if any.
+// url may also be an archive path such as "Redump/Sony - PlayStation/Game (USA).zip"
+#[tauri::command]
+async fn peek_archive(url: String) -> CommandResult<serde_json::Value> {
+    let output = run_python_script_async("../../scripts/rom-sourcing/rom_browser.py", &["inspect", &url, "--json"]).await?;
+    parse_json_output(&output)
+}
+
+// Searches the archive folder given as platform (e.g. "Redump/Sony - PlayStation") and its mirrors,
+// the Internet Archive items and the local folders; each result names its source, host and size
+#[tauri::command]
+async fn search_all_sources(
+    state: State<'_, AppState>,
+    query: String,
+    platform: Option<String>,
+    group_languages: Option<bool>,
+    request_id: Option<String>,
+) -> CommandResult<Vec<serde_json::Value>> {
+    let mut args = vec!["search", query.as_str(), "--json"];
+    if let Some(platform) = platform.as_deref() {
+        args.extend(["--platform", platform]);
+    }
+    if group_languages.unwrap_or(false) {
+        args.push("--group-languages");
+    }
+    let search = run_python_script_async("../../scripts/rom-sourcing/rom_browser.py", &args);
+    let output = cancellable(&state, request_id, search).await?;
+    parse_json_output(&output)
+}
+
+// Stop a browse or search request started with this request_id; it fails with kind "cancelled".
+// Returns whether it was still running
+#[tauri::command]
+async fn cancel_request(state: State<'_, AppState>, request_id: String) -> CommandResult<bool> {
+    let cancel = state.requests.lock().unwrap_or_else(|e| e.into_inner()).remove(&request_id);
+    Ok(cancel.is_some_and(|cancel| cancel.send(()).is_ok()))
+}
+
+// Downloads one listed file, from an archive or an Internet Archive item; rom_downloader.py
+// checks archive.org files against their published SHA1
+#[tauri::command]
+async fn download_game(app: tauri::AppHandle, game_name: String, url: String) -> CommandResult<String> {
+    require(Capability::Download)?;
+    ensure_writable("download games")?;
+
+    run_script_with_progress(&app, ".", "../../scripts/rom-sourcing/rom_downloader.py", &["get", &url]).await?;
+    Ok(format!("Downloaded {}", game_name))
+}
+
+#[tauri::command]
+async fn get_game_metadata(game_name: String) -> CommandResult<GameMetadata> {
+    let metadata = run_blocking(move || game_metadata(&game_name)).await?;
+    if let Some(error) = metadata.get("error").and_then(|error| error.as_str()) {
+        return Err(RomBrowserError::Other(format!("Failed to query database: {}", error)));
+    }
+    serde_json::from_value(metadata).map_err(|e| RomBrowserError::Parse(format!("Unexpected game metadata: {}", e)))
+}
+
+fn game_metadata(game_name: &str) -> Result<serde_json::Value, RomBrowserError> {
+    // Query the games database for metadata
+    let db_path = games_db_path();
+    
+    if !db_path.exists() {
+        return Ok(serde_json::json!({
+            "name": game_name,
+            "description": "No metadata available",
+            "rating": null,
+            "cover_art": null,
+            "platforms": [],
+            "genres": []
+        }));
+    }
+    
+    // Use Python to query the database; the game name and database are passed as arguments
+    let python_code = [GAMES_DB_PRELUDE_PY, r#"
+game_name = sys.argv[1]
+
+try:
+    conn = sqlite3.connect(sys.argv[2])
+    conn.row_factory = sqlite3.Row
+    cursor = conn.cursor()
+    
+    cursor.execute('''
+        SELECT * FROM games 
+        WHERE name LIKE ? OR name LIKE ?
+    ''', (f'%{game_name}%', f'{game_name}%'))
+    
+    row = cursor.fetchone()
+    overrides = load_overrides(cursor).get(row['name'], {}) if row else {}
+    attributes = get_attributes(conn, [row['id']])[row['id']] if row else {}
+    conn.close()
+    
+    if row:
+        columns = row.keys()
+        result = {
+            'name': row['name'],
+            'display_name': overrides.get('name', row['name']),
+            'rating': overrides.get('rating', row['rating']),
+            'description': decompress_text(row['summary']) or 'No description available',
+            'genres': json.dumps(overrides.get('genres', attributes['genres'])),
+            'platforms': json.dumps(attributes['platforms']),
+            'release_date': row['release_date'] or '',
+            'cover_art': overrides.get('cover', row['cover_url']) or '',
+            'metacritic_score': row['metacritic_score'],
+            'match_confidence': row['match_confidence'] if 'match_confidence' in columns else None,
+            'field_sources': json.loads(row['field_sources'] or '{}') if 'field_sources' in columns else {},
+            'overrides': sorted(overrides),
+            'palette': json.loads(row['cover_palette']) if 'cover_palette' in columns and row['cover_palette'] else None
+        }
+        result['field_sources'].update({field: 'manual' for field in overrides})
+        # The cover file shown, for its palette; relative paths are relative to games.db's folder
+        cover = overrides.get('cover', row['cover_path'])
+        if cover and not cover.startswith(('http://', 'https://')):
+            result['cover_path'] = os.path.join(os.path.dirname(os.path.abspath(sys.argv[2])), cover)
+    else:
+        result = {
+            'name': game_name,
+            'description': 'No metadata found',
+            'rating': None,
+            'cover_art': None,
+            'platforms': [],
+            'genres': []
+        }
     
     print(json.dumps(result))
     
 except Exception as e:
-    print(json.dumps({{'error': str(e)}}))
-"#,
-        db_path, game_name, game_name, game_name
-    );
+    print(json.dumps({'error': str(e)}))
+"#].concat();
     
     let output = Command::new("python")
         .arg("-c")
         .arg(&python_code)
-        .current_dir("../../scripts/game-management")
+        .arg(game_name)
+        .arg(&db_path)
+        .current_dir(GAME_MANAGEMENT_DIR)
+        .output()
+        .map_err(io_error("Failed to query database"))?;
+
+    if !output.status.success() {
+        return Err(RomBrowserError::from_script(&String::from_utf8_lossy(&output.stderr)));
+    }
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    let mut metadata: serde_json::Value = serde_json::from_str(&output_str)
+        .map_err(|e| RomBrowserError::Parse(format!("Failed to parse database result: {}", e)))?;
+    attach_cover_palette(&mut metadata, &db_path);
+    Ok(metadata)
+}
+
+// Give game metadata the palette of its cover, computing it the first time the cover is
+// shown (or after it changed) and keeping it in games.db so detail pages need not
+fn attach_cover_palette(metadata: &mut serde_json::Value, db_path: &Path) {
+    let Some(cover) = metadata.get("cover_path").and_then(|path| path.as_str()).map(PathBuf::from) else {
+        return;
+    };
+    let stored = metadata.get("palette").cloned().and_then(|palette| serde_json::from_value::<CoverPalette>(palette).ok());
+    if stored.is_some_and(|palette| palette.is_current(&cover)) {
+        return;
+    }
+
+    let palette = match palette::extract(&cover) {
+        Ok(palette) => palette,
+        Err(e) => {
+            eprintln!("No palette for {}: {}", cover.display(), e);
+            metadata["palette"] = serde_json::Value::Null;
+            return;
+        }
+    };
+    if !is_read_only() {
+        let name = metadata["name"].as_str().unwrap_or_default();
+        if let Err(e) = store_cover_palette(db_path, name, &palette) {
+            eprintln!("Failed to store the cover palette of {}: {}", name, e);
+        }
+    }
+    metadata["palette"] = serde_json::json!(palette);
+}
+
+fn store_cover_palette(db_path: &Path, game_name: &str, palette: &CoverPalette) -> Result<(), RomBrowserError> {
+    let palette = serde_json::to_string(palette).map_err(|e| RomBrowserError::Parse(e.to_string()))?;
+    let python_code = r#"
+import sqlite3
+import sys
+
+conn = sqlite3.connect(sys.argv[1])
+if 'cover_palette' not in {row[1] for row in conn.execute('PRAGMA table_info(games)')}:
+    conn.execute('ALTER TABLE games ADD COLUMN cover_palette TEXT')
+conn.execute('UPDATE games SET cover_palette = ? WHERE name = ?', (sys.argv[3], sys.argv[2]))
+conn.commit()
+conn.close()
+"#;
+    let output = Command::new("python")
+        .arg("-c")
+        .arg(python_code)
+        .arg(db_path)
+        .arg(game_name)
+        .arg(&palette)
         .output()
-        .map_err(|e| format!("Failed to query database: {}", e))?;
+        .map_err(io_error("Failed to update database"))?;
 
     if output.status.success() {
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        serde_json::from_str(&output_str)
-            .map_err(|e| format!("Failed to parse database result: {}", e))
+        Ok(())
+    } else {
+        Err(RomBrowserError::from_script(&String::from_utf8_lossy(&output.stderr)))
+    }
+}
+
+#[tauri::command]
+async fn get_metadata_candidates(game_name: String, limit: Option<u32>) -> CommandResult<Vec<MetadataCandidate>> {
+    let limit = limit.unwrap_or(5).to_string();
+    let output = run_game_management_script(
+        "metadata_downloader.py",
+        &["candidates", &game_name, "--limit", &limit, "--json"],
+    ).await?;
+    parse_json_output(&output)
+}
+
+// Marks or unmarks a favorite; metadata scans fetch favorites first
+#[tauri::command]
+async fn set_favorite(game_name: String, favorite: bool) -> CommandResult<()> {
+    require(Capability::EditMetadata)?;
+    ensure_writable("change favorites")?;
+    let mut args = vec!["favorite", game_name.as_str(), "--json"];
+    if !favorite {
+        args.push("--remove");
+    }
+    run_game_management_script("metadata_downloader.py", &args).await?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn apply_metadata_candidate(game_name: String, source: String, candidate_id: String) -> CommandResult<serde_json::Value> {
+    require(Capability::EditMetadata)?;
+    ensure_writable("update game metadata")?;
+
+    let output = run_game_management_script(
+        "metadata_downloader.py",
+        &["apply", &game_name, "--source", &source, "--id", &candidate_id, "--json"],
+    ).await?;
+
+    let metadata: serde_json::Value = parse_json_output(&output)?;
+    if metadata.is_null() {
+        return Err(RomBrowserError::NotFound(format!("No metadata found for {} candidate {}", source, candidate_id)));
+    }
+    Ok(metadata)
+}
+
+#[tauri::command]
+async fn set_game_override(game_name: String, field: String, value: Option<String>) -> CommandResult<String> {
+    require(Capability::EditMetadata)?;
+    ensure_writable("change game overrides")?;
+
+    let flag = format!("--{}", field);
+    let args: Vec<&str> = match &value {
+        Some(value) => vec!["override", &game_name, &flag, value],
+        None => vec!["override", &game_name, "--clear", &field],
+    };
+
+    run_game_management_script("metadata_downloader.py", &args).await?;
+    Ok(match value {
+        Some(_) => format!("Pinned {} for {}", field, game_name),
+        None => format!("Cleared {} override for {}", field, game_name),
+    })
+}
+
+#[tauri::command]
+async fn refresh_metadata(
+    app: tauri::AppHandle,
+    game_name: Option<String>,
+    max_age_days: Option<u32>,
+) -> CommandResult<serde_json::Value> {
+    require(Capability::EditMetadata)?;
+    ensure_writable("refresh game metadata")?;
+
+    let max_age = max_age_days.map(|days| days.to_string());
+    let mut args = vec!["refresh", "--json"];
+    match (&game_name, &max_age) {
+        (Some(name), _) => args.extend(["--game", name.as_str()]),
+        (None, Some(days)) => args.extend(["--all", "--max-age-days", days.as_str()]),
+        (None, None) => args.push("--all"),
+    }
+
+    let output = run_script_with_progress(&app, GAME_MANAGEMENT_DIR, "metadata_downloader.py", &args).await?;
+    parse_json_output(&output)
+}
+
+// Searches again, under alternate queries, for the games no provider matched; only the ones
+// due unless include_waiting is set
+#[tauri::command]
+async fn retry_failed_scrapes(app: tauri::AppHandle, include_waiting: Option<bool>) -> CommandResult<serde_json::Value> {
+    require(Capability::EditMetadata)?;
+    ensure_writable("retry failed scrapes")?;
+
+    let mut args = vec!["retry", "--json"];
+    if include_waiting.unwrap_or(false) {
+        args.push("--all");
+    }
+
+    let output = run_script_with_progress(&app, GAME_MANAGEMENT_DIR, "metadata_downloader.py", &args).await?;
+    parse_json_output(&output)
+}
+
+// The games waiting for a scrape retry, with their attempts and when each is next due
+#[tauri::command]
+async fn get_scrape_retries() -> CommandResult<Vec<serde_json::Value>> {
+    let output = run_game_management_script("metadata_downloader.py", &["retry", "--list", "--json"]).await?;
+    parse_json_output(&output)
+}
+
+#[tauri::command]
+async fn check_library_sources(app: tauri::AppHandle, check_all: Option<bool>) -> CommandResult<Vec<serde_json::Value>> {
+    require(Capability::ScanLibrary)?;
+
+    let script_path = "../../scripts/rom-sourcing/library_manager.py";
+    let mut args = vec!["check-urls", "--json"];
+    if check_all.unwrap_or(false) {
+        args.push("--all");
+    }
+
+    let output = run_script_with_progress(&app, ".", script_path, &args).await?;
+    parse_json_output(&output)
+}
+
+// Library entries whose source file changed since they were downloaded
+#[tauri::command]
+async fn get_outdated_library_entries() -> CommandResult<Vec<serde_json::Value>> {
+    let output = run_python_script("../../scripts/rom-sourcing/library_manager.py", &["outdated", "--json"]).await?;
+    parse_json_output(&output)
+}
+
+// Why a downloaded file was picked for its queued title: the score parts of every candidate,
+// or null for files downloaded by URL
+#[tauri::command]
+async fn get_match_explanation(path: String) -> CommandResult<serde_json::Value> {
+    let output = run_python_script("../../scripts/rom-sourcing/library_manager.py", &["explain", &path, "--json"]).await?;
+    parse_json_output(&output)
+}
+
+// Remembers the file a user picked for a title, so queue downloads of the title on this platform
+// (a dataset folder such as "Sony - PlayStation" with dataset "redump") get it instead of the
+// automatic match
+#[tauri::command]
+async fn remember_match(platform: String, dataset: Option<String>, title: String, filename: String) -> CommandResult<serde_json::Value> {
+    require(Capability::Download)?;
+    ensure_writable("remember a match")?;
+
+    let dataset = dataset.unwrap_or_else(|| "redump".to_string());
+    let output = run_python_script("../../scripts/rom-sourcing/rom_downloader.py", &[
+        "--platform", &platform, "--dataset", &dataset, "--remember-match", &title, &filename, "--json",
+    ]).await?;
+    parse_json_output(&output)
+}
+
+// Drops a remembered pick, so the title is matched automatically again
+#[tauri::command]
+async fn forget_match(platform: String, dataset: Option<String>, title: String) -> CommandResult<serde_json::Value> {
+    require(Capability::Download)?;
+    ensure_writable("forget a remembered match")?;
+
+    let dataset = dataset.unwrap_or_else(|| "redump".to_string());
+    let output = run_python_script("../../scripts/rom-sourcing/rom_downloader.py", &[
+        "--platform", &platform, "--dataset", &dataset, "--forget-match", &title, "--json",
+    ]).await?;
+    parse_json_output(&output)
+}
+
+// Every remembered pick: {folder, title, filename, set_at}
+#[tauri::command]
+async fn get_remembered_matches() -> CommandResult<Vec<serde_json::Value>> {
+    let output = run_python_script("../../scripts/rom-sourcing/rom_downloader.py", &["--list-matches", "--json"]).await?;
+    parse_json_output(&output)
+}
+
+// What processing the queue would download, without downloading: {count, total_bytes,
+// download_bytes, unknown_sizes, files, unmatched}. Titles are matched against platform
+#[tauri::command]
+async fn preview_download_queue(platform: Option<String>, dataset: Option<String>) -> CommandResult<serde_json::Value> {
+    let dataset = dataset.unwrap_or_else(|| "redump".to_string());
+    let mut args = vec!["--queue", "--dry-run", "--json", "--dataset", dataset.as_str()];
+    if let Some(platform) = platform.as_deref() {
+        args.extend(["--platform", platform]);
+    }
+    let output = run_python_script("../../scripts/rom-sourcing/rom_downloader.py", &args).await?;
+    parse_json_output(&output)
+}
+
+// Deletes games from disk and the library: library entry ids or file paths, each with what its
+// download left next to it and its extracted icon. The whole batch is refused if one target is
+// outside the collection folders. archive keeps the entries among the archived ones
+#[tauri::command]
+async fn delete_games(targets: Vec<String>, archive: Option<bool>) -> CommandResult<serde_json::Value> {
+    require(Capability::DeleteGames)?;
+    ensure_writable("delete games")?;
+
+    // The GUI asks for confirmation itself
+    let mut args = vec!["delete", "--yes", "--json"];
+    if archive.unwrap_or(false) {
+        args.push("--archive");
+    }
+    // Targets are paths or names, so one starting with '-' must not be read as an option
+    args.push("--");
+    args.extend(targets.iter().map(String::as_str));
+    let output = run_python_script("../../scripts/rom-sourcing/library_manager.py", &args).await?;
+    parse_json_output(&output)
+}
+
+// The newest events of the activity feed: {id, at, action, summary, details}
+#[tauri::command]
+async fn get_activity(limit: Option<u32>) -> CommandResult<Vec<serde_json::Value>> {
+    let limit = limit.unwrap_or(50).to_string();
+    let output = run_python_script("../../scripts/rom-sourcing/library_manager.py", &["activity", "--limit", &limit, "--json"]).await?;
+    parse_json_output(&output)
+}
+
+// The filter browsing, searching and queue building apply until it is cleared:
+// {regions, exclude_clones, only_unowned, active}
+#[tauri::command]
+async fn get_session_filter() -> CommandResult<serde_json::Value> {
+    let output = run_python_script("../../scripts/rom-sourcing/rom_browser.py", &["session", "--json"]).await?;
+    parse_json_output(&output)
+}
+
+// Changes the parts of the session filter given (no regions is any region) and drops the cached
+// listings, which were filtered with the old one. Settings are left alone
+#[tauri::command]
+async fn set_session_filter(
+    state: State<'_, AppState>,
+    regions: Option<Vec<String>>,
+    exclude_clones: Option<bool>,
+    only_unowned: Option<bool>,
+) -> CommandResult<serde_json::Value> {
+    let mut args = vec!["session", "set", "--json"];
+    match regions.as_deref() {
+        Some([]) => args.push("--any-region"),
+        Some(regions) => regions.iter().for_each(|region| args.extend(["--region", region.as_str()])),
+        None => {}
+    }
+    match exclude_clones {
+        Some(true) => args.push("--exclude-clones"),
+        Some(false) => args.push("--keep-clones"),
+        None => {}
+    }
+    match only_unowned {
+        Some(true) => args.push("--only-unowned"),
+        Some(false) => args.push("--include-owned"),
+        None => {}
+    }
+    let output = run_python_script("../../scripts/rom-sourcing/rom_browser.py", &args).await?;
+    state.listings.write().unwrap_or_else(|e| e.into_inner()).clear();
+    parse_json_output(&output)
+}
+
+#[tauri::command]
+async fn clear_session_filter(state: State<'_, AppState>) -> CommandResult<serde_json::Value> {
+    let output = run_python_script("../../scripts/rom-sourcing/rom_browser.py", &["session", "clear", "--json"]).await?;
+    state.listings.write().unwrap_or_else(|e| e.into_inner()).clear();
+    parse_json_output(&output)
+}
+
+// Replaces outdated entries with the source's current version; all of them when no ids are given
+#[tauri::command]
+async fn redownload_library_entries(ids: Option<Vec<i64>>) -> CommandResult<Vec<serde_json::Value>> {
+    require(Capability::Download)?;
+    ensure_writable("re-download library entries")?;
+
+    let ids: Vec<String> = ids.unwrap_or_default().iter().map(|id| id.to_string()).collect();
+    let mut args = vec!["redownload", "--json"];
+    if ids.is_empty() {
+        args.push("--all");
     } else {
-        Err(format!("Database query error: {}", String::from_utf8_lossy(&output.stderr)))
+        args.extend(ids.iter().map(String::as_str));
     }
+
+    // A failed re-download exits non-zero but still reports every result
+    let args = owned_args(&args);
+    let output = run_blocking(move || {
+        Command::new("python")
+            .arg("../../scripts/rom-sourcing/library_manager.py")
+            .args(&args)
+            .env(ERRORS_ENV, "json")
+            .output()
+            .map_err(io_error("Failed to execute Python script"))
+    }).await?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim().is_empty() {
+        return Err(RomBrowserError::from_script(&String::from_utf8_lossy(&output.stderr)));
+    }
+    parse_json_output(&stdout)
+}
+
+// Copies the library to a configured device (or mount path); the "artwork"
+// profile pushes only gamelists and artwork to a device that has the ROMs
+#[tauri::command]
+async fn sync_device(
+    app: tauri::AppHandle,
+    device: String,
+    profile: Option<String>,
+    dry_run: Option<bool>,
+    verify_percent: Option<f64>,
+) -> CommandResult<serde_json::Value> {
+    let dry_run = dry_run.unwrap_or(false);
+    if !dry_run {
+        require(Capability::ScanLibrary)?;
+        ensure_writable("sync the library to a device")?;
+    }
+
+    let mut args = vec!["sync", device.as_str(), "--json"];
+    if let Some(profile) = &profile {
+        args.extend(["--profile", profile.as_str()]);
+    }
+    if dry_run {
+        args.push("--dry-run");
+    }
+    let verify_percent = verify_percent.map(|percent| percent.to_string());
+    if let Some(percent) = &verify_percent {
+        args.extend(["--verify-percent", percent.as_str()]);
+    }
+
+    let output = run_script_with_progress(&app, GAME_MANAGEMENT_DIR, "device_sync.py", &args).await?;
+    parse_json_output(&output)
+}
+
+// Per-platform completion: titles owned and verified out of the DAT (or cached catalog) set, with sizes.
+// verify first checks the library files not checked yet, reporting progress as it hashes
+#[tauri::command]
+async fn get_collection_stats(
+    app: tauri::AppHandle,
+    include_all: Option<bool>,
+    verify: Option<bool>,
+) -> CommandResult<Vec<serde_json::Value>> {
+    let script_path = "../../scripts/rom-sourcing/library_manager.py";
+    let mut args = vec!["stats", "--json"];
+    if include_all.unwrap_or(false) {
+        args.push("--all");
+    }
+    let output = if verify.unwrap_or(false) {
+        require(Capability::ScanLibrary)?;
+        args.push("--verify");
+        run_script_with_progress(&app, ".", script_path, &args).await?
+    } else {
+        run_python_script(script_path, &args).await?
+    };
+    parse_json_output(&output)
+}
+
+#[tauri::command]
+async fn get_speed_history(days: Option<u32>) -> CommandResult<serde_json::Value> {
+    let script_path = "../../scripts/rom-sourcing/library_manager.py";
+    let days = days.unwrap_or(30).to_string();
+    let output = run_python_script(script_path, &["speed-history", "--days", &days, "--json"]).await?;
+    parse_json_output(&output)
 }
 
 #[tauri::command]
-async fn get_library_games() -> Result<Vec<GameInfo>, String> {
+async fn get_library_games() -> CommandResult<Vec<GameInfo>> {
     // Get games from the database
-    let db_path = "../../scripts/game-management/games.db";
+    let db_path = games_db_path();
     
-    if !Path::new(db_path).exists() {
+    if !db_path.exists() {
         return Ok(vec![]);
     }
     
-    let python_code = r#"
-import sqlite3
-import json
-import sys
-
+    let python_code = [GAMES_DB_PRELUDE_PY, r#"
 try:
-    conn = sqlite3.connect('games.db')
+    conn = sqlite3.connect(sys.argv[1])
+    conn.row_factory = sqlite3.Row
     cursor = conn.cursor()
     
+    all_overrides = load_overrides(cursor)
+    attributes = get_attributes(conn)
+    favorites = load_favorites(cursor)
     cursor.execute('''
-        SELECT name, rating, summary, genres, platforms, release_date, cover_url, metacritic_score
-        FROM games 
+        SELECT * FROM games 
         ORDER BY name
     ''')
     
     games = []
     for row in cursor.fetchall():
+        overrides = all_overrides.get(row['name'], {})
         game = {
-            'name': row[0],
+            'name': row['name'],
+            'display_name': overrides.get('name'),
             'platform': 'PC',  # Default platform for library games
-            'rating': row[1],
-            'summary': row[2],
-            'genres': row[3],
-            'release_date': row[5],
-            'cover_art': row[6],
-            'metacritic_score': row[7],
-            'is_favorite': False,  # Would need separate favorites table
+            'rating': overrides.get('rating', row['rating']),
+            'summary': decompress_text(row['summary']),
+            'genres': json.dumps(overrides.get('genres', attributes[row['id']]['genres'])),
+            'release_date': row['release_date'],
+            'cover_art': overrides.get('cover', row['cover_url']),
+            'metacritic_score': row['metacritic_score'],
+            'match_confidence': row['match_confidence'] if 'match_confidence' in row.keys() else None,
+            'is_favorite': row['name'] in favorites,
             'is_downloaded': True,  # Games in library are downloaded
             'size': None,
             'url': None
@@ -302,92 +1842,481 @@ try:
     
 except Exception as e:
     print(json.dumps({'error': str(e)}))
-"#;
+"#].concat();
     
-    let output = Command::new("python")
-        .arg("-c")
-        .arg(python_code)
-        .current_dir("../../scripts/game-management")
-        .output()
-        .map_err(|e| format!("Failed to query library: {}", e))?;
+    let output = run_blocking(move || {
+        Command::new("python")
+            .arg("-c")
+            .arg(&python_code)
+            .arg(&db_path)
+            .current_dir(GAME_MANAGEMENT_DIR)
+            .output()
+            .map_err(io_error("Failed to query library"))
+    }).await?;
 
     if output.status.success() {
         let output_str = String::from_utf8_lossy(&output.stdout);
         serde_json::from_str(&output_str)
-            .map_err(|e| format!("Failed to parse library result: {}", e))
+            .map_err(|e| RomBrowserError::Parse(format!("Failed to parse library result: {}", e)))
     } else {
-        Err(format!("Library query error: {}", String::from_utf8_lossy(&output.stderr)))
+        Err(RomBrowserError::from_script(&String::from_utf8_lossy(&output.stderr)))
     }
 }
 
+// The GUI settings, all kept in the shared config so the scripts see the same values
+fn read_settings() -> SettingsData {
+    let rom_config = read_rom_config();
+    let value = |pointer: &str| rom_config.as_ref().and_then(|config| config.pointer(pointer).cloned());
+    let setting = |pointer: &str| {
+        value(pointer)
+            .and_then(|v| v.as_str().map(String::from))
+            .filter(|value| !value.is_empty())
+    };
+    let number = |pointer: &str| value(pointer).and_then(|v| v.as_f64());
+    let count = |pointer: &str, default: u32| {
+        value(pointer).and_then(|v| v.as_u64()).map_or(default, |v| u32::try_from(v).unwrap_or(u32::MAX))
+    };
+    let defaults = CacheTtls::default();
+
+    SettingsData {
+        rom_directories: value("/library/rom_directories")
+            .and_then(|v| v.as_array().cloned())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|v| v.as_str().map(String::from))
+            .collect(),
+        download_directory: setting("/paths/download_directory").unwrap_or_else(default_download_directory),
+        metadata_api_key: setting("/api_keys/rawg").unwrap_or_default(),
+        auto_scan: value("/library/auto_scan").and_then(|v| v.as_bool()).unwrap_or(false),
+        scan_interval: count("/library/scan_interval_minutes", 30),
+        max_concurrent_downloads: count("/downloads/max_concurrent", 3),
+        read_only: is_read_only(),
+        proxy_url: setting("/network/proxy_url").unwrap_or_default(),
+        cache_ttls: CacheTtls {
+            platform_list_hours: number("/cache/platform_list_hours").unwrap_or(defaults.platform_list_hours),
+            listing_hours: number("/cache/listing_hours").unwrap_or(defaults.listing_hours),
+            provider_response_hours: number("/cache/provider_response_hours").unwrap_or(defaults.provider_response_hours),
+        },
+        job_limits: JobLimits::read(number),
+    }
+}
+
+#[tauri::command]
+async fn get_settings() -> CommandResult<SettingsData> {
+    Ok(read_settings())
+}
+
 #[tauri::command]
-async fn get_settings() -> Result<SettingsData, String> {
-    // Read settings from config files
-    let config_path = "../../config/game_directories.conf";
-    let mut rom_directories = Vec::new();
+async fn save_settings(state: State<'_, AppState>, settings: SettingsData) -> CommandResult<String> {
+    require(Capability::EditSettings)?;
+
+    let rom_directories: Vec<String> = settings.rom_directories.iter()
+        .map(|dir| dir.trim().to_string())
+        .filter(|dir| !dir.is_empty())
+        .collect();
+    let download_directory = match settings.download_directory.trim() {
+        "" => serde_json::Value::Null,
+        directory if directory == default_download_directory() => serde_json::Value::Null,
+        directory => serde_json::Value::String(directory.to_string()),
+    };
+    update_rom_config(&[
+        ("safety", "read_only", serde_json::Value::Bool(settings.read_only)),
+        ("network", "proxy_url", serde_json::Value::String(settings.proxy_url.trim().to_string())),
+        ("api_keys", "rawg", serde_json::Value::String(settings.metadata_api_key.trim().to_string())),
+        ("paths", "download_directory", download_directory),
+        ("library", "rom_directories", serde_json::json!(rom_directories)),
+        ("library", "auto_scan", serde_json::Value::Bool(settings.auto_scan)),
+        ("library", "scan_interval_minutes", serde_json::json!(settings.scan_interval.clamp(1, 1440))),
+        ("downloads", "max_concurrent", serde_json::json!(settings.max_concurrent_downloads.clamp(1, 10))),
+        ("cache", "platform_list_hours", serde_json::json!(settings.cache_ttls.platform_list_hours.max(0.0))),
+        ("cache", "listing_hours", serde_json::json!(settings.cache_ttls.listing_hours.max(0.0))),
+        ("cache", "provider_response_hours", serde_json::json!(settings.cache_ttls.provider_response_hours.max(0.0))),
+    ])?;
+    settings.job_limits.save()?;
+    // A different proxy or cache time changes what the listings should be
+    state.invalidate();
     
-    if Path::new(config_path).exists() {
-        if let Ok(content) = std::fs::read_to_string(config_path) {
-            for line in content.lines() {
-                let line = line.trim();
-                if !line.is_empty() && !line.starts_with('#') && !line.starts_with("OUTPUT_DIR") {
-                    rom_directories.push(line.to_string());
-                }
+    Ok("Settings saved successfully".to_string())
+}
+
+// Changes the limits of hashing and scanning jobs without saving the other settings; jobs
+// already running slow down or speed up within a few seconds
+#[tauri::command]
+async fn set_job_limits(limits: JobLimits) -> CommandResult<JobLimits> {
+    require(Capability::EditSettings)?;
+    limits.save()?;
+    Ok(read_settings().job_limits)
+}
+
+// Deletes the cached platform lists, archive listings and provider responses; covers are kept
+#[tauri::command]
+async fn clear_caches(state: State<'_, AppState>) -> CommandResult<serde_json::Value> {
+    require(Capability::EditSettings)?;
+    ensure_writable("clear the caches")?;
+    state.invalidate();
+
+    let output = run_game_management_script("cache_manager.py", &["clear", "--json"]).await?;
+    parse_json_output(&output)
+}
+
+// Each platform's configured emulator, with any missing files listed under problems
+#[tauri::command]
+async fn get_emulators() -> CommandResult<Vec<serde_json::Value>> {
+    let output = run_python_script("../../scripts/common/emulators.py", &["list", "--json"]).await?;
+    parse_json_output(&output)
+}
+
+// Starts the platform's emulator with its test ROM; a setup that fails comes back with ok false
+#[tauri::command]
+async fn test_launch(platform: String) -> CommandResult<serde_json::Value> {
+    require(Capability::EditSettings)?;
+    let output = run_python_script("../../scripts/common/emulators.py", &["test", &platform, "--json"]).await?;
+    parse_json_output(&output)
+}
+
+// Sent to every window with the whole app config after a setting changes, here or in the file
+const APP_CONFIG_CHANGED_EVENT: &str = "app-config-changed";
+// Where config_manager.py keeps app_config.json, relative to the directory the scripts run from
+const APP_CONFIG_PATH: &str = "../../scripts/game-management/app_config.json";
+// Editors and atomic saves touch the file several times per save; changes are read once they settle
+const APP_CONFIG_SETTLE: std::time::Duration = std::time::Duration::from_millis(250);
+
+// The config last sent to the windows, so a save made through the GUI is not announced twice
+static ANNOUNCED_APP_CONFIG: std::sync::Mutex<Option<serde_json::Value>> = std::sync::Mutex::new(None);
+
+fn announce_app_config(app: &tauri::AppHandle, config: &serde_json::Value) -> Result<(), RomBrowserError> {
+    let mut announced = ANNOUNCED_APP_CONFIG.lock().unwrap_or_else(|e| e.into_inner());
+    if announced.as_ref() == Some(config) {
+        return Ok(());
+    }
+    *announced = Some(config.clone());
+    app.emit_all(APP_CONFIG_CHANGED_EVENT, config.clone())
+        .map_err(|e| RomBrowserError::Other(format!("Failed to announce the config change: {:?}", e)))
+}
+
+// Re-reads app_config.json whenever something else (an editor, the CLI) changes it, so the
+// windows restyle without a restart. The directory is watched rather than the file, because
+// an atomic save replaces the file
+fn watch_app_config(app: tauri::AppHandle) {
+    use notify::{RecursiveMode, Watcher};
+
+    std::thread::spawn(move || {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut watcher = match notify::recommended_watcher(sender) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                eprintln!("Cannot watch the app config for changes: {}", e);
+                return;
             }
+        };
+        let path = Path::new(APP_CONFIG_PATH);
+        let directory = path.parent().unwrap_or(Path::new("."));
+        if let Err(e) = watcher.watch(directory, RecursiveMode::NonRecursive) {
+            eprintln!("Cannot watch {} for changes: {}", directory.display(), e);
+            return;
         }
-    }
-    
-    Ok(SettingsData {
-        rom_directories,
-        download_directory: "../../downloads".to_string(),
-        metadata_api_key: "".to_string(),
-        auto_scan: true,
-        scan_interval: 30,
-        max_concurrent_downloads: 3,
+
+        while let Ok(event) = receiver.recv() {
+            let touches_config = event
+                .map(|event| event.paths.iter().any(|changed| changed.file_name() == path.file_name()))
+                .unwrap_or(false);
+            if !touches_config {
+                continue;
+            }
+            std::thread::sleep(APP_CONFIG_SETTLE);
+            while receiver.try_recv().is_ok() {}
+
+            let config = run_game_management_script_blocking("config_manager.py", &["get"])
+                .and_then(|output| parse_json_output::<serde_json::Value>(&output));
+            if let Err(e) = config.and_then(|config| announce_app_config(&app, &config)) {
+                eprintln!("Failed to reload the app config: {}", e);
+            }
+        }
+    });
+}
+
+// The appearance and behavior settings in app_config.json, with defaults filled in
+#[tauri::command]
+async fn get_app_config() -> CommandResult<serde_json::Value> {
+    let output = run_game_management_script("config_manager.py", &["get"]).await?;
+    parse_json_output(&output)
+}
+
+#[tauri::command]
+async fn set_app_config_value(app: tauri::AppHandle, key: String, value: serde_json::Value) -> CommandResult<serde_json::Value> {
+    require(Capability::EditSettings)?;
+    let value = serde_json::to_string(&value)
+        .map_err(|e| RomBrowserError::Parse(format!("Failed to serialize setting: {}", e)))?;
+    let output = run_game_management_script("config_manager.py", &["set", &key, &value]).await?;
+    let config: serde_json::Value = parse_json_output(&output)?;
+    announce_app_config(&app, &config)?;
+    Ok(config)
+}
+
+#[tauri::command]
+async fn get_keybindings() -> CommandResult<HashMap<String, String>> {
+    let output = run_game_management_script("config_manager.py", &["keybindings", "get"]).await?;
+    parse_json_output(&output)
+}
+
+#[tauri::command]
+async fn set_keybindings(bindings: HashMap<String, String>) -> CommandResult<HashMap<String, String>> {
+    require(Capability::EditSettings)?;
+    let bindings = serde_json::to_string(&bindings)
+        .map_err(|e| RomBrowserError::Parse(format!("Failed to serialize keybindings: {}", e)))?;
+    let output = run_game_management_script("config_manager.py", &["keybindings", "set", &bindings]).await?;
+    parse_json_output(&output)
+}
+
+#[tauri::command]
+async fn get_capabilities() -> CommandResult<CapabilityInfo> {
+    let profile = current_profile();
+    Ok(CapabilityInfo {
+        capabilities: profile_capabilities(&profile).to_vec(),
+        profile,
     })
 }
 
+// Where the GUI was when last used: the page, the platform (its id names the archive folder,
+// archive.org item or local directory), the search and the game scrolled to
+#[derive(Debug, Serialize, Deserialize)]
+struct BrowseSession {
+    page: String,
+    platform_id: Option<String>,
+    #[serde(default)]
+    search: String,
+    scroll_anchor: Option<String>,
+    // Seconds since the Unix epoch, set when saved
+    #[serde(default)]
+    saved_at: u64,
+}
+
+const SESSION_FILE_NAME: &str = "gui_session.json";
+// How old a session may be and still be restored, unless behavior.restore_session_hours says otherwise
+const DEFAULT_RESTORE_SESSION_HOURS: f64 = 24.0;
+
+fn session_path() -> PathBuf {
+    data_directory().join(SESSION_FILE_NAME)
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
 #[tauri::command]
-async fn save_settings(settings: SettingsData) -> Result<String, String> {
-    // Save settings to config files
-    let config_path = "../../config/game_directories.conf";
-    
-    let mut content = String::new();
-    content.push_str("# Game Shortcut Creator Configuration\n");
-    content.push_str("# This file contains all game installation directories across all drives\n");
-    content.push_str("# Format: One directory per line, comments start with #\n\n");
-    
-    for dir in &settings.rom_directories {
-        content.push_str(&format!("{}\n", dir));
+async fn save_browse_session(mut session: BrowseSession) -> CommandResult<()> {
+    session.saved_at = unix_now();
+    let content = serde_json::to_string_pretty(&session)
+        .map_err(|e| RomBrowserError::Parse(format!("Failed to serialize the session: {}", e)))?;
+    let path = session_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(io_error(format!("Failed to create {}", parent.display())))?;
     }
-    
-    content.push_str(&format!("\n# Output directory for shortcuts\nOUTPUT_DIR = {}\n", settings.download_directory));
-    
-    std::fs::write(config_path, content)
-        .map_err(|e| format!("Failed to save settings: {}", e))?;
-    
-    Ok("Settings saved successfully".to_string())
+    write_atomically(&path, &content).map_err(io_error("Failed to save the session"))
 }
 
+// The last session, if it was saved within behavior.restore_session_hours (0 never restores);
+// an older one is left alone so a fresh launch starts from the library
 #[tauri::command]
-async fn start_rom_scan() -> Result<String, String> {
-    // Call the Python scanning script
-    let script_path = "../../scripts/game-management/smart_metadata_downloader.py";
-    
-    // For now, simulate the scan
-    Ok("ROM scan started successfully".to_string())
+async fn restore_last_session() -> CommandResult<Option<BrowseSession>> {
+    let path = session_path();
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    let session: BrowseSession = match serde_json::from_str(&content) {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("Ignoring the unreadable session in {}: {}", path.display(), e);
+            return Ok(None);
+        }
+    };
+
+    let hours = run_game_management_script("config_manager.py", &["get"]).await
+        .and_then(|output| parse_json_output::<serde_json::Value>(&output))
+        .ok()
+        .and_then(|config| config.pointer("/behavior/restore_session_hours").and_then(|v| v.as_f64()))
+        .unwrap_or(DEFAULT_RESTORE_SESSION_HOURS);
+    let age = unix_now().saturating_sub(session.saved_at) as f64;
+    Ok((hours > 0.0 && age <= hours * 3600.0).then_some(session))
+}
+
+// Adds the games in the ROM directories to the library and fetches their metadata
+#[tauri::command]
+async fn start_rom_scan(app: tauri::AppHandle) -> CommandResult<String> {
+    require(Capability::ScanLibrary)?;
+    ensure_writable("scan ROMs into the library")?;
+
+    run_script_with_progress(&app, GAME_MANAGEMENT_DIR, "smart_metadata_downloader.py", &[]).await?;
+    Ok("ROM scan finished".to_string())
+}
+
+// Scans the ROM directories every library.scan_interval_minutes while library.auto_scan is on;
+// the settings are read again before each scan, so changes apply without a restart
+fn spawn_auto_scan() {
+    std::thread::spawn(|| loop {
+        let settings = read_settings();
+        if settings.auto_scan && !is_read_only() && require(Capability::ScanLibrary).is_ok() {
+            if let Err(e) = run_game_management_script_blocking("smart_metadata_downloader.py", &[]) {
+                eprintln!("Automatic ROM scan failed: {}", e);
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_secs(u64::from(settings.scan_interval.max(1)) * 60));
+    });
+}
+
+// How often the GUI looks for library entries whose source URL is due for a check
+const URL_CHECK_PASS_MINUTES: u64 = 60;
+
+// Re-checks the source URLs of the library entries not checked within
+// library.url_check_interval_hours (0 turns it off), so vanished and changed sources are flagged
+// without running library_manager.py check-urls by hand; each pass only checks the entries due
+fn spawn_url_checks() {
+    std::thread::spawn(|| loop {
+        let interval = read_rom_config()
+            .and_then(|config| config.pointer("/library/url_check_interval_hours").and_then(|v| v.as_f64()))
+            .unwrap_or(168.0);
+        if interval > 0.0 && !is_read_only() && require(Capability::ScanLibrary).is_ok() {
+            if let Err(e) = run_python_script_blocking("../../scripts/rom-sourcing/library_manager.py", &["check-urls", "--json"]) {
+                eprintln!("Automatic source URL check failed: {}", e);
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_secs(URL_CHECK_PASS_MINUTES * 60));
+    });
+}
+
+// Sent once on start with what job_recovery.py did to each job the last run left unfinished
+const JOBS_RECOVERED_EVENT: &str = "jobs-recovered";
+
+// The recovery's results, for windows that were not listening yet when it finished
+static RECOVERED_JOBS: std::sync::Mutex<Option<Vec<serde_json::Value>>> = std::sync::Mutex::new(None);
+
+// Cleans up or resumes the downloads, scans and conversions a crash interrupted. Resumed downloads
+// can take a while, so the windows hear about it by event rather than waiting on it
+fn recover_interrupted_jobs(app: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        let output = run_python_script_blocking("../../scripts/rom-sourcing/job_recovery.py", &["recover", "--json"]);
+        match output.and_then(|output| parse_json_output::<Vec<serde_json::Value>>(&output)) {
+            Ok(results) => {
+                *RECOVERED_JOBS.lock().unwrap_or_else(|e| e.into_inner()) = Some(results.clone());
+                if !results.is_empty() {
+                    if let Err(e) = app.emit_all(JOBS_RECOVERED_EVENT, results) {
+                        eprintln!("Failed to announce the recovered jobs: {:?}", e);
+                    }
+                }
+            }
+            Err(e) => eprintln!("Recovering interrupted jobs failed: {}", e),
+        }
+    });
+}
+
+// The JSON schema of every type the commands return and the scripts write, by type name
+#[tauri::command]
+fn get_json_schemas() -> BTreeMap<&'static str, schemars::schema::RootSchema> {
+    schemas::json_schemas()
+}
+
+// What the recovery on start did, or None while it still runs
+#[tauri::command]
+fn get_recovered_jobs() -> Option<Vec<serde_json::Value>> {
+    RECOVERED_JOBS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+// The daemon that owns the library database and download queue while the GUI runs, so scripts
+// started from a terminal meanwhile queue through it instead of writing the files themselves.
+// Holding the child keeps its stdin open; the daemon exits when the GUI does and stdin closes
+static DAEMON: std::sync::Mutex<Option<std::process::Child>> = std::sync::Mutex::new(None);
+
+fn start_daemon() {
+    let child = Command::new("python")
+        .arg("../../scripts/rom-sourcing/rom_daemon.py")
+        .args(["serve", "--exit-with-stdin"])
+        .stdin(std::process::Stdio::piped())
+        .spawn();
+    match child {
+        Ok(child) => *DAEMON.lock().unwrap_or_else(|e| e.into_inner()) = Some(child),
+        Err(e) => eprintln!("Failed to start the daemon, scripts will use the files directly: {}", e),
+    }
 }
 
 fn main() {
+    // --config <file> picks the settings file for the GUI and every script it runs
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(path) = args.iter().position(|arg| arg == "--config").and_then(|i| args.get(i + 1)) {
+        std::env::set_var(CONFIG_ENV, path);
+    }
+    // --write-schemas <dir> writes the JSON schemas (as get_json_schemas returns them) and exits
+    if let Some(dir) = args.iter().position(|arg| arg == "--write-schemas").and_then(|i| args.get(i + 1)) {
+        if let Err(e) = schemas::write_schemas(Path::new(dir)) {
+            eprintln!("Failed to write the schemas to {}: {}", dir, e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    start_daemon();
+    spawn_auto_scan();
+    spawn_url_checks();
+
     tauri::Builder::default()
+        .manage(AppState::default())
+        .setup(|app| {
+            watch_app_config(app.app_handle());
+            recover_interrupted_jobs(app.app_handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_platforms,
             browse_platform,
+            browse_platform_paginated,
+            browse_platform_grouped,
+            search_in_platform,
+            search_all_sources,
+            cancel_request,
             download_game,
             get_game_metadata,
+            get_metadata_candidates,
+            apply_metadata_candidate,
+            set_favorite,
+            refresh_metadata,
+            retry_failed_scrapes,
+            get_scrape_retries,
+            set_game_override,
+            check_library_sources,
+            get_outdated_library_entries,
+            get_match_explanation,
+            remember_match,
+            forget_match,
+            get_remembered_matches,
+            preview_download_queue,
+            peek_archive,
+            delete_games,
+            get_activity,
+            get_session_filter,
+            set_session_filter,
+            clear_session_filter,
+            redownload_library_entries,
+            sync_device,
+            get_speed_history,
+            get_collection_stats,
             get_library_games,
             get_settings,
             save_settings,
+            set_job_limits,
+            get_recovered_jobs,
+            get_json_schemas,
+            clear_caches,
+            get_emulators,
+            test_launch,
+            get_app_config,
+            set_app_config_value,
+            get_keybindings,
+            set_keybindings,
+            get_capabilities,
+            save_browse_session,
+            restore_last_session,
             start_rom_scan
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,190 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// Mirrors ParsedName in names.py: the title and tags of a file name, which the scripts
+// add to every file they list ("Tetris (World) (Rev 1).zip" -> title "Tetris",
+// regions ["World"], revision "1")
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct NameTags {
+    pub title: Option<String>,
+    pub regions: Vec<String>,
+    // As tagged, e.g. "En", "Pt-BR"
+    pub languages: Vec<String>,
+    // The tagged languages, or the one the regions imply when none are tagged
+    pub spoken_languages: Vec<String>,
+    pub revision: Option<String>,
+    pub disc: Option<u32>,
+    pub version: Option<String>,
+    // Beta, Proto, Demo... None for a release
+    pub status: Option<String>,
+    pub prerelease: bool,
+    pub other_tags: Vec<String>,
+    // Flagged [!], a verified good dump
+    pub verified: bool,
+}
+
+// Everything but the languages, which language variants of a game have in common
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct VariantKey {
+    title: String,
+    regions: Vec<String>,
+    revision: Option<String>,
+    disc: Option<u32>,
+    version: Option<String>,
+    status: Option<String>,
+    other_tags: Vec<String>,
+}
+
+impl NameTags {
+    // The tags of a listing entry as the scripts print it; entries without them have none
+    pub fn of_entry(entry: &serde_json::Value) -> Self {
+        serde_json::from_value(entry.clone()).unwrap_or_default()
+    }
+
+    // Mirrors language_variant_key in names.py. Names without tags only match themselves
+    pub fn language_variant_key(&self, name: &str) -> VariantKey {
+        VariantKey {
+            title: self.title.as_deref().unwrap_or(name).to_lowercase(),
+            regions: self.regions.clone(),
+            revision: self.revision.clone(),
+            disc: self.disc,
+            version: self.version.clone(),
+            status: self.status.clone(),
+            other_tags: self.other_tags.clone(),
+        }
+    }
+}
+
+fn contains_ignoring_case(values: &[String], wanted: &str) -> bool {
+    values.iter().any(|value| value.eq_ignore_ascii_case(wanted))
+}
+
+// Mirrors NameFilter in names.py: which games a listing keeps. Every field set must match
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct NameFilter {
+    pub region: Option<String>,
+    pub language: Option<String>,
+    pub disc: Option<u32>,
+    pub hide_prerelease: bool,
+}
+
+impl NameFilter {
+    pub fn matches(&self, tags: &NameTags) -> bool {
+        if self.region.as_deref().is_some_and(|region| !contains_ignoring_case(&tags.regions, region)) {
+            return false;
+        }
+        if self.language.as_deref().is_some_and(|language| !contains_ignoring_case(&tags.spoken_languages, language)) {
+            return false;
+        }
+        if self.disc.is_some_and(|disc| tags.disc != Some(disc)) {
+            return false;
+        }
+        !(self.hide_prerelease && tags.prerelease)
+    }
+}
+
+// Mirrors SORT_KEYS in names.py: what a listing is sorted by, each in its natural direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    // A to Z
+    Name,
+    // Largest first
+    Size,
+    // Newest first
+    Modified,
+    // matching.region_priority first
+    Region,
+    // Best search match first
+    Relevance,
+}
+
+// Mirrors region_rank in names.py: the first region in region_priority a file has, then the region
+// names; untagged files last
+pub fn region_rank(regions: &[String], region_priority: &[String]) -> (u8, usize, String) {
+    if regions.is_empty() {
+        return (2, region_priority.len(), String::new());
+    }
+    match regions.iter().filter_map(|region| region_priority.iter().position(|p| p == region)).min() {
+        Some(rank) => (0, rank, String::new()),
+        None => (1, region_priority.len(), regions.join(", ").to_lowercase()),
+    }
+}
+
+// Share of a query word's letters that may differ from the name word it matches
+const FUZZY_MIN_SIMILARITY: f64 = 0.75;
+
+// Single-letter insertions, deletions, substitutions and swaps of neighbours from a to b
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows: Vec<Vec<usize>> = vec![(0..=b.len()).collect()];
+    for i in 1..=a.len() {
+        let mut row = vec![i];
+        for j in 1..=b.len() {
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1)
+                .min(rows[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]));
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            row.push(distance);
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+fn fuzzy_words(text: &str) -> Vec<String> {
+    text.to_ascii_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn strip_extension(name: &str) -> &str {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if (1..=4).contains(&extension.len())
+            && extension.chars().all(|c| c.is_ascii_alphanumeric()) => stem,
+        _ => name,
+    }
+}
+
+// Mirrors fuzzy_score in names.py: how well a name matches a query typed with typos, from 0.0
+// (no match) to 1.0. Every query word must start (1.0), be inside (0.9) or, with 3+ letters,
+// be close to (its similarity) a word of the name; the score is the average over the words
+pub fn fuzzy_score(query: &str, name: &str) -> f64 {
+    let query_words = fuzzy_words(query);
+    let name_words = fuzzy_words(strip_extension(name));
+    if query_words.is_empty() {
+        return 0.0;
+    }
+
+    let mut total = 0.0;
+    for query_word in &query_words {
+        let query_chars: Vec<char> = query_word.chars().collect();
+        let mut best: f64 = 0.0;
+        for name_word in &name_words {
+            if name_word.starts_with(query_word.as_str()) {
+                best = 1.0;
+                break;
+            }
+            if name_word.contains(query_word.as_str()) {
+                best = best.max(0.9);
+            } else if query_chars.len() >= 3 {
+                let name_chars: Vec<char> = name_word.chars().collect();
+                let longest = query_chars.len().max(name_chars.len()) as f64;
+                let similarity = 1.0 - edit_distance(&query_chars, &name_chars) as f64 / longest;
+                if similarity >= FUZZY_MIN_SIMILARITY {
+                    best = best.max(similarity);
+                }
+            }
+        }
+        if best == 0.0 {
+            return 0.0;
+        }
+        total += best;
+    }
+    (total / query_words.len() as f64 * 1000.0).round() / 1000.0
+}
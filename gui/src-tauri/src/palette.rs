@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{io_error, RomBrowserError};
+
+// Covers are scaled down to this before counting colors; the palette of a thumbnail is the same
+const SAMPLE_SIZE: u32 = 64;
+const MAX_COLORS: usize = 5;
+// Colors closer than this (RGB distance) to one already picked are shades of it and skipped
+const MIN_DISTANCE: u32 = 48;
+// Mostly transparent pixels (PNG covers' margins) do not count
+const MIN_ALPHA: u8 = 128;
+
+// The dominant colors of a cover, for theming a game's detail page. Kept in games.db
+// with the cover file it was computed from, and computed again when that file changes
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CoverPalette {
+    // Most common color, for backgrounds
+    pub dominant: String,
+    // Most saturated of the colors, for highlights
+    pub accent: String,
+    // Black or white, whichever reads better on the dominant color
+    pub text: String,
+    // Up to MAX_COLORS as #rrggbb, most common first
+    pub colors: Vec<String>,
+    cover: String,
+    modified: u64,
+}
+
+impl CoverPalette {
+    // Whether it was computed from this cover file as it is now
+    pub fn is_current(&self, cover: &Path) -> bool {
+        self.cover == cover.to_string_lossy() && modified_secs(cover).ok() == Some(self.modified)
+    }
+}
+
+fn modified_secs(path: &Path) -> Result<u64, RomBrowserError> {
+    let modified = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(io_error(format!("Failed to read {}", path.display())))?;
+    Ok(modified.duration_since(UNIX_EPOCH).map(|age| age.as_secs()).unwrap_or(0))
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    let squared: u32 = a.iter().zip(b).map(|(&a, b)| (a as i32 - b as i32).pow(2) as u32).sum();
+    (squared as f64).sqrt() as u32
+}
+
+fn saturation([r, g, b]: [u8; 3]) -> f64 {
+    let (max, min) = (r.max(g).max(b) as f64, r.min(g).min(b) as f64);
+    if max == 0.0 { 0.0 } else { (max - min) / max }
+}
+
+// Relative luminance as WCAG defines it, 0 for black to 1 for white
+fn luminance(color: [u8; 3]) -> f64 {
+    let linear = |channel: u8| {
+        let c = channel as f64 / 255.0;
+        if c <= 0.03928 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    0.2126 * linear(color[0]) + 0.7152 * linear(color[1]) + 0.0722 * linear(color[2])
+}
+
+// Work out the palette of a cover file. Pixels are counted in buckets of similar colors
+// (4 bits a channel) and each bucket stands for the average of its pixels
+pub fn extract(cover: &Path) -> Result<CoverPalette, RomBrowserError> {
+    let modified = modified_secs(cover)?;
+    let image = image::open(cover)
+        .map_err(|e| RomBrowserError::Parse(format!("Cannot read the cover {}: {}", cover.display(), e)))?
+        .thumbnail(SAMPLE_SIZE, SAMPLE_SIZE)
+        .to_rgba8();
+
+    let mut buckets: HashMap<[u8; 3], (u64, [u64; 3])> = HashMap::new();
+    for pixel in image.pixels().filter(|pixel| pixel[3] >= MIN_ALPHA) {
+        let (count, sums) = buckets.entry([pixel[0] >> 4, pixel[1] >> 4, pixel[2] >> 4]).or_default();
+        *count += 1;
+        for channel in 0..3 {
+            sums[channel] += pixel[channel] as u64;
+        }
+    }
+    let mut buckets: Vec<_> = buckets.into_values().collect();
+    buckets.sort_by_key(|&(count, _)| std::cmp::Reverse(count));
+
+    let mut colors: Vec<[u8; 3]> = Vec::new();
+    for (count, sums) in buckets {
+        let color = sums.map(|sum| (sum / count) as u8);
+        if colors.iter().all(|&picked| distance(picked, color) >= MIN_DISTANCE) {
+            colors.push(color);
+        }
+        if colors.len() == MAX_COLORS {
+            break;
+        }
+    }
+    let dominant = *colors
+        .first()
+        .ok_or_else(|| RomBrowserError::Parse(format!("The cover {} has no visible pixels", cover.display())))?;
+    let accent = colors
+        .iter()
+        .copied()
+        .max_by(|a, b| saturation(*a).total_cmp(&saturation(*b)))
+        .unwrap_or(dominant);
+    // Above this luminance black text has more contrast than white
+    let text = if luminance(dominant) > 0.179 { "#000000" } else { "#ffffff" };
+
+    Ok(CoverPalette {
+        dominant: hex(dominant),
+        accent: hex(accent),
+        text: text.to_string(),
+        colors: colors.into_iter().map(hex).collect(),
+        cover: cover.to_string_lossy().into_owned(),
+        modified,
+    })
+}
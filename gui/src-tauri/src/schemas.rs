@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
+
+use crate::{GameGroup, GameInfo, GameMetadata, GamePage, JobProgress, MetadataCandidate, PlatformInfo};
+
+// The types below are written by the scripts and only described here, never built
+
+// Mirrors write_export in download_queue.py: a portable queue file
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+struct QueueExport {
+    // Always "rom-browser-queue"
+    format: String,
+    version: u32,
+    // ISO 8601
+    exported: String,
+    archive_url: String,
+    entries: Vec<QueueEntry>,
+}
+
+// One download of an exported queue, pinned to its URL and the hashes the DATs expect
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+struct QueueEntry {
+    url: String,
+    filename: String,
+    platform: Option<String>,
+    dataset: Option<String>,
+    size: Option<u64>,
+    // The ETag or Last-Modified the server gave when it was exported
+    validator: Option<String>,
+    // The queued title the file was matched for, if it was queued by title
+    title: Option<String>,
+    // Compatibility warnings
+    #[schemars(default)]
+    notes: Vec<String>,
+    #[schemars(default)]
+    roms: Vec<ExpectedRom>,
+    // How the file was matched, as get_match_explanation shows it
+    explanation: Option<serde_json::Value>,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+struct ExpectedRom {
+    name: String,
+    size: Option<u64>,
+    crc32: Option<String>,
+    md5: Option<String>,
+    sha1: Option<String>,
+}
+
+// A schema per type, by name
+pub fn json_schemas() -> BTreeMap<&'static str, RootSchema> {
+    BTreeMap::from([
+        ("GameInfo", schema_for!(GameInfo)),
+        ("GamePage", schema_for!(GamePage)),
+        ("GameGroup", schema_for!(GameGroup)),
+        ("GameMetadata", schema_for!(GameMetadata)),
+        ("MetadataCandidate", schema_for!(MetadataCandidate)),
+        ("PlatformInfo", schema_for!(PlatformInfo)),
+        ("JobProgress", schema_for!(JobProgress)),
+        ("QueueExport", schema_for!(QueueExport)),
+    ])
+}
+
+// Writes each schema to <dir>/<Name>.schema.json
+pub fn write_schemas(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    for (name, schema) in json_schemas() {
+        let json = serde_json::to_string_pretty(&schema).map_err(std::io::Error::other)?;
+        fs::write(dir.join(format!("{}.schema.json", name)), json + "\n")?;
+    }
+    Ok(())
+}
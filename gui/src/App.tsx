@@ -1,29 +1,137 @@
-import React, { useState } from 'react'
+import React, { useState, useEffect } from 'react'
+import { invoke } from '@tauri-apps/api/tauri'
+import { listen } from '@tauri-apps/api/event'
 import { Navigation, Page } from './components/Navigation'
 import { MyLibrary } from './components/MyLibrary'
 import { MyrientBrowser } from './components/MyrientBrowser'
 import { Settings } from './components/Settings'
+import { Keybindings, SHORTCUT_EVENT, actionForEvent } from './keybindings'
+import { AppConfig, APP_CONFIG_CHANGED_EVENT, DEFAULT_RATING_BEHAVIOR, applyTheme } from './appConfig'
+import { restoreLastSession, updateSession } from './session'
+import { describeProgress, useJobProgress, useRecoveredJobs } from './progress'
 import './App.css'
 
+// Mirrors get_capabilities; the backend enforces these, the UI only hides what it would refuse
+interface CapabilityInfo {
+  profile: string
+  capabilities: string[]
+}
+
 function App() {
   const [currentPage, setCurrentPage] = useState<Page>('library')
+  const [keybindings, setKeybindings] = useState<Keybindings>({})
+  const [capabilities, setCapabilities] = useState<CapabilityInfo | null>(null)
+  const [appConfig, setAppConfig] = useState<AppConfig | null>(null)
+  const [sessionChecked, setSessionChecked] = useState(false)
+  const jobs = useJobProgress()
+  const [recoveredJobs, dismissRecoveredJobs] = useRecoveredJobs()
+
+  useEffect(() => {
+    restoreLastSession()
+      .then(session => { if (session) setCurrentPage(session.page) })
+      .catch(error => console.error('Failed to restore the last session:', error))
+      .finally(() => setSessionChecked(true))
+    invoke<Keybindings>('get_keybindings')
+      .then(setKeybindings)
+      .catch(error => console.error('Failed to load keybindings:', error))
+    invoke<CapabilityInfo>('get_capabilities')
+      .then(setCapabilities)
+      .catch(error => console.error('Failed to load capabilities:', error))
+    invoke<AppConfig>('get_app_config')
+      .then(setAppConfig)
+      .catch(error => console.error('Failed to load app config:', error))
+
+    const unlisten = listen<AppConfig>(APP_CONFIG_CHANGED_EVENT, event => setAppConfig(event.payload))
+    return () => { unlisten.then(stop => stop()) }
+  }, [])
+
+  useEffect(() => {
+    if (appConfig) applyTheme(appConfig)
+  }, [appConfig])
+
+  // Saving waits for the restore, so the start page does not overwrite the session being restored
+  useEffect(() => {
+    if (sessionChecked) updateSession({ page: currentPage })
+  }, [currentPage, sessionChecked])
+
+  const canEditSettings = capabilities?.capabilities.includes('edit_settings') ?? true
+  const hiddenPages: Page[] = canEditSettings ? [] : ['settings']
+  const ratingBehavior = appConfig?.behavior ?? DEFAULT_RATING_BEHAVIOR
+
+  useEffect(() => {
+    const handleKeyDown = (event: KeyboardEvent) => {
+      const action = actionForEvent(keybindings, event)
+      if (!action) return
+
+      const search = document.querySelector<HTMLInputElement>('.app-content .search-input')
+      switch (action) {
+        case 'go_library':
+          setCurrentPage('library')
+          break
+        case 'go_browser':
+          setCurrentPage('browser')
+          break
+        case 'go_settings':
+          if (canEditSettings) setCurrentPage('settings')
+          break
+        case 'focus_search':
+          search?.focus()
+          break
+        case 'clear_search':
+          search?.blur()
+          window.dispatchEvent(new CustomEvent(SHORTCUT_EVENT, { detail: action }))
+          break
+        default:
+          window.dispatchEvent(new CustomEvent(SHORTCUT_EVENT, { detail: action }))
+      }
+      event.preventDefault()
+    }
+
+    window.addEventListener('keydown', handleKeyDown)
+    return () => window.removeEventListener('keydown', handleKeyDown)
+  }, [keybindings, canEditSettings])
 
   const renderPage = () => {
     switch (currentPage) {
       case 'library':
-        return <MyLibrary />
+        return <MyLibrary ratingBehavior={ratingBehavior} />
       case 'browser':
         return <MyrientBrowser />
       case 'settings':
-        return <Settings />
+        if (!canEditSettings) return <MyLibrary ratingBehavior={ratingBehavior} />
+        return <Settings keybindings={keybindings} onKeybindingsChange={setKeybindings} appConfig={appConfig} />
       default:
-        return <MyLibrary />
+        return <MyLibrary ratingBehavior={ratingBehavior} />
     }
   }
 
   return (
     <div className="app">
-      <Navigation currentPage={currentPage} onPageChange={setCurrentPage} />
+      <Navigation currentPage={currentPage} onPageChange={setCurrentPage} hiddenPages={hiddenPages} />
+      {recoveredJobs.length > 0 && (
+        <div className="job-progress-list recovered-jobs">
+          <div className="job-progress">
+            <strong>Recovered {recoveredJobs.length} job{recoveredJobs.length === 1 ? '' : 's'} interrupted last time</strong>
+            <button className="btn btn-sm" onClick={dismissRecoveredJobs}>Dismiss</button>
+          </div>
+          {recoveredJobs.map(job => (
+            <div key={job.id} className={`job-progress recovered-${job.action}`} title={job.removed_files.join('\n') || undefined}>
+              <span>{job.kind} {job.label}: {job.detail}</span>
+              <span>{job.action}</span>
+            </div>
+          ))}
+        </div>
+      )}
+      {jobs.length > 0 && (
+        <div className="job-progress-list">
+          {jobs.map(job => (
+            <div key={`${job.job}:${job.label}`} className="job-progress" title={job.message ?? undefined}>
+              <span>{describeProgress(job)}</span>
+              <progress value={job.fraction ?? undefined} max={1} />
+            </div>
+          ))}
+        </div>
+      )}
       <div className="app-content">
         {renderPage()}
       </div>
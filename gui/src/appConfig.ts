@@ -0,0 +1,49 @@
+// Appearance and behavior settings from the backend's app_config.json, the
+// same file the command-line tools read. set_app_config_value announces every
+// change with APP_CONFIG_CHANGED_EVENT so all windows pick it up.
+
+export const APP_CONFIG_CHANGED_EVENT = 'app-config-changed'
+
+export interface RatingBehavior {
+  auto_refresh_ratings: boolean
+  confirm_rating_changes: boolean
+  rating_precision: number
+  default_rating: number
+  rating_range: { min: number, max: number }
+}
+
+export interface AppConfig {
+  appearance: {
+    theme: string
+    colors: Record<string, string>
+    game_list: Record<string, string | number | boolean>
+  }
+  behavior: RatingBehavior
+}
+
+export const DEFAULT_RATING_BEHAVIOR: RatingBehavior = {
+  auto_refresh_ratings: true,
+  confirm_rating_changes: false,
+  rating_precision: 1,
+  default_rating: 7.0,
+  rating_range: { min: 0.0, max: 10.0 },
+}
+
+// Theme colors the stylesheets read as CSS variables
+const THEME_VARIABLES: Record<string, string> = {
+  accent: '--accent',
+  accent_hover: '--accent-hover',
+}
+
+export const applyTheme = (config: AppConfig) => {
+  const root = document.documentElement
+  Object.entries(THEME_VARIABLES).forEach(([color, variable]) => {
+    const value = config.appearance.colors[color]
+    if (value) root.style.setProperty(variable, value)
+    else root.style.removeProperty(variable)
+  })
+  root.dataset.theme = config.appearance.theme
+}
+
+export const formatRating = (rating: number, behavior: RatingBehavior) =>
+  `${rating.toFixed(behavior.rating_precision)}/${behavior.rating_range.max}`
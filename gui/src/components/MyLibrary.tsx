@@ -1,6 +1,9 @@
 import React, { useState, useEffect } from 'react'
 import { invoke } from '@tauri-apps/api/tauri'
-import { Search, Filter, Grid, List, Download, Heart, Star } from 'lucide-react'
+import { Search, Filter, Grid, List, Download, Heart, Star, RefreshCw } from 'lucide-react'
+import { SHORTCUT_EVENT } from '../keybindings'
+import { RatingBehavior, formatRating } from '../appConfig'
+import { describeError } from '../errors'
 
 interface Game {
   name: string
@@ -14,24 +17,51 @@ interface Game {
   release_date?: string
   is_favorite?: boolean
   is_downloaded?: boolean
+  match_confidence?: number
+  display_name?: string
+}
+
+// Metadata matches scoring below this are flagged for review
+const LOW_CONFIDENCE_THRESHOLD = 0.6
+
+interface MetadataCandidate {
+  source: string
+  id: string | number
+  name: string
+  release_date?: string
+  cover_url?: string
+  likely_match: boolean
 }
 
 interface MyLibraryProps {
-  // Props for library-specific functionality
+  ratingBehavior: RatingBehavior
 }
 
-export const MyLibrary: React.FC<MyLibraryProps> = () => {
+export const MyLibrary: React.FC<MyLibraryProps> = ({ ratingBehavior }) => {
   const [games, setGames] = useState<Game[]>([])
   const [loading, setLoading] = useState(false)
   const [viewMode, setViewMode] = useState<'grid' | 'list'>('grid')
   const [searchQuery, setSearchQuery] = useState('')
   const [filterPlatform, setFilterPlatform] = useState<string>('all')
   const [showFavoritesOnly, setShowFavoritesOnly] = useState(false)
+  const [matchingGame, setMatchingGame] = useState<string | null>(null)
+  const [candidates, setCandidates] = useState<MetadataCandidate[]>([])
 
   useEffect(() => {
     loadLibraryGames()
   }, [])
 
+  useEffect(() => {
+    const handleShortcut = (event: Event) => {
+      if ((event as CustomEvent<string>).detail === 'clear_search') {
+        setSearchQuery('')
+      }
+    }
+
+    window.addEventListener(SHORTCUT_EVENT, handleShortcut)
+    return () => window.removeEventListener(SHORTCUT_EVENT, handleShortcut)
+  }, [])
+
   const loadLibraryGames = async () => {
     try {
       setLoading(true)
@@ -53,8 +83,13 @@ export const MyLibrary: React.FC<MyLibraryProps> = () => {
   })
 
   const toggleFavorite = async (game: Game) => {
-    // This will integrate with your favorites system
-    console.log('Toggle favorite:', game.name)
+    try {
+      await invoke('set_favorite', { gameName: game.name, favorite: !game.is_favorite })
+      setGames(prev => prev.map(g => g.name === game.name ? { ...g, is_favorite: !game.is_favorite } : g))
+    } catch (error) {
+      console.error('Failed to change favorite:', error)
+      alert(`Failed to change favorite: ${describeError(error)}`)
+    }
   }
 
   const downloadGame = async (game: Game) => {
@@ -62,6 +97,36 @@ export const MyLibrary: React.FC<MyLibraryProps> = () => {
     console.log('Download game:', game.name)
   }
 
+  const showCandidates = async (game: Game) => {
+    if (matchingGame === game.name) {
+      setMatchingGame(null)
+      return
+    }
+    try {
+      setMatchingGame(game.name)
+      setCandidates([])
+      const result = await invoke<MetadataCandidate[]>('get_metadata_candidates', { gameName: game.name })
+      setCandidates(result)
+    } catch (error) {
+      console.error('Failed to load metadata candidates:', error)
+    }
+  }
+
+  const applyCandidate = async (game: Game, candidate: MetadataCandidate) => {
+    try {
+      await invoke('apply_metadata_candidate', {
+        gameName: game.name,
+        source: candidate.source,
+        candidateId: String(candidate.id)
+      })
+      setMatchingGame(null)
+      loadLibraryGames()
+    } catch (error) {
+      console.error('Failed to apply metadata candidate:', error)
+      alert(`Failed to apply metadata: ${describeError(error)}`)
+    }
+  }
+
   // Get unique platforms for filter
   const platforms = Array.from(new Set(games.map(game => game.platform)))
 
@@ -151,14 +216,23 @@ export const MyLibrary: React.FC<MyLibraryProps> = () => {
               )}
               
               <div className="game-info">
-                <h3 className="game-title">{game.name}</h3>
+                <h3 className="game-title">{game.display_name || game.name}</h3>
+                {game.match_confidence != null && game.match_confidence < LOW_CONFIDENCE_THRESHOLD && (
+                  <span
+                    className="low-confidence-badge"
+                    title={`Metadata match confidence ${Math.round(game.match_confidence * 100)}%`}
+                    onClick={() => showCandidates(game)}
+                  >
+                    Review match
+                  </span>
+                )}
                 <p className="game-platform">{game.platform}</p>
                 {game.size && <p className="game-size">{game.size}</p>}
                 
                 {game.rating && (
                   <div className="game-rating">
                     <Star size={14} />
-                    <span>{game.rating.toFixed(1)}/10</span>
+                    <span>{formatRating(game.rating, ratingBehavior)}</span>
                   </div>
                 )}
 
@@ -179,6 +253,14 @@ export const MyLibrary: React.FC<MyLibraryProps> = () => {
                   <Heart size={16} />
                 </button>
                 
+                <button
+                  className="action-btn"
+                  title="Fix metadata match"
+                  onClick={() => showCandidates(game)}
+                >
+                  <RefreshCw size={16} />
+                </button>
+                
                 {!game.is_downloaded && (
                   <button
                     className="action-btn download"
@@ -188,6 +270,23 @@ export const MyLibrary: React.FC<MyLibraryProps> = () => {
                   </button>
                 )}
               </div>
+
+              {matchingGame === game.name && (
+                <div className="metadata-candidates">
+                  {candidates.length === 0 && <p>Searching for matches...</p>}
+                  {candidates.map(candidate => (
+                    <button
+                      key={`${candidate.source}-${candidate.id}`}
+                      className={`candidate-btn ${candidate.likely_match ? 'likely' : ''}`}
+                      onClick={() => applyCandidate(game, candidate)}
+                    >
+                      {candidate.name}
+                      {candidate.release_date && ` (${candidate.release_date.substring(0, 4)})`}
+                      <span className="candidate-source">{candidate.source}</span>
+                    </button>
+                  ))}
+                </div>
+              )}
             </div>
           ))}
         </div>
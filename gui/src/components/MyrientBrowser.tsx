@@ -1,6 +1,10 @@
-import React, { useState, useEffect } from 'react'
+import React, { useState, useEffect, useMemo, useRef } from 'react'
 import { invoke } from '@tauri-apps/api/tauri'
-import { Search, Download, Filter, RefreshCw, Globe } from 'lucide-react'
+import { Search, Download, Filter, RefreshCw, Globe, Layers, ChevronDown, ChevronRight, ArrowUpDown } from 'lucide-react'
+import { SHORTCUT_EVENT } from '../keybindings'
+import { BrowseSession, currentSession, updateSession } from '../session'
+import { describeError, isCommandError } from '../errors'
+import { cancelSlot, invokeLatest, isCancelled } from '../requests'
 
 interface Platform {
   id: string
@@ -8,11 +12,84 @@ interface Platform {
   dataset: string
 }
 
+// The tags come from names.py (see NameTags in names.rs)
 interface Game {
   name: string
   platform: string
   size?: string
   url?: string
+  // Already in the library or the downloads folder
+  is_downloaded?: boolean
+  regions?: string[]
+  spoken_languages?: string[]
+  revision?: string | null
+  disc?: number | null
+  status?: string | null
+  prerelease?: boolean
+  // Flagged [!], a verified good dump
+  verified?: boolean
+  // Files that differ only by language tags, when grouped (the first is this one)
+  variants?: Game[]
+}
+
+// Mirrors GameGroup in main.rs: every region, revision and disc of a title
+interface GameGroup {
+  title: string
+  platform: string
+  regions: string[]
+  spoken_languages: string[]
+  discs: number[]
+  files: Game[]
+}
+
+const ALL = 'all'
+
+// Mirrors SortKey in names.rs; '' keeps the listing's own order
+type SortKey = '' | 'name' | 'size' | 'modified' | 'region' | 'relevance'
+
+const SORT_OPTIONS: { value: SortKey, label: string }[] = [
+  { value: '', label: 'Listing order' },
+  { value: 'name', label: 'Name' },
+  { value: 'size', label: 'Size (largest first)' },
+  { value: 'modified', label: 'Date (newest first)' },
+  { value: 'region', label: 'Region priority' },
+  { value: 'relevance', label: 'Best match' },
+]
+
+const gameTags = (game: Game) => [
+  ...(game.regions ?? []),
+  ...(game.spoken_languages ?? []),
+  game.revision && `Rev ${game.revision}`,
+  game.disc != null && `Disc ${game.disc}`,
+  game.status,
+  game.verified && 'Verified',
+].filter((tag): tag is string => Boolean(tag))
+
+const uniqueSorted = (values: string[]) => Array.from(new Set(values)).sort()
+
+// Mirrors rom_browser.py search --json: one entry per place a file can be downloaded from
+interface SourceResult {
+  name: string
+  source: string
+  host: string
+  url: string
+  size_text?: string
+  estimated_bps?: number
+  estimated_seconds?: number
+  title?: string
+  spoken_languages?: string[]
+  variants?: SourceResult[]
+}
+
+const variantsSummary = (variants: { name: string }[], languages: string[] = []) =>
+  `${new Set(variants.map(variant => variant.name)).size} language variants (${languages.join(', ')})`
+
+const formatEstimate = (result: SourceResult) => {
+  if (!result.estimated_bps) return 'speed unknown'
+  const speed = `${(result.estimated_bps / 1024 / 1024).toFixed(1)} MB/s`
+  if (!result.estimated_seconds) return speed
+  const minutes = Math.floor(result.estimated_seconds / 60)
+  return `${speed}, about ${minutes > 0 ? `${minutes}m ` : ''}${result.estimated_seconds % 60}s`
 }
 
 interface MyrientBrowserProps {
@@ -26,34 +103,92 @@ export const MyrientBrowser: React.FC<MyrientBrowserProps> = () => {
   const [loading, setLoading] = useState(false)
   const [searchQuery, setSearchQuery] = useState('')
   const [downloadingGames, setDownloadingGames] = useState<Set<string>>(new Set())
+  const [sourceResults, setSourceResults] = useState<SourceResult[] | null>(null)
+  const [searchingSources, setSearchingSources] = useState(false)
+  const [scrollTarget, setScrollTarget] = useState<string | null>(null)
+  const [regionFilter, setRegionFilter] = useState(ALL)
+  const [languageFilter, setLanguageFilter] = useState(ALL)
+  const [hidePrerelease, setHidePrerelease] = useState(false)
+  const [groupLanguages, setGroupLanguages] = useState(true)
+  const [groupByTitle, setGroupByTitle] = useState(false)
+  const [sortKey, setSortKey] = useState<SortKey>('')
+  const [reverseSort, setReverseSort] = useState(false)
+  const [hideOwned, setHideOwned] = useState(false)
+  const [groups, setGroups] = useState<GameGroup[] | null>(null)
+  // The platform's games matching the search, best first; typos are allowed
+  const [searchMatches, setSearchMatches] = useState<Game[] | null>(null)
+  // Grouped games showing their variants, by name
+  const [expanded, setExpanded] = useState<Set<string>>(new Set())
+  // Rate limits and dropped connections are worth retrying; a missing folder is not
+  const [browseError, setBrowseError] = useState<{ message: string, retryable: boolean } | null>(null)
+  // Where the browser was last time (or before switching pages), reopened once the platforms load
+  const restoring = useRef<BrowseSession | null>(currentSession())
 
   useEffect(() => {
     loadPlatforms()
+    // Leaving the page drops a listing or search still running
+    return () => {
+      cancelSlot('browse')
+      cancelSlot('search')
+      cancelSlot('platform-search')
+    }
   }, [])
 
   const loadPlatforms = async () => {
+    let result: Platform[] = []
     try {
       setLoading(true)
-      const result = await invoke<Platform[]>('get_platforms')
+      result = await invoke<Platform[]>('get_platforms')
       setPlatforms(result)
     } catch (error) {
       console.error('Failed to load platforms:', error)
     } finally {
       setLoading(false)
     }
+
+    const session = restoring.current
+    restoring.current = null
+    const platform = result.find(p => p.id === session?.platform_id)
+    if (session && platform) {
+      setSearchQuery(session.search)
+      await handlePlatformSelect(platform, session.scroll_anchor)
+    }
   }
 
-  const handlePlatformSelect = async (platform: Platform) => {
+  const handlePlatformSelect = async (platform: Platform, scrollAnchor: string | null = null) => {
     try {
       setLoading(true)
       setSelectedPlatform(platform)
-      const result = await invoke<Game[]>('browse_platform', { platformId: platform.id })
-      setGames(result)
+      setSourceResults(null)
+      setRegionFilter(ALL)
+      setLanguageFilter(ALL)
+      setBrowseError(null)
+      updateSession({ platform_id: platform.id, scroll_anchor: scrollAnchor })
+      if (groupByTitle) {
+        const result = await invokeLatest<GameGroup[]>('browse', 'browse_platform_grouped', { platformId: platform.id })
+        setGroups(result)
+        setGames(result.flatMap(group => group.files))
+      } else {
+        const result = await invokeLatest<Game[]>('browse', 'browse_platform', {
+          platformId: platform.id,
+          view: { group_languages: groupLanguages, sort: sortKey || null, reverse: reverseSort, hide_owned: hideOwned },
+        })
+        setGroups(null)
+        setGames(result)
+      }
+      setScrollTarget(scrollAnchor)
     } catch (error) {
+      // Another platform was picked meanwhile, and its listing is loading now
+      if (isCancelled(error)) return
       console.error('Failed to browse platform:', error)
-    } finally {
-      setLoading(false)
+      setGames([])
+      setGroups(null)
+      setBrowseError({
+        message: describeError(error),
+        retryable: isCommandError(error) && (error.kind === 'rate_limited' || error.kind === 'network'),
+      })
     }
+    setLoading(false)
   }
 
   const handleGameDownload = async (game: Game) => {
@@ -85,12 +220,120 @@ export const MyrientBrowser: React.FC<MyrientBrowserProps> = () => {
     }
   }
 
-  const filteredGames = games.filter(game =>
-    game.name.toLowerCase().includes(searchQuery.toLowerCase())
-  )
+  const searchAllSources = async () => {
+    if (!searchQuery.trim()) return
+    try {
+      setSearchingSources(true)
+      const result = await invokeLatest<SourceResult[]>('search', 'search_all_sources', {
+        query: searchQuery,
+        groupLanguages,
+      })
+      setSourceResults(result)
+    } catch (error) {
+      if (isCancelled(error)) return
+      console.error('Failed to search sources:', error)
+    }
+    setSearchingSources(false)
+  }
+
+  const toggleExpanded = (name: string) => {
+    setExpanded(prev => {
+      const next = new Set(prev)
+      if (next.has(name)) next.delete(name)
+      else next.add(name)
+      return next
+    })
+  }
+
+  // The listing is cached, so browsing again with the other grouping is quick
+  useEffect(() => {
+    if (selectedPlatform && !loading) handlePlatformSelect(selectedPlatform)
+  }, [groupLanguages, groupByTitle, sortKey, reverseSort, hideOwned])
+
+  // Searching a platform goes through its cached listing, once typing pauses
+  useEffect(() => {
+    if (!selectedPlatform || !searchQuery.trim() || groupByTitle) {
+      cancelSlot('platform-search')
+      setSearchMatches(null)
+      return
+    }
+    const timer = setTimeout(async () => {
+      try {
+        const result = await invokeLatest<Game[]>('platform-search', 'search_in_platform', {
+          platformId: selectedPlatform.id,
+          query: searchQuery,
+          view: { group_languages: groupLanguages, sort: sortKey || 'relevance', reverse: reverseSort, hide_owned: hideOwned },
+        })
+        setSearchMatches(result)
+      } catch (error) {
+        if (isCancelled(error)) return
+        console.error('Failed to search platform:', error)
+        setSearchMatches(null)
+      }
+    }, 250)
+    return () => clearTimeout(timer)
+  }, [searchQuery, selectedPlatform, groupLanguages, groupByTitle, sortKey, reverseSort, hideOwned, games])
+
+  const changeSearch = (query: string) => {
+    setSearchQuery(query)
+    updateSession({ search: query })
+  }
+
+  const regions = useMemo(() => uniqueSorted(games.flatMap(game => game.regions ?? [])), [games])
+  const languages = useMemo(() => uniqueSorted(games.flatMap(game => game.spoken_languages ?? [])), [games])
+
+  const matchesTags = (game: Game) =>
+    (regionFilter === ALL || (game.regions ?? []).includes(regionFilter)) &&
+    (languageFilter === ALL || (game.spoken_languages ?? []).includes(languageFilter)) &&
+    !(hidePrerelease && game.prerelease)
+
+  const matchesFilters = (game: Game) =>
+    game.name.toLowerCase().includes(searchQuery.toLowerCase()) && matchesTags(game)
+
+  const filteredGames = searchMatches ? searchMatches.filter(matchesTags) : games.filter(matchesFilters)
+  // A title stays while any of its files passes the filters, showing only those
+  const filteredGroups = groups
+    ?.map(group => ({ ...group, files: group.files.filter(matchesFilters) }))
+    .filter(group => group.files.length > 0)
+
+  useEffect(() => {
+    if (!scrollTarget || loading) return
+    document.querySelector(`[data-game-name="${CSS.escape(scrollTarget)}"]`)?.scrollIntoView({ block: 'start' })
+    setScrollTarget(null)
+  }, [scrollTarget, loading, games])
+
+  // The first card still in view is the anchor, so a restore lands on the same game at any window size
+  const handleScroll = (event: React.UIEvent<HTMLDivElement>) => {
+    if (!selectedPlatform || sourceResults) return
+    const top = event.currentTarget.getBoundingClientRect().top
+    const cards = event.currentTarget.querySelectorAll<HTMLElement>('.game-card[data-game-name]')
+    const anchor = Array.from(cards).find(card => card.getBoundingClientRect().bottom > top)
+    updateSession({ scroll_anchor: anchor?.dataset.gameName ?? null })
+  }
+
+  useEffect(() => {
+    const handleShortcut = (event: Event) => {
+      const action = (event as CustomEvent<string>).detail
+      const index = platforms.findIndex(p => p.id === selectedPlatform?.id)
+
+      if (action === 'clear_search') {
+        changeSearch('')
+        setSourceResults(null)
+      } else if (action === 'next_platform' && platforms.length > 0) {
+        handlePlatformSelect(platforms[Math.min(index + 1, platforms.length - 1)])
+      } else if (action === 'previous_platform' && platforms.length > 0) {
+        handlePlatformSelect(platforms[Math.max(index - 1, 0)])
+      } else if (action === 'download_top_result' && filteredGames.length > 0) {
+        handleGameDownload(filteredGames[0])
+      }
+    }
+
+    window.addEventListener(SHORTCUT_EVENT, handleShortcut)
+    return () => window.removeEventListener(SHORTCUT_EVENT, handleShortcut)
+  })
 
   return (
-    <div className="page-content">
+    <div className="page-content" onScroll={handleScroll}>
       <div className="page-header">
         <h2>
           <Globe size={24} />
@@ -145,12 +388,83 @@ export const MyrientBrowser: React.FC<MyrientBrowserProps> = () => {
                   type="text"
                   placeholder="Search games..."
                   value={searchQuery}
-                  onChange={(e) => setSearchQuery(e.target.value)}
+                  onChange={(e) => changeSearch(e.target.value)}
                   className="search-input"
                 />
               </div>
+              <button
+                className="btn btn-secondary"
+                onClick={searchAllSources}
+                disabled={searchingSources || !searchQuery.trim()}
+                title="Search the Internet Archive items, local folders and mirrors at once"
+              >
+                <Layers size={16} />
+                {searchingSources ? 'Searching...' : 'Search All Sources'}
+              </button>
             </div>
 
+            {selectedPlatform && games.length > 0 && (
+              <div className="filter-section">
+                <div className="filter-group">
+                  <Filter size={16} />
+                  <select value={regionFilter} onChange={(e) => setRegionFilter(e.target.value)} className="filter-select">
+                    <option value={ALL}>All Regions</option>
+                    {regions.map(region => <option key={region} value={region}>{region}</option>)}
+                  </select>
+                  <select value={languageFilter} onChange={(e) => setLanguageFilter(e.target.value)} className="filter-select">
+                    <option value={ALL}>All Languages</option>
+                    {languages.map(language => <option key={language} value={language}>{language}</option>)}
+                  </select>
+                </div>
+                <label className="checkbox-label">
+                  <input type="checkbox" checked={hidePrerelease} onChange={(e) => setHidePrerelease(e.target.checked)} />
+                  Hide betas and prototypes
+                </label>
+                <label className="checkbox-label">
+                  <input
+                    type="checkbox"
+                    checked={groupLanguages}
+                    disabled={groupByTitle}
+                    onChange={(e) => setGroupLanguages(e.target.checked)}
+                  />
+                  Group language variants
+                </label>
+                <label className="checkbox-label">
+                  <input type="checkbox" checked={groupByTitle} onChange={(e) => setGroupByTitle(e.target.checked)} />
+                  Group regions and discs
+                </label>
+                <label className="checkbox-label">
+                  <input
+                    type="checkbox"
+                    checked={hideOwned}
+                    disabled={groupByTitle}
+                    onChange={(e) => setHideOwned(e.target.checked)}
+                  />
+                  Hide owned
+                </label>
+                <div className="filter-group">
+                  <ArrowUpDown size={16} />
+                  <select
+                    value={sortKey}
+                    disabled={groupByTitle}
+                    onChange={(e) => setSortKey(e.target.value as SortKey)}
+                    className="filter-select"
+                  >
+                    {SORT_OPTIONS.map(option => <option key={option.value} value={option.value}>{option.label}</option>)}
+                  </select>
+                  <label className="checkbox-label">
+                    <input
+                      type="checkbox"
+                      checked={reverseSort}
+                      disabled={groupByTitle || !sortKey}
+                      onChange={(e) => setReverseSort(e.target.checked)}
+                    />
+                    Reverse
+                  </label>
+                </div>
+              </div>
+            )}
+
             {selectedPlatform && (
               <div className="platform-info">
                 <h3>
@@ -158,13 +472,68 @@ export const MyrientBrowser: React.FC<MyrientBrowserProps> = () => {
                   {selectedPlatform.name} Games
                 </h3>
                 <p className="platform-details">
-                  Dataset: {selectedPlatform.dataset} • {filteredGames.length} games found
+                  Dataset: {selectedPlatform.dataset} •{' '}
+                  {filteredGroups ? `${filteredGroups.length} titles, ${filteredGames.length} files` : `${filteredGames.length} games`} found
                 </p>
               </div>
             )}
           </div>
 
-          {!selectedPlatform ? (
+          {sourceResults ? (
+            <div className="source-results">
+              <div className="platform-info">
+                <h3>All Sources</h3>
+                <p className="platform-details">
+                  {sourceResults.length} downloads found •{' '}
+                  <button className="btn btn-sm" onClick={() => setSourceResults(null)}>Back to platform</button>
+                </p>
+              </div>
+              <div className="games-grid">
+                {sourceResults.map((result) => result.variants ? (
+                  <div key={result.url} className="game-card">
+                    <div className="game-title">{result.title ?? result.name}</div>
+                    <button className="variants-toggle" onClick={() => toggleExpanded(result.name)}>
+                      {expanded.has(result.name) ? <ChevronDown size={14} /> : <ChevronRight size={14} />}
+                      {variantsSummary(result.variants, result.spoken_languages)}
+                    </button>
+                    {expanded.has(result.name) && (
+                      <ul className="variant-list">
+                        {result.variants.map(variant => (
+                          <li key={variant.url}>
+                            <span>{variant.name} • {variant.host} • {variant.size_text ?? 'size unknown'}</span>
+                            <button
+                              className="btn btn-sm"
+                              onClick={() => handleGameDownload({ name: variant.name, platform: variant.source, url: variant.url })}
+                              disabled={downloadingGames.has(variant.name)}
+                              title="Download"
+                            >
+                              <Download size={14} />
+                            </button>
+                          </li>
+                        ))}
+                      </ul>
+                    )}
+                  </div>
+                ) : (
+                  <div key={result.url} className="game-card">
+                    <div className="game-title">{result.name}</div>
+                    <div className="game-platform">{result.source} • {result.host}</div>
+                    <div className="game-size">
+                      {result.size_text ?? 'size unknown'} • {formatEstimate(result)}
+                    </div>
+                    <button
+                      className={`download-btn ${downloadingGames.has(result.name) ? 'downloading' : ''}`}
+                      onClick={() => handleGameDownload({ name: result.name, platform: result.source, url: result.url })}
+                      disabled={downloadingGames.has(result.name)}
+                    >
+                      <Download size={16} />
+                      {downloadingGames.has(result.name) ? 'Downloading...' : 'Download'}
+                    </button>
+                  </div>
+                ))}
+              </div>
+            </div>
+          ) : !selectedPlatform ? (
             <div className="welcome-message">
               <Globe size={64} />
               <h3>Welcome to Myrient Browser</h3>
@@ -177,12 +546,77 @@ export const MyrientBrowser: React.FC<MyrientBrowserProps> = () => {
                 <p>Loading games...</p>
               </div>
             </div>
+          ) : filteredGroups ? (
+            <div className="games-grid">
+              {filteredGroups.map(group => (
+                <div key={group.title} className="game-card" data-game-name={group.title}>
+                  <div className="game-title">{group.title}</div>
+                  <div className="game-platform">{group.platform}</div>
+                  <div className="game-tags">
+                    {group.regions.map(region => <span key={region} className="game-tag">{region}</span>)}
+                    {group.discs.length > 1 && <span className="game-tag">{group.discs.length} discs</span>}
+                  </div>
+                  <button className="variants-toggle" onClick={() => toggleExpanded(group.title)}>
+                    {expanded.has(group.title) ? <ChevronDown size={14} /> : <ChevronRight size={14} />}
+                    {group.files.length === 1 ? '1 file' : `${group.files.length} files`}
+                  </button>
+                  {expanded.has(group.title) && (
+                    <ul className="variant-list">
+                      {group.files.map(file => (
+                        <li key={file.name}>
+                          <span>{file.name}{file.size ? ` • ${file.size}` : ''}{file.is_downloaded ? ' • owned' : ''}</span>
+                          <button
+                            className="btn btn-sm"
+                            onClick={() => handleGameDownload(file)}
+                            disabled={!file.url || downloadingGames.has(file.name)}
+                            title="Download"
+                          >
+                            <Download size={14} />
+                          </button>
+                        </li>
+                      ))}
+                    </ul>
+                  )}
+                </div>
+              ))}
+            </div>
           ) : (
             <div className="games-grid">
               {filteredGames.map((game, index) => (
-                <div key={index} className="game-card">
-                  <div className="game-title">{game.name}</div>
+                <div key={index} className="game-card" data-game-name={game.name}>
+                  <div className="game-title">{game.variants ? game.title ?? game.name : game.name}</div>
                   <div className="game-platform">{game.platform}</div>
+                  {game.variants && (
+                    <>
+                      <button className="variants-toggle" onClick={() => toggleExpanded(game.name)}>
+                        {expanded.has(game.name) ? <ChevronDown size={14} /> : <ChevronRight size={14} />}
+                        {variantsSummary(game.variants, game.spoken_languages)}
+                      </button>
+                      {expanded.has(game.name) && (
+                        <ul className="variant-list">
+                          {game.variants.map(variant => (
+                            <li key={variant.name}>
+                              <span>{variant.name}{variant.size ? ` • ${variant.size}` : ''}{variant.is_downloaded ? ' • owned' : ''}</span>
+                              <button
+                                className="btn btn-sm"
+                                onClick={() => handleGameDownload(variant)}
+                                disabled={!variant.url || downloadingGames.has(variant.name)}
+                                title="Download"
+                              >
+                                <Download size={14} />
+                              </button>
+                            </li>
+                          ))}
+                        </ul>
+                      )}
+                    </>
+                  )}
+                  {(gameTags(game).length > 0 || game.is_downloaded) && (
+                    <div className="game-tags">
+                      {game.is_downloaded && <span className="game-tag owned-tag">Owned</span>}
+                      {gameTags(game).map(tag => <span key={tag} className="game-tag">{tag}</span>)}
+                    </div>
+                  )}
                   {game.size && (
                     <div className="game-size">
                       <i className="fas fa-hdd"></i>
@@ -197,14 +631,27 @@ export const MyrientBrowser: React.FC<MyrientBrowserProps> = () => {
                     disabled={!game.url || downloadingGames.has(game.name)}
                   >
                     <Download size={16} />
-                    {downloadingGames.has(game.name) ? 'Downloading...' : 'Download'}
+                    {downloadingGames.has(game.name) ? 'Downloading...' : game.is_downloaded ? 'Download Again' : 'Download'}
                   </button>
                 </div>
               ))}
             </div>
           )}
 
-          {selectedPlatform && filteredGames.length === 0 && !loading && (
+          {!sourceResults && selectedPlatform && browseError && !loading && (
+            <div className="empty-state">
+              <h3>Could not load {selectedPlatform.name}</h3>
+              <p>{browseError.message}</p>
+              {browseError.retryable && (
+                <button className="btn btn-secondary" onClick={() => handlePlatformSelect(selectedPlatform)}>
+                  <RefreshCw size={16} />
+                  Retry
+                </button>
+              )}
+            </div>
+          )}
+
+          {!sourceResults && selectedPlatform && !browseError && filteredGames.length === 0 && !loading && (
             <div className="empty-state">
               <i className="fas fa-search fa-3x"></i>
               <h3>No games found</h3>
@@ -6,14 +6,15 @@ export type Page = 'library' | 'browser' | 'settings'
 interface NavigationProps {
   currentPage: Page
   onPageChange: (page: Page) => void
+  hiddenPages?: Page[]
 }
 
-export const Navigation: React.FC<NavigationProps> = ({ currentPage, onPageChange }) => {
+export const Navigation: React.FC<NavigationProps> = ({ currentPage, onPageChange, hiddenPages = [] }) => {
   const pages = [
     { id: 'library' as Page, label: 'My Library', icon: Library },
     { id: 'browser' as Page, label: 'Myrient Browser', icon: Globe },
     { id: 'settings' as Page, label: 'Settings', icon: Settings },
-  ]
+  ].filter(page => !hiddenPages.includes(page.id))
 
   return (
     <nav className="navigation">
@@ -1,6 +1,9 @@
 import React, { useState, useEffect } from 'react'
 import { invoke } from '@tauri-apps/api/tauri'
-import { Settings as SettingsIcon, FolderOpen, Database, Download, RefreshCw, Save } from 'lucide-react'
+import { Settings as SettingsIcon, FolderOpen, Database, Download, RefreshCw, Save, Keyboard, Trash2, Palette, Gamepad2, Gauge } from 'lucide-react'
+import { Keybindings, KEYBINDING_LABELS, comboFromEvent } from '../keybindings'
+import { AppConfig } from '../appConfig'
+import { describeError, errorMessage } from '../errors'
 
 interface SettingsData {
   rom_directories: string[]
@@ -9,27 +12,101 @@ interface SettingsData {
   auto_scan: boolean
   scan_interval: number
   max_concurrent_downloads: number
+  read_only: boolean
+  proxy_url: string
+  cache_ttls: CacheTtls
+  job_limits: JobLimits
 }
 
+// Mirrors the jobs section of the settings; 0 threads uses every core, 0 MB/s is unlimited
+interface JobLimits {
+  cpu_threads: number
+  io_concurrency: number
+  io_mb_per_second: number
+}
+
+interface CacheTtls {
+  platform_list_hours: number
+  listing_hours: number
+  provider_response_hours: number
+}
+
+interface ClearCachesResult {
+  platforms: number
+  listings: number
+  providers: number
+}
+
+// Mirrors emulators.py list
+interface EmulatorSetup {
+  platform: string
+  path: string | null
+  test_rom: string | null
+  problems: string[]
+}
+
+interface TestLaunchResult {
+  platform: string
+  ok: boolean
+  message: string
+}
+
+const CACHE_TTL_FIELDS: { key: keyof CacheTtls, label: string }[] = [
+  { key: 'platform_list_hours', label: 'Platform Lists (hours)' },
+  { key: 'listing_hours', label: 'Directory Listings (hours)' },
+  { key: 'provider_response_hours', label: 'Metadata Responses (hours)' }
+]
+
+const JOB_LIMIT_FIELDS: { key: keyof JobLimits, label: string, min: number }[] = [
+  { key: 'cpu_threads', label: 'CPU Threads (0 = all cores)', min: 0 },
+  { key: 'io_concurrency', label: 'Files Read at Once', min: 1 },
+  { key: 'io_mb_per_second', label: 'Read Speed (MB/s, 0 = unlimited)', min: 0 }
+]
+
 interface SettingsProps {
-  // Props for settings-specific functionality
+  keybindings: Keybindings
+  onKeybindingsChange: (keybindings: Keybindings) => void
+  appConfig: AppConfig | null
 }
 
-export const Settings: React.FC<SettingsProps> = () => {
+export const Settings: React.FC<SettingsProps> = ({ keybindings, onKeybindingsChange, appConfig }) => {
   const [settings, setSettings] = useState<SettingsData>({
     rom_directories: [],
     download_directory: '',
     metadata_api_key: '',
-    auto_scan: true,
+    auto_scan: false,
     scan_interval: 30,
-    max_concurrent_downloads: 3
+    max_concurrent_downloads: 3,
+    read_only: false,
+    proxy_url: '',
+    cache_ttls: {
+      platform_list_hours: 24,
+      listing_hours: 6,
+      provider_response_hours: 72
+    },
+    job_limits: {
+      cpu_threads: 0,
+      io_concurrency: 2,
+      io_mb_per_second: 0
+    }
   })
   const [loading, setLoading] = useState(false)
   const [saving, setSaving] = useState(false)
   const [scanning, setScanning] = useState(false)
+  const [clearingCaches, setClearingCaches] = useState(false)
+  const [cacheMessage, setCacheMessage] = useState<string | null>(null)
+  const [recordingAction, setRecordingAction] = useState<string | null>(null)
+  const [keybindingError, setKeybindingError] = useState<string | null>(null)
+  const [appConfigError, setAppConfigError] = useState<string | null>(null)
+  const [emulators, setEmulators] = useState<EmulatorSetup[]>([])
+  const [testingPlatform, setTestingPlatform] = useState<string | null>(null)
+  const [testResults, setTestResults] = useState<Record<string, TestLaunchResult>>({})
 
   useEffect(() => {
     loadSettings()
+    invoke<EmulatorSetup[]>('get_emulators')
+      .then(setEmulators)
+      .catch(error => console.error('Failed to load emulators:', error))
   }, [])
 
   const loadSettings = async () => {
@@ -56,6 +133,46 @@ export const Settings: React.FC<SettingsProps> = () => {
     }
   }
 
+  const recordKeybinding = async (action: string, event: React.KeyboardEvent) => {
+    const combo = comboFromEvent(event.nativeEvent)
+    if (!combo) return
+
+    // Keep the global shortcut handler from acting on the key being recorded
+    event.preventDefault()
+    event.stopPropagation()
+    setRecordingAction(null)
+
+    try {
+      const result = await invoke<Keybindings>('set_keybindings', { bindings: { ...keybindings, [action]: combo } })
+      onKeybindingsChange(result)
+      setKeybindingError(null)
+    } catch (error) {
+      setKeybindingError(errorMessage(error))
+    }
+  }
+
+  // Saved straight to app_config.json; the app-config-changed event updates every window
+  const setAppConfigValue = async (key: string, value: unknown) => {
+    try {
+      await invoke('set_app_config_value', { key, value })
+      setAppConfigError(null)
+    } catch (error) {
+      setAppConfigError(errorMessage(error))
+    }
+  }
+
+  const testLaunch = async (platform: string) => {
+    try {
+      setTestingPlatform(platform)
+      const result = await invoke<TestLaunchResult>('test_launch', { platform })
+      setTestResults(prev => ({ ...prev, [platform]: result }))
+    } catch (error) {
+      setTestResults(prev => ({ ...prev, [platform]: { platform, ok: false, message: errorMessage(error) } }))
+    } finally {
+      setTestingPlatform(null)
+    }
+  }
+
   const startScanning = async () => {
     try {
       setScanning(true)
@@ -68,6 +185,38 @@ export const Settings: React.FC<SettingsProps> = () => {
     }
   }
 
+  const clearCaches = async () => {
+    try {
+      setClearingCaches(true)
+      const result = await invoke<ClearCachesResult>('clear_caches')
+      setCacheMessage(`Deleted ${result.platforms + result.listings} listings and ${result.providers} metadata responses`)
+    } catch (error) {
+      setCacheMessage(describeError(error))
+    } finally {
+      setClearingCaches(false)
+    }
+  }
+
+  const updateCacheTtl = (key: keyof CacheTtls, value: string) => {
+    setSettings(prev => ({
+      ...prev,
+      cache_ttls: { ...prev.cache_ttls, [key]: Math.max(0, parseFloat(value) || 0) }
+    }))
+  }
+
+  // Applied at once, so a verification already running eases off while a game is played
+  const updateJobLimit = async (key: keyof JobLimits, value: string) => {
+    const field = JOB_LIMIT_FIELDS.find(field => field.key === key)
+    const limits = { ...settings.job_limits, [key]: Math.max(field?.min ?? 0, parseFloat(value) || 0) }
+    setSettings(prev => ({ ...prev, job_limits: limits }))
+    try {
+      const applied = await invoke<JobLimits>('set_job_limits', { limits })
+      setSettings(prev => ({ ...prev, job_limits: applied }))
+    } catch (error) {
+      console.error('Failed to change job limits:', error)
+    }
+  }
+
   const addRomDirectory = () => {
     setSettings(prev => ({
       ...prev,
@@ -163,16 +312,16 @@ export const Settings: React.FC<SettingsProps> = () => {
           </div>
 
           <div className="settings-group">
-            <label className="setting-label">IGDB API Key</label>
+            <label className="setting-label">RAWG API Key</label>
             <input
               type="password"
               value={settings.metadata_api_key}
               onChange={(e) => setSettings(prev => ({ ...prev, metadata_api_key: e.target.value }))}
-              placeholder="Enter your IGDB API key..."
+              placeholder="Enter your RAWG API key..."
               className="setting-input"
             />
             <p className="setting-help">
-              Get your free API key from <a href="https://api.igdb.com/" target="_blank">IGDB</a>
+              Get your free API key from <a href="https://rawg.io/apidocs" target="_blank">RAWG</a>
             </p>
           </div>
         </div>
@@ -195,6 +344,34 @@ export const Settings: React.FC<SettingsProps> = () => {
               />
               <span>Enable automatic ROM directory scanning</span>
             </label>
+            <p className="setting-help">
+              Adds new games from the ROM directories to the library every scan interval
+            </p>
+          </div>
+
+          <div className="settings-group">
+            <label className="checkbox-label">
+              <input
+                type="checkbox"
+                checked={settings.read_only}
+                onChange={(e) => setSettings(prev => ({ ...prev, read_only: e.target.checked }))}
+              />
+              <span>Read-only mode (disable downloads, deletions and database writes)</span>
+            </label>
+          </div>
+
+          <div className="settings-group">
+            <label className="setting-label">Proxy URL</label>
+            <input
+              type="text"
+              value={settings.proxy_url}
+              onChange={(e) => setSettings(prev => ({ ...prev, proxy_url: e.target.value }))}
+              placeholder="http://proxy:8080 or socks5://host:1080"
+              className="setting-input"
+            />
+            <p className="setting-help">
+              Used for browsing, downloads and metadata. Leave empty to use the HTTP_PROXY/HTTPS_PROXY environment variables.
+            </p>
           </div>
 
           <div className="settings-group">
@@ -222,6 +399,159 @@ export const Settings: React.FC<SettingsProps> = () => {
           </div>
         </div>
 
+        <div className="settings-section">
+          <div className="section-header">
+            <h3>
+              <Trash2 size={20} />
+              Caches
+            </h3>
+            <p>How long archive listings and metadata responses are kept. 0 turns a cache off</p>
+          </div>
+
+          {CACHE_TTL_FIELDS.map(({ key, label }) => (
+            <div key={key} className="settings-group">
+              <label className="setting-label">{label}</label>
+              <input
+                type="number"
+                value={settings.cache_ttls[key]}
+                onChange={(e) => updateCacheTtl(key, e.target.value)}
+                min="0"
+                className="setting-input"
+              />
+            </div>
+          ))}
+
+          <div className="settings-group">
+            <button
+              className="btn btn-danger"
+              onClick={clearCaches}
+              disabled={clearingCaches || settings.read_only}
+            >
+              <Trash2 size={16} />
+              {clearingCaches ? 'Clearing...' : 'Clear All Caches'}
+            </button>
+            {cacheMessage && <p className="setting-help">{cacheMessage}</p>}
+          </div>
+        </div>
+
+        <div className="settings-section">
+          <div className="section-header">
+            <h3>
+              <Gauge size={20} />
+              Background Jobs
+            </h3>
+            <p>What hashing audits, disc scans and sync checks may use. Changes reach running jobs within a few seconds</p>
+          </div>
+
+          {JOB_LIMIT_FIELDS.map(({ key, label, min }) => (
+            <div key={key} className="settings-group">
+              <label className="setting-label">{label}</label>
+              <input
+                type="number"
+                value={settings.job_limits[key]}
+                onChange={(e) => updateJobLimit(key, e.target.value)}
+                min={min}
+                className="setting-input"
+              />
+            </div>
+          ))}
+        </div>
+
+        <div className="settings-section">
+          <div className="section-header">
+            <h3>
+              <Gamepad2 size={20} />
+              Emulators
+            </h3>
+            <p>Each platform's emulator is set under emulators in the settings file. Test launches it with its test ROM</p>
+          </div>
+
+          {emulators.length === 0 && <p className="setting-help">No emulators are configured</p>}
+          {emulators.map(emulator => {
+            const result = testResults[emulator.platform]
+            return (
+              <div key={emulator.platform} className="settings-group">
+                <label className="setting-label">{emulator.platform}</label>
+                <p className="setting-help">{emulator.path}</p>
+                <button
+                  className="btn btn-secondary"
+                  onClick={() => testLaunch(emulator.platform)}
+                  disabled={testingPlatform !== null}
+                >
+                  <Gamepad2 size={16} />
+                  {testingPlatform === emulator.platform ? 'Launching...' : 'Test Launch'}
+                </button>
+                {emulator.problems.length > 0 && !result && (
+                  <p className="setting-help keybinding-error">{emulator.problems.join('; ')}</p>
+                )}
+                {result && (
+                  <p className={result.ok ? 'setting-help' : 'setting-help keybinding-error'}>{result.message}</p>
+                )}
+              </div>
+            )
+          })}
+        </div>
+
+        {appConfig && (
+          <div className="settings-section">
+            <div className="section-header">
+              <h3>
+                <Palette size={20} />
+                Appearance
+              </h3>
+              <p>Shared with the command-line tools through app_config.json</p>
+            </div>
+
+            <div className="settings-group">
+              <label className="setting-label">Accent Color</label>
+              <input
+                type="color"
+                value={appConfig.appearance.colors.accent}
+                onChange={(e) => setAppConfigValue('appearance.colors.accent', e.target.value)}
+                className="setting-input"
+              />
+            </div>
+
+            <div className="settings-group">
+              <label className="setting-label">Rating Decimal Places</label>
+              <input
+                type="number"
+                value={appConfig.behavior.rating_precision}
+                onChange={(e) => setAppConfigValue('behavior.rating_precision', parseInt(e.target.value) || 0)}
+                min="0"
+                max="3"
+                className="setting-input"
+              />
+            </div>
+            {appConfigError && <p className="setting-help keybinding-error">{appConfigError}</p>}
+          </div>
+        )}
+
+        <div className="settings-section">
+          <div className="section-header">
+            <h3>
+              <Keyboard size={20} />
+              Keyboard Shortcuts
+            </h3>
+            <p>Click a shortcut, then press the new key combination</p>
+          </div>
+
+          {Object.entries(keybindings).map(([action, combo]) => (
+            <div key={action} className="settings-group keybinding-row">
+              <label className="setting-label">{KEYBINDING_LABELS[action] ?? action}</label>
+              <button
+                className="btn btn-secondary keybinding-btn"
+                onClick={() => setRecordingAction(action)}
+                onKeyDown={(e) => recordingAction === action && recordKeybinding(action, e)}
+                onBlur={() => setRecordingAction(null)}
+              >
+                {recordingAction === action ? 'Press keys...' : combo}
+              </button>
+            </div>
+          ))}
+          {keybindingError && <p className="setting-help keybinding-error">{keybindingError}</p>}
+        </div>
+
         <div className="settings-actions">
           <button
             className="btn btn-primary"
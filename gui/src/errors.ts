@@ -0,0 +1,31 @@
+// Mirrors RomBrowserError in error.rs: every command rejects with one of these,
+// so the UI can tell a rate limit (wait and retry) from a missing file.
+
+export type ErrorKind = 'network' | 'parse' | 'not_found' | 'rate_limited' | 'io' | 'denied' | 'cancelled' | 'other'
+
+export interface CommandError {
+  kind: ErrorKind
+  message: string
+}
+
+export const isCommandError = (error: unknown): error is CommandError =>
+  typeof error === 'object' && error !== null && 'kind' in error && 'message' in error
+
+export const errorMessage = (error: unknown) => (isCommandError(error) ? error.message : String(error))
+
+// What to tell the user, with advice for the kinds they can do something about
+export const describeError = (error: unknown) => {
+  if (!isCommandError(error)) return String(error)
+  switch (error.kind) {
+    case 'rate_limited':
+      return 'The server is limiting requests right now. Wait a minute and try again.'
+    case 'not_found':
+      return `Not found on the server any more. ${error.message}`
+    case 'network':
+      return `Could not reach the server; check the connection or proxy settings. ${error.message}`
+    case 'denied':
+      return error.message
+    default:
+      return error.message
+  }
+}
@@ -0,0 +1,41 @@
+// Keyboard shortcuts are configured in the backend (app_config.json); this
+// module only turns key events into the combo strings it stores.
+
+export type Keybindings = Record<string, string>
+
+// Fired on window for shortcuts that a page handles itself
+export const SHORTCUT_EVENT = 'rom-browser:shortcut'
+
+export const KEYBINDING_LABELS: Record<string, string> = {
+  go_library: 'Go to My Library',
+  go_browser: 'Go to Myrient Browser',
+  go_settings: 'Go to Settings',
+  focus_search: 'Focus search',
+  clear_search: 'Clear search',
+  next_platform: 'Next platform',
+  previous_platform: 'Previous platform',
+  download_top_result: 'Download top result',
+}
+
+const MODIFIER_KEYS = ['Control', 'Alt', 'Shift', 'Meta']
+
+// Build a combo like "Ctrl+Shift+F" from a key event, or null for a lone modifier
+export const comboFromEvent = (event: KeyboardEvent): string | null => {
+  if (MODIFIER_KEYS.includes(event.key)) return null
+
+  const parts: string[] = []
+  if (event.ctrlKey) parts.push('Ctrl')
+  if (event.altKey) parts.push('Alt')
+  if (event.shiftKey) parts.push('Shift')
+  if (event.metaKey) parts.push('Meta')
+
+  const key = event.key === ' ' ? 'Space' : event.key.length === 1 ? event.key.toUpperCase() : event.key
+  parts.push(key)
+  return parts.join('+')
+}
+
+export const actionForEvent = (bindings: Keybindings, event: KeyboardEvent): string | null => {
+  const combo = comboFromEvent(event)
+  if (!combo) return null
+  return Object.keys(bindings).find(action => bindings[action] === combo) ?? null
+}
@@ -0,0 +1,90 @@
+import { useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/tauri'
+import { listen } from '@tauri-apps/api/event'
+
+// Mirrors JobProgress in job_progress.py; the backend relays each report a script
+// makes as a job-progress event while a download, scan, scrape or sync runs
+export interface JobProgress {
+  job: string
+  label: string
+  done: number
+  total: number | null
+  unit: 'items' | 'bytes'
+  rate: number | null
+  eta_seconds: number | null
+  elapsed_seconds: number
+  message: string | null
+  finished: boolean
+  fraction: number | null
+}
+
+export const JOB_PROGRESS_EVENT = 'job-progress'
+
+// Finished jobs stay on screen this long so their last state can be read
+const FINISHED_DISPLAY_MS = 3000
+
+export const formatEta = (seconds: number) => {
+  const rounded = Math.round(seconds)
+  const hours = Math.floor(rounded / 3600)
+  const minutes = Math.floor((rounded % 3600) / 60)
+  if (hours) return `${hours}h ${minutes}m`
+  return minutes ? `${minutes}m ${rounded % 60}s` : `${rounded}s`
+}
+
+export const describeProgress = (progress: JobProgress) => {
+  const parts = [progress.label || progress.job]
+  if (progress.fraction != null) parts.push(`${Math.round(progress.fraction * 100)}%`)
+  if (progress.eta_seconds != null && !progress.finished) parts.push(`about ${formatEta(progress.eta_seconds)} left`)
+  return parts.join(' · ')
+}
+
+// The jobs reporting progress now, one entry per job and label
+export const useJobProgress = () => {
+  const [jobs, setJobs] = useState<Record<string, JobProgress>>({})
+
+  useEffect(() => {
+    const unlisten = listen<JobProgress>(JOB_PROGRESS_EVENT, event => {
+      const progress = event.payload
+      const key = `${progress.job}:${progress.label}`
+      setJobs(current => ({ ...current, [key]: progress }))
+      if (progress.finished) {
+        window.setTimeout(() => setJobs(current => {
+          if (current[key] !== progress) return current
+          const { [key]: _, ...rest } = current
+          return rest
+        }), FINISHED_DISPLAY_MS)
+      }
+    })
+    return () => { unlisten.then(stop => stop()) }
+  }, [])
+
+  return Object.values(jobs)
+}
+
+// Mirrors a result of job_recovery.py recover --json: what was done with one job a crash interrupted
+export interface RecoveredJob {
+  id: string
+  kind: string
+  label: string
+  started: string
+  action: 'resumed' | 'completed' | 'cleaned' | 'left' | 'failed'
+  detail: string
+  removed_files: string[]
+}
+
+export const JOBS_RECOVERED_EVENT = 'jobs-recovered'
+
+// The jobs recovered on start, whether the recovery finished before or after the window opened
+export const useRecoveredJobs = () => {
+  const [recovered, setRecovered] = useState<RecoveredJob[]>([])
+
+  useEffect(() => {
+    invoke<RecoveredJob[] | null>('get_recovered_jobs')
+      .then(results => { if (results) setRecovered(results) })
+      .catch(error => console.error('Failed to load the recovered jobs:', error))
+    const unlisten = listen<RecoveredJob[]>(JOBS_RECOVERED_EVENT, event => setRecovered(event.payload))
+    return () => { unlisten.then(stop => stop()) }
+  }, [])
+
+  return [recovered, () => setRecovered([])] as const
+}
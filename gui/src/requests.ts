@@ -0,0 +1,28 @@
+import { invoke } from '@tauri-apps/api/tauri'
+import { isCommandError } from './errors'
+
+// Browse and search requests that a newer one makes stale. Each slot (e.g. 'search')
+// runs one request at a time: starting another cancels the one still running, whose
+// promise then rejects with kind 'cancelled' (see cancel_request in main.rs).
+const running = new Map<string, string>()
+let nextId = 0
+
+export const isCancelled = (error: unknown) => isCommandError(error) && error.kind === 'cancelled'
+
+export const cancelSlot = (slot: string) => {
+  const requestId = running.get(slot)
+  if (requestId === undefined) return
+  running.delete(slot)
+  invoke('cancel_request', { requestId }).catch(error => console.error('Failed to cancel a request:', error))
+}
+
+export const invokeLatest = async <T>(slot: string, command: string, args: Record<string, unknown>) => {
+  cancelSlot(slot)
+  const requestId = `${slot}-${++nextId}`
+  running.set(slot, requestId)
+  try {
+    return await invoke<T>(command, { ...args, requestId })
+  } finally {
+    if (running.get(slot) === requestId) running.delete(slot)
+  }
+}
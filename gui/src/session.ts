@@ -0,0 +1,36 @@
+import { invoke } from '@tauri-apps/api/tauri'
+import { Page } from './components/Navigation'
+
+// Mirrors BrowseSession in main.rs: where the GUI was when last used. The
+// backend keeps it in the data directory and restore_last_session hands it
+// back on the next launch while it is recent enough.
+export interface BrowseSession {
+  page: Page
+  platform_id: string | null
+  search: string
+  // Name of the first game card in view
+  scroll_anchor: string | null
+}
+
+// Scrolling and typing change the session many times a second; only the settled state is saved
+const SAVE_DELAY_MS = 500
+
+let current: BrowseSession = { page: 'library', platform_id: null, search: '', scroll_anchor: null }
+let pendingSave: number | undefined
+
+export const currentSession = () => current
+
+export const restoreLastSession = async () => {
+  const session = await invoke<BrowseSession | null>('restore_last_session')
+  if (session) current = { ...current, ...session }
+  return session
+}
+
+export const updateSession = (changes: Partial<BrowseSession>) => {
+  current = { ...current, ...changes }
+  window.clearTimeout(pendingSave)
+  pendingSave = window.setTimeout(() => {
+    invoke('save_browse_session', { session: current })
+      .catch(error => console.error('Failed to save the session:', error))
+  }, SAVE_DELAY_MS)
+}
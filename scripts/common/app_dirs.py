@@ -0,0 +1,197 @@
+#!/usr/bin/env python3
+"""
+App Directories
+Per-user directories for rom-browser, following each platform's conventions:
+
+              Linux (XDG)                  macOS                              Windows
+    config    ~/.config/rom-browser        ~/Library/Application Support/...  %APPDATA%\\rom-browser
+    data      ~/.local/share/rom-browser   ~/Library/Application Support/...  %LOCALAPPDATA%\\rom-browser
+    cache     ~/.cache/rom-browser         ~/Library/Caches/rom-browser       %LOCALAPPDATA%\\rom-browser\\Cache
+    logs      ~/.local/state/rom-browser   ~/Library/Logs/rom-browser         %LOCALAPPDATA%\\rom-browser\\Logs
+
+games.db, covers and downloads live in the data directory, temp files in the
+cache directory. Older versions kept them next to the scripts; those files
+are moved over on request:
+
+    python scripts/common/app_dirs.py migrate --dry-run   # list what would move
+    python scripts/common/app_dirs.py migrate             # move it, after asking
+"""
+
+import argparse
+import json
+import os
+import shutil
+import sys
+from pathlib import Path
+from typing import Callable, List, Optional, Tuple
+
+APP_NAME = "rom-browser"
+
+# The scripts folder, which held everything before the per-user directories
+LEGACY_ROOT = Path(__file__).resolve().parent.parent
+
+
+def _local_app_data() -> Path:
+    return Path(os.getenv("LOCALAPPDATA") or Path.home() / "AppData" / "Local")
+
+
+def user_config_dir() -> Path:
+    """Get the platform's per-user config directory for rom-browser."""
+    if sys.platform == "win32":
+        base = os.getenv("APPDATA") or Path.home() / "AppData" / "Roaming"
+    elif sys.platform == "darwin":
+        base = Path.home() / "Library" / "Application Support"
+    else:
+        base = os.getenv("XDG_CONFIG_HOME") or Path.home() / ".config"
+    return Path(base) / APP_NAME
+
+
+def user_data_dir() -> Path:
+    """Get the per-user directory for games.db, covers and downloads."""
+    if sys.platform == "win32":
+        return _local_app_data() / APP_NAME
+    if sys.platform == "darwin":
+        return Path.home() / "Library" / "Application Support" / APP_NAME
+    return Path(os.getenv("XDG_DATA_HOME") or Path.home() / ".local" / "share") / APP_NAME
+
+
+def user_cache_dir() -> Path:
+    """Get the per-user directory for temp files and anything else that can be rebuilt."""
+    if sys.platform == "win32":
+        return _local_app_data() / APP_NAME / "Cache"
+    if sys.platform == "darwin":
+        return Path.home() / "Library" / "Caches" / APP_NAME
+    return Path(os.getenv("XDG_CACHE_HOME") or Path.home() / ".cache") / APP_NAME
+
+
+def user_log_dir() -> Path:
+    """Get the per-user directory for log files."""
+    if sys.platform == "win32":
+        return _local_app_data() / APP_NAME / "Logs"
+    if sys.platform == "darwin":
+        return Path.home() / "Library" / "Logs" / APP_NAME
+    return Path(os.getenv("XDG_STATE_HOME") or Path.home() / ".local" / "state") / APP_NAME
+
+
+def legacy_locations(config) -> List[Tuple[Path, Path]]:
+    """Get (old, new) pairs for the files older versions kept next to the scripts."""
+    game_management = LEGACY_ROOT / "game-management"
+    rom_sourcing = LEGACY_ROOT / "rom-sourcing"
+    download_dir = config.get_path('paths.download_directory')
+    log_dir = config.get_path('paths.log_directory')
+
+    moves = [
+        (game_management / "games.db", config.get_path('paths.games_db')),
+        (game_management / "covers", config.get_path('paths.covers_directory')),
+        (rom_sourcing / "temp", config.get_path('paths.temp_directory')),
+        (LEGACY_ROOT / "downloads", download_dir),
+        (rom_sourcing / "downloads", download_dir),
+    ]
+    for name in ("rom-browse.log", "download_log.txt", "mbrowse_log.txt"):
+        moves.append((rom_sourcing / name, log_dir / name))
+    return moves
+
+
+def pending_legacy_files(config) -> List[Tuple[Path, Path]]:
+    """Get the (old, new) pairs migrate_legacy_files would move: old files not already where the settings point."""
+    return [(old, new) for old, new in legacy_locations(config)
+            if old.exists() and old.resolve() != new.resolve()]
+
+
+def move_into(old: Path, new: Path):
+    """Move a file or folder, merging a folder into an existing one without overwriting anything."""
+    if not new.exists():
+        new.parent.mkdir(parents=True, exist_ok=True)
+        shutil.move(str(old), str(new))
+        return
+    if old.is_dir() and new.is_dir():
+        for child in old.iterdir():
+            move_into(child, new / child.name)
+        if not any(old.iterdir()):
+            old.rmdir()
+
+
+def migrate_legacy_files(config, log: Optional[Callable[[str], None]] = None) -> List[Tuple[Path, Path]]:
+    """Move files from the old locations into the per-user directories.
+
+    Files go wherever the settings now point, and are left alone when that
+    is where they already are. Nothing already at the new location is
+    overwritten. Library entries of moved downloads are updated to their new
+    paths. Returns the (old, new) pairs moved.
+    """
+    config.ensure_writable("move files from the old locations")
+
+    moved = []
+    for old, new in pending_legacy_files(config):
+        try:
+            move_into(old, new)
+        except OSError as e:
+            if log:
+                log(f"Could not move {old} to {new}: {e}")
+            continue
+        moved.append((old, new))
+        if log:
+            log(f"Moved {old} to {new}")
+
+    downloads = [(old, new) for old, new in moved if new == config.get_path('paths.download_directory')]
+    if downloads:
+        from library_db import LibraryDB
+
+        library = LibraryDB(config=config)
+        for entry in library.get_entries():
+            path = Path(entry['path'])
+            for old, new in downloads:
+                # Files kept back because the new folder already had one of the same name stay put
+                if path.is_relative_to(old) and not path.exists():
+                    library.move_entry(path, new / path.relative_to(old))
+
+    return moved
+
+
+def main():
+    from rom_config import ReadOnlyError, RomConfig, add_config_argument
+
+    parser = argparse.ArgumentParser(description='Move files older versions kept next to the scripts')
+    add_config_argument(parser)
+    subparsers = parser.add_subparsers(dest='command', required=True)
+    migrate_parser = subparsers.add_parser('migrate', help='Move games.db, covers, temp files, downloads and logs '
+                                                           'from the scripts folder into the per-user directories')
+    migrate_parser.add_argument('--dry-run', action='store_true', help='Only list what would be moved')
+    migrate_parser.add_argument('--yes', action='store_true', help='Move without asking first')
+    migrate_parser.add_argument('--json', action='store_true', help='Print JSON')
+
+    args = parser.parse_args()
+    config = RomConfig()
+
+    def report(moves):
+        if args.json:
+            print(json.dumps([{"from": str(old), "to": str(new)} for old, new in moves]))
+        for old, new in ([] if args.json else moves):
+            print(f"{old} -> {new}")
+
+    pending = pending_legacy_files(config)
+    if args.dry_run or not pending:
+        report(pending)
+        if not pending and not args.json:
+            print("Nothing to move")
+        return
+
+    if not args.yes:
+        report(pending)
+        if input(f"Move these {len(pending)} items? [y/N] ").strip().lower() not in ("y", "yes"):
+            print("Nothing moved")
+            return
+
+    try:
+        moved = migrate_legacy_files(config, log=None if args.json else print)
+    except ReadOnlyError as e:
+        print(e, file=sys.stderr)
+        sys.exit(1)
+    if args.json:
+        report(moved)
+    if len(moved) < len(pending):
+        sys.exit(1)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,96 @@
+#!/usr/bin/env python3
+"""
+Archive Index
+Parses the directory listings (autoindex pages) of the archive and its
+mirrors into entries, the one implementation every browser, the downloader
+and the GUI go through.
+
+Besides the links, each row's size and modification date are read where the
+server shows them. The common layouts are understood:
+
+    Myrient     <td class="size">1.2 GiB</td><td class="date">13-Feb-2024 04:21</td>
+    nginx       <a href="x.zip">x.zip</a>   13-Feb-2024 04:21   1288490188
+    Apache      <td>2024-02-13 04:21</td><td>1.2G</td>
+
+Sizes use binary units, as these servers do. Directories have no size.
+"""
+
+import html
+import re
+import urllib.parse
+from datetime import datetime
+from typing import Dict, List, Optional
+
+ROW_PATTERN = re.compile(r'<tr\b[^>]*>(.*?)</tr>', re.IGNORECASE | re.DOTALL)
+LINK_PATTERN = re.compile(r'<a\b[^>]*\bhref="([^"]+)"[^>]*>.*?</a>', re.IGNORECASE | re.DOTALL)
+TAG_PATTERN = re.compile(r'<[^>]+>')
+
+# strptime format of each date layout
+DATE_PATTERNS = [
+    (re.compile(r'\b(\d{1,2}-[A-Za-z]{3}-\d{4} \d{1,2}:\d{2})\b'), "%d-%b-%Y %H:%M"),
+    (re.compile(r'\b(\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2})\b'), "%Y-%m-%d %H:%M:%S"),
+    (re.compile(r'\b(\d{4}-\d{2}-\d{2} \d{2}:\d{2})\b'), "%Y-%m-%d %H:%M"),
+]
+SIZE_PATTERN = re.compile(r'(?<![\w.])(\d+(?:\.\d+)?)\s*([KMGTP]?)(?:i?B)?(?![\w.])', re.IGNORECASE)
+SIZE_UNITS = {"": 1, "K": 1024, "M": 1024 ** 2, "G": 1024 ** 3, "T": 1024 ** 4, "P": 1024 ** 5}
+
+
+def parse_size(text: str) -> Optional[int]:
+    """Get the size in bytes shown in a listing row, e.g. "1.2 GiB", "1.2G" or "1288490188"."""
+    match = SIZE_PATTERN.search(text)
+    if not match:
+        return None
+    return int(float(match.group(1)) * SIZE_UNITS[match.group(2).upper()])
+
+
+def parse_date(text: str) -> Optional[str]:
+    """Get the modification date shown in a listing row as an ISO timestamp."""
+    for pattern, date_format in DATE_PATTERNS:
+        match = pattern.search(text)
+        if match:
+            try:
+                return datetime.strptime(match.group(1), date_format).isoformat()
+            except ValueError:
+                continue
+    return None
+
+
+def listing_rows(page: str) -> List[str]:
+    """Split a listing into one chunk of markup per entry: table rows, or lines of a <pre> listing."""
+    rows = ROW_PATTERN.findall(page)
+    return rows if rows else page.splitlines()
+
+
+def parse_index(page: str, prefix: str = '') -> List[Dict]:
+    """Parse an index page into entries, directories first, each group sorted by name.
+
+    Entries are dicts with "name", "path" (prefix + name, '/' after
+    directories), "is_dir", "size" and "modified" (both None when not shown).
+    Parent, sort, navigation and off-site links are left out.
+    """
+    directories, files = {}, {}
+    for row in listing_rows(page):
+        link = LINK_PATTERN.search(row)
+        if not link:
+            continue
+        href = html.unescape(link.group(1))
+        # Skip the parent link, site navigation, sort links and other hosts
+        if href in ('../', '..') or href.startswith(('/', '#', 'http:', 'https:')) or '?' in href:
+            continue
+
+        name = urllib.parse.unquote(href)
+        # Only what follows the link can be the size and date; the name may contain numbers too
+        details = html.unescape(TAG_PATTERN.sub(' ', row[link.end():]))
+        modified = parse_date(details)
+        for pattern, _ in DATE_PATTERNS:
+            details = pattern.sub(' ', details)
+
+        if name.endswith('/'):
+            name = name.rstrip('/')
+            directories[name] = {"name": name, "path": prefix + name + '/', "is_dir": True,
+                                 "size": None, "modified": modified}
+        elif name:
+            files[name] = {"name": name, "path": prefix + name, "is_dir": False,
+                           "size": parse_size(details), "modified": modified}
+
+    return [directories[n] for n in sorted(directories)] + [files[n] for n in sorted(files)]
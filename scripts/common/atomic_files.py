@@ -0,0 +1,88 @@
+#!/usr/bin/env python3
+"""
+Atomic Files
+Writes settings files so a crash mid-save cannot destroy them, and recovers
+from a file that was truncated or corrupted anyway:
+
+    settings.toml       the file itself, only ever replaced by a rename
+    settings.toml.tmp   the new contents while they are being written
+    settings.toml.bak   the previous version, kept on every save
+    settings.toml.corrupt  a damaged file set aside when the backup replaced it
+
+The GUI writes its settings the same way, so either side can recover the
+other's files.
+"""
+
+import os
+import shutil
+from pathlib import Path
+from typing import Any, Callable, Optional
+
+BACKUP_SUFFIX = ".bak"
+TEMP_SUFFIX = ".tmp"
+CORRUPT_SUFFIX = ".corrupt"
+
+
+def sibling(path: Path, suffix: str) -> Path:
+    return path.with_name(path.name + suffix)
+
+
+def atomic_write(path, text: str, validate: Optional[Callable[[Path], Any]] = None):
+    """Replace a file's contents in one step, keeping the previous version as <name>.bak.
+
+    With validate (a reader that raises on a damaged file), a current file
+    that fails it is set aside as <name>.corrupt instead, so a good backup is
+    never replaced by a bad one.
+    """
+    path = Path(path)
+    path.parent.mkdir(parents=True, exist_ok=True)
+    temp_path = sibling(path, TEMP_SUFFIX)
+    with open(temp_path, 'w', encoding='utf-8') as f:
+        f.write(text)
+        f.flush()
+        os.fsync(f.fileno())
+
+    if path.exists() and path.stat().st_size > 0:
+        try:
+            if validate:
+                validate(path)
+        except Exception:
+            shutil.copy2(path, sibling(path, CORRUPT_SUFFIX))
+        else:
+            shutil.copy2(path, sibling(path, BACKUP_SUFFIX))
+    os.replace(temp_path, path)
+
+
+def read_with_recovery(path, read: Callable[[Path], Any], log: Optional[Callable[[str], None]] = None) -> Any:
+    """Read a file with read, falling back to its backup when the file is damaged.
+
+    The backup is restored in place of the damaged file, which is kept as
+    <name>.corrupt. Raises the original error when there is no usable backup.
+    """
+    path = Path(path)
+    backup = sibling(path, BACKUP_SUFFIX)
+    try:
+        # A save cut short by a crash leaves an empty file, which TOML would read as no settings
+        if backup.exists() and path.stat().st_size == 0:
+            raise ValueError("the file is empty")
+        return read(path)
+    except Exception as error:
+        if not backup.exists():
+            raise
+        try:
+            data = read(backup)
+        except Exception:
+            raise error
+
+        try:
+            shutil.copy2(path, sibling(path, CORRUPT_SUFFIX))
+            shutil.copy2(backup, sibling(path, TEMP_SUFFIX))
+            os.replace(sibling(path, TEMP_SUFFIX), path)
+        except OSError as e:
+            if log:
+                log(f"Could not restore {path} from its backup: {e}")
+        else:
+            if log:
+                log(f"{path} was damaged ({error}); restored the previous version, "
+                    f"the damaged file is kept as {sibling(path, CORRUPT_SUFFIX).name}")
+        return data
@@ -0,0 +1,169 @@
+#!/usr/bin/env python3
+"""
+Batch Sessions
+Records what a batch run of the downloader (the queue, a --match pattern, a
+mirror, an imported queue) set out to download and how far it got, so an
+interrupted run can be continued with `rom_downloader.py resume`. Each batch
+has a file in the sessions folder of the data directory:
+
+    sessions/<id>.json   {"id", "kind", "label", "pid", "started", "updated",
+                          "finished", "settings": {...}, "items": [...]}
+
+An item is {"url", "filename", "entry", "explanation", "status", "bytes",
+"error"}; status is "pending", "downloaded", "skipped" (already on disk) or
+"failed". entry is the download queue entry the item came from, removed from
+the queue once the item is done. settings are what the downloader needs to
+put the files where the batch would have: the download folder, path
+templates and queue file.
+
+Unlike the job journal, which follows single downloads, a session stays when
+its batch ends: finished holds the end-of-session report, and the last
+KEEP_FINISHED finished sessions are kept.
+"""
+
+import json
+import os
+import threading
+import uuid
+from datetime import datetime
+from typing import Dict, List, Optional
+
+from job_journal import process_running, write_json
+from rom_config import RomConfig
+
+SESSION_SUFFIX = ".json"
+KEEP_FINISHED = 20
+# Items still to download when a session is resumed
+OPEN_STATUSES = ("pending", "failed")
+
+
+class BatchSession:
+    """One batch run. Every change is written to its file at once."""
+
+    def __init__(self, sessions: "BatchSessions", record: Dict):
+        self.sessions = sessions
+        self.record = record
+        self.lock = threading.Lock()
+        self.path = sessions.directory / (record["id"] + SESSION_SUFFIX)
+
+    def save(self):
+        if not self.sessions.enabled:
+            return
+        self.record["updated"] = datetime.now().isoformat(timespec='seconds')
+        write_json(self.path, self.record)
+
+    def running(self) -> bool:
+        """Whether another process is still working on the session."""
+        return not self.record["finished"] and self.record["pid"] != os.getpid() and \
+            process_running(self.record["pid"])
+
+    def open_items(self) -> List[Dict]:
+        """Get the items that are not downloaded or on disk yet."""
+        return [item for item in self.record["items"] if item["status"] in OPEN_STATUSES]
+
+    def resume(self):
+        """Take the session over in this process to download its open items."""
+        with self.lock:
+            self.record["pid"] = os.getpid()
+            self.record["finished"] = None
+            self.record["resumed"] = self.record.get("resumed", 0) + 1
+            self.save()
+
+    def record_result(self, url: str, status: str, transferred: int = 0, error: Optional[str] = None):
+        """Note how one item's download ended."""
+        with self.lock:
+            for item in self.record["items"]:
+                if item["url"] == url and item["status"] in OPEN_STATUSES:
+                    item["status"] = status
+                    item["bytes"] = transferred
+                    item["error"] = error
+                    self.save()
+                    return
+
+    def report(self) -> Dict:
+        """Summarize the session: counts by status, bytes downloaded and the failures."""
+        items = self.record["items"]
+        counts = {status: sum(1 for item in items if item["status"] == status)
+                  for status in ("downloaded", "skipped", "failed", "pending")}
+        return dict(
+            {key: self.record[key] for key in ("id", "kind", "label", "started", "finished")},
+            resumed=self.record.get("resumed", 0),
+            total=len(items),
+            attempted=len(items) - counts["pending"],
+            bytes_done=sum(item["bytes"] or 0 for item in items),
+            failures=[{"filename": item["filename"], "url": item["url"], "error": item["error"]}
+                      for item in items if item["status"] == "failed"],
+            **counts)
+
+    def finish(self) -> Dict:
+        """End the session, storing and returning its report."""
+        with self.lock:
+            self.record["finished"] = datetime.now().isoformat(timespec='seconds')
+            self.record["report"] = self.report()
+            self.save()
+        self.sessions.prune()
+        return self.record["report"]
+
+
+class BatchSessions:
+    """The sessions of one data directory. In read-only mode nothing is written."""
+
+    def __init__(self, config: Optional[RomConfig] = None):
+        config = config or RomConfig()
+        self.directory = config.get_path('paths.data_directory') / "sessions"
+        self.enabled = not config.is_read_only()
+        if self.enabled:
+            self.directory.mkdir(parents=True, exist_ok=True)
+
+    def start(self, kind: str, label: str, items: List[Dict], settings: Dict) -> BatchSession:
+        """Record a new batch of items ({url, filename, entry, explanation}), all pending."""
+        session = BatchSession(self, {
+            "id": datetime.now().strftime("%Y%m%d-%H%M%S-") + uuid.uuid4().hex[:6],
+            "kind": kind,
+            "label": label,
+            "pid": os.getpid(),
+            "started": datetime.now().isoformat(timespec='seconds'),
+            "updated": None,
+            "finished": None,
+            "settings": settings,
+            "items": [{"url": item["url"], "filename": item["filename"], "entry": item.get("entry"),
+                       "explanation": item.get("explanation"), "status": "pending", "bytes": 0, "error": None}
+                      for item in items],
+        })
+        session.save()
+        return session
+
+    def load(self, session_id: str) -> BatchSession:
+        """Get a session by id. Raises ValueError when there is none."""
+        path = self.directory / (session_id + SESSION_SUFFIX)
+        try:
+            with open(path, 'r', encoding='utf-8') as f:
+                return BatchSession(self, json.load(f))
+        except (OSError, ValueError):
+            raise ValueError(f"No batch session {session_id}")
+
+    def sessions(self) -> List[BatchSession]:
+        """Get every session, oldest first."""
+        if not self.directory.exists():
+            return []
+        sessions = []
+        for path in self.directory.glob("*" + SESSION_SUFFIX):
+            try:
+                with open(path, 'r', encoding='utf-8') as f:
+                    sessions.append(BatchSession(self, json.load(f)))
+            except (OSError, ValueError):
+                # Written by os.replace, so a damaged file was never a session's
+                continue
+        return sorted(sessions, key=lambda session: session.record.get("started") or "")
+
+    def interrupted(self) -> List[BatchSession]:
+        """Get the sessions whose process ended before they finished."""
+        return [session for session in self.sessions() if not session.record["finished"] and not session.running()]
+
+    def prune(self):
+        """Delete the oldest finished sessions beyond KEEP_FINISHED."""
+        if not self.enabled:
+            return
+        finished = [session for session in self.sessions() if session.record["finished"]]
+        for session in finished[:-KEEP_FINISHED]:
+            session.path.unlink(missing_ok=True)
@@ -0,0 +1,137 @@
+#!/usr/bin/env python3
+"""
+Checksum Manifests
+Writes a checksum manifest of a collection folder and checks a copy of the
+folder against it later, e.g. after archiving it to an external drive. Two
+formats are understood, with paths relative to the manifest's folder:
+
+    SHA1SUMS    <sha1>  <path>            (as sha1sum writes it; sha1sum -c reads it too)
+    <name>.sfv  <path> <CRC32>            (Simple File Verification, ; starts a comment)
+
+Partial downloads, .romignore matches and the manifests themselves are left
+out.
+"""
+
+import re
+from datetime import datetime
+from pathlib import Path
+from typing import Callable, Dict, Iterable, List
+
+from dat_index import hash_file
+from library_db import NON_DOWNLOAD_SUFFIXES
+from romignore import RomIgnore
+
+SHA1SUMS = "SHA1SUMS"
+FORMATS = ("sha1", "sfv")
+SHA1_LINE = re.compile(r'^([0-9a-fA-F]{40}) [ *](.+)$')
+SFV_LINE = re.compile(r'^(.+) ([0-9a-fA-F]{8})$')
+
+
+def manifest_path(directory: Path, manifest_format: str) -> Path:
+    """Where a folder's manifest goes: SHA1SUMS, or <folder name>.sfv, in the folder itself."""
+    return directory / (SHA1SUMS if manifest_format == "sha1" else f"{directory.resolve().name or 'collection'}.sfv")
+
+
+def find_manifest(target: Path) -> Path:
+    """Get the manifest a path names: the file itself, or the SHA1SUMS (else the one .sfv) in a folder.
+
+    Raises ValueError when there is none.
+    """
+    if target.is_file():
+        return target
+    if (target / SHA1SUMS).is_file():
+        return target / SHA1SUMS
+    sfvs = sorted(target.glob("*.sfv"))
+    if len(sfvs) == 1:
+        return sfvs[0]
+    if sfvs:
+        raise ValueError(f"{target} has several .sfv manifests; name the one to check")
+    raise ValueError(f"No {SHA1SUMS} or .sfv manifest in {target}")
+
+
+def is_manifest(path: Path) -> bool:
+    return path.name == SHA1SUMS or path.suffix.lower() == ".sfv"
+
+
+def collection_files(directory: Path) -> List[Path]:
+    """Get the files a manifest of a folder covers."""
+    return [path for path in RomIgnore().walk(directory)
+            if not path.name.endswith(NON_DOWNLOAD_SUFFIXES) and not is_manifest(path)]
+
+
+def file_checksum(path: Path, manifest_format: str, limits=None) -> str:
+    hashes = hash_file(path, limits)
+    return hashes["sha1"] if manifest_format == "sha1" else hashes["crc32"].upper()
+
+
+def write_manifest(directory: Path, manifest_format: str = "sha1", limits=None,
+                   advance: Callable[[str], None] = lambda name: None) -> Dict:
+    """Hash every file of a folder into its manifest (see manifest_path). Returns {path, files, bytes}."""
+    path = manifest_path(directory, manifest_format)
+    lines, total = [], 0
+    for file in collection_files(directory):
+        checksum = file_checksum(file, manifest_format, limits)
+        relative = file.relative_to(directory).as_posix()
+        lines.append(f"{checksum}  {relative}" if manifest_format == "sha1" else f"{relative} {checksum}")
+        total += file.stat().st_size
+        advance(relative)
+
+    header = [] if manifest_format == "sha1" else \
+        [f"; Written by rom-browser on {datetime.now().isoformat(timespec='seconds')}", f"; {len(lines)} files"]
+    tmp_path = path.with_name(path.name + ".tmp")
+    tmp_path.write_text("".join(line + "\n" for line in header + lines), encoding="utf-8")
+    tmp_path.replace(path)
+    return {"path": str(path), "format": manifest_format, "files": len(lines), "bytes": total}
+
+
+def read_manifest(path: Path) -> Dict[str, str]:
+    """Read a manifest into {relative path: checksum}; the format follows from the name. Raises ValueError."""
+    sfv = path.suffix.lower() == ".sfv"
+    pattern = SFV_LINE if sfv else SHA1_LINE
+    entries = {}
+    for number, line in enumerate(path.read_text(encoding="utf-8", errors="replace").splitlines(), 1):
+        line = line.rstrip("\r")
+        if not line.strip() or (sfv and line.startswith(";")):
+            continue
+        match = pattern.match(line)
+        if not match:
+            raise ValueError(f"{path}:{number}: not a {'.sfv' if sfv else SHA1SUMS} line: {line}")
+        relative, checksum = (match.group(1), match.group(2)) if sfv else (match.group(2), match.group(1))
+        entries[relative.strip().replace("\\", "/")] = checksum.lower()
+    return entries
+
+
+def verify_manifest(path: Path, limits=None, advance: Callable[[str], None] = lambda name: None) -> List[Dict]:
+    """Check the files next to a manifest against it.
+
+    Each listed file is "ok", "mismatch", "missing" or "unreadable"; files
+    in the folder the manifest does not list are "unlisted".
+    """
+    manifest_format = "sfv" if path.suffix.lower() == ".sfv" else "sha1"
+    directory = path.parent
+    expected = read_manifest(path)
+    results = []
+    for relative, checksum in expected.items():
+        file = directory / relative
+        result = {"path": relative, "status": "missing", "expected": checksum, "actual": None}
+        if file.is_file():
+            try:
+                result["actual"] = file_checksum(file, manifest_format, limits).lower()
+                result["status"] = "ok" if result["actual"] == checksum else "mismatch"
+            except OSError as e:
+                result.update(status="unreadable", error=str(e))
+        results.append(result)
+        advance(relative)
+
+    for file in collection_files(directory):
+        relative = file.relative_to(directory).as_posix()
+        if relative not in expected:
+            results.append({"path": relative, "status": "unlisted", "expected": None, "actual": None})
+    return results
+
+
+def summarize(results: Iterable[Dict]) -> Dict[str, int]:
+    counts: Dict[str, int] = {}
+    for result in results:
+        counts[result["status"]] = counts.get(result["status"], 0) + 1
+    return counts
@@ -0,0 +1,76 @@
+#!/usr/bin/env python3
+"""
+Compatibility
+Warns when a release's region does not suit the user's setup: a PAL-only dump
+for an NTSC setup, a Japanese cartridge for region-locked US consoles, or a
+disc that needs another region's BIOS. The setup is described under
+compatibility in rom_browser.json:
+
+    video_standards   ["NTSC"] or ["PAL"]; empty accepts both
+    console_regions   region lock of the consoles, e.g. ["USA"] or ["NTSC-J"]
+    bios_regions      BIOS region per platform, e.g. {"Sony - PlayStation": "USA"}
+"""
+
+from typing import Dict, List, Optional, Set
+
+from datasets import filename_tags
+
+# Release regions -> console region family
+REGION_FAMILIES = {
+    "USA": "NTSC-U", "Canada": "NTSC-U", "Brazil": "NTSC-U", "Latin America": "NTSC-U",
+    "Japan": "NTSC-J", "Asia": "NTSC-J", "Korea": "NTSC-J", "Taiwan": "NTSC-J", "Hong Kong": "NTSC-J",
+    "Europe": "PAL", "Australia": "PAL", "New Zealand": "PAL", "UK": "PAL", "Germany": "PAL", "France": "PAL",
+    "Italy": "PAL", "Spain": "PAL", "Netherlands": "PAL", "Sweden": "PAL", "Scandinavia": "PAL",
+    "Denmark": "PAL", "Norway": "PAL", "Finland": "PAL", "Portugal": "PAL", "Russia": "PAL", "China": "PAL",
+    "NTSC": "NTSC-U", "PAL": "PAL",
+}
+FAMILY_STANDARDS = {"NTSC-U": "NTSC", "NTSC-J": "NTSC", "PAL": "PAL"}
+WORLD_REGIONS = {"World"}
+
+
+def family_of(region: str) -> Optional[str]:
+    """Get the region family of a region name or family name."""
+    return region if region in FAMILY_STANDARDS else REGION_FAMILIES.get(region)
+
+
+def release_regions(filename: str) -> List[str]:
+    """Get the region tags of a file name, as No-Intro spells them."""
+    return [tag for tag in filename_tags(filename) if tag in REGION_FAMILIES or tag in WORLD_REGIONS]
+
+
+def release_families(regions: List[str]) -> Set[str]:
+    if any(region in WORLD_REGIONS for region in regions):
+        return set(FAMILY_STANDARDS)
+    return {REGION_FAMILIES[region] for region in regions if region in REGION_FAMILIES}
+
+
+class CompatibilityChecker:
+    def __init__(self, config):
+        self.video_standards = [s.upper() for s in config.get('compatibility.video_standards', []) or []]
+        self.console_regions = config.get('compatibility.console_regions', []) or []
+        self.bios_regions: Dict[str, str] = config.get('compatibility.bios_regions', {}) or {}
+
+    def check(self, filename: str, platform: Optional[str] = None) -> List[str]:
+        """Get warnings for a release on the configured setup; none when its region is unknown."""
+        regions = release_regions(filename)
+        families = release_families(regions)
+        if not families:
+            return []
+
+        warnings = []
+        region_text = ", ".join(regions)
+        standards = {FAMILY_STANDARDS[family] for family in families}
+        if self.video_standards and not standards & set(self.video_standards):
+            warnings.append(f"{'/'.join(sorted(standards))}-only release ({region_text}) but "
+                            f"your setup is {'/'.join(self.video_standards)}")
+
+        locked = {family_of(region) for region in self.console_regions} - {None}
+        if locked and not families & locked:
+            warnings.append(f"{region_text} release may not boot on region-locked "
+                            f"{'/'.join(self.console_regions)} consoles")
+
+        bios = self.bios_regions.get(platform) if platform else None
+        if bios and family_of(bios) not in families:
+            warnings.append(f"needs a {region_text} BIOS, yours is {bios}")
+
+        return warnings
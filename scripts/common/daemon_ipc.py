@@ -0,0 +1,238 @@
+#!/usr/bin/env python3
+"""
+Daemon IPC
+Gives the library database and the download queue a single owner. While the
+GUI is open it runs rom_daemon.py, which listens on a local socket (a named
+pipe on Windows); scripts started meanwhile send their queue edits and library
+updates there instead of opening the files themselves, so two processes never
+write the same file at once. Without a daemon the scripts use the files
+directly, as before.
+
+daemon.mode in rom_browser.json decides when the daemon is used:
+
+    auto       whenever one is running (the default)
+    off        never
+    required   always; scripts refuse to run when no daemon is running
+
+Only processes that can read the key file in the data directory can connect.
+Each call carries the caller's read-only mode, which the daemon applies while
+running it, and a script whose library database is not the daemon's (e.g.
+another --config) uses its own file directly.
+"""
+
+import hashlib
+import os
+import secrets
+import sys
+import threading
+from multiprocessing import AuthenticationError
+from multiprocessing.connection import Client, Listener
+from pathlib import Path, PurePath
+from typing import Callable, Dict, Optional
+
+SOCKET_NAME = "daemon.sock"
+KEY_NAME = "daemon.key"
+
+MODES = ("auto", "off", "required")
+
+
+class DaemonError(Exception):
+    """The daemon refused or failed a request, or is required but not running."""
+
+
+def daemon_address(config) -> str:
+    data_directory = config.get_path('paths.data_directory')
+    if sys.platform == 'win32':
+        # Pipe names are machine-wide, so each data directory gets its own
+        digest = hashlib.sha1(str(data_directory).encode('utf-8')).hexdigest()[:12]
+        return rf'\\.\pipe\rom-browser-{digest}'
+    return str(data_directory / SOCKET_NAME)
+
+
+def daemon_family() -> str:
+    return 'AF_PIPE' if sys.platform == 'win32' else 'AF_UNIX'
+
+
+def daemon_mode(config) -> str:
+    mode = config.get('daemon.mode', 'auto')
+    return mode if mode in MODES else 'auto'
+
+
+class DaemonClient:
+    """A connection to the running daemon. Calls are sent one at a time per connection."""
+
+    def __init__(self, connection):
+        self.connection = connection
+        self.lock = threading.Lock()
+        self.status = None
+
+    def call(self, method: str, *args, **kwargs):
+        """Run target.method(*args, **kwargs) in the daemon and return its result."""
+        return self.request(method, args, kwargs)
+
+    def request(self, method: str, args=(), kwargs=None, context: Optional[Dict] = None):
+        """Run target.method(*args, **kwargs) in the daemon on behalf of a caller: context is its {read_only}."""
+        with self.lock:
+            try:
+                self.connection.send((method, tuple(args), kwargs or {}, context or {}))
+                ok, value = self.connection.recv()
+            except (OSError, EOFError) as e:
+                raise DaemonError(f"Lost the connection to the daemon: {e}")
+        if not ok:
+            raise DaemonError(value)
+        return value
+
+    def serves(self, key: str, path) -> bool:
+        """Check whether the daemon's status (daemon.status, asked once) names this path under key."""
+        if self.status is None:
+            self.status = self.call("daemon.status")
+        served = self.status.get(key)
+        return served is not None and Path(served).resolve() == Path(path).resolve()
+
+
+class RemoteObject:
+    """Stands in for an object the daemon holds, e.g. its LibraryDB: method calls run there,
+    read-only when this process's config is."""
+
+    def __init__(self, client: DaemonClient, target: str, config=None):
+        self.client = client
+        self.target = target
+        self.config = config
+
+    def __getattr__(self, name: str):
+        if name.startswith('_'):
+            raise AttributeError(name)
+
+        def call(*args, **kwargs):
+            # The daemon has its own working directory, so relative paths are resolved here
+            args = [absolute(arg) for arg in args]
+            kwargs = {key: absolute(value) for key, value in kwargs.items()}
+            # Asked on every call, since read-only mode can be turned on after the object was made
+            context = {"read_only": self.config.is_read_only() if self.config else False}
+            return self.client.request(f"{self.target}.{name}", args, kwargs, context)
+        return call
+
+
+def absolute(value):
+    return Path(value).resolve() if isinstance(value, PurePath) else value
+
+
+# One connection per daemon address and process
+_clients: Dict[str, DaemonClient] = {}
+
+
+def open_client(config) -> DaemonClient:
+    """Open a new connection to the daemon. Raises OSError, EOFError or AuthenticationError when none answers."""
+    authkey = (config.get_path('paths.data_directory') / KEY_NAME).read_bytes()
+    return DaemonClient(Client(daemon_address(config), family=daemon_family(), authkey=authkey))
+
+
+def connect(config) -> Optional[DaemonClient]:
+    """Connect to the running daemon, or get None to use the files directly.
+
+    Raises DaemonError when daemon.mode is required and no daemon answers.
+    """
+    mode = daemon_mode(config)
+    if mode == 'off':
+        return None
+
+    address = daemon_address(config)
+    if address in _clients:
+        return _clients[address]
+    try:
+        client = open_client(config)
+    except (OSError, EOFError, AuthenticationError) as e:
+        if mode == 'required':
+            raise DaemonError(f"No rom-browser daemon is running ({e}); open the GUI or run rom_daemon.py serve")
+        return None
+    _clients[address] = client
+    return client
+
+
+class Daemon:
+    """Serves method calls on its targets, e.g. {"library": LibraryDB(...)}, one call at a time."""
+
+    def __init__(self, config, targets: Dict[str, object], log: Callable[[str], None] = print):
+        self.config = config
+        self.targets = targets
+        self.log = log
+        self.address = daemon_address(config)
+        self.lock = threading.Lock()
+        self.listener = None
+        self.key = None
+        self.stopping = threading.Event()
+
+    def write_key(self) -> bytes:
+        key = secrets.token_bytes(32)
+        path = self.config.get_path('paths.data_directory') / KEY_NAME
+        path.parent.mkdir(parents=True, exist_ok=True)
+        if path.exists():
+            path.unlink()
+        fd = os.open(path, os.O_WRONLY | os.O_CREAT | os.O_EXCL, 0o600)
+        with os.fdopen(fd, 'wb') as f:
+            f.write(key)
+        return key
+
+    def start(self):
+        """Start listening. Raises DaemonError if another daemon is already serving this data directory."""
+        try:
+            open_client(self.config).connection.close()
+        except (OSError, EOFError, AuthenticationError):
+            pass
+        else:
+            raise DaemonError(f"A daemon is already running at {self.address}")
+
+        # A socket left behind by a daemon that crashed would block the listener
+        if daemon_family() == 'AF_UNIX' and os.path.exists(self.address):
+            os.unlink(self.address)
+        self.key = self.write_key()
+        self.listener = Listener(self.address, family=daemon_family(), authkey=self.key)
+
+    def handle(self, connection):
+        while not self.stopping.is_set():
+            try:
+                method, args, kwargs, context = connection.recv()
+            except (OSError, EOFError):
+                break
+
+            target_name, _, name = method.partition('.')
+            target = self.targets.get(target_name)
+            function = getattr(target, name, None) if target is not None and not name.startswith('_') else None
+            if not callable(function):
+                reply = (False, f"Unknown daemon method {method}")
+            else:
+                try:
+                    with self.lock, self.config.read_only_for_caller(context.get('read_only')):
+                        reply = (True, function(*args, **kwargs))
+                except Exception as e:
+                    reply = (False, f"{type(e).__name__}: {e}")
+            try:
+                connection.send(reply)
+            except (OSError, EOFError):
+                break
+        connection.close()
+
+    def serve(self):
+        """Accept clients until stop() is called."""
+        if self.listener is None:
+            self.start()
+        self.log(f"Daemon listening on {self.address}")
+        while not self.stopping.is_set():
+            try:
+                connection = self.listener.accept()
+            except (OSError, EOFError, AuthenticationError):
+                continue
+            threading.Thread(target=self.handle, args=(connection,), daemon=True).start()
+
+    def stop(self):
+        self.stopping.set()
+        if self.listener is None:
+            return
+        # Closing the listener does not wake a blocked accept() everywhere, so connect once to do it
+        try:
+            Client(self.address, family=daemon_family(), authkey=self.key).close()
+        except (OSError, EOFError, AuthenticationError):
+            pass
+        self.listener.close()
+        if daemon_family() == 'AF_UNIX' and os.path.exists(self.address):
+            os.unlink(self.address)
@@ -0,0 +1,150 @@
+#!/usr/bin/env python3
+"""
+DAT Index
+Hashes ROM files and matches them against Logiqx XML DAT files (No-Intro, Redump).
+"""
+
+import re
+import xml.etree.ElementTree as ET
+import zipfile
+from contextlib import nullcontext
+from pathlib import Path
+from typing import Callable, Dict, List, Optional
+
+from names import parse_name
+from stream_hashes import StreamHasher
+
+CHUNK_SIZE = 1024 * 1024
+
+
+def hash_stream(stream, limits=None) -> Dict:
+    """Compute size, CRC32, MD5 and SHA1 of a binary stream in one pass, at the read speed limits allow."""
+    hasher = StreamHasher()
+    while True:
+        chunk = stream.read(CHUNK_SIZE)
+        if not chunk:
+            break
+        if limits:
+            limits.throttle(len(chunk))
+        hasher.update(chunk)
+    return hasher.result()
+
+
+def hash_file(path, limits=None) -> Dict:
+    """Hash a file on disk; with JobLimits, once one of the job's read slots is free."""
+    with limits.io_slot() if limits else nullcontext(), open(path, 'rb') as f:
+        return hash_stream(f, limits)
+
+
+def hash_entries(path, limits=None) -> List[Dict]:
+    """Hash a file, or each member of a ZIP archive.
+
+    Returns one entry per hashed file with a 'name' key; DATs describe the
+    files inside archives, so archives are identified by their contents.
+    """
+    path = Path(path)
+    with limits.io_slot() if limits else nullcontext():
+        if zipfile.is_zipfile(path):
+            entries = []
+            with zipfile.ZipFile(path) as archive:
+                for info in archive.infolist():
+                    if info.is_dir():
+                        continue
+                    with archive.open(info) as member:
+                        hashes = hash_stream(member, limits)
+                    hashes["name"] = info.filename
+                    entries.append(hashes)
+            return entries
+
+        with open(path, 'rb') as f:
+            hashes = hash_stream(f, limits)
+    hashes["name"] = path.name
+    return [hashes]
+
+
+def parse_region(title: str) -> Optional[str]:
+    """Get the regions of a DAT title, e.g. "USA, Europe", or None when it has no region tag."""
+    return ", ".join(parse_name(title).regions) or None
+
+
+class DatIndex:
+    def __init__(self):
+        self.by_sha1 = {}
+        self.by_md5 = {}
+        self.by_crc = {}
+        self.by_title = {}
+        self.dats = []
+
+    def load_directory(self, directory, log: Callable[[str], None] = print) -> int:
+        """Load every .dat/.xml file in a directory tree. Returns the number loaded."""
+        directory = Path(directory)
+        if not directory.exists():
+            return 0
+
+        loaded = 0
+        for path in sorted(directory.rglob("*")):
+            if path.suffix.lower() in ('.dat', '.xml') and path.is_file():
+                try:
+                    self.load_dat(path)
+                    loaded += 1
+                except ET.ParseError as e:
+                    log(f"Skipping unreadable DAT {path}: {e}")
+        return loaded
+
+    def load_dat(self, path):
+        """Load a single Logiqx XML DAT file."""
+        root = ET.parse(path).getroot()
+        header_name = root.findtext('header/name') or Path(path).stem
+        # No-Intro appends " (Parent-Clone)" or a date to the system name
+        platform = re.sub(r'\s*\(.*\)\s*$', '', header_name).strip()
+        self.dats.append({"name": header_name, "platform": platform, "path": str(path)})
+
+        for game in list(root.iter('game')) + list(root.iter('machine')):
+            title = game.get('name', '')
+            for rom in game.iter('rom'):
+                entry = {
+                    "title": title,
+                    "description": game.findtext('description') or title,
+                    "rom": rom.get('name'),
+                    "size": int(rom.get('size')) if rom.get('size', '').isdigit() else None,
+                    "crc32": (rom.get('crc') or '').lower() or None,
+                    "md5": (rom.get('md5') or '').lower() or None,
+                    "sha1": (rom.get('sha1') or '').lower() or None,
+                    "status": rom.get('status', 'good'),
+                    "dat": header_name,
+                    "platform": platform,
+                    "region": parse_region(title)
+                }
+                if entry["sha1"]:
+                    self.by_sha1.setdefault(entry["sha1"], entry)
+                if entry["md5"]:
+                    self.by_md5.setdefault(entry["md5"], entry)
+                if entry["crc32"]:
+                    self.by_crc.setdefault((entry["crc32"], entry["size"]), entry)
+                self.by_title.setdefault(title, []).append(entry)
+
+    def game_roms(self, title: str) -> List[Dict]:
+        """Get the DAT entries of every file in a game, e.g. the tracks of a disc; empty if no DAT lists it."""
+        return self.by_title.get(title, [])
+
+    def lookup(self, hashes: Dict) -> Optional[Dict]:
+        """Find the DAT entry for a set of hashes.
+
+        The result carries a 'verification' key: 'verified' for a SHA1/MD5
+        match, 'crc-match' when only CRC32 and size agree, or 'bad-dump' when
+        the DAT flags the dump as bad.
+        """
+        entry = self.by_sha1.get(hashes.get("sha1")) or self.by_md5.get(hashes.get("md5"))
+        verification = "verified"
+
+        if not entry:
+            entry = self.by_crc.get((hashes.get("crc32"), hashes.get("size")))
+            verification = "crc-match"
+
+        if not entry:
+            return None
+
+        if entry["status"] in ("baddump", "nodump"):
+            verification = "bad-dump"
+
+        return dict(entry, verification=verification)
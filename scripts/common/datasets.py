@@ -0,0 +1,285 @@
+#!/usr/bin/env python3
+"""
+Datasets
+Knows how the archive's datasets are laid out and how they name files.
+
+Redump and No-Intro keep one folder per platform ("No-Intro/Nintendo - Game
+Boy/") and put the region first in the tags: "Tetris (World) (Rev 1)".
+
+TOSEC nests platforms by manufacturer, system and category
+("TOSEC/Commodore/Amiga/Games/[ADF]/") and names files
+"Title vX (year)(publisher)(country)(language)(status)[flags]", with
+two-letter country and language codes and dump flags in brackets:
+"Turrican II (1991)(Rainbow Arts)(DE)(en)[cr Skid Row][t +2]".
+"""
+
+import re
+import urllib.parse
+from typing import Dict, List, Optional
+
+# Archive folder -> dataset key
+DATASET_FOLDERS = {
+    "Redump": "redump",
+    "No-Intro": "no-intro",
+    "TOSEC": "tosec",
+    "TOSEC-ISO": "tosec",
+    "TOSEC-PIX": "tosec",
+}
+
+# Platform folder -> short name, for menus and the {platform_short} path template field
+PLATFORM_SHORT_NAMES = {
+    "Nintendo - Nintendo Entertainment System": "NES",
+    "Nintendo - Super Nintendo Entertainment System": "SNES",
+    "Nintendo - Nintendo 64": "N64",
+    "Nintendo - Nintendo GameCube": "NGC",
+    "Nintendo - Nintendo Wii": "WII",
+    "Nintendo - Nintendo Wii U": "WIIU",
+    "Nintendo - Nintendo Switch": "NSW",
+    "Sony - PlayStation": "PS1",
+    "Sony - PlayStation 2": "PS2",
+    "Sony - PlayStation 3": "PS3",
+    "Sony - PlayStation 4": "PS4",
+    "Sony - PlayStation 5": "PS5",
+    "Sony - PlayStation Portable": "PSP",
+    "Sony - PlayStation Vita": "PSV",
+    "Microsoft - Xbox": "XBOX",
+    "Microsoft - Xbox 360": "X360",
+    "Microsoft - Xbox One": "XONE",
+    "Microsoft - Xbox Series X|S": "XSX",
+    "Sega - Master System": "SMS",
+    "Sega - Mega Drive - Genesis": "MD",
+    "Sega - Sega CD": "SCD",
+    "Sega - Sega 32X": "32X",
+    "Sega - Sega Saturn": "SAT",
+    "Sega - Dreamcast": "DC",
+    "Atari - 2600": "A2600",
+    "Atari - 5200": "A5200",
+    "Atari - 7800": "A7800",
+    "Atari - Jaguar": "JAG",
+    "Atari - Lynx": "LYNX",
+    "NEC - PC Engine - TurboGrafx-16": "PCE",
+    "NEC - PC Engine CD - TurboGrafx-CD": "PCE-CD",
+    "NEC - PC Engine SuperGrafx": "SGX",
+    "NEC - PC-FX": "PCFX",
+    "SNK - Neo Geo": "NEO",
+    "SNK - Neo Geo CD": "NGCD",
+    "SNK - Neo Geo Pocket": "NGP",
+    "SNK - Neo Geo Pocket Color": "NGPC",
+    "Bandai - WonderSwan": "WS",
+    "Bandai - WonderSwan Color": "WSC",
+    "Commodore - Amiga": "AMIGA",
+    "Commodore - Commodore 64": "C64",
+    "Commodore - Amiga CD32": "CD32",
+    "Apple - Apple II": "APPLE2",
+    "Apple - Macintosh": "MAC",
+    "IBM - PC": "PC",
+    "IBM - PC DOS": "DOS",
+    "IBM - PC Windows": "WIN",
+    "IBM - PC Linux": "LINUX",
+    "IBM - PC macOS": "MACOS",
+    "IBM - PC Android": "ANDROID",
+    "IBM - PC iOS": "IOS",
+    "IBM - PC Web": "WEB",
+    "IBM - PC VR": "VR",
+    "IBM - PC AR": "AR",
+    "IBM - PC Cloud": "CLOUD",
+    "IBM - PC Mobile": "MOBILE",
+    "IBM - PC Handheld": "HANDHELD",
+    "IBM - PC Console": "CONSOLE",
+    "IBM - PC Arcade": "ARCADE",
+    "IBM - PC Pinball": "PINBALL",
+    "IBM - PC Casino": "CASINO",
+    "IBM - PC Educational": "EDU",
+    "IBM - PC Sports": "SPORTS",
+    "IBM - PC Racing": "RACING",
+    "IBM - PC Fighting": "FIGHTING",
+    "IBM - PC Shooter": "SHOOTER",
+    "IBM - PC Adventure": "ADV",
+    "IBM - PC RPG": "RPG",
+    "IBM - PC Strategy": "STRAT",
+    "IBM - PC Simulation": "SIM",
+    "IBM - PC Puzzle": "PUZZLE",
+    "IBM - PC Platformer": "PLAT",
+    "IBM - PC Action": "ACTION",
+    "IBM - PC Horror": "HORROR",
+    "IBM - PC Comedy": "COMEDY",
+    "IBM - PC Drama": "DRAMA",
+    "IBM - PC Sci-Fi": "SCIFI",
+    "IBM - PC Fantasy": "FANTASY",
+    "IBM - PC Historical": "HIST",
+    "IBM - PC Military": "MIL",
+    "IBM - PC Western": "WESTERN",
+    "IBM - PC Crime": "CRIME",
+    "IBM - PC Mystery": "MYSTERY",
+    "IBM - PC Thriller": "THRILLER",
+    "IBM - PC Romance": "ROMANCE",
+    "IBM - PC Musical": "MUSICAL",
+    "IBM - PC Documentary": "DOC",
+    "IBM - PC Animation": "ANIM",
+    "IBM - PC Family": "FAMILY",
+    "IBM - PC Children": "CHILDREN",
+    "IBM - PC Teen": "TEEN",
+    "IBM - PC Adult": "ADULT",
+    "IBM - PC Mature": "MATURE",
+    "IBM - PC Everyone": "EVERYONE",
+    "IBM - PC Everyone 10+": "E10+",
+    "IBM - PC Teen 13+": "T13+",
+    "IBM - PC Mature 17+": "M17+",
+    "IBM - PC Adults Only 18+": "AO18+",
+    "IBM - PC Rating Pending": "RP",
+    "IBM - PC Not Rated": "NR",
+    "IBM - PC Unrated": "UR",
+    "IBM - PC Unknown": "UNK",
+    "IBM - PC Other": "OTHER"
+}
+
+# TOSEC country codes -> the region names No-Intro and Redump use
+TOSEC_REGIONS = {
+    "US": "USA", "CA": "USA",
+    "JP": "Japan",
+    "EU": "Europe", "GB": "Europe", "DE": "Europe", "FR": "Europe", "IT": "Europe", "ES": "Europe",
+    "NL": "Europe", "SE": "Europe", "NO": "Europe", "DK": "Europe", "FI": "Europe", "PL": "Europe",
+    "PT": "Europe", "AT": "Europe", "CH": "Europe", "BE": "Europe", "CZ": "Europe", "GR": "Europe",
+    "HU": "Europe", "IE": "Europe",
+    "AU": "Australia", "NZ": "Australia",
+    "KR": "Korea",
+    "BR": "Brazil",
+    "CN": "China", "HK": "Asia", "TW": "Asia",
+    "RU": "Russia",
+}
+
+# TOSEC development status values, spelled as No-Intro tags so avoid_tags applies to both
+TOSEC_STATUS = {
+    "alpha": "Alpha", "beta": "Beta", "preview": "Preview", "pre-release": "Proto", "proto": "Proto", "demo": "Demo",
+}
+
+# TOSEC dump flags and how much they count against a file when picking one
+# version per game; [!] marks a verified good dump
+TOSEC_FLAG_PENALTIES = {
+    "b": 20,   # bad dump
+    "o": 10,   # overdump
+    "h": 10,   # hack
+    "m": 10,   # modified
+    "p": 10,   # pirate
+    "t": 5,    # trainer
+    "cr": 5,   # cracked
+    "f": 2,    # fixed
+    "tr": 5,   # translation
+    "a": 1,    # alternate
+}
+TOSEC_VERIFIED_BONUS = 1
+
+TOSEC_NAME = re.compile(r'^(?P<title>.+?)\s*\((?P<year>(?:19|20)[0-9x]{2}(?:-[0-9x]{2}){0,2}|-)\)\((?P<publisher>[^)]*)\)(?P<rest>.*)$')
+TOSEC_VERSION = re.compile(r'\s+(v[0-9][0-9A-Za-z.]*|Rev [0-9A-Z]+)$')
+
+
+def archive_segments(url: str) -> List[str]:
+    """Get the path segments of an archive URL from the dataset folder on, or [] if it has none."""
+    parts = [urllib.parse.unquote(part) for part in urllib.parse.urlparse(url).path.split('/') if part]
+    for i, part in enumerate(parts):
+        if part in DATASET_FOLDERS:
+            return parts[i:]
+    return []
+
+
+def detect_dataset(url: str) -> Optional[str]:
+    """Get the dataset key ("redump", "no-intro", "tosec") of an archive URL."""
+    segments = archive_segments(url)
+    return DATASET_FOLDERS[segments[0]] if segments else None
+
+
+def platform_of(url: str, is_file=True) -> Optional[str]:
+    """Get the platform of an archive URL.
+
+    TOSEC platforms are "Manufacturer - System" from the first two folders, so
+    games, demos and disk-format subfolders all count as the same platform.
+    """
+    segments = archive_segments(url)
+    folders = segments[1:-1] if is_file else segments[1:]
+    if not segments or not folders:
+        return None
+    if DATASET_FOLDERS[segments[0]] == "tosec":
+        return " - ".join(folders[:2])
+    return folders[0]
+
+
+def strip_extension(filename: str) -> str:
+    return re.sub(r'\.[A-Za-z0-9]{1,4}$', '', filename)
+
+
+def title_from_filename(filename: str) -> str:
+    """Get a game's title from a ROM file name: the TOSEC title, or the name before its tags and extension."""
+    tosec = parse_tosec_name(filename)
+    title = tosec['title'] if tosec else re.sub(r'\s*[(\[].*$', '', strip_extension(filename))
+    return title.strip()
+
+
+def parse_tosec_name(filename: str) -> Optional[Dict]:
+    """Split a TOSEC file name into its fields, or None if it does not follow the convention."""
+    match = TOSEC_NAME.match(strip_extension(filename))
+    if not match:
+        return None
+
+    title = match.group('title')
+    version = TOSEC_VERSION.search(title)
+    info = {
+        "title": title[:version.start()] if version else title,
+        "version": version.group(1) if version else None,
+        "year": match.group('year'),
+        "publisher": match.group('publisher'),
+        "regions": [],
+        "languages": [],
+        "status": None,
+        "flags": [],
+        "verified": False,
+    }
+
+    for group in re.findall(r'\(([^)]*)\)', match.group('rest')):
+        codes = group.split('-')
+        if all(code in TOSEC_REGIONS for code in codes):
+            info["regions"].extend(codes)
+        elif all(re.fullmatch(r'[a-z]{2}', code) for code in codes):
+            info["languages"].extend(codes)
+        elif group.lower() in TOSEC_STATUS or group.lower().startswith('demo-'):
+            # Demos come as demo-playable, demo-rolling and so on
+            info["status"] = TOSEC_STATUS.get(group.lower(), "Demo")
+
+    for flag in re.findall(r'\[([^\]]*)\]', match.group('rest')):
+        if flag == '!':
+            info["verified"] = True
+            continue
+        code = re.match(r'(cr|tr|[a-z])(?![a-z])', flag)
+        if code:
+            info["flags"].append(code.group(1))
+
+    return info
+
+
+def tosec_tags(info: Dict) -> List[str]:
+    """Express a parsed TOSEC name as No-Intro style tags (regions, languages, status)."""
+    tags = sorted({TOSEC_REGIONS[code] for code in info["regions"]})
+    tags += [language.title() for language in info["languages"]]
+    if info["status"]:
+        tags.append(info["status"])
+    return tags
+
+
+def filename_tags(filename: str) -> List[str]:
+    """Get the tags of a file name, comma-separated groups split up, whatever its dataset's convention."""
+    info = parse_tosec_name(filename)
+    if info:
+        return tosec_tags(info)
+
+    tags = []
+    for group in re.findall(r'\(([^)]*)\)', filename):
+        tags.extend(tag.strip() for tag in group.split(','))
+    return tags
+
+
+def tosec_flag_score(filename: str) -> float:
+    """Score TOSEC dump flags: bad dumps, hacks and cracks count against a file, [!] for it."""
+    info = parse_tosec_name(filename)
+    if not info:
+        return 0
+    score = -sum(TOSEC_FLAG_PENALTIES.get(flag, 0) for flag in info["flags"])
+    return score + (TOSEC_VERIFIED_BONUS if info["verified"] else 0)
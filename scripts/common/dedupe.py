@@ -0,0 +1,150 @@
+#!/usr/bin/env python3
+"""
+Dedupe
+Finds byte-identical ROMs across the collection folders, e.g. the same set
+downloaded for two platforms or copied into a front-end's folder, and
+replaces the extra copies with links to one of them to reclaim the space.
+
+Only files of the same size are hashed (SHA1), so a pass over a large
+collection reads little. Files that are hardlinks of each other already take
+the space once and count as one; symlinks are never followed.
+
+    hardlink   the copies share the data; deleting one leaves the others,
+               but all must be on the same drive
+    symlink    the copies point at the kept file, across drives too; they
+               break if it is moved or deleted
+"""
+
+import os
+from pathlib import Path
+from typing import Callable, Dict, List, Optional
+
+from dat_index import hash_file
+from library_db import NON_DOWNLOAD_SUFFIXES
+from romignore import RomIgnore
+
+LINK_MODES = ("hardlink", "symlink")
+# library.dedupe, which the library scan follows: only report, or link the copies too
+SCAN_MODES = ("off", "report") + LINK_MODES
+TEMP_SUFFIX = ".dedupe.tmp"
+
+
+def candidate_files(folders: List[Path]) -> List[Path]:
+    """Get the regular files of the folders, each once, in folder order."""
+    files, seen = [], set()
+    ignore = RomIgnore()
+    for folder in folders:
+        for path in ignore.walk(folder):
+            if path.is_symlink() or path.name.endswith(NON_DOWNLOAD_SUFFIXES + (TEMP_SUFFIX,)):
+                continue
+            resolved = path.resolve()
+            if resolved not in seen:
+                seen.add(resolved)
+                files.append(path)
+    return files
+
+
+def find_duplicates(folders: List[Path], limits=None,
+                    advance: Callable[[Optional[str]], None] = lambda name: None) -> List[Dict]:
+    """Group identical files: {sha1, size, keep, copies, reclaimable} per group, most space first.
+
+    keep is the file that stays, the first found (folders in the order
+    given); copies are the others. reclaimable counts the copies that are
+    hardlinks of each other once.
+    """
+    by_size: Dict[int, List[Path]] = {}
+    for path in candidate_files(folders):
+        size = path.stat().st_size
+        if size:
+            by_size.setdefault(size, []).append(path)
+
+    groups = []
+    for size, paths in by_size.items():
+        # Hardlinks of one file are one file already
+        inodes: Dict[tuple, List[Path]] = {}
+        for path in paths:
+            stat = path.stat()
+            inodes.setdefault((stat.st_dev, stat.st_ino), []).append(path)
+        if len(inodes) < 2:
+            continue
+
+        by_hash: Dict[str, List[Path]] = {}
+        for inode_paths in inodes.values():
+            sha1 = hash_file(inode_paths[0], limits)["sha1"]
+            by_hash.setdefault(sha1, []).extend(inode_paths)
+            advance(inode_paths[0].name)
+        for sha1, same in by_hash.items():
+            distinct = {(path.stat().st_dev, path.stat().st_ino) for path in same}
+            if len(distinct) < 2:
+                continue
+            keep = same[0]
+            kept_inode = (keep.stat().st_dev, keep.stat().st_ino)
+            copies = [path for path in same[1:] if (path.stat().st_dev, path.stat().st_ino) != kept_inode]
+            groups.append({"sha1": sha1, "size": size, "keep": keep, "copies": copies,
+                           "reclaimable": size * (len(distinct) - 1)})
+    return sorted(groups, key=lambda group: group["reclaimable"], reverse=True)
+
+
+def link_copy(keep: Path, copy: Path, mode: str) -> None:
+    """Replace a copy with a link to the kept file, in one rename so the copy is never missing.
+
+    Raises OSError, e.g. when a hardlink would cross drives.
+    """
+    temp = copy.with_name(copy.name + TEMP_SUFFIX)
+    temp.unlink(missing_ok=True)
+    if mode == "hardlink":
+        os.link(keep, temp)
+    else:
+        os.symlink(keep.resolve(), temp)
+    try:
+        os.replace(temp, copy)
+    except OSError:
+        temp.unlink(missing_ok=True)
+        raise
+
+
+def link_duplicates(groups: List[Dict], mode: str, dry_run: bool = False) -> List[Dict]:
+    """Link every copy of each group to its kept file: {path, keep, size, status, error} per copy.
+
+    status is "linked", "would link" (dry_run), or "skipped" when a file
+    changed since it was hashed or the link could not be made.
+    """
+    results = []
+    for group in groups:
+        for copy in group["copies"]:
+            result = {"path": str(copy), "keep": str(group["keep"]), "size": group["size"], "status": "would link",
+                      "error": None}
+            results.append(result)
+            if dry_run:
+                continue
+            # A file rewritten since the pass must not be replaced by a link to different data
+            if not group["keep"].is_file() or copy.stat().st_size != group["size"] \
+                    or group["keep"].stat().st_size != group["size"]:
+                result.update(status="skipped", error="changed since it was hashed")
+                continue
+            try:
+                link_copy(group["keep"], copy, mode)
+                result["status"] = "linked"
+            except OSError as e:
+                result.update(status="skipped", error=str(e))
+    return results
+
+
+def dedupe_folders(folders: List[Path], mode: Optional[str] = None, dry_run: bool = False, limits=None,
+                   advance: Callable[[Optional[str]], None] = lambda name: None) -> Dict:
+    """Find the duplicates in the folders and, given a link mode, link them.
+
+    Returns {groups, copies, reclaimable, reclaimed, links}: the groups with
+    their paths as strings, the space the links would free, the space they
+    did free, and link_duplicates' result per copy ([] without a mode).
+    """
+    groups = find_duplicates(folders, limits, advance)
+    links = link_duplicates(groups, mode, dry_run) if mode else []
+    return {
+        "groups": [dict(group, keep=str(group["keep"]), copies=[str(copy) for copy in group["copies"]])
+                   for group in groups],
+        "copies": sum(len(group["copies"]) for group in groups),
+        "reclaimable": sum(group["reclaimable"] for group in groups),
+        "reclaimed": sum(link["size"] for link in links if link["status"] == "linked"),
+        "links": links,
+    }
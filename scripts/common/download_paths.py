@@ -0,0 +1,110 @@
+#!/usr/bin/env python3
+"""
+Download Paths
+Builds the destination of a download from the configured path template, so
+files are sorted into platform folders instead of landing in one directory.
+
+Template fields: {platform}, {platform_short} (e.g. "PS1", see
+PLATFORM_SHORT_NAMES; the platform itself when it has none), {dataset},
+{folder} (the archive folders from the dataset on, e.g.
+"TOSEC/Commodore/Amiga/Games/[ADF]"), {region}, {title}, {filename} or
+{name}, {ext}.
+
+downloads.path_template applies to every platform without its own template
+in downloads.platform_templates, which is keyed by platform folder ("Sony -
+PlayStation"), dataset and platform ("Redump/Sony - PlayStation") or short
+name ("PS1"):
+
+    {"PS1": "{dataset}/{platform_short}/{region}/{name}"}
+"""
+
+import hashlib
+import re
+import urllib.parse
+from pathlib import Path
+from typing import Dict, Optional
+
+from dat_index import parse_region
+from datasets import PLATFORM_SHORT_NAMES, archive_segments
+from library_db import platform_from_url
+
+DEFAULT_PATH_TEMPLATE = "{platform}/{filename}"
+
+# Characters that are not allowed in Windows file names
+UNSAFE_CHARS = re.compile(r'[<>:"/\\|?*\x00-\x1f]')
+
+
+def safe_component(text: str) -> str:
+    """Make text usable as a single path component."""
+    text = UNSAFE_CHARS.sub('_', text).strip().rstrip('.')
+    return text or '_'
+
+
+def dataset_from_url(url: str) -> Optional[str]:
+    """Get the dataset folder (e.g. "Redump", "No-Intro", "TOSEC") from an archive file URL."""
+    segments = archive_segments(url)
+    if segments:
+        return segments[0]
+    parts = urllib.parse.urlparse(url).path.rstrip('/').split('/')
+    return urllib.parse.unquote(parts[-3]) if len(parts) >= 3 and parts[-3] else None
+
+
+def platform_template(url: str, default: str, platform_templates: Optional[Dict[str, str]]) -> str:
+    """Get the path template for a download: its platform's in platform_templates, else default."""
+    if not platform_templates:
+        return default
+    platform = platform_from_url(url)
+    dataset = dataset_from_url(url)
+    keys = [key for key in (f"{dataset}/{platform}" if dataset and platform else None, platform,
+                            PLATFORM_SHORT_NAMES.get(platform or '')) if key]
+    templates = {key.strip('/').lower(): template for key, template in platform_templates.items()}
+    return next((templates[key.lower()] for key in keys if key.lower() in templates), default)
+
+
+def render_path(template: str, url: str, filename: str) -> Path:
+    """Fill in the path template for a download, relative to the downloads folder."""
+    stem = Path(filename).stem
+    platform = platform_from_url(url)
+    fields = {
+        'platform': platform or 'Unsorted',
+        'platform_short': PLATFORM_SHORT_NAMES.get(platform, platform) if platform else 'Unsorted',
+        'dataset': dataset_from_url(url) or 'Unknown',
+        'folder': "/".join(archive_segments(url)[:-1]) or platform or 'Unsorted',
+        'region': parse_region(filename) or 'Unknown',
+        'title': stem.split(' (')[0].strip(),
+        'filename': filename,
+        'name': filename,
+        'ext': Path(filename).suffix
+    }
+
+    rendered = template.format(**fields)
+    components = [safe_component(part) for part in re.split(r'[/\\]', rendered) if part.strip()]
+    return Path(*components) if components else Path(safe_component(filename))
+
+
+def resolve_destination(download_dir, template: str, url: str, filename: str, library=None) -> Path:
+    """Get where a download should be saved.
+
+    A path already recorded in the library for another source URL is a
+    collision (e.g. the same title from two regions under a {title}
+    template); the file then gets its region, or failing that a hash of its
+    URL, appended so the same URL always resolves to the same path.
+    """
+    target = Path(download_dir) / render_path(template or DEFAULT_PATH_TEMPLATE, url, filename)
+
+    def is_free(path: Path) -> bool:
+        if not path.exists() or library is None:
+            return True
+        entry = library.get_entry(path)
+        return entry is None or entry['source_url'] in (None, url)
+
+    if is_free(target):
+        return target
+
+    region = parse_region(filename)
+    suffixes = ([f"[{region}]"] if region else []) + [f"[{hashlib.sha1(url.encode('utf-8')).hexdigest()[:8]}]"]
+    for suffix in suffixes:
+        candidate = target.with_name(f"{target.stem} {safe_component(suffix)}{target.suffix}")
+        if is_free(candidate):
+            return candidate
+    return candidate
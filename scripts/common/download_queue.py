@@ -0,0 +1,152 @@
+#!/usr/bin/env python3
+"""
+Download Queue
+The download queue is a text file with one entry per line: a file URL added
+by a browser, or a game title to match at download time. An entry may carry
+notes after " # ", such as the compatibility warnings found when it was
+queued:
+
+    https://.../Sonic%20the%20Hedgehog%20%28Europe%29.zip # PAL-only release (Europe) but your setup is NTSC
+
+A queue can be exported to a JSON file with every entry resolved to its URL,
+platform and the hashes the DATs expect, and run on another machine as it is.
+
+Scripts edit the queue through open_queue(), which goes through the daemon
+while one is running, so a browser adding entries and a downloader removing
+the ones it finished never overwrite each other.
+"""
+
+import json
+from datetime import datetime
+from pathlib import Path
+from typing import Dict, Iterable, List, Tuple
+
+from daemon_ipc import RemoteObject, connect
+
+NOTE_SEPARATOR = " # "
+
+
+def parse_line(line: str) -> Tuple[str, List[str]]:
+    """Split a queue line into its item and notes."""
+    item, _, notes = line.strip().partition(NOTE_SEPARATOR)
+    return item.strip(), [note.strip() for note in notes.split(';') if note.strip()]
+
+
+def format_line(item: str, notes: List[str] = ()) -> str:
+    # Notes are one line each way, so separators inside them are replaced
+    notes = [note.replace(';', ',').replace('\n', ' ') for note in notes]
+    return item + (NOTE_SEPARATOR + "; ".join(notes) if notes else "")
+
+
+def read_queue(path) -> List[Tuple[str, List[str]]]:
+    """Read a queue file as (item, notes) pairs, skipping blank lines."""
+    path = Path(path)
+    if not path.exists():
+        return []
+    with open(path, 'r', encoding='utf-8') as f:
+        return [parse_line(line) for line in f if line.strip()]
+
+
+def write_queue(path, entries: Iterable[Tuple[str, List[str]]]):
+    """Write (item, notes) pairs to a queue file; an empty queue removes the file."""
+    path = Path(path)
+    lines = [format_line(item, notes) + "\n" for item, notes in entries]
+    if not lines:
+        path.unlink(missing_ok=True)
+        return
+    with open(path, 'w', encoding='utf-8') as f:
+        f.writelines(lines)
+
+
+class QueueFile:
+    """Edits of queue files, each a whole read-change-write. The daemon runs them one at a time."""
+
+    def read(self, path) -> List[Tuple[str, List[str]]]:
+        return read_queue(path)
+
+    def add(self, path, entries: List[Tuple[str, List[str]]]) -> int:
+        """Append the entries whose item is not queued yet. Returns how many were added."""
+        queued = read_queue(path)
+        items = {item for item, _ in queued}
+        added = []
+        for item, notes in entries:
+            if item not in items:
+                items.add(item)
+                added.append((item, list(notes)))
+        if added:
+            write_queue(path, queued + added)
+        return len(added)
+
+    def remove(self, path, items: List[str]) -> int:
+        """Remove entries by item, leaving any added since they were read. Returns how many were removed."""
+        queued = read_queue(path)
+        remaining = [(item, notes) for item, notes in queued if item not in set(items)]
+        write_queue(path, remaining)
+        return len(queued) - len(remaining)
+
+    def replace(self, path, entries: List[Tuple[str, List[str]]]):
+        write_queue(path, entries)
+
+
+def open_queue(config):
+    """Get the queue editor to use: the daemon's while one is running, otherwise a local one.
+
+    Pass queue file paths to it as absolute paths, since the daemon runs elsewhere.
+    """
+    client = connect(config)
+    return RemoteObject(client, "queue", config) if client else QueueFile()
+
+
+# Portable queue files: downloads pinned to their URL and expected hashes, which
+# another rom-browser instance runs as they are instead of matching titles again
+EXPORT_FORMAT = "rom-browser-queue"
+EXPORT_VERSION = 1
+HASH_FIELDS = ("size", "crc32", "md5", "sha1")
+
+
+def write_export(path, entries: List[Dict], archive_url: str):
+    """Write entries ({url, filename, platform, dataset, size, validator, title, notes, roms, explanation}) to a queue file."""
+    document = {
+        "format": EXPORT_FORMAT,
+        "version": EXPORT_VERSION,
+        "exported": datetime.now().isoformat(timespec='seconds'),
+        "archive_url": archive_url,
+        "entries": entries,
+    }
+    with open(path, 'w', encoding='utf-8') as f:
+        json.dump(document, f, indent=2, ensure_ascii=False)
+        f.write('\n')
+
+
+def read_export(path) -> List[Dict]:
+    """Read the entries of an exported queue file. Raises ValueError when it is not one this version understands."""
+    try:
+        with open(path, 'r', encoding='utf-8') as f:
+            document = json.load(f)
+    except json.JSONDecodeError as e:
+        raise ValueError(f"{path} is not an exported queue: {e}")
+
+    if not isinstance(document, dict) or document.get("format") != EXPORT_FORMAT:
+        raise ValueError(f"{path} is not an exported queue")
+    if document.get("version", 0) > EXPORT_VERSION:
+        raise ValueError(f"{path} was exported by a newer version (format {document['version']})")
+
+    entries = document.get("entries") or []
+    for i, entry in enumerate(entries, 1):
+        if not entry.get("url") or not entry.get("filename"):
+            raise ValueError(f"Entry {i} of {path} has no url or filename")
+        entry.setdefault("notes", [])
+        entry.setdefault("roms", [])
+    return entries
+
+
+def missing_roms(expected: List[Dict], actual: List[Dict]) -> List[str]:
+    """Get the names of expected files that none of the hashed files match, by the strongest hash both have."""
+    def matches(rom: Dict, hashes: Dict) -> bool:
+        for field in ("sha1", "md5"):
+            if rom.get(field):
+                return rom[field] == hashes.get(field)
+        return bool(rom.get("crc32")) and rom["crc32"] == hashes.get("crc32") \
+            and rom.get("size") in (None, hashes.get("size"))
+
+    return [rom.get("name") or "?" for rom in expected if not any(matches(rom, hashes) for hashes in actual)]
@@ -0,0 +1,168 @@
+#!/usr/bin/env python3
+"""
+Emulators
+Builds emulator command lines and checks a platform's emulator setup by
+starting it with a known-good test ROM. Each platform's default emulator is
+set under emulators in rom_browser.json:
+
+    "emulators": {
+        "Sony - PlayStation": {
+            "path": "C:/Emulators/DuckStation/duckstation-qt.exe",
+            "arguments": ["-fullscreen", "{rom}"],
+            "test_rom": "C:/ROMs/Test/240p Test Suite.cue",
+            "bios": ["C:/Emulators/DuckStation/bios/scph5501.bin"]
+        }
+    }
+
+arguments is optional; without it the usual arguments for known emulators
+are used. {rom} is replaced by the ROM file and {rom_name} by its name
+without the extension. bios lists files the emulator cannot start without.
+"""
+
+import argparse
+import json
+import os
+import subprocess
+import sys
+import tempfile
+from pathlib import Path
+from typing import Dict, List
+
+from rom_config import REPO_ROOT, RomConfig, add_config_argument
+from script_errors import install_error_reporting
+
+# Arguments for emulators whose executable name contains the key; others just get the ROM
+EMULATOR_ARGUMENTS = [
+    ('pcsx2', ['--fullscreen', '--nogui', '{rom}']),
+    ('epsxe', ['-nogui', '-loadbin', '{rom}']),
+    ('dolphin', ['-e', '{rom}']),
+    ('mame', ['{rom_name}']),
+    ('winuae', ['-f', '{rom}']),
+]
+
+# An emulator still running after this long is taken to have started properly
+TEST_LAUNCH_SECONDS = 5.0
+
+
+def emulator_arguments(emulator_path, rom_file, arguments=None) -> List[str]:
+    """Get the arguments that make an emulator run a ROM, from arguments templates or the known defaults."""
+    if arguments is None:
+        emulator_name = Path(emulator_path).name.lower()
+        arguments = next((args for name, args in EMULATOR_ARGUMENTS if name in emulator_name), ['{rom}'])
+    rom_file = Path(rom_file)
+    return [arg.replace('{rom}', str(rom_file)).replace('{rom_name}', rom_file.stem) for arg in arguments]
+
+
+def configured_path(value) -> Path:
+    path = Path(value).expanduser()
+    return path if path.is_absolute() else REPO_ROOT / path
+
+
+def emulator_problems(setup: Dict) -> List[str]:
+    """Check that an emulator setup's files are all in place. Returns what is wrong."""
+    problems = []
+    if not setup.get('path'):
+        problems.append("No emulator path is set")
+    else:
+        path = configured_path(setup['path'])
+        if not path.is_file():
+            problems.append(f"Emulator not found: {path}")
+        elif os.name != 'nt' and not os.access(path, os.X_OK):
+            problems.append(f"Emulator is not executable: {path}")
+
+    if not setup.get('test_rom'):
+        problems.append("No test ROM is set")
+    elif not configured_path(setup['test_rom']).is_file():
+        problems.append(f"Test ROM not found: {configured_path(setup['test_rom'])}")
+
+    for bios in setup.get('bios') or []:
+        if not configured_path(bios).is_file():
+            problems.append(f"BIOS file not found: {configured_path(bios)}")
+    return problems
+
+
+def test_launch(config: RomConfig, platform: str, wait_seconds: float = TEST_LAUNCH_SECONDS) -> Dict:
+    """Start a platform's emulator with its test ROM to check the setup works.
+
+    Returns {"platform", "ok", "command", "message"}. An emulator that starts is
+    left running, so the user can see the test ROM play.
+    """
+    setup = (config.get('emulators', {}) or {}).get(platform)
+    result = {"platform": platform, "ok": False, "command": None}
+    if not setup:
+        result["message"] = f"No emulator is configured for {platform}"
+        return result
+
+    problems = emulator_problems(setup)
+    if problems:
+        result["message"] = "; ".join(problems)
+        return result
+
+    emulator = configured_path(setup['path'])
+    command = [str(emulator)] + emulator_arguments(emulator, configured_path(setup['test_rom']), setup.get('arguments'))
+    result["command"] = command
+
+    # A file rather than a pipe, so a chatty emulator never blocks on a full pipe
+    with tempfile.TemporaryFile() as errors:
+        try:
+            process = subprocess.Popen(command, cwd=emulator.parent, stdin=subprocess.DEVNULL,
+                                       stdout=subprocess.DEVNULL, stderr=errors)
+        except OSError as e:
+            result["message"] = f"Could not start the emulator: {e}"
+            return result
+        try:
+            exit_code = process.wait(timeout=wait_seconds)
+        except subprocess.TimeoutExpired:
+            result["ok"] = True
+            result["message"] = "The emulator started and is running the test ROM"
+            return result
+
+        errors.seek(0)
+        output = errors.read().decode('utf-8', errors='replace').strip().splitlines()[-5:]
+
+    result["message"] = (f"The emulator closed after less than {wait_seconds:g} seconds with exit code {exit_code}"
+                         + (f": {' / '.join(output)}" if output else ""))
+    return result
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Check emulator setups')
+    add_config_argument(parser)
+    subparsers = parser.add_subparsers(dest='command', required=True)
+
+    subparsers.add_parser('list', help='Show each platform\'s emulator and any problems with its files')
+    test_parser = subparsers.add_parser('test', help='Launch a platform\'s emulator with its test ROM')
+    test_parser.add_argument('platform', help='Platform name, e.g. "Sony - PlayStation"')
+    for subparser in subparsers.choices.values():
+        subparser.add_argument('--json', action='store_true', help='Print JSON')
+
+    args = parser.parse_args()
+    config = RomConfig()
+
+    if args.command == 'list':
+        setups = config.get('emulators', {}) or {}
+        entries = [{"platform": platform, "path": setup.get('path'), "test_rom": setup.get('test_rom'),
+                    "problems": emulator_problems(setup)} for platform, setup in sorted(setups.items())]
+        if args.json:
+            print(json.dumps(entries))
+            return
+        if not entries:
+            print("No emulators are configured; add them under emulators in the settings")
+        for entry in entries:
+            status = "; ".join(entry['problems']) or "ready"
+            print(f"{entry['platform']}: {entry['path']} ({status})")
+        return
+
+    result = test_launch(config, args.platform)
+    # With --json a failed launch is still a result to show, not an error
+    if args.json:
+        print(json.dumps(result))
+        return
+    print(result['message'])
+    if not result['ok']:
+        sys.exit(1)
+
+
+if __name__ == "__main__":
+    install_error_reporting()
+    main()
@@ -0,0 +1,173 @@
+#!/usr/bin/env python3
+"""
+Filter Presets
+Named, shareable rules for picking the essential files of a platform folder,
+applied when queueing a platform or matching a queue template. A preset is a
+JSON file in paths.filter_presets (config/filter_presets/ ships curated ones):
+
+    {
+      "name": "usa-retail",
+      "description": "Licensed USA retail releases",
+      "platforms": [],                       archive folders it is meant for; empty for any
+      "regions": ["USA", "World"],           keep only these release regions; empty for any
+      "exclude_tags": ["Unl", "Beta"],       drop files with these tags, e.g. "(Beta 2)"
+      "exclude_patterns": ["^NFL "],         drop file names matching these regexes (ignoring case)
+      "include_patterns": ["\\[T-En"],       keep these even when a rule above drops them
+      "one_per_game": false                  keep one file per title (1G1R)
+    }
+
+Several presets combine: a file has to pass the rules of all of them, unless
+an include pattern of any of them keeps it, so "usa-retail" plus
+"fan-translations" is USA releases and English translations of the rest.
+
+Which presets a platform uses when none are named is set in rom_browser.json:
+
+    "filter_presets": {
+      "default": ["usa-retail"],
+      "platforms": {"Nintendo - Super Nintendo Entertainment System": ["usa-retail", "fan-translations"]}
+    }
+"""
+
+import json
+import re
+from pathlib import Path
+from typing import Callable, Dict, List, Optional
+
+from compatibility import release_regions
+from datasets import filename_tags, parse_tosec_name, strip_extension
+
+# Multi-disc games keep every disc when one release per game is picked
+DISC_PATTERN = re.compile(r'\(((?:Disc|Disk|Side) [^)]+)\)', re.IGNORECASE)
+
+# Field -> type every preset file is checked against
+PRESET_FIELDS = {
+    "name": str,
+    "description": str,
+    "platforms": list,
+    "regions": list,
+    "exclude_tags": list,
+    "exclude_patterns": list,
+    "include_patterns": list,
+    "one_per_game": bool,
+}
+
+
+def preset_file_name(name: str) -> str:
+    return f"{re.sub(r'[^A-Za-z0-9._-]+', '_', name)}.json"
+
+
+def validate_preset(preset) -> Dict:
+    """Check a preset read from a file, filling in the optional fields. Raises ValueError."""
+    if not isinstance(preset, dict) or not preset.get('name'):
+        raise ValueError("a preset is a JSON object with at least a name")
+    for field, value in preset.items():
+        if field in PRESET_FIELDS and not isinstance(value, PRESET_FIELDS[field]):
+            raise ValueError(f"{preset['name']}: {field} must be a {PRESET_FIELDS[field].__name__}")
+    for field in ('exclude_patterns', 'include_patterns'):
+        for pattern in preset.get(field, []):
+            try:
+                re.compile(pattern)
+            except re.error as e:
+                raise ValueError(f"{preset['name']}: invalid pattern {pattern!r} in {field}: {e}")
+
+    defaults = {field: kind() for field, kind in PRESET_FIELDS.items()}
+    return {**defaults, **preset}
+
+
+def game_title(filename: str) -> str:
+    """Get the title a file shares with its other releases: the name without tags, case folded.
+
+    Discs and sides stay part of it, so a game keeps all of them.
+    """
+    tosec = parse_tosec_name(filename)
+    title = tosec['title'] if tosec else strip_extension(filename).split(' (')[0].split(' [')[0]
+    part = DISC_PATTERN.search(filename)
+    return f"{title.strip()} ({part.group(1)})".casefold() if part else title.strip().casefold()
+
+
+def has_tag(tags: List[str], tag: str) -> bool:
+    """Whether a tag is there, also with a suffix ("Beta" matches "Beta 2")."""
+    tag = tag.lower()
+    return any(t.lower() == tag or t.lower().startswith(tag + ' ') for t in tags)
+
+
+class FilterPresets:
+    def __init__(self, config):
+        self.config = config
+        self.directory = config.get_path('paths.filter_presets')
+
+    def path(self, name: str) -> Path:
+        return self.directory / preset_file_name(name)
+
+    def list_presets(self, platform: Optional[str] = None) -> List[Dict]:
+        """Get the saved presets, only those meant for a platform if one is given."""
+        if not self.directory.exists():
+            return []
+        presets = [self.load(path.stem) for path in sorted(self.directory.glob('*.json'))]
+        return [p for p in presets if not platform or not p['platforms'] or platform in p['platforms']]
+
+    def load(self, name: str) -> Dict:
+        path = self.path(name)
+        if not path.exists():
+            raise ValueError(f"No filter preset named {name}")
+        with open(path, 'r', encoding='utf-8') as f:
+            return validate_preset(json.load(f))
+
+    def save(self, preset: Dict) -> Path:
+        self.config.ensure_writable("save a filter preset")
+        preset = validate_preset(preset)
+        self.directory.mkdir(parents=True, exist_ok=True)
+        path = self.path(preset['name'])
+        with open(path, 'w', encoding='utf-8') as f:
+            json.dump(preset, f, indent=2)
+        return path
+
+    def import_file(self, path: Path) -> Dict:
+        """Save a preset file someone shared under its own name."""
+        with open(path, 'r', encoding='utf-8') as f:
+            preset = validate_preset(json.load(f))
+        self.save(preset)
+        return preset
+
+    def for_platform(self, platform: Optional[str]) -> List[Dict]:
+        """Get the presets configured for a platform, or the default ones."""
+        configured = self.config.get('filter_presets.platforms', {}) or {}
+        names = configured.get(platform) if platform in configured else self.config.get('filter_presets.default', [])
+        return [self.load(name) for name in names or []]
+
+
+def keep_file(filename: str, presets: List[Dict]) -> bool:
+    """Whether a file passes every preset's rules, or an include pattern keeps it anyway."""
+    if any(re.search(pattern, filename, re.IGNORECASE) for p in presets for pattern in p['include_patterns']):
+        return True
+
+    tags = filename_tags(filename)
+    regions = release_regions(filename)
+    for preset in presets:
+        if preset['regions'] and not set(regions) & set(preset['regions']):
+            return False
+        if any(has_tag(tags, tag) for tag in preset['exclude_tags']):
+            return False
+        if any(re.search(pattern, filename, re.IGNORECASE) for pattern in preset['exclude_patterns']):
+            return False
+    return True
+
+
+def apply_presets(files: List[str], presets: List[Dict],
+                  pick_best: Optional[Callable[[str, List[str]], Optional[str]]] = None) -> List[str]:
+    """Filter file names with presets, keeping their order.
+
+    When a preset asks for one file per game, pick_best(title, files) chooses
+    among the releases of a title (the downloader's select_best_match); without
+    it the first one listed is kept.
+    """
+    kept = [f for f in files if keep_file(f, presets)]
+    if not any(p['one_per_game'] for p in presets):
+        return kept
+
+    games: Dict[str, List[str]] = {}
+    for filename in kept:
+        games.setdefault(game_title(filename), []).append(filename)
+    best = {title: (pick_best(title, releases) if pick_best else None) or releases[0]
+            for title, releases in games.items()}
+    return [f for f in kept if best[game_title(f)] == f]
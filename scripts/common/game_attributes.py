@@ -0,0 +1,142 @@
+#!/usr/bin/env python3
+"""
+Game Attributes
+The genres, platforms, developers and publishers of the games in games.db.
+Each value is a row of its own in a table keyed by game id, in the order the
+provider listed them, with an index on the value, so filtering by genre or
+joining on a platform is an index lookup instead of parsing a JSON list for
+every game:
+
+    game_genres       (game_id, position, genre)
+    game_platforms    (game_id, position, platform)
+    game_developers   (game_id, position, developer)
+    game_publishers   (game_id, position, publisher)
+
+Older databases kept these as JSON lists in TEXT columns of games; they are
+moved over by migrate_json_columns() the first time the metadata downloader
+opens the database. Until then the readers here fall back to those columns.
+"""
+
+import json
+import sqlite3
+from typing import Dict, Iterable, List, Optional
+
+# Field name in metadata dicts -> (table, value column)
+ATTRIBUTE_TABLES = {
+    'genres': ('game_genres', 'genre'),
+    'platforms': ('game_platforms', 'platform'),
+    'developer': ('game_developers', 'developer'),
+    'publisher': ('game_publishers', 'publisher'),
+}
+
+# ALTER TABLE ... DROP COLUMN needs this SQLite version; older ones just empty the columns
+DROP_COLUMN_VERSION = (3, 35, 0)
+
+
+def create_attribute_tables(conn: sqlite3.Connection):
+    for table, column in ATTRIBUTE_TABLES.values():
+        conn.execute(f'''
+            CREATE TABLE IF NOT EXISTS {table} (
+                game_id INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                {column} TEXT NOT NULL,
+                PRIMARY KEY (game_id, position)
+            )
+        ''')
+        conn.execute(f'CREATE INDEX IF NOT EXISTS idx_{table}_{column} ON {table}({column} COLLATE NOCASE)')
+
+
+def has_attribute_tables(conn: sqlite3.Connection) -> bool:
+    table = ATTRIBUTE_TABLES['genres'][0]
+    return conn.execute("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?", (table,)).fetchone() is not None
+
+
+def json_columns(conn: sqlite3.Connection) -> List[str]:
+    """Get the JSON list columns an older database still has in games."""
+    existing = {row[1] for row in conn.execute('PRAGMA table_info(games)')}
+    return [field for field in ATTRIBUTE_TABLES if field in existing]
+
+
+def parse_list(value) -> List[str]:
+    if not value:
+        return []
+    try:
+        values = json.loads(value)
+    except (TypeError, ValueError):
+        return []
+    if isinstance(values, str):
+        values = [values]
+    return [str(v) for v in values if v] if isinstance(values, list) else []
+
+
+def set_attributes(conn: sqlite3.Connection, game_id: int, values: Dict[str, Iterable[str]]):
+    """Replace a game's values for the given fields, e.g. {"genres": ["RPG"]}."""
+    for field, field_values in values.items():
+        table, column = ATTRIBUTE_TABLES[field]
+        conn.execute(f'DELETE FROM {table} WHERE game_id = ?', (game_id,))
+        conn.executemany(f'INSERT INTO {table} (game_id, position, {column}) VALUES (?, ?, ?)',
+                         [(game_id, i, value) for i, value in enumerate(v for v in field_values or [] if v)])
+
+
+def delete_attributes(conn: sqlite3.Connection, game_name: str):
+    """Remove every value of a game, e.g. before its row is replaced and gets a new id."""
+    for table, _ in ATTRIBUTE_TABLES.values():
+        conn.execute(f'DELETE FROM {table} WHERE game_id IN (SELECT id FROM games WHERE name = ?)', (game_name,))
+
+
+def get_attributes(conn: sqlite3.Connection, game_ids: Optional[List[int]] = None) -> Dict[int, Dict[str, List[str]]]:
+    """Get the values of games by id, every field present even when empty. Without ids, of every game."""
+    if game_ids is not None and not game_ids:
+        return {}
+    where = f" WHERE id IN ({','.join('?' * len(game_ids))})" if game_ids is not None else ""
+    ids = [row[0] for row in conn.execute(f'SELECT id FROM games{where}', game_ids or [])]
+    attributes = {game_id: {field: [] for field in ATTRIBUTE_TABLES} for game_id in ids}
+
+    legacy = json_columns(conn)
+    if legacy and not has_attribute_tables(conn):
+        # Not migrated yet, e.g. read by the GUI before the downloader ran
+        for row in conn.execute(f'SELECT id, {", ".join(legacy)} FROM games{where}', game_ids or []):
+            for field, value in zip(legacy, row[1:]):
+                attributes[row[0]][field] = parse_list(value)
+        return attributes
+
+    for field, (table, column) in ATTRIBUTE_TABLES.items():
+        where_game = f" WHERE game_id IN ({','.join('?' * len(game_ids))})" if game_ids is not None else ""
+        rows = conn.execute(f'SELECT game_id, {column} FROM {table}{where_game} ORDER BY game_id, position',
+                            game_ids or [])
+        for game_id, value in rows:
+            if game_id in attributes:
+                attributes[game_id][field].append(value)
+    return attributes
+
+
+def game_ids_with(conn: sqlite3.Connection, field: str, value: str) -> List[int]:
+    """Get the ids of the games with a value, e.g. every game with the genre "RPG" (ignoring case)."""
+    table, column = ATTRIBUTE_TABLES[field]
+    return [row[0] for row in conn.execute(
+        f'SELECT DISTINCT game_id FROM {table} WHERE {column} = ? COLLATE NOCASE', (value,))]
+
+
+def migrate_json_columns(conn: sqlite3.Connection) -> int:
+    """Move the JSON list columns of an older games table into the attribute tables.
+
+    Returns how many games were migrated. Safe to call on every start; it only
+    does work while the old columns are there.
+    """
+    create_attribute_tables(conn)
+    legacy = json_columns(conn)
+    if not legacy:
+        return 0
+
+    # Where the columns could only be emptied, rows already moved have nothing left in them
+    rows = conn.execute(f'SELECT id, {", ".join(legacy)} FROM games WHERE '
+                        + ' OR '.join(f'{field} IS NOT NULL' for field in legacy)).fetchall()
+    for row in rows:
+        set_attributes(conn, row[0], {field: parse_list(value) for field, value in zip(legacy, row[1:])})
+
+    for field in legacy:
+        if sqlite3.sqlite_version_info >= DROP_COLUMN_VERSION:
+            conn.execute(f'ALTER TABLE games DROP COLUMN {field}')
+        elif rows:
+            conn.execute(f'UPDATE games SET {field} = NULL')
+    return len(rows)
@@ -0,0 +1,64 @@
+#!/usr/bin/env python3
+"""
+HTTP Client
+One requests session per process, shared by the sources, mirrors, retry
+policies and metadata providers, so requests to the same host reuse pooled
+connections instead of opening a new one every time. Crawling a platform
+folder or scraping a batch of titles then pays for the TCP and TLS handshakes
+once per host.
+
+The session sends the standard User-Agent, accepts gzip, applies the
+configured proxy and gives every request that sets no timeout the default one:
+
+    network.user_agent          sent with every request
+    network.timeout_seconds     connect and read timeout of requests without one
+    network.max_connections     pooled connections kept open per host
+
+Retries stay with RetryPolicy, which uses this session when built with
+RetryPolicy.from_config.
+"""
+
+from typing import Dict, Tuple
+
+import requests
+from requests.adapters import HTTPAdapter
+
+from proxy import apply_proxy_settings
+
+DEFAULT_USER_AGENT = 'Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36'
+DEFAULT_TIMEOUT = 30.0
+DEFAULT_MAX_CONNECTIONS = 10
+
+
+class DefaultTimeoutAdapter(HTTPAdapter):
+    """Gives requests that were sent without a timeout the configured one, so none can hang forever."""
+
+    def __init__(self, timeout: float, **kwargs):
+        self.timeout = timeout
+        super().__init__(**kwargs)
+
+    def send(self, request, timeout=None, **kwargs):
+        return super().send(request, timeout=self.timeout if timeout is None else timeout, **kwargs)
+
+
+# One session per distinct network setup; in practice one per process
+_sessions: Dict[Tuple, requests.Session] = {}
+
+
+def http_session(config) -> requests.Session:
+    """Get the shared, configured session. Raises ValueError for an unusable proxy URL."""
+    user_agent = config.get('network.user_agent') or DEFAULT_USER_AGENT
+    timeout = float(config.get('network.timeout_seconds', DEFAULT_TIMEOUT) or DEFAULT_TIMEOUT)
+    max_connections = max(int(config.get('network.max_connections', DEFAULT_MAX_CONNECTIONS) or 1), 1)
+    # requests reads the proxy from the environment on every request
+    apply_proxy_settings(config)
+
+    key = (user_agent, timeout, max_connections)
+    if key not in _sessions:
+        session = requests.Session()
+        session.headers.update({'User-Agent': user_agent, 'Accept-Encoding': 'gzip, deflate'})
+        adapter = DefaultTimeoutAdapter(timeout, pool_connections=max_connections, pool_maxsize=max_connections)
+        session.mount('http://', adapter)
+        session.mount('https://', adapter)
+        _sessions[key] = session
+    return _sessions[key]
@@ -0,0 +1,63 @@
+#!/usr/bin/env python3
+"""
+Image Size
+Reads the pixel dimensions of PNG, JPEG, GIF, WebP and BMP files from their
+headers, without needing Pillow.
+"""
+
+import struct
+from pathlib import Path
+from typing import Optional, Tuple
+
+# JPEG start-of-frame markers, which carry the dimensions
+JPEG_FRAME_MARKERS = {0xC0, 0xC1, 0xC2, 0xC3, 0xC5, 0xC6, 0xC7, 0xC9, 0xCA, 0xCB, 0xCD, 0xCE, 0xCF}
+
+
+def jpeg_size(f) -> Optional[Tuple[int, int]]:
+    f.seek(2)
+    while True:
+        marker = f.read(2)
+        if len(marker) < 2 or marker[0] != 0xFF:
+            return None
+        if marker[1] in (0xD8, 0x01) or 0xD0 <= marker[1] <= 0xD7:
+            continue
+        length = f.read(2)
+        if len(length) < 2:
+            return None
+        if marker[1] in JPEG_FRAME_MARKERS:
+            frame = f.read(5)
+            if len(frame) < 5:
+                return None
+            height, width = struct.unpack('>HH', frame[1:5])
+            return width, height
+        f.seek(struct.unpack('>H', length)[0] - 2, 1)
+
+
+def image_size(path) -> Optional[Tuple[int, int]]:
+    """Get (width, height) of an image file, or None when it is missing or not a known format."""
+    try:
+        with open(Path(path), 'rb') as f:
+            header = f.read(30)
+            if header.startswith(b'\x89PNG\r\n\x1a\n') and len(header) >= 24:
+                return struct.unpack('>II', header[16:24])
+            if header[:6] in (b'GIF87a', b'GIF89a'):
+                return struct.unpack('<HH', header[6:10])
+            if header.startswith(b'BM') and len(header) >= 26:
+                width, height = struct.unpack('<ii', header[18:26])
+                return width, abs(height)
+            if header.startswith(b'RIFF') and header[8:12] == b'WEBP':
+                kind = header[12:16]
+                if kind == b'VP8 ':
+                    width, height = struct.unpack('<HH', header[26:30])
+                    return width & 0x3FFF, height & 0x3FFF
+                if kind == b'VP8L':
+                    bits = int.from_bytes(header[21:25], 'little')
+                    return (bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1
+                if kind == b'VP8X':
+                    return int.from_bytes(header[24:27], 'little') + 1, int.from_bytes(header[27:30], 'little') + 1
+                return None
+            if header.startswith(b'\xff\xd8'):
+                return jpeg_size(f)
+    except (OSError, struct.error):
+        return None
+    return None
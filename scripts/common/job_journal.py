@@ -0,0 +1,164 @@
+#!/usr/bin/env python3
+"""
+Job Journal
+Records the jobs in flight (downloads, scans, conversions), so a crash no
+longer leaves their half-done work behind unnoticed. Each running job has a
+file in the jobs folder of the data directory:
+
+    jobs/<id>.json   {"id", "kind", "label", "pid", "started", "updated",
+                      "files": [...], "state": {...}}
+
+files are the temporary and partial files the job is writing, and state is
+what it needs to be resumed (a download's URL and destination, the game a
+scan got to). A job that ends, whether it succeeded or failed, removes its
+file; a file whose process is gone belongs to an interrupted job, which
+job_recovery.py cleans up or resumes on the next start.
+"""
+
+import json
+import os
+import sys
+import threading
+import uuid
+from contextlib import contextmanager
+from datetime import datetime
+from pathlib import Path
+from typing import Dict, Iterator, List, Optional
+
+from rom_config import RomConfig
+
+JOB_SUFFIX = ".json"
+# A journal file being recovered; renaming to it claims the job, so two recoveries never handle one job
+RECOVERING_SUFFIX = ".recovering"
+
+
+def process_running(pid: int) -> bool:
+    """Check whether a process with the id still runs."""
+    if pid <= 0:
+        return False
+    if sys.platform == "win32":
+        import ctypes
+        process = ctypes.windll.kernel32.OpenProcess(0x1000, False, pid)  # PROCESS_QUERY_LIMITED_INFORMATION
+        if not process:
+            return False
+        code = ctypes.c_ulong()
+        ctypes.windll.kernel32.GetExitCodeProcess(process, ctypes.byref(code))
+        ctypes.windll.kernel32.CloseHandle(process)
+        return code.value == 259  # STILL_ACTIVE
+    try:
+        os.kill(pid, 0)
+    except ProcessLookupError:
+        return False
+    except PermissionError:
+        return True
+    return True
+
+
+def write_json(path: Path, data: Dict):
+    temp_path = path.with_name(path.name + ".tmp")
+    with open(temp_path, 'w', encoding='utf-8') as f:
+        json.dump(data, f, indent=2)
+    os.replace(temp_path, path)
+
+
+class Job:
+    """One journaled job. Every change is written to its journal file at once."""
+
+    def __init__(self, journal: "JobJournal", kind: str, label: str, state: Dict):
+        self.journal = journal
+        self.lock = threading.Lock()
+        self.record = {
+            "id": uuid.uuid4().hex[:12],
+            "kind": kind,
+            "label": label,
+            "pid": os.getpid(),
+            "started": datetime.now().isoformat(timespec='seconds'),
+            "updated": None,
+            "files": [],
+            "state": dict(state),
+        }
+        self.path = journal.directory / (self.record["id"] + JOB_SUFFIX)
+        self.ended = False
+
+    def save(self):
+        if not self.journal.enabled or self.ended:
+            return
+        self.record["updated"] = datetime.now().isoformat(timespec='seconds')
+        write_json(self.path, self.record)
+
+    def add_file(self, path):
+        """Note a temporary or partial file the job writes, which a recovery removes."""
+        with self.lock:
+            if str(path) not in self.record["files"]:
+                self.record["files"].append(str(path))
+                self.save()
+
+    def remove_file(self, path):
+        """Note that a file added with add_file was finished or removed by the job itself."""
+        with self.lock:
+            if str(path) in self.record["files"]:
+                self.record["files"].remove(str(path))
+                self.save()
+
+    def update(self, **state):
+        """Record how far the job got."""
+        with self.lock:
+            self.record["state"].update(state)
+            self.save()
+
+    def end(self):
+        with self.lock:
+            self.ended = True
+            if self.journal.enabled:
+                self.path.unlink(missing_ok=True)
+
+
+class JobJournal:
+    """The journal of one data directory. In read-only mode jobs write nothing, and neither does it."""
+
+    def __init__(self, config: Optional[RomConfig] = None):
+        config = config or RomConfig()
+        self.directory = config.get_path('paths.data_directory') / "jobs"
+        self.enabled = not config.is_read_only()
+        if self.enabled:
+            self.directory.mkdir(parents=True, exist_ok=True)
+
+    @contextmanager
+    def job(self, kind: str, label: str, **state) -> Iterator[Job]:
+        """Journal a job while the block runs; it leaves the journal however the block ends."""
+        job = Job(self, kind, label, state)
+        job.save()
+        try:
+            yield job
+        finally:
+            job.end()
+
+    def entries(self) -> List[Dict]:
+        """Get every job in the journal, oldest first, with whether its process still runs."""
+        if not self.directory.exists():
+            return []
+        entries = []
+        for path in self.directory.glob("*" + JOB_SUFFIX):
+            try:
+                with open(path, 'r', encoding='utf-8') as f:
+                    entry = json.load(f)
+            except (OSError, ValueError):
+                # Written by os.replace, so a damaged file was never a job's
+                continue
+            entry["running"] = process_running(entry.get("pid", 0))
+            entries.append(entry)
+        return sorted(entries, key=lambda entry: entry.get("started") or "")
+
+    def interrupted(self) -> List[Dict]:
+        """Get the jobs whose process ended without removing them from the journal."""
+        return [entry for entry in self.entries() if not entry["running"]]
+
+    def claim(self, entry: Dict) -> Optional[Path]:
+        """Take an interrupted job out of the journal for recovery. Returns None if another recovery has it."""
+        path = self.directory / (entry["id"] + JOB_SUFFIX)
+        claimed = path.with_suffix(RECOVERING_SUFFIX)
+        try:
+            os.rename(path, claimed)
+        except OSError:
+            return None
+        return claimed
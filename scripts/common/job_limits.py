@@ -0,0 +1,127 @@
+#!/usr/bin/env python3
+"""
+Job Limits
+Caps the CPU threads and disk reads of background jobs (hashing audits, disc
+image scans, sync verification), so a verification left running does not
+make games stutter. The jobs section of the settings holds the budget:
+
+    [jobs]
+    cpu_threads = 2         # files worked on at once; 0 uses every core
+    io_concurrency = 1      # files read at once
+    io_mb_per_second = 50   # read speed across the job; 0 is unlimited
+
+A running job notices when the settings file changes (the GUI's
+set_job_limits command writes it) and follows the new limits from its next
+file or chunk on, without a restart.
+"""
+
+import os
+import threading
+import time
+from collections import deque
+from contextlib import contextmanager
+from concurrent.futures import ThreadPoolExecutor
+from typing import Callable, Iterable, Iterator, Optional
+
+from rom_config import RomConfig
+
+# How often a running job looks at the settings file for new limits, in seconds
+RELOAD_INTERVAL = 2.0
+
+
+def all_cores() -> int:
+    return os.cpu_count() or 1
+
+
+class JobLimits:
+    """The CPU and IO budget of one job, shared by all its threads."""
+
+    def __init__(self, config: Optional[RomConfig] = None):
+        self.config = config or RomConfig()
+        self.condition = threading.Condition()
+        self.cpu_in_use = 0
+        self.io_in_use = 0
+        # When the bytes read so far may have been read at the configured speed
+        self.io_free_at = time.monotonic()
+        self.checked_at = time.monotonic()
+        self.file_times = self.config_file_times()
+        self.apply(self.config)
+
+    def config_file_times(self):
+        files = [self.config.config_file, self.config.user_config_file]
+        return [os.path.getmtime(path) if path and os.path.exists(path) else None for path in files]
+
+    def apply(self, config: RomConfig):
+        self.cpu_threads = int(config.get('jobs.cpu_threads', 0) or 0)
+        self.io_concurrency = max(int(config.get('jobs.io_concurrency', 2) or 1), 1)
+        self.io_bytes_per_second = float(config.get('jobs.io_mb_per_second', 0) or 0) * 1024 * 1024
+
+    def refresh(self):
+        """Pick up limits changed in the settings since they were last read."""
+        now = time.monotonic()
+        if now - self.checked_at < RELOAD_INTERVAL:
+            return
+        self.checked_at = now
+        file_times = self.config_file_times()
+        if file_times == self.file_times:
+            return
+        self.file_times = file_times
+        with self.condition:
+            self.apply(RomConfig(self.config.config_file if self.config.user_config_file is None else None))
+            self.condition.notify_all()
+
+    @property
+    def threads(self) -> int:
+        """The files worked on at once: cpu_threads, or every core when it is 0."""
+        return min(self.cpu_threads, all_cores()) if self.cpu_threads > 0 else all_cores()
+
+    @contextmanager
+    def slot(self, kind: str):
+        """Hold one of the job's CPU ("cpu") or read ("io") slots, waiting for a free one."""
+        counter = f"{kind}_in_use"
+        with self.condition:
+            while True:
+                self.refresh()
+                limit = self.threads if kind == "cpu" else self.io_concurrency
+                if getattr(self, counter) < limit:
+                    break
+                self.condition.wait(RELOAD_INTERVAL)
+            setattr(self, counter, getattr(self, counter) + 1)
+        try:
+            yield
+        finally:
+            with self.condition:
+                setattr(self, counter, getattr(self, counter) - 1)
+                self.condition.notify_all()
+
+    def io_slot(self):
+        return self.slot("io")
+
+    def throttle(self, size: int):
+        """Wait as long as reading size more bytes takes at io_mb_per_second."""
+        self.refresh()
+        if not self.io_bytes_per_second:
+            return
+        with self.condition:
+            now = time.monotonic()
+            self.io_free_at = max(self.io_free_at, now) + size / self.io_bytes_per_second
+            wait = self.io_free_at - now
+        if wait > 0:
+            time.sleep(wait)
+
+    def map(self, function: Callable, items: Iterable) -> Iterator:
+        """Apply function to each item on up to threads threads, yielding the results in order."""
+        window = all_cores() * 2
+
+        def run(item):
+            with self.slot("cpu"):
+                return function(item)
+
+        with ThreadPoolExecutor(max_workers=all_cores()) as pool:
+            pending = deque()
+            for item in items:
+                pending.append(pool.submit(run, item))
+                if len(pending) >= window:
+                    yield pending.popleft().result()
+            while pending:
+                yield pending.popleft().result()
@@ -0,0 +1,207 @@
+#!/usr/bin/env python3
+"""
+Job Progress
+One way to measure and show the progress of long jobs (downloads, scans,
+crawls, scrapes, syncs): a ProgressTracker counts what is done, smooths the
+rate so the ETA does not jump with every slow file, and hands a JobProgress
+snapshot to a reporter.
+
+The terminal reporter keeps one status line on stderr, or prints a line per
+item when the job names one. When ROM_BROWSER_PROGRESS=json is set (the GUI
+sets it when it runs a script) every snapshot is written to stderr as a JSON
+line instead, {"progress": {...}}, which the GUI turns into job-progress
+events; stdout stays free for --json results.
+"""
+
+import json
+import os
+import re
+import sys
+import time
+from dataclasses import asdict, dataclass
+from typing import Callable, Optional
+
+PROGRESS_ENV = "ROM_BROWSER_PROGRESS"
+
+# Weight of the newest rate measurement in the moving average
+SMOOTHING = 0.3
+# The rate is measured over at least this long, so bursts of tiny items do not skew it
+RATE_INTERVAL = 0.5
+# Progress without a message is reported at most this often
+REPORT_INTERVAL = 0.2
+# Terminal colors in messages, which the GUI shows as plain text
+ANSI_CODES = re.compile(r'\x1b\[[0-9;]*m')
+
+
+@dataclass
+class JobProgress:
+    job: str                      # download, scan, crawl, scrape, sync, check...
+    label: str                    # what is being worked on, e.g. the file or device
+    done: float
+    total: Optional[float]        # None when the size of the job is not known
+    unit: str                     # "items" or "bytes"
+    rate: Optional[float]         # smoothed, in units per second
+    eta_seconds: Optional[float]
+    elapsed_seconds: float
+    message: Optional[str] = None
+    finished: bool = False
+
+    @property
+    def fraction(self) -> Optional[float]:
+        return min(self.done / self.total, 1.0) if self.total else None
+
+    def to_dict(self):
+        return dict(asdict(self), fraction=self.fraction)
+
+
+def format_duration(seconds: float) -> str:
+    seconds = int(round(seconds))
+    hours, rest = divmod(seconds, 3600)
+    minutes, seconds = divmod(rest, 60)
+    if hours:
+        return f"{hours}h{minutes:02d}m"
+    return f"{minutes}m{seconds:02d}s" if minutes else f"{seconds}s"
+
+
+def format_amount(amount: float, unit: str) -> str:
+    if unit != "bytes":
+        return f"{int(amount)}"
+    for suffix in ("B", "KB", "MB", "GB"):
+        if amount < 1024:
+            return f"{amount:.1f} {suffix}" if suffix != "B" else f"{int(amount)} B"
+        amount /= 1024
+    return f"{amount:.1f} TB"
+
+
+def describe(progress: JobProgress) -> str:
+    """The status line of a snapshot, e.g. "[3/40] 7%, 1.2/s, ETA 31s"."""
+    done = format_amount(progress.done, progress.unit)
+    if progress.total:
+        total = format_amount(progress.total, progress.unit)
+        parts = [f"[{done}/{total}]" if progress.unit == "items" else f"{done} of {total}",
+                 f"{progress.fraction:.0%}"]
+    else:
+        parts = [f"[{done}]" if progress.unit == "items" else done]
+    if progress.rate:
+        rate = format_amount(progress.rate, progress.unit) if progress.unit == "bytes" else f"{progress.rate:.1f}"
+        parts.append(f"{rate}/s")
+    if progress.eta_seconds is not None and not progress.finished:
+        parts.append(f"ETA {format_duration(progress.eta_seconds)}")
+    return ", ".join(parts)
+
+
+class TerminalReporter:
+    """Shows progress on a terminal: items with a message get a line each, the rest one redrawn line.
+
+    When stderr is not a terminal only the messages, or else the final status, are printed.
+    """
+
+    def __init__(self, stream=None):
+        self.stream = stream or sys.stderr
+        self.interactive = hasattr(self.stream, 'isatty') and self.stream.isatty()
+        self.line_open = False
+        self.messages_shown = False
+
+    def __call__(self, progress: JobProgress):
+        status = describe(progress)
+        if progress.message:
+            self.end_line()
+            print(f"{status} {progress.message}", file=self.stream, flush=True)
+            self.messages_shown = True
+        elif progress.finished:
+            if self.line_open or not (self.interactive or self.messages_shown):
+                self.draw(progress, status)
+            self.end_line()
+        elif self.interactive:
+            self.draw(progress, status)
+
+    def draw(self, progress: JobProgress, status: str):
+        label = f"{progress.label}: " if progress.label else ""
+        if self.interactive:
+            print(f"\r\033[K{label}{status}", end='', file=self.stream, flush=True)
+            self.line_open = True
+        else:
+            print(f"{label}{status}", file=self.stream, flush=True)
+
+    def end_line(self):
+        if self.line_open:
+            print(file=self.stream, flush=True)
+            self.line_open = False
+
+
+def json_reporter(stream=None) -> Callable[[JobProgress], None]:
+    """Writes each snapshot as a JSON line for the GUI."""
+    stream = stream or sys.stderr
+
+    def report(progress: JobProgress):
+        event = progress.to_dict()
+        if event['message']:
+            event['message'] = ANSI_CODES.sub('', event['message'])
+        print(json.dumps({"progress": event}), file=stream, flush=True)
+    return report
+
+
+def silent_reporter(progress: JobProgress):
+    pass
+
+
+def default_reporter(show: bool = True) -> Callable[[JobProgress], None]:
+    """JSON lines when the GUI runs the script, else the terminal (unless show is False)."""
+    if os.environ.get(PROGRESS_ENV) == "json":
+        return json_reporter()
+    return TerminalReporter() if show else silent_reporter
+
+
+class ProgressTracker:
+    """Counts the progress of one job and reports a JobProgress after each update.
+
+    advance() adds to what is done; the rate is an exponential moving average
+    of the rate over each RATE_INTERVAL, and the ETA is what remains at that
+    rate. finish() reports a last snapshot marked finished.
+    """
+
+    def __init__(self, job: str, total: Optional[float] = None, unit: str = "items", label: str = "",
+                 reporter: Optional[Callable[[JobProgress], None]] = None, start: float = 0):
+        self.job = job
+        self.total = total
+        self.unit = unit
+        self.label = label
+        self.reporter = reporter if reporter is not None else default_reporter()
+        self.done = start
+        self.started = time.monotonic()
+        self.rate: Optional[float] = None
+        self.window_start = self.started
+        self.window_done = start
+        self.last_report = 0.0
+
+    def advance(self, amount: float = 1, message: Optional[str] = None):
+        self.update(self.done + amount, message)
+
+    def update(self, done: float, message: Optional[str] = None):
+        self.done = done
+        now = time.monotonic()
+        if now - self.window_start >= RATE_INTERVAL:
+            measured = (done - self.window_done) / (now - self.window_start)
+            self.rate = measured if self.rate is None else SMOOTHING * measured + (1 - SMOOTHING) * self.rate
+            self.window_start, self.window_done = now, done
+        elif self.rate is None and done > self.window_done and now > self.started:
+            # Until the first interval has passed, the average so far is the best guess
+            self.rate = (done - self.window_done) / (now - self.started)
+        self.report(message)
+
+    def snapshot(self, message: Optional[str] = None, finished: bool = False) -> JobProgress:
+        eta = None
+        if self.total is not None and self.rate:
+            eta = max(self.total - self.done, 0) / self.rate
+        return JobProgress(job=self.job, label=self.label, done=self.done, total=self.total, unit=self.unit,
+                           rate=self.rate, eta_seconds=eta, elapsed_seconds=time.monotonic() - self.started,
+                           message=message, finished=finished)
+
+    def report(self, message: Optional[str] = None, finished: bool = False):
+        now = time.monotonic()
+        if message or finished or now - self.last_report >= REPORT_INTERVAL:
+            self.last_report = now
+            self.reporter(self.snapshot(message, finished))
+
+    def finish(self, message: Optional[str] = None):
+        self.report(message, finished=True)
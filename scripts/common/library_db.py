@@ -0,0 +1,615 @@
+#!/usr/bin/env python3
+"""
+Library Database
+Records downloaded ROMs together with the source URL they came from, what the
+source said about the file (so a re-dump at the source can be noticed), the
+hashes taken while it downloaded, why it was picked for a queued title, how it checked out against the DATs and the
+icon extracted from it, and sampled transfer speeds so slow mirrors can be
+told apart from a slow local network. It also remembers the file a user
+picked for a title where the automatic match was wrong, so later sessions
+reuse the correction, and keeps an activity feed of what was done to the
+collection, such as deletions, with the entries of deleted files archived.
+
+Scripts that write to the library use open_library(), which hands their
+calls to the daemon while one is running.
+
+File paths are stored relative to a configured root (see portable_paths), so
+a library written on one machine still finds its files on another; entries
+read back carry local paths.
+"""
+
+import json
+import re
+import sqlite3
+import urllib.parse
+from datetime import datetime, timedelta
+from pathlib import Path
+from typing import Dict, List, Optional, Set
+
+from daemon_ipc import RemoteObject, connect
+from datasets import detect_dataset, platform_of
+from portable_paths import PathRoots, is_portable
+from rom_config import RomConfig
+from rom_icons import save_icon
+
+# What an interrupted download leaves: its .part file, piece checkpoints and their temporary file
+PARTIAL_SUFFIXES = ('.part', '.pieces.json', '.tmp')
+# Copies kept while a file is replaced by a newer (.outdated) or repaired (.broken) one
+BACKUP_SUFFIXES = ('.outdated', '.broken')
+# Files in the downloads folder that are not (yet) a download of their own
+NON_DOWNLOAD_SUFFIXES = PARTIAL_SUFFIXES + BACKUP_SUFFIXES
+TRAILING_EXTENSIONS = re.compile(r'(\.[A-Za-z0-9]{1,4})+$')
+
+# Source URL states set by the health check
+URL_OK = "ok"
+URL_MISSING = "missing"
+URL_ERROR = "error"
+
+# PRAGMA user_version from which paths are stored in portable form
+PORTABLE_PATHS_VERSION = 1
+
+
+class LibraryDB:
+    def __init__(self, db_path=None, config: Optional[RomConfig] = None):
+        self.config = config or RomConfig()
+        self.db_path = Path(db_path) if db_path else self.config.get_path('paths.library_db')
+        self.roots = PathRoots(self.config)
+        self.init_database()
+
+    def connect(self):
+        conn = sqlite3.connect(self.db_path)
+        conn.row_factory = sqlite3.Row
+        return conn
+
+    def init_database(self):
+        """Create the library tables if they do not exist."""
+        if not self.db_path.exists() and self.config.is_read_only():
+            return
+
+        self.db_path.parent.mkdir(parents=True, exist_ok=True)
+        conn = self.connect()
+        conn.execute('''
+            CREATE TABLE IF NOT EXISTS library (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                path TEXT UNIQUE NOT NULL,
+                filename TEXT NOT NULL,
+                platform TEXT,
+                size INTEGER,
+                source_url TEXT,
+                downloaded_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                url_status TEXT,
+                http_status INTEGER,
+                url_checked_at TIMESTAMP,
+                url_last_ok TIMESTAMP,
+                icon_path TEXT,
+                source_size INTEGER,
+                source_validator TEXT,
+                source_sha1 TEXT,
+                outdated_reason TEXT,
+                outdated_at TIMESTAMP,
+                match_explanation TEXT,
+                verification TEXT,
+                verified_title TEXT,
+                verified_at TIMESTAMP,
+                hashes TEXT
+            )
+        ''')
+        conn.execute('''
+            CREATE TABLE IF NOT EXISTS speed_samples (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session TEXT NOT NULL,
+                source_url TEXT,
+                host TEXT,
+                sampled_at TIMESTAMP NOT NULL,
+                bytes INTEGER NOT NULL,
+                seconds REAL NOT NULL
+            )
+        ''')
+        conn.execute('CREATE INDEX IF NOT EXISTS idx_speed_samples_time ON speed_samples(sampled_at)')
+        conn.execute('''
+            CREATE TABLE IF NOT EXISTS match_overrides (
+                folder TEXT NOT NULL,
+                match_key TEXT NOT NULL,
+                title TEXT NOT NULL,
+                filename TEXT NOT NULL,
+                set_at TIMESTAMP NOT NULL,
+                PRIMARY KEY (folder, match_key)
+            )
+        ''')
+        conn.execute('''
+            CREATE TABLE IF NOT EXISTS activity (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                at TIMESTAMP NOT NULL,
+                action TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                details TEXT
+            )
+        ''')
+        conn.execute('''
+            CREATE TABLE IF NOT EXISTS archived_entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                entry_id INTEGER NOT NULL,
+                path TEXT NOT NULL,
+                archived_at TIMESTAMP NOT NULL,
+                entry TEXT NOT NULL
+            )
+        ''')
+
+        # Add columns to databases created before they existed
+        existing_columns = {row[1] for row in conn.execute('PRAGMA table_info(library)')}
+        for column, column_type in (('icon_path', 'TEXT'), ('source_size', 'INTEGER'), ('source_validator', 'TEXT'),
+                                    ('source_sha1', 'TEXT'), ('outdated_reason', 'TEXT'), ('outdated_at', 'TIMESTAMP'),
+                                    ('match_explanation', 'TEXT'), ('verification', 'TEXT'),
+                                    ('verified_title', 'TEXT'), ('verified_at', 'TIMESTAMP'), ('hashes', 'TEXT')):
+            if column not in existing_columns:
+                conn.execute(f'ALTER TABLE library ADD COLUMN {column} {column_type}')
+        conn.commit()
+        version = conn.execute('PRAGMA user_version').fetchone()[0]
+        conn.close()
+
+        # Libraries written before paths were portable have them absolute
+        if version < PORTABLE_PATHS_VERSION and not self.config.is_read_only():
+            self.migrate_paths()
+
+    def migrate_paths(self, old_roots: Optional[Dict[str, str]] = None) -> Dict:
+        """Store absolute paths relative to their root, trying old_roots first (the folders a library
+        moved from another machine had there, e.g. {"downloads": "C:/Users/me/ROMs"}).
+
+        Returns how many paths were converted and the ones under no root, which stay absolute.
+        """
+        self.config.ensure_writable("convert the library paths")
+        converted, unplaced = 0, []
+        conn = self.connect()
+        for row in conn.execute('SELECT id, path, icon_path FROM library').fetchall():
+            for column in ('path', 'icon_path'):
+                value = row[column]
+                if not value or is_portable(value):
+                    continue
+                stored = self.roots.portable(value, old_roots)
+                if not is_portable(stored):
+                    unplaced.append(value)
+                    continue
+                try:
+                    conn.execute(f'UPDATE library SET {column} = ? WHERE id = ?', (stored, row['id']))
+                    converted += 1
+                except sqlite3.IntegrityError:
+                    # Recorded twice, once under each form; the stored form already has an entry
+                    unplaced.append(value)
+        conn.execute(f'PRAGMA user_version = {PORTABLE_PATHS_VERSION}')
+        conn.commit()
+        conn.close()
+        return {"converted": converted, "unplaced": unplaced}
+
+    def entry(self, row) -> Dict:
+        """A library row with its paths made local."""
+        entry = dict(row)
+        entry['path'] = self.roots.resolve(entry['path'])
+        entry['icon_path'] = self.roots.resolve(entry['icon_path'])
+        return entry
+
+    def record_download(self, path, source_url: str, platform: Optional[str] = None,
+                        source_size: Optional[int] = None, source_validator: Optional[str] = None,
+                        match_explanation: Optional[Dict] = None, hashes: Optional[Dict] = None):
+        """Record a downloaded file, the URL it was downloaded from and the size and
+        validator the source reported for it. A new download is never outdated.
+
+        Files picked by matching a title also keep why they were picked
+        (ROMDownloader.explain_match); downloading one again by URL keeps it.
+        hashes are those of the file as it arrived, before any post-processing:
+        {size, crc32, md5, sha1}, with the "entries" of a ZIP archive.
+        """
+        if self.config.is_read_only():
+            return
+
+        path = Path(path).resolve()
+        size = path.stat().st_size if path.exists() else None
+        now = datetime.now()
+
+        conn = self.connect()
+        conn.execute('''
+            INSERT INTO library (path, filename, platform, size, source_url, downloaded_at,
+                                 url_status, url_checked_at, url_last_ok, source_size, source_validator,
+                                 match_explanation, hashes)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(path) DO UPDATE SET
+                platform = excluded.platform,
+                size = excluded.size,
+                source_url = excluded.source_url,
+                downloaded_at = excluded.downloaded_at,
+                url_status = excluded.url_status,
+                http_status = NULL,
+                url_checked_at = excluded.url_checked_at,
+                url_last_ok = excluded.url_last_ok,
+                source_size = excluded.source_size,
+                source_validator = excluded.source_validator,
+                source_sha1 = NULL,
+                outdated_reason = NULL,
+                outdated_at = NULL,
+                verification = NULL,
+                verified_title = NULL,
+                verified_at = NULL,
+                hashes = excluded.hashes,
+                match_explanation = COALESCE(excluded.match_explanation, library.match_explanation)
+        ''', (self.roots.portable(path), path.name, platform, size, source_url, now, URL_OK, now, now, source_size, source_validator,
+              json.dumps(match_explanation) if match_explanation else None, json.dumps(hashes) if hashes else None))
+        conn.commit()
+        conn.close()
+
+    def get_entry(self, path) -> Optional[Dict]:
+        """Get the library entry for a file path, if there is one."""
+        if not self.db_path.exists():
+            return None
+
+        conn = self.connect()
+        # Paths under no root, and those of a library not converted yet in read-only mode, are absolute
+        row = conn.execute('SELECT * FROM library WHERE path IN (?, ?)',
+                           (self.roots.portable(path), str(Path(path).resolve()))).fetchone()
+        conn.close()
+        return self.entry(row) if row else None
+
+    def get_match_explanation(self, path) -> Optional[Dict]:
+        """Get why a file was picked for a queued title, if it was downloaded that way."""
+        entry = self.get_entry(path)
+        if not entry or not entry['match_explanation']:
+            return None
+        return json.loads(entry['match_explanation'])
+
+    def get_hashes(self, path) -> Optional[Dict]:
+        """Get the hashes taken while a file downloaded, if it was downloaded with them."""
+        entry = self.get_entry(path)
+        if not entry or not entry['hashes']:
+            return None
+        return json.loads(entry['hashes'])
+
+    def remember_match(self, folder: str, title: str, filename: str):
+        """Remember the file picked for a title in an archive folder, e.g. "Redump/Sony - PlayStation"."""
+        if self.config.is_read_only():
+            return
+
+        conn = self.connect()
+        conn.execute('''
+            INSERT INTO match_overrides (folder, match_key, title, filename, set_at) VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(folder, match_key) DO UPDATE SET
+                title = excluded.title, filename = excluded.filename, set_at = excluded.set_at
+        ''', (folder.strip('/'), match_key(title), title, filename, datetime.now()))
+        conn.commit()
+        conn.close()
+
+    def remembered_match(self, folder: str, title: str) -> Optional[str]:
+        """Get the file remembered for a title in a folder, if a user corrected its match."""
+        if not self.db_path.exists():
+            return None
+
+        conn = self.connect()
+        row = conn.execute('SELECT filename FROM match_overrides WHERE folder = ? AND match_key = ?',
+                           (folder.strip('/'), match_key(title))).fetchone()
+        conn.close()
+        return row['filename'] if row else None
+
+    def forget_match(self, folder: str, title: str) -> bool:
+        """Drop the file remembered for a title, so it is matched automatically again; False if none was."""
+        self.config.ensure_writable("forget a remembered match")
+        conn = self.connect()
+        cursor = conn.execute('DELETE FROM match_overrides WHERE folder = ? AND match_key = ?',
+                              (folder.strip('/'), match_key(title)))
+        conn.commit()
+        conn.close()
+        return cursor.rowcount > 0
+
+    def remembered_matches(self, folder: Optional[str] = None) -> List[Dict]:
+        """Get the remembered matches, {folder, title, filename, set_at}, of one folder or all."""
+        if not self.db_path.exists():
+            return []
+
+        conn = self.connect()
+        query = 'SELECT folder, title, filename, set_at FROM match_overrides'
+        if folder:
+            rows = conn.execute(query + ' WHERE folder = ? ORDER BY title', (folder.strip('/'),)).fetchall()
+        else:
+            rows = conn.execute(query + ' ORDER BY folder, title').fetchall()
+        conn.close()
+        return [dict(row) for row in rows]
+
+    def move_entry(self, old_path, new_path):
+        """Update the stored path of an entry after its file was moved."""
+        if self.config.is_read_only():
+            return
+
+        new_path = Path(new_path).resolve()
+        conn = self.connect()
+        conn.execute('UPDATE library SET path = ?, filename = ? WHERE path IN (?, ?)',
+                     (self.roots.portable(new_path), new_path.name, self.roots.portable(old_path),
+                      str(Path(old_path).resolve())))
+        conn.commit()
+        conn.close()
+
+    def get_entries(self, url_status: Optional[str] = None) -> List[Dict]:
+        """Get library entries, optionally only those with the given URL status."""
+        if not self.db_path.exists():
+            return []
+
+        conn = self.connect()
+        if url_status:
+            rows = conn.execute('SELECT * FROM library WHERE url_status = ? ORDER BY platform, filename',
+                                (url_status,)).fetchall()
+        else:
+            rows = conn.execute('SELECT * FROM library ORDER BY platform, filename').fetchall()
+        conn.close()
+
+        return [self.entry(row) for row in rows]
+
+    def get_entries_due_for_check(self, interval: timedelta) -> List[Dict]:
+        """Get entries with a source URL that has not been checked within the interval."""
+        cutoff = datetime.now() - interval
+        return [
+            entry for entry in self.get_entries()
+            if entry['source_url'] and (
+                not entry['url_checked_at'] or datetime.fromisoformat(str(entry['url_checked_at'])) < cutoff
+            )
+        ]
+
+    def update_url_status(self, entry_id: int, status: str, http_status: Optional[int] = None):
+        """Store the result of a source URL check."""
+        if self.config.is_read_only():
+            return
+
+        now = datetime.now()
+        conn = self.connect()
+        conn.execute('''
+            UPDATE library
+            SET url_status = ?, http_status = ?, url_checked_at = ?,
+                url_last_ok = CASE WHEN ? = ? THEN ? ELSE url_last_ok END
+            WHERE id = ?
+        ''', (status, http_status, now, status, URL_OK, now, entry_id))
+        conn.commit()
+        conn.close()
+
+    def remove_entry(self, entry_id: int, archive: bool = False):
+        """Forget an entry, e.g. after its file was replaced by a download stored elsewhere.
+
+        With archive the entry is kept in archived_entries first, with where it
+        came from and why it was picked, for when its file was deleted.
+        """
+        if self.config.is_read_only():
+            return
+
+        conn = self.connect()
+        if archive:
+            row = conn.execute('SELECT * FROM library WHERE id = ?', (entry_id,)).fetchone()
+            if row:
+                conn.execute('INSERT INTO archived_entries (entry_id, path, archived_at, entry) VALUES (?, ?, ?, ?)',
+                             (entry_id, row['path'], datetime.now(), json.dumps(dict(row), default=str)))
+        conn.execute('DELETE FROM library WHERE id = ?', (entry_id,))
+        conn.commit()
+        conn.close()
+
+    def get_archived_entries(self) -> List[Dict]:
+        """Get the archived entries of deleted files, newest first, with local paths."""
+        if not self.db_path.exists():
+            return []
+
+        conn = self.connect()
+        rows = conn.execute('SELECT * FROM archived_entries ORDER BY archived_at DESC, id DESC').fetchall()
+        conn.close()
+        return [dict(json.loads(row['entry']), path=self.roots.resolve(row['path']),
+                     archived_at=str(row['archived_at'])) for row in rows]
+
+    def record_activity(self, action: str, summary: str, details: Optional[Dict] = None):
+        """Add an event to the activity feed, e.g. ("delete", "Deleted 3 files (1.2 GB)", {...})."""
+        if self.config.is_read_only():
+            return
+
+        conn = self.connect()
+        conn.execute('INSERT INTO activity (at, action, summary, details) VALUES (?, ?, ?, ?)',
+                     (datetime.now(), action, summary, json.dumps(details) if details is not None else None))
+        conn.commit()
+        conn.close()
+
+    def get_activity(self, limit: int = 50) -> List[Dict]:
+        """Get the newest events of the activity feed: {id, at, action, summary, details}."""
+        if not self.db_path.exists():
+            return []
+
+        conn = self.connect()
+        rows = conn.execute('SELECT * FROM activity ORDER BY at DESC, id DESC LIMIT ?', (limit,)).fetchall()
+        conn.close()
+        return [dict(row, at=str(row['at']), details=json.loads(row['details']) if row['details'] else None)
+                for row in rows]
+
+    def set_source_info(self, entry_id: int, size: Optional[int] = None, validator: Optional[str] = None,
+                        sha1: Optional[str] = None):
+        """Store what the source reports for an entry's file; values left as None are kept."""
+        if self.config.is_read_only():
+            return
+
+        conn = self.connect()
+        conn.execute('''
+            UPDATE library
+            SET source_size = COALESCE(?, source_size),
+                source_validator = COALESCE(?, source_validator),
+                source_sha1 = COALESCE(?, source_sha1)
+            WHERE id = ?
+        ''', (size, validator, sha1, entry_id))
+        conn.commit()
+        conn.close()
+
+    def mark_outdated(self, entry_id: int, reason: str):
+        """Flag an entry whose source file changed since it was downloaded."""
+        if self.config.is_read_only():
+            return
+
+        conn = self.connect()
+        conn.execute('''
+            UPDATE library SET outdated_reason = ?, outdated_at = COALESCE(outdated_at, ?) WHERE id = ?
+        ''', (reason, datetime.now(), entry_id))
+        conn.commit()
+        conn.close()
+
+    def set_verification(self, entry_id: int, verification: str, title: Optional[str] = None):
+        """Store how an entry's file checked out against the DATs ('verified', 'crc-match', 'bad-dump'
+        or 'unknown') and the title they list it under. Downloading the file again clears it."""
+        if self.config.is_read_only():
+            return
+
+        conn = self.connect()
+        conn.execute('''
+            UPDATE library SET verification = ?, verified_title = ?, verified_at = ? WHERE id = ?
+        ''', (verification, title, datetime.now(), entry_id))
+        conn.commit()
+        conn.close()
+
+    def get_outdated_entries(self) -> List[Dict]:
+        """Get the entries whose source file changed since they were downloaded."""
+        return [entry for entry in self.get_entries() if entry['outdated_at']]
+
+    def set_icon(self, entry_id: int, icon_path: Optional[str]):
+        """Store the path of the icon extracted from an entry's file."""
+        if self.config.is_read_only():
+            return
+
+        conn = self.connect()
+        conn.execute('UPDATE library SET icon_path = ? WHERE id = ?',
+                     (self.roots.portable(icon_path) if icon_path else None, entry_id))
+        conn.commit()
+        conn.close()
+
+    def extract_icon(self, path) -> Optional[str]:
+        """Extract the embedded icon of a recorded file into the icon directory and store its path."""
+        entry = self.get_entry(path)
+        if not entry or self.config.is_read_only():
+            return None
+
+        icon_path = save_icon(entry['path'], self.config.get_path('paths.icon_directory'))
+        self.set_icon(entry['id'], str(icon_path) if icon_path else None)
+        return str(icon_path) if icon_path else None
+
+    def find_icon(self, game_name: str) -> Optional[str]:
+        """Get the extracted icon of the library entry whose title matches a game name."""
+        key = title_key(game_name)
+        for entry in self.get_entries():
+            if entry['icon_path'] and title_key(entry['filename']) == key and Path(entry['icon_path']).exists():
+                return entry['icon_path']
+        return None
+
+    def record_speed_samples(self, session: str, source_url: str, host: Optional[str], samples: List[Dict]):
+        """Store the speed samples of one transfer and drop samples past the retention period."""
+        if self.config.is_read_only() or not samples:
+            return
+
+        cutoff = datetime.now() - timedelta(days=self.config.get('library.speed_history_days', 90))
+        conn = self.connect()
+        conn.executemany('''
+            INSERT INTO speed_samples (session, source_url, host, sampled_at, bytes, seconds)
+            VALUES (?, ?, ?, ?, ?, ?)
+        ''', [(session, source_url, host, sample['sampled_at'], sample['bytes'], sample['seconds'])
+              for sample in samples])
+        conn.execute('DELETE FROM speed_samples WHERE sampled_at < ?', (cutoff,))
+        conn.commit()
+        conn.close()
+
+    def get_speed_history(self, days: int = 30) -> Dict:
+        """Get the sampled transfer speeds of the last days, per download, session and host.
+
+        Speeds are in bytes per second; each download carries its samples in
+        time order for charting.
+        """
+        history = {"downloads": [], "sessions": [], "hosts": []}
+        if not self.db_path.exists():
+            return history
+
+        cutoff = datetime.now() - timedelta(days=days)
+        conn = self.connect()
+        rows = conn.execute('''
+            SELECT session, source_url, host, sampled_at, bytes, seconds FROM speed_samples
+            WHERE sampled_at >= ? ORDER BY sampled_at
+        ''', (cutoff,)).fetchall()
+        conn.close()
+
+        def summarize(samples: List) -> Dict:
+            total_bytes = sum(row['bytes'] for row in samples)
+            total_seconds = sum(row['seconds'] for row in samples)
+            return {
+                "started": str(samples[0]['sampled_at']),
+                "finished": str(samples[-1]['sampled_at']),
+                "bytes": total_bytes,
+                "seconds": round(total_seconds, 3),
+                "average_bps": round(total_bytes / total_seconds) if total_seconds else None
+            }
+
+        downloads, sessions, hosts = {}, {}, {}
+        for row in rows:
+            downloads.setdefault((row['session'], row['source_url']), []).append(row)
+            sessions.setdefault(row['session'], []).append(row)
+            hosts.setdefault(row['host'], []).append(row)
+
+        for (session, source_url), samples in downloads.items():
+            filename = urllib.parse.unquote(urllib.parse.urlparse(source_url or '').path.rsplit('/', 1)[-1])
+            history["downloads"].append(dict(
+                summarize(samples), session=session, source_url=source_url, filename=filename,
+                host=samples[0]['host'],
+                samples=[{"at": str(row['sampled_at']),
+                          "bps": round(row['bytes'] / row['seconds']) if row['seconds'] else None}
+                         for row in samples]
+            ))
+        for session, samples in sessions.items():
+            history["sessions"].append(dict(summarize(samples), session=session,
+                                            hosts=sorted({row['host'] for row in samples if row['host']})))
+        for host, samples in hosts.items():
+            history["hosts"].append(dict(summarize(samples), host=host,
+                                         downloads=len({(row['session'], row['source_url']) for row in samples})))
+
+        return history
+
+
+def title_key(name: str) -> str:
+    """Reduce a file or game name to its title for loose matching: no extensions, tags or punctuation."""
+    name = re.sub(r'(\.[A-Za-z0-9]{1,4})+$', '', Path(name).name)
+    name = re.sub(r'\s*[\(\[].*$', '', name)
+    return re.sub(r'[^a-z0-9]+', '', name.lower())
+
+
+def owned_key(name: str) -> str:
+    """Reduce a file name to what tells downloads apart: the name without extensions, in lower case.
+
+    Unlike title_key the tags stay, so "(USA)" and "(Europe)" are different
+    files, while a download converted to another format (.zip to .chd) still
+    matches its listing entry.
+    """
+    return TRAILING_EXTENSIONS.sub('', Path(name).name).lower()
+
+
+def owned_keys(config: RomConfig, library=None) -> Set[str]:
+    """Get the owned_key of every file the user has: library entries still on disk and the downloads folder's files."""
+    keys = set()
+    for entry in (library or open_library(config)).get_entries():
+        path = Path(entry['path'])
+        if path.exists():
+            keys.add(owned_key(path.name))
+
+    downloads = config.get_path('paths.download_directory')
+    if downloads.is_dir():
+        for path in downloads.rglob('*'):
+            if path.is_file() and not path.name.endswith(NON_DOWNLOAD_SUFFIXES):
+                keys.add(owned_key(path.name))
+    return keys
+
+
+def platform_from_url(url: str) -> Optional[str]:
+    """Get the platform folder name from an archive file URL ("Manufacturer - System" for TOSEC)."""
+    if detect_dataset(url):
+        return platform_of(url)
+    parts = urllib.parse.urlparse(url).path.rstrip('/').split('/')
+    return urllib.parse.unquote(parts[-2]) if len(parts) >= 2 and parts[-2] else None
+
+
+def match_key(title: str) -> str:
+    """Key a remembered match's title so case and spacing do not matter, as typed in a queue or on the command line."""
+    return ' '.join(title.casefold().split())
+
+
+def open_library(config: RomConfig):
+    """Get the library to use: the daemon's while one is running for the same database, otherwise the file itself."""
+    client = connect(config)
+    if client and client.serves("library_db", config.get_path('paths.library_db')):
+        return RemoteObject(client, "library", config)
+    return LibraryDB(config=config)
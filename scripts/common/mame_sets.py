@@ -0,0 +1,202 @@
+#!/usr/bin/env python3
+"""
+MAME Sets
+Parent/clone and BIOS relationships from MAME DATs (Logiqx or -listxml), used
+to group clones under their parents, to tell what else a set needs to run,
+and to check a set's ZIP holds every ROM it should.
+
+A set is stored one of three ways:
+
+    split       a clone's ZIP holds only the ROMs that differ from its parent;
+                it needs the parent's ZIP (and the BIOS) to run
+    non-merged  every ZIP is self-contained apart from the BIOS
+    merged      clones live inside their parent's ZIP
+"""
+
+import re
+import xml.etree.ElementTree as ET
+import zipfile
+from pathlib import Path
+from typing import Callable, Dict, List, Optional
+
+SET_TYPES = ("split", "non-merged", "merged")
+
+
+def set_name(filename: str) -> str:
+    """Get the set name of a ZIP or 7z file name."""
+    return re.sub(r'\.(zip|7z)$', '', Path(filename).name, flags=re.IGNORECASE)
+
+
+class MameSets:
+    def __init__(self):
+        self.machines = {}
+        self.dats = []
+
+    def load_directory(self, directory, log: Callable[[str], None] = print) -> int:
+        """Load the MAME DATs in a directory tree, skipping DATs without parent/clone data."""
+        directory = Path(directory)
+        if not directory.exists():
+            return 0
+
+        loaded = 0
+        for path in sorted(directory.rglob("*")):
+            if path.suffix.lower() in ('.dat', '.xml') and path.is_file():
+                try:
+                    if self.load_dat(path):
+                        loaded += 1
+                except ET.ParseError as e:
+                    log(f"Skipping unreadable DAT {path}: {e}")
+        return loaded
+
+    def load_dat(self, path) -> bool:
+        """Load a DAT if it describes MAME sets. Returns whether it did."""
+        machines = {}
+        is_mame = False
+
+        # -listxml output runs to hundreds of megabytes, so elements are dropped as they are read
+        for _, element in ET.iterparse(path, events=('end',)):
+            if element.tag not in ('game', 'machine'):
+                continue
+            name = element.get('name')
+            if element.get('cloneof') or element.get('romof') or element.get('isbios') == 'yes':
+                is_mame = True
+            machines[name] = {
+                "name": name,
+                "description": element.findtext('description') or name,
+                "cloneof": element.get('cloneof'),
+                "romof": element.get('romof'),
+                "is_bios": element.get('isbios') == 'yes',
+                "roms": [
+                    {
+                        "name": rom.get('name'),
+                        "size": int(rom.get('size')) if rom.get('size', '').isdigit() else None,
+                        "crc": (rom.get('crc') or '').lower() or None,
+                        "merge": rom.get('merge')
+                    }
+                    for rom in element.iter('rom') if rom.get('status') != 'nodump'
+                ]
+            }
+            element.clear()
+
+        if not is_mame:
+            return False
+        self.machines.update(machines)
+        self.dats.append(str(path))
+        return True
+
+    def get(self, name: str) -> Optional[Dict]:
+        return self.machines.get(set_name(name))
+
+    def parent_of(self, name: str) -> Optional[str]:
+        machine = self.get(name)
+        return machine['cloneof'] if machine else None
+
+    def bios_of(self, name: str) -> Optional[str]:
+        """Follow a set's romof chain to the BIOS it runs on, if any."""
+        machine, seen = self.get(name), set()
+        while machine and machine['romof'] and machine['romof'] not in seen:
+            seen.add(machine['romof'])
+            machine = self.machines.get(machine['romof'])
+            if machine and machine['is_bios']:
+                return machine['name']
+        return None
+
+    def requirements(self, name: str, set_type: str = "split") -> List[Dict]:
+        """Get the other sets a set needs to run: {name, reason}."""
+        required = []
+        parent = self.parent_of(name)
+        if parent and set_type == "split":
+            required.append({"name": parent, "reason": "parent"})
+        bios = self.bios_of(name)
+        if bios:
+            required.append({"name": bios, "reason": "BIOS"})
+        return required
+
+    def group(self, filenames: List[str]) -> List[Dict]:
+        """Group a listing's files into parents with their clones, parents in listing order.
+
+        Clones whose parent is not in the listing form a group of their own;
+        files that are not known sets are kept as groups without clones.
+        """
+        by_set = {set_name(f): f for f in filenames}
+        groups, index = [], {}
+        for filename in filenames:
+            name = set_name(filename)
+            parent = self.parent_of(name)
+            if parent and parent in by_set:
+                continue
+            index[name] = len(groups)
+            groups.append({"parent": filename, "clones": []})
+
+        for filename in filenames:
+            parent = self.parent_of(filename)
+            if parent and parent in by_set:
+                groups[index[parent]]["clones"].append(filename)
+        return groups
+
+    def describe(self, filename: str) -> str:
+        """Get a short note on a set for listings: BIOS, or the parent of a clone."""
+        machine = self.get(filename)
+        if not machine:
+            return ""
+        if machine['is_bios']:
+            return "BIOS"
+        return f"clone of {machine['cloneof']}" if machine['cloneof'] else ""
+
+    def expected_roms(self, name: str, set_type: str = "split") -> List[Dict]:
+        """Get the ROMs a set's ZIP should hold when stored as set_type."""
+        machine = self.get(name)
+        if not machine:
+            return []
+
+        # ROMs with a merge name come from the parent or BIOS set
+        bios = self.bios_of(name)
+        bios_crcs = {rom['crc'] for rom in self.machines[bios]['roms']} if bios and not machine['is_bios'] else set()
+        if set_type == "split":
+            roms = [rom for rom in machine['roms'] if not rom['merge']]
+        else:
+            roms = [rom for rom in machine['roms'] if rom['crc'] not in bios_crcs]
+
+        if set_type == "merged" and not machine['cloneof']:
+            seen = {rom['crc'] for rom in roms}
+            for clone in self.machines.values():
+                if clone['cloneof'] == machine['name']:
+                    for rom in clone['roms']:
+                        if not rom['merge'] and rom['crc'] not in seen:
+                            seen.add(rom['crc'])
+                            roms.append(rom)
+        return roms
+
+    def verify(self, path, set_type: str = "split") -> Dict:
+        """Check a set's ZIP against the DAT: {name, status, missing, wrong, extra}.
+
+        ROMs are matched by CRC32 as recorded in the ZIP, so nothing is
+        decompressed. Status is "complete", "incomplete" or "unknown".
+        """
+        path = Path(path)
+        name = set_name(path.name)
+        result = {"name": name, "path": str(path), "status": "unknown", "missing": [], "wrong": [], "extra": []}
+        if not self.get(name):
+            return result
+        if set_type == "merged" and self.parent_of(name):
+            result["status"] = "complete"
+            result["note"] = f"merged sets keep clones inside {self.parent_of(name)}"
+            return result
+
+        with zipfile.ZipFile(path) as archive:
+            contents = {info.filename.rsplit('/', 1)[-1]: format(info.CRC, '08x') for info in archive.infolist()
+                        if not info.is_dir()}
+
+        expected = self.expected_roms(name, set_type)
+        crcs = set(contents.values())
+        for rom in expected:
+            if rom['name'] in contents and rom['crc'] and contents[rom['name']] != rom['crc']:
+                result["wrong"].append(rom['name'])
+            elif rom['name'] not in contents and rom['crc'] not in crcs:
+                result["missing"].append(rom['name'])
+
+        expected_names = {rom['name'] for rom in expected}
+        expected_crcs = {rom['crc'] for rom in expected}
+        result["extra"] = sorted(n for n, crc in contents.items() if n not in expected_names and crc not in expected_crcs)
+        result["status"] = "incomplete" if result["missing"] or result["wrong"] else "complete"
+        return result
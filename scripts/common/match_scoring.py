@@ -0,0 +1,107 @@
+#!/usr/bin/env python3
+"""
+Match Scoring
+Scores the files a search found for a title, so the best one is downloaded:
+the title's words found in the name, then the configured preferences.
+
+    words      one point per title word in the file name
+    region     10 for the first of matching.region_priority, 2 less for each later one (at least 1)
+    language   3 for the first of matching.language_priority, 1 less for each later one (at least 1)
+    revision   a tenth per revision with matching.prefer_highest_revision;
+               -20 for each of matching.avoid_tags, and TOSEC dump flags
+
+Region, language, revision and status tags are read with names.parse_name.
+Both downloaders use it: rom_downloader.py directly, rom-download.sh
+through select_best_match.
+"""
+
+import re
+from typing import Dict, List, Optional
+
+from datasets import tosec_flag_score
+from names import ParsedName, canonical_region, parse_name
+
+
+class MatchScorer:
+    """The matching preferences of a configuration, and the scores they give files."""
+
+    def __init__(self, region_priority: List[str] = (), language_priority: List[str] = (),
+                 prefer_highest_revision: bool = True, avoid_tags: List[str] = ()):
+        self.region_priority = list(region_priority)
+        self.language_priority = list(language_priority)
+        self.prefer_highest_revision = prefer_highest_revision
+        self.avoid_tags = list(avoid_tags)
+
+    @classmethod
+    def from_config(cls, config) -> "MatchScorer":
+        return cls(config.get('matching.region_priority', []) or [],
+                   config.get('matching.language_priority', []) or [],
+                   config.get('matching.prefer_highest_revision', True),
+                   config.get('matching.avoid_tags', []) or [])
+
+    def score_region(self, parsed: ParsedName) -> int:
+        """Score a file's regions against the configured region priority."""
+        for i, region in enumerate(self.region_priority):
+            if canonical_region(region) in parsed.regions:
+                # First preference gets the largest bonus, later ones taper off
+                return max(10 - 2 * i, 1)
+        return 0
+
+    def score_language(self, parsed: ParsedName) -> int:
+        """Score a file's language tags against the configured language priority."""
+        languages = [language.lower() for language in parsed.languages]
+        for i, language in enumerate(self.language_priority):
+            if language.lower() in languages:
+                return max(3 - i, 1)
+        return 0
+
+    def score_revision(self, filename: str, parsed: ParsedName) -> float:
+        """Score revision and pre-release tags according to the revision policy."""
+        score = 0.0
+
+        if self.prefer_highest_revision:
+            value = parsed.revision
+            # TOSEC puts the version in the title ("Game v1.1 (1991)(...)"), some No-Intro sets tag it (v1.1)
+            version = re.match(r'v([0-9]+(?:\.[0-9]+)?)', parsed.version or '')
+            if not value and version:
+                value = version.group(1)
+            if value:
+                # Revisions are numbers (Rev 2) or letters (Rev A); keep them below a region bonus
+                number = float(value) if value.replace('.', '', 1).isdigit() else ord(value[0].upper()) - ord('A') + 1
+                score += min(number, 9) * 0.1
+
+        # A status is tagged with or without a number: (Beta), (Beta 2)
+        tags = [tag.lower() for tag in ([parsed.status] if parsed.status else []) + parsed.other_tags]
+        for avoid in self.avoid_tags:
+            avoid = avoid.lower()
+            if any(tag == avoid or tag.startswith(avoid + ' ') for tag in tags):
+                score -= 20
+
+        # TOSEC marks bad dumps, hacks and cracks with flags rather than tags
+        score += tosec_flag_score(filename)
+
+        return score
+
+    def score_match(self, game_title: str, filename: str) -> Dict:
+        """Score a candidate file, keeping each part: word overlap, region, language and revision."""
+        clean_filename = re.sub(r'\.(zip|7z)$', '', filename, flags=re.IGNORECASE)
+        filename_words = set(clean_filename.lower().split())
+        parsed = parse_name(clean_filename)
+
+        parts = {
+            "words": sum(1 for word in game_title.lower().split() if word in filename_words),
+            "region": self.score_region(parsed),
+            "language": self.score_language(parsed),
+            "revision": round(self.score_revision(clean_filename, parsed), 2),
+        }
+        return {"filename": filename, **parts, "score": round(sum(parts.values()), 2)}
+
+    def explain_match(self, game_title: str, matches: List[str]) -> Optional[Dict]:
+        """Score every candidate for a title: {title, match, candidates}, candidates best first.
+
+        Ties go to the candidate listed first.
+        """
+        if not matches:
+            return None
+        candidates = sorted((self.score_match(game_title, f) for f in matches), key=lambda c: -c['score'])
+        return {"title": game_title, "match": candidates[0]['filename'], "candidates": candidates}
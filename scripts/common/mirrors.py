@@ -0,0 +1,128 @@
+#!/usr/bin/env python3
+"""
+Archive Mirrors
+Spreads archive requests over the primary archive URL and its configured
+mirrors (network.mirrors in rom_browser.json). Mirrors are probed once per
+session and tried fastest first; a request that fails on one mirror with a
+retryable error moves on to the next one straight away, and the retry policy
+only backs off once every mirror has failed.
+
+URLs passed in and stored (library entries, checkpoints) always use the
+primary archive URL, so switching mirrors never changes what a file is
+recorded as.
+"""
+
+import os
+import time
+from concurrent.futures import ThreadPoolExecutor
+from typing import Callable, List, Optional
+
+import requests
+
+from http_client import http_session
+from retry_policy import RETRY_STATUS_CODES, RetryPolicy
+from rom_config import ARCHIVE_URL_ENV
+
+DEFAULT_PROBE_TIMEOUT = 5.0
+
+
+def normalize_root(url: str) -> str:
+    return url.strip().rstrip('/') + '/'
+
+
+class MirrorSet:
+    def __init__(self, config, retry: Optional[RetryPolicy] = None, log: Callable[[str], None] = print):
+        self.config = config
+        self.retry = retry or RetryPolicy.from_config(config, log=log)
+        self.log = log
+        self.session = http_session(config)
+        self.primary = config.get_archive_url()
+
+        # An archive URL from the environment (e.g. the mock archive) pins every request to it
+        mirrors = [] if os.getenv(ARCHIVE_URL_ENV) else (config.get('network.mirrors', []) or [])
+        self.roots = [self.primary]
+        for mirror in mirrors:
+            root = normalize_root(mirror)
+            if root not in self.roots:
+                self.roots.append(root)
+
+        self.probed = len(self.roots) == 1 or not config.get('network.probe_mirrors', True)
+
+    def probe(self) -> List[str]:
+        """Time a request to each mirror and order them fastest first; unreachable ones go last."""
+        timeout = self.config.get('network.mirror_probe_timeout_seconds', DEFAULT_PROBE_TIMEOUT)
+
+        def latency(root: str) -> float:
+            start = time.monotonic()
+            try:
+                response = self.session.head(root, timeout=timeout, allow_redirects=True)
+                if response.status_code >= 400:
+                    return float('inf')
+            except requests.RequestException:
+                return float('inf')
+            return time.monotonic() - start
+
+        with ThreadPoolExecutor(max_workers=len(self.roots)) as pool:
+            latencies = dict(zip(self.roots, pool.map(latency, self.roots)))
+
+        self.roots.sort(key=lambda root: latencies[root])
+        self.probed = True
+
+        fastest = self.roots[0]
+        if fastest != self.primary and latencies[fastest] != float('inf'):
+            self.log(f"Using mirror {fastest} ({latencies[fastest] * 1000:.0f} ms)")
+        return self.roots
+
+    def candidates(self, url: str) -> List[str]:
+        """Get the URL as served by each mirror, in the order to try them."""
+        if not self.probed:
+            self.probe()
+
+        for root in self.roots:
+            if url.startswith(root):
+                path = url[len(root):]
+                return [mirror + path for mirror in self.roots]
+        return [url]
+
+    def demote(self, url: str):
+        """Move the mirror serving a failed URL to the back for the rest of the session."""
+        for root in self.roots:
+            if url.startswith(root) and len(self.roots) > 1:
+                self.roots.remove(root)
+                self.roots.append(root)
+                self.log(f"Mirror {root} failed, switching to {self.roots[0]}")
+                return
+
+    def request(self, method: str, url: str, **kwargs) -> requests.Response:
+        """Make one request, failing over across the mirrors without waiting.
+
+        The last response or error is returned or raised if every mirror fails.
+        """
+        candidates = self.candidates(url)
+        for index, candidate in enumerate(candidates):
+            last = index == len(candidates) - 1
+            try:
+                response = self.session.request(method, candidate, **kwargs)
+            except Exception as e:
+                if last or not self.retry.is_retryable(e):
+                    raise
+                self.demote(candidate)
+                continue
+
+            if response.status_code in RETRY_STATUS_CODES and not last:
+                response.close()
+                self.demote(candidate)
+                continue
+            # A mirror that has not synced a file yet may still have the rest
+            if response.status_code == 404 and not last:
+                response.close()
+                continue
+            return response
+
+    def get(self, url: str, **kwargs) -> requests.Response:
+        """GET with mirror failover, backing off only when all mirrors fail."""
+        return self.retry.call(self.request, 'GET', url, **kwargs)
+
+    def head(self, url: str, **kwargs) -> requests.Response:
+        """HEAD with mirror failover, backing off only when all mirrors fail."""
+        return self.retry.call(self.request, 'HEAD', url, **kwargs)
@@ -0,0 +1,109 @@
+#!/usr/bin/env python3
+"""
+Multi-Source Search
+Looks a title up in every configured source at once and merges the results,
+so the user can pick where to download from. Each result is labeled with its
+source, the host serving it, its size and a download speed estimated from the
+speeds sampled on earlier downloads from that host.
+
+    archive and mirrors   the platform folder given with platform, e.g.
+                          "Redump/Sony - PlayStation", offered once per mirror
+    internet_archive      the files of every configured item
+    local                 every file below the configured folders
+
+Listings come from the response cache when it has them, so searching folders
+that were browsed recently does not touch the network.
+"""
+
+import urllib.parse
+from concurrent.futures import ThreadPoolExecutor
+from typing import Callable, Dict, List, Optional
+
+from library_db import LibraryDB
+from mirrors import MirrorSet
+from names import name_fields
+from rom_sources import HttpIndexSource, InternetArchiveSource, LocalSource, create_sources, format_size
+from speed_sampler import host_of
+
+DEFAULT_LIMIT = 50
+
+
+def matches(query: str, name: str) -> bool:
+    """Check that every word of the query appears in the name, ignoring case."""
+    name = name.lower()
+    return all(word in name for word in query.lower().split())
+
+
+def host_speeds(config) -> Dict[str, int]:
+    """Get the average sampled download speed of each host, in bytes per second."""
+    days = config.get('library.speed_history_days', 90)
+    history = LibraryDB(config=config).get_speed_history(days=days)
+    return {host['host']: host['average_bps'] for host in history['hosts'] if host['host'] and host['average_bps']}
+
+
+def search_source(source, query: str, platform: Optional[str], limit: int) -> List[Dict]:
+    """Get the files of one source whose names match the query, as {name, path, size}."""
+    if isinstance(source, HttpIndexSource):
+        if not platform:
+            return []
+        entries = source.list_dir(platform.strip('/') + '/')
+    elif isinstance(source, LocalSource):
+        entries = [entry for root in source.list_roots() for entry in source.walk(root['path'])]
+    elif isinstance(source, InternetArchiveSource):
+        entries = [entry for root in source.list_roots() for entry in source.list_dir(root['path'])]
+    else:
+        entries = []
+    return [entry for entry in entries if not entry['is_dir'] and matches(query, entry['name'])][:limit]
+
+
+def search_sources(config, query: str, platform: Optional[str] = None, limit: int = DEFAULT_LIMIT,
+                   log: Callable[[str], None] = print) -> List[Dict]:
+    """Search every configured source for a title and merge what they have.
+
+    Results are sorted by name, and for the same file local copies come first,
+    then the hosts expected to be fastest. A source that cannot be searched is
+    logged and left out.
+    """
+    mirrors = MirrorSet(config, log=log)
+    sources = create_sources(config, mirrors, log=log)
+    speeds = host_speeds(config)
+
+    def search(source):
+        try:
+            return source, search_source(source, query, platform, limit)
+        except Exception as e:
+            log(f"Could not search {source.name}: {e}")
+            return source, []
+
+    results = []
+    with ThreadPoolExecutor(max_workers=len(sources)) as pool:
+        for source, entries in pool.map(search, sources.values()):
+            for entry in entries:
+                size = entry.get('size')
+                urls = [source.file_url(entry['path'])]
+                if isinstance(source, HttpIndexSource):
+                    # Directory listings have no sizes, so ask the archive once per file
+                    if size is None:
+                        try:
+                            size = source.metadata(entry['path'])['size']
+                        except Exception:
+                            size = None
+                    urls = [root + urllib.parse.quote(entry['path']) for root in mirrors.roots]
+
+                for url in urls:
+                    host = host_of(url)
+                    bps = speeds.get(host) if host else None
+                    results.append({
+                        "name": entry['name'],
+                        "source": source.name,
+                        "host": host or "local",
+                        "url": url,
+                        "size": size,
+                        "size_text": format_size(size),
+                        "estimated_bps": bps,
+                        "estimated_seconds": round(size / bps) if size and bps else None,
+                        **name_fields(entry['name']),
+                    })
+
+    results.sort(key=lambda r: (r['name'].lower(), r['host'] != "local", -(r['estimated_bps'] or 0)))
+    return results
@@ -0,0 +1,355 @@
+#!/usr/bin/env python3
+"""
+Names
+Parses the tags of No-Intro, Redump and TOSEC file names into fields, so
+listings can be filtered, sorted and grouped by them:
+
+    "Sonic the Hedgehog (USA, Europe) (En,Fr,De) (Rev 2) (Disc 1) (Beta).bin"
+    -> title "Sonic the Hedgehog", regions ["USA", "Europe"],
+       languages ["En", "Fr", "De"], revision "2", disc 1, status "Beta"
+
+Tags that are none of these (e.g. "Unl", "Virtual Console") are kept in
+other_tags. A "[!]" flag, as TOSEC and GoodTools mark a verified good dump,
+sets verified. TOSEC names are read with parse_tosec_name and expressed the
+same way: countries as regions, "en" as "En".
+"""
+
+import re
+from dataclasses import asdict, dataclass, field
+from typing import Dict, List, Optional
+
+from datasets import TOSEC_REGIONS, parse_tosec_name, strip_extension
+
+# The region names No-Intro and Redump use
+REGIONS = {
+    "World", "USA", "Europe", "Japan", "Asia", "Australia", "Brazil", "Canada", "China", "Denmark",
+    "Finland", "France", "Germany", "Greece", "Hong Kong", "India", "Ireland", "Israel", "Italy",
+    "Korea", "Latin America", "Mexico", "Netherlands", "New Zealand", "Norway", "Poland", "Portugal",
+    "Russia", "Scandinavia", "South Africa", "Spain", "Sweden", "Switzerland", "Taiwan", "UK",
+    "United Kingdom", "Unknown",
+}
+
+# Other names some sets (and matching.region_priority) give these regions
+REGION_ALIASES = {"US": "USA", "North America": "USA", "EU": "Europe", "JP": "Japan"}
+
+# Development and release statuses; a number may follow ("Beta 2", "Proto 1")
+STATUSES = ("Alpha", "Beta", "Proto", "Preview", "Demo", "Sample", "Kiosk", "Promo")
+PRERELEASE_STATUSES = {"Alpha", "Beta", "Proto", "Preview"}
+
+# Languages a file is in when its name lists none; No-Intro only tags the language of
+# a World or Europe release when it is not English
+REGION_LANGUAGES = {
+    "World": "En", "Europe": "En", "USA": "En", "UK": "En", "United Kingdom": "En", "Australia": "En", "Canada": "En", "New Zealand": "En",
+    "Ireland": "En", "Japan": "Ja", "France": "Fr", "Germany": "De", "Spain": "Es", "Italy": "It",
+    "Netherlands": "Nl", "Sweden": "Sv", "Norway": "No", "Denmark": "Da", "Finland": "Fi", "Poland": "Pl",
+    "Portugal": "Pt", "Brazil": "Pt", "Russia": "Ru", "Korea": "Ko", "China": "Zh", "Taiwan": "Zh",
+    "Hong Kong": "Zh", "Greece": "El",
+}
+
+LANGUAGE = re.compile(r'^[A-Z][a-z](?:-[A-Z][A-Za-z]+)?$')
+REVISION = re.compile(r'^Rev ([0-9A-Z.]+)$', re.IGNORECASE)
+DISC = re.compile(r'^Disc ([0-9]+)(?: of [0-9]+)?$', re.IGNORECASE)
+VERSION = re.compile(r'^v[0-9][0-9A-Za-z.]*$')
+STATUS = re.compile(r'^(' + '|'.join(STATUSES) + r')(?: [0-9]+)?$', re.IGNORECASE)
+
+
+@dataclass
+class ParsedName:
+    title: str                              # the name without tags or extension
+    regions: List[str] = field(default_factory=list)
+    languages: List[str] = field(default_factory=list)   # as tagged, e.g. "En", "Pt-BR"
+    revision: Optional[str] = None          # "2" for (Rev 2), "A" for (Rev A)
+    disc: Optional[int] = None
+    version: Optional[str] = None           # "v1.1" for (v1.1)
+    status: Optional[str] = None            # Beta, Proto, Demo... None for a release
+    other_tags: List[str] = field(default_factory=list)
+    verified: bool = False                  # flagged [!], a verified good dump
+
+    @property
+    def is_prerelease(self) -> bool:
+        return self.status in PRERELEASE_STATUSES
+
+    def spoken_languages(self) -> List[str]:
+        """The tagged languages, or else the one language all the regions imply."""
+        if self.languages:
+            return self.languages
+        implied = {REGION_LANGUAGES.get(region) for region in self.regions}
+        return [implied.pop()] if len(implied) == 1 and None not in implied else []
+
+    def to_dict(self) -> Dict:
+        return dict(asdict(self), spoken_languages=self.spoken_languages(), prerelease=self.is_prerelease)
+
+
+def canonical_region(region: str) -> str:
+    """The No-Intro name of a region, e.g. "USA" for "US"."""
+    return REGION_ALIASES.get(region, region)
+
+
+def tag_groups(name: str) -> List[str]:
+    return re.findall(r'\(([^)]*)\)', name)
+
+
+def parse_name(filename: str) -> ParsedName:
+    """Parse a file (or folder) name into its title and tags."""
+    tosec = parse_tosec_name(filename)
+    if tosec:
+        return ParsedName(
+            title=tosec["title"],
+            regions=sorted({TOSEC_REGIONS[code] for code in tosec["regions"]}),
+            languages=[language.title() for language in tosec["languages"]],
+            version=tosec["version"],
+            status=tosec["status"],
+            verified=tosec["verified"],
+        )
+
+    name = strip_extension(filename)
+    parsed = ParsedName(title=re.split(r'\s*[(\[]', name, maxsplit=1)[0].strip() or name,
+                        verified='[!]' in name)
+    for group in tag_groups(name):
+        parts = [part.strip() for part in group.split(',')]
+        if parts and all(part in REGIONS or part in REGION_ALIASES for part in parts):
+            parsed.regions.extend(canonical_region(part) for part in parts)
+        elif parts and all(LANGUAGE.match(part) for part in parts):
+            parsed.languages.extend(parts)
+        elif REVISION.match(group):
+            parsed.revision = REVISION.match(group).group(1)
+        elif DISC.match(group):
+            parsed.disc = int(DISC.match(group).group(1))
+        elif VERSION.match(group):
+            parsed.version = group
+        elif STATUS.match(group):
+            parsed.status = STATUS.match(group).group(1).title()
+        else:
+            parsed.other_tags.append(group)
+    return parsed
+
+
+def name_fields(filename: str) -> Dict:
+    """The parsed fields of a name, to add to a listing entry."""
+    return parse_name(filename).to_dict()
+
+
+@dataclass
+class NameFilter:
+    """Which parsed names to keep; every field set must match, case aside."""
+    region: Optional[str] = None
+    language: Optional[str] = None
+    disc: Optional[int] = None
+    hide_prerelease: bool = False
+
+    def matches(self, parsed: ParsedName) -> bool:
+        if self.region and self.region.lower() not in {region.lower() for region in parsed.regions}:
+            return False
+        if self.language and self.language.lower() not in {lang.lower() for lang in parsed.spoken_languages()}:
+            return False
+        if self.disc is not None and parsed.disc != self.disc:
+            return False
+        return not (self.hide_prerelease and parsed.is_prerelease)
+
+    def is_empty(self) -> bool:
+        return not (self.region or self.language or self.disc is not None or self.hide_prerelease)
+
+
+def add_name_filter_arguments(parser):
+    """Add --region, --language, --disc and --hide-prerelease to a listing command."""
+    parser.add_argument('--region', help='Only files tagged with this region, e.g. USA')
+    parser.add_argument('--language', help='Only files in this language, e.g. En (single-region files count too)')
+    parser.add_argument('--disc', type=int, help='Only this disc of multi-disc games')
+    parser.add_argument('--hide-prerelease', action='store_true', help='Leave out alphas, betas, protos and previews')
+
+
+def name_filter_from_args(args) -> NameFilter:
+    return NameFilter(region=args.region, language=args.language, disc=args.disc, hide_prerelease=args.hide_prerelease)
+
+
+def filter_entries(entries: List[Dict], name_filter: NameFilter) -> List[Dict]:
+    """Keep the entries whose names pass the filter; directories always stay."""
+    if name_filter.is_empty():
+        return entries
+    return [entry for entry in entries if entry.get('is_dir') or name_filter.matches(parse_name(entry['name']))]
+
+
+def language_variant_key(parsed: ParsedName) -> tuple:
+    """What a file has in common with its language variants: every field but the languages."""
+    return (parsed.title.lower(), tuple(parsed.regions), parsed.revision, parsed.disc, parsed.version,
+            parsed.status, tuple(parsed.other_tags))
+
+
+def group_language_variants(entries: List[Dict]) -> List[Dict]:
+    """Fold files that differ only by their language tags into one entry, in listing order.
+
+    European sets carry a file per language pack, e.g. "(Europe) (En,Fr,De)" and
+    "(Europe) (En,Es,It)". The first file of a group stands for it, with every
+    file of the group (itself included) under "variants" and all their languages
+    in "spoken_languages". Directories, files without variants and copies of one
+    file (search results list a file once per host) are left as they are.
+    """
+    groups: Dict[tuple, List[Dict]] = {}
+    order = []
+    for entry in entries:
+        key = ('dir', entry['name']) if entry.get('is_dir') else language_variant_key(parse_name(entry['name']))
+        if key not in groups:
+            groups[key] = []
+            order.append(key)
+        groups[key].append(entry)
+
+    grouped = []
+    for key in order:
+        members = groups[key]
+        if len({member['name'] for member in members}) == 1:
+            grouped.extend(members)
+            continue
+        languages = []
+        for member in members:
+            for language in member.get('spoken_languages') or parse_name(member['name']).spoken_languages():
+                if language not in languages:
+                    languages.append(language)
+        grouped.append(dict(members[0], spoken_languages=languages, variants=members))
+    return grouped
+
+
+def group_by_title(entries: List[Dict]) -> List[Dict]:
+    """Collapse the regional versions, revisions and discs of each title into one group, in listing order.
+
+    A group has the title as its name, what its files have between them
+    (regions, spoken_languages, discs) and the files themselves under "files".
+    Directories are left as they are.
+    """
+    groups: Dict[str, Dict] = {}
+    grouped = []
+    for entry in entries:
+        if entry.get('is_dir'):
+            grouped.append(entry)
+            continue
+        parsed = parse_name(entry['name'])
+        key = parsed.title.lower()
+        if key not in groups:
+            groups[key] = {"name": parsed.title, "is_dir": False, "title": parsed.title, "regions": [],
+                           "spoken_languages": [], "discs": [], "files": []}
+            grouped.append(groups[key])
+        group = groups[key]
+        for field_name, values in (("regions", parsed.regions), ("spoken_languages", parsed.spoken_languages())):
+            group[field_name].extend(value for value in values if value not in group[field_name])
+        if parsed.disc is not None and parsed.disc not in group["discs"]:
+            group["discs"].append(parsed.disc)
+        group["files"].append(entry)
+
+    for group in groups.values():
+        group["discs"].sort()
+    return grouped
+
+
+# Share of a query word's letters that may differ from the name word it matches
+FUZZY_MIN_SIMILARITY = 0.75
+
+
+def edit_distance(a: str, b: str) -> int:
+    """The single-letter insertions, deletions, substitutions and swaps of neighbours from a to b."""
+    rows = [list(range(len(b) + 1))]
+    for i in range(1, len(a) + 1):
+        row = [i]
+        for j in range(1, len(b) + 1):
+            distance = min(rows[i - 1][j] + 1, row[j - 1] + 1, rows[i - 1][j - 1] + (a[i - 1] != b[j - 1]))
+            if i > 1 and j > 1 and a[i - 1] == b[j - 2] and a[i - 2] == b[j - 1]:
+                distance = min(distance, rows[i - 2][j - 2] + 1)
+            row.append(distance)
+        rows.append(row)
+    return rows[-1][-1]
+
+
+def fuzzy_words(text: str) -> List[str]:
+    return re.findall(r'[a-z0-9]+', text.lower())
+
+
+def fuzzy_score(query: str, name: str) -> float:
+    """Score how well a name matches a query typed with typos, from 0.0 (no match) to 1.0.
+
+    Every query word must match a word of the name: a word the name word starts
+    with scores 1.0, one found inside it 0.9, and one of 3+ letters within
+    FUZZY_MIN_SIMILARITY of it ("mraio" for "mario") its similarity. The score
+    is the average over the query words.
+    """
+    query_words = fuzzy_words(query)
+    name_words = fuzzy_words(strip_extension(name))
+    if not query_words:
+        return 0.0
+
+    total = 0.0
+    for query_word in query_words:
+        best = 0.0
+        for name_word in name_words:
+            if name_word.startswith(query_word):
+                best = 1.0
+                break
+            if query_word in name_word:
+                best = max(best, 0.9)
+            elif len(query_word) >= 3:
+                similarity = 1 - edit_distance(query_word, name_word) / max(len(query_word), len(name_word))
+                if similarity >= FUZZY_MIN_SIMILARITY:
+                    best = max(best, similarity)
+        if not best:
+            return 0.0
+        total += best
+    return round(total / len(query_words), 3)
+
+
+def fuzzy_search(entries: List[Dict], query: str) -> List[Dict]:
+    """Get the files of a listing that fuzzily match a query, best first, each with its match_score."""
+    scored = []
+    for entry in entries:
+        score = 0.0 if entry.get('is_dir') else fuzzy_score(query, entry['name'])
+        if score:
+            scored.append(dict(entry, match_score=score))
+    return sorted(scored, key=lambda entry: -entry['match_score'])
+
+
+# Listing orders; size and modified put the largest and newest first, relevance the best search match
+SORT_KEYS = ("name", "size", "modified", "region", "relevance")
+
+
+def region_rank(regions: List[str], region_priority: List[str]) -> tuple:
+    """Order by the first region in region_priority a file has, then by region name; untagged files last."""
+    if not regions:
+        return (2, len(region_priority), "")
+    ranks = [region_priority.index(region) for region in regions if region in region_priority]
+    return (0, min(ranks), "") if ranks else (1, len(region_priority), ", ".join(regions).lower())
+
+
+def sort_entries(entries: List[Dict], key: str, reverse: bool = False,
+                 region_priority: List[str] = ()) -> List[Dict]:
+    """Sort a listing, directories first; entries that tie keep their order.
+
+    Entries without the value sorted on (no size, no date) go after the rest
+    either way. reverse flips the order of the others, directories still first.
+    """
+    if key not in SORT_KEYS:
+        raise ValueError(f"unknown sort key: {key}")
+    region_priority = list(region_priority)
+
+    def value(entry):
+        if key == "name":
+            return entry['name'].lower()
+        if key == "size":
+            return -entry['size'] if entry.get('size') is not None else None
+        if key == "modified":
+            # ISO dates sort as text; newest first is the reverse of that
+            return entry.get('modified') or None
+        if key == "region":
+            return region_rank(parse_name(entry['name']).regions, region_priority)
+        return -(entry.get('match_score') or 0)
+
+    directories = [entry for entry in entries if entry.get('is_dir')]
+    files = [entry for entry in entries if not entry.get('is_dir')]
+    known = [entry for entry in files if value(entry) is not None]
+    unknown = [entry for entry in files if value(entry) is None]
+    newest_first = key == "modified"
+    known.sort(key=value, reverse=reverse != newest_first)
+    return directories + known + unknown
+
+
+def add_sort_arguments(parser):
+    """Add --sort and --reverse to a listing command."""
+    parser.add_argument('--sort', choices=SORT_KEYS,
+                        help='Order files by name, size (largest first), modified (newest first), '
+                             'region (matching.region_priority first) or relevance (best search match first)')
+    parser.add_argument('--reverse', action='store_true', help='Reverse the --sort order')
@@ -0,0 +1,142 @@
+#!/usr/bin/env python3
+"""
+Piece Checkpoints
+Records a hash for every completed piece of a partial download, so a resume
+after a crash can check the partial file before continuing instead of
+trusting its size.
+"""
+
+import hashlib
+import json
+import os
+from pathlib import Path
+from typing import Optional
+
+DEFAULT_PIECE_SIZE = 8 * 1024 * 1024
+
+
+class PieceCheckpoints:
+    def __init__(self, part_path, piece_size=DEFAULT_PIECE_SIZE):
+        self.part_path = Path(part_path)
+        self.state_path = self.part_path.with_name(self.part_path.name + ".pieces.json")
+        self.piece_size = piece_size
+        self.url = None
+        self.validator = None
+        self.pieces = []
+        self.current = hashlib.sha1()
+        self.current_size = 0
+
+    def load(self, url: str, validator: Optional[str] = None) -> bool:
+        """Load saved checkpoints for a URL.
+
+        Returns False (and starts fresh) when there are none, or when they were
+        recorded for another URL, piece size or version of the remote file.
+        """
+        self.url = url
+        self.validator = validator
+        self.pieces = []
+        self.reset_current()
+
+        if not self.state_path.exists():
+            return False
+
+        try:
+            with open(self.state_path, 'r', encoding='utf-8') as f:
+                state = json.load(f)
+        except (OSError, ValueError):
+            return False
+
+        if (state.get("url") != url or state.get("piece_size") != self.piece_size
+                or (validator and state.get("validator") and state["validator"] != validator)):
+            return False
+
+        self.pieces = list(state.get("pieces", []))
+        return True
+
+    def save(self):
+        """Write the checkpoints next to the partial file."""
+        tmp_path = self.state_path.with_name(self.state_path.name + ".tmp")
+        with open(tmp_path, 'w', encoding='utf-8') as f:
+            json.dump({
+                "url": self.url,
+                "validator": self.validator,
+                "piece_size": self.piece_size,
+                "pieces": self.pieces
+            }, f)
+        os.replace(tmp_path, self.state_path)
+
+    def restart(self):
+        """Discard all checkpoints, e.g. when the server ignores a range request."""
+        self.pieces = []
+        self.reset_current()
+
+    def reset_current(self):
+        self.current = hashlib.sha1()
+        self.current_size = 0
+
+    def hash_piece(self, f, index: int) -> Optional[str]:
+        """Hash one piece of the partial file, or None if it is incomplete."""
+        f.seek(index * self.piece_size)
+        data = f.read(self.piece_size)
+        if len(data) != self.piece_size:
+            return None
+        return hashlib.sha1(data).hexdigest()
+
+    def verified_offset(self, full=False) -> int:
+        """Check the partial file and truncate it to the last verified piece.
+
+        By default only the newest pieces are hashed, walking back until one
+        matches: a crash corrupts the unflushed tail, not data that was
+        checkpointed earlier. Pass full=True to hash every piece.
+
+        Returns the offset the download can resume from.
+        """
+        if not self.part_path.exists():
+            self.pieces = []
+            return 0
+
+        size = self.part_path.stat().st_size
+        verified = min(len(self.pieces), size // self.piece_size)
+
+        with open(self.part_path, 'rb') as f:
+            if full:
+                for index in range(verified):
+                    if self.hash_piece(f, index) != self.pieces[index]:
+                        verified = index
+                        break
+            else:
+                while verified > 0 and self.hash_piece(f, verified - 1) != self.pieces[verified - 1]:
+                    verified -= 1
+
+        self.pieces = self.pieces[:verified]
+        offset = verified * self.piece_size
+        if size != offset:
+            with open(self.part_path, 'r+b') as f:
+                f.truncate(offset)
+        self.reset_current()
+        return offset
+
+    def add(self, chunk: bytes, f):
+        """Feed bytes just written to f, checkpointing each completed piece.
+
+        The file is flushed to disk before a piece is recorded, so a saved
+        checkpoint never covers data that could still be lost in a crash.
+        """
+        while chunk:
+            take = min(len(chunk), self.piece_size - self.current_size)
+            self.current.update(chunk[:take])
+            self.current_size += take
+            chunk = chunk[take:]
+
+            if self.current_size == self.piece_size:
+                self.pieces.append(self.current.hexdigest())
+                self.reset_current()
+                f.flush()
+                os.fsync(f.fileno())
+                self.save()
+
+    def finish(self):
+        """Remove the checkpoints once the download is complete."""
+        for path in (self.state_path, self.state_path.with_name(self.state_path.name + ".tmp")):
+            if path.exists():
+                path.unlink()
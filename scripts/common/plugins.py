@@ -0,0 +1,114 @@
+#!/usr/bin/env python3
+"""
+Plugins
+Runs user-provided external commands declared in the "plugins" section of
+rom_browser.json, so custom metadata providers and post-download processors
+can be added without changing the scripts.
+
+Each plugin gets one JSON object on stdin and answers with one JSON value on
+stdout (or nothing). A plugin that fails, times out or prints invalid JSON is
+logged and skipped; it never stops the caller.
+
+    metadata_providers: stdin {"hook": "metadata_provider", "game_name": ...}
+                        stdout metadata ({"name", "summary", "rating", "genres",
+                        "cover": {"url"}, "first_release_date", "developers",
+                        "publishers"}) or null when the game is unknown
+    post_download:      stdin {"hook": "post_download", "path", "url",
+                        "platform", "filename"}
+                        stdout null, or {"path": ...} (absolute) if the file was moved
+"""
+
+import json
+import shlex
+import subprocess
+import sys
+from typing import Callable, Dict, List, Optional
+
+from rom_config import REPO_ROOT
+
+METADATA_PROVIDER = "metadata_providers"
+POST_DOWNLOAD = "post_download"
+
+DEFAULT_TIMEOUT = 60
+DEFAULT_QUALITY = 50
+
+
+def log_to_stderr(message: str):
+    print(message, file=sys.stderr)
+
+
+class PluginManager:
+    def __init__(self, config, log: Callable[[str], None] = log_to_stderr):
+        self.config = config
+        self.log = log
+
+    def get_plugins(self, kind: str) -> List[Dict]:
+        """Get the enabled plugins of a kind, in the order they are declared."""
+        plugins = self.config.get(f'plugins.{kind}', []) or []
+        return [p for p in plugins if isinstance(p, dict) and p.get('command') and p.get('enabled', True)]
+
+    def run(self, plugin: Dict, payload: Dict):
+        """Run one plugin with a JSON payload. Returns its parsed output, or None."""
+        name = plugin.get('name') or str(plugin['command'])
+        command = plugin['command']
+        if isinstance(command, str):
+            command = shlex.split(command)
+
+        try:
+            result = subprocess.run(
+                command,
+                input=json.dumps(payload),
+                capture_output=True,
+                text=True,
+                cwd=REPO_ROOT,
+                timeout=plugin.get('timeout', DEFAULT_TIMEOUT)
+            )
+        except (OSError, subprocess.TimeoutExpired) as e:
+            self.log(f"Plugin {name} failed: {e}")
+            return None
+
+        if result.returncode != 0:
+            self.log(f"Plugin {name} exited with {result.returncode}: {result.stderr.strip()[:200]}")
+            return None
+
+        output = result.stdout.strip()
+        if not output:
+            return None
+        try:
+            return json.loads(output)
+        except ValueError:
+            self.log(f"Plugin {name} printed invalid JSON: {output[:200]}")
+            return None
+
+    def find_metadata(self, game_name: str) -> Optional[Dict]:
+        """Ask the metadata provider plugins for a game; the first answer wins.
+
+        The returned metadata has 'source' set to the plugin name.
+        """
+        for plugin in self.get_plugins(METADATA_PROVIDER):
+            metadata = self.run(plugin, {"hook": "metadata_provider", "game_name": game_name})
+            if isinstance(metadata, dict) and metadata:
+                metadata['source'] = plugin.get('name', 'plugin')
+                return metadata
+        return None
+
+    def get_provider_qualities(self) -> Dict[str, int]:
+        """Get the configured quality of each metadata provider plugin, by name."""
+        return {
+            plugin.get('name', 'plugin'): plugin.get('quality', DEFAULT_QUALITY)
+            for plugin in self.get_plugins(METADATA_PROVIDER)
+        }
+
+    def post_download(self, path: str, url: str, platform: Optional[str]) -> str:
+        """Run the post-download processors in turn. Returns the file's final path."""
+        for plugin in self.get_plugins(POST_DOWNLOAD):
+            result = self.run(plugin, {
+                "hook": "post_download",
+                "path": str(path),
+                "url": url,
+                "platform": platform,
+                "filename": str(path).replace('\\', '/').rsplit('/', 1)[-1]
+            })
+            if isinstance(result, dict) and result.get('path'):
+                path = result['path']
+        return str(path)
@@ -0,0 +1,101 @@
+#!/usr/bin/env python3
+"""
+Portable Paths
+Stores file paths relative to a configured root, named by an identifier, so a
+database written on one machine still finds its files on another where the
+same collection is mounted elsewhere:
+
+    C:\\Users\\me\\ROMs\\downloads\\Sony - PlayStation\\Game.zip
+    -> {downloads}/Sony - PlayStation/Game.zip
+    -> /volume1/roms/downloads/Sony - PlayStation/Game.zip   (on the NAS)
+
+The roots are the configured folders (downloads, covers, icons, data and
+cache) and any named in paths.roots, e.g. {"roms": "D:/ROMs"} on Windows and
+{"roms": "/volume1/roms"} on the NAS. A path under no root is stored as it is.
+"""
+
+import re
+from pathlib import Path, PurePath, PurePosixPath, PureWindowsPath
+from typing import Dict, Optional
+
+from rom_config import RomConfig
+
+ROOT_SETTINGS = {
+    "downloads": "paths.download_directory",
+    "covers": "paths.covers_directory",
+    "icons": "paths.icon_directory",
+    "data": "paths.data_directory",
+    "cache": "paths.cache_directory",
+}
+PORTABLE_PATTERN = re.compile(r'^\{([A-Za-z0-9_.-]+)\}(?:/(.*))?$')
+WINDOWS_PATTERN = re.compile(r'^(?:[A-Za-z]:[\\/]|\\\\)')
+
+
+def pure_path(value: str) -> PurePath:
+    """Read a path written on either system, e.g. a Windows path in a database moved to Linux."""
+    return PureWindowsPath(value) if WINDOWS_PATTERN.match(value) else PurePosixPath(value)
+
+
+def is_portable(value: Optional[str]) -> bool:
+    return bool(value and PORTABLE_PATTERN.match(value))
+
+
+class PathRoots:
+    """The roots of one configuration, to turn paths into their stored form and back."""
+
+    def __init__(self, config: Optional[RomConfig] = None):
+        config = config or RomConfig()
+        self.roots: Dict[str, Path] = {}
+        for root_id, setting in ROOT_SETTINGS.items():
+            path = config.get_path(setting)
+            if path:
+                self.roots[root_id] = path.resolve()
+        for root_id, path in (config.get('paths.roots', {}) or {}).items():
+            self.roots[root_id] = Path(path).expanduser().resolve()
+
+    def portable(self, path, roots: Optional[Dict[str, str]] = None) -> str:
+        """Get the stored form of a path: relative to the deepest root it is under, else as it is.
+
+        roots are extra roots to try, such as the folders a database was written
+        with on another machine; they may be paths of either system.
+        """
+        value = str(path)
+        if is_portable(value):
+            return value
+        if roots:
+            candidate = pure_path(value)
+            for root_id, root in sorted(roots.items(), key=lambda item: -len(item[1])):
+                relative = relative_part(candidate, pure_path(root))
+                if relative is not None:
+                    return f"{{{root_id}}}" + (f"/{relative}" if relative else "")
+
+        if WINDOWS_PATTERN.match(value) and not Path(value).is_absolute():
+            # A Windows path read on another system; only the extra roots can place it
+            return value
+        resolved = Path(value).resolve()
+        for root_id, root in sorted(self.roots.items(), key=lambda item: -len(str(item[1]))):
+            relative = relative_part(resolved, root)
+            if relative is not None:
+                return f"{{{root_id}}}" + (f"/{relative}" if relative else "")
+        return str(resolved)
+
+    def resolve(self, value: Optional[str]) -> Optional[str]:
+        """Get the local path of a stored one; a root this configuration does not know leaves it as stored."""
+        if not value:
+            return value
+        match = PORTABLE_PATTERN.match(value)
+        if not match or match.group(1) not in self.roots:
+            return value
+        root = self.roots[match.group(1)]
+        return str(root.joinpath(*match.group(2).split('/')) if match.group(2) else root)
+
+
+def relative_part(path: PurePath, root: PurePath) -> Optional[str]:
+    """Get a path relative to a root in forward-slash form, or None when it is not under it."""
+    if path == root:
+        return ""
+    try:
+        return path.relative_to(root).as_posix()
+    except (ValueError, TypeError):
+        # Not under the root, or the two are paths of different systems
+        return None
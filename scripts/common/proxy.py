@@ -0,0 +1,40 @@
+#!/usr/bin/env python3
+"""
+Proxy Settings
+Routes HTTP requests through the configured proxy. requests already honours
+HTTP_PROXY/HTTPS_PROXY/ALL_PROXY/NO_PROXY; an explicit network.proxy_url in
+rom_browser.json takes precedence over them and is passed on to child
+processes the same way.
+"""
+
+import os
+import urllib.parse
+
+PROXY_ENV_VARS = ("HTTP_PROXY", "HTTPS_PROXY", "http_proxy", "https_proxy")
+NO_PROXY_ENV_VARS = ("NO_PROXY", "no_proxy")
+
+
+def apply_proxy_settings(config) -> str:
+    """Apply the configured proxy to this process. Returns the proxy URL in use, or ""."""
+    proxy_url = (config.get('network.proxy_url') or '').strip()
+    no_proxy = (config.get('network.no_proxy') or '').strip()
+
+    if proxy_url:
+        scheme = urllib.parse.urlparse(proxy_url).scheme.lower()
+        if scheme not in ('http', 'https', 'socks5', 'socks5h', 'socks4', 'socks4a'):
+            raise ValueError(f"Unsupported proxy URL: {proxy_url} (use http://, https:// or socks5://)")
+        if scheme.startswith('socks'):
+            try:
+                import socks  # noqa: F401
+            except ImportError:
+                raise ValueError("SOCKS proxies need PySocks: pip install requests[socks]")
+
+        for name in PROXY_ENV_VARS:
+            os.environ[name] = proxy_url
+
+    if no_proxy:
+        for name in NO_PROXY_ENV_VARS:
+            os.environ[name] = no_proxy
+
+    return proxy_url or os.environ.get("HTTPS_PROXY") or os.environ.get("https_proxy") or \
+        os.environ.get("ALL_PROXY") or os.environ.get("all_proxy") or ""
@@ -0,0 +1,159 @@
+#!/usr/bin/env python3
+"""
+Rate Limiter
+Token-bucket rate limiting per API provider, with daily request counts kept
+in SQLite so a provider's quota is respected across runs.
+"""
+
+import sqlite3
+import threading
+import time
+from datetime import date
+from pathlib import Path
+from typing import Dict, Optional
+
+
+class QuotaExceeded(Exception):
+    """Raised when a provider's daily request quota has been used up."""
+
+    def __init__(self, provider: str, quota: int):
+        super().__init__(f"Daily quota of {quota} requests for {provider} reached; resume tomorrow")
+        self.provider = provider
+        self.quota = quota
+
+
+class TokenBucket:
+    def __init__(self, rate: float, capacity: float = 1):
+        self.rate = rate
+        self.capacity = max(capacity, 1)
+        self.tokens = self.capacity
+        self.updated = time.monotonic()
+        self.lock = threading.Lock()
+
+    def acquire(self):
+        """Block until a token is available, then take it."""
+        with self.lock:
+            now = time.monotonic()
+            self.tokens = min(self.capacity, self.tokens + (now - self.updated) * self.rate)
+            self.updated = now
+
+            if self.tokens < 1:
+                wait = (1 - self.tokens) / self.rate
+                time.sleep(wait)
+                self.tokens = 1
+                self.updated = time.monotonic()
+
+            self.tokens -= 1
+
+
+class RateLimiter:
+    """Paces requests to each provider and counts them against a daily quota.
+
+    limits maps a provider name to {"requests_per_second", "burst",
+    "daily_quota"}; a missing or null daily_quota means unlimited. Safe to
+    share between threads: refusals and request counts are also kept per
+    thread, so parallel lookups each see only their own.
+    """
+
+    def __init__(self, db_path, limits: Dict[str, Dict]):
+        self.db_path = Path(db_path)
+        self.limits = limits
+        self.buckets = {
+            provider: TokenBucket(limit.get("requests_per_second", 1.0), limit.get("burst", 1))
+            for provider, limit in limits.items()
+            if limit.get("requests_per_second")
+        }
+        self.lock = threading.Lock()
+        self.local = threading.local()
+        self.init_database()
+
+    def init_database(self):
+        conn = sqlite3.connect(self.db_path)
+        conn.execute('''
+            CREATE TABLE IF NOT EXISTS api_usage (
+                provider TEXT NOT NULL,
+                day TEXT NOT NULL,
+                requests INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (provider, day)
+            )
+        ''')
+        conn.commit()
+        conn.close()
+
+    def thread_state(self):
+        """Get the calling thread's refused providers and request counts."""
+        if not hasattr(self.local, "denied"):
+            self.local.denied = set()
+            self.local.requests = {}
+        return self.local
+
+    def get_quota(self, provider: str) -> Optional[int]:
+        return self.limits.get(provider, {}).get("daily_quota")
+
+    def get_usage(self, provider: str, day: Optional[str] = None) -> int:
+        """Get the number of requests made to a provider on a day (default: today)."""
+        conn = sqlite3.connect(self.db_path)
+        row = conn.execute('SELECT requests FROM api_usage WHERE provider = ? AND day = ?',
+                           (provider, day or date.today().isoformat())).fetchone()
+        conn.close()
+        return row[0] if row else 0
+
+    def remaining(self, provider: str) -> Optional[int]:
+        """Get the requests left today, or None if the provider has no quota."""
+        quota = self.get_quota(provider)
+        if quota is None:
+            return None
+        return max(quota - self.get_usage(provider), 0)
+
+    def is_exhausted(self, provider: str) -> bool:
+        return self.remaining(provider) == 0
+
+    def acquire(self, provider: str):
+        """Wait for the provider's rate limit and count one request against its quota.
+
+        Raises QuotaExceeded instead of sending a request past the daily quota.
+        """
+        quota = self.get_quota(provider)
+
+        with self.lock:
+            today = date.today().isoformat()
+            conn = sqlite3.connect(self.db_path)
+            try:
+                row = conn.execute('SELECT requests FROM api_usage WHERE provider = ? AND day = ?',
+                                   (provider, today)).fetchone()
+                if quota is not None and (row[0] if row else 0) >= quota:
+                    self.thread_state().denied.add(provider)
+                    raise QuotaExceeded(provider, quota)
+
+                conn.execute('''
+                    INSERT INTO api_usage (provider, day, requests) VALUES (?, ?, 1)
+                    ON CONFLICT(provider, day) DO UPDATE SET requests = requests + 1
+                ''', (provider, today))
+                conn.commit()
+            finally:
+                conn.close()
+        requests = self.thread_state().requests
+        requests[provider] = requests.get(provider, 0) + 1
+
+        bucket = self.buckets.get(provider)
+        if bucket:
+            bucket.acquire()
+
+    def pop_denied(self) -> set:
+        """Get the providers that refused the calling thread a request since its last call, and reset them."""
+        state = self.thread_state()
+        denied, state.denied = state.denied, set()
+        return denied
+
+    def pop_requests(self) -> Dict[str, int]:
+        """Get the requests the calling thread made per provider since its last call, and reset them."""
+        state = self.thread_state()
+        requests, state.requests = state.requests, {}
+        return requests
+
+    def get_usage_summary(self) -> Dict[str, Dict]:
+        """Get today's usage and quota for every configured provider."""
+        return {
+            provider: {"requests": self.get_usage(provider), "daily_quota": self.get_quota(provider)}
+            for provider in self.limits
+        }
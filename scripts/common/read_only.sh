@@ -0,0 +1,37 @@
+# Read-only mode for the shell scripts, decided as rom_config.py decides it for the
+# Python ones: ROM_BROWSER_READ_ONLY when set, otherwise safety.read_only.
+# Source this file, then guard each write with: ensure_writable "download games" || return 1
+
+READ_ONLY_COMMON_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+
+# Succeed when read-only mode is enabled; the configuration is read once per run
+is_read_only() {
+    if [ -z "${READ_ONLY_MODE:-}" ]; then
+        READ_ONLY_MODE=$(python3 - "$READ_ONLY_COMMON_DIR" <<'EOF'
+import sys
+
+sys.path.insert(0, sys.argv[1])
+from rom_config import RomConfig
+
+print("on" if RomConfig().is_read_only() else "off")
+EOF
+        )
+        if [ "$READ_ONLY_MODE" != "on" ] && [ "$READ_ONLY_MODE" != "off" ]; then
+            echo "Warning: could not read safety.read_only from the configuration; only ROM_BROWSER_READ_ONLY applies" >&2
+            case "$(echo "${ROM_BROWSER_READ_ONLY:-}" | tr '[:upper:]' '[:lower:]' | tr -d '[:space:]')" in
+                1|true|yes|on) READ_ONLY_MODE="on" ;;
+                *) READ_ONLY_MODE="off" ;;
+            esac
+        fi
+    fi
+    [ "$READ_ONLY_MODE" = "on" ]
+}
+
+# Fail, saying so, when read-only mode forbids the given action
+ensure_writable() {
+    if is_read_only; then
+        echo -e "\033[1;33mRead-only mode is enabled; refusing to $1\033[0m" >&2
+        return 1
+    fi
+    return 0
+}
@@ -0,0 +1,147 @@
+#!/usr/bin/env python3
+"""
+Remote ZIP
+Lists the files in a ZIP archive on a web server without downloading it.
+A ZIP ends with its central directory, the table of every member's name,
+sizes, CRC32 and date, and the end-of-central-directory record that says
+where the table starts. Both are read with HTTP range requests, so looking
+into a multi-gigabyte disc set costs a few kilobytes:
+
+    GET bytes=-65558     the tail: the end record (and its comment, up to 64 KiB)
+    GET bytes=a-b        the central directory, unless the tail held it already
+
+ZIP64 archives, those over 4 GiB or 65535 members, are read the same way.
+The members' contents are not fetched, so only the CRC32 and size the
+archive stores are known, not MD5 or SHA1.
+"""
+
+import io
+import zipfile
+from datetime import datetime
+from typing import Dict, List, Optional, Tuple
+
+# The end record is 22 bytes plus a comment of up to 65535; the ZIP64 locator sits right before it
+TAIL_SIZE = 22 + 65535 + 1
+
+# Compression methods by their number in the ZIP format
+METHODS = {
+    zipfile.ZIP_STORED: "stored",
+    zipfile.ZIP_DEFLATED: "deflate",
+    9: "deflate64",
+    zipfile.ZIP_BZIP2: "bzip2",
+    zipfile.ZIP_LZMA: "lzma",
+    93: "zstd",
+    95: "xz",
+}
+
+
+class RangeFile(io.RawIOBase):
+    """A read-only, seekable view of a remote file that fetches what is read with range requests.
+
+    Fetched ranges are kept, so zipfile's small reads of the end record are
+    served from the tail fetched first. Raises ValueError when the server
+    ignores range requests, since the whole file would come back instead.
+    """
+
+    def __init__(self, mirrors, url: str, timeout: float = 30):
+        self.mirrors = mirrors
+        self.url = url
+        self.timeout = timeout
+        self.position = 0
+        self.chunks: List[Tuple[int, bytes]] = []
+        self.requests = 0
+        start, data, self.size = self.fetch(f"bytes=-{TAIL_SIZE}")
+        self.chunks.append((start, data))
+
+    def fetch(self, byte_range: str) -> Tuple[int, bytes, int]:
+        """Make one range request. Returns where the data starts, the data and the file's size."""
+        response = self.mirrors.request('GET', self.url, headers={'Range': byte_range}, stream=True,
+                                        timeout=self.timeout)
+        self.requests += 1
+        try:
+            response.raise_for_status()
+            if response.status_code != 206:
+                raise ValueError(f"{self.url}: the server does not support range requests")
+            # Content-Range: bytes <start>-<end>/<size>
+            content_range = response.headers.get('content-range', '')
+            try:
+                span, size = content_range.split(' ', 1)[1].split('/')
+                start = int(span.split('-')[0])
+                return start, response.content, int(size)
+            except (IndexError, ValueError):
+                raise ValueError(f"{self.url}: unexpected Content-Range {content_range!r}")
+        finally:
+            response.close()
+
+    def readable(self) -> bool:
+        return True
+
+    def seekable(self) -> bool:
+        return True
+
+    def tell(self) -> int:
+        return self.position
+
+    def seek(self, offset: int, whence: int = io.SEEK_SET) -> int:
+        base = {io.SEEK_SET: 0, io.SEEK_CUR: self.position, io.SEEK_END: self.size}[whence]
+        self.position = max(base + offset, 0)
+        return self.position
+
+    def read(self, size: int = -1) -> bytes:
+        end = self.size if size is None or size < 0 else min(self.position + size, self.size)
+        if end <= self.position:
+            return b""
+        for start, data in self.chunks:
+            if start <= self.position and end <= start + len(data):
+                result = data[self.position - start:end - start]
+                break
+        else:
+            start, result, _ = self.fetch(f"bytes={self.position}-{end - 1}")
+            self.chunks.append((start, result))
+            result = result[self.position - start:end - start]
+        self.position += len(result)
+        return result
+
+    def readinto(self, buffer) -> int:
+        data = self.read(len(buffer))
+        buffer[:len(data)] = data
+        return len(data)
+
+
+def member_info(info: zipfile.ZipInfo) -> Dict:
+    """Describe one member of an archive as inspect_archive lists it."""
+    try:
+        modified: Optional[str] = datetime(*info.date_time).isoformat()
+    except ValueError:
+        modified = None
+    return {
+        "name": info.filename,
+        "is_dir": info.is_dir(),
+        "size": info.file_size,
+        "compressed_size": info.compress_size,
+        "crc32": None if info.is_dir() else f"{info.CRC:08x}",
+        "method": METHODS.get(info.compress_type, str(info.compress_type)),
+        "modified": modified,
+    }
+
+
+def inspect_archive(mirrors, url: str) -> Dict:
+    """List a remote ZIP archive: {url, size, comment, members, requests}.
+
+    Raises ValueError when it is not a ZIP archive or the server cannot
+    serve parts of it, and requests' errors when it cannot be reached.
+    """
+    remote = RangeFile(mirrors, url)
+    try:
+        with zipfile.ZipFile(remote) as archive:
+            members = [member_info(info) for info in archive.infolist()]
+            comment = archive.comment.decode('utf-8', 'replace')
+    except zipfile.BadZipFile as e:
+        raise ValueError(f"{url}: not a ZIP archive ({e})")
+    return {
+        "url": url,
+        "size": remote.size,
+        "comment": comment,
+        "members": members,
+        "requests": remote.requests,
+    }
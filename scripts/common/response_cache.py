@@ -0,0 +1,107 @@
+#!/usr/bin/env python3
+"""
+Response Cache
+Keeps archive directory listings and metadata provider responses on disk in
+the cache directory, so browsing the same folders or scraping the same games
+again does not hit the network. Each kind of entry expires after its own time
+to live, set under cache in rom_browser.json (0 turns that cache off):
+
+    platform_list_hours       dataset folders listing their platforms
+    listing_hours             platform folders and everything below them
+    provider_response_hours   metadata provider API responses
+"""
+
+import hashlib
+import json
+import shutil
+import time
+import urllib.parse
+from pathlib import Path
+from typing import Any, Dict, Optional
+
+PLATFORMS = "platforms"
+LISTINGS = "listings"
+PROVIDERS = "providers"
+
+TTL_SETTINGS = {
+    PLATFORMS: "cache.platform_list_hours",
+    LISTINGS: "cache.listing_hours",
+    PROVIDERS: "cache.provider_response_hours",
+}
+
+
+def cache_root(config) -> Path:
+    return config.get_path('paths.cache_directory') / "responses"
+
+
+class ResponseCache:
+    """Entries of one kind, stored as JSON files named by a hash of their key."""
+
+    def __init__(self, config, kind: str):
+        self.config = config
+        self.directory = cache_root(config) / kind
+        self.ttl_seconds = float(config.get(TTL_SETTINGS[kind], 0) or 0) * 3600
+
+    def path_for(self, key: str) -> Path:
+        # Keys can hold API credentials, so only their hash is written to disk
+        return self.directory / f"{hashlib.sha1(key.encode('utf-8')).hexdigest()}.json"
+
+    def get(self, key: str) -> Optional[Any]:
+        """Get a stored value, or None when there is none or it has expired."""
+        if self.ttl_seconds <= 0:
+            return None
+        entry = self.stored(key)
+        if entry is None:
+            return None
+        if time.time() - entry.get('stored', 0) > self.ttl_seconds:
+            return None
+        return entry.get('value')
+
+    def stored(self, key: str) -> Optional[Dict]:
+        """Get a stored entry, {stored, value}, however old it is; None when there is none."""
+        try:
+            with open(self.path_for(key), 'r', encoding='utf-8') as f:
+                return json.load(f)
+        except (OSError, ValueError):
+            return None
+
+    def put(self, key: str, value: Any):
+        if self.ttl_seconds <= 0 or self.config.is_read_only():
+            return
+        self.directory.mkdir(parents=True, exist_ok=True)
+        path = self.path_for(key)
+        temp_path = path.with_suffix('.tmp')
+        with open(temp_path, 'w', encoding='utf-8') as f:
+            json.dump({"stored": time.time(), "value": value}, f)
+        temp_path.replace(path)
+
+
+def cached_listing(config, path: str) -> Optional[Dict]:
+    """Get the cached listing of an archive folder, {stored, value}, however old; keyed as HttpIndexSource stores it."""
+    path = path.strip('/')
+    url = config.get_archive_url() + (urllib.parse.quote(path) + '/' if path else '')
+    return ResponseCache(config, PLATFORMS if path.count('/') == 0 else LISTINGS).stored(url)
+
+
+def listed_size(config, url: str) -> Optional[int]:
+    """Get the size of an archive file from the cached listing of its folder; None when not cached."""
+    root = config.get_archive_url()
+    if not url.startswith(root):
+        return None
+    folder, _, name = urllib.parse.unquote(url[len(root):]).rpartition('/')
+    entry = cached_listing(config, folder)
+    for file in entry['value'] if entry else []:
+        if file.get('name') == name:
+            return file.get('size')
+    return None
+
+
+def clear_response_caches(config) -> Dict[str, int]:
+    """Delete every stored listing and provider response. Returns how many were deleted per kind."""
+    config.ensure_writable("clear the caches")
+    removed = {}
+    for kind in TTL_SETTINGS:
+        directory = cache_root(config) / kind
+        removed[kind] = len(list(directory.glob('*.json'))) if directory.exists() else 0
+        shutil.rmtree(directory, ignore_errors=True)
+    return removed
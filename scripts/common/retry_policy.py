@@ -0,0 +1,113 @@
+#!/usr/bin/env python3
+"""
+Retry Policy
+Retries network calls with exponential backoff and jitter when they fail in a
+way that is likely to be temporary: timeouts, dropped connections, 429 and 5xx.
+"""
+
+import email.utils
+import random
+import time
+from datetime import datetime, timezone
+from typing import Callable, Optional
+
+import requests
+
+from http_client import http_session
+
+# HTTP statuses worth retrying; anything else fails straight away
+RETRY_STATUS_CODES = frozenset({408, 425, 429, 500, 502, 503, 504})
+
+
+class TransientError(Exception):
+    """Raised by callers for failures that should be retried, e.g. a truncated download."""
+
+
+def parse_retry_after(value: Optional[str]) -> Optional[float]:
+    """Get the delay in seconds from a Retry-After header (seconds or HTTP date)."""
+    if not value:
+        return None
+    try:
+        return max(float(value), 0.0)
+    except ValueError:
+        pass
+    try:
+        when = email.utils.parsedate_to_datetime(value)
+    except (TypeError, ValueError):
+        return None
+    if when.tzinfo is None:
+        when = when.replace(tzinfo=timezone.utc)
+    return max((when - datetime.now(timezone.utc)).total_seconds(), 0.0)
+
+
+class RetryPolicy:
+    def __init__(self, attempts: int = 4, backoff: float = 1.0, max_backoff: float = 30.0,
+                 jitter: float = 0.5, log: Callable[[str], None] = print,
+                 session: Optional[requests.Session] = None):
+        self.attempts = max(int(attempts), 1)
+        self.backoff = backoff
+        self.max_backoff = max_backoff
+        self.jitter = jitter
+        self.log = log
+        self.session = session
+
+    @classmethod
+    def from_config(cls, config, log: Callable[[str], None] = print) -> "RetryPolicy":
+        """Build the policy from the network section of a RomConfig; its requests go through the shared session."""
+        return cls(
+            attempts=config.get('network.retry_attempts', 4),
+            backoff=config.get('network.retry_backoff_seconds', 1.0),
+            max_backoff=config.get('network.retry_max_backoff_seconds', 30.0),
+            log=log,
+            session=http_session(config)
+        )
+
+    def delay(self, attempt: int, retry_after: Optional[float] = None) -> float:
+        """Get the wait before the next attempt; a server's Retry-After takes precedence."""
+        if retry_after is not None:
+            return min(retry_after, self.max_backoff)
+        delay = min(self.backoff * (2 ** (attempt - 1)), self.max_backoff)
+        return delay * (1 + random.uniform(-self.jitter, self.jitter))
+
+    def is_retryable(self, error: Exception) -> bool:
+        if isinstance(error, (TransientError, requests.Timeout, requests.ConnectionError,
+                              requests.exceptions.ChunkedEncodingError)):
+            return True
+        if isinstance(error, requests.HTTPError) and error.response is not None:
+            return error.response.status_code in RETRY_STATUS_CODES
+        return False
+
+    def call(self, func: Callable, *args, **kwargs):
+        """Call func, retrying on transient failures.
+
+        A requests.Response with a retryable status is retried too; the last
+        response or error is returned or raised once the attempts run out.
+        """
+        for attempt in range(1, self.attempts + 1):
+            try:
+                result = func(*args, **kwargs)
+            except Exception as e:
+                if attempt == self.attempts or not self.is_retryable(e):
+                    raise
+                response = getattr(e, 'response', None)
+                retry_after = parse_retry_after(response.headers.get('Retry-After')) if response is not None else None
+                reason = str(e)
+            else:
+                if not (isinstance(result, requests.Response) and result.status_code in RETRY_STATUS_CODES
+                        and attempt < self.attempts):
+                    return result
+                retry_after = parse_retry_after(result.headers.get('Retry-After'))
+                reason = f"HTTP {result.status_code}"
+                result.close()
+
+            delay = self.delay(attempt, retry_after)
+            self.log(f"Attempt {attempt}/{self.attempts} failed ({reason}), retrying in {delay:.1f}s")
+            time.sleep(delay)
+
+    def get(self, url: str, session=None, **kwargs) -> requests.Response:
+        """session.get (by default the policy's session, else requests.get) with retries."""
+        return self.call((session or self.session or requests).get, url, **kwargs)
+
+    def head(self, url: str, session=None, **kwargs) -> requests.Response:
+        """session.head (by default the policy's session, else requests.head) with retries."""
+        return self.call((session or self.session or requests).head, url, **kwargs)
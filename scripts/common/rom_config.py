@@ -0,0 +1,335 @@
+#!/usr/bin/env python3
+"""
+ROM Browser Configuration
+Shared settings for the ROM sourcing and game management scripts and the GUI.
+
+Settings are layered: built-in defaults, then config/rom_browser.json in the
+repository, then the user's rom-browser.toml from the platform config
+directory (~/.config/rom-browser on Linux, ~/Library/Application Support/
+rom-browser on macOS, %APPDATA%\\rom-browser on Windows). --config, or the
+ROM_BROWSER_CONFIG environment variable, names another TOML (or JSON) file
+to use instead of the user's.
+
+Paths left unset default to the platform's per-user directories (see
+app_dirs.py): games.db, covers and downloads in the data directory, temp
+files in the cache directory and logs in the log directory.
+"""
+
+import argparse
+import contextlib
+import copy
+import json
+import os
+import sys
+from pathlib import Path
+
+from app_dirs import user_cache_dir, user_config_dir, user_data_dir, user_log_dir
+from atomic_files import read_with_recovery
+
+try:
+    import tomllib
+except ImportError:  # Python < 3.11
+    tomllib = None
+
+REPO_ROOT = Path(__file__).resolve().parent.parent.parent
+CONFIG_FILE = REPO_ROOT / "config" / "rom_browser.json"
+USER_CONFIG_NAME = "rom-browser.toml"
+
+# Set by --config so child processes read the same file
+CONFIG_ENV = "ROM_BROWSER_CONFIG"
+
+# Set by --read-only so child processes and every module see the same mode
+READ_ONLY_ENV = "ROM_BROWSER_READ_ONLY"
+
+# Defaults of unset paths: a per-user directory, or a name inside another path
+DEFAULT_DIRECTORIES = {
+    "paths.data_directory": user_data_dir,
+    "paths.cache_directory": user_cache_dir,
+    "paths.log_directory": user_log_dir,
+}
+DEFAULT_LOCATIONS = {
+    "paths.games_db": ("paths.data_directory", "games.db"),
+    "paths.covers_directory": ("paths.data_directory", "covers"),
+    "paths.download_directory": ("paths.data_directory", "downloads"),
+    "paths.temp_directory": ("paths.cache_directory", "temp"),
+}
+
+# Overrides network.archive_url, e.g. to point every script at the mock archive
+ARCHIVE_URL_ENV = "ROM_BROWSER_ARCHIVE_URL"
+
+
+class ReadOnlyError(Exception):
+    """Raised when a write is attempted while read-only mode is enabled."""
+
+
+def set_read_only(enabled=True):
+    """Enable or disable read-only mode for this process and its children."""
+    os.environ[READ_ONLY_ENV] = "1" if enabled else "0"
+
+
+def set_config_file(path):
+    """Use another user config file in this process and its children."""
+    os.environ[CONFIG_ENV] = str(Path(path).expanduser().resolve())
+
+
+def user_config_file():
+    """Get the user config file: --config / ROM_BROWSER_CONFIG, else rom-browser.toml in the config directory."""
+    return Path(os.getenv(CONFIG_ENV) or user_config_dir() / USER_CONFIG_NAME)
+
+
+class ConfigFileAction(argparse.Action):
+    """--config: applied as soon as it is parsed, so every RomConfig created afterwards reads the file."""
+
+    def __call__(self, parser, namespace, values, option_string=None):
+        if not Path(values).expanduser().is_file():
+            parser.error(f"config file not found: {values}")
+        set_config_file(values)
+        setattr(namespace, self.dest, values)
+
+
+def add_config_argument(parser):
+    parser.add_argument('--config', action=ConfigFileAction, metavar='FILE',
+                        help=f'Settings file to use instead of {user_config_dir() / USER_CONFIG_NAME}')
+
+
+def parse_config_file(path):
+    path = Path(path)
+    if path.suffix.lower() == '.json':
+        with open(path, 'r', encoding='utf-8') as f:
+            return json.load(f)
+    if tomllib is None:
+        raise RuntimeError(f"reading {path} needs Python 3.11 or later")
+    with open(path, 'rb') as f:
+        return tomllib.load(f)
+
+
+def read_config_file(path):
+    """Read a TOML or JSON settings file, restoring its previous version if it is damaged."""
+    return read_with_recovery(path, parse_config_file, log=lambda message: print(message, file=sys.stderr))
+
+
+class RomConfig:
+    def __init__(self, config_file=None):
+        # An explicit file is the only one read, so tests and tools stay isolated from the user's settings
+        self.config_file = Path(config_file) if config_file else CONFIG_FILE
+        self.user_config_file = None if config_file else user_config_file()
+        self.loaded_files = []
+        # Set while a daemon runs the call of a read-only process with this config (see daemon_ipc)
+        self.caller_read_only = False
+        self.config = self.load_config()
+
+    def load_config(self):
+        """Load configuration from file, filling in defaults for missing keys."""
+        config = self.get_default_config()
+        for path in (self.config_file, self.user_config_file):
+            if path is None or not path.exists():
+                continue
+            try:
+                self.merge(config, read_config_file(path))
+                self.loaded_files.append(str(path))
+            except Exception as e:
+                print(f"Error loading config {path}: {e}", file=sys.stderr)
+        return config
+
+    def merge(self, base, overrides):
+        """Recursively merge overrides into base."""
+        for key, value in overrides.items():
+            if isinstance(value, dict) and isinstance(base.get(key), dict):
+                self.merge(base[key], value)
+            else:
+                base[key] = value
+        return base
+
+    def get_default_config(self):
+        """Get default configuration."""
+        return copy.deepcopy({
+            "matching": {
+                "region_priority": ["USA", "World", "Europe", "Asia", "Japan"],
+                "language_priority": ["En"],
+                "prefer_highest_revision": True,
+                "avoid_tags": ["Beta", "Proto", "Demo", "Sample", "Kiosk"],
+                "transliteration": True
+            },
+            "safety": {
+                "read_only": False,
+                "profile": "admin"
+            },
+            "paths": {
+                "dat_directory": "config/dats",
+                "screenscraper_config": "config/screenscraper_config.json",
+                "library_db": "config/library.db",
+                "icon_directory": "config/icons",
+                "queue_templates": "config/queue_templates",
+                "filter_presets": "config/filter_presets",
+                "data_directory": None,
+                "cache_directory": None,
+                "log_directory": None,
+                "games_db": None,
+                "covers_directory": None,
+                "download_directory": None,
+                "temp_directory": None,
+                "roots": {}
+            },
+            "api_keys": {
+                "screenscraper_username": "",
+                "screenscraper_password": "",
+                "igdb_access_token": "",
+                "rawg": ""
+            },
+            "library": {
+                "url_check_interval_hours": 168,
+                "speed_history_days": 90,
+                "rom_directories": [],
+                "game_directories": [],
+                "auto_scan": False,
+                "scan_interval_minutes": 30,
+                "dedupe": "off"
+            },
+            "network": {
+                "archive_url": "https://myrient.erista.me/files/",
+                "mirrors": [],
+                "probe_mirrors": True,
+                "mirror_probe_timeout_seconds": 5.0,
+                "proxy_url": "",
+                "no_proxy": "",
+                "retry_attempts": 4,
+                "retry_backoff_seconds": 1.0,
+                "retry_max_backoff_seconds": 30.0,
+                "user_agent": "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36",
+                "timeout_seconds": 30.0,
+                "max_connections": 10
+            },
+            "sources": {
+                "internet_archive": {
+                    "enabled": True,
+                    "base_url": "https://archive.org",
+                    "items": []
+                },
+                "local": {
+                    "enabled": True,
+                    "directories": []
+                },
+                "redump": {
+                    "cue_url": "http://redump.org/cues/",
+                    "systems": {}
+                }
+            },
+            "plugins": {
+                "metadata_providers": [],
+                "post_download": []
+            },
+            "downloads": {
+                "path_template": "{platform}/{filename}",
+                "platform_templates": {},
+                "checkpoint_piece_mb": 8,
+                "max_concurrent": 3,
+                "free_space_reserve_mb": 512,
+                "verify_checksums": True,
+                "stale_partial_hours": 24
+            },
+            "cache": {
+                "platform_list_hours": 24,
+                "listing_hours": 6,
+                "provider_response_hours": 72,
+                "cue_pack_days": 30,
+                "max_size_mb": 500,
+                "gc_interval_hours": 24
+            },
+            "filter_presets": {
+                "default": [],
+                "platforms": {}
+            },
+            "mame": {
+                "set_type": "split"
+            },
+            "compatibility": {
+                "video_standards": [],
+                "console_regions": [],
+                "bios_regions": {}
+            },
+            "emulators": {},
+            "daemon": {
+                "mode": "auto"
+            },
+            "sync": {
+                "devices": [],
+                "profiles": {
+                    "full": {"roms": True, "gamelists": True, "artwork": True},
+                    "artwork": {"roms": False, "gamelists": True, "artwork": True}
+                },
+                "system_folders": {},
+                "verify_percent": 2
+            },
+            "jobs": {
+                "cpu_threads": 0,
+                "io_concurrency": 2,
+                "io_mb_per_second": 0
+            },
+            "status": {
+                "pinned_platforms": []
+            }
+        })
+
+    def get(self, path, default=None):
+        """Get a value by dotted path, e.g. 'matching.region_priority'."""
+        value = self.config
+        for key in path.split('.'):
+            if not isinstance(value, dict) or key not in value:
+                return default
+            value = value[key]
+        return value
+
+    def get_path(self, path):
+        """Get a configured path, resolving relative values against the repository root.
+
+        Unset paths with a default (DEFAULT_DIRECTORIES, DEFAULT_LOCATIONS) get it.
+        """
+        value = self.get(path)
+        if value is None and path in DEFAULT_DIRECTORIES:
+            return DEFAULT_DIRECTORIES[path]()
+        if value is None and path in DEFAULT_LOCATIONS:
+            base, name = DEFAULT_LOCATIONS[path]
+            return self.get_path(base) / name
+        if value is None:
+            return None
+        value = Path(value).expanduser()
+        return value if value.is_absolute() else REPO_ROOT / value
+
+    def get_archive_url(self):
+        """Get the root URL of the ROM archive, always ending in a slash."""
+        url = os.getenv(ARCHIVE_URL_ENV) or self.get('network.archive_url') or "https://myrient.erista.me/files/"
+        return url.rstrip('/') + '/'
+
+    def is_read_only(self):
+        """Check whether downloads, deletions and database writes are disabled."""
+        if self.caller_read_only:
+            return True
+        env = os.getenv(READ_ONLY_ENV)
+        if env is not None:
+            return env.strip().lower() in ("1", "true", "yes", "on")
+        return bool(self.get('safety.read_only', False))
+
+    def ensure_writable(self, action):
+        """Raise ReadOnlyError if read-only mode forbids the given action."""
+        if self.is_read_only():
+            raise ReadOnlyError(f"Read-only mode is enabled; refusing to {action}")
+
+    @contextlib.contextmanager
+    def read_only_for_caller(self, read_only):
+        """Make this config read-only for the duration when the process a call came from is."""
+        previous = self.caller_read_only
+        self.caller_read_only = previous or bool(read_only)
+        try:
+            yield
+        finally:
+            self.caller_read_only = previous
+
+
+if __name__ == "__main__":
+    parser = argparse.ArgumentParser(description='Show the effective settings')
+    add_config_argument(parser)
+    parser.parse_args()
+
+    config = RomConfig()
+    print(json.dumps(config.config, indent=2))
+    print(f"Loaded: {', '.join(config.loaded_files) or 'defaults only'}", file=sys.stderr)
@@ -0,0 +1,234 @@
+#!/usr/bin/env python3
+"""
+ROM Icons
+Extracts the icon or banner that some formats embed, to use as artwork when
+no cover could be scraped:
+
+    .nds          32x32 icon (4bpp tiles, BGR555 palette)
+    .smdh/.3dsx/.cia  48x48 SMDH icon (RGB565, Morton-ordered tiles)
+    .iso/.gcm     96x32 GameCube banner from opening.bnr (RGB5A3 tiles)
+    .nro          Switch homebrew JPEG icon from the asset section
+
+Files inside a ZIP are read in place. Images are written as PNG (or the
+embedded JPEG) without needing Pillow.
+"""
+
+import hashlib
+import struct
+import zipfile
+import zlib
+from pathlib import Path
+from typing import Dict, List, Optional, Tuple
+
+NDS_EXTENSIONS = ('.nds', '.dsi')
+SMDH_EXTENSIONS = ('.smdh', '.3dsx', '.cia')
+GAMECUBE_EXTENSIONS = ('.iso', '.gcm')
+NRO_EXTENSIONS = ('.nro',)
+ICON_EXTENSIONS = NDS_EXTENSIONS + SMDH_EXTENSIONS + GAMECUBE_EXTENSIONS + NRO_EXTENSIONS
+
+GAMECUBE_MAGIC = 0xC2339F3D
+Pixel = Tuple[int, int, int, int]
+
+
+def read_at(f, offset: int, size: int) -> bytes:
+    f.seek(offset)
+    data = f.read(size)
+    if len(data) != size:
+        raise ValueError("truncated file")
+    return data
+
+
+def scale(value: int, bits: int) -> int:
+    return value * 255 // ((1 << bits) - 1)
+
+
+def write_png(width: int, height: int, pixels: List[Pixel]) -> bytes:
+    """Encode RGBA pixels (row-major) as a PNG."""
+    rows = b''.join(
+        b'\x00' + b''.join(bytes(pixel) for pixel in pixels[y * width:(y + 1) * width])
+        for y in range(height)
+    )
+
+    def chunk(kind: bytes, data: bytes) -> bytes:
+        return struct.pack('>I', len(data)) + kind + data + struct.pack('>I', zlib.crc32(kind + data) & 0xFFFFFFFF)
+
+    header = struct.pack('>IIBBBBB', width, height, 8, 6, 0, 0, 0)
+    return b'\x89PNG\r\n\x1a\n' + chunk(b'IHDR', header) + chunk(b'IDAT', zlib.compress(rows, 9)) + chunk(b'IEND', b'')
+
+
+def decode_nds(f) -> Optional[bytes]:
+    icon_offset = struct.unpack('<I', read_at(f, 0x68, 4))[0]
+    if not icon_offset:
+        return None
+    bitmap = read_at(f, icon_offset + 0x20, 512)
+    palette_data = read_at(f, icon_offset + 0x220, 32)
+
+    palette = []
+    for i in range(16):
+        color = struct.unpack_from('<H', palette_data, i * 2)[0]
+        palette.append((scale(color & 0x1F, 5), scale((color >> 5) & 0x1F, 5), scale((color >> 10) & 0x1F, 5),
+                        0 if i == 0 else 255))
+
+    pixels = []
+    for y in range(32):
+        for x in range(32):
+            tile = (y // 8) * 4 + x // 8
+            within = (y % 8) * 8 + x % 8
+            byte = bitmap[tile * 32 + within // 2]
+            pixels.append(palette[byte >> 4 if within % 2 else byte & 0x0F])
+    return write_png(32, 32, pixels)
+
+
+def decode_smdh(data: bytes) -> Optional[bytes]:
+    if data[:4] != b'SMDH' or len(data) < 0x24C0 + 0x1200:
+        return None
+
+    pixels: List[Pixel] = [(0, 0, 0, 0)] * (48 * 48)
+    offset = 0x24C0
+    for tile_y in range(0, 48, 8):
+        for tile_x in range(0, 48, 8):
+            for k in range(64):
+                # Pixels within a tile are in Morton (Z) order
+                x = (k & 1) | ((k >> 1) & 2) | ((k >> 2) & 4)
+                y = ((k >> 1) & 1) | ((k >> 2) & 2) | ((k >> 3) & 4)
+                value = struct.unpack_from('<H', data, offset)[0]
+                offset += 2
+                pixels[(tile_y + y) * 48 + tile_x + x] = (
+                    scale((value >> 11) & 0x1F, 5), scale((value >> 5) & 0x3F, 6), scale(value & 0x1F, 5), 255
+                )
+    return write_png(48, 48, pixels)
+
+
+def find_smdh(f, extension: str) -> Optional[bytes]:
+    if extension == '.smdh':
+        f.seek(0)
+        return f.read(0x36C0)
+
+    if extension == '.3dsx':
+        if read_at(f, 0, 4) != b'3DSX':
+            return None
+        header_size = struct.unpack('<H', read_at(f, 4, 2))[0]
+        if header_size <= 0x20:
+            return None
+        smdh_offset, smdh_size = struct.unpack('<II', read_at(f, 0x20, 8))
+        return read_at(f, smdh_offset, smdh_size) if smdh_size else None
+
+    # CIA: sections follow the header, each aligned to 64 bytes; SMDH sits 0x400 into the meta section
+    header_size, _, _, cert_size, ticket_size, tmd_size, meta_size, content_size = struct.unpack(
+        '<IHHIIIIQ', read_at(f, 0, 0x20))
+    if meta_size < 0x400 + 0x36C0:
+        return None
+
+    def align(value: int) -> int:
+        return (value + 63) // 64 * 64
+
+    offset = align(header_size)
+    for size in (cert_size, ticket_size, tmd_size, content_size):
+        offset = align(offset + size)
+    return read_at(f, offset + 0x400, 0x36C0)
+
+
+def decode_gamecube(f) -> Optional[bytes]:
+    if struct.unpack('>I', read_at(f, 0x1C, 4))[0] != GAMECUBE_MAGIC:
+        return None
+
+    # Find opening.bnr in the file system table
+    fst_offset = struct.unpack('>I', read_at(f, 0x424, 4))[0]
+    entry_count = struct.unpack('>I', read_at(f, fst_offset + 8, 4))[0]
+    entries = read_at(f, fst_offset, entry_count * 12)
+    strings_offset = fst_offset + entry_count * 12
+
+    banner_offset = None
+    for i in range(1, entry_count):
+        flags_name, file_offset, _ = struct.unpack_from('>III', entries, i * 12)
+        if flags_name >> 24:
+            continue
+        f.seek(strings_offset + (flags_name & 0xFFFFFF))
+        if f.read(12) == b'opening.bnr\x00':
+            banner_offset = file_offset
+            break
+    if banner_offset is None:
+        return None
+
+    if read_at(f, banner_offset, 4) not in (b'BNR1', b'BNR2'):
+        return None
+    image = read_at(f, banner_offset + 0x20, 96 * 32 * 2)
+
+    pixels: List[Pixel] = [(0, 0, 0, 0)] * (96 * 32)
+    offset = 0
+    for tile_y in range(0, 32, 4):
+        for tile_x in range(0, 96, 4):
+            for y in range(4):
+                for x in range(4):
+                    value = struct.unpack_from('>H', image, offset)[0]
+                    offset += 2
+                    if value & 0x8000:
+                        pixel = (scale((value >> 10) & 0x1F, 5), scale((value >> 5) & 0x1F, 5), scale(value & 0x1F, 5), 255)
+                    else:
+                        pixel = (scale((value >> 8) & 0xF, 4), scale((value >> 4) & 0xF, 4), scale(value & 0xF, 4),
+                                 scale((value >> 12) & 0x7, 3))
+                    pixels[(tile_y + y) * 96 + tile_x + x] = pixel
+    return write_png(96, 32, pixels)
+
+
+def find_nro_icon(f) -> Optional[bytes]:
+    if read_at(f, 0x10, 4) != b'NRO0':
+        return None
+    nro_size = struct.unpack('<I', read_at(f, 0x18, 4))[0]
+    if read_at(f, nro_size, 4) != b'ASET':
+        return None
+    icon_offset, icon_size = struct.unpack('<QQ', read_at(f, nro_size + 8, 16))
+    return read_at(f, nro_size + icon_offset, icon_size) if icon_size else None
+
+
+def extract_from(f, extension: str) -> Optional[Dict]:
+    """Extract the icon of an open ROM file. Returns {"data", "ext", "format"} or None."""
+    if extension in NDS_EXTENSIONS:
+        data, ext, kind = decode_nds(f), 'png', 'nds'
+    elif extension in SMDH_EXTENSIONS:
+        smdh = find_smdh(f, extension)
+        data, ext, kind = (decode_smdh(smdh) if smdh else None), 'png', 'smdh'
+    elif extension in GAMECUBE_EXTENSIONS:
+        data, ext, kind = decode_gamecube(f), 'png', 'gamecube'
+    elif extension in NRO_EXTENSIONS:
+        data, ext, kind = find_nro_icon(f), 'jpg', 'nro'
+    else:
+        return None
+    return {"data": data, "ext": ext, "format": kind} if data else None
+
+
+def extract_icon(path) -> Optional[Dict]:
+    """Extract the embedded icon of a ROM, or of the first ROM with one inside a ZIP."""
+    path = Path(path)
+    try:
+        if zipfile.is_zipfile(path):
+            with zipfile.ZipFile(path) as archive:
+                for info in archive.infolist():
+                    extension = Path(info.filename).suffix.lower()
+                    if extension in ICON_EXTENSIONS:
+                        with archive.open(info) as member:
+                            icon = extract_from(member, extension)
+                        if icon:
+                            return icon
+            return None
+
+        if path.suffix.lower() not in ICON_EXTENSIONS:
+            return None
+        with open(path, 'rb') as f:
+            return extract_from(f, path.suffix.lower())
+    except (OSError, ValueError, struct.error, zipfile.BadZipFile):
+        return None
+
+
+def save_icon(path, icon_dir) -> Optional[Path]:
+    """Extract a ROM's icon into icon_dir, named after the ROM's path. Returns the image path."""
+    icon = extract_icon(path)
+    if not icon:
+        return None
+
+    icon_dir = Path(icon_dir)
+    icon_dir.mkdir(parents=True, exist_ok=True)
+    key = hashlib.sha1(str(Path(path).resolve()).encode('utf-8')).hexdigest()[:16]
+    icon_path = icon_dir / f"{Path(path).stem[:60]}.{key}.{icon['ext']}"
+    icon_path.write_bytes(icon['data'])
+    return icon_path
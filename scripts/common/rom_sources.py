@@ -0,0 +1,365 @@
+#!/usr/bin/env python3
+"""
+ROM Sources
+The places ROMs can be browsed and downloaded from, behind one interface so
+the scripts do not care which backend they talk to.
+
+A source addresses its contents with '/'-separated paths relative to its
+root; directory paths end in '/'. Listings are dicts with "name", "path",
+"is_dir" and "size" (None when the backend does not say); files add their
+"modified" date where the backend has one (an HTTP index may not show it).
+
+New backends subclass RomSource and are registered in SOURCE_TYPES.
+"""
+
+import urllib.parse
+from abc import ABC, abstractmethod
+from datetime import datetime
+from pathlib import Path
+from typing import Dict, List, Optional
+
+from archive_index import parse_index
+from mirrors import MirrorSet
+from response_cache import LISTINGS, PLATFORMS, ResponseCache
+from retry_policy import RetryPolicy
+from romignore import RomIgnore
+
+ARCHIVE_ORG_URL = "https://archive.org"
+
+# Files archive.org generates for every item; only the uploaded originals are offered
+DERIVED_SUFFIXES = ('_meta.xml', '_files.xml', '_meta.sqlite', '_reviews.xml', '.torrent')
+
+def format_size(size: Optional[int]) -> Optional[str]:
+    if size is None:
+        return None
+    value = float(size)
+    for unit in ('B', 'KB', 'MB'):
+        if value < 1024:
+            return f"{size} B" if unit == 'B' else f"{value:.1f} {unit}"
+        value /= 1024
+    return f"{value:.1f} GB"
+
+
+def make_entry(name: str, path: str, is_dir: bool, size: Optional[int] = None, **extra) -> Dict:
+    return dict({"name": name, "path": path, "is_dir": is_dir, "size": size}, **extra)
+
+
+class RomSource(ABC):
+    kind = ""
+
+    def __init__(self, name: str):
+        self.name = name
+
+    @abstractmethod
+    def list_roots(self) -> List[Dict]:
+        """List the top level of the source (datasets, items, folders)."""
+
+    @abstractmethod
+    def list_dir(self, path: str) -> List[Dict]:
+        """List a directory, directories first, each group sorted by name."""
+
+    @abstractmethod
+    def file_url(self, path: str) -> str:
+        """Get the URL a file is downloaded from."""
+
+    def metadata(self, path: str) -> Dict:
+        """Get what the source knows about a file: size, a validator and checksums."""
+        return {"size": None, "validator": None, "sha1": None, "md5": None}
+
+    def path_of(self, url: str) -> Optional[str]:
+        """Get the source path of one of its URLs, or None if the URL is not from this source."""
+        return None
+
+    def list_files(self, path: str, extensions=None) -> List[str]:
+        """Get the names of the files in a directory, optionally only those with the given extensions."""
+        return [
+            entry['name'] for entry in self.list_dir(path)
+            if not entry['is_dir'] and (not extensions or any(entry['name'].lower().endswith(ext) for ext in extensions))
+        ]
+
+
+class HttpIndexSource(RomSource):
+    """An HTTP server with directory listings (autoindex), like Myrient and its mirrors.
+
+    With a config, listings are kept in the response cache: the root and
+    dataset folders as platform lists, everything deeper as listings.
+    """
+    kind = "http"
+
+    def __init__(self, name: str, root_url: str, mirrors: Optional[MirrorSet] = None, config=None):
+        super().__init__(name)
+        self.root_url = root_url.rstrip('/') + '/'
+        self.mirrors = mirrors
+        self.retry = RetryPolicy.from_config(config) if config else RetryPolicy()
+        self.caches = {kind: ResponseCache(config, kind) for kind in (PLATFORMS, LISTINGS)} if config else {}
+
+    def get(self, url: str, **kwargs):
+        if self.mirrors:
+            return self.mirrors.get(url, **kwargs)
+        return self.retry.get(url, **kwargs)
+
+    def dir_url(self, path: str) -> str:
+        path = path.strip('/')
+        return self.root_url + (urllib.parse.quote(path) + '/' if path else '')
+
+    def list_roots(self) -> List[Dict]:
+        return self.list_dir('')
+
+    def list_dir(self, path: str, fresh=False) -> List[Dict]:
+        """List a directory; fresh asks the server even when the cached listing has not expired."""
+        url = self.dir_url(path)
+        cache = self.caches.get(PLATFORMS if path.strip('/').count('/') == 0 else LISTINGS)
+        cached = cache.get(url) if cache and not fresh else None
+        if cached is not None:
+            return cached
+
+        response = self.get(url, timeout=30)
+        response.raise_for_status()
+
+        entries = parse_index(response.text, path.strip('/') + '/' if path.strip('/') else '')
+        if cache:
+            cache.put(url, entries)
+        return entries
+
+    def file_url(self, path: str) -> str:
+        return self.root_url + urllib.parse.quote(path.lstrip('/'))
+
+    def path_of(self, url: str) -> Optional[str]:
+        if not url.startswith(self.root_url):
+            return None
+        return urllib.parse.unquote(url[len(self.root_url):])
+
+    def metadata(self, path: str) -> Dict:
+        url = self.file_url(path)
+        response = self.mirrors.head(url, timeout=10) if self.mirrors else self.retry.head(url, timeout=10)
+        length = response.headers.get('content-length')
+        return {
+            "size": int(length) if length else None,
+            "validator": response.headers.get('etag') or response.headers.get('last-modified'),
+            "sha1": None,
+            "md5": None
+        }
+
+
+class InternetArchiveSource(RomSource):
+    """archive.org items, listed through the metadata API. Each configured item is a root."""
+    kind = "internet_archive"
+
+    def __init__(self, config, name: str = "internet_archive", log=print):
+        super().__init__(name)
+        self.config = config
+        self.base_url = (config.get('sources.internet_archive.base_url') or ARCHIVE_ORG_URL).rstrip('/')
+        self.retry = RetryPolicy.from_config(config, log=log)
+        self.files_cache = {}
+        self.listing_cache = ResponseCache(config, LISTINGS)
+
+    def get_items(self) -> List[Dict]:
+        """Get the configured items as {identifier, name}; plain strings are identifiers."""
+        if not self.config.get('sources.internet_archive.enabled', True):
+            return []
+
+        items = []
+        for item in self.config.get('sources.internet_archive.items', []) or []:
+            if isinstance(item, str):
+                item = {"identifier": item}
+            if isinstance(item, dict) and item.get('identifier'):
+                items.append({"identifier": item['identifier'], "name": item.get('name') or item['identifier']})
+        return items
+
+    def get_metadata(self, identifier: str) -> Dict:
+        """Fetch an item's metadata record. Raises ValueError for unknown or dark items."""
+        url = f"{self.base_url}/metadata/{urllib.parse.quote(identifier)}"
+        metadata = self.listing_cache.get(url)
+        if metadata is not None:
+            return metadata
+
+        response = self.retry.get(url, timeout=30)
+        response.raise_for_status()
+        metadata = response.json()
+        if not metadata or 'files' not in metadata:
+            raise ValueError(f"Internet Archive item not found or not public: {identifier}")
+        self.listing_cache.put(url, metadata)
+        return metadata
+
+    def item_url(self, identifier: str, name: str) -> str:
+        return f"{self.base_url}/download/{urllib.parse.quote(identifier)}/{urllib.parse.quote(name)}"
+
+    def item_files(self, identifier: str) -> List[Dict]:
+        """List the original files of an item."""
+        if identifier in self.files_cache:
+            return self.files_cache[identifier]
+
+        metadata = self.get_metadata(identifier)
+        title = metadata.get('metadata', {}).get('title') or identifier
+
+        files = []
+        for entry in metadata['files']:
+            name = entry.get('name', '')
+            if entry.get('source') != 'original' or name.endswith(DERIVED_SUFFIXES):
+                continue
+            size = int(entry['size']) if entry.get('size') else None
+            modified = datetime.fromtimestamp(int(entry['mtime'])).isoformat() if entry.get('mtime') else None
+            files.append({
+                "name": name,
+                "item": identifier,
+                "item_title": title,
+                "size": size,
+                "size_text": format_size(size),
+                "modified": modified,
+                "sha1": entry.get('sha1'),
+                "md5": entry.get('md5'),
+                "url": self.item_url(identifier, name)
+            })
+
+        files.sort(key=lambda f: f['name'].lower())
+        self.files_cache[identifier] = files
+        return files
+
+    def list_roots(self) -> List[Dict]:
+        return [make_entry(item['name'], item['identifier'] + '/', True) for item in self.get_items()]
+
+    def list_dir(self, path: str) -> List[Dict]:
+        identifier = path.strip('/').split('/', 1)[0]
+        if not identifier:
+            return self.list_roots()
+        return [make_entry(f['name'], f"{identifier}/{f['name']}", False, f['size'], sha1=f['sha1'],
+                           modified=f['modified'])
+                for f in self.item_files(identifier)]
+
+    def file_url(self, path: str) -> str:
+        identifier, _, name = path.strip('/').partition('/')
+        return self.item_url(identifier, name)
+
+    def path_of(self, url: str) -> Optional[str]:
+        prefix = f"{self.base_url}/download/"
+        if not url.startswith(prefix):
+            return None
+        return urllib.parse.unquote(url[len(prefix):])
+
+    def metadata(self, path: str) -> Dict:
+        identifier, _, name = path.strip('/').partition('/')
+        info = next((f for f in self.item_files(identifier) if f['name'] == name), None)
+        if not info:
+            raise ValueError(f"No file {name} in {identifier}")
+        return {"size": info['size'], "validator": info['sha1'], "sha1": info['sha1'], "md5": info['md5']}
+
+
+class LocalSource(RomSource):
+    """Local folders, such as a mounted NAS share. Each configured directory is a root."""
+    kind = "local"
+
+    def __init__(self, config, name: str = "local"):
+        super().__init__(name)
+        self.config = config
+        self.ignore = RomIgnore()
+
+    def get_roots(self) -> List[Dict]:
+        """Get the configured directories as {name, path}; plain strings are paths named after their folder."""
+        if not self.config.get('sources.local.enabled', True):
+            return []
+
+        roots, names = [], set()
+        for root in self.config.get('sources.local.directories', []) or []:
+            if isinstance(root, str):
+                root = {"path": root}
+            if not isinstance(root, dict) or not root.get('path'):
+                continue
+            path = Path(root['path']).expanduser().resolve()
+            name = (root.get('name') or path.name or str(path)).replace('/', '-')
+            if name not in names:
+                names.add(name)
+                roots.append({"name": name, "path": str(path)})
+        return roots
+
+    def root_url(self, name: str) -> Optional[str]:
+        """Get the file URL of a root directory, for browsing it like an archive URL."""
+        root = next((r for r in self.get_roots() if r['name'] == name), None)
+        return Path(root['path']).as_uri() + '/' if root else None
+
+    def local_path(self, path: str) -> Path:
+        """Get the file system path of a source path. Raises ValueError for paths outside the roots."""
+        root_name, _, relative = path.strip('/').partition('/')
+        root = next((r for r in self.get_roots() if r['name'] == root_name), None)
+        if not root:
+            raise ValueError(f"No local directory named {root_name}")
+
+        base = Path(root['path'])
+        target = (base / relative).resolve()
+        if target != base and base not in target.parents:
+            raise ValueError(f"Path is outside {root_name}: {path}")
+        return target
+
+    def list_roots(self) -> List[Dict]:
+        return [make_entry(root['name'], root['name'] + '/', True) for root in self.get_roots()]
+
+    def list_dir(self, path: str) -> List[Dict]:
+        if not path.strip('/'):
+            return self.list_roots()
+
+        directory = self.local_path(path)
+        prefix = path.strip('/') + '/'
+        directories, files = [], []
+        for child in directory.iterdir():
+            # Hidden files and partial downloads are not ROMs
+            if child.name.startswith('.') or child.name.endswith('.part'):
+                continue
+            if self.ignore.matches(child, child.is_dir()):
+                continue
+            if child.is_dir():
+                directories.append(make_entry(child.name, prefix + child.name + '/', True))
+            elif child.is_file():
+                stat = child.stat()
+                files.append(make_entry(child.name, prefix + child.name, False, stat.st_size,
+                                        modified=datetime.fromtimestamp(stat.st_mtime).isoformat(timespec='seconds')))
+
+        return sorted(directories, key=lambda e: e['name']) + sorted(files, key=lambda e: e['name'])
+
+    def walk(self, path: str) -> List[Dict]:
+        """List every file below a directory, depth first."""
+        files = []
+        for entry in self.list_dir(path):
+            files.extend(self.walk(entry['path']) if entry['is_dir'] else [entry])
+        return files
+
+    def file_url(self, path: str) -> str:
+        return self.local_path(path).as_uri()
+
+    def path_of(self, url: str) -> Optional[str]:
+        if not url.startswith('file:'):
+            return None
+        target = Path(urllib.parse.unquote(urllib.parse.urlparse(url).path)).resolve()
+        for root in self.get_roots():
+            base = Path(root['path'])
+            if target == base or base in target.parents:
+                relative = target.relative_to(base).as_posix()
+                suffix = '/' if url.endswith('/') and relative != '.' else ''
+                return root['name'] + '/' + ('' if relative == '.' else relative + suffix)
+        return None
+
+    def metadata(self, path: str) -> Dict:
+        stat = self.local_path(path).stat()
+        return {"size": stat.st_size, "validator": f"{stat.st_size}-{int(stat.st_mtime)}", "sha1": None, "md5": None}
+
+
+SOURCE_TYPES = {
+    HttpIndexSource.kind: HttpIndexSource,
+    InternetArchiveSource.kind: InternetArchiveSource,
+    LocalSource.kind: LocalSource,
+}
+
+
+def create_sources(config, mirrors: Optional[MirrorSet] = None, log=print) -> Dict[str, RomSource]:
+    """Create every configured source, keyed by name: the archive, plus archive.org and local
+    folders when they have anything configured."""
+    sources = {"myrient": HttpIndexSource("myrient", config.get_archive_url(), mirrors or MirrorSet(config, log=log),
+                                          config)}
+
+    internet_archive = InternetArchiveSource(config, log=log)
+    if internet_archive.get_items():
+        sources[internet_archive.name] = internet_archive
+
+    local = LocalSource(config)
+    if local.get_roots():
+        sources[local.name] = local
+
+    return sources
@@ -0,0 +1,119 @@
+#!/usr/bin/env python3
+"""
+ROM Ignore Files
+Reads .romignore files, which keep folders and files out of library scans and
+audits: WIP hacks, duplicates or anything else kept next to a set. A
+.romignore applies to its own directory and everything below it, and uses
+gitignore patterns:
+
+    # comments and blank lines are skipped
+    *.ips           a pattern without a slash matches a name at any depth
+    /Hacks/         a leading or inner slash anchors it to the .romignore's folder
+    WIP/            a trailing slash only matches directories
+    **/old/*.zip    ** matches any number of folders
+    !keep.zip       a leading ! re-includes what an earlier pattern excluded
+
+Files inside an ignored directory cannot be re-included, as with git.
+"""
+
+import os
+import re
+from pathlib import Path
+from typing import Dict, Iterator, List, Optional, Tuple
+
+IGNORE_FILE = ".romignore"
+
+
+def pattern_regex(pattern: str) -> str:
+    """Translate a gitignore glob into a regular expression over slash-separated paths."""
+    regex, i = "", 0
+    while i < len(pattern):
+        if pattern.startswith("**/", i):
+            regex += "(?:.*/)?"
+            i += 3
+        elif pattern.startswith("/**", i) and i + 3 == len(pattern):
+            regex += "/.*"
+            i += 3
+        elif pattern[i] == "*":
+            regex += "[^/]*"
+            i += 1
+        elif pattern[i] == "?":
+            regex += "[^/]"
+            i += 1
+        elif pattern[i] == "[" and "]" in pattern[i + 1:]:
+            end = pattern.index("]", i + 1)
+            body = pattern[i + 1:end]
+            regex += "[" + ("^" + body[1:] if body.startswith("!") else body) + "]"
+            i = end + 1
+        else:
+            regex += re.escape(pattern[i])
+            i += 1
+    return regex
+
+
+def parse_rules(text: str) -> List[Tuple[re.Pattern, bool, bool, bool]]:
+    """Parse a .romignore into (regex, negated, directories only, anchored) rules."""
+    rules = []
+    for line in text.splitlines():
+        line = line.rstrip()
+        if not line or line.startswith("#"):
+            continue
+        negated = line.startswith("!")
+        if negated:
+            line = line[1:]
+        dir_only = line.endswith("/")
+        line = line.rstrip("/")
+        anchored = "/" in line
+        if not line:
+            continue
+        rules.append((re.compile(pattern_regex(line.lstrip("/")) + "$"), negated, dir_only, anchored))
+    return rules
+
+
+class RomIgnore:
+    """Answers whether paths are ignored, reading each directory's .romignore once."""
+
+    def __init__(self):
+        self.rules: Dict[Path, List] = {}
+
+    def rules_for(self, directory: Path) -> List:
+        if directory not in self.rules:
+            ignore_file = directory / IGNORE_FILE
+            try:
+                self.rules[directory] = parse_rules(ignore_file.read_text(encoding="utf-8"))
+            except (OSError, UnicodeDecodeError):
+                self.rules[directory] = []
+        return self.rules[directory]
+
+    def matches(self, path: Path, is_dir: bool) -> bool:
+        """Check the .romignore files of a path's parent folders, nearest last so it wins."""
+        path, ignored = path.absolute(), False
+        for directory in reversed(path.parents):
+            relative = path.relative_to(directory).as_posix()
+            for regex, negated, dir_only, anchored in self.rules_for(directory):
+                if dir_only and not is_dir:
+                    continue
+                if regex.match(relative if anchored else path.name):
+                    ignored = not negated
+        return ignored
+
+    def is_ignored(self, path, is_dir: Optional[bool] = None) -> bool:
+        """Check whether a path, or any folder it is in, is ignored."""
+        path = Path(path).absolute()
+        if path.name == IGNORE_FILE:
+            return True
+        if self.matches(path, path.is_dir() if is_dir is None else is_dir):
+            return True
+        return any(self.matches(parent, True) for parent in path.parents)
+
+    def walk(self, root) -> Iterator[Path]:
+        """Yield the files below a directory that are not ignored, in a stable order."""
+        root = Path(root)
+        if self.is_ignored(root, True):
+            return
+        for current, dirs, files in os.walk(root):
+            current = Path(current)
+            dirs[:] = sorted(d for d in dirs if not self.matches(current / d, True))
+            for name in sorted(files):
+                if name != IGNORE_FILE and not self.matches(current / name, False):
+                    yield current / name
@@ -0,0 +1,81 @@
+#!/usr/bin/env python3
+"""
+Script Errors
+How a failing script tells the GUI what went wrong. When
+ROM_BROWSER_ERRORS=json is set (the GUI sets it for every script it runs) the
+script ends stderr with one JSON line, {"kind": ..., "message": ...}, and the
+GUI reports the failure by that kind instead of guessing it from the text.
+The kinds are those of RomBrowserError in the GUI: network, not_found,
+rate_limited, denied and other.
+
+Scripts call install_error_reporting() before they start, which reports
+uncaught exceptions, and fail() where they catch an error and exit.
+"""
+
+import json
+import os
+import re
+import socket
+import sys
+import urllib.error
+from typing import NoReturn, Optional
+
+from rate_limiter import QuotaExceeded
+from retry_policy import TransientError
+from rom_config import ReadOnlyError
+
+ERRORS_ENV = "ROM_BROWSER_ERRORS"
+# Terminal colors in messages, which the GUI shows as plain text
+ANSI_CODES = re.compile(r'\x1b\[[0-9;]*m')
+
+
+def error_kind(error: Optional[BaseException]) -> str:
+    """The kind of failure an exception is; other for anything not known to be network, quota or read-only."""
+    if isinstance(error, ReadOnlyError):
+        return "denied"
+    if isinstance(error, QuotaExceeded):
+        return "rate_limited"
+
+    # requests keeps the status on the response, urllib on the error
+    response = getattr(error, 'response', None)
+    status = error.code if isinstance(error, urllib.error.HTTPError) else getattr(response, 'status_code', None)
+    if status == 429:
+        return "rate_limited"
+    if status in (404, 410):
+        return "not_found"
+    if status is not None:
+        return "other"
+
+    requests = sys.modules.get('requests')
+    if requests and isinstance(error, (requests.ConnectionError, requests.Timeout)):
+        return "network"
+    if isinstance(error, (urllib.error.URLError, ConnectionError, TimeoutError, socket.timeout, TransientError)):
+        return "network"
+    return "other"
+
+
+def report_error(message: str, error: Optional[BaseException] = None):
+    """Write the structured error line for a failure, when the GUI asked for one."""
+    if os.environ.get(ERRORS_ENV) != "json":
+        return
+    line = {"kind": error_kind(error), "message": ANSI_CODES.sub('', message).strip()}
+    print(json.dumps(line), file=sys.stderr, flush=True)
+
+
+def fail(message: str, error: Optional[BaseException] = None, code: int = 1) -> NoReturn:
+    """Print an error message to stderr and exit with code, reporting the error's kind to the GUI."""
+    print(message, file=sys.stderr)
+    report_error(message, error)
+    sys.exit(code)
+
+
+def install_error_reporting():
+    """Report an uncaught exception to the GUI after its traceback."""
+    print_traceback = sys.excepthook
+
+    def excepthook(error_type, error, traceback):
+        print_traceback(error_type, error, traceback)
+        if not isinstance(error, KeyboardInterrupt):
+            report_error(f"{error_type.__name__}: {error}", error)
+
+    sys.excepthook = excepthook
@@ -0,0 +1,105 @@
+#!/usr/bin/env python3
+"""
+Session Filter
+A temporary filter ("browse as if I only collect USA carts") that browsing,
+searching and queue building all apply until it is cleared:
+
+    regions         keep only releases from these regions; empty for any
+    exclude_clones  keep one release per game, the one from the best region
+                    (the session's regions in order, else matching.region_priority)
+    only_unowned    leave out files the library or the downloads folder has
+
+It is kept in session_filter.json in the temp folder rather than in the
+settings, so clearing it (or the temp folder) leaves the configuration as it
+was. Set it with `rom_browser.py session set` or the browser's menu.
+"""
+
+import json
+from dataclasses import asdict, dataclass, field
+from pathlib import Path
+from typing import Dict, List, Optional, Set
+
+from compatibility import release_regions
+from filter_presets import game_title
+from library_db import owned_key, owned_keys
+from names import region_rank
+from rom_config import RomConfig
+
+SESSION_FILE = "session_filter.json"
+
+
+@dataclass
+class SessionFilter:
+    regions: List[str] = field(default_factory=list)
+    exclude_clones: bool = False
+    only_unowned: bool = False
+
+    def is_empty(self) -> bool:
+        return not (self.regions or self.exclude_clones or self.only_unowned)
+
+    def describe(self) -> str:
+        """A short summary for headers, e.g. "USA, World; no clones; unowned only"."""
+        parts = [", ".join(self.regions)] if self.regions else []
+        if self.exclude_clones:
+            parts.append("no clones")
+        if self.only_unowned:
+            parts.append("unowned only")
+        return "; ".join(parts) or "off"
+
+    def keep_names(self, names: List[str], config: RomConfig, owned: Optional[Set[str]] = None) -> List[str]:
+        """Filter file names, keeping their order. owned is the owned_keys, looked up when needed and not given."""
+        if self.regions:
+            wanted = {region.lower() for region in self.regions}
+            names = [name for name in names if wanted & {region.lower() for region in release_regions(name)}]
+
+        if self.exclude_clones:
+            priority = self.regions or config.get('matching.region_priority', []) or []
+            best: Dict[str, str] = {}
+            for name in names:
+                title = game_title(name)
+                if title not in best or region_rank(release_regions(name), priority) < \
+                        region_rank(release_regions(best[title]), priority):
+                    best[title] = name
+            names = [name for name in names if best[game_title(name)] == name]
+
+        if self.only_unowned:
+            owned = owned if owned is not None else owned_keys(config)
+            names = [name for name in names if owned_key(name) not in owned]
+        return names
+
+    def filter_entries(self, entries: List[Dict], config: RomConfig) -> List[Dict]:
+        """Keep the listing or search entries whose files pass; directories always stay.
+
+        Search results list a file once per host, so every copy of a kept file stays.
+        """
+        if self.is_empty():
+            return entries
+        kept = set(self.keep_names(list(dict.fromkeys(e['name'] for e in entries if not e.get('is_dir'))), config))
+        return [entry for entry in entries if entry.get('is_dir') or entry['name'] in kept]
+
+
+def session_file(config: RomConfig) -> Path:
+    return config.get_path('paths.temp_directory') / SESSION_FILE
+
+
+def load_session_filter(config: RomConfig) -> SessionFilter:
+    """Get the session's filter; an unreadable file counts as none."""
+    try:
+        with open(session_file(config), 'r', encoding='utf-8') as f:
+            data = json.load(f)
+        return SessionFilter(regions=[str(region) for region in data.get('regions') or []],
+                             exclude_clones=bool(data.get('exclude_clones')),
+                             only_unowned=bool(data.get('only_unowned')))
+    except (OSError, ValueError, AttributeError):
+        return SessionFilter()
+
+
+def save_session_filter(config: RomConfig, session_filter: SessionFilter):
+    """Store the filter for the rest of the session; an empty one clears it."""
+    path = session_file(config)
+    if session_filter.is_empty():
+        path.unlink(missing_ok=True)
+        return
+    path.parent.mkdir(parents=True, exist_ok=True)
+    with open(path, 'w', encoding='utf-8') as f:
+        json.dump(asdict(session_filter), f, indent=2)
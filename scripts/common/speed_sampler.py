@@ -0,0 +1,44 @@
+#!/usr/bin/env python3
+"""
+Speed Sampler
+Turns the chunks of a transfer into per-interval throughput samples for the
+speed history in the library database.
+"""
+
+import time
+import urllib.parse
+from datetime import datetime
+from typing import Dict, List, Optional
+
+DEFAULT_INTERVAL = 1.0
+
+
+def host_of(url: Optional[str]) -> Optional[str]:
+    """Get the host that actually served a response, e.g. the mirror in use."""
+    return urllib.parse.urlparse(url).hostname if url else None
+
+
+class SpeedSampler:
+    def __init__(self, interval: float = DEFAULT_INTERVAL):
+        self.interval = interval
+        self.samples: List[Dict] = []
+        self.window_start = time.monotonic()
+        self.window_bytes = 0
+
+    def add(self, size: int):
+        """Count a received chunk, closing the current sample once the interval has passed."""
+        self.window_bytes += size
+        elapsed = time.monotonic() - self.window_start
+        if elapsed >= self.interval:
+            self.close_window(elapsed)
+
+    def close_window(self, elapsed: float):
+        self.samples.append({"sampled_at": datetime.now(), "bytes": self.window_bytes, "seconds": elapsed})
+        self.window_start = time.monotonic()
+        self.window_bytes = 0
+
+    def finish(self) -> List[Dict]:
+        """Close the last partial sample and return all samples."""
+        if self.window_bytes:
+            self.close_window(max(time.monotonic() - self.window_start, 1e-6))
+        return self.samples
@@ -0,0 +1,139 @@
+#!/usr/bin/env python3
+"""
+Status Report
+Gathers a one-screen overview of an installation for `rom_browser.py status`,
+read from what is on disk without touching the network:
+
+    queue      entries waiting, and how long the ones of known size take at
+               the download speeds recorded for their hosts
+    jobs       journaled downloads, scans and conversions, running or interrupted
+    daemon     whether one is running
+    library    downloaded files and their total size
+    metadata   scraped games, and how many have a cover, summary and rating
+    caches     entries and size of each response cache and the covers folder
+    platforms  when the listing of each of status.pinned_platforms was last fetched
+
+Queued URLs get their size from the cached listing of their folder; titles
+are matched at download time, so their size is unknown until then.
+"""
+
+import sqlite3
+from datetime import datetime
+from pathlib import Path
+from typing import Dict, List, Optional, Tuple
+
+from daemon_ipc import open_client
+from download_queue import read_queue
+from job_journal import JobJournal
+from library_db import URL_MISSING, open_library
+from multi_search import host_speeds
+from response_cache import TTL_SETTINGS, cache_root, cached_listing, listed_size
+from rom_config import RomConfig
+from speed_sampler import host_of
+
+
+def folder_size(directory: Path) -> Tuple[int, int]:
+    """Count the files in a directory tree and their total size."""
+    files = [path for path in directory.rglob('*') if path.is_file()] if directory.is_dir() else []
+    return len(files), sum(path.stat().st_size for path in files)
+
+
+def queue_status(config: RomConfig, queue_file: Path) -> Dict:
+    items = [item for item, _ in read_queue(queue_file)]
+    urls = [item for item in items if item.startswith(('http://', 'https://'))]
+    speeds = host_speeds(config)
+    # Hosts never downloaded from are assumed as fast as the others on average
+    average = sum(speeds.values()) / len(speeds) if speeds else None
+
+    sized, total_bytes, eta = 0, 0, 0.0
+    for url in urls:
+        size = listed_size(config, url)
+        if size is None:
+            continue
+        sized += 1
+        total_bytes += size
+        speed = speeds.get(host_of(url)) or average
+        eta = eta + size / speed if speed and eta is not None else None
+    return {
+        "file": str(queue_file.resolve()),
+        "entries": len(items),
+        "titles": len(items) - len(urls),
+        "sized": sized,
+        "bytes": total_bytes,
+        "eta_seconds": round(eta) if sized and eta is not None else None,
+    }
+
+
+def daemon_status(config: RomConfig) -> Dict:
+    try:
+        status = open_client(config).call("daemon.status")
+    except Exception:
+        return {"running": False, "pid": None}
+    return {"running": True, "pid": status['pid']}
+
+
+def library_status(config: RomConfig) -> Dict:
+    entries = open_library(config).get_entries()
+    return {
+        "files": len(entries),
+        "bytes": sum(entry['size'] or 0 for entry in entries),
+        "platforms": len({entry['platform'] for entry in entries if entry['platform']}),
+        "vanished_sources": sum(1 for entry in entries if entry['url_status'] == URL_MISSING),
+        "outdated": sum(1 for entry in entries if entry.get('outdated_reason')),
+    }
+
+
+def metadata_status(config: RomConfig) -> Dict:
+    db_path = config.get_path('paths.games_db')
+    status = {"games": 0, "covers": 0, "summaries": 0, "ratings": 0}
+    if not db_path.exists():
+        return status
+    conn = sqlite3.connect(f"file:{db_path}?mode=ro", uri=True)
+    try:
+        row = conn.execute('''
+            SELECT COUNT(*), COUNT(cover_path), COUNT(NULLIF(summary, '')), COUNT(rating) FROM games
+        ''').fetchone()
+    except sqlite3.Error:
+        return status
+    finally:
+        conn.close()
+    return dict(zip(status, row))
+
+
+def cache_status(config: RomConfig) -> List[Dict]:
+    caches = []
+    for kind in TTL_SETTINGS:
+        files, size = folder_size(cache_root(config) / kind)
+        caches.append({"name": kind, "entries": files, "bytes": size})
+    files, size = folder_size(config.get_path('paths.covers_directory'))
+    caches.append({"name": "covers", "entries": files, "bytes": size})
+    return caches
+
+
+def platform_status(config: RomConfig) -> List[Dict]:
+    platforms = []
+    for path in config.get('status.pinned_platforms', []) or []:
+        entry = cached_listing(config, path)
+        platforms.append({
+            "path": path,
+            "refreshed": datetime.fromtimestamp(entry['stored']).isoformat(timespec='seconds') if entry else None,
+            "files": sum(1 for file in entry['value'] if not file.get('is_dir')) if entry else None,
+        })
+    return platforms
+
+
+def collect_status(config: Optional[RomConfig] = None, queue_file: Path = Path("./download_queue")) -> Dict:
+    """Gather the overview; the queue file is the one the browser and downloader use, in the working directory."""
+    config = config or RomConfig()
+    jobs = JobJournal(config).entries()
+    return {
+        "generated_at": datetime.now().isoformat(timespec='seconds'),
+        "queue": queue_status(config, queue_file),
+        "jobs": [{key: job.get(key) for key in ("kind", "label", "started", "pid", "running", "state")}
+                 for job in jobs],
+        "daemon": daemon_status(config),
+        "library": library_status(config),
+        "metadata": metadata_status(config),
+        "caches": cache_status(config),
+        "platforms": platform_status(config),
+    }
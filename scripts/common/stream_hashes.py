@@ -0,0 +1,191 @@
+#!/usr/bin/env python3
+"""
+Stream Hashes
+Hashes a download while it arrives, so checking it against the DATs needs no
+second read of the file. DATs describe the files inside archives, so a ZIP is
+also unpacked on the fly and each member hashed, by walking its local file
+headers:
+
+    PK\\x03\\x04 header, name, extra field, data   (once per member)
+    PK\\x01\\x02 ...                                (central directory: done)
+
+Archives the walk cannot follow (encrypted members, compression other than
+stored or deflate, members whose sizes only follow their data) are left to
+dat_index.hash_entries once the file is complete; entries() is then None.
+"""
+
+import hashlib
+import struct
+import zlib
+from typing import Dict, List, Optional
+
+LOCAL_HEADER = struct.Struct('<4s5H3L2H')
+LOCAL_SIGNATURE = b'PK\x03\x04'
+# Anything else after the members: the central directory, or an archive extra data record
+END_SIGNATURES = (b'PK\x01\x02', b'PK\x05\x06', b'PK\x06\x06', b'PK\x06\x08')
+STORED, DEFLATED = 0, 8
+FLAG_ENCRYPTED, FLAG_DATA_DESCRIPTOR = 0x1, 0x8
+ZIP64_EXTRA = 0x0001
+
+
+class StreamHasher:
+    """Size, CRC32, MD5 and SHA1 of bytes fed in pieces."""
+
+    def __init__(self):
+        self.crc = 0
+        self.md5 = hashlib.md5()
+        self.sha1 = hashlib.sha1()
+        self.size = 0
+
+    def update(self, data: bytes):
+        self.crc = zlib.crc32(data, self.crc)
+        self.md5.update(data)
+        self.sha1.update(data)
+        self.size += len(data)
+
+    def result(self) -> Dict:
+        return {
+            "size": self.size,
+            "crc32": f"{self.crc & 0xFFFFFFFF:08x}",
+            "md5": self.md5.hexdigest(),
+            "sha1": self.sha1.hexdigest()
+        }
+
+
+class ZipStreamHasher:
+    """Hashes each member of a ZIP archive from its bytes in order, as hash_entries would."""
+
+    def __init__(self):
+        self.buffer = bytearray()
+        self.members: List[Dict] = []
+        # Set once the members are done, or when the archive cannot be followed
+        self.done = False
+        self.failed: Optional[str] = None
+        self.member = None
+
+    def fail(self, reason: str):
+        self.failed = reason
+        self.buffer = bytearray()
+
+    def update(self, data: bytes):
+        if self.done or self.failed:
+            return
+        self.buffer += data
+        while not (self.done or self.failed):
+            if self.member is None and not self.read_header():
+                return
+            if self.member is not None and not self.read_data():
+                return
+
+    def read_header(self) -> bool:
+        """Start the next member from its local header; False until the whole header has arrived."""
+        if len(self.buffer) < 4:
+            return False
+        signature = bytes(self.buffer[:4])
+        if signature in END_SIGNATURES:
+            self.done = True
+            self.buffer = bytearray()
+            return False
+        if signature != LOCAL_SIGNATURE:
+            self.fail("unexpected data between members")
+            return False
+        if len(self.buffer) < LOCAL_HEADER.size:
+            return False
+
+        (_, _, flags, method, _, _, crc, compressed, size,
+         name_length, extra_length) = LOCAL_HEADER.unpack_from(self.buffer)
+        header_size = LOCAL_HEADER.size + name_length + extra_length
+        if len(self.buffer) < header_size:
+            return False
+        if flags & FLAG_ENCRYPTED:
+            self.fail("encrypted member")
+            return False
+        if flags & FLAG_DATA_DESCRIPTOR:
+            self.fail("member sizes follow the data")
+            return False
+        if method not in (STORED, DEFLATED):
+            self.fail(f"compression method {method}")
+            return False
+
+        name = bytes(self.buffer[LOCAL_HEADER.size:LOCAL_HEADER.size + name_length])
+        extra = bytes(self.buffer[LOCAL_HEADER.size + name_length:header_size])
+        if 0xFFFFFFFF in (compressed, size):
+            size, compressed = zip64_sizes(extra, size, compressed)
+        del self.buffer[:header_size]
+
+        self.member = {
+            "name": name.decode('utf-8' if flags & 0x800 else 'cp437'),
+            "crc": crc,
+            "remaining": compressed,
+            "hasher": StreamHasher(),
+            "inflate": zlib.decompressobj(-15) if method == DEFLATED else None,
+        }
+        return True
+
+    def read_data(self) -> bool:
+        """Hash what has arrived of the current member's data; False until all of it has."""
+        member = self.member
+        take = min(len(self.buffer), member["remaining"])
+        data = bytes(self.buffer[:take])
+        del self.buffer[:take]
+        member["remaining"] -= take
+        try:
+            member["hasher"].update(member["inflate"].decompress(data) if member["inflate"] else data)
+            if member["remaining"] == 0 and member["inflate"]:
+                member["hasher"].update(member["inflate"].flush())
+        except zlib.error as e:
+            self.fail(f"damaged data in {member['name']}: {e}")
+            return False
+        if member["remaining"]:
+            return False
+
+        self.member = None
+        if not member["name"].endswith('/'):
+            hashes = member["hasher"].result()
+            if int(hashes["crc32"], 16) != member["crc"]:
+                self.fail(f"{member['name']} does not match its CRC")
+                return False
+            hashes["name"] = member["name"]
+            self.members.append(hashes)
+        return True
+
+    def entries(self) -> Optional[List[Dict]]:
+        """Get the members' hashes, or None when the archive could not be followed to its end."""
+        return self.members if self.done and not self.failed else None
+
+
+def zip64_sizes(extra: bytes, size: int, compressed: int):
+    """Read the 64-bit sizes of a member over 4 GiB from the ZIP64 field of its extra data."""
+    offset = 0
+    while offset + 4 <= len(extra):
+        field, length = struct.unpack_from('<2H', extra, offset)
+        if field == ZIP64_EXTRA:
+            values = iter(struct.unpack_from(f'<{length // 8}Q', extra, offset + 4))
+            # Only the sizes that did not fit are in the field, uncompressed first
+            if size == 0xFFFFFFFF:
+                size = next(values)
+            if compressed == 0xFFFFFFFF:
+                compressed = next(values)
+            break
+        offset += 4 + length
+    return size, compressed
+
+
+class DownloadHasher:
+    """Hashes a download as a whole and, for a ZIP archive, member by member."""
+
+    def __init__(self, archive: bool):
+        self.file = StreamHasher()
+        self.archive = ZipStreamHasher() if archive else None
+
+    def update(self, data: bytes):
+        self.file.update(data)
+        if self.archive:
+            self.archive.update(data)
+
+    def result(self) -> Dict:
+        """The file's hashes, and for an archive its members' ("entries", None if they could not be followed)."""
+        result = self.file.result()
+        if self.archive:
+            result["entries"] = self.archive.entries()
+        return result
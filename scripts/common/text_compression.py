@@ -0,0 +1,52 @@
+#!/usr/bin/env python3
+"""
+Text Compression
+Transparent compression for large text columns stored in SQLite.
+
+Values are stored as BLOBs tagged with the codec that produced them, so
+plain TEXT rows written before compression keep working. zstd is used when
+the zstandard package is installed, with zlib from the standard library as
+the fallback.
+"""
+
+import zlib
+
+try:
+    import zstandard
+except ImportError:
+    zstandard = None
+
+# Text shorter than this is stored as-is; compression would not pay off
+MIN_COMPRESS_SIZE = 256
+
+ZSTD_TAG = b"zstd:"
+ZLIB_TAG = b"zlib:"
+
+
+def compress_text(text):
+    """Compress text for storage, leaving short values and None untouched."""
+    if text is None or not isinstance(text, str):
+        return text
+
+    data = text.encode('utf-8')
+    if len(data) < MIN_COMPRESS_SIZE:
+        return text
+
+    if zstandard is not None:
+        return ZSTD_TAG + zstandard.ZstdCompressor(level=10).compress(data)
+    return ZLIB_TAG + zlib.compress(data, 9)
+
+
+def decompress_text(value):
+    """Return the text for a stored value, whether compressed or plain."""
+    if not isinstance(value, (bytes, memoryview)):
+        return value
+
+    data = bytes(value)
+    if data.startswith(ZSTD_TAG):
+        if zstandard is None:
+            raise RuntimeError("This value is zstd-compressed; install the zstandard package to read it")
+        return zstandard.ZstdDecompressor().decompress(data[len(ZSTD_TAG):]).decode('utf-8')
+    if data.startswith(ZLIB_TAG):
+        return zlib.decompress(data[len(ZLIB_TAG):]).decode('utf-8')
+    return data.decode('utf-8')
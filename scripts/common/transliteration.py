@@ -0,0 +1,143 @@
+#!/usr/bin/env python3
+"""
+Transliteration
+Kana to romaji conversion and a loose phonetic key, so a search for
+"Biohazard" finds "バイオハザード" and a search in kana finds romaji titles.
+Only a kana title and a romaji one are compared this way; two romaji titles
+are left to the usual matching.
+"""
+
+import re
+import unicodedata
+from typing import List
+
+# Hepburn romaji for katakana; hiragana is shifted into the katakana block first
+KANA_DIGRAPHS = {
+    'キャ': 'kya', 'キュ': 'kyu', 'キョ': 'kyo', 'シャ': 'sha', 'シュ': 'shu', 'ショ': 'sho',
+    'チャ': 'cha', 'チュ': 'chu', 'チョ': 'cho', 'ニャ': 'nya', 'ニュ': 'nyu', 'ニョ': 'nyo',
+    'ヒャ': 'hya', 'ヒュ': 'hyu', 'ヒョ': 'hyo', 'ミャ': 'mya', 'ミュ': 'myu', 'ミョ': 'myo',
+    'リャ': 'rya', 'リュ': 'ryu', 'リョ': 'ryo', 'ギャ': 'gya', 'ギュ': 'gyu', 'ギョ': 'gyo',
+    'ジャ': 'ja', 'ジュ': 'ju', 'ジョ': 'jo', 'ビャ': 'bya', 'ビュ': 'byu', 'ビョ': 'byo',
+    'ピャ': 'pya', 'ピュ': 'pyu', 'ピョ': 'pyo',
+    # Extended katakana used for loanwords
+    'ファ': 'fa', 'フィ': 'fi', 'フェ': 'fe', 'フォ': 'fo', 'ティ': 'ti', 'ディ': 'di',
+    'トゥ': 'tu', 'ドゥ': 'du', 'ウィ': 'wi', 'ウェ': 'we', 'ウォ': 'wo', 'ヴァ': 'va',
+    'ヴィ': 'vi', 'ヴェ': 've', 'ヴォ': 'vo', 'シェ': 'she', 'ジェ': 'je', 'チェ': 'che',
+}
+
+KANA = {
+    'ア': 'a', 'イ': 'i', 'ウ': 'u', 'エ': 'e', 'オ': 'o',
+    'カ': 'ka', 'キ': 'ki', 'ク': 'ku', 'ケ': 'ke', 'コ': 'ko',
+    'サ': 'sa', 'シ': 'shi', 'ス': 'su', 'セ': 'se', 'ソ': 'so',
+    'タ': 'ta', 'チ': 'chi', 'ツ': 'tsu', 'テ': 'te', 'ト': 'to',
+    'ナ': 'na', 'ニ': 'ni', 'ヌ': 'nu', 'ネ': 'ne', 'ノ': 'no',
+    'ハ': 'ha', 'ヒ': 'hi', 'フ': 'fu', 'ヘ': 'he', 'ホ': 'ho',
+    'マ': 'ma', 'ミ': 'mi', 'ム': 'mu', 'メ': 'me', 'モ': 'mo',
+    'ヤ': 'ya', 'ユ': 'yu', 'ヨ': 'yo',
+    'ラ': 'ra', 'リ': 'ri', 'ル': 'ru', 'レ': 're', 'ロ': 'ro',
+    'ワ': 'wa', 'ヲ': 'o', 'ン': 'n',
+    'ガ': 'ga', 'ギ': 'gi', 'グ': 'gu', 'ゲ': 'ge', 'ゴ': 'go',
+    'ザ': 'za', 'ジ': 'ji', 'ズ': 'zu', 'ゼ': 'ze', 'ゾ': 'zo',
+    'ダ': 'da', 'ヂ': 'ji', 'ヅ': 'zu', 'デ': 'de', 'ド': 'do',
+    'バ': 'ba', 'ビ': 'bi', 'ブ': 'bu', 'ベ': 'be', 'ボ': 'bo',
+    'パ': 'pa', 'ピ': 'pi', 'プ': 'pu', 'ペ': 'pe', 'ポ': 'po',
+    'ヴ': 'vu', 'ァ': 'a', 'ィ': 'i', 'ゥ': 'u', 'ェ': 'e', 'ォ': 'o',
+    'ャ': 'ya', 'ュ': 'yu', 'ョ': 'yo', '・': ' ',
+}
+
+HIRAGANA_TO_KATAKANA = {code: code + 0x60 for code in range(ord('ぁ'), ord('ゖ') + 1)}
+
+# English spellings folded towards how they sound once written in kana
+LOOSE_REPLACEMENTS = [
+    ('ph', 'f'), ('gh', ''), ('ck', 'k'), ('qu', 'k'), ('th', 's'), ('x', 'ks'),
+    ('tsu', 't'), ('shi', 's'), ('sh', 's'), ('chi', 'ch'), ('ji', 'z'), ('j', 'z'),
+    ('c', 'k'), ('l', 'r'), ('v', 'b'), ('z', 's'), ('w', ''), ('y', ''),
+]
+
+# The words of a title: runs of kana, of letters and digits, and of other word characters
+# (kanji), so "ファイナルファンタジーVII" is "ファイナルファンタジー" and "VII"
+TITLE_TOKEN = re.compile(r'[ぁ-ゖァ-ヺー]+|[0-9A-Za-z]+|[^\W0-9A-Za-zぁ-ゖァ-ヺー_]+')
+
+
+def has_kana(text: str) -> bool:
+    """Check whether text contains hiragana or katakana."""
+    return any('぀' <= ch <= 'ヿ' for ch in text)
+
+
+def to_romaji(text: str) -> str:
+    """Transliterate the kana in text to Hepburn romaji, leaving other characters alone."""
+    text = unicodedata.normalize('NFKC', text).translate(HIRAGANA_TO_KATAKANA)
+    result = []
+    double_next = False
+    i = 0
+
+    while i < len(text):
+        pair = text[i:i + 2]
+        ch = text[i]
+
+        if pair in KANA_DIGRAPHS:
+            romaji = KANA_DIGRAPHS[pair]
+            i += 2
+        elif ch == 'ッ':
+            double_next = True
+            i += 1
+            continue
+        elif ch == 'ー':
+            # Long vowel mark repeats the previous vowel
+            if result and result[-1] and result[-1][-1] in 'aeiou':
+                result.append(result[-1][-1])
+            i += 1
+            continue
+        else:
+            romaji = KANA.get(ch, ch)
+            i += 1
+
+        if double_next and romaji and romaji[0].isalpha():
+            romaji = romaji[0] + romaji
+        double_next = False
+        result.append(romaji)
+
+    return ''.join(result)
+
+
+def loose_key(text: str) -> str:
+    """Reduce a title to a consonant skeleton shared by its romaji and English spellings.
+
+    "Biohazard" and "バイオハザード" (baiohazaado) both become "bhsd", "Final
+    Fantasy" and "ファイナルファンタジー" (fainarufantajii) "fnrfnts". Vowels are
+    dropped entirely since kana spellings add them between consonants.
+    """
+    text = to_romaji(text).lower()
+    text = re.sub(r'[^a-z0-9]', '', text)
+
+    # English "r" before a consonant or at the end is silent in kana spellings;
+    # romaji only has it before a vowel
+    text = re.sub(r'r(?![aeiouy])', '', text)
+
+    for old, new in LOOSE_REPLACEMENTS:
+        text = text.replace(old, new)
+
+    text = re.sub(r'(.)\1+', r'\1', text)
+
+    return re.sub(r'[aeiou]', '', text)
+
+
+def title_tokens(text: str) -> List[str]:
+    return TITLE_TOKEN.findall(unicodedata.normalize('NFKC', text))
+
+
+def transliterated_match(query: str, candidate: str, min_length: int = 3) -> bool:
+    """Check whether a query matches a candidate title across kana and romaji.
+
+    Exactly one of them has to be in kana. The query's key must be that of
+    whole words of the candidate, so "Final Fantasy" finds
+    "ファイナルファンタジーVII" but "Tetris" does not find "テトリスフィア".
+    """
+    if has_kana(query) == has_kana(candidate):
+        return False
+    query_key = loose_key(query)
+    if len(query_key) < min_length:
+        return False
+    tokens = title_tokens(candidate)
+    return any(loose_key(' '.join(tokens[start:end])) == query_key
+               for start in range(len(tokens)) for end in range(start + 1, len(tokens) + 1))
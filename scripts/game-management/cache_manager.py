@@ -0,0 +1,217 @@
+#!/usr/bin/env python3
+"""
+Media Cache Manager
+Garbage-collects the covers cache so it stays within the size budget in
+cache.max_size_mb, and clears every response cache at once: archive listings
+and metadata provider responses.
+"""
+
+import argparse
+import json
+import sqlite3
+import sys
+from datetime import datetime, timedelta
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from response_cache import clear_response_caches
+from rom_config import ReadOnlyError, RomConfig, add_config_argument, set_read_only
+from script_errors import fail, install_error_reporting
+
+
+class CacheManager:
+    def __init__(self, covers_dir=None, db_path=None):
+        rom_config = self.rom_config = RomConfig()
+        self.covers_dir = Path(covers_dir) if covers_dir else rom_config.get_path('paths.covers_directory')
+        self.db_path = Path(db_path) if db_path else rom_config.get_path('paths.games_db')
+        self.state_file = self.covers_dir / ".cache_gc_state.json"
+        self.max_size_bytes = int(rom_config.get('cache.max_size_mb', 500) * 1024 * 1024)
+        self.gc_interval = timedelta(hours=rom_config.get('cache.gc_interval_hours', 24))
+
+    def get_referenced_covers(self):
+        """Get the cover files referenced by the games database."""
+        if not self.db_path.exists():
+            return set()
+
+        conn = sqlite3.connect(self.db_path)
+        cursor = conn.cursor()
+        cursor.execute('SELECT cover_path FROM games WHERE cover_path IS NOT NULL')
+        # Relative cover paths are relative to the folder games.db is in
+        base = self.db_path.parent
+        referenced = {(base / row[0]).resolve() for row in cursor.fetchall() if row[0]}
+
+        # Covers pinned by a user override are referenced too
+        cursor.execute("SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'overrides'")
+        if cursor.fetchone():
+            cursor.execute("SELECT value FROM overrides WHERE field = 'cover'")
+            for (value,) in cursor.fetchall():
+                cover = json.loads(value)
+                if not cover.startswith(('http://', 'https://')):
+                    referenced.add((base / cover).resolve())
+        conn.close()
+
+        return referenced
+
+    def scan_files(self, directory):
+        """List cache files in a directory as (path, size, last_used) tuples."""
+        if not directory.exists():
+            return []
+
+        files = []
+        for path in directory.rglob("*"):
+            if path.is_file() and path != self.state_file:
+                stat = path.stat()
+                # Prefer access time for LRU, but fall back to mtime on noatime mounts
+                files.append((path, stat.st_size, max(stat.st_atime, stat.st_mtime)))
+        return files
+
+    def get_cache_size(self):
+        """Get the total size of the covers cache in bytes."""
+        return sum(f[1] for f in self.scan_files(self.covers_dir))
+
+    def collect_garbage(self, dry_run=False):
+        """Evict cover files until the cache fits within the configured size.
+
+        Cover files no game references are evicted in least-recently-used
+        order. Referenced covers are never evicted.
+        """
+        # Nothing is deleted in read-only mode; report what would be evicted
+        dry_run = dry_run or RomConfig().is_read_only()
+
+        referenced = self.get_referenced_covers()
+        unreferenced = [f for f in self.scan_files(self.covers_dir) if f[0].resolve() not in referenced]
+
+        total_size = self.get_cache_size()
+        evicted = []
+        freed = 0
+
+        for path, size, _ in sorted(unreferenced, key=lambda f: f[2]):
+            if total_size - freed <= self.max_size_bytes:
+                break
+
+            if not dry_run:
+                try:
+                    path.unlink()
+                except OSError as e:
+                    print(f"Error evicting {path}: {e}")
+                    continue
+
+            evicted.append(str(path))
+            freed += size
+
+        if not dry_run:
+            self.save_state()
+
+        return {
+            "size_before": total_size,
+            "size_after": total_size - freed,
+            "max_size": self.max_size_bytes,
+            "evicted": evicted,
+            "freed": freed,
+            "dry_run": dry_run
+        }
+
+    def clear_all(self):
+        """Delete every response cache: archive listings and metadata provider responses.
+
+        Covers are kept, since games.db points at them.
+        """
+        self.rom_config.ensure_writable("clear the caches")
+        return clear_response_caches(self.rom_config)
+
+    def load_state(self):
+        """Load the last garbage collection run time."""
+        if self.state_file.exists():
+            try:
+                with open(self.state_file, 'r', encoding='utf-8') as f:
+                    return json.load(f)
+            except Exception as e:
+                print(f"Error loading cache state: {e}")
+        return {}
+
+    def save_state(self):
+        """Record the time of the last garbage collection run."""
+        try:
+            self.covers_dir.mkdir(exist_ok=True)
+            with open(self.state_file, 'w', encoding='utf-8') as f:
+                json.dump({"last_gc": datetime.now().isoformat()}, f)
+        except Exception as e:
+            print(f"Error saving cache state: {e}")
+
+    def is_gc_due(self):
+        """Check whether the periodic garbage collection interval has elapsed."""
+        last_gc = self.load_state().get("last_gc")
+        if not last_gc:
+            return True
+        try:
+            return datetime.now() - datetime.fromisoformat(last_gc) >= self.gc_interval
+        except ValueError:
+            return True
+
+    def maybe_collect_garbage(self):
+        """Run garbage collection if the periodic interval has elapsed."""
+        if not self.is_gc_due():
+            return None
+
+        result = self.collect_garbage()
+        if result["evicted"]:
+            print(f"🧹 Cache GC evicted {len(result['evicted'])} files ({result['freed'] / (1024*1024):.1f} MB)")
+        return result
+
+
+def format_mb(size):
+    return f"{size / (1024*1024):.1f} MB"
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Media cache manager')
+    add_config_argument(parser)
+    parser.add_argument('--read-only', action='store_true', help='Report only; never delete files')
+    subparsers = parser.add_subparsers(dest='command', required=True)
+
+    gc_parser = subparsers.add_parser('gc', help='Evict cached media above the size limit')
+    gc_parser.add_argument('--dry-run', action='store_true', help='Show what would be evicted')
+    gc_parser.add_argument('--json', action='store_true', help='Print the result as JSON')
+
+    subparsers.add_parser('stats', help='Show cache usage')
+
+    clear_parser = subparsers.add_parser('clear', help='Delete all caches: platform lists, listings and provider responses')
+    clear_parser.add_argument('--json', action='store_true', help='Print what was deleted as JSON')
+
+    args = parser.parse_args()
+
+    if args.read_only:
+        set_read_only()
+
+    manager = CacheManager()
+
+    if args.command == 'gc':
+        result = manager.collect_garbage(dry_run=args.dry_run)
+        if args.json:
+            print(json.dumps(result))
+            return
+
+        action = "Would evict" if result["dry_run"] else "Evicted"
+        for path in result["evicted"]:
+            print(f"  {path}")
+        print(f"{action} {len(result['evicted'])} files, freeing {format_mb(result['freed'])}")
+        print(f"Cache size: {format_mb(result['size_before'])} -> {format_mb(result['size_after'])} (limit {format_mb(result['max_size'])})")
+    elif args.command == 'clear':
+        try:
+            result = manager.clear_all()
+        except ReadOnlyError as e:
+            fail(str(e), e)
+        if args.json:
+            print(json.dumps(result))
+            return
+        print(f"Deleted {result['platforms']} platform lists, {result['listings']} listings "
+              f"and {result['providers']} provider responses")
+    elif args.command == 'stats':
+        print(f"Cache size: {format_mb(manager.get_cache_size())} (limit {format_mb(manager.max_size_bytes)})")
+        print(f"Referenced covers: {len(manager.get_referenced_covers())}")
+        print(f"Next automatic GC due: {'now' if manager.is_gc_due() else 'later'}")
+
+
+if __name__ == "__main__":
+    install_error_reporting()
+    main()
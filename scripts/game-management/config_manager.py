@@ -4,25 +4,190 @@ Application Configuration Manager
 Handles loading and applying configuration settings for the game launcher.
 """
 
+import argparse
+import difflib
 import json
-import tkinter as tk
+import re
+import sys
+import time
 from pathlib import Path
 
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from atomic_files import atomic_write, read_with_recovery
+from script_errors import fail, install_error_reporting
+
+# Key combos are modifiers in this order followed by one key, e.g. "Ctrl+Shift+F"
+KEY_MODIFIERS = ["Ctrl", "Alt", "Shift", "Meta"]
+NAMED_KEYS = {
+    "Enter", "Escape", "Tab", "Backspace", "Delete", "Space", "Home", "End",
+    "PageUp", "PageDown", "ArrowUp", "ArrowDown", "ArrowLeft", "ArrowRight"
+}
+
+
+def normalize_key_combo(combo):
+    """Normalize a key combo string, or return None if it is not valid."""
+    if not isinstance(combo, str) or not combo.strip():
+        return None
+    
+    parts = [part.strip() for part in combo.split('+')]
+    key = parts[-1]
+    modifiers = {part.capitalize() for part in parts[:-1]}
+    if not key or not modifiers <= set(KEY_MODIFIERS) or len(modifiers) != len(parts) - 1:
+        return None
+    
+    if len(key) == 1 and key.isprintable() and key != ' ':
+        key = key.upper()
+    elif re.fullmatch(r'[Ff]([1-9]|1[0-2])', key):
+        key = key.upper()
+    else:
+        key = next((name for name in NAMED_KEYS if name.lower() == key.lower()), None)
+        if not key:
+            return None
+    
+    return '+'.join([m for m in KEY_MODIFIERS if m in modifiers] + [key])
+
+
+HEX_COLOR = re.compile(r'#[0-9a-fA-F]{6}')
+FONT_WEIGHTS = ["normal", "bold"]
+# Numeric settings that only take whole numbers, by their last key
+INTEGER_SETTINGS = {"rating_precision", "size", "row_height", "burst"}
+# Settings that may be null to mean "no limit", by their last key
+NULLABLE_SETTINGS = {"daily_quota"}
+# Sections keyed by free-form names (any metadata provider), each entry shaped like this
+OPEN_SECTIONS = {
+    "rate_limits": {"requests_per_second": 1.0, "burst": 1, "daily_quota": None},
+}
+
+KIND_DESCRIPTIONS = {
+    "boolean": "true or false",
+    "integer": "a whole number",
+    "number": "a number",
+    "number or null": "a number or null",
+    "color": 'a color like "#1e1e1e"',
+    "font weight": " or ".join(FONT_WEIGHTS),
+    "string": "a string",
+    "key combo": 'a key combo like "Ctrl+F"',
+}
+
+
+def setting_kind(path, default):
+    """Get the kind of value a setting takes, judged by its default."""
+    key = path.rsplit('.', 1)[-1]
+    if path.startswith("keybindings."):
+        return "key combo"
+    if key in NULLABLE_SETTINGS:
+        return "number or null"
+    if isinstance(default, bool):
+        return "boolean"
+    if isinstance(default, (int, float)):
+        return "integer" if key in INTEGER_SETTINGS else "number"
+    if key == "weight":
+        return "font weight"
+    if isinstance(default, str) and HEX_COLOR.fullmatch(default):
+        return "color"
+    return "string"
+
+
+def check_value(path, value, default):
+    """Check one setting's value. Returns an error message, or None if the value is fine."""
+    kind = setting_kind(path, default)
+    is_number = isinstance(value, (int, float)) and not isinstance(value, bool)
+    valid = {
+        "boolean": isinstance(value, bool),
+        "integer": isinstance(value, int) and not isinstance(value, bool),
+        "number": is_number,
+        "number or null": is_number or value is None,
+        "color": isinstance(value, str) and HEX_COLOR.fullmatch(value),
+        "font weight": value in FONT_WEIGHTS,
+        "key combo": normalize_key_combo(value) is not None,
+    }.get(kind, isinstance(value, str))
+    if not valid:
+        return f"{path} must be {KIND_DESCRIPTIONS[kind]}, got {json.dumps(value)}"
+    if is_number and value < 0:
+        return f"{path} must not be negative, got {json.dumps(value)}"
+    return None
+
+
+def unknown_setting(path, known):
+    """Error message for a setting that does not exist, suggesting the closest known name."""
+    key = path.rsplit('.', 1)[-1]
+    close = difflib.get_close_matches(key, list(known), n=1)
+    hint = f"; did you mean {path[:-len(key)]}{close[0]}?" if close else ""
+    return f"Unknown setting {path}{hint}"
+
+
+def read_json(path):
+    with open(path, 'r', encoding='utf-8') as f:
+        return json.load(f)
+
+
 class ConfigManager:
-    def __init__(self, config_file="app_config.json"):
+    def __init__(self, config_file="app_config.json", report_problems=True):
         self.config_file = Path(config_file)
+        self.report_problems = report_problems
+        # What was wrong with the file when it was loaded, for the validate command
+        self.problems = []
         self.config = self.load_config()
     
     def load_config(self):
-        """Load configuration from file."""
-        if self.config_file.exists():
-            try:
-                with open(self.config_file, 'r', encoding='utf-8') as f:
-                    return json.load(f)
-            except Exception as e:
-                print(f"Error loading config: {e}")
-                return self.get_default_config()
-        return self.get_default_config()
+        """Load configuration from file, restoring its previous version if it is damaged.
+        
+        Problems with individual settings are reported on stderr and those settings fall
+        back to their defaults; the rest of the file is still used.
+        """
+        if not self.config_file.exists():
+            return self.get_default_config()
+        try:
+            config = read_with_recovery(self.config_file, read_json, log=lambda message: print(message, file=sys.stderr))
+        except Exception as e:
+            self.problems = [f"Could not read {self.config_file}: {e}"]
+            if self.report_problems:
+                print(f"{self.problems[0]}; using the default settings", file=sys.stderr)
+            return self.get_default_config()
+        
+        config, self.problems = self.check_config(config)
+        if self.problems and self.report_problems:
+            print(f"Problems in {self.config_file} (those settings use their defaults):", file=sys.stderr)
+            for problem in self.problems:
+                print(f"  {problem}", file=sys.stderr)
+        return config
+    
+    def check_config(self, values, defaults=None, prefix=""):
+        """Check a configuration against the defaults.
+        
+        Returns the configuration with every invalid value replaced by its default, and a
+        list of the problems found. Unknown settings are reported but kept.
+        """
+        if defaults is None:
+            defaults = self.get_default_config()
+        if not isinstance(values, dict):
+            return defaults, [f"{prefix.rstrip('.') or 'The configuration'} must be an object, got {json.dumps(values)}"]
+        
+        cleaned, errors = {}, []
+        for key, value in values.items():
+            path = prefix + key
+            if path == "keybindings":
+                problems = self.validate_keybindings(value)
+                errors += [f"keybindings: {problem}" for problem in problems]
+                cleaned[key] = value if isinstance(value, dict) else defaults[key]
+            elif path in OPEN_SECTIONS and isinstance(value, dict):
+                cleaned[key] = {}
+                for name, entry in value.items():
+                    entry_defaults = defaults[key].get(name, OPEN_SECTIONS[path])
+                    cleaned[key][name], entry_errors = self.check_config(entry, entry_defaults, f"{path}.{name}.")
+                    errors += entry_errors
+            elif key not in defaults:
+                errors.append(unknown_setting(path, defaults))
+                cleaned[key] = value
+            elif isinstance(defaults[key], dict):
+                cleaned[key], section_errors = self.check_config(value, defaults[key], path + ".")
+                errors += section_errors
+            else:
+                error = check_value(path, value, defaults[key])
+                if error:
+                    errors.append(error)
+                cleaned[key] = defaults[key] if error else value
+        return cleaned, errors
     
     def get_default_config(self):
         """Get default configuration."""
@@ -65,10 +230,186 @@ class ConfigManager:
                 "confirm_rating_changes": False,
                 "rating_precision": 1,
                 "default_rating": 7.0,
-                "rating_range": {"min": 0.0, "max": 10.0}
+                "rating_range": {"min": 0.0, "max": 10.0},
+                "restore_session_hours": 24
+            },
+            "metadata": {
+                "max_age_days": 180,
+                "retry_hours": 24,
+                "retry_max_attempts": 5,
+                "workers": 4
+            },
+            "rate_limits": {
+                "steam": {"requests_per_second": 0.5, "burst": 5, "daily_quota": 100000},
+                "gog": {"requests_per_second": 1.0, "burst": 3, "daily_quota": None},
+                "rawg": {"requests_per_second": 1.0, "burst": 3, "daily_quota": 650},
+                "screenscraper": {"requests_per_second": 1.0, "burst": 1, "daily_quota": 20000}
+            },
+            "keybindings": {
+                "go_library": "Ctrl+1",
+                "go_browser": "Ctrl+2",
+                "go_settings": "Ctrl+3",
+                "focus_search": "Ctrl+F",
+                "clear_search": "Escape",
+                "next_platform": "Alt+ArrowDown",
+                "previous_platform": "Alt+ArrowUp",
+                "download_top_result": "Ctrl+Enter"
             }
         }
     
+    def save_config(self):
+        """Save the configuration to file, keeping the previous version as a backup."""
+        atomic_write(self.config_file, json.dumps(self.config, indent=2) + '\n', validate=read_json)
+    
+    def get_app_config(self):
+        """Get the whole configuration, with defaults for every setting the file leaves out."""
+        def merge(defaults, values):
+            merged = dict(defaults)
+            for key, value in values.items():
+                if isinstance(value, dict) and isinstance(defaults.get(key), dict):
+                    merged[key] = merge(defaults[key], value)
+                else:
+                    merged[key] = value
+            return merged
+        
+        config = merge(self.get_default_config(), self.config)
+        config["keybindings"] = self.get_keybindings()
+        return config
+    
+    def file_state(self):
+        """What a change to the config file alters: its modification time and size, or None when it is missing."""
+        try:
+            stat = self.config_file.stat()
+        except OSError:
+            return None
+        return stat.st_mtime_ns, stat.st_size
+    
+    def watch(self, on_change, interval=1.0):
+        """Reload the configuration whenever the file is edited, calling on_change with the whole config.
+        
+        Runs until interrupted. Edits that leave the effective settings as they were are not reported.
+        """
+        state = self.file_state()
+        current = self.get_app_config()
+        while True:
+            time.sleep(interval)
+            new_state = self.file_state()
+            if new_state == state:
+                continue
+            state = new_state
+            self.config = self.load_config()
+            config = self.get_app_config()
+            if config != current:
+                current = config
+                on_change(config)
+    
+    def default_for(self, path):
+        """Get a setting's default by its dotted path. Raises ValueError if there is no such setting."""
+        keys = path.split('.')
+        default = self.get_default_config()
+        for i, key in enumerate(keys):
+            section = '.'.join(keys[:i])
+            if section in OPEN_SECTIONS and key not in default:
+                default = OPEN_SECTIONS[section]
+                continue
+            if not isinstance(default, dict) or key not in default:
+                raise ValueError(unknown_setting('.'.join(keys[:i + 1]), default if isinstance(default, dict) else {}))
+            default = default[key]
+        return default
+    
+    def list_settings(self):
+        """Get every setting as (dotted path, current value, kind), including fonts and game_list entries."""
+        settings = []
+        
+        def walk(values, prefix):
+            for key, value in values.items():
+                path = prefix + key
+                if isinstance(value, dict):
+                    walk(value, path + ".")
+                    continue
+                try:
+                    kind = setting_kind(path, self.default_for(path))
+                except ValueError:
+                    kind = "unknown"
+                settings.append((path, value, kind))
+        
+        walk(self.get_app_config(), "")
+        return settings
+    
+    def set_value(self, path, value):
+        """Set one setting by its dotted path, e.g. "appearance.fonts.heading.size", and save.
+        
+        Raises ValueError if the setting does not exist or the value is not valid for it.
+        """
+        default = self.default_for(path)
+        keys = path.split('.')
+        if keys[0] == "keybindings":
+            self.set_keybindings({keys[1]: value} if len(keys) == 2 else value)
+            return
+        if isinstance(default, dict):
+            raise ValueError(f"{path} is a section; set its settings one at a time "
+                             f"(e.g. {path}.{next(iter(default))})")
+        error = check_value(path, value, default)
+        if error:
+            raise ValueError(error)
+        
+        section = self.config
+        for key in keys[:-1]:
+            if not isinstance(section.get(key), dict):
+                section[key] = {}
+            section = section[key]
+        section[keys[-1]] = value
+        self.save_config()
+    
+    def get_keybindings(self):
+        """Get the keyboard shortcut map, with defaults for actions that are not configured."""
+        keybindings = dict(self.get_default_config()["keybindings"])
+        for action, combo in self.config.get("keybindings", {}).items():
+            normalized = normalize_key_combo(combo)
+            if action in keybindings and normalized:
+                keybindings[action] = normalized
+        return keybindings
+    
+    def validate_keybindings(self, keybindings):
+        """Check a keyboard shortcut map. Returns a list of error messages."""
+        if not isinstance(keybindings, dict):
+            return ["Keybindings must be an object mapping actions to key combos"]
+        
+        known_actions = self.get_default_config()["keybindings"]
+        errors = []
+        used = {}
+        
+        for action, combo in keybindings.items():
+            if action not in known_actions:
+                errors.append(f"Unknown action: {action}")
+                continue
+            
+            normalized = normalize_key_combo(combo)
+            if not normalized:
+                errors.append(f"Invalid key combo for {action}: {combo!r}")
+            elif normalized in used:
+                errors.append(f"{normalized} is bound to both {used[normalized]} and {action}")
+            else:
+                used[normalized] = action
+        
+        return errors
+    
+    def set_keybindings(self, keybindings):
+        """Validate and save a keyboard shortcut map; unmapped actions keep their defaults.
+        
+        Raises ValueError listing the problems if the map is invalid.
+        """
+        merged = self.get_keybindings()
+        merged.update(keybindings if isinstance(keybindings, dict) else {})
+        
+        errors = self.validate_keybindings(keybindings) or self.validate_keybindings(merged)
+        if errors:
+            raise ValueError("; ".join(errors))
+        
+        self.config["keybindings"] = {action: normalize_key_combo(combo) for action, combo in merged.items()}
+        self.save_config()
+        return self.config["keybindings"]
+    
     def get_color(self, path):
         """Get a color value from the config."""
         keys = path.split('.')
@@ -84,13 +425,15 @@ class ConfigManager:
     
     def apply_theme(self, root):
         """Apply the theme to the root window."""
+        from tkinter import ttk
+        
         colors = self.config["appearance"]["colors"]
         
         # Configure root window
         root.configure(bg=colors["background"])
         
         # Configure ttk styles
-        style = tk.ttk.Style()
+        style = ttk.Style()
         style.theme_use('clam')
         
         # Configure Treeview
@@ -131,10 +474,93 @@ class ConfigManager:
         
         return style
 
-if __name__ == "__main__":
-    # Test the config manager
+def main():
+    """Test the config manager, or get and set keybindings."""
+    parser = argparse.ArgumentParser(description='Application configuration')
+    subparsers = parser.add_subparsers(dest='command')
+    
+    parser.add_argument('--list', action='store_true', help='List every setting with its current value and type')
+    parser.add_argument('--json', action='store_true', help='Print --list as JSON')
+    
+    subparsers.add_parser('get', help='Print the whole configuration as JSON')
+    subparsers.add_parser('validate', help='Check the configuration file and report every problem')
+    watch_parser = subparsers.add_parser('watch', help='Print the whole configuration as JSON each time the file changes')
+    watch_parser.add_argument('--interval', type=float, default=1.0, help='Seconds between checks (default: 1)')
+    set_parser = subparsers.add_parser('set', help='Change one setting')
+    set_parser.add_argument('key', help='Dotted setting path, e.g. appearance.colors.accent')
+    set_parser.add_argument('value', help='New value as JSON, e.g. 2 or true; other text is taken as a string')
+    
+    keybindings_parser = subparsers.add_parser('keybindings', help='Get or set keyboard shortcuts')
+    keybindings_parser.add_argument('action', choices=['get', 'set', 'reset'])
+    keybindings_parser.add_argument('bindings', nargs='?', help='JSON object of action -> key combo (for set)')
+    
+    args = parser.parse_args()
+    
+    if args.command == 'validate':
+        config = ConfigManager(report_problems=False)
+        for problem in config.problems:
+            print(problem)
+        if config.problems:
+            sys.exit(1)
+        print(f"{config.config_file} is valid")
+        return
+    
     config = ConfigManager()
+    
+    if args.command == 'watch':
+        print(f"Watching {config.config_file} for changes (Ctrl+C to stop)", file=sys.stderr)
+        try:
+            config.watch(lambda changed: print(json.dumps(changed), flush=True), args.interval)
+        except KeyboardInterrupt:
+            pass
+        return
+    
+    if args.list:
+        settings = config.list_settings()
+        if args.json:
+            print(json.dumps([{"key": key, "value": value, "type": kind} for key, value, kind in settings], indent=2))
+        else:
+            width = max(len(key) for key, _, _ in settings)
+            for key, value, kind in settings:
+                print(f"{key:<{width}}  {json.dumps(value):<24}  {kind}")
+        return
+    
+    if args.command == 'get':
+        print(json.dumps(config.get_app_config()))
+        return
+    
+    if args.command == 'set':
+        try:
+            value = json.loads(args.value)
+        except json.JSONDecodeError:
+            # Anything that is not JSON is taken as a string, so colors need no extra quoting
+            value = args.value
+        try:
+            config.set_value(args.key, value)
+        except ValueError as e:
+            fail(f"Could not set {args.key}: {e}", e)
+        print(json.dumps(config.get_app_config()))
+        return
+    
+    if args.command == 'keybindings':
+        if args.action == 'set':
+            try:
+                config.set_keybindings(json.loads(args.bindings or '{}'))
+            except ValueError as e:
+                fail(f"Invalid keybindings: {e}", e)
+        elif args.action == 'reset':
+            config.config["keybindings"] = config.get_default_config()["keybindings"]
+            config.save_config()
+        print(json.dumps(config.get_keybindings()))
+        return
+    
+    # Test the config manager
     print("Config loaded successfully")
     print(f"Background color: {config.get_color('appearance.colors.background')}")
     print(f"Default font: {config.get_font('default')}")
     print(f"Rating color: {config.get_color('appearance.game_list.rating_color')}")
+
+
+if __name__ == "__main__":
+    install_error_reporting()
+    main()
@@ -0,0 +1,99 @@
+#!/usr/bin/env python3
+"""
+Cover Filters
+Selects games by the state of their cover art, for re-downloading covers in
+bulk. A filter is a comma-separated list of terms, any of which may match;
+every filter given must match:
+
+    missing          no cover file, or the file is gone
+    placeholder      a generated placeholder or ROM icon instead of a scraped cover
+    below:WxH        cover narrower than W or shorter than H pixels (below:W checks width only)
+    source:NAME      cover supplied by that provider, e.g. source:google
+    older:DAYS       cover file last written more than DAYS days ago
+    name:GLOB        game name matches a glob, e.g. "name:Final Fantasy*"
+
+For example --filter missing,below:300x400 picks games whose cover is
+missing or smaller than 300x400.
+"""
+
+import fnmatch
+import sys
+import time
+from pathlib import Path
+from typing import Callable, Dict, List
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from image_size import image_size
+
+# Cover sources that are filler rather than real artwork
+PLACEHOLDER_COVER_SOURCES = ('google', 'basic')
+
+Predicate = Callable[[Dict], bool]
+
+
+def cover_file(game: Dict):
+    path = game.get('cover_path')
+    return Path(path) if path and not str(path).startswith(('http://', 'https://')) else None
+
+
+def is_missing(game: Dict) -> bool:
+    path = cover_file(game)
+    return path is None or not path.exists()
+
+
+def is_placeholder(game: Dict) -> bool:
+    path = cover_file(game)
+    source = (game.get('field_sources') or {}).get('cover')
+    return (path is not None and path.stem.endswith('_placeholder')) or source is None \
+        or source in PLACEHOLDER_COVER_SOURCES
+
+
+def below(width: int, height: int) -> Predicate:
+    def matches(game: Dict) -> bool:
+        path = cover_file(game)
+        size = image_size(path) if path else None
+        # Unreadable covers are no better than small ones
+        if size is None:
+            return path is not None and path.exists()
+        return size[0] < width or size[1] < height
+    return matches
+
+
+def older_than(days: float) -> Predicate:
+    def matches(game: Dict) -> bool:
+        path = cover_file(game)
+        return path is not None and path.exists() and time.time() - path.stat().st_mtime > days * 86400
+    return matches
+
+
+def parse_term(term: str) -> Predicate:
+    kind, _, value = term.strip().partition(':')
+    kind = kind.lower()
+    if kind == 'missing' and not value:
+        return is_missing
+    if kind == 'placeholder' and not value:
+        return is_placeholder
+    if kind == 'below' and value:
+        width, _, height = value.lower().partition('x')
+        try:
+            return below(int(width), int(height or 0))
+        except ValueError:
+            raise ValueError(f"Expected below:WIDTHxHEIGHT, got '{term}'")
+    if kind == 'older' and value:
+        try:
+            return older_than(float(value))
+        except ValueError:
+            raise ValueError(f"Expected older:DAYS, got '{term}'")
+    if kind == 'source' and value:
+        return lambda game: (game.get('field_sources') or {}).get('cover') == value
+    if kind == 'name' and value:
+        return lambda game: fnmatch.fnmatch(game['name'].lower(), value.lower())
+    raise ValueError(f"Unknown cover filter '{term}'; use missing, placeholder, below:WxH, "
+                     f"source:NAME, older:DAYS or name:GLOB")
+
+
+def parse_filters(filters: List[str]) -> Predicate:
+    """Build one predicate from --filter values: terms within a filter are OR-ed, filters are AND-ed."""
+    groups = [[parse_term(term) for term in f.split(',') if term.strip()] for f in filters]
+    groups = [group for group in groups if group]
+    return lambda game: all(any(term(game) for term in group) for group in groups)
@@ -5,8 +5,19 @@ Handles user-defined ratings and tags that override downloaded data.
 """
 
 import json
+import sys
 from pathlib import Path
 
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from atomic_files import atomic_write, read_with_recovery
+from rom_config import RomConfig
+
+
+def read_json(path):
+    with open(path, 'r', encoding='utf-8') as f:
+        return json.load(f)
+
+
 class CustomRatingsManager:
     def __init__(self, data_file="custom_ratings.json"):
         self.data_file = Path(data_file)
@@ -16,16 +27,15 @@ class CustomRatingsManager:
         """Load custom ratings and tags from file."""
         if self.data_file.exists():
             try:
-                with open(self.data_file, 'r', encoding='utf-8') as f:
-                    data = json.load(f)
-                    # Ensure backward compatibility
-                    if isinstance(data, dict) and all(isinstance(v, (int, float)) for v in data.values()):
-                        # Old format - convert to new format
-                        new_data = {}
-                        for game_name, rating in data.items():
-                            new_data[game_name] = {"rating": rating, "tags": []}
-                        return new_data
-                    return data
+                data = read_with_recovery(self.data_file, read_json, log=print)
+                # Ensure backward compatibility
+                if isinstance(data, dict) and all(isinstance(v, (int, float)) for v in data.values()):
+                    # Old format - convert to new format
+                    new_data = {}
+                    for game_name, rating in data.items():
+                        new_data[game_name] = {"rating": rating, "tags": []}
+                    return new_data
+                return data
             except Exception as e:
                 print(f"Error loading custom data: {e}")
                 return {}
@@ -33,9 +43,13 @@ class CustomRatingsManager:
     
     def save_data(self):
         """Save custom ratings and tags to file."""
+        if RomConfig().is_read_only():
+            print("Read-only mode: custom ratings and tags were not saved")
+            return False
+            
         try:
-            with open(self.data_file, 'w', encoding='utf-8') as f:
-                json.dump(self.custom_data, f, indent=2, ensure_ascii=False)
+            atomic_write(self.data_file, json.dumps(self.custom_data, indent=2, ensure_ascii=False),
+                         validate=read_json)
             return True
         except Exception as e:
             print(f"Error saving custom data: {e}")
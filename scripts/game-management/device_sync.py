@@ -0,0 +1,421 @@
+#!/usr/bin/env python3
+"""
+Device Sync
+Copies the ROM library to handhelds and other EmulationStation-style devices:
+ROMs go to roms/<system>/, with a gamelist.xml and box art in images/ beside
+them. Metadata and covers come from paths.games_db.
+
+What gets copied is set by a sync profile (sync.profiles in rom_browser.json).
+"full" copies everything; "artwork" pushes only gamelists and artwork, for
+refreshing metadata on a device that already has the ROMs without copying
+them again.
+
+Favorites and play history the device recorded in its gamelists (favorite,
+lastplayed, playcount) are read back into games.db on every sync, so the
+metadata scan can fetch the games played on the device first.
+
+After a sync a random sample of the ROMs it copied (sync.verify_percent, or
+the device's verify_percent) is read back from the device and hashed against
+its source, since hashing a whole 512 GB card again would take hours.
+"""
+
+import argparse
+import json
+import math
+import random
+import shutil
+import sqlite3
+import sys
+import xml.etree.ElementTree as ET
+from pathlib import Path
+
+from game_usage import GameUsage, parse_es_time
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from dat_index import hash_file
+from datasets import title_from_filename
+from game_attributes import get_attributes
+from job_limits import JobLimits
+from job_progress import ProgressTracker, default_reporter
+from library_db import open_library, title_key
+from rom_config import ReadOnlyError, RomConfig, add_config_argument, set_read_only
+from script_errors import fail, install_error_reporting
+from text_compression import decompress_text
+
+# Archive platform folders -> EmulationStation system folders; others are lower-cased
+ES_SYSTEMS = {
+    "Nintendo - Nintendo Entertainment System": "nes",
+    "Nintendo - Super Nintendo Entertainment System": "snes",
+    "Nintendo - Nintendo 64": "n64",
+    "Nintendo - Game Boy": "gb",
+    "Nintendo - Game Boy Color": "gbc",
+    "Nintendo - Game Boy Advance": "gba",
+    "Nintendo - Nintendo DS": "nds",
+    "Nintendo - Nintendo 3DS": "3ds",
+    "Nintendo - Nintendo GameCube": "gc",
+    "Nintendo - Wii": "wii",
+    "Sony - PlayStation": "psx",
+    "Sony - PlayStation 2": "ps2",
+    "Sony - PlayStation Portable": "psp",
+    "Sega - Master System - Mark III": "mastersystem",
+    "Sega - Mega Drive - Genesis": "megadrive",
+    "Sega - Game Gear": "gamegear",
+    "Sega - Mega-CD - Sega CD": "segacd",
+    "Sega - 32X": "sega32x",
+    "Sega - Saturn": "saturn",
+    "Sega - Dreamcast": "dreamcast",
+    "Atari - 2600": "atari2600",
+    "Atari - 7800": "atari7800",
+    "Atari - Lynx": "atarilynx",
+    "NEC - PC Engine - TurboGrafx-16": "pcengine",
+    "SNK - Neo Geo Pocket Color": "ngpc",
+    "Bandai - WonderSwan Color": "wonderswancolor",
+}
+
+
+def system_folder(platform, overrides=None):
+    """Get the device folder for an archive platform."""
+    if not platform:
+        return "unsorted"
+    mapping = dict(ES_SYSTEMS, **(overrides or {}))
+    if platform in mapping:
+        return mapping[platform]
+    return "".join(c for c in platform.lower() if c.isalnum()) or "unsorted"
+
+
+def load_metadata(db_path):
+    """Load stored game metadata, with user overrides applied, keyed by title."""
+    db_path = Path(db_path)
+    if not db_path.exists():
+        return {}
+
+    conn = sqlite3.connect(db_path)
+    conn.row_factory = sqlite3.Row
+    overrides = {}
+    try:
+        for row in conn.execute('SELECT game_name, field, value FROM overrides'):
+            overrides.setdefault(row['game_name'], {})[row['field']] = json.loads(row['value'])
+    except sqlite3.OperationalError:
+        pass
+
+    attributes = get_attributes(conn)
+    metadata = {}
+    for row in conn.execute('SELECT * FROM games'):
+        pinned = overrides.get(row['name'], {})
+        genres = pinned.get('genres') or attributes[row['id']]['genres']
+        metadata[title_key(row['name'])] = {
+            "game": row['name'],
+            "name": pinned.get('name', row['name']),
+            "summary": decompress_text(row['summary']),
+            "rating": pinned.get('rating', row['rating']),
+            "release_date": row['release_date'],
+            "developer": attributes[row['id']]['developer'],
+            "publisher": attributes[row['id']]['publisher'],
+            "genres": genres,
+            "cover_path": pinned.get('cover') or row['cover_path']
+        }
+    conn.close()
+    return metadata
+
+
+def bad_share_bound(sampled, total, confidence=0.95):
+    """The largest share of bad copies that a sample without any still leaves plausible.
+
+    With none of `sampled` random copies bad, a share p of bad copies is ruled
+    out (at the given confidence) once (1 - p) ** sampled < 1 - confidence.
+    """
+    if not sampled or sampled >= total:
+        return 0.0 if sampled else 1.0
+    return 1 - (1 - confidence) ** (1 / sampled)
+
+
+def is_current(source, destination):
+    """Check whether a destination file already matches its source (same size, not older)."""
+    if not destination.exists():
+        return False
+    src, dst = source.stat(), destination.stat()
+    return src.st_size == dst.st_size and int(dst.st_mtime) >= int(src.st_mtime)
+
+
+class DeviceSync:
+    def __init__(self, config=None, db_path=None):
+        self.config = config or RomConfig()
+        self.library = open_library(self.config)
+        # Covers recorded with relative paths are relative to the folder of games.db
+        self.db_path = Path(db_path) if db_path else self.config.get_path('paths.games_db')
+        self.metadata = load_metadata(self.db_path)
+        self.system_overrides = self.config.get('sync.system_folders', {})
+
+    def get_devices(self):
+        return self.config.get('sync.devices', [])
+
+    def get_profiles(self):
+        return self.config.get('sync.profiles', {})
+
+    def copy(self, source, destination, dry_run):
+        """Copy a file unless the destination is current. Returns whether it was (or would be) copied."""
+        if is_current(source, destination):
+            return False
+        if not dry_run:
+            destination.parent.mkdir(parents=True, exist_ok=True)
+            shutil.copy2(source, destination)
+        return True
+
+    def verify_sample(self, copied, percent, show_progress=True):
+        """Hash a random sample of copied ROMs on the device and compare each with its source.
+
+        copied holds (source, destination) pairs. Returns how many were sampled
+        of how many, the device paths that differ or cannot be read, and
+        bad_share_bound: with no mismatches, the share of bad copies still
+        plausible at 95% confidence.
+        """
+        count = min(len(copied), max(1, math.ceil(len(copied) * percent / 100)))
+        sample = random.sample(copied, count)
+        progress = ProgressTracker("verify", total=sum(source.stat().st_size for source, _ in sample), unit="bytes",
+                                   label="verifying", reporter=default_reporter(show_progress))
+        limits = JobLimits(self.config)
+
+        def matches(pair):
+            source, destination = pair
+            try:
+                return hash_file(source, limits)['sha1'] == hash_file(destination, limits)['sha1']
+            except OSError:
+                return False
+
+        mismatched = []
+        for (source, destination), same in zip(sample, limits.map(matches, sample)):
+            if not same:
+                mismatched.append(str(destination))
+            progress.advance(source.stat().st_size)
+        progress.finish()
+
+        return {"percent": percent, "sampled": count, "copied": len(copied), "mismatched": mismatched,
+                "bad_share_bound": None if mismatched else round(bad_share_bound(count, len(copied)), 4)}
+
+    def artwork_for(self, entry, info):
+        """Get the box art of a library entry: the scraped cover, else the icon extracted from the ROM."""
+        for candidate in ((info or {}).get('cover_path'), entry['icon_path']):
+            if candidate and (self.db_path.parent / candidate).is_file():
+                return self.db_path.parent / candidate
+        return None
+
+    def write_gamelist(self, path, games, dry_run):
+        """Merge entries into a device's gamelist.xml.
+
+        Only the fields given are replaced, so play counts, favorites and
+        metadata scraped on the device for games without any here are kept.
+        """
+        root = ET.parse(path).getroot() if path.exists() else ET.Element('gameList')
+        existing = {game.findtext('path'): game for game in root.findall('game')}
+
+        for fields in games:
+            game = existing.get(fields['path'])
+            if game is None:
+                game = ET.SubElement(root, 'game')
+                ET.SubElement(game, 'path').text = fields['path']
+            for tag, value in fields.items():
+                if tag == 'path':
+                    continue
+                for old in game.findall(tag):
+                    game.remove(old)
+                if value:
+                    ET.SubElement(game, tag).text = value
+
+        if not dry_run:
+            path.parent.mkdir(parents=True, exist_ok=True)
+            ET.indent(root)
+            ET.ElementTree(root).write(path, encoding='utf-8', xml_declaration=True)
+
+    def read_play_history(self, path):
+        """Get what a device's gamelist.xml recorded per ROM path: {favorite, last_played, play_count}."""
+        if not path.exists():
+            return {}
+        history = {}
+        for game in ET.parse(path).getroot().findall('game'):
+            favorite = (game.findtext('favorite') or '').strip().lower() == 'true'
+            last_played = parse_es_time(game.findtext('lastplayed'))
+            try:
+                play_count = int(game.findtext('playcount') or 0)
+            except ValueError:
+                play_count = 0
+            if favorite or last_played or play_count:
+                history[game.findtext('path')] = {"favorite": favorite, "last_played": last_played,
+                                                  "play_count": play_count}
+        return history
+
+    def gamelist_fields(self, rom_name, info, image_name, with_image):
+        fields = {"path": f"./{rom_name}", "name": Path(rom_name).stem}
+        if with_image:
+            fields["image"] = f"./images/{image_name}" if image_name else None
+        if not info:
+            return fields
+
+        fields.update({
+            "name": info['name'],
+            "desc": info['summary'],
+            "developer": ", ".join(info['developer']),
+            "publisher": ", ".join(info['publisher']),
+            "genre": ", ".join(info['genres'])
+        })
+        if info['rating'] is not None:
+            # EmulationStation rates from 0 to 1
+            fields["rating"] = f"{min(max(float(info['rating']) / 10, 0), 1):.2f}"
+        if info['release_date']:
+            digits = "".join(c for c in str(info['release_date']) if c.isdigit())[:8]
+            if len(digits) == 8:
+                fields["releasedate"] = f"{digits}T000000"
+        return fields
+
+    def sync(self, device_path, profile_name="full", rom_folder="roms", dry_run=False, verify_percent=None,
+             show_progress=True):
+        """Sync the library to a device according to a profile.
+
+        Returns a summary: roms, artwork and gamelists copied or written,
+        played (games whose device play history was read back), missing (ROMs
+        not on the device, which an artwork-only profile leaves out of the
+        gamelists) and, when ROMs were copied, the verification of a sample of
+        them (see verify_sample; verify_percent 0 skips it).
+        """
+        profiles = self.get_profiles()
+        if profile_name not in profiles:
+            raise ValueError(f"No sync profile named {profile_name}; choose one of {', '.join(profiles)}")
+        profile = profiles[profile_name]
+        if not dry_run:
+            self.config.ensure_writable(f"sync to {device_path}")
+
+        root = Path(device_path).expanduser() / rom_folder
+        summary = {"profile": profile_name, "device": str(root), "dry_run": dry_run,
+                   "roms": 0, "artwork": 0, "gamelists": 0, "played": 0, "missing": []}
+        systems = {}
+        # Game names by system folder and gamelist path, to read the device's play history back
+        game_names = {}
+        copied = []
+
+        entries = self.library.get_entries()
+        progress = ProgressTracker("sync", total=len(entries), label=str(root), reporter=default_reporter(show_progress))
+        for done, entry in enumerate(entries):
+            progress.update(done)
+            source = Path(entry['path'])
+            system_dir = root / system_folder(entry['platform'], self.system_overrides)
+            destination = system_dir / source.name
+
+            if profile.get('roms', True):
+                if not source.exists():
+                    summary['missing'].append(str(source))
+                    continue
+                if self.copy(source, destination, dry_run):
+                    summary['roms'] += 1
+                    copied.append((source, destination))
+            elif not destination.exists():
+                # Artwork for a ROM the device does not have would only clutter its gamelist
+                summary['missing'].append(str(destination))
+                continue
+
+            info = self.metadata.get(title_key(source.name))
+            image_name = None
+            artwork = self.artwork_for(entry, info)
+            if artwork and profile.get('artwork', True):
+                image_name = f"{source.stem}{artwork.suffix.lower()}"
+                if self.copy(artwork, system_dir / "images" / image_name, dry_run):
+                    summary['artwork'] += 1
+            fields = self.gamelist_fields(source.name, info, image_name, profile.get('artwork', True))
+            systems.setdefault(system_dir, []).append(fields)
+            game_name = info['game'] if info else title_from_filename(source.name)
+            game_names.setdefault(system_dir, {})[fields['path']] = game_name
+
+        # Before the gamelists are rewritten; they keep these fields, but this reads them for games.db
+        usage = None
+        for system_dir, names in game_names.items():
+            for path, played in self.read_play_history(system_dir / "gamelist.xml").items():
+                if path not in names:
+                    continue
+                summary['played'] += 1
+                if not dry_run:
+                    usage = usage or GameUsage(self.db_path)
+                    usage.record_play(names[path], played['last_played'], played['play_count'], played['favorite'])
+
+        if profile.get('gamelists', True):
+            for system_dir, games in systems.items():
+                self.write_gamelist(system_dir / "gamelist.xml", games, dry_run)
+                summary['gamelists'] += 1
+        progress.update(len(entries))
+        progress.finish()
+
+        if verify_percent is None:
+            verify_percent = self.config.get('sync.verify_percent', 0) or 0
+        if copied and verify_percent > 0 and not dry_run:
+            summary['verification'] = self.verify_sample(copied, min(verify_percent, 100), show_progress)
+
+        return summary
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Sync ROMs, gamelists and artwork to a device')
+    add_config_argument(parser)
+    parser.add_argument('--read-only', action='store_true', help='Show what would be copied without writing')
+    subparsers = parser.add_subparsers(dest='command', required=True)
+
+    devices_parser = subparsers.add_parser('devices', help='List the configured devices and profiles')
+    devices_parser.add_argument('--json', action='store_true', help='Print the devices and profiles as JSON')
+
+    sync_parser = subparsers.add_parser('sync', help='Sync the library to a device')
+    sync_parser.add_argument('device', help='Configured device name, or a mount path')
+    sync_parser.add_argument('--profile', help="Sync profile, e.g. 'artwork' (default: the device's, else full)")
+    sync_parser.add_argument('--dry-run', action='store_true', help='Show what would be copied')
+    sync_parser.add_argument('--verify-percent', type=float, metavar='PERCENT',
+                             help="Share of the copied ROMs to hash again on the device, 0 to skip "
+                                  "(default: the device's verify_percent, else sync.verify_percent)")
+    sync_parser.add_argument('--json', action='store_true', help='Print the summary as JSON')
+
+    args = parser.parse_args()
+
+    if args.read_only:
+        set_read_only()
+
+    syncer = DeviceSync()
+
+    try:
+        if args.command == 'devices':
+            if args.json:
+                print(json.dumps({"devices": syncer.get_devices(), "profiles": syncer.get_profiles()}))
+                return
+            for device in syncer.get_devices():
+                print(f"{device['name']}: {device['path']} ({device.get('profile', 'full')})")
+            print(f"Profiles: {', '.join(syncer.get_profiles())}")
+        elif args.command == 'sync':
+            device = next((d for d in syncer.get_devices() if d['name'] == args.device), None) or {"path": args.device}
+            dry_run = args.dry_run or syncer.config.is_read_only()
+            verify_percent = args.verify_percent if args.verify_percent is not None else device.get('verify_percent')
+            summary = syncer.sync(device['path'], args.profile or device.get('profile', 'full'),
+                                  device.get('rom_folder', 'roms'), dry_run, verify_percent, not args.json)
+            if args.json:
+                print(json.dumps(summary))
+                return
+            action = "Would copy" if dry_run else "Copied"
+            print(f"{action} {summary['roms']} ROMs and {summary['artwork']} artwork files, "
+                  f"{summary['gamelists']} gamelists ({summary['profile']} profile) to {summary['device']}")
+            if summary['played']:
+                print(f"{'Would read' if dry_run else 'Read'} the favorites and play history of "
+                      f"{summary['played']} games from the device")
+            if summary['missing']:
+                print(f"{len(summary['missing'])} ROMs skipped (missing):")
+                for path in summary['missing']:
+                    print(f"  {path}")
+            verification = summary.get('verification')
+            if verification and verification['mismatched']:
+                print(f"{len(verification['mismatched'])} of {verification['sampled']} sampled ROMs differ from "
+                      f"their source on the device; verify or sync it again:")
+                for path in verification['mismatched']:
+                    print(f"  {path}")
+            elif verification and verification['sampled'] == verification['copied']:
+                print(f"Verified all {verification['copied']} copied ROMs: all match")
+            elif verification:
+                print(f"Verified {verification['sampled']} of {verification['copied']} copied ROMs: all match, so "
+                      f"with 95% confidence at most {verification['bad_share_bound']:.1%} of the copies are bad")
+    except (OSError, ValueError, ReadOnlyError, ET.ParseError) as e:
+        fail(f"Sync error: {e}", e)
+
+
+if __name__ == "__main__":
+    install_error_reporting()
+    main()
@@ -8,12 +8,16 @@ Enhanced with directory scanning and external list support.
 import json
 import os
 import sqlite3
+import sys
 from pathlib import Path
 
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from rom_config import RomConfig
+
 class GameNameResolver:
-    def __init__(self, db_path="games.db"):
+    def __init__(self, db_path=None):
         self.game_mappings = self.load_game_mappings()
-        self.db_path = Path(db_path)
+        self.db_path = Path(db_path) if db_path else RomConfig().get_path('paths.games_db')
         self.external_mappings = {}
         
     def load_game_mappings(self):
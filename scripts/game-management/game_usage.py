@@ -0,0 +1,114 @@
+#!/usr/bin/env python3
+"""
+Game Usage
+Which games the user cares about, kept in games.db (the game_usage table):
+favorites marked in the GUI or with metadata_downloader.py favorite, and when
+and how often each game was played, as EmulationStation devices record it in
+their gamelist.xml (read back by device_sync.py).
+
+The smart downloader fetches metadata in this order, so a day's limited
+provider quota goes to the games the user sees first:
+
+    favorites                    (most recently played first)
+    played games                 (most recently played first)
+    recently added games         (newest first)
+    everything else              (in the order found)
+"""
+
+import sqlite3
+from datetime import datetime
+from pathlib import Path
+from typing import Dict, List, Optional
+
+
+class GameUsage:
+    """Favorites and play history per game name."""
+
+    def __init__(self, db_path):
+        self.db_path = Path(db_path)
+        self.init_database()
+
+    def init_database(self):
+        conn = sqlite3.connect(self.db_path)
+        conn.execute('''
+            CREATE TABLE IF NOT EXISTS game_usage (
+                game_name TEXT PRIMARY KEY,
+                favorite INTEGER NOT NULL DEFAULT 0,
+                last_played TIMESTAMP,
+                play_count INTEGER NOT NULL DEFAULT 0
+            )
+        ''')
+        conn.commit()
+        conn.close()
+
+    def set_favorite(self, game_name: str, favorite: bool = True):
+        conn = sqlite3.connect(self.db_path)
+        conn.execute('''
+            INSERT INTO game_usage (game_name, favorite) VALUES (?, ?)
+            ON CONFLICT(game_name) DO UPDATE SET favorite = excluded.favorite
+        ''', (game_name, int(favorite)))
+        conn.commit()
+        conn.close()
+
+    def record_play(self, game_name: str, last_played: Optional[datetime] = None, play_count: int = 0,
+                    favorite: bool = False):
+        """Merge play history from elsewhere, e.g. a device: the later play and the higher count win.
+
+        favorite only ever marks a game; unmarking is left to set_favorite.
+        """
+        conn = sqlite3.connect(self.db_path)
+        conn.execute('''
+            INSERT INTO game_usage (game_name, favorite, last_played, play_count) VALUES (?, ?, ?, ?)
+            ON CONFLICT(game_name) DO UPDATE SET
+                favorite = MAX(favorite, excluded.favorite),
+                last_played = CASE WHEN last_played IS NULL OR excluded.last_played > last_played
+                                   THEN COALESCE(excluded.last_played, last_played) ELSE last_played END,
+                play_count = MAX(play_count, excluded.play_count)
+        ''', (game_name, int(favorite), last_played.isoformat() if last_played else None, play_count))
+        conn.commit()
+        conn.close()
+
+    def get_usage(self) -> Dict[str, Dict]:
+        """Get {game name: {favorite, last_played, play_count}} for every game with any."""
+        conn = sqlite3.connect(self.db_path)
+        conn.row_factory = sqlite3.Row
+        rows = conn.execute('SELECT * FROM game_usage').fetchall()
+        conn.close()
+        return {row['game_name']: {"favorite": bool(row['favorite']), "last_played": row['last_played'],
+                                   "play_count": row['play_count']} for row in rows}
+
+    def get_favorites(self) -> List[str]:
+        return sorted(name for name, usage in self.get_usage().items() if usage['favorite'])
+
+    def prioritize(self, game_names: List[str], added: Optional[Dict[str, float]] = None) -> List[str]:
+        """Order games for fetching: favorites, then played, then recently added, then the rest (see above).
+
+        added maps game names to when they were added (a timestamp); names
+        without usage or an added time keep their order.
+        """
+        usage = self.get_usage()
+        added = added or {}
+
+        def priority(item):
+            position, name = item
+            entry = usage.get(name) or {}
+            played = datetime.fromisoformat(entry['last_played']).timestamp() if entry.get('last_played') else None
+            if entry.get('favorite'):
+                tier = 0
+            elif played is not None or entry.get('play_count'):
+                tier = 1
+            elif name in added:
+                return 2, -added[name], position
+            else:
+                return 3, 0, position
+            return tier, -(played or 0), position
+
+        return [name for _, name in sorted(enumerate(game_names), key=priority)]
+
+
+def parse_es_time(value: Optional[str]) -> Optional[datetime]:
+    """Parse an EmulationStation time, e.g. 20231015T203000."""
+    try:
+        return datetime.strptime(value.strip(), "%Y%m%dT%H%M%S") if value else None
+    except ValueError:
+        return None
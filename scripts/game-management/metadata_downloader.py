@@ -7,16 +7,130 @@ Downloads game metadata and cover art from IGDB (Internet Game Database).
 import os
 import sys
 import json
-import requests
+import argparse
+import contextlib
 import sqlite3
 from pathlib import Path
-import time
-from datetime import datetime
+from datetime import datetime, timedelta
 import hashlib
+import difflib
+import threading
+from concurrent.futures import ThreadPoolExecutor, as_completed
+
+from cache_manager import CacheManager
+from config_manager import ConfigManager
+from cover_filters import cover_file, is_missing, parse_filters
+from game_usage import GameUsage
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from game_attributes import delete_attributes, game_ids_with, get_attributes, migrate_json_columns, set_attributes
+from library_db import open_library, title_key
+from plugins import PluginManager
+from http_client import http_session
+from proxy import apply_proxy_settings
+from rate_limiter import QuotaExceeded, RateLimiter
+from response_cache import PROVIDERS, ResponseCache
+from retry_policy import RetryPolicy
+from image_size import image_size
+from job_journal import JobJournal
+from job_progress import ProgressTracker
+from rom_config import RomConfig, add_config_argument
+from scrape_retries import DEFAULT_SYSTEM, ScrapeRetryQueue, alternate_queries, platform_hint
+from script_errors import install_error_reporting
+from text_compression import compress_text, decompress_text
+
+
+# Provider quality, used to decide whether a re-scrape may replace a stored field
+SOURCE_QUALITY = {
+    'manual': 100,
+    'steam': 80,
+    'gog': 70,
+    'rawg': 60,
+    'screenscraper': 60,
+    'google': 10,
+    'basic': 0
+}
+
+# Providers that produce filler rather than a real match
+PLACEHOLDER_SOURCES = ('google', 'basic')
+
+# Stored column -> provenance key recorded in field_sources
+PROVENANCE_FIELDS = {
+    'summary': 'summary',
+    'rating': 'rating',
+    'cover_url': 'cover',
+    'genres': 'genres',
+    'release_date': 'release_date',
+    'developer': 'developer',
+    'publisher': 'publisher'
+}
+
+
+class CachedResponse:
+    """A provider response read back from the response cache; only successful JSON responses are kept."""
+
+    def __init__(self, text):
+        self.status_code = 200
+        self.ok = True
+        self.text = text
+
+    def json(self):
+        return json.loads(self.text)
+
+    def raise_for_status(self):
+        pass
+
+
+# Fields a user can pin with an override; the scraper never writes these
+OVERRIDE_FIELDS = ('name', 'rating', 'genres', 'cover')
+
+# PRAGMA user_version from which cover paths are stored relative to games.db's folder
+RELATIVE_COVERS_VERSION = 1
+# PRAGMA user_version from which Steam ratings are its Metacritic scores, not a guess from the name and genres
+STEAM_RATINGS_VERSION = 2
+
+# What run_until_quota gives for a game whose lookup raised, or was not started once a quota ran out
+FAILED = 'failed'
+DEFERRED = 'deferred'
+
+
+def source_quality(source):
+    return SOURCE_QUALITY.get(source, 0)
+
+
+def run_until_quota(game_names, fn, workers):
+    """Run fn(game_name) for several games at once, yielding (game_name, result) as each finishes.
+
+    result is what fn returned, or FAILED if it raised. Once a provider's daily
+    quota runs out (fn raised QuotaExceeded) no more games are started: those
+    not reached are DEFERRED, to be looked up after it resets.
+    """
+    quota_reached = threading.Event()
+
+    def run(game_name):
+        if quota_reached.is_set():
+            return DEFERRED
+        try:
+            return fn(game_name)
+        except QuotaExceeded as e:
+            if not quota_reached.is_set():
+                quota_reached.set()
+                print(e)
+            return DEFERRED
+        except Exception as e:
+            print(f"Error processing {game_name}: {e}")
+            return FAILED
+
+    with ThreadPoolExecutor(max_workers=max(workers, 1)) as pool:
+        futures = {pool.submit(run, game_name): game_name for game_name in game_names}
+        for future in as_completed(futures):
+            yield futures[future], future.result()
 
 
 class GameMetadataDownloader:
     def __init__(self, api_key=None):
+        self.rom_config = RomConfig()
+        
         # Screenscraper.fr - free and unlimited for registered users
         self.username, self.password = self.get_screenscraper_credentials()
         self.base_url = "https://www.screenscraper.fr/api2"
@@ -27,26 +141,63 @@ class GameMetadataDownloader:
         
         # Local storage paths
         self.metadata_dir = Path("metadata")
-        self.covers_dir = Path("covers")
-        self.db_path = Path("games.db")
+        self.covers_dir = self.rom_config.get_path('paths.covers_directory')
+        self.db_path = self.rom_config.get_path('paths.games_db')
+        apply_proxy_settings(self.rom_config)
+        self.retry = RetryPolicy.from_config(self.rom_config)
+        self.session = http_session(self.rom_config)
+        self.library = open_library(self.rom_config)
+        
+        # Metadata provider plugins rank by their configured quality; built-in names win
+        self.plugins = PluginManager(self.rom_config, log=print)
+        for name, quality in self.plugins.get_provider_qualities().items():
+            SOURCE_QUALITY.setdefault(name, quality)
+        
+        # Cached metadata older than this is re-fetched; 0 disables the policy
+        config = ConfigManager()
+        metadata_settings = dict(config.get_default_config()["metadata"], **config.config.get("metadata", {}))
+        self.max_age_days = metadata_settings["max_age_days"]
         
         # Create directories
         self.metadata_dir.mkdir(exist_ok=True)
-        self.covers_dir.mkdir(exist_ok=True)
+        self.covers_dir.mkdir(parents=True, exist_ok=True)
+        self.db_path.parent.mkdir(parents=True, exist_ok=True)
         
         # Initialize database
         self.init_database()
         
+        # Per-provider pacing and daily quotas, counted in games.db
+        rate_limits = config.config.get("rate_limits", config.get_default_config()["rate_limits"])
+        self.limiter = RateLimiter(self.db_path, rate_limits)
+        # Games no provider matched, waiting to be searched again under other queries
+        self.retries = ScrapeRetryQueue(self.db_path, metadata_settings["retry_hours"],
+                                        metadata_settings["retry_max_attempts"])
+        # Favorites and play history, which decide what a scan fetches first
+        self.usage = GameUsage(self.db_path)
+        self.response_cache = ResponseCache(self.rom_config, PROVIDERS)
+        self.refreshing = False
+        # The journaled scan running, if any; covers being written are noted in it
+        self.journal = JobJournal(self.rom_config)
+        self.job = None
+        # Games whose metadata a batch scan fetches at once; the rate limiter paces each provider
+        self.workers = max(int(metadata_settings["workers"]), 1)
+        
     def get_screenscraper_credentials(self):
         """Get Screenscraper.fr credentials from environment or config file."""
         # Try environment variables first
         username = os.getenv('SCREENSCRAPER_USERNAME')
         password = os.getenv('SCREENSCRAPER_PASSWORD')
         
+        if username and password:
+            return username, password
+        
+        # Then the shared settings
+        username = self.rom_config.get('api_keys.screenscraper_username')
+        password = self.rom_config.get('api_keys.screenscraper_password')
         if username and password:
             return username, password
             
-        # Try config file
+        # Try the legacy config file
         config_file = Path("screenscraper_config.json")
         if config_file.exists():
             with open(config_file, 'r') as f:
@@ -58,6 +209,9 @@ class GameMetadataDownloader:
         
     def get_access_token(self):
         """Get IGDB access token (simplified - in production, implement proper OAuth flow)."""
+        if self.rom_config.get('api_keys.igdb_access_token'):
+            return self.rom_config.get('api_keys.igdb_access_token')
+        
         # Try config file first
         config_file = Path("igdb_config.json")
         if config_file.exists():
@@ -68,6 +222,27 @@ class GameMetadataDownloader:
         # Try environment variable
         return os.getenv('IGDB_ACCESS_TOKEN', 'your_access_token_here')
         
+    def api_get(self, provider, url, **kwargs):
+        """GET an API URL once the provider's rate limiter allows it, retrying transient failures.
+        
+        Every attempt counts against the provider's quota. Successful JSON
+        responses are kept in the response cache for cache.provider_response_hours;
+        a refresh skips the cached ones.
+        """
+        key = json.dumps([provider, url, kwargs.get('params')], sort_keys=True, default=str)
+        cached = None if self.refreshing else self.response_cache.get(key)
+        if cached is not None:
+            return CachedResponse(cached)
+        
+        def attempt():
+            self.limiter.acquire(provider)
+            return self.session.get(url, **kwargs)
+        
+        response = self.retry.call(attempt)
+        if response.status_code == 200 and 'json' in response.headers.get('content-type', ''):
+            self.response_cache.put(key, response.text)
+        return response
+        
     def init_database(self):
         """Initialize SQLite database for game metadata."""
         conn = sqlite3.connect(self.db_path)
@@ -83,22 +258,80 @@ class GameMetadataDownloader:
                 rating REAL,
                 rating_count INTEGER,
                 summary TEXT,
-                genres TEXT,
-                platforms TEXT,
                 release_date TEXT,
-                developer TEXT,
-                publisher TEXT,
                 steam_id INTEGER,
                 metacritic_score INTEGER,
-                last_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+                last_updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                field_sources TEXT,
+                match_confidence REAL,
+                pinned_match TEXT,
+                cover_palette TEXT
+            )
+        ''')
+        
+        # User overrides are kept apart from scraped data so re-scrapes cannot clobber them
+        cursor.execute('''
+            CREATE TABLE IF NOT EXISTS overrides (
+                game_name TEXT NOT NULL,
+                field TEXT NOT NULL,
+                value TEXT NOT NULL,
+                updated TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (game_name, field)
             )
         ''')
         
+        # Add columns to databases created before they existed
+        cursor.execute('PRAGMA table_info(games)')
+        existing_columns = {row[1] for row in cursor.fetchall()}
+        for column, column_type in (('field_sources', 'TEXT'), ('match_confidence', 'REAL'), ('pinned_match', 'TEXT'),
+                                    ('cover_palette', 'TEXT')):
+            if column not in existing_columns:
+                cursor.execute(f'ALTER TABLE games ADD COLUMN {column} {column_type}')
+        
+        # Covers stored before their paths were relative to games.db's folder
+        cursor.execute('PRAGMA user_version')
+        version = cursor.fetchone()[0]
+        if version < RELATIVE_COVERS_VERSION and not self.rom_config.is_read_only():
+            cursor.execute("SELECT id, cover_path FROM games WHERE cover_path IS NOT NULL")
+            for game_id, cover_path in cursor.fetchall():
+                stored = self.stored_cover(cover_path)
+                if stored != cover_path:
+                    cursor.execute('UPDATE games SET cover_path = ? WHERE id = ?', (stored, game_id))
+            cursor.execute(f'PRAGMA user_version = {RELATIVE_COVERS_VERSION}')
+        
+        # Steam ratings stored before were guessed, so they are dropped for the next provider or refresh to fill
+        if version < STEAM_RATINGS_VERSION and not self.rom_config.is_read_only():
+            cursor.execute("SELECT id, field_sources FROM games WHERE rating IS NOT NULL AND field_sources IS NOT NULL")
+            for game_id, field_sources in cursor.fetchall():
+                try:
+                    sources = json.loads(field_sources)
+                except ValueError:
+                    continue
+                if sources.get('rating') == 'steam':
+                    del sources['rating']
+                    cursor.execute('UPDATE games SET rating = NULL, field_sources = ? WHERE id = ?',
+                                   (json.dumps(sources), game_id))
+            cursor.execute(f'PRAGMA user_version = {STEAM_RATINGS_VERSION}')
+        
+        # Genres, platforms, developers and publishers live in tables of their own (game_attributes)
+        migrated = migrate_json_columns(conn)
+        if migrated:
+            print(f"Moved the genres, platforms, developers and publishers of {migrated} games to their own tables",
+                  file=sys.stderr)
+        
         conn.commit()
         conn.close()
         
-    def search_game(self, game_name):
-        """Search for a game using multiple sources."""
+    def search_game(self, game_name, allow_placeholder=True):
+        """Search for a game using multiple sources.
+        
+        Raises QuotaExceeded when nothing was found and a provider was skipped
+        for being over its daily quota, so no placeholder is stored for a game
+        that should be retried once the quota resets. Without allow_placeholder,
+        None is returned rather than Google Images or basic metadata.
+        """
+        self.limiter.pop_denied()
+        
         # Try Steam API first (completely free, no limits)
         steam_result = self.search_steam(game_name)
         if steam_result and steam_result.get('summary') and not steam_result['summary'].startswith('Game: '):
@@ -124,6 +357,19 @@ class GameMetadataDownloader:
         if screenscraper_result and screenscraper_result.get('summary') and not screenscraper_result['summary'].startswith('Game: '):
             return screenscraper_result
         
+        # Try metadata provider plugins declared in rom_browser.json
+        plugin_result = self.plugins.find_metadata(game_name)
+        if plugin_result and plugin_result.get('summary'):
+            return plugin_result
+        
+        denied = self.limiter.pop_denied()
+        if denied:
+            provider = sorted(denied)[0]
+            raise QuotaExceeded(provider, self.limiter.get_quota(provider))
+        
+        if not allow_placeholder:
+            return None
+        
         # Try Google Images as final fallback
         google_result = self.search_google_images(game_name)
         if google_result and google_result.get('summary') and not google_result['summary'].startswith('Game: '):
@@ -145,6 +391,13 @@ class GameMetadataDownloader:
     
     def search_steam(self, game_name):
         """Search for a game using Steam API (free and unlimited)."""
+        items = self.search_steam_candidates(game_name, limit=1)
+        if items and items[0].get('id'):
+            return self.get_steam_game_details(items[0]['id'], items[0])
+        return None
+    
+    def search_steam_candidates(self, game_name, limit=5):
+        """Get the top Steam search results for a game."""
         # Clean game name for better search results
         clean_name = self.clean_game_name_for_search(game_name)
         
@@ -157,7 +410,8 @@ class GameMetadataDownloader:
                 'l': 'english'
             }
             
-            response = requests.get(
+            response = self.api_get(
+                'steam',
                 "https://store.steampowered.com/api/storesearch",
                 params=params,
                 timeout=15
@@ -165,17 +419,13 @@ class GameMetadataDownloader:
             
             if response.status_code == 200:
                 data = response.json()
-                if data.get('items') and len(data['items']) > 0:
-                    # Get detailed info for the first result
-                    steam_id = data['items'][0].get('id')
-                    if steam_id:
-                        return self.get_steam_game_details(steam_id, data['items'][0])
+                return data.get('items', [])[:limit]
             
-            return None
+            return []
                 
         except Exception as e:
             print(f"Error searching Steam for {game_name}: {e}")
-            return None
+            return []
     
     def get_steam_game_details(self, steam_id, basic_data):
         """Get detailed game information from Steam Store API."""
@@ -187,7 +437,8 @@ class GameMetadataDownloader:
                 'l': 'english'
             }
             
-            response = requests.get(
+            response = self.api_get(
+                'steam',
                 f"https://store.steampowered.com/api/appdetails",
                 params=params,
                 timeout=15
@@ -212,50 +463,6 @@ class GameMetadataDownloader:
         # For now, return None to skip Metacritic and rely on other sources
         return None
         
-    def assign_basic_rating(self, game_name, genres):
-        """Assign a basic rating based on game name and genres."""
-        # Simple heuristic rating system
-        rating = 7.0  # Base rating
-        
-        # Adjust based on popular game names
-        popular_games = {
-            'witcher': 9.5, 'skyrim': 9.5, 'fallout': 9.0, 'elder scrolls': 9.0,
-            'dark souls': 9.0, 'bloodborne': 9.5, 'sekiro': 9.0,
-            'zelda': 9.5, 'mario': 9.0, 'pokemon': 8.5,
-            'halo': 8.5, 'gears': 8.0, 'mass effect': 9.0,
-            'dragon age': 8.5, 'bioshock': 9.0, 'portal': 9.5,
-            'half-life': 9.5, 'counter-strike': 8.5, 'dota': 8.5,
-            'league of legends': 8.0, 'world of warcraft': 8.5,
-            'minecraft': 8.5, 'terraria': 8.5, 'stardew valley': 8.5,
-            'civilization': 8.5, 'total war': 8.0, 'xcom': 8.5,
-            'doom': 8.5, 'quake': 8.0, 'wolfenstein': 8.0,
-            'tomb raider': 8.0, 'uncharted': 8.5, 'god of war': 9.0,
-            'spider-man': 8.5, 'batman': 8.5, 'assassin': 8.0,
-            'call of duty': 7.5, 'battlefield': 7.5, 'fifa': 7.0,
-            'nba': 7.0, 'madden': 7.0, 'nhl': 7.0
-        }
-        
-        game_lower = game_name.lower()
-        for keyword, score in popular_games.items():
-            if keyword in game_lower:
-                rating = score
-                break
-        
-        # Adjust based on genres
-        if genres:
-            genre_lower = [g.lower() for g in genres]
-            if 'rpg' in genre_lower:
-                rating += 0.5
-            if 'strategy' in genre_lower:
-                rating += 0.3
-            if 'indie' in genre_lower:
-                rating += 0.2
-            if 'action' in genre_lower:
-                rating += 0.1
-        
-        # Cap rating between 5.0 and 10.0
-        return max(5.0, min(10.0, rating))
-        
     def is_game_match(self, search_name, result_name):
         """Check if a search result matches our game name."""
         # Simple fuzzy matching
@@ -268,11 +475,20 @@ class GameMetadataDownloader:
     
     def search_rawg(self, game_name):
         """Search for a game using RAWG API (10,000 requests/month free)."""
-        # RAWG API requires a free API key - using a demo key for testing
-        rawg_api_key = "c542e67aec3a4340908f9de9e86038af"  # Demo key from RAWG.io
+        results = self.search_rawg_candidates(game_name, limit=1)
+        if not results:
+            return None
+        if results[0].get('id'):
+            return self.get_rawg_game_details(results[0]['id'], results[0])
+        return self.convert_rawg_to_metadata(results[0])
+    
+    def search_rawg_candidates(self, game_name, limit=5):
+        """Get the top RAWG search results for a game."""
+        # RAWG API requires a free API key - falling back to a demo key for testing
+        rawg_api_key = self.rom_config.get('api_keys.rawg') or "c542e67aec3a4340908f9de9e86038af"  # Demo key from RAWG.io
         
         if rawg_api_key == "your_rawg_api_key_here":
-            return None  # Skip if no API key provided
+            return []  # Skip if no API key provided
             
         clean_name = self.clean_game_name_for_search(game_name)
         
@@ -280,10 +496,11 @@ class GameMetadataDownloader:
             params = {
                 'key': rawg_api_key,
                 'search': clean_name,
-                'page_size': 1
+                'page_size': limit
             }
             
-            response = requests.get(
+            response = self.api_get(
+                'rawg',
                 "https://api.rawg.io/api/games",
                 params=params,
                 timeout=15
@@ -291,19 +508,13 @@ class GameMetadataDownloader:
             
             if response.status_code == 200:
                 data = response.json()
-                if data.get('results') and len(data['results']) > 0:
-                    # Get detailed info for the first result
-                    game_id = data['results'][0].get('id')
-                    if game_id:
-                        return self.get_rawg_game_details(game_id, data['results'][0])
-                    else:
-                        return self.convert_rawg_to_metadata(data['results'][0])
+                return data.get('results', [])[:limit]
             
-            return None
+            return []
                 
         except Exception as e:
             print(f"Error searching RAWG for {game_name}: {e}")
-            return None
+            return []
     
     def get_rawg_game_details(self, game_id, basic_data):
         """Get detailed game information from RAWG API."""
@@ -312,7 +523,8 @@ class GameMetadataDownloader:
                 'key': "c542e67aec3a4340908f9de9e86038af"
             }
             
-            response = requests.get(
+            response = self.api_get(
+                'rawg',
                 f"https://api.rawg.io/api/games/{game_id}",
                 params=params,
                 timeout=15
@@ -329,6 +541,97 @@ class GameMetadataDownloader:
             print(f"Error getting RAWG details for {game_id}: {e}")
             return self.convert_rawg_to_metadata(basic_data)
     
+    def get_metadata_candidates(self, game_name, limit=5):
+        """Get the top metadata matches from each provider so the user can pick one.
+        
+        Candidates that look like a match for the game name are listed first,
+        otherwise providers keep their own ranking.
+        """
+        clean_name = self.clean_game_name_for_search(game_name)
+        candidates = []
+        
+        for item in self.search_steam_candidates(game_name, limit):
+            candidates.append({
+                'source': 'steam',
+                'id': item.get('id'),
+                'name': item.get('name', ''),
+                'release_date': None,
+                'cover_url': item.get('tiny_image')
+            })
+        
+        for item in self.search_rawg_candidates(game_name, limit):
+            candidates.append({
+                'source': 'rawg',
+                'id': item.get('id'),
+                'name': item.get('name', ''),
+                'release_date': item.get('released'),
+                'cover_url': item.get('background_image')
+            })
+        
+        for candidate in candidates:
+            candidate['likely_match'] = bool(candidate['name']) and self.is_game_match(clean_name, candidate['name'])
+        
+        candidates.sort(key=lambda c: not c['likely_match'])
+        return [c for c in candidates if c['id']]
+    
+    def fetch_candidate_metadata(self, source, candidate_id):
+        """Get full metadata for a candidate returned by get_metadata_candidates."""
+        if source == 'steam':
+            return self.get_steam_game_details(candidate_id, {'id': candidate_id})
+        if source == 'rawg':
+            return self.get_rawg_game_details(candidate_id, {'id': candidate_id})
+        raise ValueError(f"Unknown metadata source: {source}")
+    
+    def apply_metadata_candidate(self, game_name, source, candidate_id, overwrite_cover=True):
+        """Replace a game's stored metadata and cover with the chosen candidate.
+        
+        The choice is pinned so later refreshes re-fetch this candidate instead
+        of searching by name again.
+        """
+        self.rom_config.ensure_writable(f"store metadata for {game_name}")
+        
+        metadata = self.fetch_candidate_metadata(source, candidate_id)
+        if not metadata:
+            return None
+        
+        cover_url = metadata.get('high_res_cover') or metadata.get('cover', {}).get('url')
+        cover_path = self.download_cover_art(cover_url, game_name, overwrite=overwrite_cover)
+        if not cover_path:
+            cover_path = self.fallback_cover(game_name)
+        
+        # The user confirmed this match, so it replaces every stored field
+        self.store_game_metadata(game_name, metadata, cover_path, match_confidence=1.0,
+                                 prefer_existing=False, pinned_match=f"{source}:{candidate_id}")
+        
+        row = self.load_game_row(game_name)
+        return self.row_to_dict(row) if row else None
+    
+    def pick_metadata_candidate(self, game_name, limit=5):
+        """Interactively choose the metadata match for a game."""
+        candidates = self.get_metadata_candidates(game_name, limit)
+        if not candidates:
+            print(f"No metadata candidates found for {game_name}")
+            return None
+        
+        print(f"\nMetadata candidates for {game_name}:")
+        for i, candidate in enumerate(candidates, 1):
+            year = f" ({candidate['release_date'][:4]})" if candidate['release_date'] else ""
+            marker = "*" if candidate['likely_match'] else " "
+            print(f" {marker}{i:2d}. {candidate['name']}{year} [{candidate['source']}]")
+        
+        choice = input("\nSelect a candidate (Enter to cancel): ").strip()
+        if not choice:
+            return None
+        if not choice.isdigit() or not 1 <= int(choice) <= len(candidates):
+            print("Invalid choice")
+            return None
+        
+        candidate = candidates[int(choice) - 1]
+        result = self.apply_metadata_candidate(game_name, candidate['source'], candidate['id'])
+        if result:
+            print(f"Applied '{candidate['name']}' from {candidate['source']} to {game_name}")
+        return result
+    
     def search_google_images(self, game_name):
         """Search for game cover art using Google Images (fallback method)."""
         try:
@@ -345,6 +648,7 @@ class GameMetadataDownloader:
             # In a production environment, you'd implement proper Google Images API or web scraping
             return {
                 'id': None,
+                'source': 'google',
                 'name': game_name,
                 'cover': {'url': None},
                 'rating': None,
@@ -367,7 +671,6 @@ class GameMetadataDownloader:
     def search_google_for_game_name(self, deformed_name):
         """Search Google to find the correct game name from a deformed filename."""
         try:
-            import requests
             from bs4 import BeautifulSoup
             import re
             
@@ -381,7 +684,7 @@ class GameMetadataDownloader:
             # Use a simple search approach
             search_url = f"https://www.google.com/search?q={search_query.replace(' ', '+')}"
             
-            response = requests.get(search_url, headers=headers, timeout=10)
+            response = self.session.get(search_url, headers=headers, timeout=10)
             
             if response.status_code == 200:
                 soup = BeautifulSoup(response.text, 'html.parser')
@@ -462,7 +765,7 @@ class GameMetadataDownloader:
                 'Referer': 'https://www.gog.com/'
             }
             
-            response = requests.get(search_url, params=params, headers=headers, timeout=15)
+            response = self.api_get('gog', search_url, params=params, headers=headers, timeout=15)
             
             if response.status_code == 200:
                 data = response.json()
@@ -477,8 +780,8 @@ class GameMetadataDownloader:
             print(f"Error searching GOG for {game_name}: {e}")
             return None
     
-    def search_screenscraper(self, game_name):
-        """Search for a game in Screenscraper.fr."""
+    def search_screenscraper(self, game_name, system=DEFAULT_SYSTEM):
+        """Search for a game in Screenscraper.fr, among the games of one system (PC by default)."""
         if not self.username or not self.password:
             return None
             
@@ -492,11 +795,12 @@ class GameMetadataDownloader:
                 'devpassword': self.password, 
                 'softname': 'testlaunchapp',
                 'output': 'json',
-                'systemeid': '1',  # PC games
+                'systemeid': system,
                 'recherche': clean_name
             }
             
-            search_response = requests.get(
+            search_response = self.api_get(
+                'screenscraper',
                 f"{self.base_url}/jeuRecherche.php",
                 headers=self.headers,
                 params=search_params,
@@ -521,7 +825,8 @@ class GameMetadataDownloader:
                                 'id': jeu_id
                             }
                             
-                            info_response = requests.get(
+                            info_response = self.api_get(
+                                'screenscraper',
                                 f"{self.base_url}/jeuInfos.php",
                                 headers=self.headers,
                                 params=info_params,
@@ -550,6 +855,7 @@ class GameMetadataDownloader:
         """Convert RAWG API response to our metadata format."""
         return {
             'id': rawg_data.get('id'),
+            'source': 'rawg',
             'name': rawg_data.get('name', ''),
             'cover': {'url': rawg_data.get('background_image')},
             'rating': rawg_data.get('rating'),
@@ -593,6 +899,7 @@ class GameMetadataDownloader:
         
         return {
             'id': gog_data.get('id'),
+            'source': 'gog',
             'name': gog_data.get('title', ''),
             'cover': {'url': cover_url},
             'rating': gog_data.get('rating'),  # GOG rating if available
@@ -611,22 +918,20 @@ class GameMetadataDownloader:
         """Convert Steam API response to our metadata format."""
         # Handle both detailed data and basic search data
         if isinstance(steam_data, dict) and 'short_description' in steam_data:
-            # Steam API doesn't provide review data, so we'll rely on Metacritic for ratings
-            rating = None
-            rating_count = None
-            
-            # Assign a basic rating based on game popularity/genre
-            # This is a simple heuristic until we get real rating data
-            rating = self.assign_basic_rating(steam_data.get('name', ''), steam_data.get('genres', []))
-            rating_count = None
+            # Steam has no review score of its own; the Metacritic score it lists (0-100) is the rating,
+            # and games without one are left unrated for the next provider to fill
+            metacritic_score = (steam_data.get('metacritic') or {}).get('score')
             
             # Detailed data from appdetails API
             return {
                 'id': steam_data.get('steam_appid'),
+                'source': 'steam',
+                'steam_id': steam_data.get('steam_appid'),
+                'metacritic_score': metacritic_score,
                 'name': steam_data.get('name', ''),
                 'cover': {'url': steam_data.get('header_image')},
-                'rating': rating,
-                'rating_count': rating_count,
+                'rating': metacritic_score / 10 if metacritic_score else None,
+                'rating_count': (steam_data.get('recommendations') or {}).get('total'),
                 'summary': steam_data.get('short_description', ''),
                 'genres': [genre.get('description', '') for genre in steam_data.get('genres', [])],
                 'platforms': ['PC'],
@@ -641,6 +946,8 @@ class GameMetadataDownloader:
             # Basic data from search API
             return {
                 'id': steam_data.get('id'),
+                'source': 'steam',
+                'steam_id': steam_data.get('id'),
                 'name': steam_data.get('name', ''),
                 'cover': {'url': steam_data.get('tiny_image')},
                 'rating': None,
@@ -683,14 +990,15 @@ class GameMetadataDownloader:
             
     def create_basic_metadata(self, game_name):
         """Create basic metadata without API calls."""
-        # Generate a simple cover art placeholder
-        cover_path = self.create_placeholder_cover(game_name)
+        # Use the ROM's own icon, or generate a simple cover art placeholder
+        cover_path = self.fallback_cover(game_name)
         
         return {
             'id': None,
+            'source': 'basic',
             'name': game_name,
             'cover': {'url': None},
-            'cover_path': cover_path,  # Add the placeholder path
+            'cover_path': cover_path,  # Add the icon or placeholder path
             'rating': None,
             'rating_count': None,
             'summary': f"Game: {game_name}\n\nNo detailed information available. Install IGDB API credentials to get full metadata.",
@@ -702,8 +1010,15 @@ class GameMetadataDownloader:
             'external_games': []
         }
         
+    def fallback_cover(self, game_name):
+        """Get artwork for a game without a scraped cover: the icon embedded in its ROM, else a placeholder."""
+        return self.library.find_icon(game_name) or self.create_placeholder_cover(game_name)
+        
     def create_placeholder_cover(self, game_name):
         """Create a placeholder cover art."""
+        if self.rom_config.is_read_only():
+            return None
+            
         try:
             from PIL import Image, ImageDraw, ImageFont
             
@@ -767,19 +1082,22 @@ class GameMetadataDownloader:
         
         return clean_name
         
-    def download_cover_art(self, cover_url, game_name):
-        """Download cover art for a game."""
+    def download_cover_art(self, cover_url, game_name, overwrite=False, suffix=""):
+        """Download cover art for a game; suffix is added to the file name, for a candidate cover."""
         if not cover_url:
             return None
             
         # Create filename from game name
         safe_name = self.safe_filename(game_name)
-        cover_path = self.covers_dir / f"{safe_name}.jpg"
+        cover_path = self.covers_dir / f"{safe_name}{suffix}.jpg"
         
         # Skip if already downloaded
-        if cover_path.exists():
+        if cover_path.exists() and not overwrite:
             return str(cover_path)
             
+        if self.rom_config.is_read_only():
+            return str(cover_path) if cover_path.exists() else None
+            
         try:
             # Handle different URL formats
             if cover_url.startswith('//'):
@@ -789,11 +1107,18 @@ class GameMetadataDownloader:
             elif not cover_url.startswith('http'):
                 cover_url = 'https://www.screenscraper.fr' + cover_url
                 
-            response = requests.get(cover_url, timeout=30)
+            response = self.retry.get(cover_url, timeout=30)
             response.raise_for_status()
             
-            with open(cover_path, 'wb') as f:
+            # A cover cut short by a crash stays a .part file, which the scan's recovery removes
+            part_path = cover_path.with_name(cover_path.name + ".part")
+            if self.job:
+                self.job.add_file(part_path.resolve())
+            with open(part_path, 'wb') as f:
                 f.write(response.content)
+            part_path.replace(cover_path)
+            if self.job:
+                self.job.remove_file(part_path.resolve())
                 
             return str(cover_path)
             
@@ -814,121 +1139,267 @@ class GameMetadataDownloader:
             
         return filename
         
-    def get_steam_metadata(self, game_name):
-        """Get Steam metadata (simplified - would need Steam API)."""
-        # This is a placeholder. In production, you'd use Steam Web API
-        return {
-            'steam_id': None,
-            'steam_genres': [],
-            'steam_tags': []
+    def store_game_metadata(self, game_name, igdb_data, cover_path, match_confidence=None, prefer_existing=True,
+                            pinned_match=None):
+        """Store game metadata in database.
+        
+        Each field records the provider that supplied it. Unless prefer_existing
+        is False, a field already supplied by a higher-quality provider is kept
+        rather than overwritten by a lower-quality one.
+        """
+        self.rom_config.ensure_writable(f"store metadata for {game_name}")
+        
+        if not isinstance(igdb_data, dict):
+            igdb_data = {}
+        source = igdb_data.get('source')
+        
+        if match_confidence is None:
+            match_confidence = self.calculate_match_confidence(game_name, igdb_data)
+        
+        # Extract data from the provider response; list fields may be strings or dicts
+        def names(items):
+            return [item.get('name', '') if isinstance(item, dict) else str(item) for item in items or []]
+        
+        fields = {
+            'summary': igdb_data.get('summary'),
+            'rating': igdb_data.get('rating'),
+            'rating_count': igdb_data.get('rating_count'),
+            'cover_url': igdb_data.get('cover', {}).get('url'),
+            'cover_path': self.stored_cover(cover_path),
+            'genres': names(igdb_data.get('genres')),
+            'platforms': names(igdb_data.get('platforms')),
+            'release_date': igdb_data.get('first_release_date'),
+            'developer': names(igdb_data.get('developers')),
+            'publisher': names(igdb_data.get('publishers'))
         }
         
-    def get_metacritic_score(self, game_name):
-        """Get Metacritic score (simplified - would need web scraping)."""
-        # This is a placeholder. In production, you'd scrape Metacritic
-        return None
+        field_sources = {}
+        for field, provenance_field in PROVENANCE_FIELDS.items():
+            if fields[field] and source:
+                field_sources[provenance_field] = source
         
-    def store_game_metadata(self, game_name, igdb_data, cover_path):
-        """Store game metadata in database."""
-        conn = sqlite3.connect(self.db_path)
-        cursor = conn.cursor()
+        # Merge against scraped values only; overrides are overlaid on read
+        existing = self.get_stored_metadata(game_name, with_overrides=False)
+        if pinned_match is None and existing:
+            pinned_match = existing.get('pinned_match')
         
-        # Extract data from IGDB response
-        genres = []
-        platforms = []
-        developers = []
-        publishers = []
+        if existing and prefer_existing:
+            existing_sources = existing.get('field_sources') or {}
+            for field, provenance_field in PROVENANCE_FIELDS.items():
+                existing_source = existing_sources.get(provenance_field)
+                if not existing.get(field) or not existing_source:
+                    continue
+                new_source = field_sources.get(provenance_field)
+                if not new_source or source_quality(existing_source) > source_quality(new_source):
+                    fields[field] = existing[field]
+                    field_sources[provenance_field] = existing_source
+                    # The cover URL and file travel together
+                    if field == 'cover_url':
+                        fields['cover_path'] = existing['cover_path']
+            match_confidence = max(match_confidence, existing.get('match_confidence') or 0.0)
         
-        if igdb_data and isinstance(igdb_data, dict):
-            try:
-                # Handle genres - they might be strings or dicts
-                genres = []
-                for g in igdb_data.get('genres', []):
-                    if isinstance(g, dict):
-                        genres.append(g.get('name', ''))
-                    else:
-                        genres.append(str(g))
-                
-                # Handle platforms - they might be strings or dicts
-                platforms = []
-                for p in igdb_data.get('platforms', []):
-                    if isinstance(p, dict):
-                        platforms.append(p.get('name', ''))
-                    else:
-                        platforms.append(str(p))
-                
-                # Handle developers - they might be strings or dicts
-                developers = []
-                for d in igdb_data.get('developers', []):
-                    if isinstance(d, dict):
-                        developers.append(d.get('name', ''))
-                    else:
-                        developers.append(str(d))
-                
-                # Handle publishers - they might be strings or dicts
-                publishers = []
-                for p in igdb_data.get('publishers', []):
-                    if isinstance(p, dict):
-                        publishers.append(p.get('name', ''))
-                    else:
-                        publishers.append(str(p))
-            except Exception as e:
-                print(f"Error extracting data from igdb_data: {e}")
-                genres = []
-                platforms = []
-                developers = []
-                publishers = []
+        conn = sqlite3.connect(self.db_path)
+        cursor = conn.cursor()
             
-        # Get Steam and Metacritic data
-        steam_data = self.get_steam_metadata(game_name)
-        metacritic_score = self.get_metacritic_score(game_name)
+        # The Steam app and Metacritic score, from whichever provider result found them
+        steam_id = igdb_data.get('steam_id') or (existing or {}).get('steam_id')
+        metacritic_score = igdb_data.get('metacritic_score') or (existing or {}).get('metacritic_score')
         
+        # Replacing the row gives the game a new id, so its old attribute rows go first
+        delete_attributes(conn, game_name)
         cursor.execute('''
             INSERT OR REPLACE INTO games 
             (name, igdb_id, cover_url, cover_path, rating, rating_count, summary,
-             genres, platforms, release_date, developer, publisher, steam_id,
-             metacritic_score, last_updated)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+             release_date, steam_id, metacritic_score, last_updated, field_sources,
+             match_confidence, pinned_match)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         ''', (
             game_name,
-            igdb_data.get('id') if igdb_data and isinstance(igdb_data, dict) else None,
-            igdb_data.get('cover', {}).get('url') if igdb_data and isinstance(igdb_data, dict) else None,
-            cover_path,
-            igdb_data.get('rating') if igdb_data and isinstance(igdb_data, dict) else None,
-            igdb_data.get('rating_count') if igdb_data and isinstance(igdb_data, dict) else None,
-            igdb_data.get('summary') if igdb_data and isinstance(igdb_data, dict) else None,
-            json.dumps(genres),
-            json.dumps(platforms),
-            igdb_data.get('first_release_date') if igdb_data and isinstance(igdb_data, dict) else None,
-            json.dumps(developers),
-            json.dumps(publishers),
-            steam_data.get('steam_id'),
+            igdb_data.get('id'),
+            fields['cover_url'],
+            fields['cover_path'],
+            fields['rating'],
+            fields['rating_count'],
+            compress_text(fields['summary']),
+            fields['release_date'],
+            steam_id,
             metacritic_score,
-            datetime.now()
+            datetime.now(),
+            json.dumps(field_sources),
+            match_confidence,
+            pinned_match
         ))
+        set_attributes(conn, cursor.lastrowid, {field: fields[field] for field in
+                                                ('genres', 'platforms', 'developer', 'publisher')})
+        
+        conn.commit()
+        conn.close()
+        
+        if source and source not in PLACEHOLDER_SOURCES:
+            self.retries.remove(game_name)
+    
+    def calculate_match_confidence(self, game_name, metadata):
+        """Score how well a provider result matches the game name, from 0.0 to 1.0."""
+        if not metadata or metadata.get('source') in PLACEHOLDER_SOURCES:
+            return 0.0
+        
+        result_name = metadata.get('name')
+        if not result_name:
+            return 0.0
+        
+        search = self.clean_game_name_for_search(game_name).lower()
+        result = self.clean_game_name_for_search(result_name).lower()
+        return round(difflib.SequenceMatcher(None, search, result).ratio(), 2)
+    
+    def get_stored_metadata(self, game_name, with_overrides=True):
+        """Get the stored metadata row for a game without downloading anything."""
+        if not self.db_path.exists():
+            return None
+        
+        row = self.load_game_row(game_name)
+        return self.row_to_dict(row, with_overrides) if row else None
+    
+    def load_game_row(self, game_name):
+        """Get a game's stored row, with its genres, platforms, developers and publishers, or None."""
+        conn = sqlite3.connect(self.db_path)
+        conn.row_factory = sqlite3.Row
+        row = conn.execute('SELECT * FROM games WHERE name = ?', (game_name,)).fetchone()
+        attributes = get_attributes(conn, [row['id']])[row['id']] if row else {}
+        conn.close()
+        return dict(dict(row), **attributes) if row else None
+        
+    def get_game_metadata(self, game_name, max_age_days=None):
+        """Get metadata for a game (from cache or download).
+        
+        Cached metadata older than max_age_days (default: the configured
+        metadata.max_age_days) is re-fetched.
+        """
+        existing = self.get_stored_metadata(game_name)
+        
+        if existing and not self.is_stale(existing, max_age_days):
+            return existing
+        
+        if self.rom_config.is_read_only():
+            if not existing:
+                print(f"Read-only mode: not downloading metadata for {game_name}")
+            return existing
+        
+        if existing:
+            print(f"Metadata for {game_name} is stale, refreshing")
+        
+        return self.refresh_game_metadata(game_name)
+    
+    def is_stale(self, metadata, max_age_days=None):
+        """Check whether stored metadata is older than the maximum age."""
+        max_age_days = self.max_age_days if max_age_days is None else max_age_days
+        if not max_age_days or not metadata.get('last_updated'):
+            return False
+        
+        try:
+            last_updated = datetime.fromisoformat(str(metadata['last_updated']))
+        except ValueError:
+            return True
+        
+        return datetime.now() - last_updated > timedelta(days=max_age_days)
+    
+    def compact_database(self):
+        """Compress large summaries stored before compression was added, then reclaim space.
+        
+        Returns (rows compressed, size before, size after) in bytes.
+        """
+        self.rom_config.ensure_writable("compact the metadata database")
+        
+        size_before = self.db_path.stat().st_size
+        conn = sqlite3.connect(self.db_path)
+        cursor = conn.cursor()
+        cursor.execute("SELECT id, summary FROM games WHERE typeof(summary) = 'text'")
+        
+        compressed = 0
+        for game_id, summary in cursor.fetchall():
+            packed = compress_text(summary)
+            if packed is not summary:
+                cursor.execute('UPDATE games SET summary = ? WHERE id = ?', (packed, game_id))
+                compressed += 1
         
         conn.commit()
+        conn.execute('VACUUM')
         conn.close()
         
-    def get_game_metadata(self, game_name):
-        """Get metadata for a game (from cache or download)."""
+        return compressed, size_before, self.db_path.stat().st_size
+    
+    def get_stored_game_names(self):
+        """Get the names of all games in the database."""
+        if not self.db_path.exists():
+            return []
+        
         conn = sqlite3.connect(self.db_path)
         cursor = conn.cursor()
+        cursor.execute('SELECT name FROM games ORDER BY name')
+        names = [row[0] for row in cursor.fetchall()]
+        conn.close()
         
-        # Check if we already have this game
-        cursor.execute('SELECT * FROM games WHERE name = ?', (game_name,))
-        row = cursor.fetchone()
+        return names
+    
+    def find_games(self, genre=None, platform=None):
+        """Get the names of the stored games with a genre and/or platform (ignoring case), with their genres."""
+        if not self.db_path.exists():
+            return []
         
-        if row:
-            conn.close()
-            return self.row_to_dict(row)
-            
+        conn = sqlite3.connect(self.db_path)
+        game_ids = None
+        for field, value in (('genres', genre), ('platforms', platform)):
+            if value:
+                matched = set(game_ids_with(conn, field, value))
+                game_ids = matched if game_ids is None else game_ids & matched
+        
+        attributes = get_attributes(conn, sorted(game_ids) if game_ids is not None else None)
+        names = dict(conn.execute('SELECT id, name FROM games').fetchall())
         conn.close()
         
+        return sorted(({'name': names[game_id], 'genres': values['genres'], 'platforms': values['platforms']}
+                       for game_id, values in attributes.items()), key=lambda game: game['name'].lower())
+    
+    def get_stale_games(self, max_age_days=None):
+        """Get the names of stored games whose metadata is older than the maximum age."""
+        if not self.db_path.exists():
+            return []
+        
+        conn = sqlite3.connect(self.db_path)
+        cursor = conn.cursor()
+        cursor.execute('SELECT name, last_updated FROM games ORDER BY name')
+        rows = cursor.fetchall()
+        conn.close()
+        
+        return [name for name, last_updated in rows
+                if self.is_stale({'last_updated': last_updated}, max_age_days)]
+    
+    def refresh_game_metadata(self, game_name):
+        """Re-scrape a game's metadata, bypassing the cache.
+        
+        A match the user picked is re-fetched from the same candidate, and
+        fields from higher-quality providers are kept.
+        """
+        self.rom_config.ensure_writable(f"refresh metadata for {game_name}")
+        self.refreshing = True
+        try:
+            return self.fetch_fresh_metadata(game_name)
+        finally:
+            self.refreshing = False
+    
+    def fetch_fresh_metadata(self, game_name):
+        existing = self.get_stored_metadata(game_name)
+        if existing and existing.get('pinned_match'):
+            source, _, candidate_id = existing['pinned_match'].partition(':')
+            result = self.apply_metadata_candidate(game_name, source, candidate_id, overwrite_cover=False)
+            if result:
+                return result
+            print(f"Could not re-fetch pinned match {existing['pinned_match']} for {game_name}")
+            return existing
+        
         # Download new metadata using resolved game name
         try:
             from game_name_resolver import GameNameResolver
-            resolver = GameNameResolver()
+            resolver = GameNameResolver(self.db_path)
             resolved_name = resolver.resolve_game_name(game_name)
             print(f"Resolved '{game_name}' to '{resolved_name}' for GameBrain search")
         except ImportError:
@@ -948,95 +1419,574 @@ class GameMetadataDownloader:
         
         # If no cover path yet, create a placeholder
         if not cover_path:
-            cover_path = self.create_placeholder_cover(game_name)
+            cover_path = self.fallback_cover(game_name)
             
         # Store in database
         self.store_game_metadata(game_name, igdb_data, cover_path)
         
+        # A placeholder is only kept until a retry under another query finds the game
+        if (igdb_data or {}).get('source') in PLACEHOLDER_SOURCES and not (existing or {}).get('field_sources'):
+            _, platform = self.find_library_files(game_name)
+            self.retries.add(game_name, platform)
+            print(f"No provider matched {game_name}; queued for a retry with alternate queries")
+        
         # Return the stored data
+        return self.get_stored_metadata(game_name)
+    
+    def get_cover_refresh_candidates(self, filters):
+        """Get the names of games whose cover matches the filters (see cover_filters).
+        
+        Games with a pinned cover are left out, since a refreshed cover would not be shown.
+        """
+        matches = parse_filters(filters)
+        names = []
+        for game_name in self.get_stored_game_names():
+            if 'cover' in self.get_overrides(game_name):
+                continue
+            if matches(self.get_stored_metadata(game_name, with_overrides=False)):
+                names.append(game_name)
+        return names
+    
+    def set_cover(self, game_name, cover_url, cover_path, source):
+        """Record a new cover for a game without touching its other fields."""
+        self.rom_config.ensure_writable(f"store the cover for {game_name}")
+        game = self.get_stored_metadata(game_name, with_overrides=False)
+        field_sources = (game.get('field_sources') or {}) if game else {}
+        if source:
+            field_sources['cover'] = source
+        
         conn = sqlite3.connect(self.db_path)
-        cursor = conn.cursor()
-        cursor.execute('SELECT * FROM games WHERE name = ?', (game_name,))
-        row = cursor.fetchone()
+        conn.execute('UPDATE games SET cover_url = ?, cover_path = ?, field_sources = ? WHERE name = ?',
+                     (cover_url, self.stored_cover(cover_path), json.dumps(field_sources), game_name))
+        conn.commit()
         conn.close()
+    
+    def refresh_cover(self, game_name):
+        """Re-fetch a game's cover. Returns 'refreshed', 'unchanged' or 'failed'.
         
-        return self.row_to_dict(row) if row else None
+        A cover whose file went missing is downloaded again from its stored
+        URL. Otherwise the game is looked up again (its pinned match, if the
+        user picked one) and the provider's cover replaces the stored one when
+        it is a different, larger image. Raises QuotaExceeded when the lookup
+        was refused for being over a provider's daily quota.
+        """
+        game = self.get_stored_metadata(game_name, with_overrides=False)
+        if not game:
+            return 'failed'
         
-    def row_to_dict(self, row):
-        """Convert database row to dictionary."""
-        columns = [
-            'id', 'name', 'igdb_id', 'cover_url', 'cover_path', 'rating', 
-            'rating_count', 'summary', 'genres', 'platforms', 'release_date',
-            'developer', 'publisher', 'steam_id', 'metacritic_score', 'last_updated'
-        ]
+        source = (game.get('field_sources') or {}).get('cover')
+        if game.get('cover_url') and is_missing(game):
+            cover_path = self.download_cover_art(game['cover_url'], game_name, overwrite=True)
+            if cover_path:
+                self.set_cover(game_name, game['cover_url'], cover_path, source)
+                return 'refreshed'
         
-        data = dict(zip(columns, row))
+        if game.get('pinned_match'):
+            pinned_source, _, candidate_id = game['pinned_match'].partition(':')
+            metadata = self.fetch_candidate_metadata(pinned_source, candidate_id)
+        else:
+            metadata = self.search_game(game_name)
         
-        # Parse JSON fields
-        for field in ['genres', 'platforms', 'developer', 'publisher']:
-            if data[field]:
-                try:
-                    data[field] = json.loads(data[field])
-                except:
-                    data[field] = []
-            else:
-                data[field] = []
-                
-        return data
+        if not metadata or metadata.get('source') in PLACEHOLDER_SOURCES:
+            return 'unchanged'
+        cover_url = metadata.get('high_res_cover') or metadata.get('cover', {}).get('url')
+        if not cover_url or (cover_url == game.get('cover_url') and not is_missing(game)):
+            return 'unchanged'
         
-    def batch_download_metadata(self, game_names, progress_callback=None):
-        """Download metadata for multiple games."""
-        results = []
+        candidate = self.download_cover_art(cover_url, game_name, overwrite=True, suffix=".new")
+        if not candidate:
+            return 'failed'
+        candidate = Path(candidate)
         
-        for i, game_name in enumerate(game_names):
-            progress_msg = f"Processing {i+1}/{len(game_names)}: {game_name}"
-            print(progress_msg)
-            
-            # Call progress callback if provided
-            if progress_callback:
-                progress_callback(progress_msg, i+1, len(game_names))
+        # Keep the current cover when the provider's is no bigger
+        current = cover_file(game)
+        current_size = image_size(current) if current and current.exists() else None
+        new_size = image_size(candidate)
+        if current_size and new_size and new_size[0] * new_size[1] <= current_size[0] * current_size[1]:
+            candidate.unlink()
+            return 'unchanged'
+        
+        cover_path = self.covers_dir / f"{self.safe_filename(game_name)}.jpg"
+        candidate.replace(cover_path)
+        self.set_cover(game_name, cover_url, str(cover_path), metadata.get('source'))
+        return 'refreshed'
+    
+    def refresh_covers(self, game_names, workers=4, reporter=None):
+        """Re-fetch covers for several games in parallel.
+        
+        Returns the game names by outcome: refreshed, unchanged, failed and
+        deferred. Once a provider's daily quota runs out no more lookups are
+        started, and the games not reached are deferred until it resets.
+        Progress goes to reporter (see job_progress), by default the terminal
+        or the GUI.
+        """
+        self.rom_config.ensure_writable("refresh covers")
+        results = {'refreshed': [], 'unchanged': [], FAILED: [], DEFERRED: []}
+        
+        progress = ProgressTracker("scrape", total=len(game_names), label="covers", reporter=reporter)
+        for game_name, outcome in run_until_quota(game_names, self.refresh_cover, workers):
+            results[outcome].append(game_name)
+            progress.advance(message=f"Cover: {game_name} ({outcome})")
+        progress.finish()
+        
+        for names in results.values():
+            names.sort()
+        
+        # Keep the covers cache within its size budget
+        CacheManager(self.covers_dir, self.db_path).maybe_collect_garbage()
+        return results
+    
+    def refresh_games(self, game_names, reporter=None):
+        """Re-scrape metadata for several games. Returns (refreshed, failed) name lists."""
+        refreshed = []
+        failed = []
+        progress = ProgressTracker("scrape", total=len(game_names), label="refresh", reporter=reporter)
+        
+        for game_name in game_names:
+            progress.advance(message=f"Refreshing: {game_name}")
             
             try:
-                metadata = self.get_game_metadata(game_name)
-                results.append(metadata)
-                
-                # Rate limiting - be nice to Screenscraper.fr
-                time.sleep(0.1)  # Much faster since it's unlimited
-                
+                if self.refresh_game_metadata(game_name):
+                    refreshed.append(game_name)
+                else:
+                    failed.append(game_name)
+            except QuotaExceeded as e:
+                print(e)
+                break
             except Exception as e:
-                error_msg = f"Error processing {game_name}: {e}"
-                print(error_msg)
-                if progress_callback:
-                    progress_callback(error_msg, i+1, len(game_names))
-                results.append(None)
-                
+                print(f"Error refreshing {game_name}: {e}")
+                failed.append(game_name)
+        
+        progress.finish()
+        return refreshed, failed
+    
+    def find_library_files(self, game_name):
+        """Get the library files whose title matches a game name, and their platform (or the name's hint)."""
+        key = title_key(game_name)
+        entries = [entry for entry in self.library.get_entries() if title_key(entry['filename']) == key]
+        platforms = [entry['platform'] for entry in entries if entry['platform']]
+        return [entry['filename'] for entry in entries], platforms[0] if platforms else platform_hint(game_name)
+    
+    def retry_failed_scrapes(self, include_waiting=False, reporter=None):
+        """Search again for the queued games no provider matched, trying each alternate query in turn.
+        
+        A match is stored like any scrape and takes the game out of the queue.
+        Returns {'matched': [{game, strategy, query}], 'failed': [names], 'deferred': [names]};
+        games are deferred when a provider quota runs out.
+        """
+        self.rom_config.ensure_writable("retry failed scrapes")
+        due = self.retries.get_due(include_waiting)
+        results = {'matched': [], 'failed': [], 'deferred': []}
+        progress = ProgressTracker("scrape", total=len(due), label="retries", reporter=reporter)
+        
+        for index, entry in enumerate(due):
+            game_name = entry['game_name']
+            progress.advance(message=f"Retrying: {game_name}")
+            file_names, platform = self.find_library_files(game_name)
+            strategies = []
+            try:
+                for strategy, query, system in alternate_queries(game_name, file_names, entry['platform'] or platform):
+                    strategies.append(strategy)
+                    if system:
+                        result = self.search_screenscraper(query, system)
+                    else:
+                        result = self.search_game(query, allow_placeholder=False)
+                    if result and result.get('summary') and not result['summary'].startswith('Game: '):
+                        break
+                else:
+                    result = None
+            except QuotaExceeded as e:
+                print(e)
+                results['deferred'] = [deferred['game_name'] for deferred in due[index:]]
+                break
+            
+            if not result:
+                self.retries.record_failure(game_name, strategies)
+                results['failed'].append(game_name)
+                continue
+            
+            cover_path = None
+            if result.get('cover') and result['cover'].get('url'):
+                cover_path = self.download_cover_art(result['cover']['url'], game_name, overwrite=True)
+            self.store_game_metadata(game_name, result, cover_path or self.fallback_cover(game_name))
+            print(f"Matched {game_name} by its {strategy.replace('_', ' ')}: {query}")
+            results['matched'].append({'game': game_name, 'strategy': strategy, 'query': query})
+        
+        progress.finish()
         return results
+        
+    def stored_cover(self, cover_path):
+        """Get the form a cover path is stored in: relative to the folder games.db is in when the
+        cover is under it, so the database and its covers can be moved to another machine together."""
+        if not cover_path or str(cover_path).startswith(('http://', 'https://')) or not Path(cover_path).is_absolute():
+            return cover_path
+        try:
+            return Path(cover_path).resolve().relative_to(self.db_path.parent.resolve()).as_posix()
+        except ValueError:
+            return str(cover_path)
+    
+    def cover_location(self, cover_path):
+        """Get the local path of a stored cover path."""
+        if not cover_path or str(cover_path).startswith(('http://', 'https://')) or Path(cover_path).is_absolute():
+            return cover_path
+        return str(self.db_path.parent / cover_path)
+    
+    def row_to_dict(self, row, with_overrides=True):
+        """Convert a row from load_game_row to the metadata dictionary."""
+        data = dict(row)
+        data['summary'] = decompress_text(data['summary'])
+        data['cover_path'] = self.cover_location(data.get('cover_path'))
+        
+        try:
+            data['field_sources'] = json.loads(data['field_sources']) if data['field_sources'] else {}
+        except (TypeError, ValueError):
+            data['field_sources'] = {}
+        
+        return self.apply_overrides(data) if with_overrides else data
+    
+    def get_overrides(self, game_name):
+        """Get the fields a user has pinned for a game."""
+        conn = sqlite3.connect(self.db_path)
+        cursor = conn.cursor()
+        cursor.execute('SELECT field, value FROM overrides WHERE game_name = ?', (game_name,))
+        overrides = {field: json.loads(value) for field, value in cursor.fetchall()}
+        conn.close()
+        return overrides
+    
+    def set_favorite(self, game_name, favorite=True):
+        """Mark a game as a favorite, or unmark it; scans fetch favorites first."""
+        self.rom_config.ensure_writable(f"{'mark' if favorite else 'unmark'} {game_name} as a favorite")
+        self.usage.set_favorite(game_name, favorite)
+    
+    def set_override(self, game_name, field, value):
+        """Pin a field for a game, or clear the pin when value is None."""
+        if field not in OVERRIDE_FIELDS:
+            raise ValueError(f"Cannot override '{field}'; choose one of {', '.join(OVERRIDE_FIELDS)}")
+        self.rom_config.ensure_writable(f"set a {field} override for {game_name}")
+        
+        if field == 'rating' and value is not None:
+            value = float(value)
+        elif field == 'genres' and isinstance(value, str):
+            value = [genre.strip() for genre in value.split(',') if genre.strip()]
+        
+        conn = sqlite3.connect(self.db_path)
+        cursor = conn.cursor()
+        if value is None:
+            cursor.execute('DELETE FROM overrides WHERE game_name = ? AND field = ?', (game_name, field))
+        else:
+            cursor.execute('''
+                INSERT OR REPLACE INTO overrides (game_name, field, value, updated)
+                VALUES (?, ?, ?, ?)
+            ''', (game_name, field, json.dumps(value), datetime.now()))
+        conn.commit()
+        conn.close()
+    
+    def apply_overrides(self, data):
+        """Overlay a user's pinned fields on stored metadata."""
+        overrides = self.get_overrides(data['name'])
+        data['display_name'] = overrides.get('name', data['name'])
+        data['overrides'] = sorted(overrides)
+        
+        if 'rating' in overrides:
+            data['rating'] = overrides['rating']
+        if 'genres' in overrides:
+            data['genres'] = overrides['genres']
+        if 'cover' in overrides:
+            data['cover_path'] = overrides['cover']
+        for field in overrides:
+            data['field_sources'][field] = 'manual'
+        
+        return data
+        
+    def batch_download_metadata(self, game_names, workers=None, reporter=None):
+        """Get metadata for several games in parallel (from cache or download).
+        
+        Returns the game names by outcome, in the order given: matched by a
+        provider, unmatched (a placeholder, or nothing in read-only mode),
+        failed and deferred. Once a provider's daily quota runs out no more
+        lookups are started, and the games not reached are deferred until it
+        resets.
+        """
+        def fetch(game_name):
+            metadata = self.get_game_metadata(game_name)
+            sources = ((metadata or {}).get('field_sources') or {}).values()
+            return 'matched' if any(source not in PLACEHOLDER_SOURCES for source in sources) else 'unmatched'
+        
+        outcomes = {}
+        progress = ProgressTracker("scrape", total=len(game_names), label="metadata", reporter=reporter)
+        for game_name, outcome in run_until_quota(game_names, fetch, workers or self.workers):
+            outcomes[game_name] = outcome
+            progress.advance(message=f"Metadata: {game_name} ({outcome})")
+        progress.finish()
+        
+        results = {'matched': [], 'unmatched': [], FAILED: [], DEFERRED: []}
+        for game_name in game_names:
+            results[outcomes[game_name]].append(game_name)
+        
+        # Keep the covers cache within its size budget
+        CacheManager(self.covers_dir, self.db_path).maybe_collect_garbage()
+        return results
+
+
+def read_game_list(lines):
+    """Get the game names of a list, one per line; blank lines and lines starting with # are skipped."""
+    game_names = []
+    for line in lines:
+        game_name = line.strip()
+        if game_name and not game_name.startswith('#') and game_name not in game_names:
+            game_names.append(game_name)
+    return game_names
 
 
 def main():
-    """Test the metadata downloader."""
+    """Scrape a list of games, or pick and manage the metadata of stored games."""
+    parser = argparse.ArgumentParser(description='Game metadata downloader')
+    add_config_argument(parser)
+    subparsers = parser.add_subparsers(dest='command', required=True)
+    
+    scrape_parser = subparsers.add_parser('scrape', help='Get metadata for a list of games',
+                                          epilog='With neither names nor --games-file, the names are read '
+                                                 'from stdin, one per line.')
+    scrape_parser.add_argument('games', nargs='*', help='Game names')
+    scrape_parser.add_argument('--games-file', metavar='PATH',
+                               help='File with one game name per line (- for stdin); # starts a comment')
+    scrape_parser.add_argument('--workers', type=int, help='Games fetched in parallel (default: metadata.workers)')
+    scrape_parser.add_argument('--json', action='store_true', help='Print a summary as JSON')
+    
+    candidates_parser = subparsers.add_parser('candidates', help='List metadata candidates for a game')
+    candidates_parser.add_argument('game', help='Game name')
+    candidates_parser.add_argument('--limit', type=int, default=5, help='Candidates per provider')
+    candidates_parser.add_argument('--json', action='store_true', help='Print the candidates as JSON')
+    
+    apply_parser = subparsers.add_parser('apply', help='Apply a metadata candidate to a game')
+    apply_parser.add_argument('game', help='Game name')
+    apply_parser.add_argument('--source', required=True, choices=['steam', 'rawg'], help='Candidate provider')
+    apply_parser.add_argument('--id', required=True, help='Candidate id')
+    apply_parser.add_argument('--json', action='store_true', help='Print the stored metadata as JSON')
+    
+    refresh_parser = subparsers.add_parser('refresh', help='Re-scrape metadata, keeping matches the user picked')
+    refresh_target = refresh_parser.add_mutually_exclusive_group(required=True)
+    refresh_target.add_argument('--all', action='store_true', help='Refresh every game in the database')
+    refresh_target.add_argument('--game', help='Refresh a single game')
+    refresh_parser.add_argument('--max-age-days', type=int, help='With --all, only refresh entries older than this')
+    refresh_parser.add_argument('--json', action='store_true', help='Print a summary as JSON')
+    
+    override_parser = subparsers.add_parser('override', help='Pin fields so re-scraping never changes them')
+    override_parser.add_argument('game', help='Game name')
+    override_parser.add_argument('--name', help='Display name')
+    override_parser.add_argument('--rating', type=float, help='Rating (0-10)')
+    override_parser.add_argument('--genres', help='Comma-separated genres')
+    override_parser.add_argument('--cover', help='Cover image path or URL')
+    override_parser.add_argument('--clear', action='append', choices=OVERRIDE_FIELDS, default=[],
+                                 help='Remove a pinned field (repeatable)')
+    
+    favorite_parser = subparsers.add_parser('favorite', help='Mark a game as a favorite, which scans fetch first, '
+                                                             'or list the favorites')
+    favorite_parser.add_argument('game', nargs='?', help='Game name (default: list the favorites)')
+    favorite_parser.add_argument('--remove', action='store_true', help='Unmark the game')
+    favorite_parser.add_argument('--json', action='store_true', help='Print the favorites as JSON')
+    
+    covers_parser = subparsers.add_parser('covers', help='Manage cover art')
+    covers_subparsers = covers_parser.add_subparsers(dest='covers_command', required=True)
+    covers_refresh_parser = covers_subparsers.add_parser(
+        'refresh', help='Re-download covers for the games a filter selects',
+        epilog='Filters: missing, placeholder, below:WxH, source:NAME, older:DAYS, name:GLOB. '
+               'Comma-separated terms match any; repeated --filter options must all match.')
+    covers_refresh_parser.add_argument('--filter', action='append', required=True, dest='filters',
+                                       help='Games to refresh, e.g. missing,below:300x400 (repeatable)')
+    covers_refresh_parser.add_argument('--workers', type=int, default=4, help='Covers fetched in parallel')
+    covers_refresh_parser.add_argument('--dry-run', action='store_true', help='List the selected games only')
+    covers_refresh_parser.add_argument('--json', action='store_true', help='Print a summary as JSON')
+    
+    retry_parser = subparsers.add_parser('retry', help='Search again for games no provider matched')
+    retry_parser.add_argument('--all', action='store_true', help='Retry every queued game, not only the ones due')
+    retry_parser.add_argument('--list', action='store_true', help='Show the queue without searching')
+    retry_parser.add_argument('--json', action='store_true', help='Print the queue or a summary as JSON')
+    
+    subparsers.add_parser('compact', help='Compress stored summaries and shrink the database')
+    
+    list_parser = subparsers.add_parser('list', help='List stored games, optionally by genre or platform')
+    list_parser.add_argument('--genre', help='Only games with this genre')
+    list_parser.add_argument('--platform', help='Only games on this platform')
+    list_parser.add_argument('--json', action='store_true', help='Print the games as JSON')
+    
+    pick_parser = subparsers.add_parser('pick', help='Interactively choose the metadata match for a game')
+    pick_parser.add_argument('game', help='Game name')
+    pick_parser.add_argument('--limit', type=int, default=5, help='Candidates per provider')
+    
+    args = parser.parse_args()
+    
+    if args.command == 'scrape':
+        lines = list(args.games)
+        if args.games_file == '-' or (not args.games and not args.games_file and not sys.stdin.isatty()):
+            lines += sys.stdin.read().splitlines()
+        elif args.games_file:
+            try:
+                lines += Path(args.games_file).read_text(encoding='utf-8-sig').splitlines()
+            except OSError as e:
+                parser.error(f"cannot read {args.games_file}: {e}")
+        game_names = read_game_list(lines)
+        if not game_names:
+            parser.error("no games to scrape; name them, or pass --games-file or a list on stdin")
+    
     downloader = GameMetadataDownloader()
     
-    # Test with a few games
-    test_games = [
-        "SkyrimSE",
-        "Stardew Valley", 
-        "Elden Ring",
-        "The Witcher 3"
-    ]
+    if args.command in ('candidates', 'apply') and args.json:
+        # Keep stdout clean for the JSON result; diagnostics go to stderr
+        with contextlib.redirect_stdout(sys.stderr):
+            if args.command == 'candidates':
+                result = downloader.get_metadata_candidates(args.game, args.limit)
+            else:
+                result = downloader.apply_metadata_candidate(args.game, args.source, args.id)
+        print(json.dumps(result, default=str))
+        return
+    
+    if args.command == 'candidates':
+        for candidate in downloader.get_metadata_candidates(args.game, args.limit):
+            print(f"{candidate['source']}:{candidate['id']}  {candidate['name']}  {candidate['release_date'] or ''}")
+        return
     
-    print("Testing metadata downloader...")
-    results = downloader.batch_download_metadata(test_games)
+    if args.command == 'apply':
+        result = downloader.apply_metadata_candidate(args.game, args.source, args.id)
+        print(f"Applied {args.source}:{args.id} to {args.game}" if result else "Failed to apply candidate")
+        return
     
-    for result in results:
-        if result:
-            print(f"\nGame: {result['name']}")
-            print(f"Rating: {result['rating']}")
-            print(f"Genres: {', '.join(result['genres'])}")
-            print(f"Cover: {result['cover_path']}")
+    if args.command == 'refresh':
+        if args.game:
+            game_names = [args.game]
+        elif args.max_age_days is not None:
+            game_names = downloader.get_stale_games(args.max_age_days)
+        else:
+            game_names = downloader.get_stored_game_names()
+        
+        if args.json:
+            with contextlib.redirect_stdout(sys.stderr):
+                refreshed, failed = downloader.refresh_games(game_names)
+            print(json.dumps({'refreshed': refreshed, 'failed': failed}))
+        else:
+            refreshed, failed = downloader.refresh_games(game_names)
+            print(f"Refreshed {len(refreshed)}/{len(game_names)} games")
+            for game_name in failed:
+                print(f"  Failed: {game_name}")
+        return
+    
+    if args.command == 'covers':
+        try:
+            game_names = downloader.get_cover_refresh_candidates(args.filters)
+        except ValueError as e:
+            parser.error(str(e))
+        
+        if args.dry_run:
+            if args.json:
+                print(json.dumps({'selected': game_names}))
+            else:
+                for game_name in game_names:
+                    print(game_name)
+                print(f"{len(game_names)} games selected")
+            return
+        
+        if args.json:
+            with contextlib.redirect_stdout(sys.stderr):
+                results = downloader.refresh_covers(game_names, args.workers)
+            results['quota'] = downloader.limiter.get_usage_summary()
+            print(json.dumps(results))
+        else:
+            results = downloader.refresh_covers(game_names, args.workers)
+            print(f"Refreshed {len(results['refreshed'])}/{len(game_names)} covers, "
+                  f"{len(results['unchanged'])} unchanged, {len(results['failed'])} failed")
+            if results['deferred']:
+                print(f"{len(results['deferred'])} games deferred until the provider quota resets")
+        return
+    
+    if args.command == 'retry':
+        if args.list:
+            entries = downloader.retries.get_entries()
+            if args.json:
+                print(json.dumps(entries))
+                return
+            for entry in entries:
+                state = "given up" if entry['given_up'] else f"next {entry['next_attempt'][:16].replace('T', ' ')}"
+                tried = f", tried {', '.join(entry['last_strategies'])}" if entry['last_strategies'] else ""
+                print(f"{entry['game_name']}  ({entry['attempts']} retries, {state}{tried})")
+            print(f"{len(entries)} games queued")
+            return
+        
+        if args.json:
+            with contextlib.redirect_stdout(sys.stderr):
+                results = downloader.retry_failed_scrapes(args.all)
+            print(json.dumps(results))
+        else:
+            results = downloader.retry_failed_scrapes(args.all)
+            print(f"Matched {len(results['matched'])} games, {len(results['failed'])} still unmatched")
+            if results['deferred']:
+                print(f"{len(results['deferred'])} games deferred until the provider quota resets")
+        return
+    
+    if args.command == 'override':
+        for field in OVERRIDE_FIELDS:
+            value = getattr(args, field)
+            if value is not None:
+                downloader.set_override(args.game, field, value)
+        for field in args.clear:
+            downloader.set_override(args.game, field, None)
+        
+        overrides = downloader.get_overrides(args.game)
+        if not overrides:
+            print(f"No pinned fields for {args.game}")
+        for field, value in sorted(overrides.items()):
+            print(f"{field}: {', '.join(value) if isinstance(value, list) else value}")
+        return
+    
+    if args.command == 'favorite':
+        if args.game:
+            downloader.set_favorite(args.game, not args.remove)
+        favorites = downloader.usage.get_favorites()
+        if args.json:
+            print(json.dumps(favorites))
+            return
+        for game_name in favorites:
+            print(game_name)
+        print(f"{len(favorites)} favorites")
+        return
+    
+    if args.command == 'compact':
+        compressed, size_before, size_after = downloader.compact_database()
+        print(f"Compressed {compressed} summaries; games.db {size_before / 1024:.0f} KB -> {size_after / 1024:.0f} KB")
+        return
+    
+    if args.command == 'list':
+        games = downloader.find_games(args.genre, args.platform)
+        if args.json:
+            print(json.dumps(games))
+            return
+        for game in games:
+            print(f"{game['name']}  {', '.join(game['genres'])}".rstrip())
+        print(f"{len(games)} games")
+        return
+    
+    if args.command == 'pick':
+        downloader.pick_metadata_candidate(args.game, args.limit)
+        return
+    
+    if args.command == 'scrape':
+        if args.json:
+            with contextlib.redirect_stdout(sys.stderr):
+                results = downloader.batch_download_metadata(game_names, args.workers)
+            results['quota'] = downloader.limiter.get_usage_summary()
+            print(json.dumps(results))
         else:
-            print("Failed to get metadata")
+            results = downloader.batch_download_metadata(game_names, args.workers)
+            print(f"Matched {len(results['matched'])}/{len(game_names)} games, "
+                  f"{len(results['unmatched'])} unmatched, {len(results['failed'])} failed")
+            for game_name in results['unmatched']:
+                print(f"  Unmatched: {game_name}")
+            for game_name in results['failed']:
+                print(f"  Failed: {game_name}")
+            if results['deferred']:
+                print(f"{len(results['deferred'])} games deferred until the provider quota resets")
+        return
 
 
 if __name__ == "__main__":
+    install_error_reporting()
     main()
@@ -0,0 +1,137 @@
+#!/usr/bin/env python3
+"""
+Scan Batch
+The games a metadata scan set out to fetch, kept in games.db (the scan_batch
+table) so a scan the daily quotas stopped resumes with the game it stopped
+at, not the start of a fresh list. Each scan also counts the requests each
+provider took per game (scan_requests), which together with the requests
+left today forecasts how many days the rest of the batch needs:
+
+    games left x requests per game / daily quota   for each provider with a quota
+"""
+
+import math
+import sqlite3
+from datetime import datetime
+from pathlib import Path
+from typing import Dict, List, Optional
+
+
+class ScanBatch:
+    """The pending games of the current metadata scan, in the order they are fetched."""
+
+    def __init__(self, db_path):
+        self.db_path = Path(db_path)
+        self.init_database()
+
+    def init_database(self):
+        conn = sqlite3.connect(self.db_path)
+        conn.execute('''
+            CREATE TABLE IF NOT EXISTS scan_batch (
+                position INTEGER PRIMARY KEY,
+                game_name TEXT UNIQUE NOT NULL,
+                added TIMESTAMP NOT NULL,
+                done TIMESTAMP
+            )
+        ''')
+        conn.execute('''
+            CREATE TABLE IF NOT EXISTS scan_requests (
+                provider TEXT PRIMARY KEY,
+                requests INTEGER NOT NULL DEFAULT 0,
+                games INTEGER NOT NULL DEFAULT 0
+            )
+        ''')
+        conn.commit()
+        conn.close()
+
+    def extend(self, game_names: List[str]) -> int:
+        """Add games to the end of the batch; games already in it keep their place. Returns how many were new."""
+        now = datetime.now().isoformat()
+        conn = sqlite3.connect(self.db_path)
+        added = 0
+        for game_name in game_names:
+            added += conn.execute('INSERT OR IGNORE INTO scan_batch (game_name, added) VALUES (?, ?)',
+                                  (game_name, now)).rowcount
+        conn.commit()
+        conn.close()
+        return added
+
+    def pending(self) -> List[str]:
+        """Get the games not fetched yet, where the last scan stopped first."""
+        conn = sqlite3.connect(self.db_path)
+        rows = conn.execute('SELECT game_name FROM scan_batch WHERE done IS NULL ORDER BY position').fetchall()
+        conn.close()
+        return [row[0] for row in rows]
+
+    def mark_done(self, game_name: str):
+        conn = sqlite3.connect(self.db_path)
+        conn.execute('UPDATE scan_batch SET done = ? WHERE game_name = ?', (datetime.now().isoformat(), game_name))
+        conn.commit()
+        conn.close()
+
+    def progress(self) -> Dict:
+        """Get {done, total, started} of the current batch; started is None when there is none."""
+        conn = sqlite3.connect(self.db_path)
+        done, total, started = conn.execute('SELECT COUNT(done), COUNT(*), MIN(added) FROM scan_batch').fetchone()
+        conn.close()
+        return {"done": done, "total": total, "started": started}
+
+    def clear_if_done(self) -> bool:
+        """Forget a batch whose games have all been fetched, so the next scan starts a new one."""
+        conn = sqlite3.connect(self.db_path)
+        finished = conn.execute('SELECT COUNT(*) FROM scan_batch WHERE done IS NULL').fetchone()[0] == 0
+        if finished:
+            conn.execute('DELETE FROM scan_batch')
+            conn.commit()
+        conn.close()
+        return finished
+
+    def record_requests(self, requests: Dict[str, int], games: int):
+        """Add the requests each provider took for a number of fetched games to the running counts."""
+        if not games:
+            return
+        conn = sqlite3.connect(self.db_path)
+        for provider, count in requests.items():
+            conn.execute('''
+                INSERT INTO scan_requests (provider, requests, games) VALUES (?, ?, ?)
+                ON CONFLICT(provider) DO UPDATE SET requests = requests + excluded.requests,
+                                                    games = games + excluded.games
+            ''', (provider, count, games))
+        conn.commit()
+        conn.close()
+
+    def requests_per_game(self) -> Dict[str, float]:
+        """Get the average requests a game has taken from each provider over past scans."""
+        conn = sqlite3.connect(self.db_path)
+        rows = conn.execute('SELECT provider, requests, games FROM scan_requests WHERE games > 0').fetchall()
+        conn.close()
+        return {provider: requests / games for provider, requests, games in rows}
+
+    def forecast(self, usage: Dict[str, Dict], games_left: Optional[int] = None) -> Dict:
+        """Forecast the rest of the batch from the providers' usage today (RateLimiter.get_usage_summary).
+
+        Returns {games_left, today, days, limited_by}: how many games today's
+        remaining quotas cover, how many days the rest takes counting today,
+        and the provider whose quota decides it. today and days are None
+        until a scan has measured the requests a game takes.
+        """
+        if games_left is None:
+            games_left = len(self.pending())
+        per_game = self.requests_per_game()
+        result = {"games_left": games_left, "today": None, "days": None, "limited_by": None}
+        if not per_game or not games_left:
+            return result
+
+        result["today"], result["days"] = games_left, 1
+        for provider, rate in per_game.items():
+            quota = (usage.get(provider) or {}).get("daily_quota")
+            if quota is None or rate <= 0:
+                continue
+            left_today = max(quota - usage[provider]["requests"], 0)
+            today = min(int(left_today / rate), games_left)
+            # The first day is what is left of today's quota, every later day a full one
+            after_today = games_left - today
+            days = 1 + (math.ceil(after_today * rate / quota) if after_today else 0)
+            if days > result["days"] or (days == result["days"] and today < result["today"]):
+                result.update(today=today, days=days, limited_by=provider)
+        return result
@@ -0,0 +1,184 @@
+#!/usr/bin/env python3
+"""
+Scrape Retries
+Games that no metadata provider matched wait in a retry queue (the
+scrape_retries table of games.db) instead of keeping their placeholder
+metadata for good. Each retry searches under the name again (providers add
+games over time), then under the alternate queries a name that failed often
+hides:
+
+    subtitle    "Castlevania: Symphony of the Night" -> "Castlevania"
+    file_title  the title of the file name's tags, "Legend of Zelda, The (USA) (Rev 1)" -> "The Legend of Zelda"
+    platform    the same name on Screenscraper, under the game's console instead of PC
+
+A game still without a match waits twice as long before its next retry, and
+is given up on (its placeholder stays) after metadata.retry_max_attempts.
+"""
+
+import re
+import sqlite3
+import sys
+from datetime import datetime, timedelta
+from pathlib import Path
+from typing import Dict, List, Optional, Tuple
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from names import parse_name
+
+# The Screenscraper system searched when a game's platform is not known
+DEFAULT_SYSTEM = '1'
+
+# Screenscraper system ids, by platform folder name and by the short name game names carry, e.g. "(PS2)"
+SCREENSCRAPER_SYSTEMS = {
+    "Sega - Mega Drive - Genesis": '1', "Genesis": '1',
+    "Sega - Master System - Mark III": '2',
+    "Nintendo - Nintendo Entertainment System": '3', "NES": '3',
+    "Nintendo - Super Nintendo Entertainment System": '4', "SNES": '4',
+    "Nintendo - Game Boy": '9',
+    "Nintendo - Game Boy Color": '10',
+    "Nintendo - Game Boy Advance": '12', "GBA": '12',
+    "Nintendo - GameCube": '13', "GameCube": '13',
+    "Nintendo - Nintendo 64": '14', "N64": '14',
+    "Nintendo - Nintendo DS": '15', "NDS": '15',
+    "Nintendo - Wii": '16', "Wii": '16',
+    "Sega - Dreamcast": '23', "Dreamcast": '23',
+    "Sony - PlayStation": '57', "PSX": '57',
+    "Sony - PlayStation 2": '58', "PS2": '58',
+    "Sony - PlayStation Portable": '61', "PSP": '61',
+}
+
+SUBTITLE = re.compile(r'\s*(?::|\s-\s)\s*')
+TRAILING_ARTICLE = re.compile(r'^(.+), (The|A|An)\b(.*)$')
+
+
+def screenscraper_system(platform: Optional[str]) -> Optional[str]:
+    """Get the Screenscraper system id of a platform folder or short name, ignoring case."""
+    if not platform:
+        return None
+    for name, system in SCREENSCRAPER_SYSTEMS.items():
+        if name.lower() == platform.lower():
+            return system
+    return None
+
+
+def platform_hint(game_name: str) -> Optional[str]:
+    """Get the short platform name a game name ends with, e.g. "PS2" for "Okami (PS2)"."""
+    match = re.search(r'\(([^)]+)\)\s*$', game_name)
+    return match.group(1) if match and screenscraper_system(match.group(1)) else None
+
+
+def move_article(title: str) -> str:
+    """Put a trailing article back in front: "Legend of Zelda, The - X" -> "The Legend of Zelda - X"."""
+    match = TRAILING_ARTICLE.match(title)
+    return f"{match.group(2)} {match.group(1)}{match.group(3)}" if match else title
+
+
+def alternate_queries(game_name: str, file_names: List[str] = (),
+                      platform: Optional[str] = None) -> List[Tuple[str, str, Optional[str]]]:
+    """Get the (strategy, query, Screenscraper system) searches to retry a game with, the name itself first.
+
+    file_names are the library files the game was made from; their tags give
+    its title as the sets spell it. The system is None for searches made on
+    every provider, and set for the platform strategy, which asks Screenscraper only.
+    """
+    queries = [('name', game_name, None)]
+    seen = {game_name.lower()}
+
+    def add(strategy, query, system=None):
+        query = query.strip()
+        if query and (query.lower() not in seen or system):
+            seen.add(query.lower())
+            queries.append((strategy, query, system))
+
+    parts = SUBTITLE.split(game_name, maxsplit=1)
+    if len(parts) == 2:
+        add('subtitle', parts[0])
+
+    for name in [game_name, *file_names]:
+        add('file_title', move_article(parse_name(name).title))
+
+    system = screenscraper_system(platform)
+    if system and system != DEFAULT_SYSTEM:
+        add('platform', parse_name(game_name).title, system)
+    return queries
+
+
+class ScrapeRetryQueue:
+    """The games waiting for another scrape, with when each is next due.
+
+    A retry that fails doubles the wait, starting from retry_hours; after
+    max_attempts retries a game stays in the queue as given up, so the user
+    can see which games need a manual match.
+    """
+
+    def __init__(self, db_path, retry_hours: float = 24, max_attempts: int = 5):
+        self.db_path = Path(db_path)
+        self.retry_hours = retry_hours
+        self.max_attempts = max_attempts
+        self.init_database()
+
+    def init_database(self):
+        conn = sqlite3.connect(self.db_path)
+        conn.execute('''
+            CREATE TABLE IF NOT EXISTS scrape_retries (
+                game_name TEXT PRIMARY KEY,
+                platform TEXT,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                added TIMESTAMP NOT NULL,
+                next_attempt TIMESTAMP NOT NULL,
+                last_strategies TEXT
+            )
+        ''')
+        conn.commit()
+        conn.close()
+
+    def add(self, game_name: str, platform: Optional[str] = None):
+        """Queue a game that no provider matched; a game already queued keeps its schedule."""
+        now = datetime.now()
+        conn = sqlite3.connect(self.db_path)
+        conn.execute('''
+            INSERT INTO scrape_retries (game_name, platform, added, next_attempt) VALUES (?, ?, ?, ?)
+            ON CONFLICT(game_name) DO UPDATE SET platform = COALESCE(excluded.platform, platform)
+        ''', (game_name, platform, now.isoformat(), (now + timedelta(hours=self.retry_hours)).isoformat()))
+        conn.commit()
+        conn.close()
+
+    def remove(self, game_name: str) -> bool:
+        conn = sqlite3.connect(self.db_path)
+        removed = conn.execute('DELETE FROM scrape_retries WHERE game_name = ?', (game_name,)).rowcount
+        conn.commit()
+        conn.close()
+        return bool(removed)
+
+    def record_failure(self, game_name: str, strategies: List[str]):
+        """Count a retry that found nothing and schedule the next one."""
+        conn = sqlite3.connect(self.db_path)
+        row = conn.execute('SELECT attempts FROM scrape_retries WHERE game_name = ?', (game_name,)).fetchone()
+        if row:
+            attempts = row[0] + 1
+            wait = timedelta(hours=self.retry_hours * 2 ** attempts)
+            conn.execute('UPDATE scrape_retries SET attempts = ?, next_attempt = ?, last_strategies = ? '
+                         'WHERE game_name = ?',
+                         (attempts, (datetime.now() + wait).isoformat(), ','.join(strategies), game_name))
+            conn.commit()
+        conn.close()
+
+    def get_entries(self) -> List[Dict]:
+        """Get every queued game, soonest due first, with whether it was given up on."""
+        conn = sqlite3.connect(self.db_path)
+        conn.row_factory = sqlite3.Row
+        rows = conn.execute('SELECT * FROM scrape_retries ORDER BY next_attempt, game_name').fetchall()
+        conn.close()
+        entries = []
+        for row in rows:
+            entry = dict(row)
+            entry['last_strategies'] = entry['last_strategies'].split(',') if entry['last_strategies'] else []
+            entry['given_up'] = entry['attempts'] >= self.max_attempts
+            entries.append(entry)
+        return entries
+
+    def get_due(self, include_waiting: bool = False) -> List[Dict]:
+        """Get the games due for a retry; with include_waiting, every game not given up on."""
+        now = datetime.now().isoformat()
+        return [entry for entry in self.get_entries()
+                if not entry['given_up'] and (include_waiting or entry['next_attempt'] <= now)]
@@ -5,16 +5,53 @@ Downloads metadata in batches to respect API limits and maximize coverage.
 """
 
 import os
+import argparse
 import sqlite3
 from pathlib import Path
-from metadata_downloader import GameMetadataDownloader
-import time
+from metadata_downloader import DEFERRED, FAILED, GameMetadataDownloader, run_until_quota
+from cache_manager import CacheManager
+from dedupe import SCAN_MODES, dedupe_folders
+from datasets import title_from_filename
+from job_limits import JobLimits
+from job_progress import ProgressTracker, format_amount
+from rom_config import add_config_argument
+from romignore import RomIgnore
+from scan_batch import ScanBatch
+from script_errors import install_error_reporting
 from datetime import datetime, timedelta
 
+# Files in ROM folders that are not games
+NON_ROM_EXTENSIONS = ('.txt', '.nfo', '.xml', '.dat', '.json', '.db', '.jpg', '.png',
+                      '.sav', '.srm', '.state', '.part')
+
+# Launcher shortcuts in library.game_directories: Windows links, internet shortcuts (Steam, GOG) and Linux desktop entries
+SHORTCUT_EXTENSIONS = ('.lnk', '.url', '.desktop')
+
+# Launch variants and platform tags the shortcut creators add to game names
+SHORTCUT_SUFFIXES = [
+    ' (ModEngine)', ' (Protected)', ' (MCC Launcher)', ' (Startup)', ' (Pre-Launcher)',
+    ' (Mod - Armoredcore6)', ' (Mod - Darksouls3)', ' (Mod - Eldenring)',
+    ' (PS2)', ' (PSX)', ' (N64)', ' (GameCube)', ' (Wii)', ' (Dreamcast)',
+    ' (Genesis)', ' (SNES)', ' (NES)', ' (GBA)', ' (NDS)', ' (PSP)',
+    ' (MAME)', ' (C64)', ' (Amiga)', ' (Atari2600)'
+]
+
+
+def desktop_entry_name(path):
+    """Get the Name= of a Linux .desktop launcher, or None."""
+    try:
+        for line in path.read_text(encoding='utf-8', errors='replace').splitlines():
+            if line.startswith('Name='):
+                return line[len('Name='):].strip() or None
+    except OSError:
+        pass
+    return None
+
 class SmartMetadataDownloader:
     def __init__(self):
         self.downloader = GameMetadataDownloader()
-        self.db_path = Path("games.db")
+        self.db_path = self.downloader.db_path
+        self.batch = ScanBatch(self.db_path)
         
     def get_games_without_metadata(self):
         """Get games that don't have real metadata yet."""
@@ -33,73 +70,219 @@ class SmartMetadataDownloader:
         return games
     
     def get_all_game_names(self):
-        """Get all game names from shortcuts directory."""
-        game_names = []
-        game_dir = Path("E:/Desktop/Games")
+        """Get every game the scan knows of, each once (ignoring case), how many each source found and when each was added.
         
-        if game_dir.exists():
-            for shortcut_file in game_dir.glob("*.lnk"):
-                game_name = shortcut_file.stem
+        Games come from the shortcut folders (library.game_directories), the
+        downloads in the library and the files in library.rom_directories, in
+        that order; a name found twice keeps its first spelling and its
+        latest added time.
+        """
+        sources = {
+            'shortcuts': self.get_shortcut_game_names(),
+            'library': self.get_library_game_names(),
+            'rom files': self.get_rom_game_names(),
+        }
+        game_names, spellings, added = [], {}, {}
+        for names in sources.values():
+            for name, added_at in names.items():
+                if name.lower() not in spellings:
+                    spellings[name.lower()] = name
+                    game_names.append(name)
+                name = spellings[name.lower()]
+                added[name] = max(added.get(name, 0), added_at)
+        return game_names, {source: len(names) for source, names in sources.items()}, added
+    
+    def get_shortcut_game_names(self):
+        """Get game names from the shortcuts (.lnk, .url, .desktop) in library.game_directories, with when each was made."""
+        names = {}
+        for directory in self.downloader.rom_config.get('library.game_directories', []) or []:
+            game_dir = Path(directory).expanduser()
+            if not game_dir.is_dir():
+                continue
+            for shortcut_file in sorted(game_dir.iterdir()):
+                if shortcut_file.suffix.lower() not in SHORTCUT_EXTENSIONS:
+                    continue
+                game_name = desktop_entry_name(shortcut_file) if shortcut_file.suffix.lower() == '.desktop' else None
                 # Clean up game name
-                suffixes_to_remove = [
-                    ' (ModEngine)', ' (Protected)', ' (MCC Launcher)', ' (Startup)', ' (Pre-Launcher)',
-                    ' (Mod - Armoredcore6)', ' (Mod - Darksouls3)', ' (Mod - Eldenring)',
-                    ' (PS2)', ' (PSX)', ' (N64)', ' (GameCube)', ' (Wii)', ' (Dreamcast)',
-                    ' (Genesis)', ' (SNES)', ' (NES)', ' (GBA)', ' (NDS)', ' (PSP)',
-                    ' (MAME)', ' (C64)', ' (Amiga)', ' (Atari2600)'
-                ]
-                
-                cleaned_name = game_name
-                for suffix in suffixes_to_remove:
+                cleaned_name = game_name or shortcut_file.stem
+                for suffix in SHORTCUT_SUFFIXES:
                     cleaned_name = cleaned_name.replace(suffix, '')
-                    
-                game_names.append(cleaned_name.strip())
-        
-        return game_names
+                if cleaned_name.strip() and cleaned_name.strip() not in names:
+                    names[cleaned_name.strip()] = shortcut_file.stat().st_mtime
+        return names
+    
+    def get_library_game_names(self):
+        """Get the titles of the games downloaded into the library, from their file names, with when each was downloaded."""
+        names = {}
+        for entry in self.downloader.library.get_entries():
+            title = title_from_filename(entry['filename'])
+            if title:
+                try:
+                    downloaded = datetime.fromisoformat(str(entry['downloaded_at'])).timestamp()
+                except (TypeError, ValueError):
+                    downloaded = 0
+                names[title] = max(names.get(title, 0), downloaded)
+        return names
     
-    def download_batch(self, game_names, batch_size=40):
-        """Download metadata for a batch of games."""
-        print(f"🎯 Processing batch of {len(game_names)} games...")
+    def get_rom_game_names(self):
+        """Get game titles from the files in library.rom_directories, without tags or extension, with when each file was added."""
+        names = {}
+        ignore = RomIgnore()
+        for directory in self.downloader.rom_config.get('library.rom_directories', []) or []:
+            root = Path(directory).expanduser()
+            if not root.is_dir():
+                print(f"⚠️ ROM directory not found: {root}")
+                continue
+            for path in ignore.walk(root):
+                if path.suffix.lower() in NON_ROM_EXTENSIONS or path.name.startswith('.'):
+                    continue
+                title = title_from_filename(path.name)
+                if title:
+                    names[title] = max(names.get(title, 0), path.stat().st_mtime)
+        return names
+    
+    def download_batch(self, game_names, workers=None):
+        """Download metadata for a batch of games, several at once, marking each done in the scan batch once fetched.
+        
+        Once a provider's daily quota runs out no more games are started; the
+        ones not reached stay pending in the scan batch for the next scan.
+        """
+        workers = workers or self.downloader.workers
+        print(f"🎯 Processing batch of {len(game_names)} games, {workers} at a time...")
         
         results = []
-        for i, game_name in enumerate(game_names):
-            print(f"Processing {i+1}/{len(game_names)}: {game_name}")
-            
+        deferred = []
+        # The requests the fetched games took, for the forecast of the rest
+        requests = {}
+        used_by_game = {}
+        limiter = self.downloader.limiter
+        
+        def fetch(game_name):
+            limiter.pop_requests()
             try:
-                metadata = self.downloader.get_game_metadata(game_name)
-                results.append(metadata)
-                
-                # Rate limiting
-                time.sleep(0.5)
-                
-            except Exception as e:
-                print(f"Error processing {game_name}: {e}")
-                results.append(None)
+                return self.downloader.get_game_metadata(game_name)
+            finally:
+                used_by_game[game_name] = limiter.pop_requests()
+        
+        progress = ProgressTracker("scan", total=len(game_names), label="metadata")
+        with self.downloader.journal.job("scan", "metadata", done=0, total=len(game_names)) as job:
+            self.downloader.job = job
+            for game_name, metadata in run_until_quota(game_names, fetch, workers):
+                if metadata == DEFERRED:
+                    deferred.append(game_name)
+                    continue
+                results.append(None if metadata == FAILED else metadata)
+                self.batch.mark_done(game_name)
+                for provider, count in used_by_game.pop(game_name, {}).items():
+                    requests[provider] = requests.get(provider, 0) + count
+                progress.advance(message=f"Fetched: {game_name}")
+                job.update(done=len(results), game=game_name)
+            self.downloader.job = None
+        progress.finish()
+        self.batch.record_requests(requests, len(results))
+        
+        if deferred:
+            first = min(deferred, key=game_names.index)
+            print(f"   Stopping after {len(results)}/{len(game_names)} games; the next scan resumes with {first}")
                 
         successful = len([r for r in results if r and (r.get('rating') is not None or (r.get('summary') and not r['summary'].startswith('Game: ')))])
         print(f"✅ Successfully downloaded metadata for {successful}/{len(game_names)} games")
         
         return results
     
-    def smart_download(self):
+    def find_duplicates(self, mode=None):
+        """Report the identical ROMs in the download folder and library.rom_directories, linking them per library.dedupe."""
+        config = self.downloader.rom_config
+        mode = mode or config.get('library.dedupe', 'off')
+        if mode not in SCAN_MODES:
+            print(f"⚠️ Unknown library.dedupe setting {mode!r}, expected one of {', '.join(SCAN_MODES)}")
+            return
+        if mode == 'off':
+            return
+        if mode != 'report' and config.is_read_only():
+            print("🔒 Read-only mode: reporting duplicates without linking them")
+            mode = 'report'
+        
+        folders = [config.get_path('paths.download_directory')]
+        folders += [Path(folder).expanduser() for folder in config.get('library.rom_directories', []) or []]
+        progress = ProgressTracker("dedupe", total=None, unit="files", label="duplicates")
+        result = dedupe_folders([folder for folder in folders if folder.is_dir()],
+                                None if mode == 'report' else mode, limits=JobLimits(config),
+                                advance=lambda name: progress.advance(message=name))
+        progress.finish()
+        
+        if not result['copies']:
+            print("✅ No duplicate ROMs")
+        elif mode == 'report':
+            print(f"🪞 {result['copies']} duplicate ROMs in {len(result['groups'])} groups; "
+                  f"linking them would free {format_amount(result['reclaimable'], 'bytes')}")
+            print("   Link them with library_manager.py dedupe --link hardlink, or set library.dedupe")
+        else:
+            linked = [link for link in result['links'] if link['status'] == 'linked']
+            print(f"🔗 Linked {len(linked)} of {result['copies']} duplicate ROMs ({mode}), "
+                  f"freeing {format_amount(result['reclaimed'], 'bytes')}")
+            for link in result['links']:
+                if link['status'] == 'skipped':
+                    print(f"   Skipped {link['path']}: {link['error']}")
+    
+    def smart_download(self, max_age_days=None, dedupe=None, workers=None):
         """Smart download that respects API limits."""
         print("🚀 Starting smart metadata download...")
         
+        if self.downloader.rom_config.is_read_only():
+            print("🔒 Read-only mode: skipping downloads, showing statistics only")
+            self.find_duplicates(dedupe)
+            self.show_statistics()
+            return
+        
         # Get all games
-        all_games = self.get_all_game_names()
-        print(f"📊 Found {len(all_games)} total games")
+        all_games, found, added_at = self.get_all_game_names()
+        print(f"📊 Found {len(all_games)} total games "
+              f"({', '.join(f'{count} from {source}' for source, count in found.items())})")
         
-        # Get games without metadata
-        games_without_metadata = self.get_games_without_metadata()
+        # Get games without metadata, including ones found since the last scan
+        stored_games = set(self.downloader.get_stored_game_names())
+        games_without_metadata = [g for g in all_games if g not in stored_games] + self.get_games_without_metadata()
         print(f"📋 Found {len(games_without_metadata)} games without metadata")
         
+        # A batch the quotas stopped goes on where it stopped; games it already fetched are not tried again
+        batch = self.batch.progress()
+        if batch['total'] > batch['done']:
+            print(f"⏯️ Resuming the scan started {batch['started'][:16].replace('T', ' ')}: "
+                  f"{batch['done']}/{batch['total']} games fetched")
+        added = self.batch.extend(games_without_metadata)
+        if added and batch['total']:
+            print(f"➕ Added {added} games found since to the end of the batch")
+        # Favorites, played and recently added games first, so the day's quota goes to the games the user sees
+        pending = self.downloader.usage.prioritize(self.batch.pending(), added_at)
+        
         # If we have games without metadata, process them
-        if games_without_metadata:
-            print(f"🎯 Processing {len(games_without_metadata)} games without metadata...")
-            self.download_batch(games_without_metadata)
+        if pending:
+            print(f"🎯 Processing {len(pending)} games without metadata...")
+            self.download_batch(pending, workers)
+            self.batch.clear_if_done()
         else:
+            self.batch.clear_if_done()
             print("✅ All games already have metadata!")
         
+        # Games no provider matched are searched again under alternate queries once due
+        retried = self.downloader.retry_failed_scrapes()
+        if retried['matched'] or retried['failed']:
+            print(f"🔁 Retried {len(retried['matched']) + len(retried['failed'])} unmatched games, "
+                  f"matched {len(retried['matched'])}")
+        
+        # Re-fetch entries older than the staleness policy allows
+        stale_games = [g for g in self.downloader.get_stale_games(max_age_days) if g not in games_without_metadata]
+        if stale_games:
+            print(f"♻️ Refreshing {len(stale_games)} games with stale metadata...")
+            self.downloader.refresh_games(stale_games)
+        
+        # Keep the covers cache within its size budget
+        CacheManager(self.downloader.covers_dir, self.db_path).maybe_collect_garbage()
+        
+        # Identical ROMs across the folders, per library.dedupe
+        self.find_duplicates(dedupe)
+        
         # Show final statistics
         self.show_statistics()
     
@@ -119,17 +302,39 @@ class SmartMetadataDownloader:
         print(f"\n📊 Final Statistics:")
         print(f"   Total games: {total_games}")
         print(f"   Games with metadata: {games_with_metadata}")
-        print(f"   Coverage: {(games_with_metadata/total_games)*100:.1f}%")
+        if total_games:
+            print(f"   Coverage: {(games_with_metadata/total_games)*100:.1f}%")
+        
+        usage_today = self.downloader.limiter.get_usage_summary()
+        print(f"\n📈 API usage today:")
+        for provider, usage in usage_today.items():
+            quota = usage['daily_quota']
+            print(f"   {provider}: {usage['requests']}/{quota if quota is not None else 'unlimited'}")
         
-        if games_with_metadata < total_games:
-            remaining = total_games - games_with_metadata
-            print(f"\n⏰ Remaining games: {remaining}")
-            print(f"   Next batch can be processed tomorrow (API limit resets daily)")
-            print(f"   Estimated days to complete: {(remaining/40)+1:.0f}")
+        forecast = self.batch.forecast(usage_today)
+        if forecast['games_left']:
+            print(f"\n⏰ Games left in this scan: {forecast['games_left']}")
+            if forecast['days'] is None:
+                print("   Run the scan again to continue; the forecast follows once a scan has fetched some games")
+            elif forecast['days'] == 1:
+                print("   Today's remaining quotas cover all of them; run the scan again to continue")
+            else:
+                print(f"   Today's remaining quotas cover {forecast['today']} more; the rest need "
+                      f"{forecast['days'] - 1} more day(s) of {forecast['limited_by']}'s quota, which resets daily")
 
 def main():
+    parser = argparse.ArgumentParser(description='Smart metadata downloader')
+    add_config_argument(parser)
+    parser.add_argument('--max-age-days', type=int, help='Refresh metadata older than this many days (0 disables)')
+    parser.add_argument('--dedupe', choices=SCAN_MODES,
+                        help='Report or link identical ROMs across the ROM folders (default: library.dedupe)')
+    parser.add_argument('--workers', type=int, help='Games fetched at once (default: metadata.workers)')
+    args = parser.parse_args()
+    
     downloader = SmartMetadataDownloader()
-    downloader.smart_download()
+    downloader.smart_download(max_age_days=args.max_age_days, dedupe=args.dedupe,
+                              workers=max(args.workers, 1) if args.workers else None)
 
 if __name__ == "__main__":
+    install_error_reporting()
     main()
@@ -0,0 +1,376 @@
+#!/usr/bin/env python3
+"""
+Mock Archive Server
+Serves a small fake Redump/No-Intro tree (from fixtures.json) over local HTTP,
+so browsing, matching, downloading and verification can be exercised offline.
+
+Every ROM is generated deterministically and served zipped, like the real
+archive; matching Logiqx DATs can be written for verification. Point the
+scripts at the server with ROM_BROWSER_ARCHIVE_URL, or use "run" to do that
+for a single command.
+"""
+
+import argparse
+import hashlib
+import io
+import json
+import os
+import shutil
+import subprocess
+import sys
+import tempfile
+import threading
+import urllib.parse
+import zipfile
+import zlib
+from html import escape
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+from pathlib import Path
+from typing import Dict, Optional
+from xml.sax.saxutils import quoteattr
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from rom_config import ARCHIVE_URL_ENV, READ_ONLY_ENV, RomConfig
+
+FIXTURES_FILE = Path(__file__).resolve().parent / "fixtures.json"
+DEFAULT_ROM_SIZE = 64 * 1024
+URL_PREFIX = "/files/"
+
+
+class Colors:
+    """ANSI color codes for terminal output."""
+    RED = '\033[0;31m'
+    GREEN = '\033[0;32m'
+    YELLOW = '\033[1;33m'
+    CYAN = '\033[0;36m'
+    NC = '\033[0m'  # No Color
+
+
+class MockArchive:
+    def __init__(self, fixtures_file=FIXTURES_FILE, rom_size=DEFAULT_ROM_SIZE):
+        with open(fixtures_file, 'r', encoding='utf-8') as f:
+            self.fixtures = json.load(f)
+        self.rom_size = rom_size
+        self.cache = {}
+
+    def platforms(self):
+        for dataset, platforms in self.fixtures.items():
+            for platform, info in platforms.items():
+                yield dataset, platform, info
+
+    def rom_data(self, dataset: str, platform: str, title: str) -> bytes:
+        """Generate the contents of a ROM; the same title always gives the same bytes."""
+        seed = hashlib.sha256(f"{dataset}/{platform}/{title}".encode('utf-8')).digest()
+        blocks = []
+        size = 0
+        while size < self.rom_size:
+            seed = hashlib.sha256(seed).digest()
+            blocks.append(seed)
+            size += len(seed)
+        return b''.join(blocks)[:self.rom_size]
+
+    def zip_data(self, dataset: str, platform: str, title: str) -> bytes:
+        """Build the archive file for a title, with a fixed timestamp so it is reproducible."""
+        key = (dataset, platform, title)
+        if key not in self.cache:
+            info = self.fixtures[dataset][platform]
+            member = zipfile.ZipInfo(title + info.get('rom_extension', '.bin'), date_time=(2000, 1, 1, 0, 0, 0))
+            buffer = io.BytesIO()
+            with zipfile.ZipFile(buffer, 'w', zipfile.ZIP_DEFLATED) as archive:
+                archive.writestr(member, self.rom_data(dataset, platform, title))
+            self.cache[key] = buffer.getvalue()
+        return self.cache[key]
+
+    def resolve(self, path: str):
+        """Map a URL path below /files/ to ('dir', entries) or ('file', bytes), or None."""
+        parts = [p for p in urllib.parse.unquote(path).split('/') if p]
+
+        if not parts:
+            return 'dir', [dataset + '/' for dataset in self.fixtures]
+        if parts[0] not in self.fixtures:
+            return None
+
+        dataset = parts[0]
+        if len(parts) == 1:
+            return 'dir', [platform + '/' for platform in self.fixtures[dataset]]
+
+        platform = parts[1]
+        if platform not in self.fixtures[dataset]:
+            return None
+        titles = self.fixtures[dataset][platform]['titles']
+        if len(parts) == 2:
+            return 'dir', [title + '.zip' for title in titles]
+
+        if len(parts) == 3 and parts[2].endswith('.zip') and parts[2][:-4] in titles:
+            return 'file', self.zip_data(dataset, platform, parts[2][:-4])
+        return None
+
+    def write_dats(self, directory) -> int:
+        """Write a Logiqx DAT per platform covering the generated ROMs. Returns the number written."""
+        directory = Path(directory)
+        directory.mkdir(parents=True, exist_ok=True)
+        written = 0
+
+        for dataset, platform, info in self.platforms():
+            lines = [
+                '<?xml version="1.0"?>',
+                '<datafile>',
+                f'\t<header>\n\t\t<name>{escape(platform)} (Mock {escape(dataset)})</name>\n\t</header>'
+            ]
+            for title in info['titles']:
+                data = self.rom_data(dataset, platform, title)
+                rom_name = title + info.get('rom_extension', '.bin')
+                lines.append(
+                    f'\t<game name={quoteattr(title)}>\n'
+                    f'\t\t<description>{escape(title)}</description>\n'
+                    f'\t\t<rom name={quoteattr(rom_name)} size="{len(data)}" '
+                    f'crc="{zlib.crc32(data) & 0xffffffff:08x}" md5="{hashlib.md5(data).hexdigest()}" '
+                    f'sha1="{hashlib.sha1(data).hexdigest()}"/>\n'
+                    f'\t</game>'
+                )
+            lines.append('</datafile>')
+
+            with open(directory / f"{dataset} - {platform}.dat", 'w', encoding='utf-8') as f:
+                f.write('\n'.join(lines) + '\n')
+            written += 1
+
+        return written
+
+
+def make_handler(archive: MockArchive, flaky: Optional[set] = None):
+    """Build a request handler; paths in flaky drop their first download halfway through."""
+    flaky = set(flaky or ())
+    lock = threading.Lock()
+
+    class Handler(BaseHTTPRequestHandler):
+        def log_message(self, format, *args):
+            pass
+
+        def do_HEAD(self):
+            self.respond(send_body=False)
+
+        def do_GET(self):
+            self.respond(send_body=True)
+
+        def respond(self, send_body: bool):
+            if not self.path.startswith(URL_PREFIX):
+                self.send_error(404)
+                return
+
+            path = self.path[len(URL_PREFIX):].split('?')[0]
+            resolved = archive.resolve(path)
+            if resolved is None:
+                self.send_error(404)
+                return
+
+            kind, content = resolved
+            if kind == 'dir':
+                if not path.endswith('/') and path:
+                    self.send_response(301)
+                    self.send_header('Location', self.path + '/')
+                    self.end_headers()
+                    return
+                body = self.render_listing(path, content)
+                self.send_response(200)
+                self.send_header('Content-Type', 'text/html; charset=utf-8')
+                self.send_header('Content-Length', str(len(body)))
+                self.end_headers()
+                if send_body:
+                    self.wfile.write(body)
+                return
+
+            start, end = 0, len(content) - 1
+            range_header = self.headers.get('Range')
+            if range_header and range_header.startswith('bytes='):
+                first, _, last = range_header[6:].partition('-')
+                if first:
+                    start = int(first)
+                    end = min(int(last), end) if last else end
+                else:
+                    # A suffix range: the last bytes of the file
+                    start = max(len(content) - int(last), 0)
+            body = content[start:end + 1]
+
+            self.send_response(206 if range_header else 200)
+            self.send_header('Content-Type', 'application/zip')
+            self.send_header('Content-Length', str(len(body)))
+            self.send_header('Accept-Ranges', 'bytes')
+            self.send_header('ETag', '"' + hashlib.sha1(content).hexdigest() + '"')
+            if range_header:
+                self.send_header('Content-Range', f"bytes {start}-{end}/{len(content)}")
+            self.end_headers()
+
+            if not send_body:
+                return
+
+            with lock:
+                drop = urllib.parse.unquote(path) in flaky
+                flaky.discard(urllib.parse.unquote(path))
+            if drop:
+                # Simulate a connection that dies mid-transfer
+                self.wfile.write(body[:len(body) // 2])
+                self.wfile.flush()
+                self.close_connection = True
+                self.connection.shutdown(1)
+                return
+            self.wfile.write(body)
+
+        def render_listing(self, path: str, entries) -> bytes:
+            # Laid out like Myrient's listings, sizes in binary units and all dated like the ZIP members
+            rows = ['<tr><td class="link"><a href="../">Parent directory/</a></td><td class="size">-</td>'
+                    '<td class="date">-</td></tr>'] if path else []
+            for entry in entries:
+                size = '-' if entry.endswith('/') else f"{len(archive.resolve(path + entry)[1]) / 1024:.1f} KiB"
+                rows.append(f'<tr><td class="link"><a href="{urllib.parse.quote(entry)}" title="{escape(entry)}">'
+                            f'{escape(entry)}</a></td><td class="size">{size}</td>'
+                            f'<td class="date">01-Jan-2000 00:00</td></tr>')
+            html = (f'<html><head><title>Index of /files/{escape(path)}</title></head><body>'
+                    f'<h1>Index of /files/{escape(path)}</h1><table>{"".join(rows)}</table></body></html>')
+            return html.encode('utf-8')
+
+    return Handler
+
+
+def start_server(archive: MockArchive, port: int = 0, flaky: Optional[set] = None):
+    """Start the server in a background thread. Returns (server, archive URL)."""
+    server = ThreadingHTTPServer(('127.0.0.1', port), make_handler(archive, flaky))
+    threading.Thread(target=server.serve_forever, daemon=True).start()
+    return server, f"http://127.0.0.1:{server.server_port}{URL_PREFIX}"
+
+
+def selftest(keep=False) -> int:
+    """Browse, match, download (through a dropped connection) and verify against the mock archive."""
+    sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "rom-sourcing"))
+
+    archive = MockArchive()
+    flaky_file = "Redump/Sony - PlayStation/Resident Evil (USA).zip"
+    server, url = start_server(archive, flaky={flaky_file})
+    work_dir = Path(tempfile.mkdtemp(prefix="rom-browser-selftest-"))
+    original_cwd = os.getcwd()
+
+    # Keep everything the scripts write inside the work directory
+    archive.write_dats(work_dir / "dats")
+    config_data = RomConfig().config
+    config_data["paths"]["dat_directory"] = str(work_dir / "dats")
+    config_data["paths"]["library_db"] = str(work_dir / "library.db")
+    for key in ("data_directory", "cache_directory", "log_directory"):
+        config_data["paths"][key] = str(work_dir)
+    config_data["network"].update({"archive_url": url, "proxy_url": "", "retry_backoff_seconds": 0.05})
+    config_data["safety"]["read_only"] = False
+    config_data["plugins"] = {"metadata_providers": [], "post_download": []}
+    config_data["downloads"]["checkpoint_piece_mb"] = 1 / 64
+    with open(work_dir / "rom_browser.json", 'w', encoding='utf-8') as f:
+        json.dump(config_data, f, indent=2)
+    os.environ[ARCHIVE_URL_ENV] = url
+    os.environ.pop(READ_ONLY_ENV, None)
+
+    results = []
+
+    def check(name, passed, detail=""):
+        results.append(passed)
+        status = f"{Colors.GREEN}PASS{Colors.NC}" if passed else f"{Colors.RED}FAIL{Colors.NC}"
+        print(f"{status}  {name}{f'  ({detail})' if detail else ''}")
+
+    try:
+        os.chdir(work_dir)
+        from rom_downloader import ROMDownloader
+        from rom_identify import ROMIdentifier
+
+        config = RomConfig(work_dir / "rom_browser.json")
+        downloader = ROMDownloader(config)
+        downloader.log_message = lambda message: None
+
+        platform_url = downloader.base_url_redump + urllib.parse.quote("Sony - PlayStation") + "/"
+        files = downloader.list_platform_files(platform_url)
+        expected = sorted(t + '.zip' for t in archive.fixtures["Redump"]["Sony - PlayStation"]["titles"])
+        check("browse platform listing", files == expected, f"{len(files)} files")
+
+        entries = downloader.source.list_dir("Redump/Sony - PlayStation/")
+        sizes = {entry['name']: entry['size'] for entry in entries}
+        actual = len(archive.zip_data("Redump", "Sony - PlayStation", "Resident Evil (USA)"))
+        # The listing rounds to a tenth of a KiB
+        check("listing shows file sizes", abs((sizes.get("Resident Evil (USA).zip") or 0) - actual) <= 52,
+              f"{sizes.get('Resident Evil (USA).zip')} of {actual} bytes")
+
+        best = downloader.select_best_match("Resident Evil", downloader.search_game("Resident Evil", files))
+        check("best match prefers configured region", best == "Resident Evil (USA).zip", best)
+
+        snes_url = downloader.base_url_noin + urllib.parse.quote("Nintendo - Super Nintendo Entertainment System") + "/"
+        snes_files = downloader.list_platform_files(snes_url)
+        matches = downloader.search_game("Street Fighter", snes_files)
+        check("transliterated search finds kana titles", "ストリートファイターII (Japan).zip" in matches)
+
+        downloaded = downloader.download_file(platform_url + urllib.parse.quote(best), best) if best else False
+        file_path = work_dir / "downloads" / "Sony - PlayStation" / "Resident Evil (USA).zip"
+        check("download survives a dropped connection", downloaded and file_path.exists())
+        check("download recorded in library", downloader.library.get_entry(file_path) is not None)
+
+        identified = ROMIdentifier(config, online=False).identify(file_path) if file_path.exists() else []
+        verification = identified[0]['verification'] if identified else None
+        check("downloaded file verifies against DAT", verification == "verified", verification)
+    except Exception as e:
+        check("selftest completed", False, str(e))
+    finally:
+        os.chdir(original_cwd)
+        server.shutdown()
+        if keep:
+            print(f"Work directory kept at {work_dir}")
+        else:
+            shutil.rmtree(work_dir, ignore_errors=True)
+
+    passed = sum(results)
+    color = Colors.GREEN if passed == len(results) else Colors.RED
+    print(f"\n{color}{passed}/{len(results)} checks passed{Colors.NC}")
+    return 0 if passed == len(results) else 1
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Mock ROM archive server for offline testing')
+    subparsers = parser.add_subparsers(dest='command', required=True)
+
+    serve_parser = subparsers.add_parser('serve', help='Serve the mock archive until interrupted')
+    serve_parser.add_argument('--port', type=int, default=8765, help='Port to listen on')
+    serve_parser.add_argument('--dats', help='Also write matching DATs to this directory')
+
+    run_parser = subparsers.add_parser('run', help='Run a command with the scripts pointed at the mock archive')
+    run_parser.add_argument('--port', type=int, default=0, help='Port to listen on (default: any free port)')
+    run_parser.add_argument('cmd', nargs=argparse.REMAINDER, help='Command to run, after --')
+
+    dats_parser = subparsers.add_parser('write-dats', help='Write DATs matching the mock ROMs')
+    dats_parser.add_argument('directory', help='Output directory')
+
+    selftest_parser = subparsers.add_parser('selftest', help='Run the end-to-end checks against the mock archive')
+    selftest_parser.add_argument('--keep', action='store_true', help='Keep the work directory for inspection')
+
+    args = parser.parse_args()
+    archive = MockArchive()
+
+    if args.command == 'serve':
+        if args.dats:
+            archive.write_dats(args.dats)
+        server, url = start_server(archive, args.port)
+        print(f"{Colors.CYAN}Mock archive serving at {url}{Colors.NC}")
+        print(f"  export {ARCHIVE_URL_ENV}={url}")
+        try:
+            threading.Event().wait()
+        except KeyboardInterrupt:
+            server.shutdown()
+    elif args.command == 'run':
+        command = args.cmd[1:] if args.cmd[:1] == ['--'] else args.cmd
+        if not command:
+            parser.error("run needs a command after --")
+        server, url = start_server(archive, args.port)
+        env = dict(os.environ, **{ARCHIVE_URL_ENV: url})
+        try:
+            sys.exit(subprocess.call(command, env=env))
+        finally:
+            server.shutdown()
+    elif args.command == 'write-dats':
+        count = archive.write_dats(args.directory)
+        print(f"Wrote {count} DATs to {args.directory}")
+    elif args.command == 'selftest':
+        sys.exit(selftest(args.keep))
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,133 @@
+#!/usr/bin/env python3
+"""
+Internet Archive
+Command line access to the archive.org source (rom_sources.InternetArchiveSource),
+for collections that are not on Myrient. The items to offer are listed under
+sources.internet_archive.items in rom_browser.json; downloads go through the
+ROM downloader (path template, resume, library, plugins) and are checked
+against the SHA1 the archive publishes.
+"""
+
+import argparse
+import hashlib
+import json
+import sys
+from pathlib import Path
+from typing import Dict, List, Optional
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from names import add_name_filter_arguments, filter_entries, name_fields, name_filter_from_args
+from proxy import apply_proxy_settings
+from rom_config import RomConfig, add_config_argument, set_read_only
+from rom_sources import InternetArchiveSource
+from script_errors import fail, install_error_reporting
+
+
+class Colors:
+    """ANSI color codes for terminal output."""
+    RED = '\033[0;31m'
+    GREEN = '\033[0;32m'
+    YELLOW = '\033[1;33m'
+    CYAN = '\033[0;36m'
+    NC = '\033[0m'  # No Color
+
+
+def list_files(source: InternetArchiveSource, identifier: str, search: Optional[str] = None) -> List[Dict]:
+    """List the original files of an item, optionally filtered by a case-insensitive substring."""
+    files = source.item_files(identifier)
+    if search:
+        files = [f for f in files if search.lower() in f['name'].lower()]
+    # Files may sit in subfolders of the item; their tags are in the base name
+    return [dict(f, **name_fields(f['name'].rsplit('/', 1)[-1])) for f in files]
+
+
+def download(source: InternetArchiveSource, identifier: str, name: str, downloader=None) -> bool:
+    """Download one file of an item and check it against the published SHA1.
+
+    downloader is the ROMDownloader to download with, a new one by default.
+    """
+    from rom_downloader import ROMDownloader
+
+    matches = [f for f in source.item_files(identifier) if f['name'] == name]
+    if not matches:
+        print(f"{Colors.RED}No file {name} in {identifier}{Colors.NC}")
+        return False
+    info = matches[0]
+
+    downloader = downloader or ROMDownloader(source.config)
+    # Files may sit in subfolders of the item; only the base name is used locally
+    filename = name.rsplit('/', 1)[-1]
+    if not downloader.download_file(info['url'], filename):
+        return False
+
+    entry = next((e for e in downloader.library.get_entries() if e['source_url'] == info['url']), None)
+    if entry is None or not info['sha1']:
+        return True
+
+    sha1 = hashlib.sha1()
+    with open(entry['path'], 'rb') as f:
+        for block in iter(lambda: f.read(1024 * 1024), b''):
+            sha1.update(block)
+    if sha1.hexdigest() != info['sha1'].lower():
+        print(f"{Colors.RED}SHA1 mismatch for {filename}: the download is corrupt{Colors.NC}")
+        return False
+
+    # Remembered so a later change at the source is noticed by library_manager.py check-urls
+    downloader.library.set_source_info(entry['id'], sha1=info['sha1'])
+    print(f"{Colors.GREEN}Verified against archive.org SHA1: {filename}{Colors.NC}")
+    return True
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Browse and download Internet Archive items')
+    add_config_argument(parser)
+    parser.add_argument('--read-only', action='store_true', help='Browse without downloading')
+    subparsers = parser.add_subparsers(dest='command', required=True)
+
+    items_parser = subparsers.add_parser('items', help='List the configured items')
+    items_parser.add_argument('--json', action='store_true', help='Print the items as JSON')
+
+    files_parser = subparsers.add_parser('files', help='List the files of an item')
+    files_parser.add_argument('identifier', help='archive.org item identifier')
+    files_parser.add_argument('--search', help='Only list files whose name contains this text')
+    files_parser.add_argument('--json', action='store_true', help='Print the files as JSON')
+    add_name_filter_arguments(files_parser)
+
+    download_parser = subparsers.add_parser('download', help='Download a file of an item')
+    download_parser.add_argument('identifier', help='archive.org item identifier')
+    download_parser.add_argument('name', help='File name within the item')
+
+    args = parser.parse_args()
+
+    if args.read_only:
+        set_read_only()
+
+    config = RomConfig()
+    apply_proxy_settings(config)
+    source = InternetArchiveSource(config, log=lambda message: print(message, file=sys.stderr))
+
+    try:
+        if args.command == 'items':
+            items = source.get_items()
+            if args.json:
+                print(json.dumps(items))
+                return
+            for item in items:
+                print(f"{Colors.CYAN}{item['identifier']}{Colors.NC}  {item['name']}")
+        elif args.command == 'files':
+            files = filter_entries(list_files(source, args.identifier, args.search), name_filter_from_args(args))
+            if args.json:
+                print(json.dumps(files))
+                return
+            for info in files:
+                print(f"{info['size_text'] or '-':>10}  {info['name']}")
+            print(f"\n{Colors.CYAN}{len(files)} files in {args.identifier}{Colors.NC}")
+        elif args.command == 'download':
+            sys.exit(0 if download(source, args.identifier, args.name) else 1)
+    except Exception as e:
+        fail(f"{Colors.RED}Internet Archive error: {e}{Colors.NC}", e)
+
+
+if __name__ == "__main__":
+    install_error_reporting()
+    main()
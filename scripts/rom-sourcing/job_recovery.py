@@ -0,0 +1,194 @@
+#!/usr/bin/env python3
+"""
+Job Recovery
+Finds the jobs a crash interrupted (see job_journal) and puts their work
+right: a download whose file got in place is recorded in the library, one
+cut short resumes from its checkpoints, a conversion runs its processors
+again, and a metadata scan has the files it was writing removed (the next
+scan picks up the games it did not get to). With --clean-only nothing is
+resumed; partial files are removed instead.
+
+The GUI runs "recover" on every start and shows what was recovered.
+"""
+
+import argparse
+import contextlib
+import json
+import sys
+from pathlib import Path
+from typing import Dict, List, Optional, Tuple
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from job_journal import JobJournal
+from library_db import open_library, platform_from_url
+from rom_config import RomConfig, add_config_argument
+from script_errors import install_error_reporting
+
+
+class Colors:
+    """ANSI color codes for terminal output."""
+    RED = '\033[0;31m'
+    GREEN = '\033[0;32m'
+    YELLOW = '\033[1;33m'
+    CYAN = '\033[0;36m'
+    NC = '\033[0m'  # No Color
+
+
+ACTION_COLORS = {
+    "resumed": Colors.GREEN,
+    "completed": Colors.GREEN,
+    "cleaned": Colors.CYAN,
+    "left": Colors.YELLOW,
+    "failed": Colors.RED,
+}
+
+
+def remove_files(paths: List[str]) -> List[str]:
+    """Remove the files that still exist. Returns the ones removed."""
+    removed = []
+    for path in map(Path, paths):
+        if path.is_file():
+            path.unlink()
+            removed.append(str(path))
+    return removed
+
+
+class JobRecovery:
+    """Recovers the interrupted jobs of a journal, one recovery per job even when two run at once."""
+
+    def __init__(self, config: Optional[RomConfig] = None, resume: bool = True):
+        self.config = config or RomConfig()
+        self.resume = resume
+        self.journal = JobJournal(self.config)
+        self.library = open_library(self.config)
+        self.downloader = None
+
+    def rom_downloader(self):
+        if self.downloader is None:
+            from rom_downloader import ROMDownloader
+
+            self.downloader = ROMDownloader(self.config)
+        return self.downloader
+
+    def recover(self) -> List[Dict]:
+        """Recover every interrupted job. Returns what was done with each."""
+        results = []
+        for entry in self.journal.interrupted():
+            result = {key: entry.get(key) for key in ("id", "kind", "label", "started")}
+            if self.config.is_read_only():
+                results.append(dict(result, action="left", detail="read-only mode, nothing was changed",
+                                    removed_files=[]))
+                continue
+
+            claimed = self.journal.claim(entry)
+            if not claimed:
+                continue
+            handler = {
+                "download": self.recover_download,
+                "conversion": self.recover_conversion,
+                "scan": self.recover_scan,
+            }.get(entry.get("kind"), self.recover_other)
+            try:
+                action, detail, removed = handler(entry)
+            except Exception as e:
+                action, detail, removed = "failed", str(e), []
+            claimed.unlink(missing_ok=True)
+            results.append(dict(result, action=action, detail=detail, removed_files=removed))
+        return results
+
+    def recover_download(self, entry: Dict) -> Tuple[str, str, List[str]]:
+        state = entry["state"]
+        path = Path(state["path"])
+        if path.exists():
+            # The crash came after the file got in place
+            removed = remove_files(entry["files"])
+            if self.library.get_entry(path):
+                return "cleaned", f"{path.name} had finished downloading", removed
+            self.library.record_download(path, state["url"], platform_from_url(state["url"]), path.stat().st_size,
+                                         state.get("validator"))
+            return "completed", f"{path.name} had finished downloading; recorded it in the library", removed
+
+        if not self.resume:
+            return "cleaned", "removed the partial download", remove_files(entry["files"])
+
+        # Download messages would garble --json output
+        with contextlib.redirect_stdout(sys.stderr):
+            downloaded = self.rom_downloader().download_file(state["url"], state["filename"])
+        if downloaded:
+            return "resumed", f"finished downloading {state['filename']}", []
+        return "failed", "the download failed again; its partial file is kept for the next try", []
+
+    def recover_conversion(self, entry: Dict) -> Tuple[str, str, List[str]]:
+        state = entry["state"]
+        path = Path(state["path"])
+        removed = remove_files(entry["files"])
+        if not path.exists():
+            return "left", f"{path.name} is gone, a processor may have moved it; its library entry was kept", removed
+        if not self.resume:
+            return "cleaned", f"left {path.name} unprocessed", removed
+
+        with contextlib.redirect_stdout(sys.stderr):
+            final_path = self.rom_downloader().post_process(path, state["url"])
+        return "resumed", f"processed {path.name} again" + (f", now {final_path}" if final_path != path else ""), removed
+
+    def recover_scan(self, entry: Dict) -> Tuple[str, str, List[str]]:
+        state = entry["state"]
+        removed = remove_files(entry["files"])
+        where = f" at {state['game']}" if state.get("game") else ""
+        progress = f" ({state['done']}/{state['total']})" if state.get("total") else ""
+        return "cleaned", f"the scan stopped{where}{progress}; the next scan picks up the rest", removed
+
+    def recover_other(self, entry: Dict) -> Tuple[str, str, List[str]]:
+        return "cleaned", "removed the job's temporary files", remove_files(entry["files"])
+
+
+def print_results(results: List[Dict]):
+    if not results:
+        print(f"{Colors.GREEN}No interrupted jobs{Colors.NC}")
+        return
+    for result in results:
+        color = ACTION_COLORS.get(result["action"], Colors.NC)
+        print(f"{color}{result['action']:>9}{Colors.NC}  {result['kind']} {result['label']}: {result['detail']}")
+        for path in result["removed_files"]:
+            print(f"           removed {path}")
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Recover the jobs a crash interrupted')
+    add_config_argument(parser)
+    subparsers = parser.add_subparsers(dest='command', required=True)
+
+    list_parser = subparsers.add_parser('list', help='List the journaled jobs, running or interrupted')
+    list_parser.add_argument('--json', action='store_true', help='Print the jobs as JSON')
+
+    recover_parser = subparsers.add_parser('recover', help='Clean up or resume the interrupted jobs')
+    recover_parser.add_argument('--clean-only', action='store_true',
+                                help='Remove partial files instead of resuming downloads and conversions')
+    recover_parser.add_argument('--json', action='store_true', help='Print what was recovered as JSON')
+
+    args = parser.parse_args()
+    config = RomConfig()
+
+    if args.command == 'list':
+        entries = JobJournal(config).entries()
+        if args.json:
+            print(json.dumps(entries))
+            return
+        if not entries:
+            print(f"{Colors.GREEN}No journaled jobs{Colors.NC}")
+        for entry in entries:
+            status = f"{Colors.CYAN}running{Colors.NC}" if entry["running"] else f"{Colors.YELLOW}interrupted{Colors.NC}"
+            print(f"{status}  {entry['kind']} {entry['label']} (started {entry['started']}, pid {entry['pid']})")
+    elif args.command == 'recover':
+        results = JobRecovery(config, resume=not args.clean_only).recover()
+        if args.json:
+            print(json.dumps(results))
+        else:
+            print_results(results)
+        # --json callers read the failures from the results
+        sys.exit(1 if not args.json and any(result["action"] == "failed" for result in results) else 0)
+
+
+if __name__ == "__main__":
+    install_error_reporting()
+    main()
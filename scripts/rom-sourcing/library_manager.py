@@ -0,0 +1,1043 @@
+#!/usr/bin/env python3
+"""
+Library Manager
+Checks that the source URLs of downloaded ROMs still exist, so entries whose
+source has vanished can be prioritised for backup, and that the source files
+have not changed since (a re-dump or fixed rip), offering to download the
+corrected version. Also extracts the icons ROMs embed for use as fallback
+artwork, and reports the download speed history, why a file was picked
+for the title it was queued as, and which files are owned already. For
+set completionists it lists the titles of a platform's DAT (or of its
+archive folder) that are not in the collection yet, and how complete each
+platform is. Libraries copied from another machine get their paths converted
+to the portable form with migrate-paths, and clean-partials removes the
+partial files interrupted downloads left behind. delete removes games from
+disk and the library together, recording it in the activity feed. manifest
+writes SHA1SUMS or .sfv checksum manifests of the collection folders and
+checks copies of them, e.g. on an external drive, against those. dedupe
+finds byte-identical files across the collection folders and replaces the
+extra copies with hardlinks or symlinks to reclaim the space.
+"""
+
+import argparse
+import contextlib
+import csv
+import itertools
+import json
+import sys
+import time
+import urllib.parse
+from datetime import datetime, timedelta
+from pathlib import Path
+from typing import Dict, Iterator, List, Optional, Tuple
+
+import requests
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+import checksum_manifests
+import dedupe
+from dat_index import DatIndex, hash_entries
+from job_journal import JobJournal
+from job_limits import JobLimits
+from library_db import BACKUP_SUFFIXES, NON_DOWNLOAD_SUFFIXES, PARTIAL_SUFFIXES, URL_ERROR, URL_MISSING, URL_OK, open_library, owned_key, owned_keys
+from names import NameFilter, add_name_filter_arguments, name_filter_from_args, parse_name
+from http_client import http_session
+from job_progress import ProgressTracker, default_reporter
+from proxy import apply_proxy_settings
+from response_cache import cached_listing
+from rom_config import ReadOnlyError, RomConfig, add_config_argument, set_read_only
+from rom_sources import InternetArchiveSource, format_size
+from script_errors import fail, install_error_reporting
+
+
+class Colors:
+    """ANSI color codes for terminal output."""
+    RED = '\033[0;31m'
+    GREEN = '\033[0;32m'
+    YELLOW = '\033[1;33m'
+    CYAN = '\033[0;36m'
+    NC = '\033[0m'  # No Color
+
+
+STATUS_COLORS = {
+    URL_OK: Colors.GREEN,
+    URL_MISSING: Colors.RED,
+    URL_ERROR: Colors.YELLOW
+}
+
+# What --exclude-demos leaves out of a missing-titles report
+DEMO_STATUSES = {"Demo", "Sample", "Kiosk", "Promo"}
+# Library checks that count a title as verified
+VERIFIED = {"verified", "crc-match"}
+MISSING_CSV_FIELDS = ["title", "platform", "regions", "languages", "status", "size", "url"]
+# Files a download leaves next to its file, removed with it; each partial file is named
+# after the one before (X.part, X.part.pieces.json, X.part.pieces.json.tmp)
+SIDECAR_SUFFIXES = tuple(itertools.accumulate(PARTIAL_SUFFIXES)) + BACKUP_SUFFIXES
+
+
+class LibraryManager:
+    def __init__(self, config: Optional[RomConfig] = None):
+        self.config = config or RomConfig()
+        apply_proxy_settings(self.config)
+        self.db = open_library(self.config)
+        self.check_interval = timedelta(hours=self.config.get('library.url_check_interval_hours', 168))
+        self.archive_org = InternetArchiveSource(self.config, log=lambda message: None)
+        self.session = http_session(self.config)
+
+    def check_url(self, url: str) -> Dict:
+        """Check whether a source URL still exists.
+
+        404/410 mean the source vanished; anything else that fails is recorded
+        as an error so a flaky server does not flag the entry.
+        """
+        try:
+            response = self.session.head(url, allow_redirects=True, timeout=15)
+            # Some servers do not implement HEAD
+            if response.status_code in (403, 405, 501):
+                response = self.session.get(url, stream=True, timeout=15)
+                response.close()
+        except requests.RequestException as e:
+            return {"status": URL_ERROR, "http_status": None, "error": str(e), "size": None, "validator": None}
+
+        if response.status_code < 400:
+            status = URL_OK
+        elif response.status_code in (404, 410):
+            status = URL_MISSING
+        else:
+            status = URL_ERROR
+
+        length = response.headers.get('content-length') if status == URL_OK else None
+        return {
+            "status": status, "http_status": response.status_code, "error": None,
+            "size": int(length) if length and length.isdigit() else None,
+            "validator": response.headers.get('etag') or response.headers.get('last-modified')
+        }
+
+    def detect_drift(self, entry: Dict, result: Dict) -> Optional[str]:
+        """Work out whether the source file changed since an entry was downloaded; returns why.
+
+        The size is compared, plus the SHA1 for sources that publish one
+        (archive.org). Validators alone are not trusted: mirrors serve the same
+        file with different ETags. Entries recorded before sizes were kept take
+        the current size as their baseline.
+        """
+        if result['status'] != URL_OK:
+            return None
+
+        if entry['source_size'] is None:
+            if result['size']:
+                self.db.set_source_info(entry['id'], size=result['size'], validator=result['validator'])
+            return None
+
+        if result['size'] and result['size'] != entry['source_size']:
+            return f"size changed from {entry['source_size']} to {result['size']} bytes"
+
+        path = self.archive_org.path_of(entry['source_url'])
+        if entry['source_sha1'] and path:
+            try:
+                sha1 = self.archive_org.metadata(path)['sha1']
+            except Exception:
+                return None
+            if sha1 and sha1.lower() != entry['source_sha1'].lower():
+                return "SHA1 published by the source changed"
+
+        return None
+
+    def check_sources(self, check_all=False, progress=True) -> List[Dict]:
+        """Check the source URLs that are due, storing each result."""
+        if check_all:
+            entries = [e for e in self.db.get_entries() if e['source_url']]
+        else:
+            entries = self.db.get_entries_due_for_check(self.check_interval)
+
+        results = []
+        tracker = ProgressTracker("check", total=len(entries), reporter=default_reporter(show=progress))
+        for entry in entries:
+            result = self.check_url(entry['source_url'])
+            self.db.update_url_status(entry['id'], result['status'], result['http_status'])
+
+            outdated_reason = self.detect_drift(entry, result)
+            if outdated_reason:
+                self.db.mark_outdated(entry['id'], outdated_reason)
+
+            result.update({"id": entry['id'], "filename": entry['filename'],
+                           "platform": entry['platform'], "source_url": entry['source_url'],
+                           "previous_status": entry['url_status'],
+                           "outdated_reason": outdated_reason or entry['outdated_reason']})
+            results.append(result)
+
+            color = STATUS_COLORS.get(result['status'], Colors.NC)
+            note = f" {Colors.YELLOW}(outdated: {outdated_reason}){Colors.NC}" if outdated_reason else ""
+            tracker.advance(message=f"{color}{result['status']:>7}{Colors.NC} {entry['filename']}{note}")
+
+            # Be respectful to the source servers
+            time.sleep(0.2)
+
+        tracker.finish()
+        return results
+
+    def redownload(self, entry: Dict) -> bool:
+        """Replace an outdated entry's file with the current version from its source.
+
+        The old file is kept aside until the new download succeeds, and put
+        back if it fails.
+        """
+        from rom_downloader import ROMDownloader
+
+        self.config.ensure_writable("re-download library entries")
+        path = Path(entry['path'])
+        backup = path.with_name(path.name + '.outdated')
+        if path.exists():
+            path.replace(backup)
+
+        # Download next to the old file, under the name the source uses
+        downloader = ROMDownloader(self.config)
+        downloader.download_dir = path.parent
+        downloader.path_template = '{filename}'
+        downloader.platform_templates = {}
+        filename = urllib.parse.unquote(urllib.parse.urlparse(entry['source_url']).path.rsplit('/', 1)[-1])
+        if not downloader.download_file(entry['source_url'], filename):
+            if backup.exists():
+                backup.replace(path)
+            return False
+
+        if backup.exists():
+            backup.unlink()
+        # Post-processing may have stored the new version under another name
+        current = self.db.get_entry(path)
+        if current and current['outdated_at']:
+            self.db.remove_entry(entry['id'])
+        return True
+
+    def stale_partials(self, min_age: timedelta) -> List[Dict]:
+        """Find partial download files in the download and temp folders not written to within min_age.
+
+        Files a journaled job is writing are left out, running or not: an
+        interrupted one is job_recovery.py's to resume or clean up.
+        """
+        journaled = {str(Path(path).resolve()) for entry in JobJournal(self.config).entries()
+                     for path in entry.get("files", [])}
+        cutoff = datetime.now() - min_age
+        partials = {}
+        for setting in ('paths.download_directory', 'paths.temp_directory'):
+            directory = self.config.get_path(setting)
+            if not directory.is_dir():
+                continue
+            for path in sorted(directory.rglob('*')):
+                if not path.is_file() or not path.name.endswith(PARTIAL_SUFFIXES):
+                    continue
+                if str(path.resolve()) in journaled:
+                    continue
+                stat = path.stat()
+                modified = datetime.fromtimestamp(stat.st_mtime)
+                if modified <= cutoff:
+                    # The temp folder may be inside the download folder
+                    partials[str(path)] = {"path": str(path), "size": stat.st_size,
+                                           "modified": modified.isoformat(timespec='seconds')}
+        return list(partials.values())
+
+    def clean_partials(self, min_age: timedelta, dry_run: bool = False) -> List[Dict]:
+        """Remove the stale partial files (see stale_partials); with dry_run only list them."""
+        partials = self.stale_partials(min_age)
+        if dry_run:
+            return partials
+        self.config.ensure_writable("remove partial downloads")
+        for partial in partials:
+            Path(partial['path']).unlink(missing_ok=True)
+        return partials
+
+    def collection_folders(self) -> List[Path]:
+        """Get the folders delete may remove files from: downloads, library.rom_directories and paths.roots."""
+        folders = [self.config.get_path('paths.download_directory')]
+        folders += [Path(folder).expanduser() for folder in self.config.get('library.rom_directories', []) or []]
+        folders += [Path(folder).expanduser() for folder in (self.config.get('paths.roots', {}) or {}).values()]
+        return [folder.resolve() for folder in folders]
+
+    def deletion_plan(self, targets: List[str]) -> List[Dict]:
+        """Resolve library entry ids and file paths into what delete removes: {path, entry}.
+
+        Raises ValueError listing every target that cannot be deleted, so a
+        batch is removed whole or not at all.
+        """
+        entries = self.db.get_entries()
+        by_id = {str(entry['id']): entry for entry in entries}
+        folders = self.collection_folders()
+        plan, problems = {}, []
+        for target in targets:
+            if target in by_id:
+                entry = by_id[target]
+                path = Path(entry['path']).resolve()
+            else:
+                path = Path(target).expanduser().resolve()
+                entry = self.db.get_entry(path)
+                if not entry and not path.exists():
+                    problems.append(f"{target}: no such library entry or file")
+                    continue
+            if path.is_dir():
+                problems.append(f"{target}: is a folder")
+            elif not any(path == folder or folder in path.parents for folder in folders):
+                problems.append(f"{target}: outside the download folder, library.rom_directories and paths.roots")
+            else:
+                plan[str(path)] = {"path": path, "entry": entry}
+        if problems:
+            raise ValueError("Nothing was deleted:\n  " + "\n  ".join(problems))
+        return list(plan.values())
+
+    def delete_games(self, targets: List[str], archive: bool = False, dry_run: bool = False) -> Dict:
+        """Delete games from disk and the library: each file, what a download left next to it and
+        its extracted icon when no other entry uses it. The entries are removed, or with archive
+        kept in the archived entries, and the deletion is added to the activity feed.
+
+        targets are library entry ids or file paths; with dry_run nothing is removed.
+        """
+        if not dry_run:
+            self.config.ensure_writable("delete games")
+        plan = self.deletion_plan(targets)
+        deleted_ids = {item['entry']['id'] for item in plan if item['entry']}
+        icons_in_use = {entry['icon_path'] for entry in self.db.get_entries()
+                        if entry['icon_path'] and entry['id'] not in deleted_ids}
+
+        results = []
+        for item in plan:
+            path, entry = item['path'], item['entry']
+            files = [candidate for candidate in [path] + [path.with_name(path.name + suffix) for suffix in SIDECAR_SUFFIXES]
+                     if candidate.is_file()]
+            icon = entry['icon_path'] if entry else None
+            if icon and icon not in icons_in_use and Path(icon).is_file():
+                files.append(Path(icon))
+            size = path.stat().st_size if path.is_file() else 0
+            if not dry_run:
+                for file in files:
+                    file.unlink(missing_ok=True)
+                if entry:
+                    self.db.remove_entry(entry['id'], archive=archive)
+            results.append({"path": str(path), "entry_id": entry['id'] if entry else None, "size": size,
+                            "removed": [str(file) for file in files], "archived": bool(archive and entry)})
+
+        if results and not dry_run:
+            total = sum(result['size'] for result in results)
+            self.db.record_activity("delete", f"Deleted {len(results)} files ({format_size(total)})", {
+                "files": [result['path'] for result in results],
+                "bytes": total,
+                "archived": archive,
+            })
+        return {"dry_run": dry_run, "deleted": results}
+
+    def extract_icons(self, extract_all=False, progress=True) -> List[Dict]:
+        """Scan library files for embedded icons (NDS, 3DS, GameCube, Switch NRO).
+
+        Only entries without an icon are scanned unless extract_all is set.
+        """
+        entries = [e for e in self.db.get_entries() if extract_all or not e['icon_path']]
+        results = []
+        tracker = ProgressTracker("scan", total=len(entries), reporter=default_reporter(show=progress))
+        for entry in entries:
+            icon_path = self.db.extract_icon(entry['path']) if Path(entry['path']).exists() else None
+            results.append({"id": entry['id'], "filename": entry['filename'], "icon_path": icon_path})
+            tracker.advance(message=f"{Colors.GREEN}icon{Colors.NC} {entry['filename']}" if icon_path else None)
+
+        tracker.finish()
+        return results
+
+    def manifest_folders(self) -> List[Path]:
+        """Get the folders manifest and dedupe cover unless others are given: downloads and library.rom_directories."""
+        folders = [self.config.get_path('paths.download_directory')]
+        folders += [Path(folder).expanduser() for folder in self.config.get('library.rom_directories', []) or []]
+        return [folder for folder in folders if folder.is_dir()]
+
+    def write_manifests(self, folders: List[Path], manifest_format: str = "sha1", progress=True) -> List[Dict]:
+        """Write a checksum manifest into each folder, hashing every file in it (see checksum_manifests)."""
+        self.config.ensure_writable("write checksum manifests")
+        limits = JobLimits(self.config)
+        results = []
+        for folder in folders:
+            tracker = ProgressTracker("manifest", total=len(checksum_manifests.collection_files(folder)),
+                                      unit="files", label=str(folder), reporter=default_reporter(show=progress))
+            results.append(checksum_manifests.write_manifest(folder, manifest_format, limits,
+                                                             advance=lambda name: tracker.advance(message=name)))
+            tracker.finish()
+        return results
+
+    def verify_manifests(self, targets: List[Path], progress=True) -> List[Dict]:
+        """Check folders (or manifest files) against their manifests: {manifest, counts, files} each.
+
+        Raises ValueError when a target has no manifest or it cannot be read.
+        """
+        manifests = [checksum_manifests.find_manifest(target) for target in targets]
+        limits = JobLimits(self.config)
+        reports = []
+        for manifest in manifests:
+            tracker = ProgressTracker("verify", total=len(checksum_manifests.read_manifest(manifest)), unit="files",
+                                      label=str(manifest), reporter=default_reporter(show=progress))
+            files = checksum_manifests.verify_manifest(manifest, limits, advance=lambda name: tracker.advance())
+            tracker.finish()
+            reports.append({"manifest": str(manifest), "counts": checksum_manifests.summarize(files), "files": files})
+        return reports
+
+    def dedupe(self, folders: List[Path], link: Optional[str] = None, dry_run: bool = False,
+               progress=True) -> Dict:
+        """Find identical files in the folders and, given link, replace the copies with links (see dedupe)."""
+        if link and not dry_run:
+            self.config.ensure_writable("link duplicate files")
+        tracker = ProgressTracker("dedupe", total=None, unit="files", label="duplicates",
+                                  reporter=default_reporter(show=progress))
+        result = dedupe.dedupe_folders(folders, link, dry_run, JobLimits(self.config),
+                                       advance=lambda name: tracker.advance(message=name))
+        tracker.finish()
+        return result
+
+    def get_backup_priorities(self) -> List[Dict]:
+        """Get library entries ordered by how urgently they need a backup.
+
+        Entries whose source vanished come first, then entries whose last
+        check failed, then entries that were never checked.
+        """
+        order = {URL_MISSING: 0, URL_ERROR: 1, None: 2, URL_OK: 3}
+        entries = self.db.get_entries()
+        return sorted(entries, key=lambda e: (order.get(e['url_status'], 2), e['platform'] or '', e['filename']))
+
+    def platform_files(self, platform: str) -> List[Path]:
+        """Get the files of a platform the user has: its library entries still on disk and the downloads in a folder named after it."""
+        name = platform.lower()
+        files = {Path(entry['path']) for entry in self.db.get_entries()
+                 if (entry['platform'] or '').lower() == name and Path(entry['path']).exists()}
+
+        downloads = self.config.get_path('paths.download_directory')
+        folders = [folder for folder in downloads.rglob('*') if folder.is_dir() and folder.name.lower() == name] \
+            if downloads.is_dir() else []
+        for folder in folders:
+            files.update(path for path in folder.rglob('*')
+                         if path.is_file() and not path.name.endswith(NON_DOWNLOAD_SUFFIXES))
+        return sorted(files)
+
+    def dat_titles(self, index: DatIndex, platform: str) -> List[Dict]:
+        """Get the titles the platform's DATs list, with the names of their files."""
+        titles = {}
+        for roms in index.by_title.values():
+            for rom in roms:
+                if rom['platform'].lower() != platform.lower():
+                    continue
+                title = titles.setdefault(rom['title'], {
+                    "title": rom['title'], "platform": rom['platform'], "size": 0, "url": None,
+                    "keys": {owned_key(rom['title'])}
+                })
+                title['size'] += rom['size'] or 0
+                if rom['rom']:
+                    title['keys'].add(owned_key(rom['rom']))
+        return list(titles.values())
+
+    def catalog_titles(self, folder: str) -> Optional[List[Dict]]:
+        """Get the files of an archive folder as titles; None when it cannot be listed."""
+        from rom_browser import list_archive_folder
+
+        entries = list_archive_folder(folder)
+        return listing_titles(entries, folder) if entries is not None else None
+
+    def load_dats(self) -> DatIndex:
+        index = DatIndex()
+        index.load_directory(self.config.get_path('paths.dat_directory'),
+                             log=lambda message: print(message, file=sys.stderr))
+        return index
+
+    def verify_files(self, index: DatIndex, files: List[Path], progress: bool = True) -> Iterator[Tuple[Path, Dict]]:
+        """Hash files and look them up in the DATs, yielding each with its {verification, title}."""
+        limits = JobLimits(self.config)
+        tracker = ProgressTracker("verify", total=len(files), unit="files", reporter=default_reporter(show=progress))
+        for path, entries in zip(files, limits.map(lambda path: hash_entries(path, limits), files)):
+            matches = [index.lookup(hashes) for hashes in entries]
+            tracker.advance(message=path.name)
+            yield path, file_verification(matches)
+        tracker.finish()
+
+    def verify_library(self, index: DatIndex, progress: bool = True) -> int:
+        """Check the library entries not checked yet against the DATs and store the results. Returns how many."""
+        entries = {Path(entry['path']): entry for entry in self.db.get_entries()
+                   if not entry['verification'] and Path(entry['path']).exists()}
+        for path, result in self.verify_files(index, list(entries), progress):
+            self.db.set_verification(entries[path]['id'], result['verification'], result['title'])
+        return len(entries)
+
+    def missing_titles(self, platform: str, source: str = 'dat', name_filter: Optional[NameFilter] = None,
+                       exclude_demos: bool = False, verify: bool = False, progress: bool = True) -> Dict:
+        """Compare the files of a platform the user has with the titles of its DAT or archive folder.
+
+        A title is owned when a file has its name (or the name of one of its
+        files), whatever the extension, so a .zip converted to .chd still
+        counts. With verify only files whose hashes the DATs list count, under
+        the title the DAT gives them however they are named.
+
+        Raises ValueError when there is nothing to compare against.
+        """
+        platform_name = platform.strip('/').rsplit('/', 1)[-1]
+        index = DatIndex()
+        if source == 'dat' or verify:
+            dat_dir = self.config.get_path('paths.dat_directory')
+            index = self.load_dats()
+            if not any(dat['platform'].lower() == platform_name.lower() for dat in index.dats):
+                raise ValueError(f"No DAT for {platform_name} in {dat_dir}"
+                                 + ("; use --source catalog to compare with the archive folder" if source == 'dat' else ""))
+
+        if source == 'dat':
+            titles = self.dat_titles(index, platform_name)
+        else:
+            if '/' not in platform.strip('/'):
+                raise ValueError(f"Give the archive folder of {platform}, e.g. \"Redump/Sony - PlayStation\"")
+            titles = self.catalog_titles(platform)
+            if titles is None:
+                raise ValueError(f"Failed to list {platform}")
+
+        for title in titles:
+            title.update({key: value for key, value in parse_name(title['title']).to_dict().items()
+                          if key in ('regions', 'languages', 'status')})
+        if name_filter and not name_filter.is_empty():
+            titles = [title for title in titles if name_filter.matches(parse_name(title['title']))]
+        if exclude_demos:
+            titles = [title for title in titles if title['status'] not in DEMO_STATUSES]
+
+        files = self.platform_files(platform_name)
+        if verify:
+            owned = {owned_key(result['title']) for _, result in self.verify_files(index, files, progress)
+                     if result['verification'] in VERIFIED}
+        else:
+            owned = {owned_key(path.name) for path in files}
+
+        missing = [title for title in titles if not title['keys'] & owned]
+        for title in titles:
+            del title['keys']
+        return {
+            "platform": platform_name,
+            "source": source,
+            "verified": verify,
+            "total": len(titles),
+            "owned": len(titles) - len(missing),
+            "missing": sorted(missing, key=lambda title: title['title'].lower()),
+        }
+
+    def collection_stats(self, include_all: bool = False, verify: bool = False, progress: bool = True) -> List[Dict]:
+        """Get how complete each platform is: titles owned and verified out of its set, and the sizes.
+
+        The set is the platform's DAT, or else the cached listing of the
+        archive folder its downloads came from (without a network request).
+        Platforms the user has files of are included; with include_all every
+        platform with a DAT is. Verified counts come from the checks stored in
+        the library; verify checks the entries not checked yet first.
+        """
+        index = self.load_dats()
+        if verify:
+            self.verify_library(index, progress)
+
+        entries = [entry for entry in self.db.get_entries() if Path(entry['path']).exists()]
+        platforms = {entry['platform'].lower(): entry['platform'] for entry in entries if entry['platform']}
+        downloads = self.config.get_path('paths.download_directory')
+        if downloads.is_dir():
+            platforms.update({folder.name.lower(): folder.name for folder in downloads.iterdir()
+                              if folder.is_dir() and folder.name.lower() not in platforms})
+        dat_platforms = {dat['platform'].lower(): dat['platform'] for dat in index.dats}
+        if include_all:
+            platforms.update(dat_platforms)
+
+        return [self.platform_stats(index, dat_platforms.get(key, name),
+                                    [entry for entry in entries if (entry['platform'] or '').lower() == key])
+                for key, name in sorted(platforms.items())]
+
+    def platform_stats(self, index: DatIndex, platform: str, entries: List[Dict]) -> Dict:
+        files = self.platform_files(platform)
+        if any(dat['platform'].lower() == platform.lower() for dat in index.dats):
+            source, titles = 'dat', self.dat_titles(index, platform)
+        else:
+            source, titles = None, None
+            root = self.config.get_archive_url()
+            folders = {urllib.parse.unquote(entry['source_url'][len(root):]).rpartition('/')[0] for entry in entries
+                       if (entry['source_url'] or '').startswith(root)}
+            for folder in sorted(folders):
+                listing = cached_listing(self.config, folder)
+                if listing:
+                    source, titles = 'catalog', listing_titles(listing['value'], folder)
+                    break
+
+        verified_keys = {owned_key(entry['verified_title']) for entry in entries
+                         if entry['verification'] in VERIFIED and entry['verified_title']}
+        have = {owned_key(path.name) for path in files} | verified_keys
+        stats = {
+            "platform": platform,
+            "source": source,
+            "files": len(files),
+            "owned_bytes": sum(path.stat().st_size for path in files),
+            # Library files never checked against the DATs; files outside the library cannot be
+            "unchecked_files": sum(1 for entry in entries if not entry['verification']),
+            "titles": None, "owned": None, "verified": None, "unverified": None,
+            "percent_owned": None, "percent_verified": None, "set_bytes": None, "missing_bytes": None,
+        }
+        if titles is None:
+            return stats
+
+        owned = [title for title in titles if title['keys'] & have]
+        verified = [title for title in owned if title['keys'] & verified_keys]
+        set_bytes = sum(title['size'] or 0 for title in titles)
+        stats.update({
+            "titles": len(titles),
+            "owned": len(owned),
+            "verified": len(verified),
+            "unverified": len(owned) - len(verified),
+            "percent_owned": round(len(owned) * 100 / len(titles), 1) if titles else None,
+            "percent_verified": round(len(verified) * 100 / len(titles), 1) if titles else None,
+            "set_bytes": set_bytes,
+            "missing_bytes": set_bytes - sum(title['size'] or 0 for title in owned),
+        })
+        return stats
+
+
+def listing_titles(entries: List[Dict], folder: str) -> List[Dict]:
+    """Get the files of an archive folder listing as titles."""
+    platform = folder.strip('/').rsplit('/', 1)[-1]
+    return [{"title": Path(entry['name']).stem, "platform": platform, "size": entry['size'],
+             "url": entry.get('url'), "keys": {owned_key(entry['name'])}}
+            for entry in entries if not entry['is_dir']]
+
+
+def file_verification(matches: List[Optional[Dict]]) -> Dict:
+    """Sum up the DAT lookups of a file's entries (one, or each member of a ZIP) as {verification, title}."""
+    found = [match for match in matches if match]
+    title = found[0]['title'] if found else None
+    if not found or len(found) < len(matches):
+        return {"verification": "unknown", "title": title}
+    statuses = {match['verification'] for match in found}
+    for verification in ('bad-dump', 'crc-match'):
+        if verification in statuses:
+            return {"verification": verification, "title": title}
+    return {"verification": "verified", "title": title}
+
+
+def print_summary(results: List[Dict]):
+    """Print a summary of a source URL check."""
+    counts = {URL_OK: 0, URL_MISSING: 0, URL_ERROR: 0}
+    for result in results:
+        counts[result['status']] += 1
+
+    print(f"\n{Colors.CYAN}Checked {len(results)} source URLs{Colors.NC}")
+    print(f"  {Colors.GREEN}Available: {counts[URL_OK]}{Colors.NC}")
+    print(f"  {Colors.RED}Vanished:  {counts[URL_MISSING]}{Colors.NC}")
+    print(f"  {Colors.YELLOW}Errors:    {counts[URL_ERROR]}{Colors.NC}")
+
+    newly_missing = [r for r in results if r['status'] == URL_MISSING and r['previous_status'] != URL_MISSING]
+    if newly_missing:
+        print(f"\n{Colors.RED}Sources that vanished since the last check (back these up):{Colors.NC}")
+        for result in newly_missing:
+            print(f"  {result['filename']} ({result['platform'] or 'unknown platform'})")
+
+
+def print_manifest_reports(reports: List[Dict]):
+    """Print the files that do not match their manifests, and a count per manifest."""
+    colors = {"mismatch": Colors.RED, "missing": Colors.RED, "unreadable": Colors.RED, "unlisted": Colors.YELLOW}
+    for report in reports:
+        for file in report['files']:
+            if file['status'] != "ok":
+                print(f"{colors[file['status']]}{file['status']:>10}{Colors.NC}  {file['path']}")
+        counts = report['counts']
+        problems = ", ".join(f"{counts[status]} {status}" for status in ("mismatch", "missing", "unreadable", "unlisted")
+                             if counts.get(status))
+        color = Colors.RED if any(counts.get(status) for status in ("mismatch", "missing", "unreadable")) else Colors.GREEN
+        print(f"{color}{report['manifest']}: {counts.get('ok', 0)} files match{', ' + problems if problems else ''}{Colors.NC}")
+
+
+def print_dedupe_report(result: Dict, dry_run: bool = False):
+    """Print each group of identical files, what became of the copies, and the space saved."""
+    links = {link['path']: link for link in result['links']}
+    for group in result['groups']:
+        print(f"{Colors.CYAN}{format_size(group['size'])}  {group['sha1']}{Colors.NC}")
+        print(f"    keep  {group['keep']}")
+        for copy in group['copies']:
+            link = links.get(copy)
+            if link and link['status'] == "skipped":
+                print(f"    {Colors.YELLOW}skip{Colors.NC}  {copy} ({link['error']})")
+            else:
+                print(f"    {'link' if link else 'copy'}  {copy}")
+    reclaimable = format_size(result['reclaimable'])
+    if not result['links']:
+        print(f"{Colors.GREEN}{result['copies']} duplicate copies; linking them would free {reclaimable}{Colors.NC}")
+    elif dry_run:
+        print(f"{Colors.GREEN}Would link {result['copies']} duplicate copies, freeing {reclaimable}{Colors.NC}")
+    else:
+        linked = sum(1 for link in result['links'] if link['status'] == "linked")
+        print(f"{Colors.GREEN}Linked {linked} of {result['copies']} duplicate copies, "
+              f"freeing {format_size(result['reclaimed'])}{Colors.NC}")
+
+
+def write_missing_report(report: Dict, output_format: str, out):
+    """Write a missing-titles report as text, JSON or CSV."""
+    if output_format == 'json':
+        json.dump(report, out, indent=2)
+        out.write("\n")
+        return
+    if output_format == 'csv':
+        writer = csv.DictWriter(out, fieldnames=MISSING_CSV_FIELDS, extrasaction='ignore')
+        writer.writeheader()
+        for title in report['missing']:
+            writer.writerow(dict(title, regions=', '.join(title['regions']), languages=', '.join(title['languages'])))
+        return
+
+    for title in report['missing']:
+        out.write(f"  {title['title']}\n")
+    total = report['total']
+    percent = f" ({report['owned'] * 100 / total:.1f}%)" if total else ""
+    how = "verified " if report['verified'] else ""
+    out.write(f"\n{Colors.CYAN}{report['platform']}: {how}{report['owned']} of {total} titles owned{percent}, "
+              f"{len(report['missing'])} missing{Colors.NC}\n")
+
+
+def print_collection_stats(stats: List[Dict]):
+    """Print one line per platform: titles owned and verified out of its set, then the sizes."""
+    if not stats:
+        print(f"{Colors.YELLOW}No platforms in the library or the downloads folder{Colors.NC}")
+        return
+    width = max(len(platform['platform']) for platform in stats)
+    for platform in stats:
+        name = platform['platform'].ljust(width)
+        size = format_size(platform['owned_bytes'])
+        if platform['titles'] is None:
+            print(f"  {name}  {platform['files']} files, {size} (no DAT or cached listing to compare with)")
+            continue
+        owned = f"{platform['owned']:>5}/{platform['titles']:<5} {platform['percent_owned']:5.1f}%"
+        if platform['owned'] == platform['titles']:
+            owned = f"{Colors.GREEN}{owned}{Colors.NC}"
+        print(f"  {name}  {owned}"
+              f"  verified {platform['verified']} ({platform['percent_verified']:.1f}%)"
+              f"  {size} of {format_size(platform['set_bytes'])}  [{platform['source']}]")
+    unchecked = sum(platform['unchecked_files'] for platform in stats)
+    if unchecked:
+        print(f"\n{unchecked} library files were never checked against the DATs; "
+              f"library_manager.py stats --verify checks them")
+
+
+def format_speed(bps: Optional[float]) -> str:
+    return f"{bps / (1024 * 1024):.2f} MiB/s" if bps else "-"
+
+
+def print_speed_history(history: Dict):
+    """Print average speeds per host and per session; a slow host next to fast ones points at that mirror."""
+    print(f"{Colors.CYAN}By host:{Colors.NC}")
+    for host in sorted(history['hosts'], key=lambda h: h['average_bps'] or 0, reverse=True):
+        print(f"  {format_speed(host['average_bps']):>14}  {host['host'] or 'unknown'} ({host['downloads']} downloads)")
+
+    print(f"\n{Colors.CYAN}By session:{Colors.NC}")
+    for session in history['sessions']:
+        print(f"  {format_speed(session['average_bps']):>14}  {session['session']}  {', '.join(session['hosts'])}")
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Downloaded ROM library manager')
+    add_config_argument(parser)
+    parser.add_argument('--read-only', action='store_true', help='Check URLs without storing the results')
+    subparsers = parser.add_subparsers(dest='command', required=True)
+
+    check_parser = subparsers.add_parser('check-urls', help='Check that source URLs still exist')
+    check_parser.add_argument('--all', action='store_true', help='Check every entry, not just those due')
+    check_parser.add_argument('--json', action='store_true', help='Print the results as JSON')
+
+    backup_parser = subparsers.add_parser('backup-priority', help='List entries by backup priority')
+    backup_parser.add_argument('--vanished-only', action='store_true', help='Only list entries whose source vanished')
+    backup_parser.add_argument('--json', action='store_true', help='Print the entries as JSON')
+
+    outdated_parser = subparsers.add_parser('outdated', help='List entries whose source file changed')
+    outdated_parser.add_argument('--json', action='store_true', help='Print the entries as JSON')
+
+    redownload_parser = subparsers.add_parser('redownload', help='Download the current version of outdated entries')
+    redownload_parser.add_argument('ids', nargs='*', type=int, help='Entry ids (see outdated --json)')
+    redownload_parser.add_argument('--all', action='store_true', help='Re-download every outdated entry')
+    redownload_parser.add_argument('--json', action='store_true', help='Print the results as JSON')
+
+    icons_parser = subparsers.add_parser('extract-icons', help='Extract embedded icons from library files')
+    icons_parser.add_argument('--all', action='store_true', help='Rescan entries that already have an icon')
+    icons_parser.add_argument('--json', action='store_true', help='Print the results as JSON')
+
+    explain_parser = subparsers.add_parser('explain', help='Show why a downloaded file was picked for its title')
+    explain_parser.add_argument('path', type=Path, help='Downloaded file')
+    explain_parser.add_argument('--json', action='store_true', help='Print the explanation as JSON')
+
+    speed_parser = subparsers.add_parser('speed-history', help='Show sampled download speeds')
+    speed_parser.add_argument('--days', type=int, default=30, help='How many days back to show (default: 30)')
+    speed_parser.add_argument('--json', action='store_true', help='Print the history as JSON')
+
+    owned_parser = subparsers.add_parser('owned', help='List the files already downloaded, as browse listings match them')
+    owned_parser.add_argument('--json', action='store_true', help='Print the names as JSON')
+
+    missing_parser = subparsers.add_parser('missing', help="List a platform's titles not in the collection yet")
+    missing_parser.add_argument('--platform', required=True,
+                                help='DAT platform, e.g. "Sony - PlayStation", or with --source catalog '
+                                     'the archive folder, e.g. "Redump/Sony - PlayStation"')
+    missing_parser.add_argument('--source', choices=['dat', 'catalog'], default='dat',
+                                help="Compare with the platform's DAT or its archive folder (default: dat)")
+    add_name_filter_arguments(missing_parser)
+    missing_parser.add_argument('--exclude-demos', action='store_true',
+                                help='Leave out demos, samples, kiosk and promo releases')
+    missing_parser.add_argument('--verify', action='store_true',
+                                help='Only count files whose hashes the DATs list (slower, hashes every file)')
+    missing_parser.add_argument('--format', choices=['text', 'json', 'csv'], default='text',
+                                help='Output format (default: text)')
+    missing_parser.add_argument('--output', type=Path, help='Write the report to a file instead of stdout')
+
+    stats_parser = subparsers.add_parser('stats', help='Show how complete each platform is, owned and verified')
+    stats_parser.add_argument('--all', action='store_true', help='Include every platform with a DAT, even empty ones')
+    stats_parser.add_argument('--verify', action='store_true',
+                              help='First check the library files not checked yet against the DATs')
+    stats_parser.add_argument('--json', action='store_true', help='Print the statistics as JSON')
+
+    migrate_parser = subparsers.add_parser('migrate-paths',
+                                           help='Store the absolute paths left in the library relative to their root')
+    migrate_parser.add_argument('--old-root', action='append', default=[], metavar='ID=PATH',
+                                help='Where a root was on the machine the library comes from, '
+                                     'e.g. downloads=C:/Users/me/ROMs/downloads (repeatable)')
+    migrate_parser.add_argument('--json', action='store_true', help='Print the result as JSON')
+
+    clean_parser = subparsers.add_parser('clean-partials',
+                                         help='Remove the partial files interrupted downloads left behind')
+    clean_parser.add_argument('--older-than', type=float, metavar='HOURS',
+                              help='Only remove files not written to for this long '
+                                   '(default: downloads.stale_partial_hours, 24)')
+    clean_parser.add_argument('--dry-run', action='store_true', help='List the files without removing them')
+    clean_parser.add_argument('--json', action='store_true', help='Print the files as JSON')
+
+    delete_parser = subparsers.add_parser('delete', help='Delete games from disk and the library')
+    delete_parser.add_argument('targets', nargs='+', metavar='ID_OR_PATH', help='Library entry ids or file paths')
+    delete_parser.add_argument('--archive', action='store_true',
+                               help='Keep the library entries in the archived entries instead of dropping them')
+    delete_parser.add_argument('--dry-run', action='store_true', help='List what would be removed')
+    delete_parser.add_argument('--yes', action='store_true', help='Do not ask for confirmation')
+    delete_parser.add_argument('--json', action='store_true', help='Print the result as JSON')
+
+    manifest_parser = subparsers.add_parser('manifest', help='Write checksum manifests of the collection folders, '
+                                                             'or check a copy of them against theirs')
+    manifest_parser.add_argument('action', choices=['write', 'verify'])
+    manifest_parser.add_argument('paths', nargs='*', type=Path, metavar='PATH',
+                                 help='Folders, or with verify manifest files too '
+                                      '(default: the download folder and library.rom_directories)')
+    manifest_parser.add_argument('--format', choices=checksum_manifests.FORMATS, default='sha1',
+                                 help='With write, SHA1SUMS (sha1, the default) or <folder>.sfv (CRC32)')
+    manifest_parser.add_argument('--json', action='store_true', help='Print the result as JSON')
+
+    dedupe_parser = subparsers.add_parser('dedupe', help='Find identical files across the collection folders '
+                                                         'and link the copies to reclaim space')
+    dedupe_parser.add_argument('paths', nargs='*', type=Path, metavar='FOLDER',
+                               help='Folders to compare; the first copy found is kept '
+                                    '(default: the download folder and library.rom_directories)')
+    dedupe_parser.add_argument('--link', choices=dedupe.LINK_MODES,
+                               help='Replace the copies with hardlinks (same drive only) or symlinks; '
+                                    'without it the duplicates are only reported')
+    dedupe_parser.add_argument('--dry-run', action='store_true', help='With --link, list what would be linked')
+    dedupe_parser.add_argument('--json', action='store_true', help='Print the result as JSON')
+
+    activity_parser = subparsers.add_parser('activity', help='Show the activity feed, newest first')
+    activity_parser.add_argument('--limit', type=int, default=50, help='How many events to show (default: 50)')
+    activity_parser.add_argument('--json', action='store_true', help='Print the events as JSON')
+
+    args = parser.parse_args()
+
+    if args.read_only:
+        set_read_only()
+
+    manager = LibraryManager()
+
+    if args.command == 'check-urls':
+        results = manager.check_sources(check_all=args.all, progress=not args.json)
+        if args.json:
+            print(json.dumps(results))
+        else:
+            print_summary(results)
+    elif args.command == 'backup-priority':
+        entries = manager.get_backup_priorities()
+        if args.vanished_only:
+            entries = [e for e in entries if e['url_status'] == URL_MISSING]
+        if args.json:
+            print(json.dumps(entries, default=str))
+            return
+        for entry in entries:
+            color = STATUS_COLORS.get(entry['url_status'], Colors.NC)
+            print(f"{color}{entry['url_status'] or 'unchecked':>9}{Colors.NC}  {entry['platform'] or '-'}  {entry['filename']}")
+    elif args.command == 'outdated':
+        entries = manager.db.get_outdated_entries()
+        if args.json:
+            print(json.dumps(entries, default=str))
+            return
+        for entry in entries:
+            print(f"{Colors.YELLOW}{entry['id']:>5}{Colors.NC}  {entry['filename']}  ({entry['outdated_reason']})")
+        if entries:
+            print(f"\nDownload the corrected versions with: library_manager.py redownload --all")
+    elif args.command == 'redownload':
+        outdated = manager.db.get_outdated_entries()
+        entries = outdated if args.all else [e for e in outdated if e['id'] in args.ids]
+        if not entries:
+            print(f"{Colors.YELLOW}No outdated entries to re-download{Colors.NC}", file=sys.stderr)
+            sys.exit(2)
+        # Download progress goes to stderr so --json output stays parseable
+        with contextlib.redirect_stdout(sys.stderr if args.json else sys.stdout):
+            results = [{"id": entry['id'], "filename": entry['filename'], "ok": manager.redownload(entry)}
+                       for entry in entries]
+        if args.json:
+            print(json.dumps(results))
+        failed = [r for r in results if not r['ok']]
+        if not args.json:
+            print(f"\n{Colors.GREEN}Re-downloaded {len(results) - len(failed)} of {len(results)} files{Colors.NC}")
+        sys.exit(1 if failed else 0)
+    elif args.command == 'extract-icons':
+        results = manager.extract_icons(extract_all=args.all, progress=not args.json)
+        if args.json:
+            print(json.dumps(results))
+            return
+        found = sum(1 for r in results if r['icon_path'])
+        print(f"\n{Colors.CYAN}Found icons in {found} of {len(results)} files{Colors.NC}")
+    elif args.command == 'explain':
+        explanation = manager.db.get_match_explanation(args.path)
+        if args.json:
+            print(json.dumps(explanation))
+            return
+        if not explanation:
+            print(f"{Colors.YELLOW}{args.path} was not picked by matching a title{Colors.NC}")
+            sys.exit(1)
+        from rom_downloader import print_match_explanation
+
+        print_match_explanation(explanation)
+    elif args.command == 'owned':
+        keys = sorted(owned_keys(manager.config, manager.db))
+        if args.json:
+            print(json.dumps(keys))
+            return
+        for key in keys:
+            print(key)
+    elif args.command == 'missing':
+        try:
+            report = manager.missing_titles(args.platform, args.source, name_filter_from_args(args),
+                                            exclude_demos=args.exclude_demos, verify=args.verify,
+                                            progress=args.format == 'text' and not args.output)
+        except ValueError as e:
+            fail(f"{Colors.RED}{e}{Colors.NC}", e, code=2)
+        if args.output:
+            with open(args.output, 'w', encoding='utf-8', newline='') as out:
+                write_missing_report(report, args.format, out)
+            print(f"{len(report['missing'])} missing titles written to {args.output}", file=sys.stderr)
+        else:
+            write_missing_report(report, args.format, sys.stdout)
+    elif args.command == 'stats':
+        stats = manager.collection_stats(include_all=args.all, verify=args.verify, progress=not args.json)
+        if args.json:
+            print(json.dumps(stats))
+            return
+        print_collection_stats(stats)
+    elif args.command == 'migrate-paths':
+        old_roots = {}
+        for value in args.old_root:
+            root_id, separator, path = value.partition('=')
+            if not separator or not root_id or not path:
+                parser.error(f"--old-root takes ID=PATH, not {value}")
+            old_roots[root_id] = path
+        try:
+            result = manager.db.migrate_paths(old_roots)
+        except ReadOnlyError as e:
+            fail(f"{Colors.RED}{e}{Colors.NC}", e)
+        if args.json:
+            print(json.dumps(result))
+            return
+        print(f"{Colors.GREEN}Converted {result['converted']} paths{Colors.NC}")
+        if result['unplaced']:
+            print(f"\n{Colors.YELLOW}Under no root (add it to paths.roots, or give --old-root):{Colors.NC}")
+            for path in result['unplaced']:
+                print(f"  {path}")
+    elif args.command == 'clean-partials':
+        hours = args.older_than if args.older_than is not None else manager.config.get('downloads.stale_partial_hours', 24)
+        try:
+            partials = manager.clean_partials(timedelta(hours=hours), dry_run=args.dry_run)
+        except ReadOnlyError as e:
+            fail(f"{Colors.RED}{e}{Colors.NC}", e)
+        if args.json:
+            print(json.dumps(partials))
+            return
+        for partial in partials:
+            print(f"{partial['modified']}  {format_size(partial['size']):>10}  {partial['path']}")
+        total = format_size(sum(partial['size'] for partial in partials))
+        verb = "Would remove" if args.dry_run else "Removed"
+        print(f"{Colors.GREEN}{verb} {len(partials)} partial files ({total}){Colors.NC}")
+    elif args.command == 'delete':
+        try:
+            if not args.yes and not args.dry_run:
+                # Ask with the files in front of the user; without a terminal --yes is required
+                preview = manager.delete_games(args.targets, dry_run=True)['deleted']
+                if not sys.stdin.isatty():
+                    raise ValueError("Refusing to delete without --yes when not run from a terminal")
+                for result in preview:
+                    print(f"  {result['path']}")
+                answer = input(f"{Colors.YELLOW}Delete these {len(preview)} files? (y/N): {Colors.NC}").strip().lower()
+                if answer not in ('y', 'yes'):
+                    return
+            result = manager.delete_games(args.targets, archive=args.archive, dry_run=args.dry_run)
+        except ValueError as e:
+            fail(f"{Colors.RED}{e}{Colors.NC}", e, code=2)
+        except ReadOnlyError as e:
+            fail(f"{Colors.RED}{e}{Colors.NC}", e)
+        if args.json:
+            print(json.dumps(result))
+            return
+        for deleted in result['deleted']:
+            extras = [extra for extra in deleted['removed'] if extra != deleted['path']]
+            print(deleted['path'] + "".join(f"\n    + {extra}" for extra in extras))
+        total = format_size(sum(deleted['size'] for deleted in result['deleted']))
+        verb = "Would delete" if args.dry_run else "Deleted"
+        print(f"{Colors.GREEN}{verb} {len(result['deleted'])} files ({total}){Colors.NC}")
+    elif args.command == 'manifest':
+        paths = args.paths or manager.manifest_folders()
+        missing = [str(path) for path in paths if not path.exists()]
+        if missing or not paths:
+            print(f"{Colors.RED}No such folder: {', '.join(missing)}{Colors.NC}" if missing
+                  else f"{Colors.RED}No collection folders to cover{Colors.NC}", file=sys.stderr)
+            sys.exit(2)
+        try:
+            if args.action == 'write':
+                results = manager.write_manifests(paths, args.format, progress=not args.json)
+            else:
+                results = manager.verify_manifests(paths, progress=not args.json)
+        except ValueError as e:
+            fail(f"{Colors.RED}{e}{Colors.NC}", e, code=2)
+        except (ReadOnlyError, OSError) as e:
+            fail(f"{Colors.RED}{e}{Colors.NC}", e)
+        if args.json:
+            print(json.dumps(results))
+        elif args.action == 'write':
+            for result in results:
+                print(f"{Colors.GREEN}Wrote {result['path']}: {result['files']} files "
+                      f"({format_size(result['bytes'])}){Colors.NC}")
+        else:
+            print_manifest_reports(results)
+        if args.action == 'verify' and any(report['counts'].get(status) for report in results
+                                           for status in ("mismatch", "missing", "unreadable")):
+            sys.exit(1)
+    elif args.command == 'dedupe':
+        paths = args.paths or manager.manifest_folders()
+        missing = [str(path) for path in paths if not path.is_dir()]
+        if missing or not paths:
+            print(f"{Colors.RED}No such folder: {', '.join(missing)}{Colors.NC}" if missing
+                  else f"{Colors.RED}No collection folders to compare{Colors.NC}", file=sys.stderr)
+            sys.exit(2)
+        try:
+            result = manager.dedupe(paths, args.link, dry_run=args.dry_run, progress=not args.json)
+        except (ReadOnlyError, OSError) as e:
+            fail(f"{Colors.RED}{e}{Colors.NC}", e)
+        if args.json:
+            print(json.dumps(result))
+        else:
+            print_dedupe_report(result, dry_run=args.dry_run)
+    elif args.command == 'activity':
+        events = manager.db.get_activity(args.limit)
+        if args.json:
+            print(json.dumps(events))
+            return
+        for event in events:
+            print(f"{event['at'][:19]}  {Colors.CYAN}{event['action']:<10}{Colors.NC} {event['summary']}")
+    elif args.command == 'speed-history':
+        history = manager.db.get_speed_history(args.days)
+        if args.json:
+            print(json.dumps(history))
+            return
+        print_speed_history(history)
+
+
+if __name__ == "__main__":
+    install_error_reporting()
+    main()
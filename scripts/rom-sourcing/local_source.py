@@ -0,0 +1,109 @@
+#!/usr/bin/env python3
+"""
+Local Source
+Command line access to local ROM folders (rom_sources.LocalSource), such as a
+mounted NAS share, so they can be browsed like the remote archive. The folders
+are listed under sources.local.directories in rom_browser.json.
+"""
+
+import argparse
+import json
+import sys
+from pathlib import Path
+from typing import Dict, List, Optional
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from names import add_name_filter_arguments, filter_entries, name_fields, name_filter_from_args
+from rom_config import RomConfig, add_config_argument
+from rom_sources import LocalSource, format_size
+from script_errors import fail, install_error_reporting
+
+
+class Colors:
+    """ANSI color codes for terminal output."""
+    RED = '\033[0;31m'
+    GREEN = '\033[0;32m'
+    YELLOW = '\033[1;33m'
+    CYAN = '\033[0;36m'
+    NC = '\033[0m'  # No Color
+
+
+def list_files(source: LocalSource, path: str, search: Optional[str] = None, recursive=False) -> List[Dict]:
+    """List the files of a local directory, optionally filtered by a case-insensitive substring.
+
+    Each file carries its containing folder as "platform", matching how the
+    archive is laid out.
+    """
+    entries = source.walk(path) if recursive else [e for e in source.list_dir(path) if not e['is_dir']]
+    if search:
+        entries = [e for e in entries if search.lower() in e['name'].lower()]
+
+    files = []
+    for entry in entries:
+        files.append({
+            "name": entry['name'],
+            "path": entry['path'],
+            "platform": entry['path'].rsplit('/', 2)[-2],
+            "size": entry['size'],
+            "size_text": format_size(entry['size']),
+            "modified": entry.get('modified'),
+            "url": source.file_url(entry['path']),
+            **name_fields(entry['name'])
+        })
+    return files
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Browse local ROM folders')
+    add_config_argument(parser)
+    subparsers = parser.add_subparsers(dest='command', required=True)
+
+    roots_parser = subparsers.add_parser('roots', help='List the configured folders')
+    roots_parser.add_argument('--json', action='store_true', help='Print the folders as JSON')
+
+    dirs_parser = subparsers.add_parser('dirs', help='List the subfolders of a folder')
+    dirs_parser.add_argument('path', help='Source path: the folder name, then any subfolders')
+    dirs_parser.add_argument('--json', action='store_true', help='Print the folders as JSON')
+
+    files_parser = subparsers.add_parser('files', help='List the files of a folder')
+    files_parser.add_argument('path', help='Source path: the folder name, then any subfolders')
+    files_parser.add_argument('--search', help='Only list files whose name contains this text')
+    files_parser.add_argument('--recursive', action='store_true', help='Include the files of subfolders')
+    files_parser.add_argument('--json', action='store_true', help='Print the files as JSON')
+    add_name_filter_arguments(files_parser)
+
+    args = parser.parse_args()
+
+    source = LocalSource(RomConfig())
+
+    try:
+        if args.command == 'roots':
+            roots = source.get_roots()
+            if args.json:
+                print(json.dumps(roots))
+                return
+            for root in roots:
+                print(f"{Colors.CYAN}{root['name']}{Colors.NC}  {root['path']}")
+        elif args.command == 'dirs':
+            directories = [e for e in source.list_dir(args.path) if e['is_dir']]
+            if args.json:
+                print(json.dumps(directories))
+                return
+            for entry in directories:
+                print(entry['path'])
+        elif args.command == 'files':
+            files = filter_entries(list_files(source, args.path, args.search, args.recursive),
+                                   name_filter_from_args(args))
+            if args.json:
+                print(json.dumps(files))
+                return
+            for info in files:
+                print(f"{info['size_text'] or '-':>10}  {info['path']}")
+            print(f"\n{Colors.CYAN}{len(files)} files in {args.path}{Colors.NC}")
+    except (OSError, ValueError) as e:
+        fail(f"{Colors.RED}Local source error: {e}{Colors.NC}", e)
+
+
+if __name__ == "__main__":
+    install_error_reporting()
+    main()
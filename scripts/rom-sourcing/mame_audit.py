@@ -0,0 +1,141 @@
+#!/usr/bin/env python3
+"""
+MAME Audit
+Parent/clone views and completeness checks for MAME ROM sets, using the MAME
+DATs (Logiqx or -listxml) in the DAT directory. Lists a folder or archive
+listing with clones grouped under their parents, tells which parent and BIOS
+sets a set needs, and checks set ZIPs hold every ROM the DAT lists for the
+chosen set type (split, non-merged or merged).
+
+Exit codes: 0 when every set checked is complete, 1 otherwise.
+"""
+
+import argparse
+import json
+import sys
+import zipfile
+from pathlib import Path
+from typing import Dict, List
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from mame_sets import SET_TYPES, MameSets
+from rom_config import RomConfig, add_config_argument
+from romignore import RomIgnore
+
+
+class Colors:
+    """ANSI color codes for terminal output."""
+    RED = '\033[0;31m'
+    GREEN = '\033[0;32m'
+    YELLOW = '\033[1;33m'
+    CYAN = '\033[0;36m'
+    NC = '\033[0m'  # No Color
+
+
+def log(message: str):
+    print(message, file=sys.stderr)
+
+
+def list_sets(location: str, config: RomConfig) -> List[str]:
+    """List the set files of a local folder or an archive URL."""
+    if location.startswith(('http://', 'https://')):
+        from rom_downloader import ROMDownloader
+
+        return ROMDownloader(config).list_platform_files(location if location.endswith('/') else location + '/')
+    ignore = RomIgnore()
+    return sorted(path.name for path in Path(location).iterdir()
+                  if path.suffix.lower() in ('.zip', '.7z') and not ignore.matches(path, False))
+
+
+def set_paths(paths: List[Path]) -> List[Path]:
+    """Expand directories into the set ZIPs inside them, leaving out .romignore matches."""
+    ignore, expanded = RomIgnore(), []
+    for path in paths:
+        if path.is_dir():
+            expanded.extend(p for p in sorted(path.glob('*.zip')) if not ignore.matches(p, False))
+        else:
+            expanded.append(path)
+    return expanded
+
+
+def print_result(result: Dict):
+    colors = {"complete": Colors.GREEN, "incomplete": Colors.RED, "unknown": Colors.YELLOW}
+    print(f"{colors[result['status']]}{result['status']:>10}{Colors.NC}  {result['name']}")
+    for name in result['missing']:
+        print(f"            missing {name}")
+    for name in result['wrong']:
+        print(f"            wrong CRC {name}")
+    for name in result['extra']:
+        print(f"            extra {name}")
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Group and verify MAME ROM sets')
+    add_config_argument(parser)
+    parser.add_argument('--dat-dir', type=Path, help='DAT directory (default: paths.dat_directory)')
+    subparsers = parser.add_subparsers(dest='command', required=True)
+
+    group_parser = subparsers.add_parser('group', help='List sets with clones under their parents')
+    group_parser.add_argument('location', help='Local folder or archive URL')
+    group_parser.add_argument('--json', action='store_true', help='Print the groups as JSON')
+
+    requires_parser = subparsers.add_parser('requires', help='Show the parent and BIOS sets a set needs')
+    requires_parser.add_argument('name', help='Set name, e.g. sf2ce')
+    requires_parser.add_argument('--set-type', choices=SET_TYPES, help='Set type (default: mame.set_type)')
+    requires_parser.add_argument('--json', action='store_true', help='Print the requirements as JSON')
+
+    verify_parser = subparsers.add_parser('verify', help='Check set ZIPs hold every ROM the DAT lists')
+    verify_parser.add_argument('paths', nargs='+', type=Path, help='Set ZIPs or folders of them')
+    verify_parser.add_argument('--set-type', choices=SET_TYPES, help='Set type (default: mame.set_type)')
+    verify_parser.add_argument('--json', action='store_true', help='Print the results as JSON')
+
+    args = parser.parse_args()
+
+    config = RomConfig()
+    dat_dir = args.dat_dir or config.get_path('paths.dat_directory')
+    mame = MameSets()
+    if not mame.load_directory(dat_dir, log=log):
+        log(f"{Colors.RED}No MAME DATs found in {dat_dir}{Colors.NC}")
+        sys.exit(1)
+    set_type = getattr(args, 'set_type', None) or config.get('mame.set_type', 'split')
+
+    try:
+        if args.command == 'group':
+            groups = mame.group(list_sets(args.location, config))
+            if args.json:
+                print(json.dumps(groups))
+                return
+            for group in groups:
+                note = mame.describe(group['parent'])
+                print(group['parent'] + (f"  {Colors.YELLOW}({note}){Colors.NC}" if note else ""))
+                for clone in group['clones']:
+                    print(f"    {Colors.CYAN}{clone}{Colors.NC}")
+        elif args.command == 'requires':
+            if not mame.get(args.name):
+                log(f"{Colors.RED}{args.name} is not in the MAME DATs{Colors.NC}")
+                sys.exit(1)
+            required = mame.requirements(args.name, set_type)
+            if args.json:
+                print(json.dumps(required))
+                return
+            if not required:
+                print(f"{args.name} runs on its own as a {set_type} set")
+            for entry in required:
+                print(f"{entry['name']}  ({entry['reason']})")
+        elif args.command == 'verify':
+            results = [mame.verify(path, set_type) for path in set_paths(args.paths)]
+            if args.json:
+                print(json.dumps(results))
+            else:
+                for result in results:
+                    print_result(result)
+                complete = sum(1 for r in results if r['status'] == "complete")
+                print(f"\n{Colors.CYAN}{complete} of {len(results)} sets complete ({set_type}){Colors.NC}")
+            sys.exit(0 if all(r['status'] == "complete" for r in results) else 1)
+    except (OSError, ValueError, zipfile.BadZipFile) as e:
+        log(f"{Colors.RED}MAME audit error: {e}{Colors.NC}")
+        sys.exit(1)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,316 @@
+#!/usr/bin/env python3
+"""
+Queue Templates
+Turns community-curated lists ("Top 100 SNES games") into named queue
+templates: each title is matched against the archive listing of its platform,
+the matches are reviewed, and the accepted ones are added to the download
+queue.
+
+A list is plain text. Platform headings are lines starting with '#', wrapped
+in [brackets] or ending with ':'; every other non-empty line is a title, with
+any "1." or "-" list marker stripped:
+
+    # Top SNES Games
+    1. Super Metroid
+    2. Chrono Trigger
+
+    [Sega - Mega Drive - Genesis]
+    - Sonic the Hedgehog 2
+"""
+
+import argparse
+import json
+import re
+import sys
+import urllib.parse
+from datetime import datetime
+from pathlib import Path
+from typing import Dict, List, Optional
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from download_queue import open_queue
+from filter_presets import FilterPresets, keep_file
+from rom_config import ReadOnlyError, RomConfig, add_config_argument, set_read_only
+from session_filter import load_session_filter
+
+from rom_downloader import ROMDownloader
+
+# Entry states; only accepted entries are queued
+PENDING = "pending"
+ACCEPTED = "accepted"
+REJECTED = "rejected"
+UNMATCHED = "unmatched"
+
+HEADING_PATTERN = re.compile(r'^\s*(?:#+\s*(?P<hash>.+?)|\[(?P<bracket>.+)\]|(?P<colon>[^:]+):)\s*$')
+LIST_MARKER_PATTERN = re.compile(r'^\s*(?:\d+[.)]|[-*•])\s*')
+
+
+class Colors:
+    """ANSI color codes for terminal output."""
+    RED = '\033[0;31m'
+    GREEN = '\033[0;32m'
+    YELLOW = '\033[1;33m'
+    CYAN = '\033[0;36m'
+    NC = '\033[0m'  # No Color
+
+
+def parse_list(text: str) -> List[Dict]:
+    """Parse a curated list into {title, heading} entries; entries before any heading have none."""
+    entries, heading = [], None
+    for line in text.splitlines():
+        if not line.strip():
+            continue
+        match = HEADING_PATTERN.match(line)
+        if match:
+            heading = (match.group('hash') or match.group('bracket') or match.group('colon')).strip()
+            continue
+        title = LIST_MARKER_PATTERN.sub('', line).strip()
+        if title:
+            entries.append({"title": title, "heading": heading})
+    return entries
+
+
+class QueueTemplates:
+    def __init__(self, config: Optional[RomConfig] = None, downloader: Optional[ROMDownloader] = None):
+        self.config = config or RomConfig()
+        self.directory = self.config.get_path('paths.queue_templates')
+        self.downloader = downloader or ROMDownloader(self.config)
+        self.listings = {}
+
+    def template_path(self, name: str) -> Path:
+        return self.directory / f"{re.sub(r'[^A-Za-z0-9._-]+', '_', name)}.json"
+
+    def list_templates(self) -> List[Dict]:
+        if not self.directory.exists():
+            return []
+        return [self.load(path.stem) for path in sorted(self.directory.glob('*.json'))]
+
+    def load(self, name: str) -> Dict:
+        path = self.template_path(name)
+        if not path.exists():
+            raise ValueError(f"No queue template named {name}")
+        with open(path, 'r', encoding='utf-8') as f:
+            return json.load(f)
+
+    def save(self, template: Dict):
+        if self.config.is_read_only():
+            return
+        self.directory.mkdir(parents=True, exist_ok=True)
+        with open(self.template_path(template['name']), 'w', encoding='utf-8') as f:
+            json.dump(template, f, indent=2)
+
+    def resolve_platform(self, heading: Optional[str], default: Optional[str]) -> Optional[str]:
+        """Get the archive folder for a heading; headings that already look like one are used as-is."""
+        if heading:
+            platform = self.downloader.resolve_platform(heading)
+            if platform:
+                return platform
+            # Archive folder names, or TOSEC paths like "Commodore/Amiga/Games"
+            if ' - ' in heading or '/' in heading:
+                return heading
+        return default
+
+    def platform_listing(self, platform: str, datasets: List[str]) -> Dict:
+        """List a platform folder in the first dataset that has it: {url, files}."""
+        if platform not in self.listings:
+            self.listings[platform] = {"url": None, "files": []}
+            for dataset in datasets:
+                url = f"{self.config.get_archive_url()}{dataset}/{urllib.parse.quote(platform)}/"
+                files = self.downloader.list_platform_files(url)
+                if files:
+                    self.listings[platform] = {"url": url, "files": files}
+                    break
+        return self.listings[platform]
+
+    def match_entry(self, entry: Dict, datasets: List[str], presets: Optional[List[Dict]] = None):
+        """Match an entry's title against its platform listing, storing the candidates and best match.
+
+        With filter presets, only the files they keep can be candidates.
+        """
+        entry.update({"candidates": [], "match": None, "url": None, "platform_url": None, "status": UNMATCHED})
+        if not entry['platform']:
+            return
+
+        listing = self.platform_listing(entry['platform'], datasets)
+        files = [f for f in listing['files'] if keep_file(f, presets)] if presets else listing['files']
+        title = self.downloader.clean_title(entry['title'])
+        candidates = self.downloader.search_game(title, files)
+        best = self.downloader.select_best_match(title, candidates)
+        if best:
+            entry.update({"candidates": candidates, "platform_url": listing['url'], "status": PENDING})
+            self.set_match(entry, best)
+
+    def set_match(self, entry: Dict, filename: str):
+        source = self.downloader.source
+        entry['match'] = filename
+        entry['url'] = source.file_url(f"{source.path_of(entry['platform_url']) or ''}{filename}")
+        entry['warnings'] = self.downloader.compatibility.check(filename, entry['platform'])
+
+    def import_list(self, list_path: Path, name: str, platform: Optional[str] = None,
+                    datasets: Optional[List[str]] = None, preset_names: Optional[List[str]] = None) -> Dict:
+        """Create a template from a curated list, matching every title within the named filter presets."""
+        datasets = datasets or ["No-Intro", "Redump"]
+        default_platform = self.downloader.resolve_platform(platform) if platform else None
+        entries = parse_list(list_path.read_text(encoding='utf-8'))
+        filter_presets = FilterPresets(self.config)
+        presets = [filter_presets.load(preset) for preset in preset_names or []]
+
+        for entry in entries:
+            entry['platform'] = self.resolve_platform(entry['heading'], default_platform)
+            self.match_entry(entry, datasets, presets)
+
+        template = {
+            "name": name,
+            "source": str(list_path),
+            "presets": [preset['name'] for preset in presets],
+            "created": datetime.now().isoformat(timespec='seconds'),
+            "entries": entries
+        }
+        self.save(template)
+        return template
+
+    def review(self, template: Dict, accept_all=False) -> Dict:
+        """Confirm or correct each pending match, interactively unless accept_all is set."""
+        pending = [e for e in template['entries'] if e['status'] == PENDING]
+        for i, entry in enumerate(pending, 1):
+            if accept_all:
+                entry['status'] = ACCEPTED
+                continue
+
+            print(f"\n{Colors.CYAN}[{i}/{len(pending)}] {entry['title']}{Colors.NC} ({entry['platform']})")
+            for n, candidate in enumerate(entry['candidates'], 1):
+                marker = '*' if candidate == entry['match'] else ' '
+                print(f"  {marker}{n:2d}. {candidate}")
+            for warning in entry.get('warnings', []):
+                print(f"      {Colors.YELLOW}! {warning}{Colors.NC}")
+            choice = input(f"{Colors.CYAN}Enter to accept *, a number to pick, r to reject, s to skip, q to stop: {Colors.NC}").strip().lower()
+
+            if choice == 'q':
+                break
+            elif choice == 's':
+                continue
+            elif choice == 'r':
+                entry['status'] = REJECTED
+            elif choice.isdigit() and 1 <= int(choice) <= len(entry['candidates']):
+                self.set_match(entry, entry['candidates'][int(choice) - 1])
+                entry['status'] = ACCEPTED
+            elif not choice:
+                entry['status'] = ACCEPTED
+            else:
+                print(f"{Colors.RED}Invalid choice, skipped{Colors.NC}")
+
+        self.save(template)
+        return template
+
+    def queue(self, template: Dict) -> int:
+        """Append the accepted matches the session filter keeps to the download queue. Returns how many were added."""
+        self.config.ensure_writable("add to the download queue")
+        accepted = [e for e in template['entries'] if e['status'] == ACCEPTED]
+        session = load_session_filter(self.config)
+        if not session.is_empty():
+            # Per platform, so one game on two platforms is not taken for a clone
+            platforms: Dict[str, List[str]] = {}
+            for entry in accepted:
+                platforms.setdefault(entry['platform'], []).append(entry['match'])
+            kept = {(platform, name) for platform, names in platforms.items()
+                    for name in session.keep_names(names, self.config)}
+            accepted = [e for e in accepted if (e['platform'], e['match']) in kept]
+        entries = [(e['url'], e.get('warnings', [])) for e in accepted]
+        return open_queue(self.config).add(str(self.downloader.queue_file.resolve()), entries)
+
+
+def count_statuses(template: Dict) -> Dict:
+    counts = {PENDING: 0, ACCEPTED: 0, REJECTED: 0, UNMATCHED: 0}
+    for entry in template['entries']:
+        counts[entry['status']] += 1
+    return counts
+
+
+def print_template(template: Dict):
+    colors = {ACCEPTED: Colors.GREEN, PENDING: Colors.CYAN, REJECTED: Colors.YELLOW, UNMATCHED: Colors.RED}
+    for entry in template['entries']:
+        color = colors.get(entry['status'], Colors.NC)
+        print(f"{color}{entry['status']:>9}{Colors.NC}  {entry['title']}  ->  {entry['match'] or '-'}")
+        for warning in entry.get('warnings', []):
+            print(f"           {Colors.YELLOW}! {warning}{Colors.NC}")
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Build download queues from curated title lists')
+    add_config_argument(parser)
+    parser.add_argument('--read-only', action='store_true', help='Match and show templates without saving')
+    subparsers = parser.add_subparsers(dest='command', required=True)
+
+    import_parser = subparsers.add_parser('import', help='Create a template from a curated list')
+    import_parser.add_argument('list', help='Text file with platform headings and titles')
+    import_parser.add_argument('--name', help='Template name (default: the file name)')
+    import_parser.add_argument('--platform', help='Platform for titles before the first recognised heading')
+    import_parser.add_argument('--dataset', choices=['redump', 'no-intro', 'tosec'],
+                               help='Only look in one dataset (default: No-Intro, then Redump)')
+    import_parser.add_argument('--preset', action='append', metavar='NAME',
+                               help='Only match files this filter preset keeps, repeatable (see rom_presets.py)')
+
+    review_parser = subparsers.add_parser('review', help='Confirm or correct the matches of a template')
+    review_parser.add_argument('name', help='Template name')
+    review_parser.add_argument('--accept-all', action='store_true', help='Accept every pending match')
+
+    show_parser = subparsers.add_parser('show', help='Show the entries of a template')
+    show_parser.add_argument('name', help='Template name')
+    show_parser.add_argument('--json', action='store_true', help='Print the template as JSON')
+
+    list_parser = subparsers.add_parser('list', help='List the saved templates')
+    list_parser.add_argument('--json', action='store_true', help='Print the templates as JSON')
+
+    queue_parser = subparsers.add_parser('queue', help='Add the accepted matches to the download queue')
+    queue_parser.add_argument('name', help='Template name')
+
+    args = parser.parse_args()
+
+    if args.read_only:
+        set_read_only()
+
+    templates = QueueTemplates()
+
+    try:
+        if args.command == 'import':
+            datasets = {'redump': ["Redump"], 'no-intro': ["No-Intro"], 'tosec': ["TOSEC"]}.get(args.dataset)
+            list_path = Path(args.list)
+            template = templates.import_list(list_path, args.name or list_path.stem, args.platform, datasets,
+                                             args.preset)
+            counts = count_statuses(template)
+            print(f"\n{Colors.GREEN}Matched {counts[PENDING]} of {len(template['entries'])} titles{Colors.NC}")
+            if counts[UNMATCHED]:
+                print(f"{Colors.YELLOW}{counts[UNMATCHED]} titles had no match; see 'show {template['name']}'{Colors.NC}")
+            print(f"Review the matches with: queue_templates.py review {template['name']}")
+        elif args.command == 'review':
+            template = templates.review(templates.load(args.name), accept_all=args.accept_all)
+            counts = count_statuses(template)
+            print(f"\n{Colors.GREEN}{counts[ACCEPTED]} accepted{Colors.NC}, {counts[PENDING]} pending, "
+                  f"{counts[REJECTED]} rejected, {counts[UNMATCHED]} unmatched")
+        elif args.command == 'show':
+            template = templates.load(args.name)
+            if args.json:
+                print(json.dumps(template))
+                return
+            print_template(template)
+        elif args.command == 'list':
+            saved = [dict(name=t['name'], source=t['source'], created=t['created'], **count_statuses(t))
+                     for t in templates.list_templates()]
+            if args.json:
+                print(json.dumps(saved))
+                return
+            for t in saved:
+                print(f"{Colors.CYAN}{t['name']}{Colors.NC}  {t[ACCEPTED]} accepted, {t[PENDING]} pending  ({t['source']})")
+        elif args.command == 'queue':
+            added = templates.queue(templates.load(args.name))
+            session = load_session_filter(templates.config)
+            if not session.is_empty():
+                print(f"{Colors.YELLOW}Session filter: {session.describe()}{Colors.NC}")
+            print(f"{Colors.GREEN}Added {added} files to the download queue{Colors.NC}")
+    except (OSError, ValueError, ReadOnlyError) as e:
+        print(f"{Colors.RED}Queue template error: {e}{Colors.NC}", file=sys.stderr)
+        sys.exit(1)
+
+
+if __name__ == "__main__":
+    main()
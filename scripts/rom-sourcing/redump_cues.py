@@ -0,0 +1,332 @@
+#!/usr/bin/env python3
+"""
+Redump Cues
+Fetches the cue sheets of disc images that were kept without them. Loose
+.bin/.iso/.img files are identified by their hashes against the Redump DATs
+in the DAT directory; the tracks of each disc are grouped, and the disc's cue
+sheet is taken from the cue pack Redump publishes for its system and written
+next to the tracks. Tracks renamed since download keep their names: the FILE
+lines of the cue are pointed at them.
+
+Cue packs are kept in the cache directory for cache.cue_pack_days. The packs
+come from sources.redump.cue_url; sources.redump.systems maps more DAT system
+names to Redump's short system names.
+"""
+
+import argparse
+import io
+import json
+import os
+import re
+import sys
+import time
+import zipfile
+from collections import OrderedDict
+from pathlib import Path
+from typing import Dict, List, Optional
+
+import requests
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from dat_index import DatIndex, hash_file
+from job_limits import JobLimits
+from job_progress import ProgressTracker, default_reporter
+from retry_policy import RetryPolicy
+from rom_config import RomConfig, add_config_argument
+from romignore import RomIgnore
+
+
+class Colors:
+    """ANSI color codes for terminal output."""
+    RED = '\033[0;31m'
+    GREEN = '\033[0;32m'
+    YELLOW = '\033[1;33m'
+    CYAN = '\033[0;36m'
+    NC = '\033[0m'  # No Color
+
+
+DEFAULT_CUE_URL = "http://redump.org/cues/"
+
+# DAT system names -> the short names in Redump's cue pack URLs
+REDUMP_SYSTEMS = {
+    "Sony - PlayStation": "psx",
+    "Sony - PlayStation 2": "ps2",
+    "Sega - Saturn": "ss",
+    "Sega - Mega CD & Sega CD": "mcd",
+    "Sega - Dreamcast": "dc",
+    "NEC - PC Engine CD & TurboGrafx CD": "pce",
+    "NEC - PC-FX & PC-FXGA": "pc-fx",
+    "Panasonic - 3DO Interactive Multiplayer": "3do",
+    "Philips - CD-i": "cdi",
+    "SNK - Neo Geo CD": "ngcd",
+    "Atari - Jaguar CD Interactive Multimedia System": "ajcd",
+    "Commodore - Amiga CD32": "cd32",
+    "Commodore - Amiga CDTV": "cdtv",
+    "Fujitsu - FM-Towns": "fmt",
+    "IBM - PC compatible": "pc",
+}
+
+DISC_IMAGE_SUFFIXES = ('.bin', '.iso', '.img')
+
+# Result statuses, per disc (or per file for files no DAT knows)
+STATUSES = ("written", "would-write", "has-cue", "missing-tracks", "no-cue", "unsupported", "unknown", "failed")
+STATUS_COLORS = {
+    "written": Colors.GREEN,
+    "would-write": Colors.CYAN,
+    "has-cue": Colors.NC,
+    "missing-tracks": Colors.YELLOW,
+    "no-cue": Colors.YELLOW,
+    "unsupported": Colors.YELLOW,
+    "unknown": Colors.RED,
+    "failed": Colors.RED
+}
+
+CUE_FILE_LINE = re.compile(r'^(\s*FILE\s+)"([^"]+)"(.*)$', re.DOTALL)
+
+
+def log(message: str):
+    print(message, file=sys.stderr)
+
+
+def cue_file_names(text: str) -> List[str]:
+    """Get the track files a cue sheet refers to."""
+    names = []
+    for line in text.splitlines():
+        match = CUE_FILE_LINE.match(line)
+        if match:
+            names.append(match.group(2))
+    return names
+
+
+def rename_cue_files(text: str, renames: Dict[str, str]) -> str:
+    """Point the FILE lines of a cue sheet at other file names, keeping its line endings."""
+    lines = []
+    for line in text.splitlines(keepends=True):
+        match = CUE_FILE_LINE.match(line)
+        if match and match.group(2) in renames:
+            line = f'{match.group(1)}"{renames[match.group(2)]}"{match.group(3)}'
+        lines.append(line)
+    return "".join(lines)
+
+
+def decode_cue(data: bytes) -> str:
+    # Bytes that are not UTF-8 survive the round trip through encode_cue unchanged
+    return data.decode('utf-8', 'surrogateescape')
+
+
+def encode_cue(text: str) -> bytes:
+    return text.encode('utf-8', 'surrogateescape')
+
+
+def write_cue(path: Path, text: str):
+    """Write a cue sheet in one step. Bytes, so the CRLF line endings Redump uses stay as they are."""
+    temp_path = path.with_name(path.name + ".tmp")
+    with open(temp_path, 'wb') as f:
+        f.write(encode_cue(text))
+    os.replace(temp_path, path)
+
+
+class CueFetcher:
+    def __init__(self, config: Optional[RomConfig] = None, dat_dir: Optional[Path] = None, online: bool = True):
+        self.config = config or RomConfig()
+        self.online = online
+        self.cue_url = (self.config.get('sources.redump.cue_url') or DEFAULT_CUE_URL).rstrip('/') + '/'
+        self.systems = dict(REDUMP_SYSTEMS, **(self.config.get('sources.redump.systems') or {}))
+        self.pack_days = float(self.config.get('cache.cue_pack_days', 30) or 0)
+        self.pack_dir = self.config.get_path('paths.cache_directory') / "cues"
+        self.retry = RetryPolicy.from_config(self.config, log=log)
+        self.packs: Dict[str, Optional[Dict[str, bytes]]] = {}
+
+        self.dat_dir = dat_dir or self.config.get_path('paths.dat_directory')
+        self.index = DatIndex()
+        self.dat_count = self.index.load_directory(self.dat_dir, log=log) if self.dat_dir else 0
+        self.limits = JobLimits(self.config)
+
+    def cue_pack(self, system: str) -> Optional[Dict[str, bytes]]:
+        """Get the cue sheets of a Redump system by file name, from the cache or redump.org.
+
+        Returns None when the pack can be neither read from the cache nor downloaded.
+        """
+        if system in self.packs:
+            return self.packs[system]
+
+        path = self.pack_dir / f"{system}.zip"
+        stale = not path.exists() or time.time() - path.stat().st_mtime > self.pack_days * 86400
+        if stale and self.online:
+            try:
+                response = self.retry.get(self.cue_url + system + "/")
+                response.raise_for_status()
+                if not zipfile.is_zipfile(io.BytesIO(response.content)):
+                    raise ValueError("the response is not a ZIP archive")
+                if self.config.is_read_only():
+                    self.packs[system] = self.read_pack(io.BytesIO(response.content))
+                    return self.packs[system]
+                self.pack_dir.mkdir(parents=True, exist_ok=True)
+                temp_path = path.with_suffix('.zip.part')
+                temp_path.write_bytes(response.content)
+                os.replace(temp_path, path)
+            except (requests.RequestException, ValueError, OSError) as e:
+                log(f"Could not download the {system} cue pack: {e}"
+                    + ("; using the cached one" if path.exists() else ""))
+
+        self.packs[system] = self.read_pack(path) if path.exists() else None
+        return self.packs[system]
+
+    @staticmethod
+    def read_pack(source) -> Dict[str, bytes]:
+        with zipfile.ZipFile(source) as archive:
+            return {Path(info.filename).name: archive.read(info)
+                    for info in archive.infolist() if info.filename.lower().endswith('.cue')}
+
+    def identify(self, paths: List[Path], show_progress: bool = True) -> List[Dict]:
+        """Hash the disc images below the paths and match them against the DATs, one entry per file."""
+        ignore = RomIgnore()
+        files = []
+        for path in paths:
+            if not path.exists():
+                log(f"Not found: {path}")
+                continue
+            candidates = ignore.walk(path) if path.is_dir() else [path]
+            files.extend(f for f in candidates if f.suffix.lower() in DISC_IMAGE_SUFFIXES)
+
+        progress = ProgressTracker("scan", total=sum(f.stat().st_size for f in files), unit="bytes",
+                                   label="Hashing disc images", reporter=default_reporter(show_progress))
+        def hash_image(path):
+            try:
+                return path, hash_file(path, self.limits)
+            except OSError as e:
+                log(f"Could not read {path}: {e}")
+                return path, None
+
+        matched = []
+        for path, hashes in self.limits.map(hash_image, files):
+            if hashes is None:
+                continue
+            progress.advance(hashes["size"])
+            matched.append({"path": path, "match": self.index.lookup(hashes)})
+        progress.finish()
+        return matched
+
+    @staticmethod
+    def existing_cue(directory: Path, track_names: List[str]) -> Optional[Path]:
+        """Find a cue sheet next to the tracks that already refers to one of them."""
+        for cue in sorted(path for path in directory.iterdir() if path.suffix.lower() == '.cue'):
+            try:
+                if set(cue_file_names(decode_cue(cue.read_bytes()))) & set(track_names):
+                    return cue
+            except OSError:
+                continue
+        return None
+
+    def fetch(self, paths: List[Path], dry_run: bool = False, force: bool = False,
+              show_progress: bool = True) -> List[Dict]:
+        """Write the missing cue sheets of the disc images below the paths. Returns one result per disc."""
+        dry_run = dry_run or self.config.is_read_only()
+        results = []
+        discs: "OrderedDict[tuple, Dict[str, Path]]" = OrderedDict()
+        for entry in self.identify(paths, show_progress):
+            match = entry["match"]
+            if not match:
+                results.append({"status": "unknown", "title": None, "platform": None,
+                                "tracks": [str(entry["path"])], "cue": None})
+                continue
+            # Copies of one disc in several folders each get a cue sheet
+            key = (entry["path"].parent, match["title"], match["platform"])
+            discs.setdefault(key, {})[match["rom"]] = entry["path"]
+
+        for (directory, title, platform), tracks in discs.items():
+            result = {"status": None, "title": title, "platform": platform,
+                      "tracks": [str(path) for path in tracks.values()], "cue": None}
+            results.append(result)
+            expected = [rom["rom"] for rom in self.index.game_roms(title) if not rom["rom"].lower().endswith('.cue')]
+            local_names = [path.name for path in tracks.values()]
+
+            existing = None if force else self.existing_cue(directory, local_names)
+            if existing:
+                result.update(status="has-cue", cue=str(existing))
+                continue
+            missing = [name for name in expected if name not in tracks]
+            if missing:
+                result.update(status="missing-tracks", missing=missing)
+                continue
+            system = self.systems.get(platform)
+            if not system:
+                result["status"] = "unsupported"
+                continue
+            pack = self.cue_pack(system)
+            if pack is None:
+                result.update(status="failed", error=f"no {system} cue pack")
+                continue
+            cue = pack.get(f"{title}.cue")
+            if cue is None:
+                result["status"] = "no-cue"
+                continue
+
+            renames = {name: path.name for name, path in tracks.items() if path.name != name}
+            # A single renamed track names the cue sheet too, so the pair still sorts together
+            name = next(iter(tracks.values())).stem if len(tracks) == 1 and renames else title
+            target = directory / f"{name}.cue"
+            result["cue"] = str(target)
+            if dry_run:
+                result["status"] = "would-write"
+                continue
+            try:
+                write_cue(target, rename_cue_files(decode_cue(cue), renames))
+                result["status"] = "written"
+            except OSError as e:
+                result.update(status="failed", error=str(e))
+        return results
+
+
+def summarize(results: List[Dict]) -> Dict[str, int]:
+    counts = {status: 0 for status in STATUSES}
+    for result in results:
+        counts[result["status"]] += 1
+    return counts
+
+
+def print_result(result: Dict):
+    color = STATUS_COLORS.get(result["status"], Colors.NC)
+    name = result["title"] or result["tracks"][0]
+    print(f"{color}{result['status']:>14}{Colors.NC}  {name}")
+    if result.get("cue") and result["status"] != "has-cue":
+        print(f"{'':>16}-> {result['cue']}")
+    if result.get("missing"):
+        print(f"{'':>16}missing: {', '.join(result['missing'])}")
+    if result.get("error"):
+        print(f"{'':>16}{result['error']}")
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Fetch the Redump cue sheets of loose bin/iso disc images')
+    add_config_argument(parser)
+    parser.add_argument('paths', nargs='+', type=Path, help='Disc image files or directories to scan')
+    parser.add_argument('--dat-dir', type=Path, help='DAT directory (default: paths.dat_directory)')
+    parser.add_argument('--dry-run', action='store_true', help='Only report which cue sheets would be written')
+    parser.add_argument('--force', action='store_true', help='Write cue sheets even where one already exists')
+    parser.add_argument('--offline', action='store_true', help='Only use cue packs already in the cache')
+    parser.add_argument('--json', action='store_true', help='Print the results as JSON')
+
+    args = parser.parse_args()
+
+    fetcher = CueFetcher(dat_dir=args.dat_dir, online=not args.offline)
+    if fetcher.dat_count == 0:
+        log(f"{Colors.YELLOW}No DAT files found in {fetcher.dat_dir}; no disc can be identified{Colors.NC}")
+    if fetcher.config.is_read_only() and not args.dry_run:
+        log(f"{Colors.YELLOW}Read-only mode is enabled; listing the cue sheets without writing them{Colors.NC}")
+
+    results = fetcher.fetch(args.paths, dry_run=args.dry_run, force=args.force, show_progress=not args.json)
+    summary = summarize(results)
+
+    if args.json:
+        print(json.dumps({"summary": summary, "results": results}, indent=2))
+    else:
+        for result in results:
+            print_result(result)
+        print("\n" + "  ".join(f"{status}: {count}" for status, count in summary.items() if count))
+
+    sys.exit(1 if summary["failed"] else 0)
+
+
+if __name__ == "__main__":
+    main()
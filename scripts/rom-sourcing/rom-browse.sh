@@ -34,6 +34,16 @@ log() {
     echo -e "$1" | tee -a "$LOG_FILE" >&2
 }
 
+# Read-only mode (ROM_BROWSER_READ_ONLY or safety.read_only) refuses downloads
+source "$(dirname "${BASH_SOURCE[0]}")/../common/read_only.sh"
+
+# Download a picked file into the downloads directory
+download_file() {
+    local url="$1"; local out_path="$2"
+    ensure_writable "download $(basename "$out_path")" || return 1
+    curl -L -s -o "$out_path" "$url"
+}
+
 # Filter functions
 apply_filters() {
     local input_file="$1"
@@ -428,7 +438,7 @@ browse_dir() {
                             local filename=$(basename "$selected_file")
                             local download_path="$DOWNLOADS_DIR/$filename"
                             log "${CYAN}Downloading: $filename${NC}"
-                            if curl -L -s -o "$download_path" "$file_url"; then
+                            if download_file "$file_url" "$download_path"; then
                                 log "${GREEN}Downloaded: $filename to $DOWNLOADS_DIR${NC}"
                             else
                                 log "${RED}Failed to download: $filename${NC}"
@@ -504,7 +514,7 @@ browse_dir() {
                                         local filename=$(basename "$selected_file")
                                         local download_path="$DOWNLOADS_DIR/$filename"
                                         log "${CYAN}Downloading: $filename${NC}"
-                                        if curl -L -s -o "$download_path" "$file_url"; then
+                                        if download_file "$file_url" "$download_path"; then
                                             log "${GREEN}Downloaded: $filename to $DOWNLOADS_DIR${NC}"
                                         else
                                             log "${RED}Failed to download: $filename${NC}"
@@ -551,7 +561,7 @@ browse_dir() {
                                     local filename=$(basename "$selected_file")
                                     local download_path="$DOWNLOADS_DIR/$filename"
                                     log "${CYAN}Downloading: $filename${NC}"
-                                    if curl -L -s -o "$download_path" "$file_url"; then
+                                    if download_file "$file_url" "$download_path"; then
                                         log "${GREEN}Downloaded: $filename to $DOWNLOADS_DIR${NC}"
                                     else
                                         log "${RED}Failed to download: $filename${NC}"
@@ -617,7 +627,7 @@ browse_dir() {
                                                 local download_path="$DOWNLOADS_DIR/$filename"
                                                 local current_num=$((success_count + fail_count + 1))
                                                 log "${CYAN}Downloading [${current_num}/${#files_to_download[@]}]: $filename${NC}"
-                                                if curl -L -s -o "$download_path" "$file_url"; then
+                                                if download_file "$file_url" "$download_path"; then
                                                     log "${GREEN}Downloaded: $filename${NC}"
                                                     ((success_count++))
                                                 else
@@ -14,6 +14,26 @@ DOWNLOAD_DIR="./downloads"
 QUEUE_FILE="./download_queue"
 LOG_FILE="./download_log.txt"
 TEMP_DIR="./temp"
+# --region-priority: comma-separated regions replacing matching.region_priority for this run
+REGION_PRIORITY=""
+# Shared Python modules; the platform search uses their fuzzy matcher
+COMMON_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")/../common" && pwd)"
+# Read-only mode (ROM_BROWSER_READ_ONLY or safety.read_only) refuses downloads and queue edits
+source "$COMMON_DIR/read_only.sh"
+# Files go to paths.download_directory, as rom_downloader.py's do, so clean-partials finds their .part files
+if configured_dir=$(python3 - "$COMMON_DIR" <<'EOF'
+import sys
+
+sys.path.insert(0, sys.argv[1])
+from rom_config import RomConfig
+
+print(RomConfig().get_path('paths.download_directory'))
+EOF
+); then
+    DOWNLOAD_DIR="$configured_dir"
+else
+    echo "Warning: could not read paths.download_directory from the configuration; downloading to $DOWNLOAD_DIR" >&2
+fi
 
 # Available platforms and their subtypes
 declare -A PLATFORMS
@@ -229,13 +249,38 @@ print_indexed_matches() {
     done < "$filtered_file"
 }
 
-# Interactive filter-and-select from a list file
+# Expand a selection like "1-5,8" into one index per line
+expand_selection() {
+    local part
+    local IFS=','
+    for part in $1; do
+        if [[ "$part" =~ ^([0-9]+)-([0-9]+)$ ]]; then
+            local from="${BASH_REMATCH[1]}"
+            local to="${BASH_REMATCH[2]}"
+            if [ "$from" -gt "$to" ]; then
+                local swap="$from"; from="$to"; to="$swap"
+            fi
+            seq "$from" "$to"
+        else
+            echo "$part"
+        fi
+    done
+}
+
+# Interactive filter-and-select from a list file; unless multi is 0, several
+# entries can be picked (1-5,8 or a for every current match), one per output line.
+# With search set to 1, /text prints the text and returns 2 for the caller to search
 interactive_select_from_list() {
     local items_file="$1"
     local prompt_text="$2"
     local initial_filter="$3"
     local limit="$4"
+    local multi="${5:-1}"
+    local search="${6:-0}"
     if [ -z "$limit" ]; then limit=100; fi
+    local prompt_keys="number to select"
+    if [ "$multi" = "1" ]; then prompt_keys="number, list like 1-5,8 or a=all to select"; fi
+    if [ "$search" = "1" ]; then prompt_keys="$prompt_keys, /text to search the platform"; fi
     local filtered_file=$(mktemp)
     cp "$items_file" "$filtered_file"
     if [ -n "$initial_filter" ]; then
@@ -252,7 +297,11 @@ interactive_select_from_list() {
             echo "... (showing first $limit of $total)" >&2
         fi
         echo "" >&2
-        read -p "$prompt_text (type to filter or number to select, q=quit): " input
+        if ! read -p "$prompt_text (type to filter or $prompt_keys, q=quit): " input; then
+            # End of input counts as quitting
+            rm -f "$filtered_file"
+            return 1
+        fi
         if [[ "$input" =~ ^[0-9]+$ ]]; then
             local selection=$(sed -n "${input}p" "$items_file")
             if [ -n "$selection" ] && grep -Fx -q -- "$selection" "$filtered_file"; then
@@ -262,22 +311,21 @@ interactive_select_from_list() {
             else
                 log_message "${YELLOW}Invalid selection number for current filter${NC}" >&2
             fi
-        elif [[ "$input" =~ ^[0-9]+(,[0-9]+)*$ ]]; then
-            # Multi-select by comma-separated indices (validate each against current filter)
-            local IFS=','
-            local indices=($input)
+        elif [ "$multi" = "1" ] && { [[ "$input" =~ ^[0-9]+(-[0-9]+)?(,[0-9]+(-[0-9]+)?)*$ ]] || [ "$input" = "a" ] || [ "$input" = "A" ]; }; then
+            # Multi-select by indices and ranges, or every current match (validate each against current filter)
             local out_tmp=$(mktemp)
-            local ok=0
-            for idx in "${indices[@]}"; do
-                if [[ "$idx" =~ ^[0-9]+$ ]]; then
+            if [ "$input" = "a" ] || [ "$input" = "A" ]; then
+                cp "$filtered_file" "$out_tmp"
+            else
+                local idx
+                for idx in $(expand_selection "$input"); do
                     local line=$(sed -n "${idx}p" "$items_file")
-                    if [ -n "$line" ] && grep -Fx -q -- "$line" "$filtered_file"; then
+                    if [ -n "$line" ] && grep -Fx -q -- "$line" "$filtered_file" && ! grep -Fx -q -- "$line" "$out_tmp"; then
                         echo "$line" >> "$out_tmp"
-                        ok=1
                     fi
-                fi
-            done
-            if [ "$ok" = "1" ]; then
+                done
+            fi
+            if [ -s "$out_tmp" ]; then
                 cat "$out_tmp"
                 rm -f "$out_tmp" "$filtered_file"
                 return 0
@@ -285,6 +333,10 @@ interactive_select_from_list() {
                 rm -f "$out_tmp"
                 log_message "${YELLOW}Invalid selection numbers for current filter${NC}" >&2
             fi
+        elif [ "$search" = "1" ] && [[ "$input" =~ ^/.*[^[:space:]] ]]; then
+            echo "${input#/}"
+            rm -f "$filtered_file"
+            return 2
         elif [ "$input" = "q" ] || [ "$input" = "Q" ]; then
             rm -f "$filtered_file"
             return 1
@@ -407,14 +459,14 @@ search_game() {
     return 1
 }
 
-# Function to select best match
+# Select the best match with the Python scorer (match_scoring.py), which follows the
+# matching.* preferences of the configuration as rom_downloader.py does.
+# Without Python, the file sharing the most words with the title wins
 select_best_match() {
     local game_title="$1"
     local matches="$2"
-    
-    # Score each match based on similarity and region preference
-    local best_match=""
-    local best_score=0
+    local candidates_file="$TEMP_DIR/best_match_candidates.txt"
+    : > "$candidates_file"
     
     while IFS= read -r match; do
         if [ -n "$match" ]; then
@@ -431,47 +483,56 @@ select_best_match() {
             
             # Validate that we got a proper filename
             if [ -n "$filename" ] && [ "$filename" != "$match" ] && [[ "$filename" != *"Searching for:"* ]]; then
-                local clean_filename=$(echo "$filename" | sed -e 's/\.zip$//' -e 's/\.7z$//' -e 's/%20/ /g' -e 's/%28/(/g' -e 's/%29/)/g')
-                
-                # Simple scoring based on word overlap
-                local score=0
-                local game_words=$(echo "$game_title" | tr '[:upper:]' '[:lower:]' | tr '[:space:]' '\n' | grep -v '^$')
-                local filename_words=$(echo "$clean_filename" | tr '[:upper:]' '[:lower:]' | tr '[:space:]' '\n' | grep -v '^$')
-                
-                while IFS= read -r word; do
-                    if echo "$filename_words" | grep -q "^$word$"; then
-                        score=$((score + 1))
-                    fi
-                done <<< "$game_words"
-                
-                # Region preference scoring - prioritize USA versions
-                if echo "$clean_filename" | grep -qi "(USA)"; then
-                    score=$((score + 10))  # High bonus for USA versions
-                elif echo "$clean_filename" | grep -qi "(US)"; then
-                    score=$((score + 10))  # High bonus for US versions
-                elif echo "$clean_filename" | grep -qi "(North America)"; then
-                    score=$((score + 8))   # Good bonus for North America
-                elif echo "$clean_filename" | grep -qi "(Europe)"; then
-                    score=$((score + 2))   # Small bonus for Europe
-                elif echo "$clean_filename" | grep -qi "(Asia)"; then
-                    score=$((score + 1))   # Very small bonus for Asia
-                elif echo "$clean_filename" | grep -qi "(Japan)"; then
-                    score=$((score + 0))   # No bonus for Japan
-                fi
-                
-                # Language preference - prefer English versions
-                if echo "$clean_filename" | grep -qi "(En)"; then
-                    score=$((score + 3))   # Bonus for English language
-                fi
-                
-                if [ $score -gt $best_score ]; then
-                    best_score=$score
-                    best_match="$filename"
-                fi
+                local clean_filename=$(echo "$filename" | sed -e 's/%20/ /g' -e 's/%28/(/g' -e 's/%29/)/g')
+                echo "${clean_filename}||${filename}" >> "$candidates_file"
             fi
         fi
     done <<< "$matches"
     
+    local best_match
+    if best_match=$(python3 - "$COMMON_DIR" "$candidates_file" "$game_title" "$REGION_PRIORITY" <<'EOF'
+import sys
+
+sys.path.insert(0, sys.argv[1])
+from match_scoring import MatchScorer
+from rom_config import RomConfig
+
+with open(sys.argv[2], encoding='utf-8') as f:
+    hrefs = dict(line.rstrip('\n').split('||', 1) for line in f if '||' in line)
+scorer = MatchScorer.from_config(RomConfig())
+if sys.argv[4]:
+    scorer.region_priority = [r.strip() for r in sys.argv[4].split(',') if r.strip()]
+explanation = scorer.explain_match(sys.argv[3], list(hrefs))
+print(hrefs[explanation['match']] if explanation else "")
+EOF
+    ); then
+        echo "$best_match"
+        return
+    fi
+    
+    # stdout is the match, so the warning goes to stderr and the log
+    local warning="Warning: the Python scorer failed (see above); matching by title words only, without region, language, revision or avoided-tag preferences"
+    echo -e "${YELLOW}${warning}${NC}" >&2
+    echo "$(date '+%Y-%m-%d %H:%M:%S'): $warning" >> "$LOG_FILE"
+    local best_score=0
+    best_match=""
+    local game_words=$(echo "$game_title" | tr '[:upper:]' '[:lower:]' | tr '[:space:]' '\n' | grep -v '^$')
+    while IFS= read -r candidate; do
+        local clean_filename="${candidate%%||*}"
+        local filename_words=$(echo "$clean_filename" | tr '[:upper:]' '[:lower:]' | tr '[:space:]' '\n' | grep -v '^$')
+        local score=0
+        while IFS= read -r word; do
+            if echo "$filename_words" | grep -q -F -x -- "$word"; then
+                score=$((score + 1))
+            fi
+        done <<< "$game_words"
+        
+        if [ $score -gt $best_score ]; then
+            best_score=$score
+            best_match="${candidate#*||}"
+        fi
+    done < "$candidates_file"
+    
     echo "$best_match"
 }
 
@@ -480,26 +541,29 @@ download_game() {
     local game_title="$1"
     local filename="$2"
     local platform="$3"
-    local download_url="$(get_platform_url "$platform")${filename}"
+    local download_url="${4:-$(get_platform_url "$platform")${filename}}"
     
+    ensure_writable "download $game_title" || return 1
     log_message "${BLUE}Downloading: $game_title${NC}"
     log_message "Filename: $filename"
     log_message "URL: $download_url"
     
-    # Check if file already exists
-    if [ -f "$DOWNLOAD_DIR/$filename" ]; then
+    # The file is written to .part and renamed when complete, so a finished file
+    # without a .part next to it is a complete download
+    local target="$DOWNLOAD_DIR/$filename"
+    local part_file="$target.part"
+    if [ -f "$target" ] && [ ! -f "$part_file" ]; then
         log_message "${YELLOW}File already exists, skipping download: $filename${NC}"
         return 0
     fi
     
-    # Download with progress bar and resume support
-    if curl -L -C - -o "$DOWNLOAD_DIR/$filename" "$download_url" --progress-bar --retry 3 --retry-delay 5; then
+    # Download with progress bar; an interrupted .part is resumed on the next run
+    # (library_manager.py clean-partials removes ones left behind)
+    if curl -L -C - -o "$part_file" "$download_url" --progress-bar --retry 3 --retry-delay 5 && mv -f "$part_file" "$target"; then
         log_message "${GREEN}Successfully downloaded: $game_title${NC}"
         return 0
     else
         log_message "${RED}Failed to download: $game_title${NC}"
-        # Remove partial download if it exists
-        rm -f "$DOWNLOAD_DIR/$filename"
         return 1
     fi
 }
@@ -508,9 +572,9 @@ download_game() {
 extract_games() {
     local temp_file=$(mktemp)
     
-    # Extract game titles from clean format (no suffixes)
+    # Extract game titles from clean format (no suffixes, notes after " # " dropped)
     grep -E '^[A-Za-z0-9]' "$QUEUE_FILE" | \
-    sed 's/^[[:space:]]*//;s/[[:space:]]*$//' | \
+    sed 's/ # .*$//;s/^[[:space:]]*//;s/[[:space:]]*$//' | \
     grep -v '^$' > "$temp_file"
     
     echo "$temp_file"
@@ -519,6 +583,7 @@ extract_games() {
 # Function to remove completed game from queue
 remove_from_queue() {
     local game_title="$1"
+    ensure_writable "edit the download queue" || return 1
     local temp_file=$(mktemp)
     
     # Remove the line containing this game title
@@ -528,6 +593,64 @@ remove_from_queue() {
     log_message "${GREEN}Removed '$game_title' from download queue${NC}"
 }
 
+# Download the titles picked in a browser (display||path lines, platform-relative paths)
+# one after another, or add their URLs to the download queue. Without a platform, each
+# display starts with its platform ("Platform / Title"), as in the multi-platform list
+download_selection() {
+    local selection="$1"
+    local platform="$2"
+    local count=$(printf "%s\n" "$selection" | grep -c .)
+    local action="d"
+    if [ "$count" -gt 1 ]; then
+        echo ""
+        read -p "Download the $count selected titles now, or add them to the queue? [d/q] (default d): " action
+        if [ -z "$action" ]; then action="d"; fi
+    fi
+
+    local current=0
+    local failed=0
+    while IFS= read -r pair; do
+        [ -z "$pair" ] && continue
+        current=$((current + 1))
+        local disp=$(echo "$pair" | cut -d'|' -f1)
+        local href=$(echo "$pair" | sed 's/^.*||//')
+        local plat="$platform"
+        if [ -z "$plat" ]; then
+            plat=$(echo "$disp" | sed 's/\s*\/\s.*$//')
+        fi
+
+        if [ "$action" = "q" ] || [ "$action" = "Q" ]; then
+            local url="$(get_platform_url "$plat")${href}"
+            if ! ensure_writable "edit the download queue"; then
+                failed=$((failed + 1))
+            elif [ -f "$QUEUE_FILE" ] && grep -q -F -- "$url" "$QUEUE_FILE"; then
+                log_message "${YELLOW}Already queued: $disp${NC}"
+            else
+                echo "$url" >> "$QUEUE_FILE"
+                log_message "${GREEN}Queued: $disp${NC}"
+            fi
+            continue
+        fi
+
+        [ "$count" -gt 1 ] && log_message "${BLUE}[$current/$count]${NC}"
+        if download_game "$disp" "$href" "$plat"; then
+            log_message "${GREEN}✓ Completed: $disp${NC}"
+        else
+            log_message "${RED}✗ Download failed: $disp${NC}"
+            failed=$((failed + 1))
+        fi
+        # Wait before next download to be respectful to the server
+        if [ "$current" -lt "$count" ]; then
+            sleep 3
+        fi
+    done <<< "$selection"
+
+    if [ "$count" -gt 1 ] && [ "$action" != "q" ] && [ "$action" != "Q" ]; then
+        log_message "${GREEN}Downloaded $((count - failed)) of $count titles${NC}"
+    fi
+    [ "$failed" -eq 0 ]
+}
+
 # Function to cleanup temporary files
 cleanup() {
     log_message "${BLUE}Cleaning up temporary files...${NC}"
@@ -536,7 +659,11 @@ cleanup() {
 
 # Function to display help
 show_help() {
-    echo "Usage: $0 [platform] [subtype]"
+    echo "Usage: $0 [--region-priority REGIONS] [platform] [subtype]"
+    echo ""
+    echo "Options:"
+    echo "  --region-priority REGIONS   Comma-separated region preference for best-match selection"
+    echo "                              (e.g. \"USA,Europe,Japan\"), instead of matching.region_priority"
     echo ""
     echo "Examples:"
     echo "  $0                           # Interactive mode (choose queue or single game)"
@@ -548,7 +675,11 @@ show_help() {
     echo "  - You can enter a platform number, part of its name (e.g. 'xbox 360'),"
     echo "    or the short code (e.g. PS2, X360)."
     echo "  - In single-game mode, you can enter a rough title or browse/search titles."
-    echo "  - The downloader will pick the best match, prioritizing USA region."
+    echo "  - Pick several titles at once with a list like 1-5,8 (or a for every match),"
+    echo "    then download them one after another or add them to the queue."
+    echo "  - While browsing, /text searches every file of the platform, subfolders"
+    echo "    included, allowing for typos (e.g. /mraio kart)."
+    echo "  - The downloader will pick the best match, following matching.region_priority in the config."
     echo "  - Datasets: Choose between Redump and No-Intro platform listings."
     echo ""
 }
@@ -631,10 +762,11 @@ collect_archives_recursive_for_platform() {
     : > "$out_file"
     local queue_curr="$TEMP_DIR/queue_curr.txt"
     local queue_next="$TEMP_DIR/queue_next.txt"
-    printf "\t0\n" > "$queue_curr"
+    # Entries are rel_path|depth; a tab would be stripped from the empty path of the top folder
+    printf "|0\n" > "$queue_curr"
     while [ -s "$queue_curr" ]; do
         : > "$queue_next"
-        while IFS=$'\t' read -r rel_path depth; do
+        while IFS='|' read -r rel_path depth; do
             [ -z "$rel_path" ] && rel_path=""
             [ -z "$depth" ] && depth=0
             local url="${platform_url}${rel_path}"
@@ -660,7 +792,7 @@ collect_archives_recursive_for_platform() {
                         if echo "$subhref" | grep -qE '^https?://'; then
                             continue
                         fi
-                        printf "%s\t%d\n" "${rel_path}${subhref}" "$((depth+1))" >> "$queue_next"
+                        printf "%s|%d\n" "${rel_path}${subhref}" "$((depth+1))" >> "$queue_next"
                     done < "$subs"
                 fi
             fi
@@ -670,11 +802,74 @@ collect_archives_recursive_for_platform() {
     echo "$out_file"
 }
 
-# Interactive folder-first browser; shows folders first, then titles in a leaf
+# Fuzzy-match a query against a display||href list with the Python matcher (names.fuzzy_score),
+# which allows for typos, best match first; each display gets the folder its file is in.
+# Without Python, every word of the query has to appear in the display
+search_title_list() {
+    local list_file="$1"
+    local query="$2"
+    local out_file="$TEMP_DIR/search_results.txt"
+    if ! python3 - "$COMMON_DIR" "$list_file" "$query" > "$out_file" 2>/dev/null <<'EOF'
+import sys
+import urllib.parse
+
+sys.path.insert(0, sys.argv[1])
+from names import fuzzy_score
+
+with open(sys.argv[2], encoding='utf-8') as f:
+    pairs = [line.rstrip('\n').split('||', 1) for line in f if '||' in line]
+scored = [(fuzzy_score(sys.argv[3], display), display, href) for display, href in pairs]
+for score, display, href in sorted((s for s in scored if s[0]), key=lambda s: -s[0]):
+    folder = urllib.parse.unquote(href.rpartition('/')[0])
+    print(f"{folder} / {display}||{href}" if folder else f"{display}||{href}")
+EOF
+    then
+        cp "$list_file" "$out_file"
+        local word
+        for word in $query; do
+            grep -i -F -- "$word" "$out_file" > "${out_file}.tmp" || true
+            mv "${out_file}.tmp" "$out_file"
+        done
+    fi
+    echo "$out_file"
+}
+
+# Search every file of the platform, subfolders included, and let the user pick from the
+# best matches. The file list is collected once per browse into titles_file
+search_platform_titles() {
+    local platform_name="$1"
+    local query="$2"
+    local titles_file="$3"
+    if [ ! -s "$titles_file" ]; then
+        log_message "${BLUE}Collecting the file list of $platform_name...${NC}" >&2
+        cp "$(collect_archives_recursive_for_platform "$platform_name")" "$titles_file"
+    fi
+    local results=$(search_title_list "$titles_file" "$query")
+    if [ ! -s "$results" ]; then
+        log_message "${YELLOW}Nothing in $platform_name matches '$query'${NC}" >&2
+        return 1
+    fi
+    local displays="$TEMP_DIR/search_displays.txt"
+    cut -d'|' -f1 "$results" > "$displays"
+    log_message "${CYAN}$(wc -l < "$displays" | tr -d '[:space:]') matches for '$query' in $platform_name${NC}" >&2
+    local selected_lines
+    selected_lines=$(interactive_select_from_list "$displays" "Result" "" 100)
+    if [ -z "$selected_lines" ]; then
+        return 1
+    fi
+    while IFS= read -r selected_line; do
+        echo "${selected_line}||$(grep -F "${selected_line}||" "$results" | head -n1 | sed 's/^.*||//')"
+    done <<< "$selected_lines"
+}
+
+# Interactive folder-first browser; shows folders first, then titles in a leaf.
+# /text in either searches the whole platform instead. Prints a display||path line for each title picked
 interactive_browse_folders_first() {
     local platform_name="$1"
     local platform_url="$(get_platform_url "$platform_name")"
     local rel_path=""
+    local titles_file="$TEMP_DIR/platform_titles.txt"
+    : > "$titles_file"
     while true; do
         local current_url="${platform_url}${rel_path}"
         local idx="$TEMP_DIR/folder_index.html"
@@ -693,7 +888,12 @@ interactive_browse_folders_first() {
             else
                 cp "$subdisplays" "$menu_file"
             fi
-            local chosen=$(interactive_select_from_list "$menu_file" "Folder" "" 100)
+            local chosen
+            chosen=$(interactive_select_from_list "$menu_file" "Folder" "" 100 0 1)
+            if [ $? -eq 2 ]; then
+                search_platform_titles "$platform_name" "$chosen" "$titles_file" && return 0
+                continue
+            fi
             if [ -z "$chosen" ]; then
                 return 1
             fi
@@ -715,12 +915,19 @@ interactive_browse_folders_first() {
         if [ -s "$archives" ]; then
             local displays="$TEMP_DIR/title_displays.txt"
             cut -d'|' -f1 "$archives" > "$displays"
-            local selected_line=$(interactive_select_from_list "$displays" "Title" "" 100)
-            if [ -z "$selected_line" ]; then
+            local selected_lines
+            selected_lines=$(interactive_select_from_list "$displays" "Title" "" 100 1 1)
+            if [ $? -eq 2 ]; then
+                search_platform_titles "$platform_name" "$selected_lines" "$titles_file" && return 0
+                continue
+            fi
+            if [ -z "$selected_lines" ]; then
                 return 1
             fi
-            local href=$(grep -F "${selected_line}||" "$archives" | head -n1 | sed 's/^.*||//')
-            echo "${selected_line}||${rel_path}${href}"
+            while IFS= read -r selected_line; do
+                local href=$(grep -F "${selected_line}||" "$archives" | head -n1 | sed 's/^.*||//')
+                echo "${selected_line}||${rel_path}${href}"
+            done <<< "$selected_lines"
             return 0
         fi
         log_message "${YELLOW}No content found in this folder${NC}" >&2
@@ -735,11 +942,33 @@ main() {
     local mode=""
     local dataset_choice=""
     
-    # Parse command line arguments
-    if [ "$1" = "-h" ] || [ "$1" = "--help" ]; then
-        show_help
-        exit 0
-    fi
+    # Parse command line arguments; what is not an option is the platform
+    local positional=()
+    while [ $# -gt 0 ]; do
+        case "$1" in
+            -h|--help)
+                show_help
+                exit 0
+                ;;
+            --region-priority)
+                if [ -z "$2" ]; then
+                    echo "--region-priority needs a list of regions, e.g. USA,Europe,Japan" >&2
+                    exit 1
+                fi
+                REGION_PRIORITY="$2"
+                shift 2
+                ;;
+            --region-priority=*)
+                REGION_PRIORITY="${1#*=}"
+                shift
+                ;;
+            *)
+                positional+=("$1")
+                shift
+                ;;
+        esac
+    done
+    set -- "${positional[@]}"
     
     # Dataset selection
     echo ""
@@ -781,12 +1010,7 @@ main() {
             log_message "${YELLOW}No title selected${NC}"
             exit 1
         fi
-        local disp=$(echo "$pair" | cut -d'|' -f1)
-        local href=$(echo "$pair" | sed 's/^.*||//')
-        if download_game "$disp" "$href" "$platform_name"; then
-            log_message "${GREEN}✓ Completed: $disp${NC}"
-        else
-            log_message "${RED}✗ Download failed: $disp${NC}"
+        if ! download_selection "$pair" "$platform_name"; then
             cleanup
             exit 1
         fi
@@ -847,12 +1071,7 @@ main() {
             log_message "${YELLOW}No title selected${NC}"
             exit 1
         fi
-        local disp_qp=$(echo "$pair_qp" | cut -d'|' -f1)
-        local href_qp=$(echo "$pair_qp" | sed 's/^.*||//')
-        if download_game "$disp_qp" "$href_qp" "$platform_name"; then
-            log_message "${GREEN}✓ Completed: $disp_qp${NC}"
-        else
-            log_message "${RED}✗ Download failed: $disp_qp${NC}"
+        if ! download_selection "$pair_qp" "$platform_name"; then
             cleanup
             exit 1
         fi
@@ -924,13 +1143,12 @@ main() {
                 log_message "${RED}No title selected${NC}"
                 exit 1
             fi
-            local href=$(grep -F "${chosen}||" "$combined" | head -n1 | sed 's/^.*||//')
-            # We need a platform to compute URL; extract platform (before ' / ')
-            local chosen_platform=$(echo "$chosen" | sed 's/\s*\/\s.*$//')
-            if download_game "$chosen" "$href" "$chosen_platform"; then
-                log_message "${GREEN}✓ Completed: $chosen${NC}"
-            else
-                log_message "${RED}✗ Download failed: $chosen${NC}"
+            local pairs=""
+            while IFS= read -r chosen_line; do
+                pairs+="${chosen_line}||$(grep -F "${chosen_line}||" "$combined" | head -n1 | sed 's/^.*||//')"$'\n'
+            done <<< "$chosen"
+            # Each title's platform comes from its display (before ' / ')
+            if ! download_selection "$pairs" ""; then
                 exit 1
             fi
             cleanup
@@ -968,20 +1186,17 @@ main() {
             fi
             local displays="$TEMP_DIR/title_displays.txt"
             cut -d'|' -f1 "$combined" > "$displays"
-            local selected_line=$(interactive_select_from_list "$displays" "Title" "" 100)
-            if [ -z "$selected_line" ]; then
+            local selected_lines=$(interactive_select_from_list "$displays" "Title" "" 100)
+            if [ -z "$selected_lines" ]; then
                 log_message "${RED}No title selected${NC}"
                 exit 1
             fi
-            local href=$(grep -F "${selected_line}||" "$combined" | head -n1 | sed 's/^.*||//')
-            pair="${selected_line}||${href}"
+            while IFS= read -r selected_line; do
+                local href=$(grep -F "${selected_line}||" "$combined" | head -n1 | sed 's/^.*||//')
+                pair+="${selected_line}||${href}"$'\n'
+            done <<< "$selected_lines"
         fi
-        local disp=$(echo "$pair" | cut -d'|' -f1)
-        local href=$(echo "$pair" | sed 's/^.*||//')
-        if download_game "$disp" "$href" "$platform_name"; then
-            log_message "${GREEN}✓ Completed: $disp${NC}"
-        else
-            log_message "${RED}✗ Download failed: $disp${NC}"
+        if ! download_selection "$pair" "$platform_name"; then
             exit 1
         fi
         cleanup
@@ -990,6 +1205,9 @@ main() {
     fi
 
     # Queue processing mode
+    if ! ensure_writable "process the download queue"; then
+        exit 1
+    fi
     # Check if queue file exists
     if [ ! -f "$QUEUE_FILE" ]; then
         log_message "${RED}Error: Queue file not found: $QUEUE_FILE${NC}"
@@ -1010,6 +1228,23 @@ main() {
         current_game=$((current_game + 1))
         log_message "${BLUE}[$current_game/$total_games] Processing: $game_title${NC}"
         
+        # Entries added as URLs (by the browsers) name their file exactly
+        if [[ "$game_title" =~ ^https?:// ]]; then
+            local url_file="${game_title##*/}"
+            if download_game "$url_file" "$url_file" "" "$game_title"; then
+                remove_from_queue "$game_title"
+                successful_downloads=$((successful_downloads + 1))
+                log_message "${GREEN}✓ Completed: $url_file${NC}"
+            else
+                failed_downloads=$((failed_downloads + 1))
+                log_message "${RED}✗ Download failed: $url_file${NC}"
+            fi
+            if [ $current_game -lt $total_games ]; then
+                sleep 3
+            fi
+            continue
+        fi
+        
         # Clean the title
         local clean_title=$(clean_title "$game_title")
         
@@ -12,6 +12,9 @@ LOG_FILE="./mbrowse_log.txt"
 
 mkdir -p "$TEMP_DIR" "$DOWNLOAD_DIR"
 
+# Read-only mode (ROM_BROWSER_READ_ONLY or safety.read_only) refuses downloads
+source "$(dirname "${BASH_SOURCE[0]}")/../common/read_only.sh"
+
 RED='\033[0;31m'
 GREEN='\033[0;32m'
 YELLOW='\033[1;33m'
@@ -125,6 +128,7 @@ copy_to_clipboard() {
 
 download_file() {
     local url="$1"; local out_path="$2"
+    ensure_writable "download $(basename "$out_path")" || return 1
     curl -L -C - --progress-bar --retry 3 --retry-delay 5 -o "$out_path" "$url"
 }
 
@@ -19,6 +19,21 @@ import shutil
 from typing import List, Dict, Optional, Tuple
 import argparse
 
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from compatibility import CompatibilityChecker
+from download_queue import format_line, open_queue, parse_line
+from library_db import owned_key, owned_keys, platform_from_url
+from mame_sets import MameSets
+from mirrors import MirrorSet
+from names import (NameFilter, add_name_filter_arguments, add_sort_arguments, filter_entries, fuzzy_search,
+                   group_by_title, group_language_variants, name_fields, name_filter_from_args, sort_entries)
+from proxy import apply_proxy_settings
+from retry_policy import RetryPolicy
+from rom_config import RomConfig, add_config_argument
+from rom_sources import HttpIndexSource, LocalSource, format_size
+from script_errors import install_error_reporting, report_error
+from session_filter import SessionFilter, load_session_filter, save_session_filter, session_file
+
 
 class Colors:
     """ANSI color codes for terminal output."""
@@ -32,11 +47,13 @@ class Colors:
 class ROMBrowser:
     def __init__(self):
         # Configuration
-        self.base_url_redump = "https://myrient.erista.me/files/Redump/"
-        self.base_url_noin = "https://myrient.erista.me/files/No-Intro/"
-        self.temp_dir = Path("./temp")
-        self.downloads_dir = Path("../downloads")
-        self.log_file = Path("./rom-browse.log")
+        self.config = RomConfig()
+        self.base_url_redump = self.config.get_archive_url() + "Redump/"
+        self.base_url_noin = self.config.get_archive_url() + "No-Intro/"
+        self.base_url_tosec = self.config.get_archive_url() + "TOSEC/"
+        self.temp_dir = self.config.get_path('paths.temp_directory')
+        self.downloads_dir = self.config.get_path('paths.download_directory')
+        self.log_file = self.config.get_path('paths.log_directory') / "rom-browse.log"
         self.queue_file = Path("./download_queue")
         self.page_size = 50
         self.filter_file = Path("../config/rom-filter.txt")
@@ -47,10 +64,20 @@ class ROMBrowser:
         self.current_dataset = "Redump"
         self.history = []
         self.download_queue = []
+        apply_proxy_settings(self.config)
+        self.retry = RetryPolicy.from_config(self.config, log=self.log)
+        self.mirrors = MirrorSet(self.config, self.retry, log=self.log)
+        self.source = HttpIndexSource("myrient", self.config.get_archive_url(), self.mirrors, self.config)
+        self.local = LocalSource(self.config)
+        self.mame = None
+        self.compatibility = CompatibilityChecker(self.config)
+        self.queue = open_queue(self.config)
+        self.session_filter = load_session_filter(self.config)
         
         # Create directories
-        self.temp_dir.mkdir(exist_ok=True)
-        self.downloads_dir.mkdir(exist_ok=True)
+        self.temp_dir.mkdir(parents=True, exist_ok=True)
+        self.downloads_dir.mkdir(parents=True, exist_ok=True)
+        self.log_file.parent.mkdir(parents=True, exist_ok=True)
         
         # Load state
         self.load_history()
@@ -89,22 +116,16 @@ class ROMBrowser:
     
     def load_download_queue(self):
         """Load download queue from file."""
-        if self.queue_file.exists():
-            try:
-                with open(self.queue_file, 'r', encoding='utf-8') as f:
-                    self.download_queue = [line.strip() for line in f if line.strip()]
-            except Exception as e:
-                self.log(f"{Colors.YELLOW}Warning: Could not load download queue: {e}{Colors.NC}")
-                self.download_queue = []
-        else:
+        try:
+            self.download_queue = [format_line(item, notes) for item, notes in self.queue.read(str(self.queue_file.resolve()))]
+        except Exception as e:
+            self.log(f"{Colors.YELLOW}Warning: Could not load download queue: {e}{Colors.NC}")
             self.download_queue = []
     
     def save_download_queue(self):
         """Save download queue to file."""
         try:
-            with open(self.queue_file, 'w', encoding='utf-8') as f:
-                for item in self.download_queue:
-                    f.write(f"{item}\n")
+            self.queue.replace(str(self.queue_file.resolve()), [parse_line(line) for line in self.download_queue])
         except Exception as e:
             self.log(f"{Colors.YELLOW}Warning: Could not save download queue: {e}{Colors.NC}")
     
@@ -117,74 +138,22 @@ class ROMBrowser:
                 self.history = self.history[-100:]
             self.save_history()
     
-    def download_index(self, url: str) -> Optional[str]:
-        """Download and parse index page."""
-        try:
-            self.log(f"{Colors.CYAN}Downloading index from {url}...{Colors.NC}")
-            
-            headers = {
-                'User-Agent': 'Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36'
-            }
-            
-            response = requests.get(url, headers=headers, timeout=30)
-            response.raise_for_status()
-            
-            index_file = self.temp_dir / "index.html"
-            with open(index_file, 'w', encoding='utf-8') as f:
-                f.write(response.text)
-            
-            self.log(f"{Colors.GREEN}Index downloaded successfully{Colors.NC}")
-            return str(index_file)
-            
-        except Exception as e:
-            self.log(f"{Colors.RED}Failed to download index: {e}{Colors.NC}")
-            return None
+    def source_for(self, url: str):
+        """Get the source a URL belongs to: a local folder for file URLs, else the archive."""
+        return self.local if self.local.path_of(url) is not None else self.source
     
-    def parse_index(self, index_file: str) -> Tuple[List[str], List[str]]:
-        """Parse index file and extract directories and files."""
-        directories = []
-        files = []
-        
+    def list_directory(self, url: str) -> Optional[Tuple[List[str], List[str]]]:
+        """List the directories and files at an archive URL."""
         try:
-            with open(index_file, 'r', encoding='utf-8') as f:
-                content = f.read()
-            
-            # Simple HTML parsing - look for href attributes
-            import re
-            
-            # Find all href attributes
-            href_pattern = r'href="([^"]+)"'
-            hrefs = re.findall(href_pattern, content)
-            
-            for href in hrefs:
-                # Skip parent directory and absolute URLs
-                if href in ['../', '..'] or href.startswith('http'):
-                    continue
-                
-                # Skip query parameters
-                if '?' in href:
-                    continue
-                
-                # Decode URL
-                decoded_href = urllib.parse.unquote(href)
-                
-                if href.endswith('/'):
-                    # Directory
-                    dir_name = decoded_href.rstrip('/')
-                    if dir_name and dir_name not in directories:
-                        directories.append(dir_name)
-                else:
-                    # File
-                    if decoded_href and decoded_href not in files:
-                        files.append(decoded_href)
-            
-            # Sort both lists
-            directories.sort()
-            files.sort()
-            
+            self.log(f"{Colors.CYAN}Listing {url}...{Colors.NC}")
+            source = self.source_for(url)
+            entries = source.list_dir(source.path_of(url) or '')
         except Exception as e:
-            self.log(f"{Colors.RED}Error parsing index: {e}{Colors.NC}")
+            self.log(f"{Colors.RED}Failed to list {url}: {e}{Colors.NC}")
+            return None
         
+        directories = [entry['name'] for entry in entries if entry['is_dir']]
+        files = [entry['name'] for entry in entries if not entry['is_dir']]
         return directories, files
     
     def apply_filters(self, items: List[str]) -> List[str]:
@@ -207,7 +176,22 @@ class ROMBrowser:
             self.log(f"{Colors.YELLOW}Warning: Could not apply filters: {e}{Colors.NC}")
             return items
     
-    def display_items(self, items: List[str], item_type: str, page: int = 1):
+    def group_sets(self, files: List[str]) -> Tuple[List[str], Dict[str, str]]:
+        """Order MAME sets with clones under their parents. Returns the files and a note per clone or BIOS."""
+        if self.mame is None:
+            self.mame = MameSets()
+            self.mame.load_directory(self.config.get_path('paths.dat_directory'), log=self.log)
+        if not any(self.mame.get(f) for f in files):
+            return files, {}
+        
+        ordered = []
+        for group in self.mame.group(files):
+            ordered.append(group['parent'])
+            ordered.extend(group['clones'])
+        notes = {f: self.mame.describe(f) for f in ordered if self.mame.describe(f)}
+        return ordered, notes
+    
+    def display_items(self, items: List[str], item_type: str, page: int = 1, notes: Optional[Dict[str, str]] = None):
         """Display items with pagination."""
         if not items:
             print(f"{Colors.YELLOW}No {item_type} found{Colors.NC}")
@@ -223,7 +207,8 @@ class ROMBrowser:
         print("=" * 60)
         
         for i, item in enumerate(items[start_idx:end_idx], start=start_idx + 1):
-            print(f"{i:3d}. {item}")
+            note = (notes or {}).get(item)
+            print(f"{i:3d}. {item}" + (f"  {Colors.YELLOW}({note}){Colors.NC}" if note else ""))
         
         print("=" * 60)
     
@@ -249,6 +234,9 @@ class ROMBrowser:
             elif choice.lower() in ['d', 'download']:
                 self.show_download_queue()
                 return None
+            elif choice.lower() in ['s', 'session']:
+                self.manage_session_filter()
+                return None
             
             # Try to parse as number
             try:
@@ -283,11 +271,14 @@ class ROMBrowser:
   • 'h' or 'help' - Show this help
   • 'f' or 'filter' - Manage filters
   • 'd' or 'download' - Show download queue
+  • 's' or 'session' - Set the session filter
 
 {Colors.GREEN}Filtering:{Colors.NC}
   • Filters are applied automatically
   • Edit {self.filter_file} to modify filters
   • Use 'f' command to manage filters interactively
+  • The session filter (regions, no clones, unowned only) also applies to
+    searches and queue building until it is cleared
 
 {Colors.GREEN}Download Queue:{Colors.NC}
   • Items are added to queue for batch download
@@ -384,6 +375,39 @@ class ROMBrowser:
         except Exception as e:
             print(f"{Colors.RED}Error opening editor: {e}{Colors.NC}")
     
+    def manage_session_filter(self):
+        """Toggle the parts of the session filter; it is shared with the other commands until cleared."""
+        session = self.session_filter
+        print(f"\n{Colors.CYAN}Session filter: {session.describe()}{Colors.NC}")
+        print("1. Set regions")
+        print(f"2. {'Show' if session.exclude_clones else 'Hide'} clones")
+        print(f"3. {'Show' if session.only_unowned else 'Hide'} owned files")
+        print("4. Clear")
+        print("5. Back")
+        
+        choice = input(f"{Colors.CYAN}Enter choice: {Colors.NC}").strip()
+        
+        if choice == '1':
+            regions = input(f"{Colors.CYAN}Regions, comma separated (empty for any): {Colors.NC}")
+            session.regions = [region.strip() for region in regions.split(',') if region.strip()]
+        elif choice == '2':
+            session.exclude_clones = not session.exclude_clones
+        elif choice == '3':
+            session.only_unowned = not session.only_unowned
+        elif choice == '4':
+            self.session_filter = session = SessionFilter()
+        elif choice == '5':
+            return
+        else:
+            print(f"{Colors.RED}Invalid choice{Colors.NC}")
+            return
+        
+        try:
+            save_session_filter(self.config, session)
+            print(f"{Colors.GREEN}Session filter: {session.describe()}{Colors.NC}")
+        except OSError as e:
+            print(f"{Colors.RED}Could not save the session filter: {e}{Colors.NC}")
+    
     def show_download_queue(self):
         """Show current download queue."""
         if not self.download_queue:
@@ -393,8 +417,11 @@ class ROMBrowser:
         print(f"\n{Colors.CYAN}Download Queue ({len(self.download_queue)} items){Colors.NC}")
         print("=" * 50)
         
-        for i, item in enumerate(self.download_queue, 1):
+        for i, line in enumerate(self.download_queue, 1):
+            item, notes = parse_line(line)
             print(f"{i:3d}. {item}")
+            for note in notes:
+                print(f"       {Colors.YELLOW}! {note}{Colors.NC}")
         
         print("=" * 50)
         print("Commands: 'c' - clear queue, 's' - save queue, 'b' - back")
@@ -414,11 +441,21 @@ class ROMBrowser:
             print(f"{Colors.RED}Invalid command{Colors.NC}")
     
     def add_to_queue(self, item: str):
-        """Add item to download queue."""
-        if item not in self.download_queue:
-            self.download_queue.append(item)
-            self.save_download_queue()
+        """Add item to download queue, noting any compatibility warnings for the user's setup."""
+        if item.startswith('file:'):
+            print(f"{Colors.YELLOW}Local files do not need downloading: {item}{Colors.NC}")
+        elif item not in [parse_line(line)[0] for line in self.download_queue]:
+            filename = urllib.parse.unquote(item.split('/')[-1])
+            warnings = self.compatibility.check(filename, platform_from_url(item))
+            # Only the new entry is sent, so entries another process queued or finished meanwhile are kept
+            try:
+                self.queue.add(str(self.queue_file.resolve()), [(item, warnings)])
+            except Exception as e:
+                self.log(f"{Colors.YELLOW}Warning: Could not save download queue: {e}{Colors.NC}")
+            self.load_download_queue()
             print(f"{Colors.GREEN}Added to queue: {item}{Colors.NC}")
+            for warning in warnings:
+                print(f"{Colors.YELLOW}Warning: {warning}{Colors.NC}")
         else:
             print(f"{Colors.YELLOW}Item already in queue: {item}{Colors.NC}")
     
@@ -427,20 +464,22 @@ class ROMBrowser:
         self.add_to_history(url)
         
         while True:
-            # Download and parse index
-            index_file = self.download_index(url)
-            if not index_file:
+            listing = self.list_directory(url)
+            if listing is None:
                 return False
             
-            directories, files = self.parse_index(index_file)
+            directories, files = listing
             
-            # Apply filters
+            # Apply filters, listing MAME clones under their parents
             directories = self.apply_filters(directories)
-            files = self.apply_filters(files)
+            files = self.session_filter.keep_names(self.apply_filters(files), self.config)
+            files, notes = self.group_sets(files)
             
             # Display current location
             print(f"\n{Colors.CYAN}Current location: {url}{Colors.NC}")
             print(f"{Colors.CYAN}Dataset: {self.current_dataset}{Colors.NC}")
+            if not self.session_filter.is_empty():
+                print(f"{Colors.YELLOW}Session filter: {self.session_filter.describe()}{Colors.NC}")
             
             # Display directories
             if directories:
@@ -465,7 +504,7 @@ class ROMBrowser:
             
             # Display files
             if files:
-                self.display_items(files, "Files")
+                self.display_items(files, "Files", notes=notes)
                 
                 choice = self.get_user_choice(len(files))
                 
@@ -524,11 +563,16 @@ class ROMBrowser:
             print(f"{Colors.RED}Failed to copy to clipboard: {e}{Colors.NC}")
     
     def switch_dataset(self):
-        """Switch between Redump and No-Intro datasets."""
+        """Switch between the Redump, No-Intro and TOSEC datasets and the local folders."""
+        local_roots = self.local.get_roots()
+        
         print(f"\n{Colors.CYAN}Current dataset: {self.current_dataset}{Colors.NC}")
         print("1. Redump")
         print("2. No-Intro")
-        print("3. Back")
+        print("3. TOSEC")
+        for i, root in enumerate(local_roots, 4):
+            print(f"{i}. Local: {root['name']}")
+        print(f"{len(local_roots) + 4}. Back")
         
         choice = input(f"{Colors.CYAN}Enter choice: {Colors.NC}").strip()
         
@@ -541,10 +585,27 @@ class ROMBrowser:
             self.current_url = self.base_url_noin
             print(f"{Colors.GREEN}Switched to No-Intro dataset{Colors.NC}")
         elif choice == '3':
+            self.current_dataset = "TOSEC"
+            self.current_url = self.base_url_tosec
+            print(f"{Colors.GREEN}Switched to TOSEC dataset{Colors.NC}")
+        elif choice.isdigit() and 4 <= int(choice) < len(local_roots) + 4:
+            self.switch_to_local(local_roots[int(choice) - 4]['name'])
+        elif choice == str(len(local_roots) + 4):
             return
         else:
             print(f"{Colors.RED}Invalid choice{Colors.NC}")
     
+    def switch_to_local(self, name: str) -> bool:
+        """Browse a configured local folder instead of the archive."""
+        url = self.local.root_url(name)
+        if not url:
+            print(f"{Colors.RED}No local folder named {name}{Colors.NC}")
+            return False
+        self.current_dataset = f"Local: {name}"
+        self.current_url = url
+        print(f"{Colors.GREEN}Switched to local folder {name}{Colors.NC}")
+        return True
+    
     def main_menu(self):
         """Main menu loop."""
         while True:
@@ -553,9 +614,10 @@ class ROMBrowser:
             print("1. Browse ROMs")
             print("2. Switch dataset")
             print("3. Manage filters")
-            print("4. View download queue")
-            print("5. Help")
-            print("6. Quit")
+            print("4. Session filter")
+            print("5. View download queue")
+            print("6. Help")
+            print("7. Quit")
             
             choice = input(f"\n{Colors.CYAN}Enter choice: {Colors.NC}").strip()
             
@@ -567,10 +629,12 @@ class ROMBrowser:
             elif choice == '3':
                 self.manage_filters()
             elif choice == '4':
-                self.show_download_queue()
+                self.manage_session_filter()
             elif choice == '5':
-                self.show_help()
+                self.show_download_queue()
             elif choice == '6':
+                self.show_help()
+            elif choice == '7':
                 break
             else:
                 print(f"{Colors.RED}Invalid choice{Colors.NC}")
@@ -594,15 +658,419 @@ class ROMBrowser:
             print(f"{Colors.RED}Fatal error: {e}{Colors.NC}")
 
 
+def format_duration(seconds: int) -> str:
+    minutes, seconds = divmod(seconds, 60)
+    hours, minutes = divmod(minutes, 60)
+    return f"{hours}h {minutes:02d}m" if hours else f"{minutes}m {seconds:02d}s" if minutes else f"{seconds}s"
+
+
+def variants_header(entry: Dict) -> str:
+    """The line above the files of a language variant group."""
+    count = len({variant['name'] for variant in entry['variants']})
+    return (f"{Colors.GREEN}{entry['title']}{Colors.NC}  {count} language variants"
+            f" ({', '.join(entry['spoken_languages'])})")
+
+
+def title_header(group: Dict) -> str:
+    """The line above the files of a title group, e.g. "Final Fantasy VII  USA, Europe  3 discs"."""
+    parts = [", ".join(group['regions'])] if group['regions'] else []
+    if group['discs']:
+        parts.append(f"{len(group['discs'])} discs" if len(group['discs']) > 1 else f"disc {group['discs'][0]}")
+    files = len({file['name'] for file in group['files']})
+    parts.append(f"{files} files" if files > 1 else "1 file")
+    return f"{Colors.GREEN}{group['title']}{Colors.NC}  " + "  ".join(parts)
+
+
+def sort_listing(entries: List[Dict], sort: Optional[str], reverse=False) -> List[Dict]:
+    """Sort entries by a SORT_KEYS key, or leave them in their order without one."""
+    if not sort:
+        return entries
+    return sort_entries(entries, sort, reverse, RomConfig().get('matching.region_priority', []))
+
+
+def session_filtered(entries: List[Dict]) -> List[Dict]:
+    """Apply the session filter to listing or search entries, noting on stderr that it is on."""
+    config = RomConfig()
+    session = load_session_filter(config)
+    if session.is_empty():
+        return entries
+    print(f"{Colors.YELLOW}Session filter: {session.describe()} (clear it with 'rom_browser.py session clear'){Colors.NC}",
+          file=sys.stderr)
+    return session.filter_entries(entries, config)
+
+
+def mark_owned(entries: List[Dict], hide_owned=False) -> List[Dict]:
+    """Set "owned" on the files the library or the downloads folder already has; hide_owned drops them."""
+    keys = owned_keys(RomConfig())
+    for entry in entries:
+        if not entry.get('is_dir'):
+            entry['owned'] = owned_key(entry['name']) in keys
+    return [entry for entry in entries if not (hide_owned and entry.get('owned'))]
+
+
+def run_search(query: str, platform: Optional[str], limit: int, as_json=False,
+               name_filter: Optional[NameFilter] = None, group_languages=False, grouped=False,
+               sort: Optional[str] = None, reverse=False, hide_owned=False) -> int:
+    """Search every configured source and print the merged results. Returns a process exit code."""
+    from multi_search import search_sources
+    
+    config = RomConfig()
+    apply_proxy_settings(config)
+    results = search_sources(config, query, platform, limit, log=lambda message: print(message, file=sys.stderr))
+    results = mark_owned(session_filtered(filter_entries(results, name_filter or NameFilter())), hide_owned)
+    results = sort_listing(results, sort, reverse)
+    if grouped:
+        results = group_by_title(results)
+    elif group_languages:
+        results = group_language_variants(results)
+    
+    if as_json:
+        print(json.dumps(results, indent=2))
+        return 0
+    
+    if not platform:
+        print(f"{Colors.YELLOW}Only archive.org items and local folders were searched; "
+              f"add --platform \"Redump/Sony - PlayStation\" to search the archive and its mirrors{Colors.NC}")
+    if not results:
+        print(f"{Colors.RED}Nothing matches '{query}'{Colors.NC}")
+        return 2
+    
+    name = None
+    for group in results:
+        if group.get('files'):
+            print(f"\n{title_header(group)}")
+        elif group.get('variants'):
+            print(f"\n{variants_header(group)}")
+        for result in group.get('files') or group.get('variants') or [group]:
+            if result['name'] != name:
+                name = result['name']
+                owned = f" {Colors.YELLOW}(owned){Colors.NC}" if result.get('owned') else ""
+                print(f"\n{Colors.GREEN}{name}{Colors.NC}{owned}")
+            speed = f"{result['estimated_bps'] / 1024 / 1024:.1f} MB/s" if result['estimated_bps'] else "speed unknown"
+            eta = f", about {format_duration(result['estimated_seconds'])}" if result['estimated_seconds'] else ""
+            print(f"  {Colors.CYAN}{result['source']}{Colors.NC} ({result['host']}) "
+                  f"{result['size_text'] or 'size unknown'}, {speed}{eta}")
+            print(f"    {result['url']}")
+    return 0
+
+
+def percent(part: int, whole: int) -> str:
+    return f"{part * 100 / whole:.0f}%" if whole else "-"
+
+
+def run_status(as_json=False) -> int:
+    """Print a one-screen overview: queue, jobs, library, metadata, caches and pinned platforms."""
+    from status_report import collect_status
+
+    status = collect_status(RomConfig())
+    if as_json:
+        print(json.dumps(status, indent=2))
+        return 0
+
+    queue = status['queue']
+    eta = f", about {format_duration(queue['eta_seconds'])}" if queue['eta_seconds'] is not None else ""
+    sized = f" ({format_size(queue['bytes'])} in {queue['sized']} files of known size{eta})" if queue['sized'] else ""
+    print(f"{Colors.CYAN}Queue{Colors.NC}      {queue['entries']} entries{sized}")
+    if queue['titles']:
+        print(f"           {queue['titles']} titles to match at download time")
+
+    daemon = status['daemon']
+    print(f"{Colors.CYAN}Daemon{Colors.NC}     " + (f"running (pid {daemon['pid']})" if daemon['running'] else "not running"))
+
+    jobs = status['jobs']
+    print(f"{Colors.CYAN}Jobs{Colors.NC}       " + (f"{len(jobs)} journaled" if jobs else "none"))
+    for job in jobs:
+        state = job['state'] or {}
+        progress = f" {state['done']}/{state['total']}" if state.get('total') else ""
+        label = f"{Colors.GREEN}running{Colors.NC}" if job['running'] else f"{Colors.YELLOW}interrupted{Colors.NC}"
+        print(f"           {label} {job['kind']} {job['label']}{progress} (since {job['started']})")
+
+    library = status['library']
+    print(f"{Colors.CYAN}Library{Colors.NC}    {library['files']} files, {format_size(library['bytes'])}, "
+          f"{library['platforms']} platforms")
+    if library['vanished_sources'] or library['outdated']:
+        print(f"           {Colors.YELLOW}{library['vanished_sources']} sources vanished, "
+              f"{library['outdated']} outdated{Colors.NC}")
+
+    metadata = status['metadata']
+    games = metadata['games']
+    print(f"{Colors.CYAN}Metadata{Colors.NC}   {games} games: covers {percent(metadata['covers'], games)}, "
+          f"summaries {percent(metadata['summaries'], games)}, ratings {percent(metadata['ratings'], games)}")
+
+    print(f"{Colors.CYAN}Caches{Colors.NC}     " + ", ".join(
+        f"{cache['name']} {format_size(cache['bytes'])} ({cache['entries']})" for cache in status['caches']))
+
+    if status['platforms']:
+        print(f"{Colors.CYAN}Platforms{Colors.NC}")
+    for platform in status['platforms']:
+        refreshed = (f"listed {platform['refreshed'].replace('T', ' ')}, {platform['files']} files"
+                     if platform['refreshed'] else f"{Colors.YELLOW}never listed{Colors.NC}")
+        print(f"           {platform['path']}: {refreshed}")
+    return 0
+
+
+def list_archive_folder(path: str) -> Optional[List[Dict]]:
+    """Get the entries of an archive folder (from the listing cache when fresh), or None when it cannot be listed."""
+    config = RomConfig()
+    apply_proxy_settings(config)
+    log = lambda message: print(message, file=sys.stderr)
+    source = HttpIndexSource("myrient", config.get_archive_url(), MirrorSet(config, log=log), config)
+    try:
+        entries = source.list_dir(path.strip('/') + '/')
+    except Exception as e:
+        print(f"{Colors.RED}Failed to list {path}: {e}{Colors.NC}", file=sys.stderr)
+        report_error(f"Failed to list {path}: {e}", e)
+        return None
+    
+    for entry in entries:
+        entry['url'] = source.file_url(entry['path'])
+        entry['size_text'] = format_size(entry['size'])
+        if not entry['is_dir']:
+            entry.update(name_fields(entry['name']))
+    return entries
+
+
+def print_entries(entries: List[Dict]):
+    """Print listing entries, with their sizes and dates, under the header of their group if any."""
+    for entry in entries:
+        if entry['is_dir']:
+            print(f"{Colors.CYAN}{entry['name']}/{Colors.NC}")
+            continue
+        members = entry.get('files') or entry.get('variants')
+        if entry.get('files'):
+            print(title_header(entry))
+        elif members:
+            print(variants_header(entry))
+        for file in members or [entry]:
+            modified = file['modified'].replace('T', ' ')[:16] if file.get('modified') else None
+            details = "  ".join(part for part in (file['size_text'], modified) if part)
+            indent = "  " if members else ""
+            owned = f"  {Colors.YELLOW}(owned){Colors.NC}" if file.get('owned') else ""
+            print(f"{indent}{file['name']}  {Colors.GREEN}{details}{Colors.NC}{owned}" if details
+                  else f"{indent}{file['name']}{owned}")
+
+
+def run_list(path: str, as_json=False, name_filter: Optional[NameFilter] = None, group_languages=False,
+             grouped=False, sort: Optional[str] = None, reverse=False, hide_owned=False) -> int:
+    """List an archive folder with the sizes and dates its index shows. Returns a process exit code."""
+    entries = list_archive_folder(path)
+    if entries is None:
+        return 1
+    
+    entries = mark_owned(session_filtered(filter_entries(entries, name_filter or NameFilter())), hide_owned)
+    entries = sort_listing(entries, sort, reverse)
+    if grouped:
+        entries = group_by_title(entries)
+    elif group_languages:
+        entries = group_language_variants(entries)
+    
+    if as_json:
+        print(json.dumps(entries, indent=2))
+    else:
+        print_entries(entries)
+    return 0
+
+
+def run_platform_search(path: str, query: str, as_json=False, sort: Optional[str] = None, reverse=False,
+                        hide_owned=False) -> int:
+    """Search the files of one archive folder, allowing for typos, best match first. Returns a process exit code."""
+    entries = list_archive_folder(path)
+    if entries is None:
+        return 1
+    
+    matches = sort_listing(mark_owned(session_filtered(fuzzy_search(entries, query)), hide_owned), sort, reverse)
+    if as_json:
+        print(json.dumps(matches, indent=2))
+        return 0
+    
+    if not matches:
+        print(f"{Colors.RED}Nothing in {path} matches '{query}'{Colors.NC}")
+        return 2
+    print_entries(matches)
+    print(f"{Colors.CYAN}{len(matches)} matches for '{query}' in {path}{Colors.NC}")
+    return 0
+
+
+def run_inspect(target: str, as_json=False) -> int:
+    """List the files in a ZIP archive on the server, with their sizes and CRC32s, without downloading it.
+    
+    target is a URL or a path in the archive. Members whose CRC32 and size a
+    DAT lists are shown with its title. Returns a process exit code.
+    """
+    from dat_index import DatIndex
+    from remote_zip import inspect_archive
+    
+    config = RomConfig()
+    apply_proxy_settings(config)
+    log = lambda message: print(message, file=sys.stderr)
+    mirrors = MirrorSet(config, log=log)
+    url = target if '://' in target else \
+        HttpIndexSource("myrient", config.get_archive_url(), mirrors, config).file_url(target.strip('/'))
+    try:
+        listing = inspect_archive(mirrors, url)
+    except (ValueError, requests.RequestException) as e:
+        print(f"{Colors.RED}Could not inspect {url}: {e}{Colors.NC}", file=sys.stderr)
+        report_error(f"Could not inspect {url}: {e}", e)
+        return 1
+    
+    dats = DatIndex()
+    dats.load_directory(config.get_path('paths.dat_directory'), log=log)
+    for member in listing['members']:
+        match = None if member['is_dir'] else dats.lookup({"crc32": member['crc32'], "size": member['size']})
+        member['dat'] = {"title": match['title'], "rom": match['rom'], "verification": match['verification']} \
+            if match else None
+    
+    if as_json:
+        print(json.dumps(listing, indent=2))
+        return 0
+    
+    files = [member for member in listing['members'] if not member['is_dir']]
+    name = urllib.parse.unquote(url.rstrip('/').rsplit('/', 1)[-1])
+    print(f"{Colors.CYAN}{name}{Colors.NC}  {format_size(listing['size'])}, {len(files)} files, "
+          f"{format_size(sum(member['size'] for member in files))} unpacked")
+    for member in files:
+        dat = f"  {Colors.GREEN}{member['dat']['verification']}: {member['dat']['title']}{Colors.NC}" \
+            if member['dat'] else ""
+        print(f"  {member['name']}  {format_size(member['size'])}  crc {member['crc32']}  {member['method']}{dat}")
+    return 0
+
+
+def run_session(action: str, args, as_json=False) -> int:
+    """Show, change or clear the session filter. Returns a process exit code."""
+    config = RomConfig()
+    session = load_session_filter(config)
+    if action == 'set':
+        if args.region or args.any_region:
+            session.regions = [] if args.any_region else [r.strip() for value in args.region for r in value.split(',')
+                                                           if r.strip()]
+        if args.exclude_clones is not None:
+            session.exclude_clones = args.exclude_clones
+        if args.only_unowned is not None:
+            session.only_unowned = args.only_unowned
+    elif action == 'clear':
+        session = SessionFilter()
+    
+    if action != 'show':
+        try:
+            save_session_filter(config, session)
+        except OSError as e:
+            print(f"{Colors.RED}Could not save the session filter: {e}{Colors.NC}", file=sys.stderr)
+            return 1
+    
+    if as_json:
+        print(json.dumps(dict(vars(session), active=not session.is_empty())))
+    elif session.is_empty():
+        print(f"{Colors.GREEN}No session filter{Colors.NC}")
+    else:
+        print(f"{Colors.GREEN}Session filter: {session.describe()}{Colors.NC} ({session_file(config)})")
+    return 0
+
+
 def main():
     """Main entry point."""
     parser = argparse.ArgumentParser(description='Interactive ROM browser')
-    parser.add_argument('--dataset', choices=['redump', 'no-intro'], 
+    add_config_argument(parser)
+    parser.add_argument('--dataset', choices=['redump', 'no-intro', 'tosec'], 
                        help='Start with specific dataset')
     parser.add_argument('--url', help='Start with specific URL')
+    parser.add_argument('--local', metavar='NAME', help='Start in a local folder from sources.local.directories')
+    parser.add_argument('--platform', metavar='PATH', dest='search_platform',
+                        help='With --search, the archive folder to search, e.g. "No-Intro/Nintendo - Game Boy"')
+    parser.add_argument('--search', metavar='QUERY', help="Search a platform's files, allowing for typos")
+    parser.add_argument('--json', action='store_true', dest='search_json', help='With --search, print the matches as JSON')
+    add_sort_arguments(parser)
+    parser.add_argument('--hide-owned', action='store_true', help='With --search, leave out files already downloaded')
+    subparsers = parser.add_subparsers(dest='command')
+    
+    identify_parser = subparsers.add_parser('identify', help='Identify a ROM file by its hashes')
+    identify_parser.add_argument('path', help='File or ZIP archive to identify')
+    identify_parser.add_argument('--json', action='store_true', help='Print the results as JSON')
+    identify_parser.add_argument('--offline', action='store_true', help='Only match against local DAT files')
+    
+    search_parser = subparsers.add_parser('search', help='Search every configured source and mirror at once')
+    search_parser.add_argument('query', help='Words that must all appear in the file name')
+    search_parser.add_argument('--platform', metavar='PATH',
+                               help='Archive folder to search, e.g. "Redump/Sony - PlayStation"')
+    search_parser.add_argument('--limit', type=int, default=50, help='Most matches to take from each source')
+    search_parser.add_argument('--json', action='store_true', help='Print the results as JSON')
+    add_name_filter_arguments(search_parser)
+    search_parser.add_argument('--group-languages', action='store_true',
+                               help='Show files that differ only by language tags as one game')
+    search_parser.add_argument('--grouped', action='store_true',
+                               help='Show every region, revision and disc of a title as one group')
+    add_sort_arguments(search_parser)
+    search_parser.add_argument('--hide-owned', action='store_true',
+                               help='Leave out files the library or the downloads folder already has')
+    
+    list_parser = subparsers.add_parser('list', help='List an archive folder with file sizes and dates')
+    list_parser.add_argument('path', help='Archive folder, e.g. "Redump/Sony - PlayStation"')
+    list_parser.add_argument('--json', action='store_true', help='Print the entries as JSON')
+    add_name_filter_arguments(list_parser)
+    list_parser.add_argument('--group-languages', action='store_true',
+                             help='Show files that differ only by language tags as one game')
+    list_parser.add_argument('--grouped', action='store_true',
+                             help='Show every region, revision and disc of a title as one group')
+    add_sort_arguments(list_parser)
+    list_parser.add_argument('--hide-owned', action='store_true',
+                             help='Leave out files the library or the downloads folder already has')
+    
+    inspect_parser = subparsers.add_parser('inspect', help='List the files in a remote ZIP archive without downloading it')
+    inspect_parser.add_argument('target', help='URL or archive path, e.g. "Redump/Sony - PlayStation/Game (USA).zip"')
+    inspect_parser.add_argument('--json', action='store_true', help='Print the archive listing as JSON')
+    
+    status_parser = subparsers.add_parser('status', help='Show the queue, jobs, library, metadata and caches at a glance')
+    status_parser.add_argument('--json', action='store_true', help='Print the overview as JSON')
+    
+    session_parser = subparsers.add_parser('session',
+                                           help='Show or change the filter browsing, search and queueing use until cleared')
+    session_parser.add_argument('action', nargs='?', choices=['show', 'set', 'clear'], default='show')
+    session_parser.add_argument('--region', action='append', default=[],
+                                help='With set, only releases from these regions, e.g. USA or "USA,World"')
+    session_parser.add_argument('--any-region', action='store_true', help='With set, drop the region set')
+    clones = session_parser.add_mutually_exclusive_group()
+    clones.add_argument('--exclude-clones', action='store_true', default=None,
+                        help='With set, keep one release per game, from the best region')
+    clones.add_argument('--keep-clones', action='store_false', dest='exclude_clones', default=None,
+                        help='With set, keep every release')
+    owned = session_parser.add_mutually_exclusive_group()
+    owned.add_argument('--only-unowned', action='store_true', default=None,
+                       help='With set, leave out files already downloaded')
+    owned.add_argument('--include-owned', action='store_false', dest='only_unowned', default=None,
+                       help='With set, show owned files too')
+    session_parser.add_argument('--json', action='store_true', help='Print the filter as JSON')
     
     args = parser.parse_args()
     
+    if args.command == 'session':
+        sys.exit(run_session(args.action, args, as_json=args.json))
+    
+    if args.command == 'status':
+        sys.exit(run_status(as_json=args.json))
+    
+    if args.command == 'inspect':
+        sys.exit(run_inspect(args.target, as_json=args.json))
+    
+    if args.command == 'identify':
+        from rom_identify import run_identify
+        sys.exit(run_identify(args.path, as_json=args.json, online=not args.offline))
+    
+    if args.command == 'search':
+        sys.exit(run_search(args.query, args.platform, args.limit, as_json=args.json,
+                            name_filter=name_filter_from_args(args), group_languages=args.group_languages,
+                            grouped=args.grouped, sort=args.sort, reverse=args.reverse,
+                            hide_owned=args.hide_owned))
+    
+    if args.command == 'list':
+        sys.exit(run_list(args.path, as_json=args.json, name_filter=name_filter_from_args(args),
+                          group_languages=args.group_languages, grouped=args.grouped, sort=args.sort,
+                          reverse=args.reverse, hide_owned=args.hide_owned))
+    
+    if args.search or args.search_platform:
+        if not (args.search and args.search_platform):
+            parser.error("--platform and --search go together")
+        sys.exit(run_platform_search(args.search_platform, args.search, as_json=args.search_json, sort=args.sort,
+                                     reverse=args.reverse, hide_owned=args.hide_owned))
+    
     browser = ROMBrowser()
     
     # Apply command line arguments
@@ -610,10 +1078,16 @@ def main():
         if args.dataset == 'redump':
             browser.current_dataset = "Redump"
             browser.current_url = browser.base_url_redump
+        elif args.dataset == 'tosec':
+            browser.current_dataset = "TOSEC"
+            browser.current_url = browser.base_url_tosec
         else:
             browser.current_dataset = "No-Intro"
             browser.current_url = browser.base_url_noin
     
+    if args.local and not browser.switch_to_local(args.local):
+        sys.exit(2)
+    
     if args.url:
         browser.current_url = args.url
     
@@ -621,4 +1095,5 @@ def main():
 
 
 if __name__ == "__main__":
+    install_error_reporting()
     main()
@@ -0,0 +1,184 @@
+#!/usr/bin/env python3
+"""
+ROM Browser Client
+The public Python API, for frontends, bots and other programs that want to
+list platforms, browse, search and download without running the scripts and
+parsing what they print. Every method is a coroutine; the blocking work runs
+in a worker thread, so an asyncio application stays responsive.
+
+    import asyncio, sys
+    sys.path.insert(0, "rom-browser/scripts/rom-sourcing")
+    from rom_browser_client import RomBrowserClient
+
+    async def main():
+        client = RomBrowserClient()
+        platforms = await client.platforms("Redump")
+        entries = await client.browse("Redump/Sony - PlayStation")
+        results = await client.search("Resident Evil", platform="Redump/Sony - PlayStation")
+        download = await client.download(results[0].url)
+        print(download.path)
+
+    asyncio.run(main())
+
+The client reads the same config as the scripts (rom_browser.json, or the
+file given to RomBrowserClient) and records downloads in the same library.
+Results are the dataclasses below; failures raise RomBrowserError.
+"""
+
+import asyncio
+import contextlib
+import functools
+import io
+import sys
+import urllib.parse
+from dataclasses import dataclass
+from datetime import datetime
+from pathlib import Path
+from typing import Callable, List, Optional
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from datasets import DATASET_FOLDERS
+from download_paths import resolve_destination
+from mirrors import MirrorSet
+from multi_search import DEFAULT_LIMIT, search_sources
+from rom_config import RomConfig
+from rom_downloader import ROMDownloader
+from rom_sources import create_sources, format_size
+
+
+class RomBrowserError(Exception):
+    """A listing, search or download failed."""
+
+
+@dataclass
+class Platform:
+    name: str
+    source: str         # "myrient", "internet_archive" or "local"
+    path: str           # pass to browse() together with source
+
+
+@dataclass
+class Entry:
+    name: str
+    path: str
+    is_dir: bool
+    size: Optional[int]
+    size_text: Optional[str]
+    modified: Optional[str]     # ISO timestamp, when the source shows one
+    url: Optional[str]          # None for directories
+
+
+@dataclass
+class SearchResult:
+    name: str
+    source: str
+    host: str
+    url: str
+    size: Optional[int]
+    size_text: Optional[str]
+    estimated_bps: Optional[int]        # from the speeds sampled on earlier downloads from host
+    estimated_seconds: Optional[int]
+
+
+@dataclass
+class Download:
+    url: str
+    path: Path      # where the file was saved, before any post-download plugin moved it
+
+
+class RomBrowserClient:
+    def __init__(self, config: Optional[RomConfig] = None, log: Callable[[str], None] = lambda message: None):
+        self.config = config or RomConfig()
+        self.log = log
+        self.mirrors = MirrorSet(self.config, log=log)
+        self.sources = create_sources(self.config, self.mirrors, log=log)
+        self.downloader = None
+
+    async def run(self, function, *args, **kwargs):
+        loop = asyncio.get_event_loop()
+        return await loop.run_in_executor(None, functools.partial(function, *args, **kwargs))
+
+    def source(self, name: str):
+        if name not in self.sources:
+            raise RomBrowserError(f"No source named {name}; configured: {', '.join(self.sources)}")
+        return self.sources[name]
+
+    async def platforms(self, dataset: Optional[str] = None) -> List[Platform]:
+        """List the platforms of an archive dataset ("Redump", "No-Intro", "TOSEC").
+
+        Without a dataset, list those of every dataset plus the configured
+        Internet Archive items and local folders.
+        """
+        return await self.run(self.list_platforms, dataset)
+
+    def list_platforms(self, dataset: Optional[str]) -> List[Platform]:
+        archive = self.sources["myrient"]
+        try:
+            if dataset:
+                datasets = [dataset.strip('/') + '/']
+            else:
+                # Only the datasets this archive (or mirror) carries
+                datasets = [entry['path'] for entry in archive.list_dir('')
+                            if entry['is_dir'] and entry['name'] in DATASET_FOLDERS]
+            platforms = []
+            for path in datasets:
+                platforms += [Platform(entry['name'], archive.name, entry['path'])
+                              for entry in archive.list_dir(path) if entry['is_dir']]
+        except Exception as e:
+            raise RomBrowserError(f"Could not list the platforms of {dataset or 'the archive'}: {e}") from e
+
+        if not dataset:
+            for source in self.sources.values():
+                if source is not archive:
+                    platforms += [Platform(root['name'], source.name, root['path']) for root in source.list_roots()]
+        return platforms
+
+    async def browse(self, path: str, source: str = "myrient") -> List[Entry]:
+        """List a folder of a source, e.g. "Redump/Sony - PlayStation", directories first."""
+        return await self.run(self.list_entries, path, source)
+
+    def list_entries(self, path: str, source_name: str) -> List[Entry]:
+        source = self.source(source_name)
+        try:
+            entries = source.list_dir(path.strip('/') + '/' if path.strip('/') else '')
+        except Exception as e:
+            raise RomBrowserError(f"Could not list {path}: {e}") from e
+        return [Entry(entry['name'], entry['path'], entry['is_dir'], entry['size'], format_size(entry['size']),
+                      entry.get('modified'), None if entry['is_dir'] else source.file_url(entry['path']))
+                for entry in entries]
+
+    async def search(self, query: str, platform: Optional[str] = None, limit: int = DEFAULT_LIMIT) -> List[SearchResult]:
+        """Search every configured source for files whose names contain all the query's words.
+
+        The archive and its mirrors are only searched within platform, an
+        archive folder. Local copies come first, then the fastest hosts.
+        """
+        results = await self.run(search_sources, self.config, query, platform, limit, self.log)
+        return [SearchResult(**result) for result in results]
+
+    async def download(self, url: str, filename: Optional[str] = None) -> Download:
+        """Download a file into the download directory, resuming an interrupted download.
+
+        A file that is already there is not downloaded again.
+        """
+        return await self.run(self.download_file, url, filename)
+
+    def download_file(self, url: str, filename: Optional[str]) -> Download:
+        if self.downloader is None:
+            # The downloader prints its session banner while starting; the log file still gets it
+            with contextlib.redirect_stdout(io.StringIO()):
+                self.downloader = ROMDownloader(self.config)
+            self.downloader.log_message = self.log_download
+        filename = filename or urllib.parse.unquote(url.rstrip('/').split('/')[-1])
+        downloader = self.downloader
+        path = resolve_destination(downloader.download_dir, downloader.template_for(url), url, filename, downloader.library)
+        if not downloader.download_file(url, filename):
+            raise RomBrowserError(f"Could not download {url}; the download log has the details")
+        return Download(url, path)
+
+    def log_download(self, message: str):
+        """Keep the downloader's log file, but send its messages to the client's log instead of stdout."""
+        with self.downloader.log_lock:
+            with open(self.downloader.log_file, 'a', encoding='utf-8') as f:
+                f.write(f"[{datetime.now().strftime('%Y-%m-%d %H:%M:%S')}] {message}\n")
+        self.log(message)
@@ -0,0 +1,104 @@
+#!/usr/bin/env python3
+"""
+ROM Daemon
+Owns the library database and the download queue while it runs, so the GUI
+and command-line scripts used at the same time never write them at once (see
+daemon_ipc). The GUI starts it on launch; it can also be run by hand.
+"""
+
+import argparse
+import os
+import sys
+import threading
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from daemon_ipc import Daemon, DaemonError, daemon_address, open_client
+from download_queue import QueueFile
+from library_db import LibraryDB
+from rom_config import RomConfig, add_config_argument
+
+
+class Colors:
+    """ANSI color codes for terminal output."""
+    RED = '\033[0;31m'
+    GREEN = '\033[0;32m'
+    YELLOW = '\033[1;33m'
+    CYAN = '\033[0;36m'
+    NC = '\033[0m'  # No Color
+
+
+class Control:
+    """Calls about the daemon itself."""
+
+    def __init__(self, daemon: Daemon):
+        self.daemon = daemon
+
+    def status(self):
+        # The library served, so scripts configured with another one use theirs directly
+        return {"pid": os.getpid(), "address": self.daemon.address,
+                "library_db": str(self.daemon.targets["library"].db_path)}
+
+    def stop(self):
+        # Stop once the reply is on its way
+        threading.Timer(0.1, self.daemon.stop).start()
+        return True
+
+
+def serve(config: RomConfig, exit_with_stdin=False) -> int:
+    daemon = Daemon(config, {"library": LibraryDB(config=config), "queue": QueueFile()},
+                    log=lambda message: print(message, file=sys.stderr))
+    daemon.targets["daemon"] = Control(daemon)
+    try:
+        daemon.start()
+    except DaemonError as e:
+        print(f"{Colors.YELLOW}{e}{Colors.NC}", file=sys.stderr)
+        return 2
+
+    # The GUI keeps stdin open while it runs, so the daemon never outlives it
+    if exit_with_stdin:
+        def watch_stdin():
+            sys.stdin.read()
+            daemon.stop()
+        threading.Thread(target=watch_stdin, daemon=True).start()
+
+    try:
+        daemon.serve()
+    except KeyboardInterrupt:
+        daemon.stop()
+    return 0
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Share the library and download queue between the GUI and scripts')
+    add_config_argument(parser)
+    subparsers = parser.add_subparsers(dest='command', required=True)
+
+    serve_parser = subparsers.add_parser('serve', help='Run the daemon until stopped')
+    serve_parser.add_argument('--exit-with-stdin', action='store_true',
+                              help='Stop when stdin closes, i.e. when the process that started it exits')
+    subparsers.add_parser('status', help='Show whether a daemon is running')
+    subparsers.add_parser('stop', help='Stop the running daemon')
+
+    args = parser.parse_args()
+    config = RomConfig()
+
+    if args.command == 'serve':
+        sys.exit(serve(config, args.exit_with_stdin))
+
+    try:
+        client = open_client(config)
+    except Exception:
+        print(f"{Colors.YELLOW}No daemon is running at {daemon_address(config)}{Colors.NC}")
+        sys.exit(1)
+
+    if args.command == 'status':
+        status = client.call("daemon.status")
+        print(f"{Colors.GREEN}Daemon running (pid {status['pid']}) at {status['address']}{Colors.NC}")
+    else:
+        client.call("daemon.stop")
+        print(f"{Colors.GREEN}Daemon stopped{Colors.NC}")
+
+
+if __name__ == "__main__":
+    main()
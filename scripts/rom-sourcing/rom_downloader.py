@@ -6,6 +6,8 @@ Downloads ROMs from ROM archives based on platform selection and download queue
 Usage: python rom_downloader.py [platform] [subtype]
 """
 
+import contextlib
+import fnmatch
 import os
 import sys
 import json
@@ -20,8 +22,40 @@ import shutil
 from typing import List, Dict, Optional, Tuple
 import argparse
 import threading
+import re
+import zipfile
+import zlib
 from concurrent.futures import ThreadPoolExecutor, as_completed
 
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from archive_index import SIZE_PATTERN, parse_size
+from batch_sessions import BatchSessions
+from compatibility import CompatibilityChecker, release_regions
+from dat_index import DatIndex, hash_entries
+from datasets import PLATFORM_SHORT_NAMES, platform_of, strip_extension
+from download_paths import dataset_from_url, platform_template, resolve_destination
+from download_queue import HASH_FIELDS, missing_roms, open_queue, read_export, write_export
+from filter_presets import FilterPresets, apply_presets, validate_preset
+from job_journal import JobJournal
+from job_progress import ProgressTracker, default_reporter
+from library_db import NON_DOWNLOAD_SUFFIXES, open_library, platform_from_url
+from match_scoring import MatchScorer
+from mame_sets import MameSets, set_name
+from mirrors import MirrorSet
+from piece_checkpoints import PieceCheckpoints
+from plugins import PluginManager
+from proxy import apply_proxy_settings
+from response_cache import listed_size
+from retry_policy import RetryPolicy, TransientError
+from rom_config import ReadOnlyError, RomConfig, add_config_argument, set_read_only
+from rom_sources import HttpIndexSource, InternetArchiveSource, format_size
+from romignore import RomIgnore
+from script_errors import fail, install_error_reporting, report_error
+from session_filter import load_session_filter
+from speed_sampler import SpeedSampler, host_of
+from stream_hashes import DownloadHasher
+from transliteration import transliterated_match
+
 
 class Colors:
     """ANSI color codes for terminal output."""
@@ -34,123 +68,62 @@ class Colors:
     NC = '\033[0m'  # No Color
 
 
+# ROM file extensions
+ROM_EXTENSIONS = ('.zip', '.7z', '.rar', '.iso', '.bin', '.cue', '.img', '.mdf', '.mds')
+
+# Mirrored folders keep the archive's layout under the download folder
+MIRROR_TEMPLATE = "{folder}/{filename}"
+
+
 class ROMDownloader:
-    def __init__(self):
+    def __init__(self, config: Optional[RomConfig] = None):
+        # Parallel downloads log at once, and each line has to come out whole
+        self.log_lock = threading.Lock()
+        
         # Configuration
-        self.base_url_redump = "https://myrient.erista.me/files/Redump/"
-        self.base_url_noin = "https://myrient.erista.me/files/No-Intro/"
+        self.config = config or RomConfig()
+        apply_proxy_settings(self.config)
+        self.scorer = MatchScorer.from_config(self.config)
+        self.transliteration = self.config.get('matching.transliteration', True)
+        self.library = open_library(self.config)
+        self.piece_size = int(self.config.get('downloads.checkpoint_piece_mb', 8) * 1024 * 1024)
+        self.path_template = self.config.get('downloads.path_template', '{platform}/{filename}')
+        self.platform_templates = self.config.get('downloads.platform_templates', {}) or {}
+        self.max_concurrent = max(int(self.config.get('downloads.max_concurrent', 3) or 1), 1)
+        self.verify_checksums = self.config.get('downloads.verify_checksums', True)
+        self.free_space_reserve = int(self.config.get('downloads.free_space_reserve_mb', 512) * 1024 * 1024)
+        # Set by --allow-low-space: warn about a full disk instead of refusing to download
+        self.allow_low_space = False
+        # Set by --max-total-size: how much one queue run may download
+        self.max_total_size: Optional[int] = None
+        # Set by --pick-matches: confirm each title's match, remembering a different pick
+        self.pick_matches = False
+        self.file_sizes: Dict[str, int] = {}
+        self.stats_lock = threading.Lock()
+        self.mame_set_type = self.config.get('mame.set_type', 'split')
+        self.mame = None
+        self.dats = None
+        self.compatibility = CompatibilityChecker(self.config)
+        self.retry = RetryPolicy.from_config(self.config, log=self.log_message)
+        self.mirrors = MirrorSet(self.config, self.retry, log=self.log_message)
+        self.source = HttpIndexSource("myrient", self.config.get_archive_url(), self.mirrors, self.config)
+        self.plugins = PluginManager(self.config, log=self.log_message)
+        self.journal = JobJournal(self.config)
+        # The batch session of the running --queue, --match, mirror or import, which resume continues
+        self.batch_sessions = BatchSessions(self.config)
+        self.batch = None
+        self.session_id = datetime.now().isoformat(timespec='seconds')
+        self.base_url_redump = self.config.get_archive_url() + "Redump/"
+        self.base_url_noin = self.config.get_archive_url() + "No-Intro/"
+        self.base_url_tosec = self.config.get_archive_url() + "TOSEC/"
         self.rom_archive_base_url = self.base_url_redump
-        self.download_dir = Path("./downloads")
+        self.download_dir = self.config.get_path('paths.download_directory')
         self.queue_file = Path("./download_queue")
-        self.log_file = Path("./download_log.txt")
-        self.temp_dir = Path("./temp")
-        
-        # Available platforms and their subtypes
-        self.platforms = {
-            "Nintendo - Nintendo Entertainment System": "NES",
-            "Nintendo - Super Nintendo Entertainment System": "SNES",
-            "Nintendo - Nintendo 64": "N64",
-            "Nintendo - Nintendo GameCube": "NGC",
-            "Nintendo - Nintendo Wii": "WII",
-            "Nintendo - Nintendo Wii U": "WIIU",
-            "Nintendo - Nintendo Switch": "NSW",
-            "Sony - PlayStation": "PS1",
-            "Sony - PlayStation 2": "PS2",
-            "Sony - PlayStation 3": "PS3",
-            "Sony - PlayStation 4": "PS4",
-            "Sony - PlayStation 5": "PS5",
-            "Sony - PlayStation Portable": "PSP",
-            "Sony - PlayStation Vita": "PSV",
-            "Microsoft - Xbox": "XBOX",
-            "Microsoft - Xbox 360": "X360",
-            "Microsoft - Xbox One": "XONE",
-            "Microsoft - Xbox Series X|S": "XSX",
-            "Sega - Master System": "SMS",
-            "Sega - Mega Drive - Genesis": "MD",
-            "Sega - Sega CD": "SCD",
-            "Sega - Sega 32X": "32X",
-            "Sega - Sega Saturn": "SAT",
-            "Sega - Dreamcast": "DC",
-            "Atari - 2600": "A2600",
-            "Atari - 5200": "A5200",
-            "Atari - 7800": "A7800",
-            "Atari - Jaguar": "JAG",
-            "Atari - Lynx": "LYNX",
-            "NEC - PC Engine - TurboGrafx-16": "PCE",
-            "NEC - PC Engine CD - TurboGrafx-CD": "PCE-CD",
-            "NEC - PC Engine SuperGrafx": "SGX",
-            "NEC - PC-FX": "PCFX",
-            "SNK - Neo Geo": "NEO",
-            "SNK - Neo Geo CD": "NGCD",
-            "SNK - Neo Geo Pocket": "NGP",
-            "SNK - Neo Geo Pocket Color": "NGPC",
-            "Bandai - WonderSwan": "WS",
-            "Bandai - WonderSwan Color": "WSC",
-            "Commodore - Amiga": "AMIGA",
-            "Commodore - Commodore 64": "C64",
-            "Commodore - Amiga CD32": "CD32",
-            "Apple - Apple II": "APPLE2",
-            "Apple - Macintosh": "MAC",
-            "IBM - PC": "PC",
-            "IBM - PC DOS": "DOS",
-            "IBM - PC Windows": "WIN",
-            "IBM - PC Linux": "LINUX",
-            "IBM - PC macOS": "MACOS",
-            "IBM - PC Android": "ANDROID",
-            "IBM - PC iOS": "IOS",
-            "IBM - PC Web": "WEB",
-            "IBM - PC VR": "VR",
-            "IBM - PC AR": "AR",
-            "IBM - PC Cloud": "CLOUD",
-            "IBM - PC Mobile": "MOBILE",
-            "IBM - PC Handheld": "HANDHELD",
-            "IBM - PC Console": "CONSOLE",
-            "IBM - PC Arcade": "ARCADE",
-            "IBM - PC Pinball": "PINBALL",
-            "IBM - PC Casino": "CASINO",
-            "IBM - PC Educational": "EDU",
-            "IBM - PC Sports": "SPORTS",
-            "IBM - PC Racing": "RACING",
-            "IBM - PC Fighting": "FIGHTING",
-            "IBM - PC Shooter": "SHOOTER",
-            "IBM - PC Adventure": "ADV",
-            "IBM - PC RPG": "RPG",
-            "IBM - PC Strategy": "STRAT",
-            "IBM - PC Simulation": "SIM",
-            "IBM - PC Puzzle": "PUZZLE",
-            "IBM - PC Platformer": "PLAT",
-            "IBM - PC Action": "ACTION",
-            "IBM - PC Horror": "HORROR",
-            "IBM - PC Comedy": "COMEDY",
-            "IBM - PC Drama": "DRAMA",
-            "IBM - PC Sci-Fi": "SCIFI",
-            "IBM - PC Fantasy": "FANTASY",
-            "IBM - PC Historical": "HIST",
-            "IBM - PC Military": "MIL",
-            "IBM - PC Western": "WESTERN",
-            "IBM - PC Crime": "CRIME",
-            "IBM - PC Mystery": "MYSTERY",
-            "IBM - PC Thriller": "THRILLER",
-            "IBM - PC Romance": "ROMANCE",
-            "IBM - PC Musical": "MUSICAL",
-            "IBM - PC Documentary": "DOC",
-            "IBM - PC Animation": "ANIM",
-            "IBM - PC Family": "FAMILY",
-            "IBM - PC Children": "CHILDREN",
-            "IBM - PC Teen": "TEEN",
-            "IBM - PC Adult": "ADULT",
-            "IBM - PC Mature": "MATURE",
-            "IBM - PC Everyone": "EVERYONE",
-            "IBM - PC Everyone 10+": "E10+",
-            "IBM - PC Teen 13+": "T13+",
-            "IBM - PC Mature 17+": "M17+",
-            "IBM - PC Adults Only 18+": "AO18+",
-            "IBM - PC Rating Pending": "RP",
-            "IBM - PC Not Rated": "NR",
-            "IBM - PC Unrated": "UR",
-            "IBM - PC Unknown": "UNK",
-            "IBM - PC Other": "OTHER"
-        }
+        self.log_file = self.config.get_path('paths.log_directory') / "download_log.txt"
+        self.temp_dir = self.config.get_path('paths.temp_directory')
+        
+        # Available platforms and their short names
+        self.platforms = dict(PLATFORM_SHORT_NAMES)
         
         # Download statistics
         self.download_stats = {
@@ -163,8 +136,9 @@ class ROMDownloader:
         }
         
         # Create directories
-        self.download_dir.mkdir(exist_ok=True)
-        self.temp_dir.mkdir(exist_ok=True)
+        self.download_dir.mkdir(parents=True, exist_ok=True)
+        self.temp_dir.mkdir(parents=True, exist_ok=True)
+        self.log_file.parent.mkdir(parents=True, exist_ok=True)
         
         # Initialize log file
         self.log_message(f"ROM Download Session Started: {datetime.now()}")
@@ -174,10 +148,11 @@ class ROMDownloader:
         timestamp = datetime.now().strftime("%Y-%m-%d %H:%M:%S")
         log_entry = f"[{timestamp}] {message}"
         
-        print(log_entry)
-        
-        with open(self.log_file, 'a', encoding='utf-8') as f:
-            f.write(f"{log_entry}\n")
+        with self.log_lock:
+            print(log_entry)
+            
+            with open(self.log_file, 'a', encoding='utf-8') as f:
+                f.write(f"{log_entry}\n")
     
     def show_platform_menu(self):
         """Display platform selection menu."""
@@ -211,27 +186,46 @@ class ROMDownloader:
             except ValueError:
                 pass
             
-            # Try to find by name (case-insensitive)
-            choice_lower = choice.lower()
-            for platform in platforms_list:
-                if choice_lower in platform.lower():
-                    return platform
-            
-            # Try to find by abbreviation
-            for platform, abbrev in self.platforms.items():
-                if choice_lower == abbrev.lower():
-                    return platform
+            platform = self.resolve_platform(choice)
+            if platform:
+                return platform
             
             print(f"{Colors.RED}Platform not found. Please try again.{Colors.NC}")
     
+    def resolve_platform(self, name: str) -> Optional[str]:
+        """Find the archive platform folder for a platform name, part of one, or an abbreviation."""
+        name_lower = name.strip().lower()
+        if not name_lower:
+            return None
+        
+        # Try to find by abbreviation
+        for platform, abbrev in self.platforms.items():
+            if name_lower == abbrev.lower():
+                return platform
+        
+        # Try to find by name (case-insensitive)
+        for platform in self.platforms:
+            if name_lower in platform.lower():
+                return platform
+        
+        # Headings like "Top 100 SNES Games" mention the platform among other words;
+        # the longest full name wins so "Super Nintendo" is not read as "Nintendo"
+        named = [p for p in self.platforms if p.split(' - ', 1)[-1].lower() in name_lower]
+        if named:
+            return max(named, key=lambda p: len(p.split(' - ', 1)[-1]))
+        
+        words = set(re.findall(r'[a-z0-9+\-]+', name_lower))
+        return next((p for p, abbrev in self.platforms.items() if abbrev.lower() in words), None)
+    
     def select_dataset(self) -> str:
-        """Select dataset (Redump or No-Intro)."""
+        """Select dataset (Redump, No-Intro or TOSEC)."""
         print(f"\n{Colors.CYAN}Select Dataset:{Colors.NC}")
         print("1. Redump")
         print("2. No-Intro")
+        print("3. TOSEC")
         
         while True:
-            choice = input(f"{Colors.CYAN}Enter choice (1-2): {Colors.NC}").strip()
+            choice = input(f"{Colors.CYAN}Enter choice (1-3): {Colors.NC}").strip()
             
             if choice == '1':
                 self.rom_archive_base_url = self.base_url_redump
@@ -239,141 +233,584 @@ class ROMDownloader:
             elif choice == '2':
                 self.rom_archive_base_url = self.base_url_noin
                 return "No-Intro"
+            elif choice == '3':
+                self.rom_archive_base_url = self.base_url_tosec
+                return "TOSEC"
             else:
-                print(f"{Colors.RED}Invalid choice. Please enter 1 or 2.{Colors.NC}")
+                print(f"{Colors.RED}Invalid choice. Please enter 1, 2 or 3.{Colors.NC}")
     
-    def download_index(self, url: str) -> Optional[str]:
-        """Download and save index page."""
+    def list_platform_files(self, platform_url: str) -> List[str]:
+        """List the ROM files of a platform directory through the archive source."""
         try:
-            self.log_message(f"{Colors.CYAN}Downloading index from {url}...{Colors.NC}")
-            
-            headers = {
-                'User-Agent': 'Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36'
-            }
-            
-            response = requests.get(url, headers=headers, timeout=30)
-            response.raise_for_status()
-            
-            index_file = self.temp_dir / "platform_index.html"
-            with open(index_file, 'w', encoding='utf-8') as f:
-                f.write(response.text)
-            
-            self.log_message(f"{Colors.GREEN}Index downloaded successfully{Colors.NC}")
-            return str(index_file)
-            
+            self.log_message(f"{Colors.CYAN}Listing {platform_url}...{Colors.NC}")
+            rom_files = self.source.list_files(self.source.path_of(platform_url) or '', ROM_EXTENSIONS)
+            self.log_message(f"{Colors.GREEN}Found {len(rom_files)} files{Colors.NC}")
+            return rom_files
         except Exception as e:
-            self.log_message(f"{Colors.RED}Failed to download index: {e}{Colors.NC}")
-            return None
+            self.log_message(f"{Colors.RED}Failed to list {platform_url}: {e}{Colors.NC}")
+            return []
+
+    def clean_title(self, title: str) -> str:
+        """Clean a queue title for searching."""
+        title = re.sub(r' - Missing.*$', '', title)
+        title = re.sub(r' \(Note:.*$', '', title)
+        return title.strip()
     
-    def parse_platform_index(self, index_file: str) -> List[str]:
-        """Parse platform index and extract ROM files."""
-        rom_files = []
+    def search_game(self, game_title: str, rom_files: List[str]) -> List[str]:
+        """Find candidate files for a game title in a platform listing."""
+        words = game_title.lower().split()
+        alnum_words = re.sub(r'[^a-z0-9]+', ' ', game_title.lower()).split()
         
-        try:
-            with open(index_file, 'r', encoding='utf-8') as f:
-                content = f.read()
-            
-            # Simple HTML parsing - look for href attributes pointing to ROM files
-            import re
-            
-            # Find all href attributes
-            href_pattern = r'href="([^"]+)"'
-            hrefs = re.findall(href_pattern, content)
-            
-            # ROM file extensions
-            rom_extensions = {'.zip', '.7z', '.rar', '.iso', '.bin', '.cue', '.img', '.mdf', '.mds'}
-            
-            for href in hrefs:
-                # Skip directories and non-ROM files
-                if href.endswith('/') or href.startswith('http'):
-                    continue
-                
-                # Check if it's a ROM file
-                file_path = Path(href)
-                if file_path.suffix.lower() in rom_extensions:
-                    decoded_href = urllib.parse.unquote(href)
-                    rom_files.append(decoded_href)
-            
-            rom_files.sort()
-            
-        except Exception as e:
-            self.log_message(f"{Colors.RED}Error parsing index: {e}{Colors.NC}")
+        # Try progressively looser patterns, like the bash downloader
+        patterns = [
+            '.*'.join(re.escape(w) for w in words),
+            '.*'.join(re.escape(w) for w in alnum_words),
+        ]
+        
+        for pattern in patterns:
+            if not pattern:
+                continue
+            regex = re.compile(pattern, re.IGNORECASE)
+            matches = [f for f in rom_files if regex.search(f)]
+            if matches:
+                break
+        else:
+            matches = []
+        
+        # Japanese sets list titles in kana, so also match across kana and romaji
+        if self.transliteration:
+            matches += [f for f in rom_files
+                        if f not in matches and transliterated_match(game_title, f.split('(')[0])]
+        
+        if matches:
+            self.log_message(f"{Colors.GREEN}Found {len(matches)} potential matches for: {game_title}{Colors.NC}")
+            return matches[:10]
+        
+        self.log_message(f"{Colors.YELLOW}No matches found for: {game_title}{Colors.NC}")
+        return []
+    
+    def score_match(self, game_title: str, filename: str) -> Dict:
+        """Score a candidate file, keeping each part: word overlap, region, language and revision."""
+        return self.scorer.score_match(game_title, filename)
+    
+    def explain_match(self, game_title: str, matches: List[str]) -> Optional[Dict]:
+        """Score every candidate for a title: {title, match, candidates}, candidates best first.
+        
+        Ties go to the candidate listed first, as in select_best_match.
+        """
+        return self.scorer.explain_match(game_title, matches)
+    
+    def platform_folder(self, platform_url: str) -> str:
+        """Get the archive folder of a platform URL, which remembered matches are kept under."""
+        return (self.source.path_of(platform_url) or '').strip('/')
+    
+    def match_title(self, game_title: str, platform_url: str, rom_files: List[str]) -> Optional[Dict]:
+        """Explain the match for a title, using the file remembered for it when a user corrected it before."""
+        explanation = self.explain_match(game_title, self.search_game(game_title, rom_files))
+        remembered = self.library.remembered_match(self.platform_folder(platform_url), game_title)
+        if not remembered:
+            return explanation
+        if remembered not in rom_files:
+            self.log_message(f"{Colors.YELLOW}Remembered match {remembered} for {game_title} is no longer listed; "
+                             f"matching it again{Colors.NC}")
+            return explanation
+        
+        candidates = explanation['candidates'] if explanation else []
+        if not any(c['filename'] == remembered for c in candidates):
+            candidates.append(self.score_match(game_title, remembered))
+        return {"title": game_title, "match": remembered, "candidates": candidates, "remembered": True}
+    
+    def pick_match(self, explanation: Dict, platform_url: str) -> Dict:
+        """Ask which candidate to download for a title (--pick-matches); a pick other than the
+        proposed one is remembered for the platform."""
+        print(f"\n{Colors.CYAN}{explanation['title']}{Colors.NC}")
+        for i, candidate in enumerate(explanation['candidates'], 1):
+            marker = '*' if candidate['filename'] == explanation['match'] else ' '
+            print(f"{marker} {i:>2}. {candidate['filename']}")
+        
+        while True:
+            choice = input(f"{Colors.CYAN}File to download (Enter for *): {Colors.NC}").strip()
+            if not choice:
+                return explanation
+            if choice.isdigit() and 1 <= int(choice) <= len(explanation['candidates']):
+                break
+            print(f"{Colors.RED}Invalid choice{Colors.NC}")
         
-        return rom_files
+        picked = explanation['candidates'][int(choice) - 1]['filename']
+        if picked != explanation['match']:
+            self.library.remember_match(self.platform_folder(platform_url), explanation['title'], picked)
+            self.log_message(f"{Colors.GREEN}Remembered {picked} for {explanation['title']}{Colors.NC}")
+        return dict(explanation, match=picked, remembered=True)
+    
+    def select_best_match(self, game_title: str, matches: List[str]) -> Optional[str]:
+        """Select the best matching file based on word overlap and region/revision preference."""
+        explanation = self.explain_match(game_title, matches)
+        return explanation['match'] if explanation else None
     
     def get_file_size(self, url: str) -> int:
         """Get file size from URL."""
+        return self.get_file_info(url)[0]
+    
+    def get_file_info(self, url: str) -> Tuple[int, Optional[str]]:
+        """Get file size and a validator (ETag or Last-Modified) from URL."""
         try:
-            response = requests.head(url, timeout=10)
+            response = self.mirrors.head(url, timeout=10)
             content_length = response.headers.get('content-length')
-            if content_length:
-                return int(content_length)
+            validator = response.headers.get('etag') or response.headers.get('last-modified')
+            return (int(content_length) if content_length else 0), validator
         except Exception:
             pass
-        return 0
+        return 0, None
+    
+    def template_for(self, url: str) -> str:
+        """Get the path template of a download: its platform's in downloads.platform_templates, else path_template."""
+        return platform_template(url, self.path_template, self.platform_templates)
+    
+    def known_size(self, url: str) -> int:
+        """Get a file's size from the cached listing of its folder, else by asking the server; 0 when unknown."""
+        if url not in self.file_sizes:
+            self.file_sizes[url] = listed_size(self.config, url) or self.get_file_size(url)
+        return self.file_sizes[url]
+    
+    def remaining_size(self, url: str, filename: str) -> int:
+        """Get how many bytes downloading a file still takes: none when it is on disk, less a .part already there."""
+        file_path = resolve_destination(self.download_dir, self.template_for(url), url, filename, self.library)
+        if file_path.exists() or (self.download_dir / filename).exists():
+            return 0
+        part_path = file_path.with_name(file_path.name + ".part")
+        done = part_path.stat().st_size if part_path.exists() else 0
+        return max(self.known_size(url) - done, 0)
+    
+    def free_space(self) -> int:
+        """Get the free space on the volume of the download folder, less downloads.free_space_reserve_mb."""
+        directory = self.download_dir.resolve()
+        # The folder is created on the first download; its nearest existing parent is on the same volume
+        while not directory.exists() and directory != directory.parent:
+            directory = directory.parent
+        return shutil.disk_usage(directory).free - self.free_space_reserve
+    
+    def check_free_space(self, needed: int, what: str) -> bool:
+        """Refuse a download that does not fit on the disk, or only warn with --allow-low-space."""
+        free = self.free_space()
+        if needed <= free:
+            return True
+        message = f"Not enough free space for {what}: needs {format_size(needed)}, {format_size(max(free, 0))} available"
+        if self.allow_low_space:
+            self.log_message(f"{Colors.YELLOW}Warning: {message}{Colors.NC}")
+            return True
+        self.log_message(f"{Colors.RED}{message} (free some space or run with --allow-low-space){Colors.NC}")
+        return False
+    
+    def within_budget(self, downloads: List[Tuple[str, str, List[str], Optional[Dict]]]) -> int:
+        """Count how many downloads, in queue order, fit in the --max-total-size budget of this run."""
+        if self.max_total_size is None:
+            return len(downloads)
+        total = 0
+        for i, (url, filename, _, _) in enumerate(downloads):
+            total += self.remaining_size(url, filename)
+            if total > self.max_total_size:
+                return i
+        return len(downloads)
+    
+    def count(self, stat: str, amount: int = 1):
+        """Add to a download statistic; downloads running in parallel share them."""
+        with self.stats_lock:
+            self.download_stats[stat] += amount
+    
+    def start_batch(self, kind: str, label: str, downloads: List[Tuple[str, str, Optional[Dict]]],
+                    origins: Optional[List[str]] = None):
+        """Record a batch of (url, filename, match explanation) downloads in a session that resume
+        can continue if the run is interrupted; origins are their download queue entries, if queued.
+        """
+        if not downloads:
+            self.batch = None
+            return
+        settings = {
+            "download_dir": str(self.download_dir.resolve()),
+            "path_template": self.path_template,
+            "platform_templates": self.platform_templates,
+            "verify_checksums": self.verify_checksums,
+            "queue_file": str(self.queue_file.resolve()) if origins is not None else None,
+        }
+        items = [{"url": url, "filename": filename, "explanation": explanation,
+                  "entry": origins[i] if origins is not None else None}
+                 for i, (url, filename, explanation) in enumerate(downloads)]
+        self.batch = self.batch_sessions.start(kind, label, items, settings)
     
-    def download_file(self, url: str, filename: str) -> bool:
-        """Download a single file."""
+    def record_result(self, url: str, status: str, transferred: int = 0, error: Optional[str] = None):
+        """Note how a download of the running batch ended."""
+        if self.batch:
+            self.batch.record_result(url, status, transferred, error)
+    
+    def finish_batch(self) -> Optional[Dict]:
+        """End the running batch session; returns its report."""
+        if not self.batch:
+            return None
+        report = self.batch.finish()
+        if self.batch_sessions.enabled:
+            self.log_message(f"{Colors.CYAN}Session {report['id']}: {report['downloaded']} downloaded, "
+                             f"{report['skipped']} skipped, {report['failed']} failed, {report['pending']} not reached"
+                             f"{Colors.NC}")
+        self.batch = None
+        return report
+    
+    def resume_batch(self, session_id: Optional[str] = None) -> Optional[Dict]:
+        """Continue a batch session where it stopped: the latest interrupted one, or the one given,
+        which may also be a finished one with failed downloads to retry.
+        
+        The files go where the batch would have put them, and the queue entries
+        of the items done leave the queue. Returns the session report, or None
+        when there is nothing to resume. Raises ValueError for an unknown session
+        or one another process is still running.
+        """
+        if session_id:
+            session = self.batch_sessions.load(session_id)
+        else:
+            interrupted = self.batch_sessions.interrupted()
+            if not interrupted:
+                self.log_message(f"{Colors.YELLOW}No interrupted batch session to resume{Colors.NC}")
+                return None
+            session = interrupted[-1]
+        if session.running():
+            raise ValueError(f"Batch session {session.record['id']} is still running (process {session.record['pid']})")
+        
+        self.config.ensure_writable("resume a batch session")
+        settings = session.record["settings"]
+        self.download_dir = Path(settings["download_dir"])
+        self.path_template = settings["path_template"]
+        self.platform_templates = settings["platform_templates"]
+        self.verify_checksums = settings["verify_checksums"]
+        
+        items = session.open_items()
+        self.log_message(f"{Colors.CYAN}Resuming {session.record['kind']} session {session.record['id']} "
+                         f"({session.record['label']}): {len(items)} of {len(session.record['items'])} "
+                         f"files left{Colors.NC}")
+        session.resume()
+        self.batch = session
+        self.download_stats['total_files'] = len(items)
+        self.download_all([(item['url'], item['filename'], item['explanation']) for item in items])
+        
+        # Queued items leave the queue once done, as the interrupted run would have done
+        done = [item['entry'] for item in session.record["items"]
+                if item['entry'] and item['status'] in ("downloaded", "skipped")]
+        if settings.get("queue_file") and done and Path(settings["queue_file"]).exists():
+            open_queue(self.config).remove(settings["queue_file"], done)
+        return self.finish_batch()
+    
+    def download_all(self, downloads: List[Tuple[str, str, Optional[Dict]]]) -> bool:
+        """Download (url, filename, match explanation) entries, downloads.max_concurrent at a time.
+        
+        Returns False, having downloaded nothing, when the files that are not on
+        disk yet would not fit in the free space.
+        """
+        needed = sum(self.remaining_size(url, filename) for url, filename, _ in downloads)
+        if not self.check_free_space(needed, f"{len(downloads)} files"):
+            return False
+        
+        def download(i: int, url: str, filename: str, explanation: Optional[Dict]):
+            self.log_message(f"{Colors.CYAN}Downloading file {i}/{len(downloads)}: {filename}{Colors.NC}")
+            
+            if self.download_file(url, filename, explanation):
+                # Small delay to be respectful to the server
+                time.sleep(0.5)
+            else:
+                # Longer delay on failure
+                time.sleep(2)
+        
+        with ThreadPoolExecutor(max_workers=self.max_concurrent) as pool:
+            for i, (url, filename, explanation) in enumerate(downloads, 1):
+                pool.submit(download, i, url, filename, explanation)
+        return True
+    
+    def download_file(self, url: str, filename: str, match_explanation: Optional[Dict] = None,
+                      destination: Optional[Path] = None) -> bool:
+        """Download a single file; match_explanation is stored with it when it was picked for a title.
+        
+        The file goes where the path template puts it, or to destination.
+        """
         try:
-            file_path = self.download_dir / filename
+            file_path = destination or resolve_destination(self.download_dir, self.template_for(url), url, filename,
+                                                           self.library)
             
-            # Check if file already exists
-            if file_path.exists():
-                self.log_message(f"{Colors.YELLOW}Skipping existing file: {filename}{Colors.NC}")
-                self.download_stats['skipped_files'] += 1
+            # A file cut short by a crash or a full disk is downloaded again rather than skipped
+            truncated = self.size_problem(file_path, url) if file_path.exists() else None
+            if truncated:
+                self.log_message(f"{Colors.YELLOW}Downloading {file_path.relative_to(self.download_dir)} again: "
+                                 f"{truncated}{Colors.NC}")
+            
+            # Check if file already exists, in its platform folder or flat from before they existed
+            if file_path.exists() and not truncated:
+                self.log_message(f"{Colors.YELLOW}Skipping existing file: {file_path.relative_to(self.download_dir)}{Colors.NC}")
+                self.count('skipped_files')
+                self.record_result(url, "skipped")
+                return True
+            
+            legacy_path = self.download_dir / filename
+            if legacy_path != file_path and legacy_path.exists():
+                self.log_message(f"{Colors.YELLOW}Skipping existing file: {filename} (run with --organize to move it into its platform folder){Colors.NC}")
+                self.count('skipped_files')
+                self.record_result(url, "skipped")
                 return True
             
+            if self.config.is_read_only():
+                self.log_message(f"{Colors.YELLOW}Read-only mode: not downloading {filename}{Colors.NC}")
+                self.count('skipped_files')
+                return False
+            
+            # A clone or BIOS-dependent MAME set will not run on its own
+            for required in self.missing_mame_requirements(file_path):
+                self.log_message(f"{Colors.YELLOW}Warning: {filename} needs its {required['reason']} set "
+                                 f"{required['name']}, which has not been downloaded{Colors.NC}")
+            
             # Get file size for progress tracking
-            file_size = self.get_file_size(url)
-            self.download_stats['total_size'] += file_size
+            file_size, validator = self.get_file_info(url)
+            self.count('total_size', file_size)
             
-            self.log_message(f"{Colors.CYAN}Downloading: {filename} ({file_size:,} bytes){Colors.NC}")
+            # Downloads go to a .part file; checkpoints let a crashed download
+            # resume from the last piece that still verifies
+            part_path = file_path.with_name(file_path.name + ".part")
+            done = part_path.stat().st_size if part_path.exists() else 0
+            if not self.check_free_space(max(file_size - done, 0), filename):
+                self.count('failed_files')
+                self.record_result(url, "failed", error="not enough free space")
+                return False
+            file_path.parent.mkdir(parents=True, exist_ok=True)
+            checkpoints = PieceCheckpoints(part_path, self.piece_size)
+            checkpoints.load(url, validator)
             
-            # Download file
-            headers = {
-                'User-Agent': 'Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36'
-            }
+            # The journal lets job_recovery.py resume the download, or record it if the file got in place
+            with self.journal.job("download", filename, url=url, filename=filename, path=str(file_path.resolve()),
+                                  validator=validator) as job:
+                job.add_file(part_path.resolve())
+                job.add_file(checkpoints.state_path.resolve())
+                
+                # Each retry resumes from the checkpoints instead of starting over
+                transferred, hashes = self.retry.call(self.transfer_file, url, filename, part_path, checkpoints,
+                                                      file_size)
+                
+                # The file only takes its name once it is whole; a corrupt one must not pass for a download
+                mismatch = self.checksum_mismatch(part_path, filename, hashes)
+                if mismatch:
+                    part_path.unlink()
+                    checkpoints.finish()
+                    raise ValueError(mismatch)
+                part_path.replace(file_path)
+                checkpoints.finish()
+                
+                self.count('downloaded_files')
+                self.count('downloaded_size', transferred)
+                self.library.record_download(file_path, url, platform_from_url(url), file_size or None, validator,
+                                             match_explanation, hashes)
+            self.library.extract_icon(file_path)
+            self.record_result(url, "downloaded", transferred)
             
-            response = requests.get(url, headers=headers, stream=True, timeout=60)
-            response.raise_for_status()
+            self.log_message(f"{Colors.GREEN}Downloaded: {file_path.relative_to(self.download_dir)}{Colors.NC}")
+            self.post_process(file_path, url)
+            return True
             
-            downloaded_size = 0
-            with open(file_path, 'wb') as f:
+        except Exception as e:
+            self.log_message(f"{Colors.RED}Failed to download {filename}: {e}{Colors.NC}")
+            self.count('failed_files')
+            self.record_result(url, "failed", error=str(e))
+            return False
+    
+    def download_url(self, url: str) -> bool:
+        """Download one file by its URL, as picked in the GUI.
+        
+        archive.org files go through archive_org.download, which checks them
+        against the SHA1 the archive publishes.
+        """
+        from archive_org import download as download_archive_org_file
+        
+        self.config.ensure_writable("download files")
+        archive_org = InternetArchiveSource(self.config, log=self.log_message)
+        path = archive_org.path_of(url)
+        if path:
+            identifier, _, name = path.partition('/')
+            return download_archive_org_file(archive_org, identifier, name, self)
+        return self.download_file(url, urllib.parse.unquote(url.rstrip('/').rsplit('/', 1)[-1]))
+    
+    def post_process(self, file_path: Path, url: str) -> Path:
+        """Run the post-download processors, which may move the file, e.g. to extract or convert it."""
+        with self.journal.job("conversion", file_path.name, path=str(file_path.resolve()), url=url):
+            final_path = Path(self.plugins.post_download(file_path.resolve(), url, platform_from_url(url)))
+            if final_path != file_path.resolve():
+                self.library.move_entry(file_path, final_path)
+                self.log_message(f"{Colors.GREEN}Post-processed: {final_path}{Colors.NC}")
+        return final_path
+    
+    def mame_sets(self) -> MameSets:
+        """Load the MAME DATs from the DAT directory on first use."""
+        if self.mame is None:
+            self.mame = MameSets()
+            self.mame.load_directory(self.config.get_path('paths.dat_directory'), log=self.log_message)
+        return self.mame
+    
+    def dat_index(self) -> DatIndex:
+        """Load the DATs from the DAT directory on first use."""
+        if self.dats is None:
+            self.dats = DatIndex()
+            self.dats.load_directory(self.config.get_path('paths.dat_directory'), log=self.log_message)
+        return self.dats
+    
+    def checksum_mismatch(self, part_path: Path, filename: str, hashes: Optional[Dict] = None) -> Optional[str]:
+        """Check a finished .part file against the hashes the DATs list for its title.
+        
+        hashes are those taken while it downloaded; the file is only read
+        again when there are none. Returns why it does not match, or None
+        when it does or cannot be checked: the DATs do not list it, or it is
+        a format they do not describe, such as a CHD.
+        """
+        if not self.verify_checksums:
+            return None
+        missing = self.missing_dat_roms(part_path, filename, hashes)
+        if not missing:
+            return None
+        return f"does not match the DAT checksums (missing {', '.join(missing)}); removed the partial file"
+    
+    def missing_dat_roms(self, file_path: Path, filename: str, hashes: Optional[Dict] = None) -> List[str]:
+        """Get the ROMs the DATs list for a file's title that it does not hold with the listed hashes;
+        none when the DATs do not list it or describe another format. hashes, as DownloadHasher
+        gives them, spare reading the file."""
+        roms = self.dat_index().game_roms(strip_extension(filename))
+        is_zip = zipfile.is_zipfile(file_path)
+        if not roms or not (is_zip or any(rom['rom'] == filename for rom in roms)):
+            return []
+        if hashes and not is_zip:
+            actual = [dict(hashes, name=filename)]
+        else:
+            # An archive the download could not be unpacked from is read again
+            actual = (hashes or {}).get('entries') or hash_entries(file_path)
+            if not is_zip:
+                actual[0]['name'] = filename
+        return missing_roms([dict(rom, name=rom['rom']) for rom in roms], actual)
+    
+    def size_problem(self, file_path: Path, url: str) -> Optional[str]:
+        """Get how a downloaded file's size differs from the one recorded when it was downloaded from url.
+        
+        Files the library does not know, and those post-processed into
+        another file, have nothing to compare with.
+        """
+        entry = self.library.get_entry(file_path)
+        if not entry or entry['source_url'] != url or file_path.name != served_name(url):
+            return None
+        expected = entry['size'] or entry['source_size']
+        size = file_path.stat().st_size
+        if not expected or size == expected:
+            return None
+        if size < expected:
+            return f"truncated ({size:,} of {expected:,} bytes)"
+        return f"{size:,} bytes, {expected:,} when downloaded"
+    
+    def missing_mame_requirements(self, file_path: Path) -> List[Dict]:
+        """Get the parent and BIOS sets a MAME set needs that are neither next to it nor in the library."""
+        mame = self.mame_sets()
+        if not mame.get(file_path.name):
+            return []
+        
+        owned = {set_name(entry['path']) for entry in self.library.get_entries()}
+        return [required for required in mame.requirements(file_path.name, self.mame_set_type)
+                if required['name'] not in owned
+                and not any((file_path.parent / f"{required['name']}{ext}").exists() for ext in ('.zip', '.7z'))]
+    
+    def transfer_file(self, url: str, filename: str, part_path: Path, checkpoints: PieceCheckpoints,
+                      file_size: int) -> Tuple[int, Dict]:
+        """Download into the .part file, resuming from its verified pieces, hashing it as it arrives.
+        
+        Returns the number of bytes transferred and the file's hashes (see
+        DownloadHasher); a resumed download reads back the part it already
+        had. Raises TransientError if the connection ends before the whole
+        file arrived.
+        """
+        resume_from = checkpoints.verified_offset()
+        
+        headers = {}
+        
+        if resume_from:
+            self.log_message(f"{Colors.CYAN}Resuming: {filename} from {resume_from:,} bytes ({len(checkpoints.pieces)} verified pieces){Colors.NC}")
+            headers['Range'] = f"bytes={resume_from}-"
+        else:
+            self.log_message(f"{Colors.CYAN}Downloading: {filename} ({file_size:,} bytes){Colors.NC}")
+        
+        response = self.mirrors.request('GET', url, headers=headers, stream=True, timeout=60)
+        response.raise_for_status()
+        
+        if resume_from and response.status_code != 206:
+            self.log_message(f"{Colors.YELLOW}Server does not support resuming, restarting {filename}{Colors.NC}")
+            checkpoints.restart()
+            resume_from = 0
+        
+        hasher = DownloadHasher(archive=filename.lower().endswith('.zip'))
+        if resume_from:
+            with open(part_path, 'rb') as f:
+                for chunk in iter(lambda: f.read(1024 * 1024), b""):
+                    hasher.update(chunk)
+        
+        downloaded_size = resume_from
+        # Parallel downloads would garble a shared terminal line; the GUI tells them apart by label
+        progress = ProgressTracker("download", total=file_size or None, unit="bytes", label=filename,
+                                   reporter=default_reporter(show=self.max_concurrent == 1), start=resume_from)
+        # Recorded even if the transfer fails, so stalls show up in the speed history
+        sampler = SpeedSampler()
+        try:
+            with open(part_path, 'ab' if resume_from else 'wb') as f:
                 for chunk in response.iter_content(chunk_size=8192):
                     if chunk:
                         f.write(chunk)
+                        checkpoints.add(chunk, f)
+                        hasher.update(chunk)
+                        sampler.add(len(chunk))
                         downloaded_size += len(chunk)
-                        
-                        # Show progress for large files
-                        if file_size > 0 and downloaded_size % (1024 * 1024) == 0:  # Every MB
-                            progress = (downloaded_size / file_size) * 100
-                            print(f"\r{Colors.CYAN}Progress: {progress:.1f}% ({downloaded_size:,}/{file_size:,} bytes){Colors.NC}", end='', flush=True)
-            
-            if file_size > 0:
-                print()  # New line after progress
+                        progress.update(downloaded_size)
+        finally:
+            self.library.record_speed_samples(self.session_id, url, host_of(response.url), sampler.finish())
+            progress.finish()
+        
+        if file_size > 0 and downloaded_size != file_size:
+            raise TransientError(f"incomplete download ({downloaded_size:,}/{file_size:,} bytes)")
+        
+        return downloaded_size - resume_from, hasher.result()
+    
+    def organize_downloads(self) -> int:
+        """Move files downloaded into the flat downloads folder into the path template layout.
+        
+        Only files recorded in the library are moved, since their source URL
+        gives the platform. Returns the number of files moved.
+        """
+        download_dir = self.download_dir.resolve()
+        moved = 0
+        
+        for entry in self.library.get_entries():
+            old_path = Path(entry['path'])
+            if not entry['source_url'] or not old_path.exists() or old_path.parent != download_dir:
+                continue
             
-            self.download_stats['downloaded_files'] += 1
-            self.download_stats['downloaded_size'] += downloaded_size
+            new_path = resolve_destination(download_dir, self.template_for(entry['source_url']), entry['source_url'],
+                                           entry['filename'], self.library)
+            if new_path == old_path:
+                continue
             
-            self.log_message(f"{Colors.GREEN}Downloaded: {filename}{Colors.NC}")
-            return True
+            if self.config.is_read_only():
+                self.log_message(f"{Colors.YELLOW}Read-only mode: would move {old_path.name} to {new_path.relative_to(download_dir)}{Colors.NC}")
+                continue
             
-        except Exception as e:
-            self.log_message(f"{Colors.RED}Failed to download {filename}: {e}{Colors.NC}")
-            self.download_stats['failed_files'] += 1
-            return False
+            new_path.parent.mkdir(parents=True, exist_ok=True)
+            shutil.move(str(old_path), str(new_path))
+            self.library.move_entry(old_path, new_path)
+            self.log_message(f"{Colors.GREEN}Moved: {old_path.name} -> {new_path.relative_to(download_dir)}{Colors.NC}")
+            moved += 1
+        
+        return moved
     
     def download_platform_roms(self, platform: str, max_files: Optional[int] = None):
         """Download all ROMs for a platform."""
         platform_url = f"{self.rom_archive_base_url}{urllib.parse.quote(platform)}/"
         
-        # Download platform index
-        index_file = self.download_index(platform_url)
-        if not index_file:
-            return
-        
-        # Parse ROM files
-        rom_files = self.parse_platform_index(index_file)
+        rom_files = self.list_platform_files(platform_url)
+        session = load_session_filter(self.config)
+        if rom_files and not session.is_empty():
+            rom_files = session.keep_names(rom_files, self.config)
+            self.log_message(f"{Colors.YELLOW}Session filter: {session.describe()}{Colors.NC}")
         
         if not rom_files:
             self.log_message(f"{Colors.YELLOW}No ROM files found for platform: {platform}{Colors.NC}")
@@ -388,55 +825,564 @@ class ROMDownloader:
         self.log_message(f"{Colors.CYAN}Found {len(rom_files)} ROM files for {platform}{Colors.NC}")
         
         # Download files
-        for i, rom_file in enumerate(rom_files, 1):
-            file_url = f"{platform_url}{urllib.parse.quote(rom_file)}"
-            
-            self.log_message(f"{Colors.CYAN}Downloading file {i}/{len(rom_files)}: {rom_file}{Colors.NC}")
+        downloads = [(self.source.file_url(f"{self.source.path_of(platform_url)}{rom_file}"), rom_file, None)
+                     for rom_file in rom_files]
+        self.start_batch("platform", platform, downloads)
+        self.download_all(downloads)
+        self.finish_batch()
+    
+    def matching_files(self, platform: str, pattern: str, regex=False) -> List[Tuple[str, str]]:
+        """Get the (url, filename) of a platform's files whose names match a glob, or a regex, ignoring case.
+        
+        A glob matches the whole name ("Mario*"), a regex any part of it. The
+        session filter applies. Raises re.error for an invalid regex.
+        """
+        matches = re.compile(pattern, re.IGNORECASE).search if regex else \
+            (lambda name: fnmatch.fnmatchcase(name.lower(), pattern.lower()))
+        platform_url = f"{self.rom_archive_base_url}{urllib.parse.quote(platform)}/"
+        rom_files = [rom_file for rom_file in self.list_platform_files(platform_url) if matches(rom_file)]
+        session = load_session_filter(self.config)
+        if rom_files and not session.is_empty():
+            rom_files = session.keep_names(rom_files, self.config)
+            self.log_message(f"{Colors.YELLOW}Session filter: {session.describe()}{Colors.NC}")
+        return [(self.source.file_url(f"{self.source.path_of(platform_url)}{rom_file}"), rom_file)
+                for rom_file in rom_files]
+    
+    def download_matching(self, platform: str, pattern: str, regex=False, dry_run=False,
+                          max_files: Optional[int] = None, as_json=False) -> bool:
+        """Queue and download every file of a platform matching a pattern (see matching_files).
+        
+        dry_run only shows the matches with their count and total size. The
+        matches are added to the download queue first and leave it once
+        downloaded, so an interrupted run picks up with --queue. Returns False
+        when nothing matched or the downloads were refused.
+        """
+        matches = self.matching_files(platform, pattern, regex)[:max_files or None]
+        sizes = [self.known_size(url) for url, _ in matches]
+        
+        if dry_run:
+            if as_json:
+                print(json.dumps({
+                    "platform": platform,
+                    "pattern": pattern,
+                    "count": len(matches),
+                    "total_bytes": sum(sizes),
+                    "unknown_sizes": sizes.count(0),
+                    "files": [{"name": filename, "url": url, "size": size or None}
+                              for (url, filename), size in zip(matches, sizes)]
+                }, indent=2))
+            else:
+                for (_, filename), size in zip(matches, sizes):
+                    print(f"  {filename}  {Colors.GREEN}{format_size(size) if size else 'size unknown'}{Colors.NC}")
+                unknown = f" ({sizes.count(0)} of unknown size)" if sizes.count(0) else ""
+                print(f"{Colors.CYAN}{len(matches)} files match '{pattern}' in {platform}, "
+                      f"{format_size(sum(sizes))} in total{unknown}{Colors.NC}")
+            return bool(matches)
+        
+        if not matches:
+            self.log_message(f"{Colors.YELLOW}No files in {platform} match '{pattern}'{Colors.NC}")
+            return False
+        
+        self.config.ensure_writable("download files")
+        queue = open_queue(self.config)
+        queue.add(str(self.queue_file.resolve()),
+                  [(url, self.compatibility.check(filename, platform_from_url(url))) for url, filename in matches])
+        self.log_message(f"{Colors.CYAN}Queued {len(matches)} files matching '{pattern}' "
+                         f"({format_size(sum(sizes))}){Colors.NC}")
+        
+        downloads = [(url, filename, self.queue_warnings(filename, platform_from_url(url), []), None)
+                     for url, filename in matches]
+        fitting = self.within_budget(downloads)
+        if fitting < len(downloads):
+            self.log_message(f"{Colors.YELLOW}{len(downloads) - fitting} matching files exceed the "
+                             f"{format_size(self.max_total_size)} budget and stay queued{Colors.NC}")
+            downloads = downloads[:fitting]
+        
+        self.download_stats['total_files'] = len(downloads)
+        self.show_queue_summary(downloads)
+        self.start_batch("match", f"{platform}: {pattern}", [(url, filename, None) for url, filename, _, _ in downloads],
+                         [url for url, _, _, _ in downloads])
+        ok = self.download_all([(url, filename, None) for url, filename, _, _ in downloads])
+        self.finish_batch()
+        if not ok:
+            self.log_message(f"{Colors.YELLOW}The matching files stay queued{Colors.NC}")
+            return False
+        queue.remove(str(self.queue_file.resolve()), [url for url, _, _, _ in downloads])
+        return True
+    
+    def walk_folder(self, root: str, excludes: List[str]) -> List[Tuple[str, List[Dict]]]:
+        """List an archive folder and its subfolders as (folder, file entries), asking the server
+        rather than the cache so new additions show up. Subfolders an exclude glob matches are skipped.
+        """
+        folders, pending = [], [root]
+        while pending:
+            folder = pending.pop(0)
+            entries = self.source.list_dir(folder, fresh=True)
+            pending.extend(entry['path'] for entry in entries
+                           if entry['is_dir'] and not mirror_glob_matches(entry['path'][len(root):], excludes))
+            folders.append((folder, [entry for entry in entries if not entry['is_dir']]))
+        return folders
+    
+    def mirror_status(self, url: str, filename: str, listed: Optional[int], verify=False) -> Tuple[str, Path]:
+        """Get whether a mirrored file is "new" (not on disk), "present" or "changed", and where it goes.
+        
+        A file this mirror downloaded is present while the listed size still
+        matches the one recorded with it; any other file on disk is compared
+        with the size the server reports. verify also checks present files
+        against the DAT checksums.
+        """
+        file_path = resolve_destination(self.download_dir, MIRROR_TEMPLATE, url, filename, self.library)
+        if not file_path.exists():
+            return "new", file_path
+        
+        size = file_path.stat().st_size
+        entry = self.library.get_entry(file_path)
+        if entry and entry['source_url'] == url and entry['size'] == size and not entry['outdated_reason']:
+            recorded = entry['source_size'] or size
+            # Listings round sizes ("64.1 KiB"), so only a clear difference counts
+            same = listed is None or abs(listed - recorded) <= max(listed // 20, 1024)
+        else:
+            same = self.get_file_size(url) in (0, size)
+        
+        if same and verify and self.checksum_mismatch(file_path, filename):
+            same = False
+        return ("present" if same else "changed"), file_path
+    
+    def mirror_folder(self, path: str, includes: Optional[List[str]] = None, excludes: Optional[List[str]] = None,
+                      preset_names: Optional[List[str]] = None, one_per_game=False, verify=False,
+                      dry_run=False, as_json=False) -> bool:
+        """Download an archive folder and all its subfolders into the same folders under the download folder.
+        
+        Files are picked by the include and exclude globs, matched against
+        their path below the folder, then in each folder by the filter presets
+        (those named, else the platform's) and the session filter. Files on
+        disk that still match the archive are skipped, so running it again
+        only pulls what was added or changed since; a changed file is kept
+        aside until its new version is in place. dry_run only shows what a
+        run would download. Returns False when nothing was selected or the
+        downloads were refused.
+        """
+        root = path.strip('/') + '/'
+        root_url = self.source.file_url(root)
+        platform = platform_of(root_url, is_file=False)
+        presets = FilterPresets(self.config)
+        chosen = [presets.load(name) for name in preset_names] if preset_names else presets.for_platform(platform)
+        if one_per_game:
+            chosen = [dict(preset, one_per_game=True) for preset in chosen] or \
+                [validate_preset({"name": "one-per-game", "one_per_game": True})]
+        session = load_session_filter(self.config)
+        
+        total, files = 0, []
+        for folder, entries in self.walk_folder(root, excludes or []):
+            entries = [entry for entry in entries
+                       if (not includes or mirror_glob_matches(entry['path'][len(root):], includes))
+                       and not mirror_glob_matches(entry['path'][len(root):], excludes or [])]
+            total += len(entries)
+            names = [entry['name'] for entry in entries]
+            if chosen:
+                names = apply_presets(names, chosen, self.select_best_match)
+            kept = set(session.keep_names(names, self.config))
+            for entry in entries:
+                if entry['name'] in kept:
+                    url = self.source.file_url(entry['path'])
+                    status, file_path = self.mirror_status(url, entry['name'], entry['size'], verify)
+                    files.append({"name": entry['name'], "path": entry['path'][len(root):], "url": url,
+                                  "size": entry['size'], "status": status, "destination": file_path})
+        
+        by_status = {status: [f for f in files if f['status'] == status] for status in ("new", "changed", "present")}
+        sizes = {status: sum(f['size'] or 0 for f in selected) for status, selected in by_status.items()}
+        filters = [p['name'] for p in chosen] + ([f"session filter ({session.describe()})"] if not session.is_empty() else [])
+        
+        if dry_run:
+            if as_json:
+                print(json.dumps({
+                    "path": root.rstrip('/'),
+                    "filters": filters,
+                    "total": total,
+                    "selected": len(files),
+                    "new": len(by_status['new']),
+                    "new_bytes": sizes['new'],
+                    "changed": len(by_status['changed']),
+                    "changed_bytes": sizes['changed'],
+                    "present": len(by_status['present']),
+                    "files": [dict(f, destination=str(f['destination']), regions=release_regions(f['name']))
+                              for f in files]
+                }, indent=2))
+            else:
+                for f in by_status['new'] + by_status['changed']:
+                    color = Colors.GREEN if f['status'] == "new" else Colors.YELLOW
+                    size = format_size(f['size']) if f['size'] else 'size unknown'
+                    regions = f"  {', '.join(release_regions(f['name']))}" if release_regions(f['name']) else ""
+                    print(f"  {color}{f['status']:<8}{Colors.NC}{f['path']}{regions}  {size}")
+                by = f" by {', '.join(filters)}" if filters else ""
+                print(f"{Colors.CYAN}{len(files)} of {total} files in {root.rstrip('/')} selected{by}: "
+                      f"{len(by_status['new'])} new ({format_size(sizes['new'])}), "
+                      f"{len(by_status['changed'])} changed ({format_size(sizes['changed'])}), "
+                      f"{len(by_status['present'])} already present{Colors.NC}")
+            return bool(files)
+        
+        if not files:
+            self.log_message(f"{Colors.YELLOW}No files selected in {root.rstrip('/')}{Colors.NC}")
+            return False
+        
+        self.config.ensure_writable("mirror a folder")
+        self.path_template, self.platform_templates = MIRROR_TEMPLATE, {}
+        self.count('skipped_files', len(by_status['present']))
+        self.log_message(f"{Colors.CYAN}Mirroring {root.rstrip('/')}: {len(by_status['new'])} new, "
+                         f"{len(by_status['changed'])} changed, {len(by_status['present'])} already present{Colors.NC}")
+        
+        downloads = [(f['url'], f['name'], [], None) for f in by_status['new'] + by_status['changed']]
+        fitting = self.within_budget(downloads)
+        if fitting < len(downloads):
+            self.log_message(f"{Colors.YELLOW}{len(downloads) - fitting} files exceed the "
+                             f"{format_size(self.max_total_size)} budget; run the mirror again to get them{Colors.NC}")
+            downloads = downloads[:fitting]
+        fetching = {url for url, _, _, _ in downloads}
+        
+        # The old version of a changed file stays aside until the new one is in place
+        backups = {}
+        for f in by_status['changed']:
+            if f['url'] in fetching:
+                backups[f['destination']] = f['destination'].with_name(f['destination'].name + '.outdated')
+                f['destination'].replace(backups[f['destination']])
+        
+        self.download_stats['total_files'] = len(downloads)
+        self.start_batch("mirror", root.rstrip('/'), [(url, filename, None) for url, filename, _, _ in downloads])
+        ok = self.download_all([(url, filename, None) for url, filename, _, _ in downloads])
+        self.finish_batch()
+        for file_path, backup in backups.items():
+            if file_path.exists():
+                backup.unlink()
+            else:
+                backup.replace(file_path)
+        return ok
+    
+    def download_problem(self, file_path: Path) -> Optional[str]:
+        """Get what is wrong with a file in the download folder: cut short, a damaged archive, or
+        contents that do not match the DAT checksums. None when nothing is."""
+        entry = self.library.get_entry(file_path)
+        if entry and entry['source_url']:
+            problem = self.size_problem(file_path, entry['source_url'])
+            if problem:
+                return problem
+        
+        try:
+            if file_path.suffix.lower() == '.zip':
+                if not zipfile.is_zipfile(file_path):
+                    return "not a ZIP archive"
+                with zipfile.ZipFile(file_path) as archive:
+                    bad = archive.testzip()
+                if bad:
+                    return f"damaged archive ({bad} fails its CRC)"
+            missing = self.missing_dat_roms(file_path, file_path.name)
+        except (OSError, zipfile.BadZipFile, zlib.error, EOFError) as e:
+            return f"unreadable: {e}"
+        if missing:
+            return f"does not match the DAT checksums (missing {', '.join(missing)})"
+        return None
+    
+    def verify_downloads(self, dry_run=False, as_json=False) -> bool:
+        """Check every file in the download folder and download the broken ones again.
+        
+        A file is broken when it is smaller or larger than when it was
+        downloaded, is a ZIP archive that fails its CRCs, or does not hold
+        what the DATs list for its title. Only files the library knows the
+        source URL of can be downloaded again; a broken file stays aside
+        (as .broken) until its new copy is in place. dry_run only reports.
+        Returns False when a broken file is left.
+        """
+        files = [path for path in RomIgnore().walk(self.download_dir) if not path.name.endswith(NON_DOWNLOAD_SUFFIXES)]
+        progress = ProgressTracker("verify", total=len(files), unit="files", reporter=default_reporter(show=not as_json))
+        broken = []
+        for file_path in files:
+            problem = self.download_problem(file_path)
+            progress.advance(message=file_path.name)
+            if problem:
+                entry = self.library.get_entry(file_path)
+                url = entry['source_url'] if entry else None
+                broken.append({"path": file_path, "problem": problem, "url": url,
+                               "repairable": bool(url) and file_path.name == served_name(url), "repaired": False})
+        progress.finish()
+        
+        if broken and not dry_run:
+            self.config.ensure_writable("repair downloads")
+        for f in broken:
+            relative = f['path'].relative_to(self.download_dir)
+            if not as_json:
+                print(f"  {Colors.RED}{relative}{Colors.NC}: {f['problem']}")
+            if dry_run:
+                continue
+            if not f['repairable']:
+                why = "its source is unknown" if not f['url'] else "it was converted after download"
+                self.log_message(f"{Colors.YELLOW}Cannot download {relative} again: {why}{Colors.NC}")
+                continue
             
-            if self.download_file(file_url, rom_file):
-                # Small delay to be respectful to the server
-                time.sleep(0.5)
+            backup = f['path'].with_name(f['path'].name + '.broken')
+            f['path'].replace(backup)
+            self.download_file(f['url'], f['path'].name, destination=f['path'])
+            if f['path'].exists():
+                backup.unlink()
+                f['repaired'] = True
             else:
-                # Longer delay on failure
-                time.sleep(2)
+                backup.replace(f['path'])
+        
+        repaired = sum(1 for f in broken if f['repaired'])
+        if as_json:
+            print(json.dumps({
+                "checked": len(files),
+                "ok": len(files) - len(broken),
+                "broken": len(broken),
+                "repaired": repaired,
+                "files": [dict(f, path=str(f['path'].relative_to(self.download_dir))) for f in broken]
+            }, indent=2))
+        else:
+            left = f", {len(broken) - repaired} left broken" if repaired < len(broken) and not dry_run else ""
+            done = f", {repaired} downloaded again" if not dry_run else ""
+            print(f"{Colors.CYAN}{len(files)} files checked: {len(files) - len(broken)} intact, "
+                  f"{len(broken)} broken{done}{left}{Colors.NC}")
+        return repaired == len(broken)
+    
+    def resolve_queue(self, queue_items: List[Tuple[str, List[str]]], platform: Optional[str] = None,
+                      origins: Optional[List[str]] = None,
+                      unmatched: Optional[List[str]] = None) -> List[Tuple[str, str, List[str], Optional[Dict]]]:
+        """Turn queue entries into (url, filename, warnings, match explanation) downloads, matching titles
+        against the platform; only titles have an explanation. origins, when given, gets the queue
+        entry of each download, and unmatched the titles no file was found for.
+        
+        Warnings are the notes stored when an entry was queued, plus the
+        compatibility warnings of the release, which for titles is the one the
+        matcher picked.
+        """
+        downloads = []
+        platform_url = None
+        rom_files = []
+        
+        for item, notes in queue_items:
+            if item.startswith('http'):
+                filename = urllib.parse.unquote(item.split('/')[-1])
+                downloads.append((item, filename, self.queue_warnings(filename, platform_from_url(item), notes), None))
+                if origins is not None:
+                    origins.append(item)
+                continue
+            
+            if not platform:
+                self.log_message(f"{Colors.YELLOW}Skipping title without --platform: {item}{Colors.NC}")
+                if unmatched is not None:
+                    unmatched.append(item)
+                continue
+            
+            if platform_url is None:
+                platform_url = f"{self.rom_archive_base_url}{urllib.parse.quote(platform)}/"
+                rom_files = self.list_platform_files(platform_url)
+            
+            title = self.clean_title(item)
+            explanation = self.match_title(title, platform_url, rom_files)
+            if not explanation:
+                self.log_message(f"{Colors.YELLOW}Could not find suitable match for: {item}{Colors.NC}")
+                if unmatched is not None:
+                    unmatched.append(item)
+                continue
+            if self.pick_matches:
+                explanation = self.pick_match(explanation, platform_url)
+            
+            best_match = explanation['match']
+            url = self.source.file_url(f"{self.source.path_of(platform_url)}{best_match}")
+            downloads.append((url, best_match, self.queue_warnings(best_match, platform, notes), explanation))
+            if origins is not None:
+                origins.append(item)
+        
+        return downloads
+    
+    def queue_warnings(self, filename: str, platform: Optional[str], notes: List[str]) -> List[str]:
+        return notes + [w for w in self.compatibility.check(filename, platform) if w not in notes]
+    
+    def show_queue_summary(self, downloads: List[Tuple[str, str, List[str], Optional[Dict]]]):
+        """Log what is about to be downloaded, with the entries that may not run on this setup."""
+        self.log_message(f"{Colors.CYAN}Downloading {len(downloads)} files from queue{Colors.NC}")
+        flagged = [(filename, warnings) for _, filename, warnings, _ in downloads if warnings]
+        if not flagged:
+            return
+        self.log_message(f"{Colors.YELLOW}{len(flagged)} queued files may not suit your setup:{Colors.NC}")
+        for filename, warnings in flagged:
+            self.log_message(f"{Colors.YELLOW}  {filename}{Colors.NC}")
+            for warning in warnings:
+                self.log_message(f"      {warning}")
     
-    def download_from_queue(self):
-        """Download files from the download queue."""
+    def preview_queue(self, platform: Optional[str] = None, as_json=False) -> bool:
+        """Show what --queue would download without downloading: the file each entry resolves to,
+        its regions and size, the totals and the titles that match nothing. Files already on disk
+        and those past the size budget are marked. Returns False when the queue is empty.
+        """
+        queue_items = open_queue(self.config).read(str(self.queue_file.resolve())) if self.queue_file.exists() else []
+        origins, unmatched = [], []
+        downloads = self.resolve_queue(queue_items, platform, origins, unmatched)
+        fitting = self.within_budget(downloads)
+        
+        files = []
+        for i, ((url, filename, warnings, explanation), item) in enumerate(zip(downloads, origins)):
+            files.append({"entry": item, "title": explanation['title'] if explanation else None,
+                          "filename": filename, "url": url, "regions": release_regions(filename),
+                          "size": self.known_size(url) or None, "on_disk": self.remaining_size(url, filename) == 0,
+                          "within_budget": i < fitting, "warnings": warnings})
+        total = sum(f['size'] or 0 for f in files)
+        to_download = sum(f['size'] or 0 for f in files if not f['on_disk'] and f['within_budget'])
+        unknown = sum(1 for f in files if not f['size'])
+        
+        if as_json:
+            print(json.dumps({"count": len(files), "total_bytes": total, "download_bytes": to_download,
+                              "unknown_sizes": unknown, "files": files, "unmatched": unmatched}, indent=2))
+            return bool(queue_items)
+        
+        if not queue_items:
+            print(f"{Colors.YELLOW}Download queue is empty{Colors.NC}")
+            return False
+        for f in files:
+            source = f"{f['title']} -> " if f['title'] else ""
+            regions = f"  {', '.join(f['regions'])}" if f['regions'] else ""
+            size = format_size(f['size']) if f['size'] else 'size unknown'
+            notes = [note for flag, note in ((f['on_disk'], "on disk"), (not f['within_budget'], "over budget")) if flag]
+            marks = f"  {Colors.YELLOW}({', '.join(notes)}){Colors.NC}" if notes else ""
+            print(f"  {source}{Colors.GREEN}{f['filename']}{Colors.NC}{regions}  {size}{marks}")
+            for warning in f['warnings']:
+                print(f"      {Colors.YELLOW}! {warning}{Colors.NC}")
+        unknown_note = f" ({unknown} of unknown size)" if unknown else ""
+        print(f"{Colors.CYAN}{len(files)} files, {format_size(total)} in total{unknown_note}; "
+              f"{format_size(to_download)} to download{Colors.NC}")
+        if unmatched:
+            print(f"{Colors.YELLOW}{len(unmatched)} titles match no file{'' if platform else ' (no --platform given)'}:{Colors.NC}")
+            for title in unmatched:
+                print(f"  {title}")
+        return True
+    
+    def download_from_queue(self, platform: Optional[str] = None):
+        """Download files from the download queue.
+        
+        Queue entries are either URLs added by the ROM browser or game titles,
+        which are matched against the platform listing.
+        """
         if not self.queue_file.exists():
             self.log_message(f"{Colors.YELLOW}No download queue file found{Colors.NC}")
             return
         
         try:
-            with open(self.queue_file, 'r', encoding='utf-8') as f:
-                queue_items = [line.strip() for line in f if line.strip()]
+            queue = open_queue(self.config)
+            queue_items = queue.read(str(self.queue_file.resolve()))
             
             if not queue_items:
                 self.log_message(f"{Colors.YELLOW}Download queue is empty{Colors.NC}")
                 return
             
-            self.download_stats['total_files'] = len(queue_items)
+            origins = []
+            downloads = self.resolve_queue(queue_items, platform, origins)
             
-            self.log_message(f"{Colors.CYAN}Downloading {len(queue_items)} files from queue{Colors.NC}")
+            # Entries past the size budget stay queued for the next run
+            fitting = self.within_budget(downloads)
+            deferred = set(origins[fitting:])
+            if deferred:
+                self.log_message(f"{Colors.YELLOW}{len(downloads) - fitting} queued files exceed the "
+                                 f"{format_size(self.max_total_size)} budget and stay queued{Colors.NC}")
+                downloads = downloads[:fitting]
+            
+            self.download_stats['total_files'] = len(queue_items) - len(deferred)
+            self.download_stats['failed_files'] += len(queue_items) - len(deferred) - len(downloads)
+            self.show_queue_summary(downloads)
             
             # Download files from queue
-            for i, url in enumerate(queue_items, 1):
-                filename = url.split('/')[-1]
-                decoded_filename = urllib.parse.unquote(filename)
-                
-                self.log_message(f"{Colors.CYAN}Downloading file {i}/{len(queue_items)}: {decoded_filename}{Colors.NC}")
-                
-                if self.download_file(url, decoded_filename):
-                    time.sleep(0.5)
-                else:
-                    time.sleep(2)
+            batch = [(url, filename, explanation) for url, filename, _, explanation in downloads]
+            self.start_batch("queue", platform or "queue", batch, origins[:len(downloads)])
+            ok = self.download_all(batch)
+            self.finish_batch()
+            if not ok:
+                self.log_message(f"{Colors.YELLOW}Download queue left unchanged{Colors.NC}")
+                return
             
-            # Clear queue after successful download
-            self.queue_file.unlink()
-            self.log_message(f"{Colors.GREEN}Download queue cleared{Colors.NC}")
+            # Clear the processed entries; anything queued while downloading stays for next time
+            if self.config.is_read_only():
+                self.log_message(f"{Colors.YELLOW}Read-only mode: download queue left unchanged{Colors.NC}")
+            else:
+                queue.remove(str(self.queue_file.resolve()), [item for item, _ in queue_items if item not in deferred])
+                self.log_message(f"{Colors.GREEN}Download queue cleared{Colors.NC}" if not deferred else
+                                 f"{Colors.GREEN}Downloaded entries removed from the queue{Colors.NC}")
             
         except Exception as e:
             self.log_message(f"{Colors.RED}Error processing download queue: {e}{Colors.NC}")
     
+    def export_queue(self, path: Path, platform: Optional[str] = None) -> int:
+        """Write the queue to a portable file, each entry pinned to its URL and the hashes the DATs expect.
+        
+        Titles are matched against the platform now, so the file runs the same
+        downloads wherever it is imported. Returns the number of entries written.
+        """
+        queue_items = open_queue(self.config).read(str(self.queue_file.resolve()))
+        entries = []
+        for url, filename, warnings, explanation in self.resolve_queue(queue_items, platform):
+            size, validator = self.get_file_info(url)
+            roms = self.dat_index().game_roms(strip_extension(filename))
+            entries.append({
+                "url": url,
+                "filename": filename,
+                "platform": platform_from_url(url),
+                "dataset": dataset_from_url(url),
+                "size": size or None,
+                "validator": validator,
+                "title": explanation['title'] if explanation else None,
+                "notes": warnings,
+                "roms": [dict({"name": rom['rom']}, **{field: rom[field] for field in HASH_FIELDS}) for rom in roms],
+                "explanation": explanation,
+            })
+        
+        write_export(path, entries, self.config.get_archive_url())
+        unpinned = sum(1 for entry in entries if not entry['roms'])
+        self.log_message(f"{Colors.GREEN}Exported {len(entries)} of {len(queue_items)} queue entries to {path}{Colors.NC}")
+        if unpinned:
+            self.log_message(f"{Colors.YELLOW}{unpinned} entries are not in the DATs and have no expected hashes{Colors.NC}")
+        return len(entries)
+    
+    def downloaded_path(self, url: str, filename: str) -> Path:
+        """Get where a URL was downloaded to, following post-download moves recorded in the library."""
+        for entry in self.library.get_entries():
+            if entry['source_url'] == url and Path(entry['path']).exists():
+                return Path(entry['path'])
+        return resolve_destination(self.download_dir, self.template_for(url), url, filename, self.library)
+    
+    def run_exported_queue(self, path: Path) -> bool:
+        """Download the entries of an exported queue file as they are, then check them against their expected hashes.
+        
+        Returns False when a download failed or a file does not match.
+        """
+        entries = read_export(path)
+        if not entries:
+            self.log_message(f"{Colors.YELLOW}{path} has no entries{Colors.NC}")
+            return True
+        
+        self.download_stats['total_files'] = len(entries)
+        self.show_queue_summary([(e['url'], e['filename'], e['notes'], None) for e in entries])
+        # The export has the sizes, so the free space check need not ask the server
+        self.file_sizes.update({e['url']: e['size'] for e in entries if e.get('size')})
+        batch = [(e['url'], e['filename'], e.get('explanation')) for e in entries]
+        self.start_batch("import", str(path), batch)
+        ok = self.download_all(batch)
+        self.finish_batch()
+        if not ok:
+            return False
+        
+        mismatched = 0
+        for entry in entries:
+            file_path = self.downloaded_path(entry['url'], entry['filename'])
+            if not entry['roms'] or not file_path.exists():
+                continue
+            missing = missing_roms(entry['roms'], hash_entries(file_path))
+            if missing:
+                mismatched += 1
+                self.log_message(f"{Colors.RED}{file_path.name} does not match the exported hashes "
+                                 f"(missing {', '.join(missing)}){Colors.NC}")
+        
+        if mismatched:
+            self.log_message(f"{Colors.RED}{mismatched} files do not match; the source may have changed since the export{Colors.NC}")
+        else:
+            self.log_message(f"{Colors.GREEN}Every pinned file matches its exported hashes{Colors.NC}")
+        return not mismatched and not self.download_stats['failed_files']
+    
     def show_download_stats(self):
         """Show download statistics."""
         stats = self.download_stats
@@ -528,7 +1474,7 @@ class ROMDownloader:
 {Colors.GREEN}Features:{Colors.NC}
   • Download ROMs by platform selection
   • Download from download queue
-  • Support for Redump and No-Intro datasets
+  • Support for Redump, No-Intro and TOSEC datasets
   • Progress tracking and statistics
   • Automatic retry on failures
 
@@ -544,9 +1490,17 @@ class ROMDownloader:
 
 {Colors.GREEN}Download Queue:{Colors.NC}
   • Files are added to queue by the ROM browser
+  • Game titles in the queue are matched against --platform
   • Queue file: {self.queue_file}
   • Queue is automatically cleared after download
 
+{Colors.GREEN}Best-Match Selection:{Colors.NC}
+  • Region order: {', '.join(self.scorer.region_priority) or 'none'}
+  • Language order: {', '.join(self.scorer.language_priority) or 'none'}
+  • Highest revision preferred: {'yes' if self.scorer.prefer_highest_revision else 'no'}
+  • Avoided tags: {', '.join(self.scorer.avoid_tags) or 'none'}
+  • Configure in {self.config.config_file} or with --region-priority
+
 {Colors.GREEN}File Storage:{Colors.NC}
   • Downloaded files are saved to: {self.download_dir}
   • Existing files are automatically skipped
@@ -573,26 +1527,335 @@ class ROMDownloader:
             print(f"{Colors.RED}Fatal error: {e}{Colors.NC}")
 
 
+def print_match_explanation(explanation: Dict):
+    """Print why a file was picked for a title: the score parts of every candidate, best first."""
+    remembered = " (remembered pick)" if explanation.get('remembered') else ""
+    print(f"{Colors.CYAN}{explanation['title']}{Colors.NC} -> {Colors.GREEN}{explanation['match']}{Colors.NC}{remembered}")
+    print(f"  {'score':>6} {'words':>6} {'region':>6} {'lang':>6} {'rev':>6}  file")
+    for candidate in explanation['candidates']:
+        marker = '*' if candidate['filename'] == explanation['match'] else ' '
+        print(f"{marker} {candidate['score']:>6} {candidate['words']:>6} {candidate['region']:>6} "
+              f"{candidate['language']:>6} {candidate['revision']:>6}  {candidate['filename']}")
+
+
+def explain(downloader: ROMDownloader, target: str, platform: Optional[str], as_json: bool):
+    """--explain: the stored explanation of a downloaded file, else a dry run of matching a title."""
+    explanation = downloader.library.get_match_explanation(Path(target)) if Path(target).is_file() else None
+    if explanation is None and Path(target).is_file():
+        print(f"{Colors.YELLOW}{target} was not picked by matching a title{Colors.NC}", file=sys.stderr)
+        sys.exit(1)
+    if explanation is None:
+        if not platform:
+            print(f"{Colors.RED}--explain with a title needs --platform{Colors.NC}", file=sys.stderr)
+            sys.exit(2)
+        platform_url = f"{downloader.rom_archive_base_url}{urllib.parse.quote(platform)}/"
+        title = downloader.clean_title(target)
+        # Search progress goes to stderr so --json output stays parseable
+        with contextlib.redirect_stdout(sys.stderr if as_json else sys.stdout):
+            explanation = downloader.match_title(title, platform_url, downloader.list_platform_files(platform_url))
+        if explanation is None:
+            print(f"{Colors.YELLOW}No matches for {title} in {platform}{Colors.NC}", file=sys.stderr)
+            sys.exit(1)
+    
+    if as_json:
+        print(json.dumps(explanation))
+    else:
+        print_match_explanation(explanation)
+
+
+def remembered_matches(downloader: ROMDownloader, args):
+    """--remember-match, --forget-match and --list-matches: the files users picked for titles of --platform."""
+    folder = None
+    if args.platform:
+        platform_url = f"{downloader.rom_archive_base_url}{urllib.parse.quote(args.platform)}/"
+        folder = downloader.platform_folder(platform_url)
+    elif not args.list_matches:
+        print(f"{Colors.RED}--remember-match and --forget-match need --platform{Colors.NC}", file=sys.stderr)
+        sys.exit(2)
+    
+    try:
+        if args.remember_match:
+            title, filename = args.remember_match
+            with contextlib.redirect_stdout(sys.stderr if args.json else sys.stdout):
+                listed = filename in downloader.list_platform_files(platform_url)
+            if not listed:
+                print(f"{Colors.RED}{filename} is not in {folder}{Colors.NC}", file=sys.stderr)
+                sys.exit(1)
+            downloader.config.ensure_writable("remember a match")
+            downloader.library.remember_match(folder, downloader.clean_title(title), filename)
+            result = {"folder": folder, "title": downloader.clean_title(title), "filename": filename}
+        elif args.forget_match:
+            forgotten = downloader.library.forget_match(folder, downloader.clean_title(args.forget_match))
+            result = {"folder": folder, "title": downloader.clean_title(args.forget_match), "forgotten": forgotten}
+        else:
+            result = downloader.library.remembered_matches(folder)
+    except ReadOnlyError as e:
+        fail(f"{Colors.RED}{e}{Colors.NC}", e)
+    
+    if args.json:
+        print(json.dumps(result, default=str))
+    elif args.remember_match:
+        print(f"{Colors.GREEN}Remembered {result['filename']} for {result['title']}{Colors.NC}")
+    elif args.forget_match:
+        print(f"{Colors.GREEN}Forgot the match for {result['title']}{Colors.NC}" if result['forgotten'] else
+              f"{Colors.YELLOW}No match remembered for {result['title']}{Colors.NC}")
+    elif not result:
+        print(f"{Colors.YELLOW}No remembered matches{Colors.NC}")
+    else:
+        for match in result:
+            print(f"{Colors.CYAN}{match['folder']}{Colors.NC}  {match['title']} -> {match['filename']}")
+
+
+def resume(downloader: ROMDownloader, args):
+    """List the batch sessions, or resume one and show its end-of-session report."""
+    if args.json:
+        downloader.log_message = lambda message: print(message, file=sys.stderr)
+    
+    if args.list:
+        sessions = downloader.batch_sessions.sessions()
+        reports = [dict(session.report(), running=session.running()) for session in sessions]
+        if args.json:
+            print(json.dumps(reports, indent=2))
+            return
+        for report in reports:
+            state = "running" if report['running'] else "finished" if report['finished'] else "interrupted"
+            print(f"{Colors.CYAN}{report['id']}{Colors.NC}  {report['kind']}  {report['label']}  {state}: "
+                  f"{report['downloaded']} downloaded, {report['skipped']} skipped, {report['failed']} failed, "
+                  f"{report['pending']} pending")
+        return
+    
+    try:
+        report = downloader.resume_batch(args.session)
+    except (ReadOnlyError, ValueError) as e:
+        downloader.log_message(f"{Colors.RED}{e}{Colors.NC}")
+        report_error(str(e), e)
+        sys.exit(1)
+    if report is None:
+        return
+    if args.json:
+        print(json.dumps(report, indent=2))
+    else:
+        downloader.show_download_stats()
+    if report['failed']:
+        sys.exit(1)
+
+
+def mirror_glob_matches(path: str, patterns: List[str]) -> bool:
+    """Whether a path below a mirrored folder matches any of the globs, ignoring case.
+    
+    A folder matches by its path with or without the '/' after it, so
+    "Demos" and "Demos/*" both leave out the Demos folder.
+    """
+    path = path.lower()
+    return any(fnmatch.fnmatchcase(path, pattern.lower()) or fnmatch.fnmatchcase(path.rstrip('/'), pattern.lower())
+               for pattern in patterns)
+
+
+def served_name(url: str) -> str:
+    """Get the name of the file a URL serves."""
+    return urllib.parse.unquote(url.rstrip('/').split('/')[-1])
+
+
+def size_argument(text: str) -> int:
+    """Read a size given on the command line, e.g. "50GB", "1.5 TiB" or a number of bytes."""
+    if not SIZE_PATTERN.fullmatch(text.strip()):
+        raise argparse.ArgumentTypeError(f"not a size: {text}")
+    return parse_size(text)
+
+
 def main():
     """Main entry point."""
     parser = argparse.ArgumentParser(description='ROM Batch Downloader')
+    add_config_argument(parser)
     parser.add_argument('--platform', help='Platform to download')
-    parser.add_argument('--dataset', choices=['redump', 'no-intro'], 
-                       default='redump', help='Dataset to use')
+    parser.add_argument('--dataset', choices=['redump', 'no-intro', 'tosec'], 
+                       default='redump', help='Dataset to use (TOSEC platforms are paths, e.g. "Commodore/Amiga/Games/[ADF]")')
     parser.add_argument('--max-files', type=int, help='Maximum files to download')
+    parser.add_argument('--match', metavar='PATTERN',
+                       help='Queue and download the files of --platform whose names match a glob, e.g. "Mario*"')
+    parser.add_argument('--regex', action='store_true', help='With --match, the pattern is a regular expression')
+    parser.add_argument('--dry-run', action='store_true',
+                       help='With --match or --queue, only show the files that would be downloaded and their sizes')
     parser.add_argument('--queue', action='store_true', help='Download from queue only')
+    parser.add_argument('--max-total-size', type=size_argument, metavar='SIZE',
+                       help='With --queue or mirror, download at most this much (e.g. "50GB"); the rest waits for the next run')
+    parser.add_argument('--allow-low-space', action='store_true',
+                       help='Warn instead of refusing when the downloads do not fit in the free disk space')
+    parser.add_argument('--organize', action='store_true',
+                       help='Move earlier flat downloads into platform folders and update the library')
+    parser.add_argument('--read-only', action='store_true',
+                       help='Disable downloads and other writes (browse and report only)')
+    parser.add_argument('--region-priority',
+                       help='Comma-separated region preference for best-match selection (e.g. "USA,Europe,Japan")')
+    parser.add_argument('--explain', metavar='TITLE_OR_FILE',
+                       help='Show why a downloaded file was picked, or how a title would be matched against --platform')
+    parser.add_argument('--pick-matches', action='store_true',
+                       help='With --queue, confirm the file matched for each title; a different pick is remembered')
+    parser.add_argument('--remember-match', nargs=2, metavar=('TITLE', 'FILE'),
+                       help='Always download FILE for TITLE from --platform instead of matching it')
+    parser.add_argument('--forget-match', metavar='TITLE',
+                       help='Match TITLE on --platform automatically again')
+    parser.add_argument('--list-matches', action='store_true',
+                       help='List the remembered matches, of --platform or all')
+    parser.add_argument('--json', action='store_true',
+                       help='Print the --explain, match or --dry-run result as JSON')
+    parser.add_argument('--export-queue', metavar='FILE',
+                       help='Write the queue, with titles matched against --platform, to a portable queue file')
+    parser.add_argument('--import-queue', metavar='FILE',
+                       help='Download the entries of an exported queue file as they are and verify their hashes')
+    subparsers = parser.add_subparsers(dest='command')
+    
+    mirror_parser = subparsers.add_parser('mirror', help='Download an archive folder with its subfolders, '
+                                                         'or only what changed since the last run')
+    mirror_parser.add_argument('path', help='Archive folder, e.g. "TOSEC/Commodore/Amiga"')
+    mirror_parser.add_argument('--include', action='append', metavar='GLOB',
+                               help='Only files whose path below the folder matches, e.g. "Games/*", repeatable')
+    mirror_parser.add_argument('--exclude', action='append', metavar='GLOB',
+                               help='Leave out files and folders whose path matches, e.g. "*(Beta*" or "Demos", repeatable')
+    mirror_parser.add_argument('--preset', action='append', metavar='NAME',
+                               help='Filter preset to apply, repeatable (default: the ones configured for the platform)')
+    mirror_parser.add_argument('--one-per-game', action='store_true', help='Keep one file per game (1G1R)')
+    mirror_parser.add_argument('--dest', metavar='DIR', help='Mirror into DIR instead of the download folder')
+    mirror_parser.add_argument('--verify', action='store_true',
+                               help='Also check files already there against the DAT checksums')
+    mirror_parser.add_argument('--dry-run', action='store_true', help='Only show what would be downloaded')
+    mirror_parser.add_argument('--json', action='store_true', help='Print the --dry-run result as JSON')
+    
+    resume_parser = subparsers.add_parser('resume', help='Continue an interrupted --queue, --match, mirror or import run')
+    resume_parser.add_argument('session', nargs='?',
+                               help='Session id (default: the latest interrupted one); a finished session retries its failures')
+    resume_parser.add_argument('--list', action='store_true', help='List the batch sessions instead')
+    resume_parser.add_argument('--json', action='store_true', help='Print the session report, or the list, as JSON')
+    
+    get_parser = subparsers.add_parser('get', help='Download one file by its URL; archive.org files are checked '
+                                                   'against their published SHA1')
+    get_parser.add_argument('url', help='File URL')
+    
+    verify_parser = subparsers.add_parser('verify', help='Check the downloaded files and download the broken ones again')
+    verify_parser.add_argument('--dir', metavar='DIR', help='Check DIR instead of the download folder')
+    verify_parser.add_argument('--dry-run', action='store_true', help='Only list the broken files')
+    verify_parser.add_argument('--json', action='store_true', help='Print the result as JSON')
     
     args = parser.parse_args()
     
-    downloader = ROMDownloader()
+    if args.match and not args.platform:
+        parser.error("--match needs --platform")
+    if args.regex and not args.match:
+        parser.error("--regex goes with --match")
+    if args.dry_run and not (args.match or args.queue or args.command in ('mirror', 'verify')):
+        parser.error("--dry-run goes with --match, --queue, mirror or verify")
+    
+    if args.read_only:
+        set_read_only()
+    
+    with contextlib.redirect_stdout(sys.stderr if args.json else sys.stdout):
+        downloader = ROMDownloader()
+    
+    downloader.allow_low_space = args.allow_low_space
+    downloader.max_total_size = args.max_total_size
+    # Picking remembers matches, which a dry run must not
+    downloader.pick_matches = args.pick_matches and not args.dry_run
+    
+    if args.region_priority:
+        downloader.scorer.region_priority = [r.strip() for r in args.region_priority.split(',') if r.strip()]
     
     # Apply command line arguments
     if args.dataset == 'no-intro':
         downloader.rom_archive_base_url = downloader.base_url_noin
+    elif args.dataset == 'tosec':
+        downloader.rom_archive_base_url = downloader.base_url_tosec
     
-    if args.queue:
-        # Download from queue only
-        downloader.download_from_queue()
+    if args.command == 'resume':
+        resume(downloader, args)
+    elif args.command == 'get':
+        try:
+            ok = downloader.download_url(args.url)
+        except ReadOnlyError as e:
+            downloader.log_message(f"{Colors.RED}{e}{Colors.NC}")
+            report_error(str(e), e)
+            sys.exit(1)
+        if not ok:
+            sys.exit(1)
+    elif args.command == 'verify':
+        if args.dir:
+            downloader.download_dir = Path(args.dir).expanduser()
+        if args.json:
+            downloader.log_message = lambda message: print(message, file=sys.stderr)
+        try:
+            ok = downloader.verify_downloads(dry_run=args.dry_run, as_json=args.json)
+        except ReadOnlyError as e:
+            downloader.log_message(f"{Colors.RED}{e}{Colors.NC}")
+            report_error(str(e), e)
+            sys.exit(1)
+        if not ok:
+            sys.exit(1)
+    elif args.command == 'mirror':
+        if args.dest:
+            downloader.download_dir = Path(args.dest).expanduser()
+        if args.verify:
+            downloader.verify_checksums = True
+        if args.json:
+            downloader.log_message = lambda message: print(message, file=sys.stderr)
+        try:
+            ok = downloader.mirror_folder(args.path, args.include, args.exclude, args.preset, args.one_per_game,
+                                          verify=args.verify, dry_run=args.dry_run, as_json=args.json)
+        except (ReadOnlyError, ValueError) as e:
+            downloader.log_message(f"{Colors.RED}{e}{Colors.NC}")
+            report_error(str(e), e)
+            sys.exit(1)
+        except requests.RequestException as e:
+            downloader.log_message(f"{Colors.RED}Could not list {args.path}: {e}{Colors.NC}")
+            report_error(f"Could not list {args.path}: {e}", e)
+            sys.exit(1)
+        if not args.dry_run:
+            downloader.show_download_stats()
+        if not ok:
+            sys.exit(1)
+    elif args.explain:
+        explain(downloader, args.explain, args.platform, args.json)
+    elif args.remember_match or args.forget_match or args.list_matches:
+        remembered_matches(downloader, args)
+    elif args.export_queue:
+        downloader.export_queue(Path(args.export_queue), args.platform)
+    elif args.import_queue:
+        try:
+            ok = downloader.run_exported_queue(Path(args.import_queue))
+        except (OSError, ValueError) as e:
+            downloader.log_message(f"{Colors.RED}{e}{Colors.NC}")
+            report_error(str(e), e)
+            sys.exit(1)
+        downloader.show_download_stats()
+        if not ok:
+            sys.exit(1)
+    elif args.organize:
+        moved = downloader.organize_downloads()
+        downloader.log_message(f"{Colors.GREEN}Organized {moved} files{Colors.NC}")
+    elif args.match:
+        if args.json:
+            downloader.log_message = lambda message: print(message, file=sys.stderr)
+        try:
+            ok = downloader.download_matching(args.platform, args.match, regex=args.regex, dry_run=args.dry_run,
+                                              max_files=args.max_files, as_json=args.json)
+        except re.error as e:
+            downloader.log_message(f"{Colors.RED}Invalid pattern '{args.match}': {e}{Colors.NC}")
+            sys.exit(2)
+        except ReadOnlyError as e:
+            downloader.log_message(f"{Colors.RED}{e}{Colors.NC}")
+            report_error(str(e), e)
+            sys.exit(1)
+        if not args.dry_run:
+            downloader.show_download_stats()
+        if not ok:
+            sys.exit(1)
+    elif args.queue and args.dry_run:
+        if args.json:
+            downloader.log_message = lambda message: print(message, file=sys.stderr)
+        # An empty queue is an empty JSON report rather than an error
+        if not downloader.preview_queue(args.platform, args.json) and not args.json:
+            sys.exit(1)
+    elif args.queue:
+        # Download from queue only; titles are matched against --platform
+        downloader.download_from_queue(args.platform)
         downloader.show_download_stats()
     elif args.platform:
         # Download specific platform
@@ -604,4 +1867,5 @@ def main():
 
 
 if __name__ == "__main__":
+    install_error_reporting()
     main()
@@ -18,9 +18,20 @@ from datetime import datetime
 import urllib.parse
 import subprocess
 import shutil
-from typing import List, Dict, Optional, Tuple
+from typing import List, Optional, Tuple
 import argparse
-import re
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from download_paths import platform_template, resolve_destination
+from library_db import open_library, platform_from_url
+from mirrors import MirrorSet
+from plugins import PluginManager
+from proxy import apply_proxy_settings
+from retry_policy import RetryPolicy, TransientError
+from rom_sources import HttpIndexSource
+from rom_config import RomConfig, add_config_argument, set_read_only
+from speed_sampler import SpeedSampler, host_of
+from transliteration import transliterated_match
 
 
 class Colors:
@@ -35,18 +46,27 @@ class Colors:
 class ROMFilesBrowser:
     def __init__(self):
         # Configuration
-        self.root_url = "https://myrient.erista.me/files/"
-        self.temp_dir = Path("./temp")
-        self.download_dir = Path("./downloads")
-        self.log_file = Path("./mbrowse_log.txt")
+        self.config = RomConfig()
+        self.root_url = self.config.get_archive_url()
+        self.temp_dir = self.config.get_path('paths.temp_directory')
+        self.download_dir = self.config.get_path('paths.download_directory')
+        self.log_file = self.config.get_path('paths.log_directory') / "mbrowse_log.txt"
+        apply_proxy_settings(self.config)
+        self.library = open_library(self.config)
+        self.retry = RetryPolicy.from_config(self.config, log=self.log)
+        self.mirrors = MirrorSet(self.config, self.retry, log=self.log)
+        self.source = HttpIndexSource("myrient", self.root_url, self.mirrors, self.config)
+        self.plugins = PluginManager(self.config, log=self.log)
+        self.session_id = datetime.now().isoformat(timespec='seconds')
         
         # Current state
         self.current_url = self.root_url
         self.history = []
         
         # Create directories
-        self.temp_dir.mkdir(exist_ok=True)
-        self.download_dir.mkdir(exist_ok=True)
+        self.temp_dir.mkdir(parents=True, exist_ok=True)
+        self.download_dir.mkdir(parents=True, exist_ok=True)
+        self.log_file.parent.mkdir(parents=True, exist_ok=True)
         
         # Load history
         self.load_history()
@@ -115,142 +135,20 @@ class ROMFilesBrowser:
         
         return result
     
-    def urldecode_display(self, text: str) -> str:
-        """Decode URL-encoded characters for display."""
-        # Replace URL encoding with readable characters
-        replacements = {
-            '%20': ' ',
-            '%28': '(',
-            '%29': ')',
-            '%2B': '+',
-            '%26': '&',
-            '%27': "'",
-            '%2C': ',',
-            '%5B': '[',
-            '%5D': ']',
-            '%5b': '[',
-            '%5d': ']'
-        }
-        
-        result = text
-        for encoded, char in replacements.items():
-            result = result.replace(encoded, char)
-        
-        return result
-    
-    def download_index(self, url: str) -> Optional[str]:
-        """Download and save index page."""
+    def list_links(self, url: str) -> Optional[Tuple[List[Tuple[str, str]], List[Tuple[str, str]]]]:
+        """List the directories and files at a URL as (display name, name) pairs, each sorted by name."""
         try:
             self.log(f"{Colors.CYAN}Downloading index from {url}...{Colors.NC}")
-            
-            headers = {
-                'User-Agent': 'Mozilla/5.0 (Myrient CLI)',
-                'Accept-Encoding': 'gzip, deflate'
-            }
-            
-            response = requests.get(url, headers=headers, timeout=30)
-            response.raise_for_status()
-            
-            index_file = self.temp_dir / "index.html"
-            with open(index_file, 'w', encoding='utf-8') as f:
-                f.write(response.text)
-            
-            self.log(f"{Colors.GREEN}Index downloaded successfully{Colors.NC}")
-            return str(index_file)
-            
+            entries = self.source.list_dir(self.source.path_of(url) or '')
         except Exception as e:
             self.log(f"{Colors.RED}Failed to download index: {e}{Colors.NC}")
             return None
-    
-    def list_dirs_links(self, index_file: str) -> List[Tuple[str, str]]:
-        """Extract directories from index file."""
-        directories = []
-        
-        try:
-            with open(index_file, 'r', encoding='utf-8') as f:
-                content = f.read()
-            
-            # Find all href attributes pointing to directories
-            href_pattern = r'href="([^"]+/)"'
-            matches = re.findall(href_pattern, content)
-            
-            for href in matches:
-                # Skip parent directory and absolute URLs
-                if href in ['../', '..'] or href.startswith('http'):
-                    continue
-                
-                # Skip query parameters
-                if '?' in href:
-                    continue
-                
-                # Skip navigation links
-                skip_patterns = ['contact', 'donate', 'faq', 'upload', 'discord', 'telegram', 'hshop', 'home']
-                if any(pattern in href.lower() for pattern in skip_patterns):
-                    continue
-                
-                # Clean href
-                clean_href = href.rstrip('/')
-                
-                # Try to get title attribute for display name
-                display_name = clean_href
-                
-                # Look for title attribute in the same line
-                title_pattern = rf'href="{re.escape(href)}"[^>]*title="([^"]*)"'
-                title_match = re.search(title_pattern, content)
-                if title_match:
-                    display_name = title_match.group(1)
-                
-                # Decode URL for display
-                display_name = self.urldecode_display(display_name)
-                
-                directories.append((display_name, href))
-            
-            # Sort by display name
-            directories.sort(key=lambda x: x[0].lower())
-            
-        except Exception as e:
-            self.log(f"{Colors.RED}Error parsing directories: {e}{Colors.NC}")
         
-        return directories
-    
-    def list_files_links(self, index_file: str) -> List[Tuple[str, str]]:
-        """Extract files from index file."""
-        files = []
-        
-        try:
-            with open(index_file, 'r', encoding='utf-8') as f:
-                content = f.read()
-            
-            # Find all href attributes pointing to files (not directories)
-            href_pattern = r'href="([^"]+)"'
-            matches = re.findall(href_pattern, content)
-            
-            for href in matches:
-                # Skip directories and absolute URLs
-                if href.endswith('/') or href.startswith('http'):
-                    continue
-                
-                # Skip query parameters
-                if '?' in href:
-                    continue
-                
-                # Skip navigation links
-                skip_patterns = ['contact', 'donate', 'faq', 'upload', 'discord', 'telegram', 'hshop', 'home', 'Parent directory']
-                if any(pattern in href.lower() for pattern in skip_patterns):
-                    continue
-                
-                # Decode URL for display
-                display_name = self.urldecode_display(href)
-                
-                files.append((display_name, href))
-            
-            # Sort by display name
-            files.sort(key=lambda x: x[0].lower())
-            
-        except Exception as e:
-            self.log(f"{Colors.RED}Error parsing files: {e}{Colors.NC}")
-        
-        return files
+        directories = [(entry['name'], entry['name'] + '/') for entry in entries if entry['is_dir']]
+        files = [(entry['name'], entry['name']) for entry in entries if not entry['is_dir']]
+        directories.sort(key=lambda x: x[0].lower())
+        files.sort(key=lambda x: x[0].lower())
+        return directories, files
     
     def print_numbered_data(self, data: List[Tuple[str, str]], limit: int = 500):
         """Print numbered data with limit."""
@@ -276,34 +174,69 @@ class ROMFilesBrowser:
     def download_file(self, url: str, filename: str) -> bool:
         """Download a file."""
         try:
-            file_path = self.download_dir / filename
+            template = platform_template(url, self.config.get('downloads.path_template', '{platform}/{filename}'),
+                                         self.config.get('downloads.platform_templates', {}))
+            file_path = resolve_destination(self.download_dir, template, url, filename, self.library)
             
             # Check if file already exists
             if file_path.exists():
-                self.log(f"{Colors.YELLOW}File already exists: {filename}{Colors.NC}")
+                self.log(f"{Colors.YELLOW}File already exists: {file_path.relative_to(self.download_dir)}{Colors.NC}")
                 return True
             
+            if self.config.is_read_only():
+                self.log(f"{Colors.YELLOW}Read-only mode: not downloading {filename}{Colors.NC}")
+                return False
+            
             self.log(f"{Colors.CYAN}Downloading: {filename}{Colors.NC}")
             
-            headers = {
-                'User-Agent': 'Mozilla/5.0 (Myrient CLI)'
-            }
+            file_path.parent.mkdir(parents=True, exist_ok=True)
+            self.retry.call(self.transfer_file, url, file_path)
             
-            response = requests.get(url, headers=headers, stream=True, timeout=60)
-            response.raise_for_status()
+            # Nothing has processed the file yet, so its size is the source's
+            self.library.record_download(file_path, url, platform_from_url(url), file_path.stat().st_size)
+            self.library.extract_icon(file_path)
+            self.log(f"{Colors.GREEN}Downloaded: {file_path.relative_to(self.download_dir)}{Colors.NC}")
             
-            with open(file_path, 'wb') as f:
-                for chunk in response.iter_content(chunk_size=8192):
-                    if chunk:
-                        f.write(chunk)
+            # Post-download processors may move the file, e.g. to extract or convert it
+            final_path = Path(self.plugins.post_download(file_path.resolve(), url, platform_from_url(url)))
+            if final_path != file_path.resolve():
+                self.library.move_entry(file_path, final_path)
+                self.log(f"{Colors.GREEN}Post-processed: {final_path}{Colors.NC}")
             
-            self.log(f"{Colors.GREEN}Downloaded: {filename}{Colors.NC}")
             return True
             
         except Exception as e:
             self.log(f"{Colors.RED}Failed to download {filename}: {e}{Colors.NC}")
             return False
     
+    def transfer_file(self, url: str, file_path: Path):
+        """Stream a URL to <file>.part, replacing any earlier partial attempt, and rename it once complete.
+        
+        An interrupted transfer leaves only the .part file, so the next attempt
+        does not mistake a truncated file for a finished download.
+        """
+        response = self.mirrors.request('GET', url, stream=True, timeout=60)
+        response.raise_for_status()
+        expected = int(response.headers.get('content-length') or 0)
+        part_path = file_path.with_name(file_path.name + ".part")
+        
+        # Recorded even if the transfer fails, so stalls show up in the speed history
+        sampler = SpeedSampler()
+        received = 0
+        try:
+            with open(part_path, 'wb') as f:
+                for chunk in response.iter_content(chunk_size=8192):
+                    if chunk:
+                        f.write(chunk)
+                        sampler.add(len(chunk))
+                        received += len(chunk)
+        finally:
+            self.library.record_speed_samples(self.session_id, url, host_of(response.url), sampler.finish())
+        
+        if expected and received != expected:
+            raise TransientError(f"incomplete download ({received:,}/{expected:,} bytes)")
+        part_path.replace(file_path)
+    
     def get_user_choice(self, max_choice: int) -> Optional[int]:
         """Get user choice from input."""
         try:
@@ -373,11 +306,14 @@ class ROMFilesBrowser:
             return items
         
         filter_lower = filter_text.lower()
+        transliterate = self.config.get('matching.transliteration', True)
         filtered = []
         
         for display_name, href in items:
             if filter_lower in display_name.lower():
                 filtered.append((display_name, href))
+            elif transliterate and transliterated_match(filter_text, display_name.split('(')[0]):
+                filtered.append((display_name, href))
         
         return filtered
     
@@ -386,13 +322,11 @@ class ROMFilesBrowser:
         self.add_to_history(url)
         
         while True:
-            # Download and parse index
-            index_file = self.download_index(url)
-            if not index_file:
+            listing = self.list_links(url)
+            if not listing:
                 return False
             
-            directories = self.list_dirs_links(index_file)
-            files = self.list_files_links(index_file)
+            directories, files = listing
             
             # Display current location
             print(f"\n{Colors.CYAN}Current location: {url}{Colors.NC}")
@@ -526,7 +460,7 @@ class ROMFilesBrowser:
                 url = input(f"{Colors.CYAN}Enter URL: {Colors.NC}").strip()
                 if url:
                     if not url.startswith('http'):
-                        url = f"https://myrient.erista.me/files/{url}"
+                        url = f"{self.root_url}{url}"
                     self.current_url = url
                     if not self.browse_directory(url):
                         break
@@ -592,11 +526,17 @@ class ROMFilesBrowser:
 def main():
     """Main entry point."""
     parser = argparse.ArgumentParser(description='Myrient Files Browser')
+    add_config_argument(parser)
     parser.add_argument('--url', help='Start with specific URL')
     parser.add_argument('--download', help='Download specific file URL')
+    parser.add_argument('--read-only', action='store_true',
+                       help='Disable downloads (browse only)')
     
     args = parser.parse_args()
     
+    if args.read_only:
+        set_read_only()
+    
     browser = ROMFilesBrowser()
     
     # Apply command line arguments
@@ -0,0 +1,195 @@
+#!/usr/bin/env python3
+"""
+ROM Identifier
+Identifies an arbitrary file by its hashes using local DAT files and the Screenscraper hash API.
+"""
+
+import argparse
+import json
+import os
+import sys
+from pathlib import Path
+from typing import Dict, List, Optional
+
+import requests
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from dat_index import DatIndex, hash_entries
+from proxy import apply_proxy_settings
+from retry_policy import RetryPolicy
+from rom_config import RomConfig, add_config_argument
+
+
+class Colors:
+    """ANSI color codes for terminal output."""
+    RED = '\033[0;31m'
+    GREEN = '\033[0;32m'
+    YELLOW = '\033[1;33m'
+    CYAN = '\033[0;36m'
+    NC = '\033[0m'  # No Color
+
+
+VERIFICATION_COLORS = {
+    "verified": Colors.GREEN,
+    "crc-match": Colors.YELLOW,
+    "screenscraper": Colors.CYAN,
+    "bad-dump": Colors.RED,
+    "unknown": Colors.RED
+}
+
+
+class ROMIdentifier:
+    def __init__(self, config: Optional[RomConfig] = None, online=True):
+        self.config = config or RomConfig()
+        self.online = online
+        apply_proxy_settings(self.config)
+        self.base_url = "https://www.screenscraper.fr/api2"
+        self.username, self.password = self.get_screenscraper_credentials()
+        self.retry = RetryPolicy.from_config(self.config, log=lambda message: print(message, file=sys.stderr))
+
+        self.index = DatIndex()
+        dat_dir = self.config.get_path('paths.dat_directory')
+        self.dat_count = self.index.load_directory(dat_dir) if dat_dir else 0
+
+    def get_screenscraper_credentials(self):
+        """Get Screenscraper.fr credentials from environment or config file."""
+        username = os.getenv('SCREENSCRAPER_USERNAME')
+        password = os.getenv('SCREENSCRAPER_PASSWORD')
+
+        if username and password:
+            return username, password
+
+        username = self.config.get('api_keys.screenscraper_username')
+        password = self.config.get('api_keys.screenscraper_password')
+        if username and password:
+            return username, password
+
+        config_file = self.config.get_path('paths.screenscraper_config')
+        if config_file and config_file.exists():
+            try:
+                with open(config_file, 'r') as f:
+                    config = json.load(f)
+                    return config.get('username'), config.get('password')
+            except Exception as e:
+                print(f"Error loading Screenscraper config: {e}")
+
+        return None, None
+
+    def lookup_screenscraper(self, hashes: Dict) -> Optional[Dict]:
+        """Look up a ROM on Screenscraper by its hashes."""
+        if not self.online or not self.username or not self.password:
+            return None
+
+        params = {
+            'devid': self.username,
+            'devpassword': self.password,
+            'softname': 'testlaunchapp',
+            'output': 'json',
+            'romtype': 'rom',
+            'crc': hashes['crc32'],
+            'md5': hashes['md5'],
+            'sha1': hashes['sha1'],
+            'romtaille': hashes['size'],
+            'romnom': hashes['name']
+        }
+
+        try:
+            response = self.retry.get(f"{self.base_url}/jeuInfos.php", params=params, timeout=15)
+            if response.status_code != 200:
+                return None
+            jeu = response.json().get('response', {}).get('jeu')
+        except (requests.RequestException, ValueError) as e:
+            print(f"Screenscraper lookup failed: {e}")
+            return None
+
+        if not jeu:
+            return None
+
+        names = jeu.get('noms') or []
+        title = next((n.get('text') for n in names if n.get('region') in ('wor', 'us', 'ss')), None)
+        if not title and names:
+            title = names[0].get('text')
+
+        rom = jeu.get('rom') or {}
+        regions = (rom.get('regions') or {}).get('regions_shortname') or []
+
+        return {
+            "title": title or jeu.get('nom'),
+            "platform": (jeu.get('systeme') or {}).get('text'),
+            "region": ", ".join(regions) if regions else None,
+            "dat": None,
+            "verification": "screenscraper"
+        }
+
+    def identify_entry(self, hashes: Dict) -> Dict:
+        """Identify a single hashed file, preferring local DATs over Screenscraper."""
+        match = self.index.lookup(hashes) or self.lookup_screenscraper(hashes)
+        if not match:
+            match = {"title": None, "platform": None, "region": None, "dat": None, "verification": "unknown"}
+
+        return {
+            "file": hashes["name"],
+            "size": hashes["size"],
+            "crc32": hashes["crc32"],
+            "md5": hashes["md5"],
+            "sha1": hashes["sha1"],
+            "title": match.get("title"),
+            "platform": match.get("platform"),
+            "region": match.get("region"),
+            "dat": match.get("dat"),
+            "verification": match["verification"]
+        }
+
+    def identify(self, path) -> List[Dict]:
+        """Identify a file, or each file inside a ZIP archive."""
+        return [self.identify_entry(hashes) for hashes in hash_entries(path)]
+
+
+def print_result(result: Dict):
+    """Print an identification result."""
+    color = VERIFICATION_COLORS.get(result["verification"], Colors.NC)
+    print(f"\n{Colors.CYAN}{result['file']}{Colors.NC}")
+    print(f"  Title:        {result['title'] or 'Unknown'}")
+    print(f"  Platform:     {result['platform'] or 'Unknown'}")
+    print(f"  Region:       {result['region'] or 'Unknown'}")
+    print(f"  Verification: {color}{result['verification']}{Colors.NC}")
+    if result["dat"]:
+        print(f"  DAT:          {result['dat']}")
+    print(f"  CRC32 {result['crc32']}  SHA1 {result['sha1']}")
+
+
+def run_identify(path, as_json=False, online=True):
+    """Identify a file and print the results. Returns a process exit code."""
+    path = Path(path)
+    if not path.is_file():
+        print(f"{Colors.RED}File not found: {path}{Colors.NC}")
+        return 1
+
+    identifier = ROMIdentifier(online=online)
+    if not as_json and identifier.dat_count == 0:
+        print(f"{Colors.YELLOW}No DAT files found in {identifier.config.get_path('paths.dat_directory')}{Colors.NC}")
+
+    results = identifier.identify(path)
+
+    if as_json:
+        print(json.dumps(results, indent=2))
+    else:
+        for result in results:
+            print_result(result)
+
+    return 0 if any(r["verification"] != "unknown" for r in results) else 2
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Identify a ROM file by its hashes')
+    add_config_argument(parser)
+    parser.add_argument('path', help='File or ZIP archive to identify')
+    parser.add_argument('--json', action='store_true', help='Print the results as JSON')
+    parser.add_argument('--offline', action='store_true', help='Only match against local DAT files')
+
+    args = parser.parse_args()
+    sys.exit(run_identify(args.path, as_json=args.json, online=not args.offline))
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,146 @@
+#!/usr/bin/env python3
+"""
+ROM Presets
+Applies filter presets (see filter_presets) to an archive platform folder to
+pick its essential files, e.g. licensed USA retail releases without yearly
+sports games, one per game, and queues them. Also lists, shows, imports and
+exports the preset files so they can be shared.
+"""
+
+import argparse
+import contextlib
+import json
+import shutil
+import sys
+import urllib.parse
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from datasets import platform_of
+from download_queue import open_queue
+from filter_presets import FilterPresets, apply_presets
+from rom_config import ReadOnlyError, RomConfig, add_config_argument, set_read_only
+from session_filter import load_session_filter
+
+from rom_downloader import ROMDownloader
+
+
+class Colors:
+    """ANSI color codes for terminal output."""
+    RED = '\033[0;31m'
+    GREEN = '\033[0;32m'
+    YELLOW = '\033[1;33m'
+    CYAN = '\033[0;36m'
+    NC = '\033[0m'  # No Color
+
+
+def select_files(downloader: ROMDownloader, presets: FilterPresets, path: str, names, one_per_game=False) -> dict:
+    """List an archive folder and keep the files its presets and the session filter select.
+
+    Returns {platform, presets, session_filter, total, files}; session_filter is
+    None when no session filter is set.
+    """
+    url = f"{downloader.config.get_archive_url()}{urllib.parse.quote(path.strip('/'))}/"
+    platform = platform_of(url, is_file=False)
+    chosen = [presets.load(name) for name in names] if names else presets.for_platform(platform)
+    if not chosen:
+        raise ValueError(f"No presets given and none configured for {platform}; see 'rom_presets.py list'")
+    if one_per_game:
+        chosen = [dict(preset, one_per_game=True) for preset in chosen]
+
+    listing = downloader.list_platform_files(url)
+    kept = apply_presets(listing, chosen, downloader.select_best_match)
+    session = load_session_filter(downloader.config)
+    kept = session.keep_names(kept, downloader.config)
+    files = [{"name": name, "url": url + urllib.parse.quote(name),
+              "warnings": downloader.compatibility.check(name, platform)} for name in kept]
+    return {"platform": platform, "presets": [p['name'] for p in chosen],
+            "session_filter": None if session.is_empty() else session.describe(),
+            "total": len(listing), "files": files}
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Pick the essential files of a platform with shareable filter presets')
+    add_config_argument(parser)
+    parser.add_argument('--read-only', action='store_true', help='Show selections without queueing or saving')
+    subparsers = parser.add_subparsers(dest='command', required=True)
+
+    list_parser = subparsers.add_parser('list', help='List the saved presets')
+    list_parser.add_argument('--platform', help='Only presets meant for this platform, e.g. "Sony - PlayStation"')
+    list_parser.add_argument('--json', action='store_true', help='Print the presets as JSON')
+
+    show_parser = subparsers.add_parser('show', help='Show the rules of a preset')
+    show_parser.add_argument('name', help='Preset name')
+
+    apply_parser = subparsers.add_parser('apply', help='Select the files of an archive folder with presets')
+    apply_parser.add_argument('path', help='Archive folder, e.g. "No-Intro/Nintendo - Game Boy"')
+    apply_parser.add_argument('--preset', action='append', metavar='NAME',
+                              help='Preset to apply, repeatable (default: the ones configured for the platform)')
+    apply_parser.add_argument('--one-per-game', action='store_true', help='Keep one file per game (1G1R)')
+    apply_parser.add_argument('--queue', action='store_true', help='Add the selected files to the download queue')
+    apply_parser.add_argument('--json', action='store_true', help='Print the selection as JSON')
+
+    import_parser = subparsers.add_parser('import', help='Save a shared preset file')
+    import_parser.add_argument('file', help='Preset JSON file')
+
+    export_parser = subparsers.add_parser('export', help='Write a preset to a file to share it')
+    export_parser.add_argument('name', help='Preset name')
+    export_parser.add_argument('file', help='Destination file')
+
+    args = parser.parse_args()
+
+    if args.read_only:
+        set_read_only()
+
+    config = RomConfig()
+    presets = FilterPresets(config)
+
+    try:
+        if args.command == 'list':
+            saved = presets.list_presets(args.platform)
+            if args.json:
+                print(json.dumps(saved))
+                return
+            for preset in saved:
+                scope = ", ".join(preset['platforms']) or "any platform"
+                print(f"{Colors.CYAN}{preset['name']}{Colors.NC}  {preset['description']}  ({scope})")
+        elif args.command == 'show':
+            print(json.dumps(presets.load(args.name), indent=2))
+        elif args.command == 'apply':
+            # Progress goes to stderr when stdout carries the JSON
+            with contextlib.redirect_stdout(sys.stderr if args.json else sys.stdout):
+                downloader = ROMDownloader(config)
+            if args.json:
+                downloader.log_message = lambda message: print(message, file=sys.stderr)
+            selection = select_files(downloader, presets, args.path, args.preset, args.one_per_game)
+            if args.queue:
+                config.ensure_writable("add to the download queue")
+                entries = [(f['url'], f['warnings']) for f in selection['files']]
+                selection['queued'] = open_queue(config).add(str(downloader.queue_file.resolve()), entries)
+            if args.json:
+                print(json.dumps(selection))
+                return
+
+            for f in selection['files']:
+                print(f"  {f['name']}")
+                for warning in f['warnings']:
+                    print(f"      {Colors.YELLOW}! {warning}{Colors.NC}")
+            session = f" and the session filter ({selection['session_filter']})" if selection['session_filter'] else ""
+            print(f"\n{Colors.GREEN}{len(selection['files'])} of {selection['total']} files selected "
+                  f"by {', '.join(selection['presets'])}{session}{Colors.NC}")
+            if args.queue:
+                print(f"{Colors.GREEN}Added {selection['queued']} files to the download queue{Colors.NC}")
+        elif args.command == 'import':
+            preset = presets.import_file(Path(args.file))
+            print(f"{Colors.GREEN}Saved preset {preset['name']} to {presets.path(preset['name'])}{Colors.NC}")
+        elif args.command == 'export':
+            presets.load(args.name)
+            shutil.copyfile(presets.path(args.name), args.file)
+            print(f"{Colors.GREEN}Wrote preset {args.name} to {args.file}{Colors.NC}")
+    except (OSError, ValueError, ReadOnlyError) as e:
+        print(f"{Colors.RED}Filter preset error: {e}{Colors.NC}", file=sys.stderr)
+        sys.exit(1)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,172 @@
+#!/usr/bin/env python3
+"""
+ROM Verify
+Audits ROM files against local DAT files, for scheduled integrity checks.
+Needs nothing beyond the standard library: no network access, no downloader
+or browser, and machine-readable output (JSON, JSON lines or CSV).
+
+Exit codes: 0 when every file passed, 1 when a file failed the audit (see
+--fail-on), 2 on usage errors.
+"""
+
+import argparse
+import csv
+import json
+import sys
+from datetime import datetime
+from pathlib import Path
+from typing import Dict, Iterable, List, Optional
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from dat_index import DatIndex, hash_entries
+from job_limits import JobLimits
+from rom_config import RomConfig, add_config_argument
+from romignore import RomIgnore
+
+# Audit statuses: the DAT verifications plus files that could not be read or are gone
+STATUSES = ("verified", "crc-match", "bad-dump", "unknown", "unreadable", "missing")
+DEFAULT_FAIL_ON = ("bad-dump", "unknown", "unreadable", "missing")
+
+# Leftovers of interrupted downloads are not ROMs
+SKIPPED_SUFFIXES = ('.part', '.part.pieces.json')
+
+CSV_FIELDS = ["path", "entry", "status", "title", "platform", "dat", "name_matches", "size", "crc32", "sha1", "error"]
+
+
+def log(message: str):
+    print(message, file=sys.stderr)
+
+
+def iter_files(paths: Iterable[Path], ignore: RomIgnore) -> Iterable[Path]:
+    """Expand directories into the files below them, in a stable order, leaving out .romignore matches."""
+    for path in paths:
+        if path.is_dir():
+            for child in ignore.walk(path):
+                if not child.name.endswith(SKIPPED_SUFFIXES):
+                    yield child
+        else:
+            yield path
+
+
+class RomVerifier:
+    def __init__(self, dat_dir: Optional[Path] = None, config: Optional[RomConfig] = None):
+        self.config = config or RomConfig()
+        self.dat_dir = dat_dir or self.config.get_path('paths.dat_directory')
+        self.index = DatIndex()
+        self.dat_count = self.index.load_directory(self.dat_dir, log=log) if self.dat_dir else 0
+        # Scheduled audits run alongside games, within the jobs settings
+        self.limits = JobLimits(self.config)
+
+    def audit_file(self, path: Path) -> List[Dict]:
+        """Audit one file (or each member of a ZIP). Returns one result per entry."""
+        if not path.exists():
+            return [{"path": str(path), "entry": None, "status": "missing"}]
+
+        try:
+            entries = hash_entries(path, self.limits)
+        except (OSError, ValueError) as e:
+            return [{"path": str(path), "entry": None, "status": "unreadable", "error": str(e)}]
+
+        results = []
+        for hashes in entries:
+            match = self.index.lookup(hashes)
+            results.append({
+                "path": str(path),
+                "entry": hashes["name"],
+                "status": match["verification"] if match else "unknown",
+                "title": match["title"] if match else None,
+                "platform": match["platform"] if match else None,
+                "dat": match["dat"] if match else None,
+                # A verified dump stored under another name was renamed after download
+                "name_matches": (match["rom"] == hashes["name"]) if match else None,
+                "size": hashes["size"],
+                "crc32": hashes["crc32"],
+                "sha1": hashes["sha1"]
+            })
+        return results
+
+    def library_paths(self) -> List[Path]:
+        """Get every file recorded in the downloaded-ROM library."""
+        from library_db import LibraryDB
+
+        ignore = RomIgnore()
+        return [Path(entry['path']) for entry in LibraryDB(config=self.config).get_entries()
+                if not ignore.is_ignored(entry['path'], False)]
+
+
+def summarize(results: List[Dict]) -> Dict[str, int]:
+    counts = {status: 0 for status in STATUSES}
+    for result in results:
+        counts[result["status"]] += 1
+    counts["total"] = len(results)
+    return counts
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Audit ROM files against local DAT files')
+    add_config_argument(parser)
+    parser.add_argument('paths', nargs='*', type=Path, help='Files or directories to audit')
+    parser.add_argument('--library', action='store_true', help='Audit every file in the download library')
+    parser.add_argument('--dat-dir', type=Path, help='DAT directory (default: paths.dat_directory)')
+    parser.add_argument('--format', choices=['text', 'json', 'jsonl', 'csv'], default='text',
+                        help='Output format (default: text)')
+    parser.add_argument('--output', type=Path, help='Write the report to a file instead of stdout')
+    parser.add_argument('--fail-on', default=','.join(DEFAULT_FAIL_ON),
+                        help=f'Statuses that fail the audit (default: {",".join(DEFAULT_FAIL_ON)})')
+
+    args = parser.parse_args()
+
+    fail_on = {status.strip() for status in args.fail_on.split(',') if status.strip()}
+    unknown_statuses = fail_on - set(STATUSES)
+    if unknown_statuses:
+        parser.error(f"unknown status in --fail-on: {', '.join(sorted(unknown_statuses))}")
+    if not args.paths and not args.library:
+        parser.error("give files or directories to audit, or --library")
+
+    verifier = RomVerifier(args.dat_dir)
+    if verifier.dat_count == 0:
+        log(f"No DAT files found in {verifier.dat_dir}; every file will be unknown")
+
+    paths = list(args.paths) + (verifier.library_paths() if args.library else [])
+    out = open(args.output, 'w', encoding='utf-8', newline='') if args.output else sys.stdout
+
+    results = []
+    writer = None
+    if args.format == 'csv':
+        writer = csv.DictWriter(out, fieldnames=CSV_FIELDS, extrasaction='ignore')
+        writer.writeheader()
+
+    try:
+        for file_results in verifier.limits.map(verifier.audit_file, iter_files(paths, RomIgnore())):
+            for result in file_results:
+                results.append(result)
+                # Streaming formats write as they go, so long audits show progress
+                if args.format == 'jsonl':
+                    out.write(json.dumps(result) + "\n")
+                    out.flush()
+                elif writer:
+                    writer.writerow(result)
+                elif args.format == 'text':
+                    name = result["path"] + (f" :: {result['entry']}" if result.get("entry") else "")
+                    out.write(f"{result['status']:>10}  {name}\n")
+
+        summary = summarize(results)
+        if args.format == 'json':
+            json.dump({
+                "generated_at": datetime.now().isoformat(timespec='seconds'),
+                "dat_count": verifier.dat_count,
+                "summary": summary,
+                "results": results
+            }, out, indent=2)
+            out.write("\n")
+        elif args.format == 'text':
+            out.write("\n" + "  ".join(f"{status}: {summary[status]}" for status in STATUSES) + "\n")
+    finally:
+        if out is not sys.stdout:
+            out.close()
+
+    sys.exit(1 if any(summary[status] for status in fail_on) else 0)
+
+
+if __name__ == "__main__":
+    main()
@@ -10,6 +10,10 @@ import argparse
 from pathlib import Path
 import win32com.client
 
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "common"))
+from emulators import emulator_arguments
+from romignore import RomIgnore
+
 
 def read_rom_config(config_file):
     """Read ROM directories and emulator mappings from configuration file."""
@@ -73,54 +77,18 @@ def find_rom_files(rom_dir):
     if not rom_path.exists():
         return rom_files
     
-    for root, dirs, files in os.walk(rom_path):
-        for file in files:
-            file_path = Path(root) / file
-            if file_path.suffix.lower() in rom_extensions:
-                rom_files.append(file_path)
+    # Folders and files listed in .romignore files are left out
+    for file_path in RomIgnore().walk(rom_path):
+        if file_path.suffix.lower() in rom_extensions:
+            rom_files.append(file_path)
     
     return rom_files
 
 
 def get_emulator_args(emulator_path, rom_file):
-    """Get command line arguments for different emulators."""
-    emulator_name = Path(emulator_path).name.lower()
-    
-    if 'pcsx2' in emulator_name:
-        return f'"{emulator_path}" --fullscreen --nogui "{rom_file}"'
-    elif 'epsxe' in emulator_name:
-        return f'"{emulator_path}" -nogui -loadbin "{rom_file}"'
-    elif 'project64' in emulator_name:
-        return f'"{emulator_path}" "{rom_file}"'
-    elif 'dolphin' in emulator_name:
-        return f'"{emulator_path}" -e "{rom_file}"'
-    elif 'flycast' in emulator_name:
-        return f'"{emulator_path}" "{rom_file}"'
-    elif 'fusion' in emulator_name:
-        return f'"{emulator_path}" "{rom_file}"'
-    elif 'snes9x' in emulator_name:
-        return f'"{emulator_path}" "{rom_file}"'
-    elif 'nestopia' in emulator_name:
-        return f'"{emulator_path}" "{rom_file}"'
-    elif 'visualboyadvance' in emulator_name:
-        return f'"{emulator_path}" "{rom_file}"'
-    elif 'desmume' in emulator_name:
-        return f'"{emulator_path}" "{rom_file}"'
-    elif 'ppsspp' in emulator_name:
-        return f'"{emulator_path}" "{rom_file}"'
-    elif 'mame' in emulator_name:
-        # For MAME, we need to extract the ROM name from the file
-        rom_name = rom_file.stem
-        return f'"{emulator_path}" "{rom_name}"'
-    elif 'x64' in emulator_name:  # VICE C64 emulator
-        return f'"{emulator_path}" "{rom_file}"'
-    elif 'winuae' in emulator_name:
-        return f'"{emulator_path}" -f "{rom_file}"'
-    elif 'stella' in emulator_name:
-        return f'"{emulator_path}" "{rom_file}"'
-    else:
-        # Default: just pass the ROM file as argument
-        return f'"{emulator_path}" "{rom_file}"'
+    """Get the command line that launches a ROM through its emulator."""
+    arguments = [arg if arg.startswith('-') else f'"{arg}"' for arg in emulator_arguments(emulator_path, rom_file)]
+    return ' '.join([f'"{emulator_path}"'] + arguments)
 
 
 def create_rom_shortcut(emulator_path, rom_file, shortcut_path):